@@ -0,0 +1,709 @@
+//! Agents module - supervises Claude Code sessions working on issues
+//!
+//! Each agent runs Claude Code in a PTY (so the frontend renders it like any
+//! terminal) but is tracked here with the issue it belongs to and a lifecycle
+//! state. Claude Code gives no machine-readable signal in interactive mode,
+//! so the state comes from output activity: the spinner redraws constantly
+//! while it works and the screen goes quiet when it waits for the user.
+//! Every transition is emitted as an `agent-state` event, and approval
+//! prompts spotted in the output as `agent-needs-input`. Agents started with
+//! `streamJson` run non-interactively and report `agent-activity` events
+//! parsed from Claude Code's JSON output instead.
+
+mod prompts;
+pub mod queue;
+mod stream;
+
+use crate::attention;
+use crate::lifecycle::{run_hooks, HookContext, HookEvent};
+use crate::notifications;
+use crate::pty::{self, PtyObserver, PtyState};
+use crate::usage::{self, UsageRecord};
+use parking_lot::Mutex;
+use portable_pty::CommandBuilder;
+pub(crate) use prompts::strip_ansi;
+use prompts::{ApprovalPrompt, PromptDetector};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{Duration, Instant};
+use stream::{AgentActivity, StreamParser};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// Program run when the caller does not choose one
+const DEFAULT_COMMAND: &str = "claude";
+
+/// Quiet period after which a working agent counts as waiting
+const IDLE_AFTER: Duration = Duration::from_secs(3);
+
+/// How often idle agents are checked
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Activity entries kept per agent for `get_agent_activity`
+const ACTIVITY_HISTORY: usize = 500;
+
+/// Arguments that make Claude Code print its session as NDJSON
+const STREAM_JSON_ARGS: [&str; 4] = ["-p", "--output-format", "stream-json", "--verbose"];
+
+/// Default instructions appended after the issue
+const DEFAULT_INSTRUCTIONS: &str = "Implement what this issue asks for in the current \
+    working tree. Keep changes focused, run the project's checks, and commit when done.";
+
+/// State for supervised agents
+pub struct AgentState {
+    agents: Mutex<HashMap<u32, Agent>>,
+    next_id: AtomicU32,
+    /// Agents waiting for a free slot, next first
+    queue: Mutex<VecDeque<u32>>,
+    max_running: Mutex<Option<usize>>,
+}
+
+impl Default for AgentState {
+    fn default() -> Self {
+        Self {
+            agents: Mutex::new(HashMap::new()),
+            next_id: AtomicU32::new(1),
+            queue: Mutex::new(VecDeque::new()),
+            max_running: Mutex::new(Some(queue::DEFAULT_MAX_RUNNING)),
+        }
+    }
+}
+
+struct Agent {
+    options: SpawnAgentOptions,
+    pty_id: Option<u32>,
+    status: AgentStatus,
+    /// Bumped on every (re)start so events from an old PTY are ignored
+    generation: u32,
+    last_output: Instant,
+    started_at: i64,
+    prompts: PromptDetector,
+    /// Parser for `streamJson` agents
+    stream: Option<StreamParser>,
+    activity: VecDeque<AgentActivity>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentStatus {
+    /// Waiting for a slot under the concurrency limit
+    Queued,
+    /// Spawned, no output yet
+    Starting,
+    /// Producing output
+    Working,
+    /// Quiet, most likely waiting for input
+    Waiting,
+    Exited,
+}
+
+/// Issue an agent is started for
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IssueContext {
+    pub(crate) repo: String,
+    pub(crate) number: u64,
+    pub(crate) title: String,
+    #[serde(default)]
+    body: Option<String>,
+    #[serde(default)]
+    labels: Vec<String>,
+    #[serde(default)]
+    url: Option<String>,
+}
+
+/// Options for `spawn_agent`
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpawnAgentOptions {
+    issue: IssueContext,
+    /// Worktree the agent works in
+    cwd: String,
+    cols: u16,
+    rows: u16,
+    /// Replaces the default instructions that follow the issue
+    instructions: Option<String>,
+    /// Program to run instead of `claude`
+    command: Option<String>,
+    /// Extra arguments placed before the prompt
+    #[serde(default)]
+    args: Vec<String>,
+    #[serde(default)]
+    env: HashMap<String, String>,
+    /// Run non-interactively and report structured activity
+    #[serde(default)]
+    stream_json: bool,
+}
+
+impl IssueContext {
+    pub(crate) fn new(
+        repo: String,
+        number: u64,
+        title: String,
+        body: Option<String>,
+        labels: Vec<String>,
+        url: Option<String>,
+    ) -> Self {
+        Self {
+            repo,
+            number,
+            title,
+            body,
+            labels,
+            url,
+        }
+    }
+}
+
+impl SpawnAgentOptions {
+    /// Options that run `program` (Claude Code unless given) with `args`
+    /// before the prompt
+    pub(crate) fn new(
+        issue: IssueContext,
+        cwd: String,
+        cols: u16,
+        rows: u16,
+        instructions: Option<String>,
+        command: Option<(String, Vec<String>)>,
+    ) -> Self {
+        let (command, args) = match command {
+            Some((program, args)) => (Some(program), args),
+            None => (None, Vec::new()),
+        };
+        Self {
+            issue,
+            cwd,
+            cols,
+            rows,
+            instructions,
+            command,
+            args,
+            env: HashMap::new(),
+            stream_json: false,
+        }
+    }
+}
+
+/// Summary of an agent for the frontend
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentInfo {
+    pub(crate) id: u32,
+    pub(crate) issue: IssueContext,
+    cwd: String,
+    pub(crate) pty_id: Option<u32>,
+    pub(crate) status: AgentStatus,
+    started_at: i64,
+    /// Approval prompt waiting for an answer
+    pending_prompt: Option<ApprovalPrompt>,
+}
+
+/// Payload of `agent-state`
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AgentStateEvent {
+    id: u32,
+    pty_id: Option<u32>,
+    repo: String,
+    issue: u64,
+    status: AgentStatus,
+    previous: AgentStatus,
+}
+
+/// Payload of `agent-needs-input`
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AgentNeedsInputEvent {
+    id: u32,
+    pty_id: Option<u32>,
+    repo: String,
+    issue: u64,
+    #[serde(flatten)]
+    prompt: ApprovalPrompt,
+}
+
+/// Payload of `agent-activity`
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AgentActivityEvent {
+    id: u32,
+    pty_id: Option<u32>,
+    repo: String,
+    issue: u64,
+    #[serde(flatten)]
+    activity: AgentActivity,
+}
+
+impl Agent {
+    fn info(&self, id: u32) -> AgentInfo {
+        AgentInfo {
+            id,
+            issue: self.options.issue.clone(),
+            cwd: self.options.cwd.clone(),
+            pty_id: self.pty_id,
+            status: self.status,
+            started_at: self.started_at,
+            pending_prompt: self.prompts.pending().cloned(),
+        }
+    }
+
+    /// Move to `status`, returning the event to emit if anything changed
+    fn transition(&mut self, id: u32, status: AgentStatus) -> Option<AgentStateEvent> {
+        if self.status == status {
+            return None;
+        }
+        let previous = std::mem::replace(&mut self.status, status);
+        Some(AgentStateEvent {
+            id,
+            pty_id: self.pty_id,
+            repo: self.options.issue.repo.clone(),
+            issue: self.options.issue.number,
+            status,
+            previous,
+        })
+    }
+}
+
+/// Forwards an agent's PTY activity into its lifecycle state
+struct AgentObserver {
+    app: AppHandle,
+    id: u32,
+    generation: u32,
+}
+
+impl AgentObserver {
+    fn update<T>(&self, f: impl FnOnce(&mut Agent) -> Option<T>) -> Option<T> {
+        let state = self.app.state::<AgentState>();
+        let mut agents = state.agents.lock();
+        match agents.get_mut(&self.id) {
+            Some(agent) if agent.generation == self.generation => f(agent),
+            _ => None,
+        }
+    }
+}
+
+impl PtyObserver for AgentObserver {
+    fn on_data(&mut self, data: &str) {
+        let id = self.id;
+        let (state_event, input_event, activity_events) = self
+            .update(|agent| {
+                agent.last_output = Instant::now();
+                let activities = agent
+                    .stream
+                    .as_mut()
+                    .map(|stream| stream.push(data))
+                    .unwrap_or_default();
+                let activity_events: Vec<AgentActivityEvent> = activities
+                    .into_iter()
+                    .map(|activity| {
+                        agent.activity.push_back(activity.clone());
+                        if agent.activity.len() > ACTIVITY_HISTORY {
+                            agent.activity.pop_front();
+                        }
+                        AgentActivityEvent {
+                            id,
+                            pty_id: agent.pty_id,
+                            repo: agent.options.issue.repo.clone(),
+                            issue: agent.options.issue.number,
+                            activity,
+                        }
+                    })
+                    .collect();
+                let prompt = agent.prompts.push(data);
+                // Redraws of an unanswered prompt don't mean it's working again
+                let status = if agent.prompts.pending().is_some() {
+                    AgentStatus::Waiting
+                } else {
+                    AgentStatus::Working
+                };
+                let input_event = prompt.map(|prompt| AgentNeedsInputEvent {
+                    id,
+                    pty_id: agent.pty_id,
+                    repo: agent.options.issue.repo.clone(),
+                    issue: agent.options.issue.number,
+                    prompt,
+                });
+                Some((agent.transition(id, status), input_event, activity_events))
+            })
+            .unwrap_or((None, None, Vec::new()));
+
+        if let Some(event) = state_event {
+            let _ = self.app.emit("agent-state", event);
+        }
+        if let Some(event) = input_event {
+            notifications::agent_needs_input(&self.app, id, event.prompt.question());
+            attention::agent_needs_input(&self.app, event.pty_id);
+            let _ = self.app.emit("agent-needs-input", event);
+        }
+        for event in activity_events {
+            if let Some(record) = usage_record(&event) {
+                if let Err(e) = usage::record(&self.app, record) {
+                    eprintln!("Failed to record usage of agent {}: {}", id, e);
+                }
+            }
+            let _ = self.app.emit("agent-activity", event);
+        }
+    }
+
+    fn on_exit(&mut self) {
+        let id = self.id;
+        if let Some(event) = self.update(|agent| agent.transition(id, AgentStatus::Exited)) {
+            let _ = self.app.emit("agent-state", event);
+            notifications::agent_exited(&self.app, id);
+            queue::start_next(&self.app);
+            if let Some(context) = hook_context(&self.app, id, false) {
+                let app = self.app.clone();
+                tauri::async_runtime::spawn(async move {
+                    let _ = run_hooks(&app, HookEvent::PostAgentExit, context).await;
+                });
+            }
+        }
+    }
+}
+
+/// Usage to store for an activity entry. Tokens come from each response and
+/// the cost from the final result, so nothing is counted twice.
+fn usage_record(event: &AgentActivityEvent) -> Option<UsageRecord> {
+    let record = UsageRecord {
+        repo: event.repo.clone(),
+        issue: event.issue,
+        agent_id: event.id,
+        ..Default::default()
+    };
+    match &event.activity {
+        AgentActivity::Usage { model, usage, .. } => Some(UsageRecord {
+            model: model.clone(),
+            input_tokens: usage.input_tokens,
+            output_tokens: usage.output_tokens,
+            cache_creation_tokens: usage.cache_creation_input_tokens,
+            cache_read_tokens: usage.cache_read_input_tokens,
+            ..record
+        }),
+        AgentActivity::Result {
+            total_cost_usd: Some(cost),
+            ..
+        } => Some(UsageRecord {
+            cost_usd: *cost,
+            ..record
+        }),
+        _ => None,
+    }
+}
+
+/// Lifecycle hook context for an agent, only while it runs when `running`
+fn hook_context(app: &AppHandle, id: u32, running: bool) -> Option<HookContext> {
+    let state = app.state::<AgentState>();
+    let agents = state.agents.lock();
+    let agent = agents.get(&id)?;
+    if running
+        && !matches!(
+            agent.status,
+            AgentStatus::Starting | AgentStatus::Working | AgentStatus::Waiting
+        )
+    {
+        return None;
+    }
+    Some(HookContext {
+        repo: agent.options.issue.repo.clone(),
+        issue: agent.options.issue.number,
+        cwd: agent.options.cwd.clone(),
+        ..Default::default()
+    })
+}
+
+/// Run `pre_session_kill` hooks for a running agent about to be stopped
+async fn before_kill(app: &AppHandle, id: u32) -> Result<(), String> {
+    match hook_context(app, id, true) {
+        Some(context) => run_hooks(app, HookEvent::PreSessionKill, context).await,
+        None => Ok(()),
+    }
+}
+
+/// Prompt Claude Code starts with: the issue followed by instructions
+fn issue_prompt(issue: &IssueContext, instructions: Option<&str>) -> String {
+    let mut prompt = format!(
+        "You are working on GitHub issue #{} in {}: {}\n",
+        issue.number, issue.repo, issue.title
+    );
+    if let Some(url) = &issue.url {
+        prompt.push_str(&format!("{}\n", url));
+    }
+    if !issue.labels.is_empty() {
+        prompt.push_str(&format!("Labels: {}\n", issue.labels.join(", ")));
+    }
+    if let Some(body) = issue.body.as_deref().map(str::trim) {
+        if !body.is_empty() {
+            prompt.push_str(&format!("\n{}\n", body));
+        }
+    }
+    prompt.push('\n');
+    prompt.push_str(instructions.unwrap_or(DEFAULT_INSTRUCTIONS));
+    prompt
+}
+
+/// Start (or restart) the agent's process in a fresh PTY
+fn launch(app: &AppHandle, id: u32) -> Result<AgentInfo, String> {
+    let state = app.state::<AgentState>();
+    let (options, generation, event) = {
+        let mut agents = state.agents.lock();
+        let agent = agents
+            .get_mut(&id)
+            .ok_or_else(|| format!("Agent {} not found", id))?;
+        agent.generation += 1;
+        agent.pty_id = None;
+        agent.last_output = Instant::now();
+        agent.started_at = chrono::Utc::now().timestamp();
+        agent.prompts = PromptDetector::default();
+        agent.stream = agent.options.stream_json.then(StreamParser::default);
+        agent.activity.clear();
+        let event = agent.transition(id, AgentStatus::Starting);
+        (agent.options.clone(), agent.generation, event)
+    };
+    if let Some(event) = event {
+        let _ = app.emit("agent-state", event);
+    }
+
+    let mut cmd = CommandBuilder::new(options.command.as_deref().unwrap_or(DEFAULT_COMMAND));
+    if options.stream_json {
+        cmd.args(STREAM_JSON_ARGS);
+    }
+    cmd.args(&options.args);
+    cmd.arg(issue_prompt(
+        &options.issue,
+        options.instructions.as_deref(),
+    ));
+    cmd.cwd(&options.cwd);
+    for (key, value) in &options.env {
+        cmd.env(key, value);
+    }
+
+    let observer = AgentObserver {
+        app: app.clone(),
+        id,
+        generation,
+    };
+    let spawned = pty::spawn_session(
+        app,
+        &app.state::<PtyState>(),
+        cmd,
+        options.cols,
+        options.rows,
+        None,
+        Some(Box::new(observer)),
+    );
+
+    let event = {
+        let mut agents = state.agents.lock();
+        let agent = agents
+            .get_mut(&id)
+            .ok_or_else(|| format!("Agent {} not found", id))?;
+        match &spawned {
+            Ok(pty_id) => {
+                agent.pty_id = Some(*pty_id);
+                None
+            }
+            Err(_) => agent.transition(id, AgentStatus::Exited),
+        }
+    };
+    if let Some(event) = event {
+        let _ = app.emit("agent-state", event);
+    }
+    spawned?;
+
+    // Print mode never waits for input, so quiet output means it is thinking
+    if !options.stream_json {
+        watch_idle(app.clone(), id, generation);
+    }
+    info(&state, id)
+}
+
+fn info(state: &AgentState, id: u32) -> Result<AgentInfo, String> {
+    state
+        .agents
+        .lock()
+        .get(&id)
+        .map(|agent| agent.info(id))
+        .ok_or_else(|| format!("Agent {} not found", id))
+}
+
+/// Mark the agent waiting once its output has been quiet for a while
+fn watch_idle(app: AppHandle, id: u32, generation: u32) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(IDLE_CHECK_INTERVAL).await;
+            let event = {
+                let state = app.state::<AgentState>();
+                let mut agents = state.agents.lock();
+                let Some(agent) = agents.get_mut(&id) else {
+                    break;
+                };
+                if agent.generation != generation || agent.status == AgentStatus::Exited {
+                    break;
+                }
+                if agent.status == AgentStatus::Working && agent.last_output.elapsed() >= IDLE_AFTER
+                {
+                    agent.transition(id, AgentStatus::Waiting)
+                } else {
+                    None
+                }
+            };
+            if let Some(event) = event {
+                let _ = app.emit("agent-state", event);
+            }
+        }
+    });
+}
+
+/// Kill the agent's PTY (or take it out of the queue) and mark it exited
+fn stop(app: &AppHandle, id: u32) -> Result<(), String> {
+    queue::dequeue(app, id);
+    let state = app.state::<AgentState>();
+    let (pty_id, event) = {
+        let mut agents = state.agents.lock();
+        let agent = agents
+            .get_mut(&id)
+            .ok_or_else(|| format!("Agent {} not found", id))?;
+        // Ignore the exit the killed PTY is about to report
+        agent.generation += 1;
+        (agent.pty_id, agent.transition(id, AgentStatus::Exited))
+    };
+
+    if let Some(pty_id) = pty_id {
+        pty::kill_session(&app.state::<PtyState>(), pty_id);
+    }
+    if let Some(event) = event {
+        let _ = app.emit("agent-state", event);
+    }
+    queue::start_next(app);
+    Ok(())
+}
+
+/// Start Claude Code on an issue in the given worktree.
+///
+/// When the concurrency limit is reached the agent is queued instead and
+/// starts once a slot frees up.
+#[tauri::command]
+pub async fn spawn_agent(
+    app: AppHandle,
+    state: State<'_, AgentState>,
+    options: SpawnAgentOptions,
+) -> Result<AgentInfo, String> {
+    start_agent(&app, &state, options)
+}
+
+/// Register an agent and start or queue it, as `spawn_agent` does
+pub(crate) fn start_agent(
+    app: &AppHandle,
+    state: &AgentState,
+    options: SpawnAgentOptions,
+) -> Result<AgentInfo, String> {
+    let id = state.next_id.fetch_add(1, Ordering::SeqCst);
+    state.agents.lock().insert(
+        id,
+        Agent {
+            options,
+            pty_id: None,
+            status: AgentStatus::Queued,
+            generation: 0,
+            last_output: Instant::now(),
+            started_at: chrono::Utc::now().timestamp(),
+            prompts: PromptDetector::default(),
+            stream: None,
+            activity: VecDeque::new(),
+        },
+    );
+
+    queue::start_or_queue(app, id)
+}
+
+/// Stop an agent, keeping it listed as exited
+#[tauri::command]
+pub async fn stop_agent(app: AppHandle, id: u32) -> Result<(), String> {
+    before_kill(&app, id).await?;
+    stop(&app, id)
+}
+
+/// Stop an agent if it is running and start it again with the same options.
+///
+/// The restart takes its turn in the queue like any other start.
+#[tauri::command]
+pub async fn restart_agent(app: AppHandle, id: u32) -> Result<AgentInfo, String> {
+    before_kill(&app, id).await?;
+    stop(&app, id)?;
+    queue::start_or_queue(&app, id)
+}
+
+/// Answer an agent's approval prompt, or type a reply when it waits for input.
+///
+/// An answer matching one of the pending prompt's option keys selects that
+/// option; anything else is typed and submitted.
+#[tauri::command]
+pub async fn respond_to_agent(
+    app: AppHandle,
+    state: State<'_, AgentState>,
+    id: u32,
+    answer: String,
+) -> Result<(), String> {
+    let (pty_id, data) = {
+        let mut agents = state.agents.lock();
+        let agent = agents
+            .get_mut(&id)
+            .ok_or_else(|| format!("Agent {} not found", id))?;
+        if matches!(agent.status, AgentStatus::Queued | AgentStatus::Exited) {
+            return Err(format!("Agent {} is not running", id));
+        }
+        let pty_id = agent
+            .pty_id
+            .ok_or_else(|| format!("Agent {} has no terminal", id))?;
+
+        let answer = answer.trim();
+        let data = match agent.prompts.pending() {
+            // The menu picks an option on its number alone
+            Some(prompt) if prompt.has_key(answer) => answer.to_string(),
+            _ => format!("{}\r", answer),
+        };
+        agent.prompts.answered();
+        (pty_id, data)
+    };
+
+    pty::write_session(&app.state::<PtyState>(), pty_id, &data)
+}
+
+/// Stop an agent and forget it
+#[tauri::command]
+pub async fn remove_agent(
+    app: AppHandle,
+    state: State<'_, AgentState>,
+    id: u32,
+) -> Result<(), String> {
+    stop(&app, id)?;
+    state.agents.lock().remove(&id);
+    Ok(())
+}
+
+/// Structured activity of a `streamJson` agent's current run, oldest first
+#[tauri::command]
+pub async fn get_agent_activity(
+    state: State<'_, AgentState>,
+    id: u32,
+) -> Result<Vec<AgentActivity>, String> {
+    let agents = state.agents.lock();
+    let agent = agents
+        .get(&id)
+        .ok_or_else(|| format!("Agent {} not found", id))?;
+    Ok(agent.activity.iter().cloned().collect())
+}
+
+/// All known agents, running or exited
+#[tauri::command]
+pub async fn list_agents(state: State<'_, AgentState>) -> Result<Vec<AgentInfo>, String> {
+    Ok(agent_list(&state))
+}
+
+/// All known agents, oldest first, as `list_agents` returns them
+pub(crate) fn agent_list(state: &AgentState) -> Vec<AgentInfo> {
+    let agents = state.agents.lock();
+    let mut info: Vec<AgentInfo> = agents.iter().map(|(id, agent)| agent.info(*id)).collect();
+    info.sort_by_key(|agent| agent.id);
+    info
+}
@@ -0,0 +1,212 @@
+//! Approval prompt detection
+//!
+//! Claude Code asks before editing files or running commands with a question
+//! followed by a numbered menu, the selected entry marked with `❯`:
+//!
+//! ```text
+//! Do you want to proceed?
+//! ❯ 1. Yes
+//!   2. Yes, and don't ask again for this command
+//!   3. No, and tell Claude what to do differently (esc)
+//! ```
+//!
+//! The detector keeps the recent output with escape sequences stripped and
+//! looks for that shape at its end.
+
+use serde::Serialize;
+
+/// Stripped output kept for matching
+const TAIL_CHARS: usize = 8 * 1024;
+
+/// Output seen after a prompt before it is considered answered
+const ANSWERED_AFTER_CHARS: usize = 2 * 1024;
+
+/// Lines above the menu searched for the question
+const QUESTION_LOOKBACK: usize = 4;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptOption {
+    /// What to type to pick this option
+    key: String,
+    label: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApprovalPrompt {
+    question: String,
+    options: Vec<PromptOption>,
+}
+
+impl ApprovalPrompt {
+    pub(crate) fn question(&self) -> &str {
+        &self.question
+    }
+
+    pub(crate) fn has_key(&self, key: &str) -> bool {
+        self.options.iter().any(|option| option.key == key)
+    }
+}
+
+/// Watches an agent's output for approval prompts
+#[derive(Default)]
+pub(crate) struct PromptDetector {
+    tail: String,
+    /// Prompt reported and not yet answered
+    pending: Option<ApprovalPrompt>,
+    /// Characters seen since `pending` was detected
+    since_pending: usize,
+}
+
+impl PromptDetector {
+    /// Feed output, returning a prompt the first time it appears
+    pub(crate) fn push(&mut self, data: &str) -> Option<ApprovalPrompt> {
+        let text = strip_ansi(data);
+        self.tail.push_str(&text);
+        if self.tail.len() > TAIL_CHARS {
+            let mut cut = self.tail.len() - TAIL_CHARS;
+            while !self.tail.is_char_boundary(cut) {
+                cut += 1;
+            }
+            self.tail.drain(..cut);
+        }
+
+        match find_prompt(&self.tail) {
+            Some(prompt) if self.pending.as_ref() != Some(&prompt) => {
+                self.pending = Some(prompt.clone());
+                self.since_pending = 0;
+                // Redraws of this prompt shouldn't match again once it's answered
+                self.tail.clear();
+                Some(prompt)
+            }
+            Some(_) => {
+                self.tail.clear();
+                None
+            }
+            None => {
+                // Plenty of output without the prompt: it was answered in the terminal
+                self.since_pending += text.len();
+                if self.since_pending > ANSWERED_AFTER_CHARS {
+                    self.pending = None;
+                }
+                None
+            }
+        }
+    }
+
+    pub(crate) fn pending(&self) -> Option<&ApprovalPrompt> {
+        self.pending.as_ref()
+    }
+
+    /// Forget the pending prompt once it has been answered
+    pub(crate) fn answered(&mut self) {
+        self.pending = None;
+        self.since_pending = 0;
+        self.tail.clear();
+    }
+}
+
+/// Remove escape sequences, turning cursor movement into line breaks so
+/// redrawn lines don't run together
+pub(crate) fn strip_ansi(data: &str) -> String {
+    let mut out = String::with_capacity(data.len());
+    let mut chars = data.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\x1b' => match chars.next() {
+                Some('[') => {
+                    let mut last = None;
+                    for c in chars.by_ref() {
+                        if ('\x40'..='\x7e').contains(&c) {
+                            last = Some(c);
+                            break;
+                        }
+                    }
+                    match last {
+                        Some('C') => out.push(' '),
+                        Some('A' | 'B' | 'E' | 'F' | 'G' | 'H' | 'd' | 'f') => out.push('\n'),
+                        _ => {}
+                    }
+                }
+                // OSC runs until BEL or ST
+                Some(']') => {
+                    while let Some(c) = chars.next() {
+                        if c == '\x07' {
+                            break;
+                        }
+                        if c == '\x1b' && chars.peek() == Some(&'\\') {
+                            chars.next();
+                            break;
+                        }
+                    }
+                }
+                _ => {}
+            },
+            '\r' => out.push('\n'),
+            c if c.is_control() && c != '\n' && c != '\t' => {}
+            c => out.push(c),
+        }
+    }
+
+    out
+}
+
+/// Parse `❯ 1. Yes` / `2. No` into (selected, key, label)
+fn parse_option(line: &str) -> Option<(bool, String, String)> {
+    let (selected, rest) = match line.strip_prefix('❯').or_else(|| line.strip_prefix('>')) {
+        Some(rest) => (true, rest.trim_start()),
+        None => (false, line),
+    };
+    let (key, label) = rest.split_once(". ")?;
+    if key.is_empty() || !key.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let label = label.trim();
+    if label.is_empty() {
+        return None;
+    }
+    Some((selected, key.to_string(), label.to_string()))
+}
+
+fn find_prompt(text: &str) -> Option<ApprovalPrompt> {
+    // Drop the box drawing around the prompt
+    let lines: Vec<&str> = text
+        .lines()
+        .map(|line| line.trim_matches(|c: char| c.is_whitespace() || "│╭╮╰╯─".contains(c)))
+        .filter(|line| !line.is_empty())
+        .collect();
+
+    // Last run of consecutive option lines
+    let end = lines
+        .iter()
+        .rposition(|line| parse_option(line).is_some())?
+        + 1;
+    let mut start = end;
+    while start > 0 && parse_option(lines[start - 1]).is_some() {
+        start -= 1;
+    }
+
+    let parsed: Vec<(bool, String, String)> = lines[start..end]
+        .iter()
+        .filter_map(|line| parse_option(line))
+        .collect();
+    // Plain numbered lists in the transcript have no selection marker
+    if parsed.len() < 2 || parsed[0].1 != "1" || !parsed.iter().any(|(selected, ..)| *selected) {
+        return None;
+    }
+
+    let question = lines[start.saturating_sub(QUESTION_LOOKBACK)..start]
+        .iter()
+        .rev()
+        .find(|line| line.ends_with('?'))?;
+
+    Some(ApprovalPrompt {
+        question: question.to_string(),
+        options: parsed
+            .into_iter()
+            .map(|(_, key, label)| PromptOption { key, label })
+            .collect(),
+    })
+}
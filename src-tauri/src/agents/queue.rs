@@ -0,0 +1,162 @@
+//! Concurrency limit for agents
+//!
+//! Only so many Claude Code sessions run at once; further starts wait in a
+//! FIFO queue and launch as running agents exit. Queued agents are told their
+//! place with `agent-queue-position` events whenever the queue moves.
+
+use super::{launch, AgentInfo, AgentState, AgentStatus};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// Agents allowed to run at once until the user changes it
+pub(super) const DEFAULT_MAX_RUNNING: usize = 3;
+
+/// Payload of `agent-queue-position`
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct QueuePositionEvent {
+    id: u32,
+    repo: String,
+    issue: u64,
+    /// 1 is next to start
+    position: usize,
+    queued: usize,
+}
+
+fn running(state: &AgentState) -> usize {
+    state
+        .agents
+        .lock()
+        .values()
+        .filter(|agent| {
+            matches!(
+                agent.status,
+                AgentStatus::Starting | AgentStatus::Working | AgentStatus::Waiting
+            )
+        })
+        .count()
+}
+
+fn has_slot(state: &AgentState) -> bool {
+    let max = *state.max_running.lock();
+    max.is_none_or(|max| running(state) < max)
+}
+
+/// Launch the agent now if a slot is free, otherwise queue it
+pub(super) fn start_or_queue(app: &AppHandle, id: u32) -> Result<AgentInfo, String> {
+    let state = app.state::<AgentState>();
+    {
+        // Held while launching so concurrent starts can't both take the last slot
+        let queue = state.queue.lock();
+        // Agents already waiting go first
+        if queue.is_empty() && has_slot(&state) {
+            return launch(app, id);
+        }
+    }
+
+    let event = {
+        let mut agents = state.agents.lock();
+        let agent = agents
+            .get_mut(&id)
+            .ok_or_else(|| format!("Agent {} not found", id))?;
+        agent.transition(id, AgentStatus::Queued)
+    };
+    {
+        let mut queue = state.queue.lock();
+        if !queue.contains(&id) {
+            queue.push_back(id);
+        }
+    }
+    if let Some(event) = event {
+        let _ = app.emit("agent-state", event);
+    }
+    emit_positions(app);
+    // A slot may have freed up since the check above
+    start_next(app);
+
+    super::info(&state, id)
+}
+
+/// Take an agent out of the queue, if it is in it
+pub(super) fn dequeue(app: &AppHandle, id: u32) {
+    let state = app.state::<AgentState>();
+    let removed = {
+        let mut queue = state.queue.lock();
+        let before = queue.len();
+        queue.retain(|queued| *queued != id);
+        queue.len() != before
+    };
+    if removed {
+        emit_positions(app);
+    }
+}
+
+/// Launch queued agents while there are free slots
+pub(super) fn start_next(app: &AppHandle) {
+    let state = app.state::<AgentState>();
+    let mut started = false;
+    loop {
+        let mut queue = state.queue.lock();
+        if !has_slot(&state) {
+            break;
+        }
+        let Some(id) = queue.pop_front() else {
+            break;
+        };
+        started = true;
+        if let Err(e) = launch(app, id) {
+            eprintln!("Failed to start queued agent {}: {}", id, e);
+        }
+    }
+    if started {
+        emit_positions(app);
+    }
+}
+
+fn emit_positions(app: &AppHandle) {
+    let state = app.state::<AgentState>();
+    let queue: Vec<u32> = state.queue.lock().iter().copied().collect();
+    let events: Vec<QueuePositionEvent> = {
+        let agents = state.agents.lock();
+        queue
+            .iter()
+            .enumerate()
+            .filter_map(|(index, id)| {
+                agents.get(id).map(|agent| QueuePositionEvent {
+                    id: *id,
+                    repo: agent.options.issue.repo.clone(),
+                    issue: agent.options.issue.number,
+                    position: index + 1,
+                    queued: queue.len(),
+                })
+            })
+            .collect()
+    };
+    for event in events {
+        let _ = app.emit("agent-queue-position", event);
+    }
+}
+
+/// Set how many agents may run at once; `None` removes the limit.
+///
+/// Raising the limit starts queued agents right away. Lowering it never stops
+/// running ones, it only holds back new starts.
+#[tauri::command]
+pub async fn set_agent_limit(
+    app: AppHandle,
+    state: State<'_, AgentState>,
+    limit: Option<usize>,
+) -> Result<(), String> {
+    if limit == Some(0) {
+        return Err("Agent limit must be at least 1".to_string());
+    }
+    *state.max_running.lock() = limit;
+    start_next(&app);
+    Ok(())
+}
+
+/// Current agent limit, `None` when unlimited
+#[tauri::command]
+pub async fn get_agent_limit(state: State<'_, AgentState>) -> Result<Option<usize>, String> {
+    Ok(*state.max_running.lock())
+}
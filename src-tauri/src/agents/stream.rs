@@ -0,0 +1,354 @@
+//! Claude Code `stream-json` output
+//!
+//! With `-p --output-format stream-json --verbose` Claude Code prints one JSON
+//! object per line instead of drawing its UI: the session's `system` init,
+//! `assistant` messages made of text, thinking and tool-use blocks, `user`
+//! messages carrying tool results, and a closing `result`. The parser turns
+//! those lines into [`AgentActivity`] entries for the card's activity feed.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+
+/// A line longer than this is dropped rather than buffered further
+const MAX_LINE: usize = 8 * 1024 * 1024;
+
+/// Characters of a tool result kept for display
+const RESULT_PREVIEW_CHARS: usize = 2000;
+
+/// Tools whose input names a file they change
+const EDIT_TOOLS: [&str; 4] = ["Edit", "MultiEdit", "Write", "NotebookEdit"];
+
+/// Token counts as reported by the API
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct TokenUsage {
+    #[serde(default)]
+    pub(crate) input_tokens: u64,
+    #[serde(default)]
+    pub(crate) output_tokens: u64,
+    #[serde(default)]
+    pub(crate) cache_creation_input_tokens: u64,
+    #[serde(default)]
+    pub(crate) cache_read_input_tokens: u64,
+}
+
+/// One entry of an agent's structured activity
+#[derive(Clone, Debug, Serialize)]
+#[serde(
+    tag = "type",
+    rename_all = "snake_case",
+    rename_all_fields = "camelCase"
+)]
+pub enum AgentActivity {
+    /// The session started
+    Init {
+        session_id: Option<String>,
+        model: Option<String>,
+        tools: Vec<String>,
+    },
+    /// Text the assistant wrote
+    Message {
+        text: String,
+    },
+    Thinking {
+        text: String,
+    },
+    ToolCall {
+        tool_use_id: String,
+        name: String,
+        input: Value,
+    },
+    /// A tool call that changes a file, reported alongside its `tool_call`
+    FileEdit {
+        tool_use_id: String,
+        tool: String,
+        path: String,
+    },
+    ToolResult {
+        tool_use_id: String,
+        is_error: bool,
+        /// Start of the result's text
+        preview: String,
+    },
+    /// Tokens used by one API response
+    Usage {
+        message_id: Option<String>,
+        model: Option<String>,
+        usage: TokenUsage,
+    },
+    /// The run finished
+    Result {
+        is_error: bool,
+        subtype: Option<String>,
+        result: Option<String>,
+        duration_ms: Option<u64>,
+        num_turns: Option<u64>,
+        total_cost_usd: Option<f64>,
+        usage: Option<TokenUsage>,
+    },
+    /// A line that was not JSON, such as an error printed before streaming
+    Output {
+        text: String,
+    },
+}
+
+/// Splits PTY output into lines and parses them
+#[derive(Default)]
+pub(crate) struct StreamParser {
+    buffer: String,
+    /// Messages whose usage was already reported; each content block of a
+    /// message repeats it
+    counted: HashSet<String>,
+}
+
+impl StreamParser {
+    pub(crate) fn push(&mut self, data: &str) -> Vec<AgentActivity> {
+        self.buffer.push_str(data);
+        let mut activities = Vec::new();
+        while let Some(end) = self.buffer.find('\n') {
+            let line: String = self.buffer.drain(..=end).collect();
+            let line = line.trim();
+            if !line.is_empty() {
+                self.parse_line(line, &mut activities);
+            }
+        }
+        if self.buffer.len() > MAX_LINE {
+            self.buffer.clear();
+        }
+        activities
+    }
+
+    fn parse_line(&mut self, line: &str, out: &mut Vec<AgentActivity>) {
+        let Ok(value) = serde_json::from_str::<Value>(line) else {
+            out.push(AgentActivity::Output {
+                text: line.to_string(),
+            });
+            return;
+        };
+
+        match value["type"].as_str() {
+            Some("system") if value["subtype"] == "init" => out.push(AgentActivity::Init {
+                session_id: string(&value["session_id"]),
+                model: string(&value["model"]),
+                tools: value["tools"]
+                    .as_array()
+                    .map(|tools| tools.iter().filter_map(string).collect())
+                    .unwrap_or_default(),
+            }),
+            Some("assistant") => self.assistant(&value["message"], out),
+            Some("user") => {
+                for block in blocks(&value["message"]) {
+                    if block["type"] == "tool_result" {
+                        out.push(AgentActivity::ToolResult {
+                            tool_use_id: string(&block["tool_use_id"]).unwrap_or_default(),
+                            is_error: block["is_error"].as_bool().unwrap_or(false),
+                            preview: preview(&block["content"]),
+                        });
+                    }
+                }
+            }
+            Some("result") => out.push(AgentActivity::Result {
+                is_error: value["is_error"].as_bool().unwrap_or(false),
+                subtype: string(&value["subtype"]),
+                result: string(&value["result"]),
+                duration_ms: value["duration_ms"].as_u64(),
+                num_turns: value["num_turns"].as_u64(),
+                total_cost_usd: value["total_cost_usd"].as_f64(),
+                usage: serde_json::from_value(value["usage"].clone()).ok(),
+            }),
+            _ => {}
+        }
+    }
+
+    fn assistant(&mut self, message: &Value, out: &mut Vec<AgentActivity>) {
+        for block in blocks(message) {
+            match block["type"].as_str() {
+                Some("text") => {
+                    if let Some(text) = string(&block["text"]) {
+                        out.push(AgentActivity::Message { text });
+                    }
+                }
+                Some("thinking") => {
+                    if let Some(text) = string(&block["thinking"]) {
+                        out.push(AgentActivity::Thinking { text });
+                    }
+                }
+                Some("tool_use") => {
+                    let tool_use_id = string(&block["id"]).unwrap_or_default();
+                    let name = string(&block["name"]).unwrap_or_default();
+                    let input = block["input"].clone();
+                    let path =
+                        string(&input["file_path"]).or_else(|| string(&input["notebook_path"]));
+                    let edit = EDIT_TOOLS
+                        .contains(&name.as_str())
+                        .then_some(path)
+                        .flatten();
+                    out.push(AgentActivity::ToolCall {
+                        tool_use_id: tool_use_id.clone(),
+                        name: name.clone(),
+                        input,
+                    });
+                    if let Some(path) = edit {
+                        out.push(AgentActivity::FileEdit {
+                            tool_use_id,
+                            tool: name,
+                            path,
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let message_id = string(&message["id"]);
+        let first = message_id
+            .as_ref()
+            .is_none_or(|id| self.counted.insert(id.clone()));
+        if first {
+            if let Ok(usage) = serde_json::from_value::<TokenUsage>(message["usage"].clone()) {
+                out.push(AgentActivity::Usage {
+                    message_id,
+                    model: string(&message["model"]),
+                    usage,
+                });
+            }
+        }
+    }
+}
+
+fn string(value: &Value) -> Option<String> {
+    value.as_str().map(str::to_string)
+}
+
+fn blocks(message: &Value) -> impl Iterator<Item = &Value> {
+    message["content"].as_array().into_iter().flatten()
+}
+
+/// Text of a tool result, which is a string or a list of text blocks
+fn preview(content: &Value) -> String {
+    let text = match content {
+        Value::String(text) => text.clone(),
+        Value::Array(parts) => parts
+            .iter()
+            .filter_map(|part| part["text"].as_str())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    };
+    text.chars().take(RESULT_PREVIEW_CHARS).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn joins_lines_split_across_chunks() {
+        let mut parser = StreamParser::default();
+        assert!(parser
+            .push(r#"{"type":"system","subtype":"init","#)
+            .is_empty());
+        let activities = parser.push("\"model\":\"opus\",\"tools\":[\"Bash\",1]}\n");
+        assert!(matches!(
+            activities.as_slice(),
+            [AgentActivity::Init { session_id: None, model: Some(model), tools }]
+                if model == "opus" && tools == &["Bash"]
+        ));
+    }
+
+    #[test]
+    fn passes_on_lines_that_are_not_json() {
+        let mut parser = StreamParser::default();
+        let activities = parser.push("\n  \nError: not logged in\r\n{\"type\":\"unknown\"}\n");
+        assert!(matches!(
+            activities.as_slice(),
+            [AgentActivity::Output { text }] if text == "Error: not logged in"
+        ));
+    }
+
+    #[test]
+    fn reports_file_edits_with_their_tool_calls() {
+        let mut parser = StreamParser::default();
+        let line = r#"{"type":"assistant","message":{"content":[
+            {"type":"tool_use","id":"t1","name":"Edit","input":{"file_path":"src/a.rs"}},
+            {"type":"tool_use","id":"t2","name":"Read","input":{"file_path":"src/b.rs"}},
+            {"type":"tool_use","id":"t3","name":"Write","input":{}}
+        ]}}"#
+            .replace('\n', "");
+        let activities = parser.push(&format!("{}\n", line));
+        assert!(matches!(
+            activities.as_slice(),
+            [
+                AgentActivity::ToolCall { name: first, .. },
+                AgentActivity::FileEdit { tool_use_id, path, .. },
+                AgentActivity::ToolCall { name: second, .. },
+                AgentActivity::ToolCall { name: third, .. },
+            ] if first == "Edit" && tool_use_id == "t1" && path == "src/a.rs"
+                && second == "Read" && third == "Write"
+        ));
+    }
+
+    #[test]
+    fn counts_usage_once_per_message() {
+        let mut parser = StreamParser::default();
+        let line = r#"{"type":"assistant","message":{"id":"m1","content":[{"type":"text","text":"hi"}],"usage":{"input_tokens":3,"output_tokens":5}}}"#;
+        let first = parser.push(&format!("{}\n", line));
+        let again = parser.push(&format!("{}\n", line));
+        assert!(matches!(
+            first.as_slice(),
+            [AgentActivity::Message { .. }, AgentActivity::Usage { usage, .. }]
+                if usage.input_tokens == 3 && usage.output_tokens == 5
+        ));
+        assert!(matches!(again.as_slice(), [AgentActivity::Message { .. }]));
+    }
+
+    #[test]
+    fn previews_tool_results() {
+        let mut parser = StreamParser::default();
+        let long = "x".repeat(RESULT_PREVIEW_CHARS + 10);
+        let line = serde_json::json!({
+            "type": "user",
+            "message": {"content": [
+                {"type": "tool_result", "tool_use_id": "t1", "content": [
+                    {"type": "text", "text": "a"}, {"type": "image"}, {"type": "text", "text": "b"}
+                ]},
+                {"type": "tool_result", "tool_use_id": "t2", "is_error": true, "content": long},
+                {"type": "tool_result", "content": 5},
+            ]},
+        });
+        let activities = parser.push(&format!("{}\n", line));
+        assert!(matches!(
+            activities.as_slice(),
+            [
+                AgentActivity::ToolResult { is_error: false, preview: joined, .. },
+                AgentActivity::ToolResult { is_error: true, preview: cut, .. },
+                AgentActivity::ToolResult { tool_use_id, preview: empty, .. },
+            ] if joined == "a\nb" && cut.len() == RESULT_PREVIEW_CHARS
+                && tool_use_id.is_empty() && empty.is_empty()
+        ));
+    }
+
+    #[test]
+    fn drops_an_overlong_line() {
+        let mut parser = StreamParser::default();
+        assert!(parser.push(&"x".repeat(MAX_LINE + 1)).is_empty());
+        let activities = parser.push("tail\n");
+        assert!(matches!(
+            activities.as_slice(),
+            [AgentActivity::Output { text }] if text == "tail"
+        ));
+    }
+
+    #[test]
+    fn reads_the_result() {
+        let mut parser = StreamParser::default();
+        let line = r#"{"type":"result","subtype":"success","is_error":false,"num_turns":2,"total_cost_usd":0.5,"usage":"bad"}"#;
+        let activities = parser.push(&format!("{}\n", line));
+        assert!(matches!(
+            activities.as_slice(),
+            [AgentActivity::Result { is_error: false, subtype: Some(subtype), num_turns: Some(2), usage: None, .. }]
+                if subtype == "success"
+        ));
+    }
+}
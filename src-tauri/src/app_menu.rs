@@ -0,0 +1,281 @@
+//! Application menu - File, Edit, Session and View
+//!
+//! Replaces the default menu bar with Antler's own. Items acting on the
+//! window itself are handled here: zoom, full screen and the drop-down
+//! terminal. The rest depend on what the UI shows, such as the terminal in
+//! front for "Kill Session", so they are sent to the focused window as
+//! `menu-command` events. "Go to Session" lists the open PTY sessions, named
+//! after the issue of the agent running in them, and is rebuilt as sessions
+//! start and exit and agents change state.
+
+use crate::agents::{self, AgentState};
+use crate::pty::{self, PtyState};
+use crate::{quake, tray};
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::HashMap;
+use tauri::menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::{AppHandle, Emitter, Listener, Manager, WebviewWindow, Wry};
+
+/// Zoom limits and step of the View menu
+const MIN_ZOOM: f64 = 0.5;
+const MAX_ZOOM: f64 = 3.0;
+const ZOOM_STEP: f64 = 0.1;
+
+/// Prefix of the menu's item ids, keeping them apart from the tray's
+const ID_PREFIX: &str = "menu:";
+
+/// State for the application menu
+#[derive(Default)]
+pub struct AppMenuState {
+    /// The "Go to Session" submenu, refilled as sessions change
+    sessions: Mutex<Option<Submenu<Wry>>>,
+    /// Zoom factor of each window
+    zoom: Mutex<HashMap<String, f64>>,
+}
+
+/// Payload of `menu-command`
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MenuCommandEvent {
+    /// `new-terminal`, `kill-session`, `pop-out-session`, `dock-session`,
+    /// `clear-terminal` or `focus-session`
+    command: String,
+    /// Session picked from "Go to Session"
+    pty_id: Option<u32>,
+}
+
+/// Commands left to the UI
+const UI_COMMANDS: &[&str] = &[
+    "new-terminal",
+    "kill-session",
+    "pop-out-session",
+    "dock-session",
+    "clear-terminal",
+];
+
+fn item(
+    app: &AppHandle,
+    id: &str,
+    text: &str,
+    accelerator: Option<&str>,
+) -> tauri::Result<MenuItem<Wry>> {
+    MenuItem::with_id(app, format!("{}{}", ID_PREFIX, id), text, true, accelerator)
+}
+
+fn build(app: &AppHandle) -> tauri::Result<(Menu<Wry>, Submenu<Wry>)> {
+    let menu = Menu::new(app)?;
+
+    #[cfg(target_os = "macos")]
+    menu.append(&Submenu::with_items(
+        app,
+        "Antler",
+        true,
+        &[
+            &PredefinedMenuItem::about(app, None, None)?,
+            &PredefinedMenuItem::separator(app)?,
+            &PredefinedMenuItem::services(app, None)?,
+            &PredefinedMenuItem::separator(app)?,
+            &PredefinedMenuItem::hide(app, None)?,
+            &PredefinedMenuItem::hide_others(app, None)?,
+            &PredefinedMenuItem::show_all(app, None)?,
+            &PredefinedMenuItem::separator(app)?,
+            &PredefinedMenuItem::quit(app, None)?,
+        ],
+    )?)?;
+
+    let file = Submenu::with_items(
+        app,
+        "File",
+        true,
+        &[
+            &item(app, "new-terminal", "New Terminal", Some("CmdOrCtrl+T"))?,
+            &PredefinedMenuItem::separator(app)?,
+            &PredefinedMenuItem::close_window(app, None)?,
+        ],
+    )?;
+    #[cfg(not(target_os = "macos"))]
+    {
+        file.append(&PredefinedMenuItem::separator(app)?)?;
+        file.append(&PredefinedMenuItem::quit(app, None)?)?;
+    }
+    menu.append(&file)?;
+
+    // Clipboard shortcuts only reach the webview through these on macOS
+    menu.append(&Submenu::with_items(
+        app,
+        "Edit",
+        true,
+        &[
+            &PredefinedMenuItem::undo(app, None)?,
+            &PredefinedMenuItem::redo(app, None)?,
+            &PredefinedMenuItem::separator(app)?,
+            &PredefinedMenuItem::cut(app, None)?,
+            &PredefinedMenuItem::copy(app, None)?,
+            &PredefinedMenuItem::paste(app, None)?,
+            &PredefinedMenuItem::select_all(app, None)?,
+        ],
+    )?)?;
+
+    let sessions = Submenu::new(app, "Go to Session", true)?;
+    fill_sessions(app, &sessions)?;
+    menu.append(&Submenu::with_items(
+        app,
+        "Session",
+        true,
+        &[
+            &item(
+                app,
+                "kill-session",
+                "Kill Session",
+                Some("CmdOrCtrl+Shift+K"),
+            )?,
+            &item(app, "clear-terminal", "Clear Terminal", Some("CmdOrCtrl+K"))?,
+            &PredefinedMenuItem::separator(app)?,
+            &item(app, "pop-out-session", "Pop Out Session", None)?,
+            &item(app, "dock-session", "Dock Session", None)?,
+            &PredefinedMenuItem::separator(app)?,
+            &sessions,
+        ],
+    )?)?;
+
+    menu.append(&Submenu::with_items(
+        app,
+        "View",
+        true,
+        &[
+            &item(app, "zoom-in", "Zoom In", Some("CmdOrCtrl+="))?,
+            &item(app, "zoom-out", "Zoom Out", Some("CmdOrCtrl+-"))?,
+            &item(app, "zoom-reset", "Actual Size", Some("CmdOrCtrl+0"))?,
+            &PredefinedMenuItem::separator(app)?,
+            &item(app, "toggle-quake", "Drop-down Terminal", None)?,
+            &item(
+                app,
+                "toggle-fullscreen",
+                "Toggle Full Screen",
+                Some(if cfg!(target_os = "macos") {
+                    "Ctrl+Cmd+F"
+                } else {
+                    "F11"
+                }),
+            )?,
+        ],
+    )?)?;
+
+    Ok((menu, sessions))
+}
+
+/// Replace the entries of "Go to Session" with the open sessions
+fn fill_sessions(app: &AppHandle, submenu: &Submenu<Wry>) -> tauri::Result<()> {
+    for entry in submenu.items()? {
+        submenu.remove(&entry)?;
+    }
+
+    let agents = agents::agent_list(&app.state::<AgentState>());
+    let sessions = pty::session_programs(&app.state::<PtyState>());
+    if sessions.is_empty() {
+        submenu.append(&MenuItem::new(app, "No Sessions", false, None::<&str>)?)?;
+    }
+    for (pty_id, program) in sessions {
+        let text = match agents.iter().find(|agent| agent.pty_id == Some(pty_id)) {
+            Some(agent) => tray::agent_label(agent),
+            None => {
+                let name = std::path::Path::new(&program)
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| "shell".to_string());
+                format!("Terminal {} ({})", pty_id, name)
+            }
+        };
+        submenu.append(&item(app, &format!("session:{}", pty_id), &text, None)?)?;
+    }
+    Ok(())
+}
+
+/// Rebuild "Go to Session" after sessions or agents changed
+pub(crate) fn refresh_sessions(app: &AppHandle) {
+    let submenu = app.state::<AppMenuState>().sessions.lock().clone();
+    if let Some(submenu) = submenu {
+        if let Err(e) = fill_sessions(app, &submenu) {
+            eprintln!("Failed to update the session menu: {}", e);
+        }
+    }
+}
+
+/// The window menu commands act on
+fn focused_window(app: &AppHandle) -> Option<WebviewWindow> {
+    let windows = app.webview_windows();
+    windows
+        .values()
+        .find(|window| window.is_focused().unwrap_or(false))
+        .or_else(|| windows.get("main"))
+        .cloned()
+}
+
+fn zoom(app: &AppHandle, window: &WebviewWindow, change: Option<f64>) {
+    let state = app.state::<AppMenuState>();
+    let mut levels = state.zoom.lock();
+    let level = levels.entry(window.label().to_string()).or_insert(1.0);
+    *level = match change {
+        Some(change) => (*level + change).clamp(MIN_ZOOM, MAX_ZOOM),
+        None => 1.0,
+    };
+    if let Err(e) = window.set_zoom(*level) {
+        eprintln!("Failed to zoom: {}", e);
+    }
+}
+
+fn on_menu_event(app: &AppHandle, event: MenuEvent) {
+    // Tray menu events arrive here too
+    let Some(id) = event.id().as_ref().strip_prefix(ID_PREFIX) else {
+        return;
+    };
+    let window = focused_window(app);
+    let command = if let Some(pty_id) = id.strip_prefix("session:") {
+        MenuCommandEvent {
+            command: "focus-session".to_string(),
+            pty_id: pty_id.parse().ok(),
+        }
+    } else if UI_COMMANDS.contains(&id) {
+        MenuCommandEvent {
+            command: id.to_string(),
+            pty_id: None,
+        }
+    } else {
+        match (id, window) {
+            ("zoom-in", Some(window)) => zoom(app, &window, Some(ZOOM_STEP)),
+            ("zoom-out", Some(window)) => zoom(app, &window, Some(-ZOOM_STEP)),
+            ("zoom-reset", Some(window)) => zoom(app, &window, None),
+            ("toggle-fullscreen", Some(window)) => {
+                let fullscreen = window.is_fullscreen().unwrap_or(false);
+                let _ = window.set_fullscreen(!fullscreen);
+            }
+            ("toggle-quake", _) => {
+                if let Err(e) = quake::toggle(app) {
+                    eprintln!("{}", e);
+                }
+            }
+            _ => {}
+        }
+        return;
+    };
+
+    let _ = match window {
+        Some(window) => app.emit_to(window.label(), "menu-command", command),
+        None => app.emit("menu-command", command),
+    };
+}
+
+/// Install the menu and keep "Go to Session" current
+pub(crate) fn create(app: &AppHandle) -> tauri::Result<()> {
+    let (menu, sessions) = build(app)?;
+    app.set_menu(menu)?;
+    app.on_menu_event(on_menu_event);
+    *app.state::<AppMenuState>().sessions.lock() = Some(sessions);
+
+    for event in ["agent-state", "pty-exit"] {
+        let handle = app.clone();
+        app.listen(event, move |_| refresh_sessions(&handle));
+    }
+    Ok(())
+}
@@ -0,0 +1,167 @@
+//! User attention - the dock icon bounces, the taskbar button flashes
+//!
+//! While no Antler window has focus, a session that needs the user asks
+//! for attention on the window showing it: its pop-out window if it has one,
+//! the main window otherwise. An agent stopping at an approval prompt keeps
+//! the dock icon bouncing until the app is brought forward; a terminal bell,
+//! also emitted as `pty-bell`, bounces it once. On Windows both flash the
+//! taskbar button until the window is. Bells inside OSC sequences only end
+//! the sequence and don't count. `set_session_attention` turns this off for
+//! a session.
+
+use crate::{notifications, popout};
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, State, UserAttentionType};
+
+/// A session's bells ask for attention at most once in this long, so a
+/// shell beeping at every failed completion doesn't keep the dock bouncing
+const BELL_COOLDOWN: Duration = Duration::from_secs(5);
+
+/// State for attention requests
+#[derive(Default)]
+pub struct AttentionState {
+    /// PTYs that never ask for attention
+    muted: Mutex<HashSet<u32>>,
+    /// Where each PTY's output stopped in an escape sequence
+    scanners: Mutex<HashMap<u32, Scanner>>,
+    /// When each PTY's bell last asked for attention
+    last_bell: Mutex<HashMap<u32, Instant>>,
+}
+
+/// Payload of `pty-bell`
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PtyBellEvent {
+    pty_id: u32,
+}
+
+/// Escape-sequence state carried between output chunks
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum Scanner {
+    #[default]
+    Ground,
+    Escape,
+    /// OSC, ended by BEL or ST
+    Osc,
+    /// DCS, SOS, PM or APC, ended by ST
+    Text,
+    /// ESC inside an OSC or text sequence, maybe the start of ST
+    TextEscape,
+}
+
+impl Scanner {
+    /// Feed `data`, returning whether it rang the bell
+    fn scan(&mut self, data: &str) -> bool {
+        let mut rang = false;
+        for c in data.chars() {
+            *self = match (*self, c) {
+                (Scanner::Ground, '\x07') => {
+                    rang = true;
+                    Scanner::Ground
+                }
+                (Scanner::Ground, '\x1b') => Scanner::Escape,
+                (Scanner::Ground, _) => Scanner::Ground,
+                (Scanner::Osc, '\x07') => Scanner::Ground,
+                (Scanner::Osc | Scanner::Text, '\x1b') => Scanner::TextEscape,
+                (Scanner::Osc, _) => Scanner::Osc,
+                (Scanner::Text, _) => Scanner::Text,
+                (Scanner::TextEscape, '\\') => Scanner::Ground,
+                // Anything else after ESC ends the sequence and may start one
+                (Scanner::Escape | Scanner::TextEscape, ']') => Scanner::Osc,
+                (Scanner::Escape | Scanner::TextEscape, 'P' | 'X' | '^' | '_') => Scanner::Text,
+                (Scanner::Escape | Scanner::TextEscape, '\x1b') => Scanner::Escape,
+                (Scanner::Escape | Scanner::TextEscape, _) => Scanner::Ground,
+            };
+        }
+        rang
+    }
+}
+
+/// The window showing PTY `pty_id`, or the main window
+fn session_window(app: &AppHandle, pty_id: Option<u32>) -> Option<tauri::WebviewWindow> {
+    pty_id
+        .and_then(|id| app.get_webview_window(&popout::label(id)))
+        .or_else(|| app.get_webview_window("main"))
+}
+
+fn request(app: &AppHandle, pty_id: Option<u32>, kind: UserAttentionType) {
+    if notifications::window_focused(app) {
+        return;
+    }
+    if let Some(id) = pty_id {
+        if app.state::<AttentionState>().muted.lock().contains(&id) {
+            return;
+        }
+    }
+    if let Some(window) = session_window(app, pty_id) {
+        if let Err(e) = window.request_user_attention(Some(kind)) {
+            eprintln!("Failed to request attention: {}", e);
+        }
+    }
+}
+
+/// An agent is waiting at an approval prompt
+pub(crate) fn agent_needs_input(app: &AppHandle, pty_id: Option<u32>) {
+    request(app, pty_id, UserAttentionType::Critical);
+}
+
+/// Look for bells in a chunk of a PTY's output
+pub(crate) fn scan_output(app: &AppHandle, pty_id: u32, data: &str) {
+    let state = app.state::<AttentionState>();
+    let rang = {
+        let mut scanners = state.scanners.lock();
+        // Most output has no BEL or ESC and isn't inside a sequence
+        if !data.contains(['\x07', '\x1b']) && !scanners.contains_key(&pty_id) {
+            return;
+        }
+        let scanner = scanners.entry(pty_id).or_default();
+        let rang = scanner.scan(data);
+        if *scanner == Scanner::Ground {
+            scanners.remove(&pty_id);
+        }
+        rang
+    };
+    if !rang {
+        return;
+    }
+
+    let _ = app.emit("pty-bell", PtyBellEvent { pty_id });
+    let now = Instant::now();
+    {
+        let mut last_bell = state.last_bell.lock();
+        if let Some(last) = last_bell.get(&pty_id) {
+            if now.duration_since(*last) < BELL_COOLDOWN {
+                return;
+            }
+        }
+        last_bell.insert(pty_id, now);
+    }
+    request(app, Some(pty_id), UserAttentionType::Informational);
+}
+
+/// Drop what was kept for a PTY that exited
+pub(crate) fn forget_session(app: &AppHandle, pty_id: u32) {
+    let state = app.state::<AttentionState>();
+    state.muted.lock().remove(&pty_id);
+    state.scanners.lock().remove(&pty_id);
+    state.last_bell.lock().remove(&pty_id);
+}
+
+/// Let PTY `ptyId` ask for attention, or stop it from asking
+#[tauri::command]
+pub async fn set_session_attention(
+    state: State<'_, AttentionState>,
+    pty_id: u32,
+    enabled: bool,
+) -> Result<(), String> {
+    let mut muted = state.muted.lock();
+    if enabled {
+        muted.remove(&pty_id);
+    } else {
+        muted.insert(pty_id);
+    }
+    Ok(())
+}
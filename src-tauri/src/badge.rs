@@ -0,0 +1,153 @@
+//! Dock and taskbar badge - the number of agents needing a look
+//!
+//! The count of running and waiting agents goes on the Dock icon on macOS,
+//! on the launcher entry on Linux desktops that support it, and on Windows as
+//! an overlay drawn over the taskbar button. It follows `agent-state` events
+//! like the tray does; `set_badge_count` pins a count of the UI's choosing
+//! instead, until it is cleared again.
+
+use crate::agents::{self, AgentState};
+use crate::{quake, tray};
+use parking_lot::Mutex;
+use tauri::{AppHandle, Listener, Manager, State, Window};
+
+/// State for the badge
+#[derive(Default)]
+pub struct BadgeState {
+    /// Count set by the UI, shown instead of the agents'
+    pinned: Mutex<Option<u32>>,
+}
+
+/// Number of agents running or waiting for input
+fn agent_count(app: &AppHandle) -> u32 {
+    agents::agent_list(&app.state::<AgentState>())
+        .iter()
+        .filter(|agent| tray::is_active(agent))
+        .count() as u32
+}
+
+#[cfg(not(windows))]
+fn apply(window: &Window, count: u32) -> tauri::Result<()> {
+    window.set_badge_count((count > 0).then_some(i64::from(count)))
+}
+
+#[cfg(windows)]
+fn apply(window: &Window, count: u32) -> tauri::Result<()> {
+    window.set_overlay_icon((count > 0).then(|| overlay::icon(count)))
+}
+
+/// Show `count` on every window but the drop-down terminal
+fn show(app: &AppHandle, count: u32) {
+    for (label, window) in app.webview_windows() {
+        if label == quake::WINDOW_LABEL {
+            continue;
+        }
+        if let Err(e) = apply(&window.as_ref().window(), count) {
+            eprintln!("Failed to set the badge of window '{}': {}", label, e);
+        }
+    }
+}
+
+/// Show the pinned count, or the agents' when none is
+pub(crate) fn refresh(app: &AppHandle) {
+    let pinned = *app.state::<BadgeState>().pinned.lock();
+    show(app, pinned.unwrap_or_else(|| agent_count(app)));
+}
+
+/// Keep the badge in step with the agents
+pub(crate) fn init(app: &AppHandle) {
+    let handle = app.clone();
+    app.listen("agent-state", move |_| refresh(&handle));
+    refresh(app);
+}
+
+/// Show `count` on the badge (`0` hides it), or go back to counting agents
+/// with `None`
+#[tauri::command]
+pub async fn set_badge_count(
+    app: AppHandle,
+    state: State<'_, BadgeState>,
+    count: Option<u32>,
+) -> Result<(), String> {
+    *state.pinned.lock() = count;
+    refresh(&app);
+    Ok(())
+}
+
+/// Windows has no badge, so the count is drawn as an overlay icon
+#[cfg(windows)]
+mod overlay {
+    use tauri::image::Image;
+
+    /// Overlay icons are shown at 16x16; drawn at twice that for high DPI
+    const SIZE: u32 = 32;
+    /// Pixels per cell of the digit font
+    const SCALE: u32 = 3;
+    const BACKGROUND: [u8; 4] = [0xd9, 0x2d, 0x20, 0xff];
+    const FOREGROUND: [u8; 4] = [0xff, 0xff, 0xff, 0xff];
+
+    /// 3x5 glyphs for 0-9 and '+', one row per entry, high bit leftmost
+    const GLYPHS: [[u8; 5]; 11] = [
+        [0b111, 0b101, 0b101, 0b101, 0b111],
+        [0b010, 0b110, 0b010, 0b010, 0b111],
+        [0b111, 0b001, 0b111, 0b100, 0b111],
+        [0b111, 0b001, 0b111, 0b001, 0b111],
+        [0b101, 0b101, 0b111, 0b001, 0b001],
+        [0b111, 0b100, 0b111, 0b001, 0b111],
+        [0b111, 0b100, 0b111, 0b101, 0b111],
+        [0b111, 0b001, 0b010, 0b010, 0b010],
+        [0b111, 0b101, 0b111, 0b101, 0b111],
+        [0b111, 0b101, 0b111, 0b001, 0b111],
+        [0b000, 0b010, 0b111, 0b010, 0b000],
+    ];
+    const PLUS: usize = 10;
+
+    /// A filled circle with `count` in it, "9+" past nine
+    pub(super) fn icon(count: u32) -> Image<'static> {
+        let glyphs: Vec<usize> = if count > 9 {
+            vec![9, PLUS]
+        } else {
+            vec![count as usize]
+        };
+        let mut rgba = vec![0u8; (SIZE * SIZE * 4) as usize];
+        let mut put = |x: u32, y: u32, color: [u8; 4]| {
+            let offset = ((y * SIZE + x) * 4) as usize;
+            rgba[offset..offset + 4].copy_from_slice(&color);
+        };
+
+        let center = SIZE as f32 / 2.0;
+        for y in 0..SIZE {
+            for x in 0..SIZE {
+                let (dx, dy) = (x as f32 + 0.5 - center, y as f32 + 0.5 - center);
+                if dx * dx + dy * dy <= center * center {
+                    put(x, y, BACKGROUND);
+                }
+            }
+        }
+
+        let glyph_width = 3 * SCALE;
+        let width = glyphs.len() as u32 * (glyph_width + SCALE) - SCALE;
+        let left = (SIZE - width) / 2;
+        let top = (SIZE - 5 * SCALE) / 2;
+        for (i, glyph) in glyphs.iter().enumerate() {
+            let origin = left + i as u32 * (glyph_width + SCALE);
+            for (row, bits) in GLYPHS[*glyph].iter().enumerate() {
+                for column in 0..3u32 {
+                    if bits & (0b100 >> column) == 0 {
+                        continue;
+                    }
+                    for py in 0..SCALE {
+                        for px in 0..SCALE {
+                            put(
+                                origin + column * SCALE + px,
+                                top + row as u32 * SCALE + py,
+                                FOREGROUND,
+                            );
+                        }
+                    }
+                }
+            }
+        }
+        Image::new_owned(rgba, SIZE, SIZE)
+    }
+}
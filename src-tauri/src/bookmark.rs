@@ -0,0 +1,78 @@
+//! Security-scoped bookmarks
+//!
+//! A sandboxed macOS build can only reach a folder the user picked in the
+//! open dialog until it quits. A security-scoped bookmark taken while that
+//! access lasts reopens the folder on a later launch without asking again;
+//! the workspace keeps one per project and resolves them at startup.
+//! Elsewhere there is nothing to keep, so these do nothing.
+
+/// A bookmark resolved at launch
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+pub(crate) struct Resolved {
+    pub(crate) path: String,
+    /// The folder moved or the bookmark is old; take a fresh one
+    pub(crate) stale: bool,
+}
+
+/// Bookmark `path`, which the app must currently have access to
+#[cfg(target_os = "macos")]
+pub(crate) fn create(path: &str) -> Option<Vec<u8>> {
+    use objc2_foundation::{NSString, NSURLBookmarkCreationOptions, NSURL};
+
+    let url = NSURL::fileURLWithPath(&NSString::from_str(path));
+    match url.bookmarkDataWithOptions_includingResourceValuesForKeys_relativeToURL_error(
+        NSURLBookmarkCreationOptions::WithSecurityScope,
+        None,
+        None,
+    ) {
+        Ok(data) => Some(data.to_vec()),
+        Err(e) => {
+            eprintln!("Failed to bookmark {}: {}", path, e.localizedDescription());
+            None
+        }
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+pub(crate) fn create(_path: &str) -> Option<Vec<u8>> {
+    None
+}
+
+/// Resolve `bookmark` and start accessing the folder it names. Access lasts
+/// until the app quits, which suits projects that are used the whole run.
+#[cfg(target_os = "macos")]
+pub(crate) fn resolve(bookmark: &[u8]) -> Result<Resolved, String> {
+    use objc2::runtime::Bool;
+    use objc2_foundation::{NSData, NSURLBookmarkResolutionOptions, NSURL};
+
+    let data = NSData::with_bytes(bookmark);
+    let mut stale = Bool::NO;
+    // SAFETY: `stale` is a valid pointer for the duration of the call
+    let url = unsafe {
+        NSURL::URLByResolvingBookmarkData_options_relativeToURL_bookmarkDataIsStale_error(
+            &data,
+            NSURLBookmarkResolutionOptions::WithSecurityScope,
+            None,
+            &mut stale,
+        )
+    }
+    .map_err(|e| format!("Failed to resolve bookmark: {}", e.localizedDescription()))?;
+    let path = url
+        .path()
+        .map(|path| path.to_string())
+        .ok_or_else(|| "Bookmark has no path".to_string())?;
+
+    // SAFETY: balanced by the system when the app exits
+    if !unsafe { url.startAccessingSecurityScopedResource() } {
+        return Err(format!("Access to {} was not granted", path));
+    }
+    Ok(Resolved {
+        path,
+        stale: stale.as_bool(),
+    })
+}
+
+#[cfg(not(target_os = "macos"))]
+pub(crate) fn resolve(_bookmark: &[u8]) -> Result<Resolved, String> {
+    Err("Security-scoped bookmarks are only used on macOS".to_string())
+}
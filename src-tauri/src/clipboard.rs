@@ -0,0 +1,275 @@
+//! Styled copy - terminal output that keeps its colors when pasted
+//!
+//! `copy_styled` takes a terminal selection with its escape sequences,
+//! turns the SGR colors and attributes into an HTML `<pre>` with inline
+//! styles, and puts it on the clipboard next to the plain text, so Slack,
+//! Notion and mail clients paste the colored version while editors and
+//! terminals get plain text. Escape sequences other than SGR are dropped.
+
+use serde::Serialize;
+
+/// Colors 0-15, as xterm shows them
+const PALETTE: [(u8, u8, u8); 16] = [
+    (0x00, 0x00, 0x00),
+    (0xcd, 0x00, 0x00),
+    (0x00, 0xcd, 0x00),
+    (0xcd, 0xcd, 0x00),
+    (0x00, 0x00, 0xee),
+    (0xcd, 0x00, 0xcd),
+    (0x00, 0xcd, 0xcd),
+    (0xe5, 0xe5, 0xe5),
+    (0x7f, 0x7f, 0x7f),
+    (0xff, 0x00, 0x00),
+    (0x00, 0xff, 0x00),
+    (0xff, 0xff, 0x00),
+    (0x5c, 0x5c, 0xff),
+    (0xff, 0x00, 0xff),
+    (0x00, 0xff, 0xff),
+    (0xff, 0xff, 0xff),
+];
+
+/// Stand-ins for the unset colors when video is inverted
+const DEFAULT_FOREGROUND: (u8, u8, u8) = (0x00, 0x00, 0x00);
+const DEFAULT_BACKGROUND: (u8, u8, u8) = (0xff, 0xff, 0xff);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Color {
+    Indexed(u8),
+    Rgb(u8, u8, u8),
+}
+
+impl Color {
+    fn rgb(self) -> (u8, u8, u8) {
+        match self {
+            Color::Indexed(i) if i < 16 => PALETTE[i as usize],
+            // 6x6x6 cube
+            Color::Indexed(i) if i < 232 => {
+                let level = |n: u8| if n == 0 { 0 } else { 55 + n * 40 };
+                let i = i - 16;
+                (level(i / 36), level(i / 6 % 6), level(i % 6))
+            }
+            Color::Indexed(i) => {
+                let gray = 8 + (i - 232) * 10;
+                (gray, gray, gray)
+            }
+            Color::Rgb(r, g, b) => (r, g, b),
+        }
+    }
+}
+
+/// SGR state of a run of text
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Style {
+    foreground: Option<Color>,
+    background: Option<Color>,
+    bold: bool,
+    dim: bool,
+    italic: bool,
+    underline: bool,
+    strikethrough: bool,
+    inverse: bool,
+}
+
+/// The color after a 38 or 48: `5;n` or `2;r;g;b`
+fn extended_color(params: &mut impl Iterator<Item = u16>) -> Option<Color> {
+    let mut next = || params.next().map(|n| n.min(255) as u8);
+    match next()? {
+        5 => next().map(Color::Indexed),
+        2 => Some(Color::Rgb(next()?, next()?, next()?)),
+        _ => None,
+    }
+}
+
+impl Style {
+    fn apply(&mut self, params: &[u16]) {
+        if params.is_empty() {
+            *self = Style::default();
+            return;
+        }
+        let mut params = params.iter().copied();
+        while let Some(param) = params.next() {
+            match param {
+                0 => *self = Style::default(),
+                1 => self.bold = true,
+                2 => self.dim = true,
+                3 => self.italic = true,
+                4 => self.underline = true,
+                7 => self.inverse = true,
+                9 => self.strikethrough = true,
+                22 => {
+                    self.bold = false;
+                    self.dim = false;
+                }
+                23 => self.italic = false,
+                24 => self.underline = false,
+                27 => self.inverse = false,
+                29 => self.strikethrough = false,
+                30..=37 => self.foreground = Some(Color::Indexed((param - 30) as u8)),
+                38 => self.foreground = extended_color(&mut params),
+                39 => self.foreground = None,
+                40..=47 => self.background = Some(Color::Indexed((param - 40) as u8)),
+                48 => self.background = extended_color(&mut params),
+                49 => self.background = None,
+                90..=97 => self.foreground = Some(Color::Indexed((param - 90 + 8) as u8)),
+                100..=107 => self.background = Some(Color::Indexed((param - 100 + 8) as u8)),
+                _ => {}
+            }
+        }
+    }
+
+    /// Inline CSS for the style, empty for plain text
+    fn css(&self) -> String {
+        let hex = |(r, g, b): (u8, u8, u8)| format!("#{:02x}{:02x}{:02x}", r, g, b);
+        let (mut foreground, mut background) = (
+            self.foreground.map(Color::rgb),
+            self.background.map(Color::rgb),
+        );
+        if self.inverse {
+            (foreground, background) = (
+                Some(background.unwrap_or(DEFAULT_BACKGROUND)),
+                Some(foreground.unwrap_or(DEFAULT_FOREGROUND)),
+            );
+        }
+
+        let mut css = Vec::new();
+        if let Some(color) = foreground {
+            css.push(format!("color:{}", hex(color)));
+        }
+        if let Some(color) = background {
+            css.push(format!("background-color:{}", hex(color)));
+        }
+        if self.bold {
+            css.push("font-weight:bold".to_string());
+        }
+        if self.dim {
+            css.push("opacity:0.6".to_string());
+        }
+        if self.italic {
+            css.push("font-style:italic".to_string());
+        }
+        match (self.underline, self.strikethrough) {
+            (true, true) => css.push("text-decoration:underline line-through".to_string()),
+            (true, false) => css.push("text-decoration:underline".to_string()),
+            (false, true) => css.push("text-decoration:line-through".to_string()),
+            (false, false) => {}
+        }
+        css.join(";")
+    }
+}
+
+/// Split terminal output into styled runs of plain text
+fn parse(data: &str) -> Vec<(Style, String)> {
+    let mut runs: Vec<(Style, String)> = Vec::new();
+    let mut style = Style::default();
+    let mut chars = data.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\x1b' => match chars.next() {
+                Some('[') => {
+                    let mut sequence = String::new();
+                    let mut last = None;
+                    for c in chars.by_ref() {
+                        if ('\x40'..='\x7e').contains(&c) {
+                            last = Some(c);
+                            break;
+                        }
+                        sequence.push(c);
+                    }
+                    // Private sequences such as `\x1b[?25l` aren't SGR
+                    if last == Some('m') && !sequence.starts_with(['?', '>', '<', '=']) {
+                        let params: Vec<u16> = sequence
+                            .split([';', ':'])
+                            .map(|param| param.parse().unwrap_or(0))
+                            .collect();
+                        style.apply(if sequence.is_empty() { &[] } else { &params });
+                    }
+                }
+                // OSC runs until BEL or ST
+                Some(']') => {
+                    while let Some(c) = chars.next() {
+                        if c == '\x07' {
+                            break;
+                        }
+                        if c == '\x1b' && chars.peek() == Some(&'\\') {
+                            chars.next();
+                            break;
+                        }
+                    }
+                }
+                _ => {}
+            },
+            c if c.is_control() && c != '\n' && c != '\t' => {}
+            c => match runs.last_mut() {
+                Some((last, text)) if *last == style => text.push(c),
+                _ => runs.push((style, c.to_string())),
+            },
+        }
+    }
+    runs
+}
+
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn to_html(runs: &[(Style, String)]) -> String {
+    let mut html = String::from(
+        "<pre style=\"font-family:ui-monospace,SFMono-Regular,Menlo,Consolas,monospace;white-space:pre-wrap\">",
+    );
+    for (style, text) in runs {
+        let css = style.css();
+        if css.is_empty() {
+            html.push_str(&escape_html(text));
+        } else {
+            html.push_str(&format!(
+                "<span style=\"{}\">{}</span>",
+                css,
+                escape_html(text)
+            ));
+        }
+    }
+    html.push_str("</pre>");
+    html
+}
+
+/// What `copy_styled` put on the clipboard
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StyledCopy {
+    text: String,
+    html: String,
+}
+
+/// Put terminal output on the clipboard as HTML with its colors and as
+/// plain text, returning both
+#[tauri::command]
+pub async fn copy_styled(text: String) -> Result<StyledCopy, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let runs = parse(&text);
+        let copy = StyledCopy {
+            text: runs.iter().map(|(_, text)| text.as_str()).collect(),
+            html: to_html(&runs),
+        };
+        // Linux keeps serving the selection (or hands it to a clipboard
+        // manager) after this handle is dropped
+        let mut clipboard =
+            arboard::Clipboard::new().map_err(|e| format!("Clipboard unavailable: {}", e))?;
+        clipboard
+            .set_html(copy.html.as_str(), Some(copy.text.as_str()))
+            .map_err(|e| format!("Failed to copy: {}", e))?;
+        Ok(copy)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
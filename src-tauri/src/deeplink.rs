@@ -0,0 +1,144 @@
+//! `antler://` links - opening cards and terminals from outside the app
+//!
+//! Links in GitHub comments and notifications use the custom scheme:
+//!
+//! ```text
+//! antler://issue/42                 issue 42 of the active project
+//! antler://issue/owner/repo/42      issue 42 of owner/repo
+//! antler://session/<id>             a terminal session
+//! ```
+//!
+//! Each link brings the window forward and is emitted as a `deep-link` event
+//! the UI turns into a focused card or terminal. A link that starts the app
+//! arrives before the page is listening, so links are held until the UI
+//! first calls `take_deep_links`. On Windows and Linux a link launches a new
+//! process; the single-instance plugin hands it to the running one instead.
+
+use crate::tray;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Emitter, Manager, State, Url};
+use tauri_plugin_deep_link::DeepLinkExt;
+
+/// Links held for the UI at most, oldest dropped first
+const MAX_PENDING: usize = 20;
+
+/// State for `antler://` links
+#[derive(Default)]
+pub struct DeepLinkState {
+    /// Links received before the UI asked for them
+    pending: Mutex<Vec<DeepLink>>,
+    /// Set once the UI has taken the pending links and listens for events
+    ready: AtomicBool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum DeepLinkTarget {
+    Issue {
+        /// `None` for the active project
+        repo: Option<String>,
+        number: u64,
+    },
+    Session {
+        id: String,
+    },
+}
+
+/// Payload of `deep-link`
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeepLink {
+    url: String,
+    #[serde(flatten)]
+    target: DeepLinkTarget,
+}
+
+fn parse(url: &Url) -> Option<DeepLinkTarget> {
+    if url.scheme() != "antler" {
+        return None;
+    }
+    let segments: Vec<&str> = url
+        .path_segments()
+        .map(|segments| segments.filter(|segment| !segment.is_empty()).collect())
+        .unwrap_or_default();
+    match (url.host_str()?, segments.as_slice()) {
+        ("issue", [number]) => Some(DeepLinkTarget::Issue {
+            repo: None,
+            number: number.parse().ok()?,
+        }),
+        ("issue", [owner, repo, number]) => Some(DeepLinkTarget::Issue {
+            repo: Some(format!("{}/{}", owner, repo)),
+            number: number.parse().ok()?,
+        }),
+        ("session", [id]) => Some(DeepLinkTarget::Session { id: id.to_string() }),
+        _ => None,
+    }
+}
+
+fn route(app: &AppHandle, url: &Url) {
+    let Some(target) = parse(url) else {
+        eprintln!("Ignoring unsupported link {}", url);
+        return;
+    };
+    let link = DeepLink {
+        url: url.to_string(),
+        target,
+    };
+    tray::show_windows(app);
+
+    let state = app.state::<DeepLinkState>();
+    let mut pending = state.pending.lock();
+    if state.ready.load(Ordering::SeqCst) {
+        drop(pending);
+        let _ = app.emit("deep-link", link);
+        return;
+    }
+    if pending.len() == MAX_PENDING {
+        pending.remove(0);
+    }
+    pending.push(link);
+}
+
+/// Register the scheme where it is done at runtime and start routing links,
+/// including the one the app was started with
+pub(crate) fn init(app: &AppHandle) {
+    // Installers register the scheme; this covers dev builds and AppImages
+    #[cfg(any(windows, target_os = "linux"))]
+    if let Err(e) = app.deep_link().register_all() {
+        eprintln!("Failed to register the antler:// scheme: {}", e);
+    }
+
+    let handle = app.clone();
+    app.deep_link().on_open_url(move |event| {
+        for url in event.urls() {
+            route(&handle, &url);
+        }
+    });
+    if let Ok(Some(urls)) = app.deep_link().get_current() {
+        for url in urls {
+            route(app, &url);
+        }
+    }
+}
+
+/// A second launch without a link just brings the running app forward
+pub(crate) fn on_second_instance(app: &AppHandle, args: Vec<String>) {
+    let link = args
+        .iter()
+        .skip(1)
+        .any(|arg| Url::parse(arg).is_ok_and(|url| url.scheme() == "antler"));
+    if !link {
+        tray::show_windows(app);
+    }
+}
+
+/// Links received before the UI was listening, after which they arrive as
+/// `deep-link` events only
+#[tauri::command]
+pub async fn take_deep_links(state: State<'_, DeepLinkState>) -> Result<Vec<DeepLink>, String> {
+    let mut pending = state.pending.lock();
+    state.ready.store(true, Ordering::SeqCst);
+    Ok(std::mem::take(&mut *pending))
+}
@@ -0,0 +1,499 @@
+//! Dev session pipeline - everything needed to start work on an issue
+//!
+//! `start_dev_session` runs the steps the board used to chain by hand: move
+//! the card to the development column, create the worktree, bring up the
+//! devcontainer and start an agent. Each step reports `dev-session-progress`
+//! events, and when one fails the steps already done are undone in reverse
+//! so no half-created branch or container is left behind. Resources that
+//! existed before the pipeline ran are reused and never rolled back.
+
+use crate::agents::{self, AgentInfo, AgentState, IssueContext, SpawnAgentOptions};
+use crate::devcontainer::{self, ContainerState, UpOptions};
+use crate::git::cleanup::worktree_base;
+use crate::git::worktree::{create_worktree, same_path, worktrees};
+use crate::git::{ref_exists, run_git};
+use crate::github::columns::{move_to_column, DEFAULT_COLUMN_LABELS};
+use crate::github::issues::{edit_labels, Issue, RawIssue};
+use crate::github::GitHubState;
+use crate::jobs::{self, JobKind};
+use crate::lifecycle::{run_hooks, HookContext, HookEvent};
+use crate::sessions::{update_session, IssueSession, SessionRegistry, SessionUpdate};
+use crate::workspace;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tauri::{AppHandle, Emitter, State};
+
+/// Column the card moves to when the caller does not choose one
+const DEFAULT_COLUMN: &str = "development";
+
+/// Terminal size for the agent until the frontend resizes it
+const DEFAULT_COLS: u16 = 120;
+const DEFAULT_ROWS: u16 = 40;
+
+/// Options for `start_dev_session`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DevSessionOptions {
+    /// Local clone of the repository, defaults to the workspace project's
+    path: Option<String>,
+    /// Branch to work on, defaults to `<number>-<title-slug>`
+    branch: Option<String>,
+    /// Worktree location, defaults to `<branch>` under the project's worktree
+    /// base (`<path>/.worktrees` unless configured)
+    worktree_path: Option<String>,
+    /// Column to move the card to
+    column: Option<String>,
+    /// Every label that represents a column, defaults to the project's
+    columns: Option<Vec<String>>,
+    /// Bring up the devcontainer; by default only when one is configured
+    devcontainer: Option<bool>,
+    /// Run the agent inside the devcontainer when there is one
+    #[serde(default = "crate::git::default_true")]
+    agent_in_container: bool,
+    cols: Option<u16>,
+    rows: Option<u16>,
+    /// Replaces the agent's default instructions
+    instructions: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DevSessionStep {
+    Column,
+    Worktree,
+    Devcontainer,
+    Agent,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    Running,
+    Done,
+    /// Nothing to do, e.g. the card was already in the column
+    Skipped,
+    Failed,
+    RolledBack,
+    RollbackFailed,
+}
+
+/// Payload of `dev-session-progress`
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ProgressEvent {
+    repo: String,
+    issue: u64,
+    step: DevSessionStep,
+    status: StepStatus,
+    message: Option<String>,
+}
+
+/// Result of `start_dev_session`
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DevSession {
+    session: IssueSession,
+    agent: AgentInfo,
+}
+
+/// How to undo a completed step
+enum Undo {
+    Column {
+        previous: Option<String>,
+        column: String,
+        columns: Vec<String>,
+    },
+    Worktree {
+        repo: String,
+        path: String,
+        branch: String,
+        remove_branch: bool,
+    },
+    /// Stop the container, removing it too if this run created it
+    Devcontainer { workspace: String, created: bool },
+}
+
+impl Undo {
+    /// Job that retries this undo later
+    fn job(&self, issue: u64, repo: &str) -> JobKind {
+        match self {
+            Undo::Column {
+                previous: Some(previous),
+                columns,
+                ..
+            } => JobKind::SetColumn {
+                repo: repo.to_string(),
+                number: issue,
+                column: previous.clone(),
+                columns: columns.clone(),
+            },
+            Undo::Column {
+                previous: None,
+                column,
+                ..
+            } => JobKind::EditLabels {
+                repo: repo.to_string(),
+                number: issue,
+                add: Vec::new(),
+                remove: vec![column.clone()],
+            },
+            Undo::Worktree {
+                repo,
+                path,
+                branch,
+                remove_branch,
+            } => JobKind::RemoveWorktree {
+                repo: repo.clone(),
+                path: path.clone(),
+                branch: remove_branch.then(|| branch.clone()),
+            },
+            Undo::Devcontainer { workspace, created } => JobKind::DevcontainerDown {
+                workspace: workspace.clone(),
+                keep: !created,
+            },
+        }
+    }
+
+    fn step(&self) -> DevSessionStep {
+        match self {
+            Undo::Column { .. } => DevSessionStep::Column,
+            Undo::Worktree { .. } => DevSessionStep::Worktree,
+            Undo::Devcontainer { .. } => DevSessionStep::Devcontainer,
+        }
+    }
+}
+
+struct Pipeline<'a> {
+    app: &'a AppHandle,
+    github: &'a GitHubState,
+    repo: &'a str,
+    issue: u64,
+    done: Vec<Undo>,
+}
+
+impl Pipeline<'_> {
+    fn progress(&self, step: DevSessionStep, status: StepStatus, message: Option<String>) {
+        let _ = self.app.emit(
+            "dev-session-progress",
+            ProgressEvent {
+                repo: self.repo.to_string(),
+                issue: self.issue,
+                step,
+                status,
+                message,
+            },
+        );
+    }
+
+    /// Report a failed step, undo everything before it and return the error.
+    ///
+    /// Undo steps that fail are handed to the job queue to retry.
+    async fn fail(mut self, step: DevSessionStep, error: String) -> String {
+        self.progress(step, StepStatus::Failed, Some(error.clone()));
+
+        while let Some(undo) = self.done.pop() {
+            let step = undo.step();
+            let job = undo.job(self.issue, self.repo);
+            match self.undo(undo).await {
+                Ok(()) => self.progress(step, StepStatus::RolledBack, None),
+                Err(e) => {
+                    let message = match jobs::enqueue(self.app, job, None, None) {
+                        Ok(job) => format!("{} (retrying as job {})", e, job.id),
+                        Err(_) => e,
+                    };
+                    self.progress(step, StepStatus::RollbackFailed, Some(message));
+                }
+            }
+        }
+        error
+    }
+
+    async fn undo(&self, undo: Undo) -> Result<(), String> {
+        match undo {
+            Undo::Column {
+                previous: Some(previous),
+                columns,
+                ..
+            } => move_to_column(self.github, self.repo, self.issue, &previous, &columns)
+                .await
+                .map(|_| ())
+                .map_err(|e| format!("Failed to restore column: {}", e)),
+            Undo::Column {
+                previous: None,
+                column,
+                ..
+            } => edit_labels(self.github, self.repo, self.issue, &[], &[column])
+                .await
+                .map_err(|e| format!("Failed to restore column: {}", e)),
+            Undo::Worktree {
+                repo,
+                path,
+                branch,
+                remove_branch,
+            } => {
+                run_git(&repo, &["worktree", "remove", "--force", &path])?;
+                if remove_branch {
+                    run_git(&repo, &["branch", "-D", &branch])?;
+                }
+                Ok(())
+            }
+            Undo::Devcontainer { workspace, created } => {
+                devcontainer::devcontainer_down(workspace, Some(created)).await
+            }
+        }
+    }
+}
+
+/// Branch name the frontend uses for issues: `<number>-<title-slug>`
+fn branch_name(number: u64, title: &str) -> String {
+    let mut slug = String::new();
+    for c in title.to_lowercase().chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c);
+        } else if !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug: String = slug.trim_matches('-').chars().take(50).collect();
+    let slug = if slug.is_empty() { "issue" } else { &slug };
+    format!("{}-{}", number, slug)
+}
+
+/// Move the card, create the worktree, bring up the devcontainer and start
+/// an agent for an issue, undoing completed steps if a later one fails
+#[tauri::command]
+pub async fn start_dev_session(
+    app: AppHandle,
+    github: State<'_, GitHubState>,
+    agent_state: State<'_, AgentState>,
+    registry: State<'_, SessionRegistry>,
+    repo: String,
+    issue: u64,
+    options: DevSessionOptions,
+) -> Result<DevSession, String> {
+    let project = workspace::project(&app, &repo)?;
+    let path = options
+        .path
+        .clone()
+        .or_else(|| project.as_ref().map(|project| project.path.clone()))
+        .ok_or_else(|| format!("No local clone known for {}; pass a path", repo))?;
+
+    let mut pipeline = Pipeline {
+        app: &app,
+        github: &github,
+        repo: &repo,
+        issue,
+        done: Vec::new(),
+    };
+
+    // Column
+    let step = DevSessionStep::Column;
+    pipeline.progress(step, StepStatus::Running, None);
+    let raw: RawIssue = match github
+        .get(&format!("/repos/{}/issues/{}", repo, issue))
+        .await
+    {
+        Ok(raw) => raw,
+        Err(e) => return Err(pipeline.fail(step, e.to_string()).await),
+    };
+    let details = Issue::from(raw);
+    let project_columns = project.as_ref().and_then(|project| project.columns.clone());
+    let columns = options
+        .columns
+        .clone()
+        .or(project_columns)
+        .unwrap_or_else(|| {
+            DEFAULT_COLUMN_LABELS
+                .iter()
+                .map(|c| c.to_string())
+                .collect()
+        });
+    let column = options
+        .column
+        .clone()
+        .unwrap_or_else(|| DEFAULT_COLUMN.to_string());
+    let previous = details
+        .labels
+        .iter()
+        .find(|label| columns.iter().any(|c| c.eq_ignore_ascii_case(&label.name)))
+        .map(|label| label.name.clone());
+
+    if previous
+        .as_deref()
+        .is_some_and(|p| p.eq_ignore_ascii_case(&column))
+    {
+        pipeline.progress(step, StepStatus::Skipped, None);
+    } else {
+        if let Err(e) = move_to_column(&github, &repo, issue, &column, &columns).await {
+            return Err(pipeline.fail(step, e.to_string()).await);
+        }
+        pipeline.done.push(Undo::Column {
+            previous,
+            column,
+            columns,
+        });
+        pipeline.progress(step, StepStatus::Done, None);
+    }
+
+    // Worktree
+    let step = DevSessionStep::Worktree;
+    pipeline.progress(step, StepStatus::Running, None);
+    let branch = options
+        .branch
+        .clone()
+        .unwrap_or_else(|| branch_name(issue, &details.title));
+    let worktree_path = options.worktree_path.clone().unwrap_or_else(|| {
+        let base = project
+            .as_ref()
+            .and_then(|project| project.worktree_base.as_deref());
+        Path::new(&worktree_base(&path, base))
+            .join(&branch)
+            .to_string_lossy()
+            .into_owned()
+    });
+    let branch_existed = ref_exists(&path, &format!("refs/heads/{}", branch));
+    let worktree_existed = worktrees(&path)
+        .map(|list| list.iter().any(|wt| same_path(&wt.path, &worktree_path)))
+        .unwrap_or(false);
+
+    let worktree = match create_worktree(
+        app.clone(),
+        path.clone(),
+        branch.clone(),
+        worktree_path,
+        None,
+    )
+    .await
+    {
+        Ok(worktree) => worktree,
+        Err(e) => return Err(pipeline.fail(step, e).await),
+    };
+    if worktree_existed {
+        pipeline.progress(step, StepStatus::Skipped, None);
+    } else {
+        pipeline.done.push(Undo::Worktree {
+            repo: path.clone(),
+            path: worktree.path.clone(),
+            branch: branch.clone(),
+            remove_branch: !branch_existed,
+        });
+        let context = HookContext {
+            repo: repo.clone(),
+            issue,
+            cwd: worktree.path.clone(),
+            branch: Some(branch.clone()),
+            container_id: None,
+        };
+        if let Err(e) = run_hooks(&app, HookEvent::PostWorktreeCreate, context).await {
+            return Err(pipeline.fail(step, e).await);
+        }
+        pipeline.progress(step, StepStatus::Done, None);
+    }
+
+    // Devcontainer
+    let step = DevSessionStep::Devcontainer;
+    let wants_container = options
+        .devcontainer
+        .unwrap_or_else(|| devcontainer::has_config(&worktree.path));
+    let mut container_id = None;
+    if wants_container {
+        pipeline.progress(step, StepStatus::Running, None);
+        // Rolled back to how it was: removed if missing, stopped if stopped
+        let before = devcontainer::container_state(&worktree.path)
+            .map(|(_, state)| state)
+            .unwrap_or(ContainerState::Running);
+        match devcontainer::devcontainer_up(
+            app.clone(),
+            worktree.path.clone(),
+            Some(UpOptions::default()),
+        )
+        .await
+        {
+            Ok(up) => container_id = Some(up.container_id),
+            Err(e) => return Err(pipeline.fail(step, e).await),
+        }
+        if before != ContainerState::Running {
+            pipeline.done.push(Undo::Devcontainer {
+                workspace: worktree.path.clone(),
+                created: before == ContainerState::Missing,
+            });
+        }
+        let context = HookContext {
+            repo: repo.clone(),
+            issue,
+            cwd: worktree.path.clone(),
+            branch: Some(branch.clone()),
+            container_id: container_id.clone(),
+        };
+        if let Err(e) = run_hooks(&app, HookEvent::PostDevcontainerUp, context).await {
+            return Err(pipeline.fail(step, e).await);
+        }
+        pipeline.progress(step, StepStatus::Done, None);
+    } else {
+        pipeline.progress(step, StepStatus::Skipped, None);
+    }
+
+    // Agent
+    let step = DevSessionStep::Agent;
+    pipeline.progress(step, StepStatus::Running, None);
+    let hook_context = HookContext {
+        repo: repo.clone(),
+        issue,
+        cwd: worktree.path.clone(),
+        branch: Some(branch.clone()),
+        container_id: container_id.clone(),
+    };
+    if let Err(e) = run_hooks(&app, HookEvent::PreAgentStart, hook_context.clone()).await {
+        return Err(pipeline.fail(step, e).await);
+    }
+    let context = IssueContext::new(
+        repo.clone(),
+        issue,
+        details.title.clone(),
+        Some(details.body.clone()),
+        details.labels.iter().map(|l| l.name.clone()).collect(),
+        Some(details.url.clone()),
+    );
+    // `devcontainer exec` runs the agent in the container, prompt last
+    let command = (container_id.is_some() && options.agent_in_container).then(|| {
+        (
+            "devcontainer".to_string(),
+            vec![
+                "exec".to_string(),
+                "--workspace-folder".to_string(),
+                worktree.path.clone(),
+                "claude".to_string(),
+            ],
+        )
+    });
+    let agent = match agents::start_agent(
+        &app,
+        &agent_state,
+        SpawnAgentOptions::new(
+            context,
+            worktree.path.clone(),
+            options.cols.unwrap_or(DEFAULT_COLS),
+            options.rows.unwrap_or(DEFAULT_ROWS),
+            options.instructions.clone(),
+            command,
+        ),
+    ) {
+        Ok(agent) => agent,
+        Err(e) => return Err(pipeline.fail(step, e).await),
+    };
+    pipeline.progress(step, StepStatus::Done, None);
+
+    let update = SessionUpdate {
+        worktree: Some(Some(worktree.path.clone())),
+        branch: Some(Some(branch)),
+        container_id: Some(container_id),
+        agent_id: Some(Some(agent.id)),
+        add_pty_ids: agent.pty_id.into_iter().collect(),
+        ..Default::default()
+    };
+    let session = update_session(&app, &registry, &repo, issue, update)?;
+    // Failures are logged by the hook runner; the session is already up
+    let _ = run_hooks(&app, HookEvent::PostSessionStart, hook_context).await;
+
+    Ok(DevSession { session, agent })
+}
@@ -0,0 +1,283 @@
+//! Devcontainer module - wraps the devcontainer CLI
+//!
+//! `up` streams build output as `devcontainer-log` events and returns the
+//! container ID. The CLI has no `down` or `status`, so those find the
+//! container through the `devcontainer.local_folder` label Docker keeps on it.
+
+use crate::taskbar;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+use tauri::{AppHandle, Emitter};
+
+/// Label the devcontainer CLI puts on containers it creates
+const LOCAL_FOLDER_LABEL: &str = "devcontainer.local_folder";
+
+/// Options for `devcontainer_up`
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpOptions {
+    /// Path to devcontainer.json when it is not in the default location
+    config: Option<String>,
+    /// Recreate the container even if one exists
+    #[serde(default)]
+    remove_existing: bool,
+}
+
+/// Result of `devcontainer_up`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpResult {
+    pub(crate) container_id: String,
+    remote_user: Option<String>,
+    remote_workspace_folder: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UpOutput {
+    outcome: String,
+    message: Option<String>,
+    description: Option<String>,
+    #[serde(flatten)]
+    result: Option<UpResult>,
+}
+
+/// Output of `devcontainer_exec`
+#[derive(Clone, Debug, Serialize)]
+pub struct ExecOutput {
+    code: Option<i32>,
+    stdout: String,
+    stderr: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContainerState {
+    Running,
+    Stopped,
+    /// No container exists for the workspace
+    Missing,
+}
+
+/// Result of `devcontainer_status`
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DevcontainerStatus {
+    /// Whether the workspace has a devcontainer configuration
+    configured: bool,
+    container_id: Option<String>,
+    state: ContainerState,
+}
+
+/// Event payload for build output
+#[derive(Clone, Serialize)]
+struct DevcontainerLogEvent {
+    workspace: String,
+    text: String,
+}
+
+#[derive(Deserialize)]
+struct JsonLogLine {
+    text: Option<String>,
+}
+
+fn run(program: &str, args: &[&str]) -> Result<String, String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("{} {} failed: {}", program, args[0], stderr.trim()));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+pub(crate) fn has_config(workspace: &str) -> bool {
+    let root = Path::new(workspace);
+    root.join(".devcontainer/devcontainer.json").is_file()
+        || root.join(".devcontainer.json").is_file()
+        || root
+            .join(".devcontainer")
+            .read_dir()
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .any(|entry| entry.path().join("devcontainer.json").is_file())
+            })
+            .unwrap_or(false)
+}
+
+/// ID of the workspace's container, running or not
+pub(crate) fn container_id(workspace: &str) -> Result<Option<String>, String> {
+    // The label holds the absolute path the CLI was given
+    let workspace = std::fs::canonicalize(workspace)
+        .map(|path| path.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| workspace.to_string());
+    let filter = format!("label={}={}", LOCAL_FOLDER_LABEL, workspace);
+    let output = run("docker", &["ps", "-aq", "--filter", &filter])?;
+    Ok(output.lines().next().map(|id| id.trim().to_string()))
+}
+
+/// The workspace's container, if any, and whether it is running
+pub(crate) fn container_state(workspace: &str) -> Result<(Option<String>, ContainerState), String> {
+    let Some(id) = container_id(workspace)? else {
+        return Ok((None, ContainerState::Missing));
+    };
+    let running = run("docker", &["inspect", "-f", "{{.State.Running}}", &id])?;
+    let state = if running.trim() == "true" {
+        ContainerState::Running
+    } else {
+        ContainerState::Stopped
+    };
+    Ok((Some(id), state))
+}
+
+/// Build (if needed) and start the workspace's devcontainer
+#[tauri::command]
+pub async fn devcontainer_up(
+    app: AppHandle,
+    workspace: String,
+    options: Option<UpOptions>,
+) -> Result<UpResult, String> {
+    let options = options.unwrap_or_default();
+    // The CLI reports no share done, only a stream of log lines
+    let taskbar_key = format!("devcontainer:{}", workspace);
+    taskbar::report(&app, &taskbar_key, None);
+
+    let handle = app.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let mut cmd = Command::new("devcontainer");
+        cmd.args([
+            "up",
+            "--log-format",
+            "json",
+            "--workspace-folder",
+            &workspace,
+        ]);
+        if let Some(config) = &options.config {
+            cmd.args(["--config", config]);
+        }
+        if options.remove_existing {
+            cmd.arg("--remove-existing-container");
+        }
+
+        let mut child = cmd
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to run devcontainer: {}", e))?;
+
+        // Build logs go to stderr, one JSON object per line
+        let stderr = child
+            .stderr
+            .take()
+            .ok_or("Failed to read devcontainer output")?;
+        let log_app = app.clone();
+        let log_workspace = workspace.clone();
+        let logs = thread::spawn(move || {
+            for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                let text = serde_json::from_str::<JsonLogLine>(&line)
+                    .ok()
+                    .and_then(|log| log.text)
+                    .unwrap_or(line);
+                let _ = log_app.emit(
+                    "devcontainer-log",
+                    DevcontainerLogEvent {
+                        workspace: log_workspace.clone(),
+                        text,
+                    },
+                );
+            }
+        });
+
+        let output = child
+            .wait_with_output()
+            .map_err(|e| format!("Failed to wait for devcontainer: {}", e))?;
+        let _ = logs.join();
+
+        // The result is the last JSON line on stdout
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let result = stdout
+            .lines()
+            .rev()
+            .find_map(|line| serde_json::from_str::<UpOutput>(line).ok())
+            .ok_or_else(|| "devcontainer up did not report a result".to_string())?;
+
+        match (result.outcome.as_str(), result.result) {
+            ("success", Some(result)) => Ok(result),
+            _ => Err(result
+                .description
+                .or(result.message)
+                .unwrap_or_else(|| format!("devcontainer up failed ({})", result.outcome))),
+        }
+    })
+    .await
+    .map_err(|e| e.to_string())
+    .and_then(|result| result);
+    taskbar::finish(&handle, &taskbar_key);
+    result
+}
+
+/// Run a command inside the workspace's devcontainer
+#[tauri::command]
+pub async fn devcontainer_exec(workspace: String, cmd: Vec<String>) -> Result<ExecOutput, String> {
+    if cmd.is_empty() {
+        return Err("No command given".to_string());
+    }
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let output = Command::new("devcontainer")
+            .args(["exec", "--workspace-folder", &workspace, "--"])
+            .args(&cmd)
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|e| format!("Failed to run devcontainer: {}", e))?;
+
+        Ok(ExecOutput {
+            code: output.status.code(),
+            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Stop the workspace's devcontainer, optionally removing it
+#[tauri::command]
+pub async fn devcontainer_down(workspace: String, remove: Option<bool>) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let Some(id) = container_id(&workspace)? else {
+            return Ok(());
+        };
+        run("docker", &["stop", &id])?;
+        if remove.unwrap_or(false) {
+            run("docker", &["rm", &id])?;
+        }
+        Ok(())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Whether the workspace has a devcontainer and what state it is in
+#[tauri::command]
+pub async fn devcontainer_status(workspace: String) -> Result<DevcontainerStatus, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let configured = has_config(&workspace);
+        let (container_id, state) = container_state(&workspace)?;
+        Ok(DevcontainerStatus {
+            configured,
+            container_id,
+            state,
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
@@ -0,0 +1,409 @@
+//! Docker module - talks to the Docker Engine API directly
+//!
+//! Uses the local socket (or named pipe on Windows) rather than the docker
+//! CLI, so listing and log streaming don't spawn a process per call. Log
+//! streams emit `docker-log` events until they end or are stopped.
+
+use bollard::container::{
+    ListContainersOptions, LogOutput, LogsOptions, RemoveContainerOptions, StopContainerOptions,
+};
+use bollard::exec::{CreateExecOptions, StartExecResults};
+use bollard::image::ListImagesOptions;
+use bollard::Docker;
+use futures_util::StreamExt;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// Label the devcontainer CLI puts on containers it creates
+const LOCAL_FOLDER_LABEL: &str = "devcontainer.local_folder";
+
+/// Lines of history sent when a log stream starts without a `tail`
+const DEFAULT_LOG_TAIL: u64 = 200;
+
+/// Docker client and active log streams
+pub struct DockerState {
+    client: Mutex<Option<Docker>>,
+    streams: Mutex<HashMap<u32, Arc<AtomicBool>>>,
+    next_id: AtomicU32,
+}
+
+impl Default for DockerState {
+    fn default() -> Self {
+        Self {
+            client: Mutex::new(None),
+            streams: Mutex::new(HashMap::new()),
+            next_id: AtomicU32::new(1),
+        }
+    }
+}
+
+impl DockerState {
+    /// Client for the local engine, created on first use
+    pub(crate) fn client(&self) -> Result<Docker, String> {
+        let mut client = self.client.lock();
+        if let Some(docker) = client.as_ref() {
+            return Ok(docker.clone());
+        }
+        let docker = Docker::connect_with_local_defaults()
+            .map_err(|e| format!("Failed to connect to Docker: {}", e))?;
+        *client = Some(docker.clone());
+        Ok(docker)
+    }
+}
+
+/// Options for `list_containers`
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListContainersParams {
+    /// Include stopped containers
+    #[serde(default)]
+    all: bool,
+    /// Only containers created by the devcontainer CLI
+    #[serde(default)]
+    devcontainers_only: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Container {
+    id: String,
+    name: String,
+    image: String,
+    /// Engine state: created, running, paused, exited, ...
+    state: String,
+    /// Human-readable status such as "Up 5 minutes"
+    status: String,
+    created: i64,
+    /// Workspace folder for devcontainers
+    workspace: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Image {
+    id: String,
+    tags: Vec<String>,
+    size: i64,
+    created: i64,
+}
+
+/// Options for `stream_container_logs`
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogStreamOptions {
+    /// Keep streaming new output, defaults to true
+    follow: Option<bool>,
+    /// Lines of existing output to send first
+    tail: Option<u64>,
+}
+
+/// Options for `docker_exec`
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DockerExecOptions {
+    workdir: Option<String>,
+    user: Option<String>,
+}
+
+/// Output of `docker_exec`
+#[derive(Clone, Debug, Serialize)]
+pub struct DockerExecOutput {
+    code: Option<i64>,
+    stdout: String,
+    stderr: String,
+}
+
+/// Options for `remove_container`
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoveOptions {
+    /// Remove even if running
+    #[serde(default)]
+    force: bool,
+    /// Also remove anonymous volumes
+    #[serde(default)]
+    volumes: bool,
+}
+
+/// Event payload for container output
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DockerLogEvent {
+    stream_id: u32,
+    container: String,
+    /// stdout or stderr
+    stream: &'static str,
+    text: String,
+}
+
+/// Event payload when a log stream finishes
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DockerLogEndEvent {
+    stream_id: u32,
+    container: String,
+    error: Option<String>,
+}
+
+fn split_output(output: LogOutput) -> (&'static str, String) {
+    match output {
+        LogOutput::StdErr { message } => ("stderr", String::from_utf8_lossy(&message).into_owned()),
+        LogOutput::StdOut { message }
+        | LogOutput::Console { message }
+        | LogOutput::StdIn { message } => {
+            ("stdout", String::from_utf8_lossy(&message).into_owned())
+        }
+    }
+}
+
+/// List containers on the local engine
+#[tauri::command]
+pub async fn list_containers(
+    state: State<'_, DockerState>,
+    options: Option<ListContainersParams>,
+) -> Result<Vec<Container>, String> {
+    let options = options.unwrap_or_default();
+    let docker = state.client()?;
+
+    let mut filters = HashMap::new();
+    if options.devcontainers_only {
+        filters.insert("label".to_string(), vec![LOCAL_FOLDER_LABEL.to_string()]);
+    }
+
+    let containers = docker
+        .list_containers(Some(ListContainersOptions {
+            all: options.all,
+            filters,
+            ..Default::default()
+        }))
+        .await
+        .map_err(|e| format!("Failed to list containers: {}", e))?;
+
+    Ok(containers
+        .into_iter()
+        .map(|c| Container {
+            id: c.id.unwrap_or_default(),
+            // Names come back with a leading slash
+            name: c
+                .names
+                .and_then(|names| names.into_iter().next())
+                .map(|name| name.trim_start_matches('/').to_string())
+                .unwrap_or_default(),
+            image: c.image.unwrap_or_default(),
+            state: c.state.unwrap_or_default(),
+            status: c.status.unwrap_or_default(),
+            created: c.created.unwrap_or_default(),
+            workspace: c
+                .labels
+                .and_then(|mut labels| labels.remove(LOCAL_FOLDER_LABEL)),
+        })
+        .collect())
+}
+
+/// List images on the local engine
+#[tauri::command]
+pub async fn list_images(state: State<'_, DockerState>) -> Result<Vec<Image>, String> {
+    let docker = state.client()?;
+    let images = docker
+        .list_images(Some(ListImagesOptions::<String> {
+            all: false,
+            ..Default::default()
+        }))
+        .await
+        .map_err(|e| format!("Failed to list images: {}", e))?;
+
+    Ok(images
+        .into_iter()
+        .map(|image| Image {
+            id: image.id,
+            tags: image.repo_tags,
+            size: image.size,
+            created: image.created,
+        })
+        .collect())
+}
+
+/// Stream a container's output as `docker-log` events.
+///
+/// Returns a stream ID for `stop_log_stream`. A `docker-log-end` event is
+/// emitted when the stream finishes for any reason.
+#[tauri::command]
+pub async fn stream_container_logs(
+    app: AppHandle,
+    state: State<'_, DockerState>,
+    container: String,
+    options: Option<LogStreamOptions>,
+) -> Result<u32, String> {
+    let options = options.unwrap_or_default();
+    let docker = state.client()?;
+
+    let id = state.next_id.fetch_add(1, Ordering::SeqCst);
+    let stopped = Arc::new(AtomicBool::new(false));
+    state.streams.lock().insert(id, stopped.clone());
+
+    let mut logs = docker.logs(
+        &container,
+        Some(LogsOptions {
+            follow: options.follow.unwrap_or(true),
+            stdout: true,
+            stderr: true,
+            tail: options.tail.unwrap_or(DEFAULT_LOG_TAIL).to_string(),
+            ..Default::default()
+        }),
+    );
+
+    tauri::async_runtime::spawn(async move {
+        let mut error = None;
+        while let Some(output) = logs.next().await {
+            if stopped.load(Ordering::SeqCst) {
+                break;
+            }
+            match output {
+                Ok(output) => {
+                    let (stream, text) = split_output(output);
+                    let _ = app.emit(
+                        "docker-log",
+                        DockerLogEvent {
+                            stream_id: id,
+                            container: container.clone(),
+                            stream,
+                            text,
+                        },
+                    );
+                }
+                Err(e) => {
+                    error = Some(e.to_string());
+                    break;
+                }
+            }
+        }
+
+        app.state::<DockerState>().streams.lock().remove(&id);
+        let _ = app.emit(
+            "docker-log-end",
+            DockerLogEndEvent {
+                stream_id: id,
+                container,
+                error,
+            },
+        );
+    });
+
+    Ok(id)
+}
+
+/// Stop a log stream started by `stream_container_logs`.
+///
+/// The stream ends at the next line of output, which for a quiet container
+/// may be some time later; no further events are emitted after this call.
+#[tauri::command]
+pub async fn stop_log_stream(state: State<'_, DockerState>, id: u32) -> Result<(), String> {
+    if let Some(stopped) = state.streams.lock().remove(&id) {
+        stopped.store(true, Ordering::SeqCst);
+    }
+    Ok(())
+}
+
+/// Run a command in a running container and collect its output
+#[tauri::command]
+pub async fn docker_exec(
+    state: State<'_, DockerState>,
+    container: String,
+    cmd: Vec<String>,
+    options: Option<DockerExecOptions>,
+) -> Result<DockerExecOutput, String> {
+    if cmd.is_empty() {
+        return Err("No command given".to_string());
+    }
+
+    let options = options.unwrap_or_default();
+    let docker = state.client()?;
+
+    let exec = docker
+        .create_exec(
+            &container,
+            CreateExecOptions {
+                cmd: Some(cmd),
+                attach_stdout: Some(true),
+                attach_stderr: Some(true),
+                working_dir: options.workdir,
+                user: options.user,
+                ..Default::default()
+            },
+        )
+        .await
+        .map_err(|e| format!("Failed to create exec: {}", e))?;
+
+    let mut stdout = String::new();
+    let mut stderr = String::new();
+    if let StartExecResults::Attached { mut output, .. } =
+        docker
+            .start_exec(&exec.id, None)
+            .await
+            .map_err(|e| format!("Failed to start exec: {}", e))?
+    {
+        while let Some(chunk) = output.next().await {
+            let chunk = chunk.map_err(|e| format!("Failed to read exec output: {}", e))?;
+            match split_output(chunk) {
+                ("stderr", text) => stderr.push_str(&text),
+                (_, text) => stdout.push_str(&text),
+            }
+        }
+    }
+
+    let inspect = docker
+        .inspect_exec(&exec.id)
+        .await
+        .map_err(|e| format!("Failed to inspect exec: {}", e))?;
+
+    Ok(DockerExecOutput {
+        code: inspect.exit_code,
+        stdout,
+        stderr,
+    })
+}
+
+/// Stop a container, killing it after `timeout_secs` (default 10)
+#[tauri::command]
+pub async fn stop_container(
+    state: State<'_, DockerState>,
+    id: String,
+    timeout_secs: Option<i64>,
+) -> Result<(), String> {
+    let docker = state.client()?;
+    docker
+        .stop_container(
+            &id,
+            Some(StopContainerOptions {
+                t: timeout_secs.unwrap_or(10),
+            }),
+        )
+        .await
+        .map_err(|e| format!("Failed to stop container: {}", e))
+}
+
+/// Remove a container
+#[tauri::command]
+pub async fn remove_container(
+    state: State<'_, DockerState>,
+    id: String,
+    options: Option<RemoveOptions>,
+) -> Result<(), String> {
+    let options = options.unwrap_or_default();
+    let docker = state.client()?;
+    docker
+        .remove_container(
+            &id,
+            Some(RemoveContainerOptions {
+                force: options.force,
+                v: options.volumes,
+                ..Default::default()
+            }),
+        )
+        .await
+        .map_err(|e| format!("Failed to remove container: {}", e))
+}
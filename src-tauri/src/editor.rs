@@ -0,0 +1,288 @@
+//! External editors - jumping to a line in the user's editor
+//!
+//! `open_in_editor` opens a file at a line and column, so clicking a
+//! diagnostic or a diff line lands on the code. VS Code, Cursor, the
+//! JetBrains IDEs and vim are known by their command-line flags; anything
+//! else can be described as a command template. GUI editors are launched and
+//! left running, while vim gets a PTY of its own that the UI shows as a
+//! terminal. The user's choice is passed in from their settings, and without
+//! one the first editor found on the `PATH` is used.
+
+use crate::files::open::launch;
+use crate::pty::{spawn_session, PtyState};
+use portable_pty::CommandBuilder;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::path::PathBuf;
+use std::process::Command;
+use tauri::{AppHandle, State};
+
+/// Editors with known flags, in the order they are tried
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EditorPreset {
+    Vscode,
+    Cursor,
+    Jetbrains,
+    Vim,
+}
+
+const PRESETS: [EditorPreset; 4] = [
+    EditorPreset::Vscode,
+    EditorPreset::Cursor,
+    EditorPreset::Jetbrains,
+    EditorPreset::Vim,
+];
+
+/// JetBrains launcher scripts, as the Toolbox app installs them
+const JETBRAINS_COMMANDS: [&str; 9] = [
+    "idea",
+    "webstorm",
+    "pycharm",
+    "goland",
+    "rustrover",
+    "clion",
+    "phpstorm",
+    "rider",
+    "rubymine",
+];
+
+impl EditorPreset {
+    /// Name shown to users
+    fn display_name(self) -> &'static str {
+        match self {
+            Self::Vscode => "VS Code",
+            Self::Cursor => "Cursor",
+            Self::Jetbrains => "A JetBrains IDE",
+            Self::Vim => "Vim",
+        }
+    }
+
+    fn commands(self) -> &'static [&'static str] {
+        match self {
+            Self::Vscode => &["code"],
+            Self::Cursor => &["cursor"],
+            Self::Jetbrains => &JETBRAINS_COMMANDS,
+            Self::Vim => &["nvim", "vim"],
+        }
+    }
+
+    /// Where the app bundle keeps its command-line launcher, for installs
+    /// that never put it on the `PATH`
+    #[cfg(target_os = "macos")]
+    fn bundled(self) -> Option<&'static str> {
+        match self {
+            Self::Vscode => {
+                Some("/Applications/Visual Studio Code.app/Contents/Resources/app/bin/code")
+            }
+            Self::Cursor => Some("/Applications/Cursor.app/Contents/Resources/app/bin/cursor"),
+            Self::Jetbrains | Self::Vim => None,
+        }
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    fn bundled(self) -> Option<&'static str> {
+        None
+    }
+
+    fn find(self) -> Option<PathBuf> {
+        self.commands()
+            .iter()
+            .find_map(|command| find_in_path(command))
+            .or_else(|| {
+                self.bundled()
+                    .map(PathBuf::from)
+                    .filter(|path| path.is_file())
+            })
+    }
+
+    fn args(self, file: &str, line: u32, column: u32) -> Vec<String> {
+        match self {
+            Self::Vscode | Self::Cursor => vec![
+                "--goto".to_string(),
+                format!("{}:{}:{}", file, line, column),
+            ],
+            Self::Jetbrains => vec![
+                "--line".to_string(),
+                line.to_string(),
+                "--column".to_string(),
+                column.to_string(),
+                file.to_string(),
+            ],
+            Self::Vim => vec![
+                format!("+call cursor({}, {})", line, column),
+                file.to_string(),
+            ],
+        }
+    }
+}
+
+/// An editor described by its command line. `{file}`, `{line}` and `{col}`
+/// in `args` are replaced; `terminal` runs it in a PTY like vim.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomEditor {
+    command: String,
+    args: Vec<String>,
+    #[serde(default)]
+    terminal: bool,
+}
+
+/// A preset name such as `"vscode"`, or a custom command
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub enum EditorChoice {
+    Preset(EditorPreset),
+    Custom(CustomEditor),
+}
+
+/// Options for `open_in_editor`
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenInEditorOptions {
+    /// The editor from the user's settings; the first one found otherwise
+    editor: Option<EditorChoice>,
+    /// Size of the PTY for terminal editors
+    cols: Option<u16>,
+    rows: Option<u16>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DetectedEditor {
+    preset: EditorPreset,
+    command: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenedEditor {
+    command: String,
+    /// The PTY a terminal editor runs in, for the UI to show
+    pty_id: Option<u32>,
+}
+
+/// The full path of `command` on the `PATH`, trying `PATHEXT` on Windows
+fn find_in_path(command: &str) -> Option<PathBuf> {
+    let extensions: Vec<String> = if cfg!(windows) {
+        env::var("PATHEXT")
+            .unwrap_or_else(|_| ".COM;.EXE;.BAT;.CMD".to_string())
+            .split(';')
+            .map(str::to_string)
+            .collect()
+    } else {
+        vec![String::new()]
+    };
+    env::split_paths(&env::var_os("PATH")?).find_map(|dir| {
+        extensions
+            .iter()
+            .map(|extension| dir.join(format!("{}{}", command, extension)))
+            .find(|candidate| candidate.is_file())
+    })
+}
+
+fn fill(template: &str, file: &str, line: u32, column: u32) -> String {
+    template
+        .replace("{file}", file)
+        .replace("{line}", &line.to_string())
+        .replace("{col}", &column.to_string())
+}
+
+/// Editors with known flags that are installed, in the order
+/// `open_in_editor` tries them
+#[tauri::command]
+pub async fn list_editors() -> Result<Vec<DetectedEditor>, String> {
+    Ok(PRESETS
+        .iter()
+        .filter_map(|preset| {
+            preset.find().map(|path| DetectedEditor {
+                preset: *preset,
+                command: path.to_string_lossy().into_owned(),
+            })
+        })
+        .collect())
+}
+
+/// Open `path` in an external editor at `line` and `col`, both 1-based
+#[tauri::command]
+pub async fn open_in_editor(
+    app: AppHandle,
+    state: State<'_, PtyState>,
+    path: String,
+    line: Option<u32>,
+    col: Option<u32>,
+    options: Option<OpenInEditorOptions>,
+) -> Result<OpenedEditor, String> {
+    let options = options.unwrap_or_default();
+    let file =
+        std::path::absolute(&path).map_err(|e| format!("Failed to resolve {}: {}", path, e))?;
+    if !file.is_file() {
+        return Err(format!("{} is not a file", file.display()));
+    }
+    let file_arg = file.to_string_lossy().into_owned();
+    let (line, column) = (line.unwrap_or(1).max(1), col.unwrap_or(1).max(1));
+
+    let (program, args, terminal) = match options.editor {
+        Some(EditorChoice::Custom(custom)) => {
+            let program =
+                find_in_path(&custom.command).unwrap_or_else(|| PathBuf::from(&custom.command));
+            let args = custom
+                .args
+                .iter()
+                .map(|arg| fill(arg, &file_arg, line, column))
+                .collect();
+            (program, args, custom.terminal)
+        }
+        Some(EditorChoice::Preset(preset)) => {
+            let program = preset
+                .find()
+                .ok_or_else(|| format!("{} is not installed", preset.display_name()))?;
+            (
+                program,
+                preset.args(&file_arg, line, column),
+                preset == EditorPreset::Vim,
+            )
+        }
+        None => {
+            let (preset, program) = PRESETS
+                .iter()
+                .find_map(|preset| preset.find().map(|program| (*preset, program)))
+                .ok_or_else(|| "No supported editor found".to_string())?;
+            (
+                program,
+                preset.args(&file_arg, line, column),
+                preset == EditorPreset::Vim,
+            )
+        }
+    };
+    let command = program.to_string_lossy().into_owned();
+
+    if !terminal {
+        let mut gui = Command::new(&program);
+        gui.args(&args);
+        launch(gui)?;
+        return Ok(OpenedEditor {
+            command,
+            pty_id: None,
+        });
+    }
+
+    let mut cmd = CommandBuilder::new(&program);
+    cmd.args(&args);
+    if let Some(dir) = file.parent() {
+        cmd.cwd(dir);
+    }
+    let id = spawn_session(
+        &app,
+        &state,
+        cmd,
+        options.cols.unwrap_or(80),
+        options.rows.unwrap_or(24),
+        None,
+        None,
+    )?;
+    Ok(OpenedEditor {
+        command,
+        pty_id: Some(id),
+    })
+}
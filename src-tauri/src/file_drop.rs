@@ -0,0 +1,183 @@
+//! Dropping files on a terminal - their paths typed at the prompt
+//!
+//! The native drop event carries the files' paths, which the webview alone
+//! can't see. Each window drops into one PTY: the terminal it shows for a
+//! pop-out or the drop-down window, or whichever terminal view the UI marks
+//! with `set_drop_target` as the pointer or focus moves. The paths are
+//! written into it separated by spaces and quoted for the session's shell:
+//! POSIX quoting by default, PowerShell and cmd quoting for those shells, and
+//! for WSL the Windows paths turned into their `/mnt/<drive>` form first.
+//! Nothing is sent with the paths, so the user still presses Enter.
+
+use crate::pty::{self, PtyState};
+use crate::{popout, quake};
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, DragDropEvent, Emitter, Manager, State, Window, WindowEvent};
+
+/// PTYs each window drops files into, set by the UI
+#[derive(Default)]
+pub struct FileDropState {
+    targets: Mutex<HashMap<String, u32>>,
+}
+
+/// Payload of `pty-files-dropped`
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FilesDroppedEvent {
+    pty_id: u32,
+    paths: Vec<PathBuf>,
+}
+
+/// How a session's shell reads quoted words
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Quoting {
+    Posix,
+    PowerShell,
+    Cmd,
+    /// POSIX quoting of paths translated into the WSL file system
+    Wsl,
+}
+
+impl Quoting {
+    /// From the program the session was started with
+    fn for_program(program: &str) -> Self {
+        let name = Path::new(program)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_ascii_lowercase())
+            .unwrap_or_default();
+        match name.as_str() {
+            "wsl" => Quoting::Wsl,
+            "powershell" | "pwsh" => Quoting::PowerShell,
+            "cmd" => Quoting::Cmd,
+            // The default shell is cmd on Windows unless COMSPEC says otherwise
+            "" if cfg!(windows) => Quoting::Cmd,
+            _ => Quoting::Posix,
+        }
+    }
+
+    fn quote(self, path: &Path) -> String {
+        let path = path.to_string_lossy();
+        match self {
+            Quoting::Posix => quote_posix(&path),
+            Quoting::Wsl => quote_posix(&wsl_path(&path)),
+            Quoting::PowerShell => {
+                if is_plain(&path, "\\:") {
+                    path.into_owned()
+                } else {
+                    format!("'{}'", path.replace('\'', "''"))
+                }
+            }
+            // Windows file names can't contain `"`, so quoting is all it takes
+            Quoting::Cmd => {
+                if is_plain(&path, "\\:") {
+                    path.into_owned()
+                } else {
+                    format!("\"{}\"", path)
+                }
+            }
+        }
+    }
+}
+
+/// Whether `text` needs no quoting: letters, digits, `_-./` and whatever
+/// else the shell takes literally in `extra`
+fn is_plain(text: &str, extra: &str) -> bool {
+    !text.is_empty()
+        && text
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_-./".contains(c) || extra.contains(c))
+}
+
+fn quote_posix(text: &str) -> String {
+    if is_plain(text, "+,@%:=") {
+        text.to_string()
+    } else {
+        format!("'{}'", text.replace('\'', "'\\''"))
+    }
+}
+
+/// `C:\Users\me` as WSL sees it: `/mnt/c/Users/me`. Paths already inside a
+/// distribution (`\\wsl$\Ubuntu\home\me`) lose their share prefix.
+fn wsl_path(path: &str) -> String {
+    for prefix in [r"\\wsl$\", r"\\wsl.localhost\"] {
+        if let Some(rest) = path.strip_prefix(prefix) {
+            let inside = rest.split_once('\\').map_or("", |(_, inside)| inside);
+            return format!("/{}", inside.replace('\\', "/"));
+        }
+    }
+    let mut chars = path.chars();
+    match (chars.next(), chars.next()) {
+        (Some(drive), Some(':')) if drive.is_ascii_alphabetic() => format!(
+            "/mnt/{}{}",
+            drive.to_ascii_lowercase(),
+            chars.as_str().replace('\\', "/")
+        ),
+        _ => path.replace('\\', "/"),
+    }
+}
+
+/// The PTY files dropped on `window` go to
+fn target(app: &AppHandle, label: &str) -> Option<u32> {
+    if let Some(id) = app.state::<FileDropState>().targets.lock().get(label) {
+        return Some(*id);
+    }
+    if label == quake::WINDOW_LABEL {
+        return quake::attached_session(app);
+    }
+    label
+        .strip_prefix(popout::LABEL_PREFIX)
+        .and_then(|id| id.parse().ok())
+}
+
+fn drop_paths(app: &AppHandle, pty_id: u32, paths: &[PathBuf]) -> Result<(), String> {
+    let state = app.state::<PtyState>();
+    let quoting = Quoting::for_program(&pty::program(&state, pty_id)?);
+    let mut words: Vec<String> = paths.iter().map(|path| quoting.quote(path)).collect();
+    // Leave the cursor after a space, ready for the next argument
+    words.push(String::new());
+    pty::write_session(&state, pty_id, &words.join(" "))?;
+    let _ = app.emit(
+        "pty-files-dropped",
+        FilesDroppedEvent {
+            pty_id,
+            paths: paths.to_vec(),
+        },
+    );
+    Ok(())
+}
+
+/// Type the paths of files dropped on a terminal into its PTY
+pub(crate) fn on_window_event(window: &Window, event: &WindowEvent) {
+    let WindowEvent::DragDrop(DragDropEvent::Drop { paths, .. }) = event else {
+        return;
+    };
+    if paths.is_empty() {
+        return;
+    }
+    let app = window.app_handle();
+    let Some(pty_id) = target(app, window.label()) else {
+        return;
+    };
+    if let Err(e) = drop_paths(app, pty_id, paths) {
+        eprintln!("Failed to drop files into PTY {}: {}", pty_id, e);
+    }
+}
+
+/// Make PTY `ptyId` the one files dropped on the calling window go to, or
+/// go back to the window's own terminal, if it has one, with `None`
+#[tauri::command]
+pub async fn set_drop_target(
+    window: Window,
+    state: State<'_, FileDropState>,
+    pty_id: Option<u32>,
+) -> Result<(), String> {
+    let mut targets = state.targets.lock();
+    match pty_id {
+        Some(id) => targets.insert(window.label().to_string(), id),
+        None => targets.remove(window.label()),
+    };
+    Ok(())
+}
@@ -0,0 +1,570 @@
+//! Zip and tar.gz archives
+//!
+//! `create_archive` packs files and directories, `extract_archive` unpacks
+//! one, both on a background thread. Progress is emitted as
+//! `archive-progress` in bytes - of the files packed, of the data extracted
+//! from a zip, of the archive read for a tarball - and the outcome as
+//! `archive-done`. Cancelling removes a half-written archive; files already
+//! extracted are kept. Entries that would land outside the destination are
+//! skipped, as are zip symlinks pointing out of it.
+
+use crate::taskbar;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, State};
+use walkdir::WalkDir;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Running archive operations
+pub struct ArchiveState {
+    operations: Mutex<HashMap<u32, Arc<AtomicBool>>>,
+    next_id: AtomicU32,
+}
+
+impl Default for ArchiveState {
+    fn default() -> Self {
+        Self {
+            operations: Mutex::new(HashMap::new()),
+            next_id: AtomicU32::new(1),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchiveFormat {
+    Zip,
+    TarGz,
+    Tar,
+}
+
+impl ArchiveFormat {
+    fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy().to_lowercase();
+        if name.ends_with(".zip") {
+            Some(Self::Zip)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else if name.ends_with(".tar") {
+            Some(Self::Tar)
+        } else {
+            None
+        }
+    }
+}
+
+/// Event payload for progress
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ArchiveProgressEvent {
+    id: u32,
+    processed: u64,
+    total: u64,
+    entries: u64,
+}
+
+/// Event payload for a finished, failed or cancelled operation
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ArchiveDoneEvent {
+    id: u32,
+    dest: String,
+    entries: u64,
+    bytes: u64,
+    cancelled: bool,
+    error: Option<String>,
+}
+
+/// Progress shared between the entry loop and the readers feeding it
+struct Tracker {
+    app: AppHandle,
+    id: u32,
+    total: u64,
+    processed: AtomicU64,
+    entries: AtomicU64,
+    cancelled: Arc<AtomicBool>,
+    reported: Mutex<Instant>,
+}
+
+impl Tracker {
+    fn add(&self, bytes: u64) {
+        self.processed.fetch_add(bytes, Ordering::SeqCst);
+        let mut reported = self.reported.lock();
+        if reported.elapsed() >= PROGRESS_INTERVAL {
+            *reported = Instant::now();
+            let processed = self.processed.load(Ordering::SeqCst);
+            let _ = self.app.emit(
+                "archive-progress",
+                ArchiveProgressEvent {
+                    id: self.id,
+                    processed,
+                    total: self.total,
+                    entries: self.entries.load(Ordering::SeqCst),
+                },
+            );
+            taskbar::report(&self.app, &taskbar_key(self.id), self.share_done(processed));
+        }
+    }
+
+    fn share_done(&self, processed: u64) -> Option<f64> {
+        (self.total > 0).then(|| processed as f64 / self.total as f64)
+    }
+
+    /// Count an entry, failing once the operation is cancelled
+    fn entry(&self) -> io::Result<()> {
+        self.check()?;
+        self.entries.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+
+    fn check(&self) -> io::Result<()> {
+        if self.cancelled.load(Ordering::SeqCst) {
+            return Err(io::Error::other("cancelled"));
+        }
+        Ok(())
+    }
+}
+
+/// Counts bytes read and stops reading when cancelled
+struct TrackedReader<R> {
+    inner: R,
+    tracker: Arc<Tracker>,
+}
+
+impl<R: Read> Read for TrackedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.tracker.check()?;
+        let read = self.inner.read(buf)?;
+        self.tracker.add(read as u64);
+        Ok(read)
+    }
+}
+
+/// A filesystem entry to pack and its name inside the archive
+struct Input {
+    path: PathBuf,
+    name: String,
+}
+
+/// Every entry under `paths`, named relative to each path's parent
+fn collect_inputs(paths: &[String]) -> Result<(Vec<Input>, u64), String> {
+    let mut inputs = Vec::new();
+    let mut total = 0;
+    for path in paths {
+        let path = Path::new(path);
+        if path.symlink_metadata().is_err() {
+            return Err(format!("{} does not exist", path.display()));
+        }
+        let base = path.parent().unwrap_or(Path::new(""));
+        for entry in WalkDir::new(path) {
+            let entry = entry.map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            if entry.file_type().is_file() {
+                total += entry.metadata().map(|meta| meta.len()).unwrap_or(0);
+            }
+            let name = entry
+                .path()
+                .strip_prefix(base)
+                .unwrap_or(entry.path())
+                .components()
+                .map(|part| part.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            inputs.push(Input {
+                path: entry.into_path(),
+                name,
+            });
+        }
+    }
+    Ok((inputs, total))
+}
+
+#[cfg(unix)]
+fn mode(meta: &fs::Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    meta.permissions().mode()
+}
+
+#[cfg(not(unix))]
+fn mode(meta: &fs::Metadata) -> u32 {
+    if meta.is_dir() {
+        0o755
+    } else {
+        0o644
+    }
+}
+
+fn write_zip(inputs: &[Input], file: File, tracker: &Arc<Tracker>) -> io::Result<()> {
+    let mut zip = ZipWriter::new(file);
+    for input in inputs {
+        tracker.entry()?;
+        let meta = input.path.symlink_metadata()?;
+        let options = SimpleFileOptions::default()
+            .compression_method(CompressionMethod::Deflated)
+            .unix_permissions(mode(&meta))
+            .large_file(meta.len() >= u32::MAX as u64);
+        if meta.is_symlink() {
+            let target = fs::read_link(&input.path)?;
+            zip.add_symlink(&input.name, target.to_string_lossy(), options)?;
+        } else if meta.is_dir() {
+            zip.add_directory(&input.name, options)?;
+        } else {
+            zip.start_file(&input.name, options)?;
+            let mut reader = TrackedReader {
+                inner: File::open(&input.path)?,
+                tracker: tracker.clone(),
+            };
+            io::copy(&mut reader, &mut zip)?;
+        }
+    }
+    zip.finish()?.sync_all()
+}
+
+fn write_tar<W: Write>(inputs: &[Input], out: W, tracker: &Arc<Tracker>) -> io::Result<W> {
+    let mut tar = tar::Builder::new(out);
+    tar.follow_symlinks(false);
+    for input in inputs {
+        tracker.entry()?;
+        let meta = input.path.symlink_metadata()?;
+        if meta.is_file() {
+            let mut header = tar::Header::new_gnu();
+            header.set_metadata(&meta);
+            let reader = TrackedReader {
+                inner: File::open(&input.path)?,
+                tracker: tracker.clone(),
+            };
+            tar.append_data(&mut header, &input.name, reader)?;
+        } else {
+            tar.append_path_with_name(&input.path, &input.name)?;
+        }
+    }
+    tar.into_inner()
+}
+
+fn create(
+    inputs: &[Input],
+    dest: &Path,
+    format: ArchiveFormat,
+    tracker: &Arc<Tracker>,
+) -> io::Result<()> {
+    let file = File::create(dest)?;
+    match format {
+        ArchiveFormat::Zip => write_zip(inputs, file, tracker),
+        ArchiveFormat::TarGz => {
+            let gz = write_tar(
+                inputs,
+                GzEncoder::new(file, Compression::default()),
+                tracker,
+            )?;
+            gz.finish()?.sync_all()
+        }
+        ArchiveFormat::Tar => write_tar(inputs, file, tracker)?.sync_all(),
+    }
+}
+
+/// Whether `path` is inside `root`, a canonical path, once the symlinks
+/// along the part of it that exists are followed
+fn resolves_inside(root: &Path, path: &Path) -> bool {
+    let mut existing = path;
+    while fs::symlink_metadata(existing).is_err() {
+        match existing.parent() {
+            Some(parent) => existing = parent,
+            None => return false,
+        }
+    }
+    existing
+        .canonicalize()
+        .is_ok_and(|existing| existing.starts_with(root))
+}
+
+/// Whether a symlink at `name`, relative to the destination, pointing at
+/// `target` stays inside the destination
+#[cfg(unix)]
+fn link_stays_inside(name: &Path, target: &Path) -> bool {
+    use std::path::Component;
+    let mut depth = name.components().count().saturating_sub(1);
+    for component in target.components() {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::CurDir => {}
+            Component::ParentDir => match depth.checked_sub(1) {
+                Some(parent) => depth = parent,
+                None => return false,
+            },
+            Component::RootDir | Component::Prefix(_) => return false,
+        }
+    }
+    true
+}
+
+fn extract_zip(src: &Path, dest: &Path, tracker: &Arc<Tracker>) -> io::Result<()> {
+    let root = dest.canonicalize()?;
+    let mut zip = ZipArchive::new(File::open(src)?)?;
+    for index in 0..zip.len() {
+        tracker.entry()?;
+        let mut entry = zip.by_index(index)?;
+        let Some(name) = entry.enclosed_name() else {
+            continue;
+        };
+        let path = root.join(&name);
+        // A symlink already there, from the archive or not, could lead out
+        if !resolves_inside(&root, &path) {
+            continue;
+        }
+        if entry.is_dir() {
+            fs::create_dir_all(&path)?;
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // Replaced rather than written through
+        if fs::symlink_metadata(&path).is_ok_and(|meta| meta.file_type().is_symlink()) {
+            fs::remove_file(&path)?;
+        }
+        #[cfg(unix)]
+        if entry.is_symlink() {
+            let mut target = String::new();
+            entry.read_to_string(&mut target)?;
+            if !link_stays_inside(&name, Path::new(&target)) {
+                continue;
+            }
+            let _ = fs::remove_file(&path);
+            std::os::unix::fs::symlink(target, &path)?;
+            continue;
+        }
+
+        let mut out = File::create(&path)?;
+        let mut reader = TrackedReader {
+            inner: &mut entry,
+            tracker: tracker.clone(),
+        };
+        io::copy(&mut reader, &mut out)?;
+        #[cfg(unix)]
+        if let Some(mode) = entry.unix_mode() {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(mode))?;
+        }
+    }
+    Ok(())
+}
+
+fn extract_tar<R: Read>(reader: R, dest: &Path, tracker: &Arc<Tracker>) -> io::Result<()> {
+    let mut tar = tar::Archive::new(reader);
+    tar.set_preserve_permissions(true);
+    for entry in tar.entries()? {
+        tracker.entry()?;
+        // unpack_in refuses paths that escape `dest`
+        entry?.unpack_in(dest)?;
+    }
+    Ok(())
+}
+
+fn extract(
+    src: &Path,
+    dest: &Path,
+    format: ArchiveFormat,
+    tracker: &Arc<Tracker>,
+) -> io::Result<()> {
+    fs::create_dir_all(dest)?;
+    let tracked = || -> io::Result<TrackedReader<File>> {
+        Ok(TrackedReader {
+            inner: File::open(src)?,
+            tracker: tracker.clone(),
+        })
+    };
+    match format {
+        ArchiveFormat::Zip => extract_zip(src, dest, tracker),
+        ArchiveFormat::TarGz => extract_tar(GzDecoder::new(tracked()?), dest, tracker),
+        ArchiveFormat::Tar => extract_tar(tracked()?, dest, tracker),
+    }
+}
+
+fn taskbar_key(id: u32) -> String {
+    format!("archive:{}", id)
+}
+
+/// Register an operation and run `work` for it on a background thread
+fn start(
+    app: AppHandle,
+    state: &ArchiveState,
+    dest: PathBuf,
+    total: u64,
+    work: impl FnOnce(&Arc<Tracker>) -> io::Result<()> + Send + 'static,
+    on_failure: impl FnOnce() + Send + 'static,
+) -> u32 {
+    let id = state.next_id.fetch_add(1, Ordering::SeqCst);
+    let cancelled = Arc::new(AtomicBool::new(false));
+    state.operations.lock().insert(id, cancelled.clone());
+
+    thread::spawn(move || {
+        let tracker = Arc::new(Tracker {
+            app: app.clone(),
+            id,
+            total,
+            processed: AtomicU64::new(0),
+            entries: AtomicU64::new(0),
+            cancelled: cancelled.clone(),
+            reported: Mutex::new(Instant::now()),
+        });
+        taskbar::report(&app, &taskbar_key(id), tracker.share_done(0));
+        let result = work(&tracker);
+        let cancelled = cancelled.load(Ordering::SeqCst);
+        if result.is_err() {
+            on_failure();
+        }
+
+        app.state::<ArchiveState>().operations.lock().remove(&id);
+        taskbar::finish(&app, &taskbar_key(id));
+        let _ = app.emit(
+            "archive-done",
+            ArchiveDoneEvent {
+                id,
+                dest: dest.to_string_lossy().into_owned(),
+                entries: tracker.entries.load(Ordering::SeqCst),
+                bytes: tracker.processed.load(Ordering::SeqCst),
+                cancelled,
+                error: result.err().filter(|_| !cancelled).map(|e| e.to_string()),
+            },
+        );
+    });
+    id
+}
+
+/// Pack `paths` into an archive at `dest`, returning the ID that tags its
+/// events. The format defaults to the one `dest`'s extension names.
+#[tauri::command]
+pub async fn create_archive(
+    app: AppHandle,
+    state: State<'_, ArchiveState>,
+    paths: Vec<String>,
+    dest: String,
+    format: Option<ArchiveFormat>,
+) -> Result<u32, String> {
+    let dest = PathBuf::from(dest);
+    let format = format
+        .or_else(|| ArchiveFormat::from_path(&dest))
+        .ok_or_else(|| format!("Can't tell the archive format of {}", dest.display()))?;
+    if paths.is_empty() {
+        return Err("Nothing to archive".to_string());
+    }
+    let (inputs, total) = tauri::async_runtime::spawn_blocking(move || collect_inputs(&paths))
+        .await
+        .map_err(|e| format!("Archiving failed: {}", e))??;
+
+    let target = dest.clone();
+    let partial = dest.clone();
+    Ok(start(
+        app,
+        &state,
+        dest,
+        total,
+        move |tracker| create(&inputs, &target, format, tracker),
+        move || {
+            let _ = fs::remove_file(&partial);
+        },
+    ))
+}
+
+/// Unpack the archive at `src` into `dest`, returning the ID that tags its
+/// events
+#[tauri::command]
+pub async fn extract_archive(
+    app: AppHandle,
+    state: State<'_, ArchiveState>,
+    src: String,
+    dest: String,
+) -> Result<u32, String> {
+    let src = PathBuf::from(src);
+    let dest = PathBuf::from(dest);
+    let format = ArchiveFormat::from_path(&src)
+        .ok_or_else(|| format!("Can't tell the archive format of {}", src.display()))?;
+    let total = match format {
+        ArchiveFormat::Zip => File::open(&src)
+            .map_err(|e| e.to_string())
+            .and_then(|file| ZipArchive::new(file).map_err(|e| e.to_string()))
+            .map_err(|e| format!("Failed to read {}: {}", src.display(), e))?
+            .decompressed_size()
+            .map_or(0, |size| size as u64),
+        ArchiveFormat::TarGz | ArchiveFormat::Tar => fs::metadata(&src)
+            .map_err(|e| format!("Failed to read {}: {}", src.display(), e))?
+            .len(),
+    };
+
+    let target = dest.clone();
+    Ok(start(
+        app,
+        &state,
+        dest,
+        total,
+        move |tracker| extract(&src, &target, format, tracker),
+        || {},
+    ))
+}
+
+/// Stop an archive operation; `archive-done` still follows
+#[tauri::command]
+pub async fn cancel_archive(state: State<'_, ArchiveState>, id: u32) -> Result<(), String> {
+    if let Some(cancelled) = state.operations.lock().get(&id) {
+        cancelled.store(true, Ordering::SeqCst);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn keeps_symlinks_inside_the_destination() {
+        let inside =
+            |name: &str, target: &str| link_stays_inside(Path::new(name), Path::new(target));
+        assert!(inside("link", "file"));
+        assert!(inside("a/link", "../b"));
+        assert!(inside("a/b/link", "../../c"));
+        assert!(inside("link", "./x/../y"));
+        assert!(inside("link", ""));
+        assert!(!inside("link", ".."));
+        assert!(!inside("link", "../x"));
+        assert!(!inside("a/b/link", "../../../c"));
+        assert!(!inside("link", "x/../../y"));
+        assert!(!inside("a/link", "b/../../../a"));
+        assert!(!inside("link", "/etc/passwd"));
+    }
+
+    #[test]
+    fn resolves_paths_through_existing_symlinks() {
+        let base = std::env::temp_dir().join(format!("antler-archive-test-{}", std::process::id()));
+        let root = base.join("root");
+        fs::create_dir_all(root.join("dir")).unwrap();
+        let root = root.canonicalize().unwrap();
+
+        assert!(resolves_inside(&root, &root.join("dir/new/file")));
+        assert!(resolves_inside(&root, &root.join("missing")));
+        assert!(!resolves_inside(&root, &base.join("outside")));
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(&base, root.join("escape")).unwrap();
+            std::os::unix::fs::symlink(root.join("dir"), root.join("alias")).unwrap();
+            assert!(!resolves_inside(&root, &root.join("escape/file")));
+            assert!(resolves_inside(&root, &root.join("alias/file")));
+        }
+
+        fs::remove_dir_all(&base).unwrap();
+    }
+}
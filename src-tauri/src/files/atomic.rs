@@ -0,0 +1,103 @@
+//! Atomic file writes
+//!
+//! The contents go to a temporary file next to the target, which is flushed
+//! to disk and then renamed over it, so readers see either the old file or
+//! the complete new one - never a half-written config if the app dies
+//! mid-write. The previous version can be kept as `<name>.bak`.
+
+use serde::Deserialize;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Options for `write_file_atomic`
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AtomicWriteOptions {
+    /// Copy the existing file to `<name>.bak` before replacing it
+    #[serde(default)]
+    backup: bool,
+}
+
+/// `<name><suffix>` next to `path`
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+/// Make the rename itself durable
+#[cfg(unix)]
+fn sync_dir(dir: &Path) {
+    if let Ok(dir) = File::open(dir) {
+        let _ = dir.sync_all();
+    }
+}
+
+/// Directories can't be opened for syncing here; NTFS journals the rename
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) {}
+
+/// Replace `path` with `contents` atomically
+pub(crate) fn write_atomic(path: &Path, contents: &[u8], backup: bool) -> Result<(), String> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let existing = fs::metadata(path).ok();
+    if existing.as_ref().is_some_and(|meta| meta.is_dir()) {
+        return Err(format!("{} is a directory", path.display()));
+    }
+
+    let nonce = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.subsec_nanos())
+        .unwrap_or_default();
+    let temp = sibling(path, &format!(".{}-{}.tmp", std::process::id(), nonce));
+    let written = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&temp)
+        .and_then(|mut file| {
+            file.write_all(contents)?;
+            // Keep the mode of the file being replaced, e.g. an executable bit
+            if let Some(meta) = &existing {
+                file.set_permissions(meta.permissions())?;
+            }
+            file.sync_all()
+        });
+    if let Err(e) = written {
+        let _ = fs::remove_file(&temp);
+        return Err(format!("Failed to write {}: {}", temp.display(), e));
+    }
+
+    if backup && existing.is_some() {
+        let bak = sibling(path, ".bak");
+        if let Err(e) = fs::copy(path, &bak) {
+            let _ = fs::remove_file(&temp);
+            return Err(format!("Failed to back up to {}: {}", bak.display(), e));
+        }
+    }
+    if let Err(e) = fs::rename(&temp, path) {
+        let _ = fs::remove_file(&temp);
+        return Err(format!("Failed to replace {}: {}", path.display(), e));
+    }
+    sync_dir(dir);
+    Ok(())
+}
+
+/// Write `contents` to `path` so it is never left half-written
+#[tauri::command]
+pub async fn write_file_atomic(
+    path: String,
+    contents: String,
+    options: Option<AtomicWriteOptions>,
+) -> Result<(), String> {
+    let options = options.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || {
+        write_atomic(Path::new(&path), contents.as_bytes(), options.backup)
+    })
+    .await
+    .map_err(|e| format!("Write failed: {}", e))?
+}
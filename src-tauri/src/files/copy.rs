@@ -0,0 +1,401 @@
+//! Copying and moving directory trees
+//!
+//! `copy_tree` and `move_tree` run on a background thread, emitting
+//! `copy-progress` with files and bytes done a few times a second and
+//! `copy-done` with the outcome, so copying a template project into a new
+//! worktree shows progress and can be cancelled. Directories that already
+//! exist are merged; what happens to a file that already exists is up to the
+//! collision policy. Symlinks are recreated rather than followed.
+//!
+//! A move is a single rename when `dest` is free and on the same filesystem,
+//! otherwise a copy that removes each source file once it has been copied,
+//! so a cancelled move leaves every file in exactly one place.
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, State};
+use walkdir::WalkDir;
+
+const BUFFER_SIZE: usize = 256 * 1024;
+
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Running copies and moves
+pub struct CopyState {
+    operations: Mutex<HashMap<u32, Arc<AtomicBool>>>,
+    next_id: AtomicU32,
+}
+
+impl Default for CopyState {
+    fn default() -> Self {
+        Self {
+            operations: Mutex::new(HashMap::new()),
+            next_id: AtomicU32::new(1),
+        }
+    }
+}
+
+/// What to do when a file already exists at the destination
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Collision {
+    /// Stop with an error
+    #[default]
+    Error,
+    /// Keep the existing file
+    Skip,
+    /// Replace the existing file
+    Overwrite,
+    /// Copy alongside it as `name (2).ext`
+    Rename,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CopyOptions {
+    collision: Option<Collision>,
+}
+
+/// Event payload for progress
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CopyProgressEvent {
+    id: u32,
+    files: u64,
+    total_files: u64,
+    bytes: u64,
+    total_bytes: u64,
+    skipped: u64,
+}
+
+/// Event payload for a finished, failed or cancelled operation
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CopyDoneEvent {
+    id: u32,
+    dest: String,
+    files: u64,
+    bytes: u64,
+    skipped: u64,
+    /// Moved with a single rename, so nothing was counted
+    renamed: bool,
+    cancelled: bool,
+    error: Option<String>,
+}
+
+/// Files and bytes under `path`, for the progress totals
+fn measure(path: &Path) -> (u64, u64) {
+    WalkDir::new(path)
+        .into_iter()
+        .flatten()
+        .filter(|entry| !entry.file_type().is_dir())
+        .fold((0, 0), |(files, bytes), entry| {
+            let size = entry.metadata().map_or(0, |meta| meta.len());
+            (files + 1, bytes + size)
+        })
+}
+
+/// `path` made absolute, resolving whatever part of it already exists
+fn absolute(path: &Path) -> PathBuf {
+    let mut missing = Vec::new();
+    let mut current = path;
+    loop {
+        if let Ok(resolved) = fs::canonicalize(current) {
+            return missing
+                .iter()
+                .rev()
+                .fold(resolved, |path, name| path.join(name));
+        }
+        match (current.parent(), current.file_name()) {
+            (Some(parent), Some(name)) => {
+                missing.push(name);
+                current = parent;
+            }
+            _ => return path.to_path_buf(),
+        }
+    }
+}
+
+/// The first of `name (2).ext`, `name (3).ext`, ... next to `path` that is free
+fn free_name(path: &Path) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .map(|extension| format!(".{}", extension.to_string_lossy()))
+        .unwrap_or_default();
+    (2..)
+        .map(|n| path.with_file_name(format!("{} ({}){}", stem, n, extension)))
+        .find(|candidate| fs::symlink_metadata(candidate).is_err())
+        .expect("ran out of names")
+}
+
+/// A copy or move in progress
+struct Transfer<'a> {
+    app: &'a AppHandle,
+    id: u32,
+    cancelled: &'a AtomicBool,
+    collision: Collision,
+    remove_source: bool,
+    total_files: u64,
+    total_bytes: u64,
+    files: u64,
+    bytes: u64,
+    skipped: u64,
+    reported: Instant,
+}
+
+impl Transfer<'_> {
+    fn check(&self) -> Result<(), String> {
+        if self.cancelled.load(Ordering::SeqCst) {
+            return Err("cancelled".to_string());
+        }
+        Ok(())
+    }
+
+    fn report(&mut self) {
+        if self.reported.elapsed() < PROGRESS_INTERVAL {
+            return;
+        }
+        self.reported = Instant::now();
+        let _ = self.app.emit(
+            "copy-progress",
+            CopyProgressEvent {
+                id: self.id,
+                files: self.files,
+                total_files: self.total_files,
+                bytes: self.bytes,
+                total_bytes: self.total_bytes,
+                skipped: self.skipped,
+            },
+        );
+    }
+
+    /// Where `from` should go given what is already at `to`, or `None` to
+    /// skip it. A directory merges into an existing directory.
+    fn target(&self, to: &Path, is_dir: bool) -> Result<Option<PathBuf>, String> {
+        let Ok(existing) = fs::symlink_metadata(to) else {
+            return Ok(Some(to.to_path_buf()));
+        };
+        if is_dir && existing.is_dir() {
+            return Ok(Some(to.to_path_buf()));
+        }
+        match self.collision {
+            Collision::Error => Err(format!("{} already exists", to.display())),
+            Collision::Skip => Ok(None),
+            Collision::Overwrite => {
+                let removed = if existing.is_dir() {
+                    fs::remove_dir_all(to)
+                } else {
+                    fs::remove_file(to)
+                };
+                removed.map_err(|e| format!("Failed to replace {}: {}", to.display(), e))?;
+                Ok(Some(to.to_path_buf()))
+            }
+            Collision::Rename => Ok(Some(free_name(to))),
+        }
+    }
+
+    fn copy_entry(&mut self, from: &Path, to: &Path) -> Result<(), String> {
+        self.check()?;
+        let meta = fs::symlink_metadata(from)
+            .map_err(|e| format!("Failed to read {}: {}", from.display(), e))?;
+        let Some(to) = self.target(to, meta.is_dir())? else {
+            self.skipped += if meta.is_dir() { measure(from).0 } else { 1 };
+            self.report();
+            return Ok(());
+        };
+
+        if meta.is_dir() {
+            fs::create_dir_all(&to)
+                .map_err(|e| format!("Failed to create {}: {}", to.display(), e))?;
+            let entries = fs::read_dir(from)
+                .map_err(|e| format!("Failed to read {}: {}", from.display(), e))?;
+            for entry in entries {
+                let entry =
+                    entry.map_err(|e| format!("Failed to read {}: {}", from.display(), e))?;
+                self.copy_entry(&entry.path(), &to.join(entry.file_name()))?;
+            }
+            let _ = fs::set_permissions(&to, meta.permissions());
+            if self.remove_source {
+                // Anything skipped is still inside, and keeps it
+                let _ = fs::remove_dir(from);
+            }
+            return Ok(());
+        }
+
+        if meta.is_symlink() {
+            let target = fs::read_link(from)
+                .map_err(|e| format!("Failed to read {}: {}", from.display(), e))?;
+            super::link::link(&target, &to)?;
+        } else {
+            if let Err(e) = self.copy_file(from, &to) {
+                let _ = fs::remove_file(&to);
+                return Err(e);
+            }
+            let _ = fs::set_permissions(&to, meta.permissions());
+        }
+        if self.remove_source {
+            fs::remove_file(from)
+                .map_err(|e| format!("Failed to remove {}: {}", from.display(), e))?;
+        }
+        self.files += 1;
+        self.report();
+        Ok(())
+    }
+
+    fn copy_file(&mut self, from: &Path, to: &Path) -> Result<(), String> {
+        let mut reader =
+            File::open(from).map_err(|e| format!("Failed to open {}: {}", from.display(), e))?;
+        let mut writer =
+            File::create(to).map_err(|e| format!("Failed to create {}: {}", to.display(), e))?;
+        let mut buf = vec![0; BUFFER_SIZE];
+        loop {
+            self.check()?;
+            let read = reader
+                .read(&mut buf)
+                .map_err(|e| format!("Failed to read {}: {}", from.display(), e))?;
+            if read == 0 {
+                break;
+            }
+            writer
+                .write_all(&buf[..read])
+                .map_err(|e| format!("Failed to write {}: {}", to.display(), e))?;
+            self.bytes += read as u64;
+            self.report();
+        }
+        writer
+            .flush()
+            .map_err(|e| format!("Failed to write {}: {}", to.display(), e))
+    }
+}
+
+/// Register an operation and run it on a background thread
+fn start(
+    app: AppHandle,
+    state: &CopyState,
+    src: String,
+    dest: String,
+    options: Option<CopyOptions>,
+    remove_source: bool,
+) -> Result<u32, String> {
+    let src = PathBuf::from(src);
+    let dest = PathBuf::from(dest);
+    if fs::symlink_metadata(&src).is_err() {
+        return Err(format!("{} does not exist", src.display()));
+    }
+    let (from, to) = (absolute(&src), absolute(&dest));
+    if to.starts_with(&from) {
+        return Err(format!(
+            "Can't {} {} into itself",
+            if remove_source { "move" } else { "copy" },
+            src.display()
+        ));
+    }
+    let collision = options.unwrap_or_default().collision.unwrap_or_default();
+
+    let id = state.next_id.fetch_add(1, Ordering::SeqCst);
+    let cancelled = Arc::new(AtomicBool::new(false));
+    state.operations.lock().insert(id, cancelled.clone());
+
+    thread::spawn(move || {
+        let mut transfer = Transfer {
+            app: &app,
+            id,
+            cancelled: &cancelled,
+            collision,
+            remove_source,
+            total_files: 0,
+            total_bytes: 0,
+            files: 0,
+            bytes: 0,
+            skipped: 0,
+            reported: Instant::now(),
+        };
+        let result = run(&mut transfer, &src, &dest);
+        let cancelled = cancelled.load(Ordering::SeqCst);
+
+        app.state::<CopyState>().operations.lock().remove(&id);
+        let _ = app.emit(
+            "copy-done",
+            CopyDoneEvent {
+                id,
+                dest: dest.to_string_lossy().into_owned(),
+                files: transfer.files,
+                bytes: transfer.bytes,
+                skipped: transfer.skipped,
+                renamed: result.as_ref().is_ok_and(|renamed| *renamed),
+                cancelled,
+                error: result.err().filter(|_| !cancelled),
+            },
+        );
+    });
+    Ok(id)
+}
+
+/// Copy or move `src` to `dest`, returning whether it was a plain rename
+fn run(transfer: &mut Transfer, src: &Path, dest: &Path) -> Result<bool, String> {
+    if let Some(parent) = dest
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+
+    if transfer.remove_source && fs::symlink_metadata(dest).is_err() {
+        match fs::rename(src, dest) {
+            Ok(()) => return Ok(true),
+            // Most likely another filesystem; copy instead
+            Err(e) if e.kind() != io::ErrorKind::NotFound => {}
+            Err(e) => return Err(format!("Failed to move {}: {}", src.display(), e)),
+        }
+    }
+
+    (transfer.total_files, transfer.total_bytes) = measure(src);
+    transfer.copy_entry(src, dest)?;
+    Ok(false)
+}
+
+/// Copy `src` to `dest`, returning the ID that tags its events
+#[tauri::command]
+pub async fn copy_tree(
+    app: AppHandle,
+    state: State<'_, CopyState>,
+    src: String,
+    dest: String,
+    options: Option<CopyOptions>,
+) -> Result<u32, String> {
+    start(app, &state, src, dest, options, false)
+}
+
+/// Move `src` to `dest`, returning the ID that tags its events
+#[tauri::command]
+pub async fn move_tree(
+    app: AppHandle,
+    state: State<'_, CopyState>,
+    src: String,
+    dest: String,
+    options: Option<CopyOptions>,
+) -> Result<u32, String> {
+    start(app, &state, src, dest, options, true)
+}
+
+/// Stop a copy or move; `copy-done` still follows with what was done
+#[tauri::command]
+pub async fn cancel_copy(state: State<'_, CopyState>, id: u32) -> Result<(), String> {
+    if let Some(cancelled) = state.operations.lock().get(&id) {
+        cancelled.store(true, Ordering::SeqCst);
+    }
+    Ok(())
+}
@@ -0,0 +1,160 @@
+//! Text diffs
+//!
+//! `diff_text` and `diff_files` compare two texts with the `similar` crate
+//! and return hunks in the same shape as `git_diff`, so before/after previews
+//! of agent edits use the same renderer as the working tree diff. Diffing
+//! gives up on finding the smallest diff after a few seconds, which keeps
+//! very large inputs from hanging.
+
+use super::inspect::looks_binary;
+use crate::git::diff::{DiffHunk, DiffLine, DiffLineKind};
+use serde::{Deserialize, Serialize};
+use similar::{Algorithm, ChangeTag, TextDiff};
+use std::path::Path;
+use std::time::Duration;
+
+/// Lines of context around each hunk when the caller sets none, as git does
+const DEFAULT_CONTEXT: usize = 3;
+
+/// How long to look for a minimal diff before settling for a larger one
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Options for `diff_text` and `diff_files`
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffTextOptions {
+    /// Unchanged lines shown around each change
+    context: Option<usize>,
+    /// Use the patience algorithm, which often reads better for code
+    #[serde(default)]
+    patience: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextDiffResult {
+    hunks: Vec<DiffHunk>,
+    added: u32,
+    removed: u32,
+    /// Similarity of the two texts from 0 to 1
+    ratio: f32,
+    /// Either side looked binary, so no hunks were computed
+    binary: bool,
+}
+
+fn diff(old: &str, new: &str, options: &DiffTextOptions) -> TextDiffResult {
+    let algorithm = if options.patience {
+        Algorithm::Patience
+    } else {
+        Algorithm::Myers
+    };
+    let diff = TextDiff::configure()
+        .algorithm(algorithm)
+        .timeout(TIMEOUT)
+        .diff_lines(old, new);
+
+    let mut hunks = Vec::new();
+    let (mut added, mut removed) = (0, 0);
+    for group in diff.grouped_ops(options.context.unwrap_or(DEFAULT_CONTEXT)) {
+        let (Some(first), Some(last)) = (group.first(), group.last()) else {
+            continue;
+        };
+        let old_range = first.old_range().start..last.old_range().end;
+        let new_range = first.new_range().start..last.new_range().end;
+        let mut hunk = DiffHunk {
+            // An empty side starts at the line before it, as in unified diffs
+            old_start: old_range.start as u32 + u32::from(!old_range.is_empty()),
+            old_lines: old_range.len() as u32,
+            new_start: new_range.start as u32 + u32::from(!new_range.is_empty()),
+            new_lines: new_range.len() as u32,
+            header: String::new(),
+            lines: Vec::new(),
+        };
+
+        for op in &group {
+            for change in diff.iter_changes(op) {
+                let kind = match change.tag() {
+                    ChangeTag::Equal => DiffLineKind::Context,
+                    ChangeTag::Insert => {
+                        added += 1;
+                        DiffLineKind::Added
+                    }
+                    ChangeTag::Delete => {
+                        removed += 1;
+                        DiffLineKind::Removed
+                    }
+                };
+                let content = change.value();
+                hunk.lines.push(DiffLine {
+                    kind,
+                    content: content
+                        .strip_suffix('\n')
+                        .unwrap_or(content)
+                        .trim_end_matches('\r')
+                        .to_string(),
+                    old_line: change.old_index().map(|line| line as u32 + 1),
+                    new_line: change.new_index().map(|line| line as u32 + 1),
+                });
+            }
+        }
+        hunks.push(hunk);
+    }
+
+    TextDiffResult {
+        hunks,
+        added,
+        removed,
+        ratio: diff.ratio(),
+        binary: false,
+    }
+}
+
+fn read(path: &str) -> Result<Vec<u8>, String> {
+    // A missing side diffs as empty, like a created or deleted file
+    if !Path::new(path).exists() {
+        return Ok(Vec::new());
+    }
+    std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path, e))
+}
+
+/// Diff two strings line by line
+#[tauri::command]
+pub async fn diff_text(
+    a: String,
+    b: String,
+    options: Option<DiffTextOptions>,
+) -> Result<TextDiffResult, String> {
+    let options = options.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || diff(&a, &b, &options))
+        .await
+        .map_err(|e| format!("Diff failed: {}", e))
+}
+
+/// Diff two files line by line; a path that doesn't exist counts as empty
+#[tauri::command]
+pub async fn diff_files(
+    path_a: String,
+    path_b: String,
+    options: Option<DiffTextOptions>,
+) -> Result<TextDiffResult, String> {
+    let options = options.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || {
+        let (a, b) = (read(&path_a)?, read(&path_b)?);
+        if looks_binary(&a) || looks_binary(&b) {
+            return Ok(TextDiffResult {
+                hunks: Vec::new(),
+                added: 0,
+                removed: 0,
+                ratio: if a == b { 1.0 } else { 0.0 },
+                binary: true,
+            });
+        }
+        Ok(diff(
+            &String::from_utf8_lossy(&a),
+            &String::from_utf8_lossy(&b),
+            &options,
+        ))
+    })
+    .await
+    .map_err(|e| format!("Diff failed: {}", e))?
+}
@@ -0,0 +1,382 @@
+//! Downloads - fetching files over HTTP straight to disk
+//!
+//! `download_file` streams a URL into `<dest>.part` in the background,
+//! emitting `download-progress` a few times a second and `download-done`
+//! with the outcome, and renames it to `dest` once complete and, when a
+//! checksum was given, verified. A download that was cancelled or failed
+//! keeps its partial file, and the next download to the same `dest` asks the
+//! server for the rest with a range request, tied by `If-Range` to the
+//! version it started on; servers that ignore it, and resources that changed
+//! since, send the whole file again. A checksum mismatch discards the
+//! partial file. Requests go through the proxy from `set_proxy_settings`
+//! unless the download names its own, and can trust extra CA certificates.
+
+use super::hash::{HashAlgorithm, Hasher};
+use crate::{proxy, taskbar};
+use parking_lot::Mutex;
+use reqwest::header::{CONTENT_RANGE, ETAG, IF_RANGE, LAST_MODIFIED, RANGE, USER_AGENT};
+use reqwest::{Certificate, Proxy, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+const BUFFER_SIZE: usize = 256 * 1024;
+
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Time to reach the server
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Time the server may go without sending anything
+const READ_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Running downloads
+pub struct DownloadState {
+    downloads: Mutex<HashMap<u32, Arc<AtomicBool>>>,
+    next_id: AtomicU32,
+}
+
+impl Default for DownloadState {
+    fn default() -> Self {
+        Self {
+            downloads: Mutex::new(HashMap::new()),
+            next_id: AtomicU32::new(1),
+        }
+    }
+}
+
+/// Options for `download_file`
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadOptions {
+    /// Expected hex digest of the whole file
+    checksum: Option<String>,
+    /// Algorithm of `checksum`, SHA-256 by default
+    #[serde(default)]
+    algorithm: HashAlgorithm,
+    /// Proxy URL, such as `http://proxy:3128`, instead of the configured one
+    proxy: Option<String>,
+    /// PEM file with CA certificates to trust besides the built-in ones
+    ca_cert: Option<String>,
+    /// Extra request headers
+    #[serde(default)]
+    headers: HashMap<String, String>,
+}
+
+/// Event payload for progress
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DownloadProgressEvent {
+    id: u32,
+    bytes: u64,
+    /// `None` while the server hasn't said
+    total: Option<u64>,
+}
+
+/// Event payload for a finished, failed or cancelled download
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DownloadDoneEvent {
+    id: u32,
+    dest: String,
+    bytes: u64,
+    cancelled: bool,
+    error: Option<String>,
+}
+
+fn part_path(dest: &Path) -> PathBuf {
+    let mut name = dest.as_os_str().to_owned();
+    name.push(".part");
+    PathBuf::from(name)
+}
+
+/// Where the ETag or Last-Modified of the partial file's version is kept
+fn validator_path(dest: &Path) -> PathBuf {
+    let mut name = dest.as_os_str().to_owned();
+    name.push(".part.validator");
+    PathBuf::from(name)
+}
+
+/// What tells this version of the resource from later ones, as `If-Range`
+/// takes it: a strong ETag, or else the modification date
+fn validator(response: &reqwest::Response) -> Option<String> {
+    let header = |name| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+    };
+    header(ETAG)
+        .filter(|etag| !etag.starts_with("W/"))
+        .or_else(|| header(LAST_MODIFIED))
+}
+
+fn client(app: &AppHandle, options: &DownloadOptions) -> Result<reqwest::Client, String> {
+    let builder = reqwest::Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .read_timeout(READ_TIMEOUT);
+    let mut builder = match &options.proxy {
+        Some(url) => builder.proxy(Proxy::all(url).map_err(|e| format!("Bad proxy: {}", e))?),
+        None => proxy::configure(app, builder),
+    };
+    if let Some(path) = &options.ca_cert {
+        let pem = fs::read(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        for cert in Certificate::from_pem_bundle(&pem)
+            .map_err(|e| format!("Bad certificate in {}: {}", path, e))?
+        {
+            builder = builder.add_root_certificate(cert);
+        }
+    }
+    builder.build().map_err(|e| e.to_string())
+}
+
+/// Where a download is, reported as it goes
+struct Progress {
+    app: AppHandle,
+    id: u32,
+    bytes: u64,
+    total: Option<u64>,
+    reported: Instant,
+}
+
+impl Progress {
+    fn add(&mut self, bytes: u64) {
+        self.bytes += bytes;
+        if self.reported.elapsed() >= PROGRESS_INTERVAL {
+            self.reported = Instant::now();
+            let _ = self.app.emit(
+                "download-progress",
+                DownloadProgressEvent {
+                    id: self.id,
+                    bytes: self.bytes,
+                    total: self.total,
+                },
+            );
+            let done = self
+                .total
+                .map(|total| self.bytes as f64 / total.max(1) as f64);
+            taskbar::report(&self.app, &format!("download:{}", self.id), done);
+        }
+    }
+}
+
+/// Digest of what an earlier attempt already wrote
+fn hash_part(part: &Path, hasher: &mut Hasher) -> Result<(), String> {
+    let mut file =
+        File::open(part).map_err(|e| format!("Failed to read {}: {}", part.display(), e))?;
+    let mut buf = vec![0; BUFFER_SIZE];
+    loop {
+        let read = file
+            .read(&mut buf)
+            .map_err(|e| format!("Failed to read {}: {}", part.display(), e))?;
+        if read == 0 {
+            return Ok(());
+        }
+        hasher.update(&buf[..read]);
+    }
+}
+
+/// First byte of a partial response, from its `Content-Range`
+fn range_start(response: &reqwest::Response) -> Option<u64> {
+    let range = response.headers().get(CONTENT_RANGE)?.to_str().ok()?;
+    let (start, _) = range.strip_prefix("bytes ")?.split_once('-')?;
+    start.trim().parse().ok()
+}
+
+/// Download into the partial file, returning whether it completed rather
+/// than being cancelled
+async fn fetch(
+    url: &str,
+    dest: &Path,
+    options: &DownloadOptions,
+    progress: &mut Progress,
+    cancelled: &AtomicBool,
+) -> Result<bool, String> {
+    let part = part_path(dest);
+    if let Some(parent) = dest.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let validator_file = validator_path(dest);
+    // Without a validator there's no telling the rest would still match
+    let saved = fs::read_to_string(&validator_file)
+        .ok()
+        .filter(|v| !v.trim().is_empty());
+    let mut existing = match saved {
+        Some(_) => fs::metadata(&part).map(|meta| meta.len()).unwrap_or(0),
+        None => 0,
+    };
+
+    let client = client(&progress.app, options)?;
+    let mut response = loop {
+        let mut request = client.get(url).header(USER_AGENT, "Antler");
+        for (key, value) in &options.headers {
+            request = request.header(key, value);
+        }
+        if let (true, Some(saved)) = (existing > 0, &saved) {
+            // A resource that changed since comes back whole
+            request = request
+                .header(RANGE, format!("bytes={}-", existing))
+                .header(IF_RANGE, saved.trim());
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("Failed to reach {}: {}", url, e))?;
+        if response.status() == StatusCode::PARTIAL_CONTENT && existing > 0 {
+            if range_start(&response) == Some(existing) {
+                break response;
+            }
+            // Bytes from elsewhere in the file would corrupt it
+            existing = 0;
+            continue;
+        }
+        if response.status() != StatusCode::RANGE_NOT_SATISFIABLE || existing == 0 {
+            break response;
+        }
+        let total = response
+            .headers()
+            .get(CONTENT_RANGE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("bytes */"))
+            .and_then(|v| v.parse::<u64>().ok());
+        // Asked for the rest of a file that was complete already
+        if total == Some(existing) {
+            progress.bytes = existing;
+            return Ok(true);
+        }
+        // The partial file is longer than the resource is now
+        existing = 0;
+    };
+
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("{} answered {}", url, status));
+    }
+    let resumed = existing > 0 && status == StatusCode::PARTIAL_CONTENT;
+    let start = if resumed { existing } else { 0 };
+    progress.bytes = start;
+    progress.total = response.content_length().map(|length| start + length);
+    if !resumed {
+        match validator(&response) {
+            Some(validator) => fs::write(&validator_file, validator),
+            None => fs::remove_file(&validator_file).or(Ok(())),
+        }
+        .map_err(|e| format!("Failed to write {}: {}", validator_file.display(), e))?;
+    }
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(resumed)
+        .truncate(!resumed)
+        .open(&part)
+        .map_err(|e| format!("Failed to create {}: {}", part.display(), e))?;
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("Download of {} failed: {}", url, e))?
+    {
+        if cancelled.load(Ordering::SeqCst) {
+            return Ok(false);
+        }
+        file.write_all(&chunk)
+            .map_err(|e| format!("Failed to write {}: {}", part.display(), e))?;
+        progress.add(chunk.len() as u64);
+    }
+    file.flush()
+        .map_err(|e| format!("Failed to write {}: {}", part.display(), e))?;
+    Ok(true)
+}
+
+/// Check the partial file against the checksum and move it into place
+fn finish(dest: &Path, options: &DownloadOptions) -> Result<(), String> {
+    let part = part_path(dest);
+    if let Some(expected) = &options.checksum {
+        let mut hasher = Hasher::new(options.algorithm);
+        hash_part(&part, &mut hasher)?;
+        let actual = hasher.finish();
+        if !actual.eq_ignore_ascii_case(expected.trim()) {
+            let _ = fs::remove_file(&part);
+            let _ = fs::remove_file(validator_path(dest));
+            return Err(format!(
+                "Checksum mismatch for {}: expected {}, got {}",
+                dest.display(),
+                expected.trim(),
+                actual
+            ));
+        }
+    }
+    fs::rename(&part, dest).map_err(|e| format!("Failed to replace {}: {}", dest.display(), e))?;
+    let _ = fs::remove_file(validator_path(dest));
+    Ok(())
+}
+
+/// Download `url` to `dest` in the background, returning the ID that tags
+/// its events
+#[tauri::command]
+pub async fn download_file(
+    app: AppHandle,
+    state: State<'_, DownloadState>,
+    url: String,
+    dest: String,
+    options: Option<DownloadOptions>,
+) -> Result<u32, String> {
+    let options = options.unwrap_or_default();
+    let id = state.next_id.fetch_add(1, Ordering::SeqCst);
+    let cancelled = Arc::new(AtomicBool::new(false));
+    state.downloads.lock().insert(id, cancelled.clone());
+
+    tauri::async_runtime::spawn(async move {
+        let taskbar_key = format!("download:{}", id);
+        taskbar::report(&app, &taskbar_key, None);
+        let mut progress = Progress {
+            app: app.clone(),
+            id,
+            bytes: 0,
+            total: None,
+            reported: Instant::now(),
+        };
+        let dest_path = PathBuf::from(&dest);
+        let result = match fetch(&url, &dest_path, &options, &mut progress, &cancelled).await {
+            Ok(true) => tauri::async_runtime::spawn_blocking(move || finish(&dest_path, &options))
+                .await
+                .map_err(|e| e.to_string())
+                .and_then(|result| result),
+            Ok(false) => Ok(()),
+            Err(e) => Err(e),
+        };
+        let cancelled = cancelled.load(Ordering::SeqCst);
+
+        app.state::<DownloadState>().downloads.lock().remove(&id);
+        taskbar::finish(&app, &taskbar_key);
+        let _ = app.emit(
+            "download-done",
+            DownloadDoneEvent {
+                id,
+                dest,
+                bytes: progress.bytes,
+                cancelled,
+                error: result.err().filter(|_| !cancelled),
+            },
+        );
+    });
+    Ok(id)
+}
+
+/// Stop a download, keeping what it fetched for the next attempt;
+/// `download-done` still follows
+#[tauri::command]
+pub async fn cancel_download(state: State<'_, DownloadState>, id: u32) -> Result<(), String> {
+    if let Some(cancelled) = state.downloads.lock().get(&id) {
+        cancelled.store(true, Ordering::SeqCst);
+    }
+    Ok(())
+}
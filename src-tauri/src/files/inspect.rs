@@ -0,0 +1,131 @@
+//! File type detection
+//!
+//! `inspect_file` reads the start of a file and reports its MIME type (from
+//! magic bytes, falling back to the extension), whether it looks binary and
+//! which text encoding it appears to use, so the preview pane can choose
+//! between a text view, an image and a "binary file" placeholder.
+
+use serde::Serialize;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// Bytes read to sniff the content, as git does to spot binary files
+const SNIFF_LEN: usize = 8000;
+
+const UTF8_BOM: &[u8] = &[0xef, 0xbb, 0xbf];
+const UTF16LE_BOM: &[u8] = &[0xff, 0xfe];
+const UTF16BE_BOM: &[u8] = &[0xfe, 0xff];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PreviewKind {
+    Text,
+    Image,
+    Binary,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileInspection {
+    mime: String,
+    binary: bool,
+    size: u64,
+    /// `ascii`, `utf-8`, `utf-16le` or `utf-16be`; `None` for binary files
+    /// and text in an encoding that couldn't be told
+    encoding: Option<String>,
+    /// The file starts with a byte order mark
+    bom: bool,
+    preview: PreviewKind,
+}
+
+/// Whether `data`, the start of a file, contains a NUL byte
+pub(crate) fn looks_binary(data: &[u8]) -> bool {
+    data[..data.len().min(SNIFF_LEN)].contains(&0)
+}
+
+/// The encoding of `sample` and whether it starts with a byte order mark
+pub(crate) fn guess_encoding(sample: &[u8]) -> (Option<&'static str>, bool) {
+    if sample.starts_with(UTF8_BOM) {
+        return (Some("utf-8"), true);
+    }
+    if sample.starts_with(UTF16LE_BOM) {
+        return (Some("utf-16le"), true);
+    }
+    if sample.starts_with(UTF16BE_BOM) {
+        return (Some("utf-16be"), true);
+    }
+    if sample.is_ascii() {
+        return (Some("ascii"), false);
+    }
+    match std::str::from_utf8(sample) {
+        Ok(_) => (Some("utf-8"), false),
+        // The sample may end in the middle of a character
+        Err(e) if e.error_len().is_none() => (Some("utf-8"), false),
+        Err(_) => (None, false),
+    }
+}
+
+fn inspect(path: &Path) -> Result<FileInspection, String> {
+    let mut file =
+        File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let meta = file
+        .metadata()
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    if meta.is_dir() {
+        return Err(format!("{} is a directory", path.display()));
+    }
+    let size = meta.len();
+    let mut sample = Vec::with_capacity(SNIFF_LEN);
+    file.by_ref()
+        .take(SNIFF_LEN as u64)
+        .read_to_end(&mut sample)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    let detected = infer::get(&sample);
+    let (encoding, bom) = guess_encoding(&sample);
+    let utf16 = matches!(encoding, Some("utf-16le" | "utf-16be"));
+    let binary = !utf16
+        && (looks_binary(&sample)
+            || detected.is_some_and(|kind| kind.matcher_type() != infer::MatcherType::Text));
+
+    let mime = detected
+        .map(|kind| kind.mime_type().to_string())
+        .or_else(|| {
+            mime_guess::from_path(path)
+                .first()
+                .map(|mime| mime.to_string())
+        })
+        .unwrap_or_else(|| {
+            if binary {
+                "application/octet-stream".to_string()
+            } else {
+                "text/plain".to_string()
+            }
+        });
+    // SVG is text but is best previewed as an image
+    let preview = if mime.starts_with("image/") {
+        PreviewKind::Image
+    } else if binary {
+        PreviewKind::Binary
+    } else {
+        PreviewKind::Text
+    };
+
+    Ok(FileInspection {
+        mime,
+        binary,
+        size,
+        encoding: (!binary).then_some(encoding).flatten().map(str::to_string),
+        bom,
+        preview,
+    })
+}
+
+/// Detect the type of the file at `path`
+#[tauri::command]
+pub async fn inspect_file(path: String) -> Result<FileInspection, String> {
+    tauri::async_runtime::spawn_blocking(move || inspect(Path::new(&path)))
+        .await
+        .map_err(|e| format!("Inspection failed: {}", e))?
+}
@@ -0,0 +1,181 @@
+//! Symlinks
+//!
+//! `create_symlink` links `dest` to `src`, and `resolve_link` reports where a
+//! link points. Windows only allows symlinks with Developer Mode on (or as
+//! administrator); without it a directory link falls back to a junction,
+//! which needs no privilege, and a file link fails with an error saying how
+//! to enable Developer Mode.
+
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkKind {
+    Symlink,
+    /// Windows directory junction
+    Junction,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatedLink {
+    path: String,
+    kind: LinkKind,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkInfo {
+    /// `None` when the path isn't a link
+    kind: Option<LinkKind>,
+    /// What the link contains, possibly relative to its directory
+    target: Option<String>,
+    /// The fully resolved path, if everything along the way exists
+    resolved: Option<String>,
+    /// The link points at something that exists
+    exists: bool,
+    /// Whether Windows allows unprivileged symlinks; always true elsewhere
+    developer_mode: Option<bool>,
+}
+
+/// `target` as seen from a link at `link`
+fn from_link(link: &Path, target: &Path) -> PathBuf {
+    match link.parent() {
+        Some(dir) if target.is_relative() => dir.join(target),
+        _ => target.to_path_buf(),
+    }
+}
+
+#[cfg(unix)]
+pub(crate) fn link(src: &Path, dest: &Path) -> Result<LinkKind, String> {
+    std::os::unix::fs::symlink(src, dest).map_err(|e| {
+        format!(
+            "Failed to link {} to {}: {}",
+            dest.display(),
+            src.display(),
+            e
+        )
+    })?;
+    Ok(LinkKind::Symlink)
+}
+
+/// Whether Developer Mode is on, allowing symlinks without elevation
+#[cfg(windows)]
+fn developer_mode() -> Option<bool> {
+    let output = std::process::Command::new("reg")
+        .args([
+            "query",
+            r"HKLM\SOFTWARE\Microsoft\Windows\CurrentVersion\AppModelUnlock",
+            "/v",
+            "AllowDevelopmentWithoutDevLicense",
+        ])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return Some(false);
+    }
+    Some(String::from_utf8_lossy(&output.stdout).contains("0x1"))
+}
+
+#[cfg(not(windows))]
+fn developer_mode() -> Option<bool> {
+    Some(true)
+}
+
+#[cfg(windows)]
+pub(crate) fn link(src: &Path, dest: &Path) -> Result<LinkKind, String> {
+    use std::os::windows::fs::{symlink_dir, symlink_file};
+    // ERROR_PRIVILEGE_NOT_HELD: no Developer Mode and not elevated
+    const PRIVILEGE_NOT_HELD: i32 = 1314;
+
+    let target = from_link(dest, src);
+    let is_dir = target.is_dir();
+    let created = if is_dir {
+        symlink_dir(src, dest)
+    } else {
+        symlink_file(src, dest)
+    };
+    match created {
+        Ok(()) => Ok(LinkKind::Symlink),
+        Err(e) if e.raw_os_error() == Some(PRIVILEGE_NOT_HELD) && is_dir => {
+            // Junctions need an absolute target
+            let target = fs::canonicalize(&target)
+                .map_err(|e| format!("Failed to resolve {}: {}", target.display(), e))?;
+            junction::create(&target, dest).map_err(|e| {
+                format!(
+                    "Failed to create a junction from {} to {}: {}",
+                    dest.display(),
+                    target.display(),
+                    e
+                )
+            })?;
+            Ok(LinkKind::Junction)
+        }
+        Err(e) if e.raw_os_error() == Some(PRIVILEGE_NOT_HELD) => Err(format!(
+            "Windows only allows symlinks to files with Developer Mode on \
+             (Settings > System > For developers) or as administrator: {}",
+            e
+        )),
+        Err(e) => Err(format!(
+            "Failed to link {} to {}: {}",
+            dest.display(),
+            src.display(),
+            e
+        )),
+    }
+}
+
+#[cfg(windows)]
+fn junction_target(path: &Path) -> Option<PathBuf> {
+    junction::exists(path)
+        .unwrap_or(false)
+        .then(|| junction::get_target(path).ok())
+        .flatten()
+}
+
+#[cfg(not(windows))]
+fn junction_target(_path: &Path) -> Option<PathBuf> {
+    None
+}
+
+/// Create a link at `dest` pointing to `src`
+#[tauri::command]
+pub async fn create_symlink(src: String, dest: String) -> Result<CreatedLink, String> {
+    let dest_path = Path::new(&dest);
+    if dest_path.symlink_metadata().is_ok() {
+        return Err(format!("{} already exists", dest));
+    }
+    let kind = link(Path::new(&src), dest_path)?;
+    Ok(CreatedLink { path: dest, kind })
+}
+
+/// Where the link at `path` points
+#[tauri::command]
+pub async fn resolve_link(path: String) -> Result<LinkInfo, String> {
+    let path = Path::new(&path);
+    let meta = fs::symlink_metadata(path)
+        .map_err(|e| format!("Failed to stat {}: {}", path.display(), e))?;
+
+    // Junctions count as symlinks to the standard library, so check first
+    let (kind, target) = if let Some(target) = junction_target(path) {
+        (Some(LinkKind::Junction), Some(target))
+    } else if meta.is_symlink() {
+        (Some(LinkKind::Symlink), fs::read_link(path).ok())
+    } else {
+        (None, None)
+    };
+
+    Ok(LinkInfo {
+        kind,
+        exists: target
+            .as_ref()
+            .is_some_and(|target| from_link(path, target).exists()),
+        target: target.map(|target| target.to_string_lossy().into_owned()),
+        resolved: fs::canonicalize(path)
+            .ok()
+            .map(|resolved| resolved.to_string_lossy().into_owned()),
+        developer_mode: developer_mode(),
+    })
+}
@@ -0,0 +1,26 @@
+//! Files module - native filesystem operations
+//!
+//! The fs plugin covers plain reads and writes; these commands handle what
+//! it can't do well from TypeScript, such as watching directories for
+//! changes without polling, following a log file as it grows or reading one
+//! too large to hold in memory.
+
+pub mod archive;
+pub mod atomic;
+pub mod copy;
+pub mod diff;
+pub mod download;
+pub mod hash;
+pub mod inspect;
+pub mod link;
+pub mod open;
+pub mod size;
+pub mod stat;
+pub mod stream;
+pub mod symbols;
+pub mod tail;
+pub mod temp;
+pub mod text;
+pub mod trash;
+pub mod tree;
+pub mod watch;
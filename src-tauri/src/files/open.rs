@@ -0,0 +1,119 @@
+//! Handing paths to the OS
+//!
+//! `reveal_path` shows a file in Finder, Explorer or the desktop's file
+//! manager with the file selected, and `open_path` opens it in its default
+//! app, so the board and file views hand off the way each platform expects.
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+
+/// Start `command` without waiting for it, reaping it in the background
+pub(crate) fn launch(mut command: Command) -> Result<(), String> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    thread::spawn(move || child.wait());
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn reveal(path: &Path) -> Result<(), String> {
+    let mut command = Command::new("open");
+    command.arg("-R").arg(path);
+    launch(command)
+}
+
+#[cfg(windows)]
+fn reveal(path: &Path) -> Result<(), String> {
+    use std::os::windows::process::CommandExt;
+    // Explorer parses its own command line and wants the path quoted after
+    // the comma, which the standard quoting wouldn't do
+    let mut command = Command::new("explorer");
+    command.raw_arg(format!("/select,\"{}\"", path.display()));
+    launch(command)
+}
+
+/// Ask the file manager over D-Bus to select the file, falling back to
+/// opening its folder where no file manager implements the interface
+#[cfg(not(any(target_os = "macos", windows)))]
+fn reveal(path: &Path) -> Result<(), String> {
+    let uri =
+        tauri::Url::from_file_path(path).map_err(|_| format!("Can't reveal {}", path.display()))?;
+    let shown = Command::new("dbus-send")
+        .args([
+            "--session",
+            "--print-reply",
+            "--dest=org.freedesktop.FileManager1",
+            "--type=method_call",
+            "/org/freedesktop/FileManager1",
+            "org.freedesktop.FileManager1.ShowItems",
+        ])
+        .arg(format!("array:string:{}", uri))
+        .arg("string:")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success());
+    if shown {
+        return Ok(());
+    }
+    let folder = if path.is_dir() {
+        path
+    } else {
+        path.parent().unwrap_or(path)
+    };
+    open(folder)
+}
+
+#[cfg(target_os = "macos")]
+fn open(path: &Path) -> Result<(), String> {
+    let mut command = Command::new("open");
+    command.arg(path);
+    launch(command)
+}
+
+#[cfg(windows)]
+fn open(path: &Path) -> Result<(), String> {
+    // Explorer opens a file with its default app, and unlike `start` needs
+    // no shell to interpret the path
+    let mut command = Command::new("explorer");
+    command.arg(path);
+    launch(command)
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn open(path: &Path) -> Result<(), String> {
+    let mut command = Command::new("xdg-open");
+    command.arg(path);
+    launch(command)
+}
+
+/// `path` made absolute, which Explorer and D-Bus both need
+fn absolute(path: &str) -> Result<PathBuf, String> {
+    let path =
+        std::path::absolute(path).map_err(|e| format!("Failed to resolve {}: {}", path, e))?;
+    if !path.exists() {
+        return Err(format!("{} does not exist", path.display()));
+    }
+    Ok(path)
+}
+
+/// Show `path` in the platform's file manager, selected
+#[tauri::command]
+pub async fn reveal_path(path: String) -> Result<(), String> {
+    let path = absolute(&path)?;
+    tauri::async_runtime::spawn_blocking(move || reveal(&path))
+        .await
+        .map_err(|e| format!("Reveal failed: {}", e))?
+}
+
+/// Open `path` with its default app
+#[tauri::command]
+pub async fn open_path(path: String) -> Result<(), String> {
+    open(&absolute(&path)?)
+}
@@ -0,0 +1,140 @@
+//! Directory size
+//!
+//! `dir_size` walks a directory on a background thread, emitting
+//! `dir-size-progress` with the running totals a few times a second and
+//! `dir-size-done` with the result, so the cleanup UI can show a growing
+//! number for a large worktree and cancel it. Symlinks aren't followed.
+
+use crate::git::disk_usage::allocated_size;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, State};
+use walkdir::WalkDir;
+
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Running size computations
+pub struct DirSizeState {
+    walks: Mutex<HashMap<u32, Arc<AtomicBool>>>,
+    next_id: AtomicU32,
+}
+
+impl Default for DirSizeState {
+    fn default() -> Self {
+        Self {
+            walks: Mutex::new(HashMap::new()),
+            next_id: AtomicU32::new(1),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DirSize {
+    /// Sum of file lengths, what a copy would need
+    bytes: u64,
+    /// Disk space in use on Unix, the same as `bytes` elsewhere
+    allocated_bytes: u64,
+    files: u64,
+    dirs: u64,
+    /// Entries that couldn't be read, usually for lack of permission
+    errors: u64,
+}
+
+/// Event payload for running totals
+#[derive(Clone, Serialize)]
+struct DirSizeProgressEvent {
+    id: u32,
+    #[serde(flatten)]
+    size: DirSize,
+}
+
+/// Event payload for a finished or cancelled computation
+#[derive(Clone, Serialize)]
+struct DirSizeDoneEvent {
+    id: u32,
+    path: String,
+    #[serde(flatten)]
+    size: DirSize,
+    cancelled: bool,
+}
+
+fn measure(app: &AppHandle, id: u32, path: &Path, cancelled: &AtomicBool) -> DirSize {
+    let mut size = DirSize::default();
+    let mut reported = Instant::now();
+    for entry in WalkDir::new(path) {
+        if cancelled.load(Ordering::SeqCst) {
+            break;
+        }
+        let Ok(meta) = entry.and_then(|entry| entry.metadata()) else {
+            size.errors += 1;
+            continue;
+        };
+        if meta.is_dir() {
+            size.dirs += 1;
+        } else if meta.is_file() {
+            size.files += 1;
+            size.bytes += meta.len();
+            size.allocated_bytes += allocated_size(&meta);
+        }
+
+        if reported.elapsed() >= PROGRESS_INTERVAL {
+            reported = Instant::now();
+            let _ = app.emit(
+                "dir-size-progress",
+                DirSizeProgressEvent {
+                    id,
+                    size: size.clone(),
+                },
+            );
+        }
+    }
+    size
+}
+
+/// Start measuring `path`, returning the ID that tags its events
+#[tauri::command]
+pub async fn dir_size(
+    app: AppHandle,
+    state: State<'_, DirSizeState>,
+    path: String,
+) -> Result<u32, String> {
+    if !Path::new(&path).is_dir() {
+        return Err(format!("{} is not a directory", path));
+    }
+
+    let id = state.next_id.fetch_add(1, Ordering::SeqCst);
+    let cancelled = Arc::new(AtomicBool::new(false));
+    state.walks.lock().insert(id, cancelled.clone());
+
+    thread::spawn(move || {
+        let size = measure(&app, id, Path::new(&path), &cancelled);
+        app.state::<DirSizeState>().walks.lock().remove(&id);
+        let _ = app.emit(
+            "dir-size-done",
+            DirSizeDoneEvent {
+                id,
+                path,
+                size,
+                cancelled: cancelled.load(Ordering::SeqCst),
+            },
+        );
+    });
+
+    Ok(id)
+}
+
+/// Stop measuring; `dir-size-done` still follows with the partial totals
+#[tauri::command]
+pub async fn cancel_dir_size(state: State<'_, DirSizeState>, id: u32) -> Result<(), String> {
+    if let Some(cancelled) = state.walks.lock().get(&id) {
+        cancelled.store(true, Ordering::SeqCst);
+    }
+    Ok(())
+}
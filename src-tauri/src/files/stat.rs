@@ -0,0 +1,213 @@
+//! Extended file metadata and permissions
+//!
+//! `stat_extended` reports what is needed to explain a "permission denied"
+//! in a worktree - mode bits, owner, timestamps, a symlink's target and
+//! extended attributes such as macOS quarantine flags - and
+//! `set_permissions` fixes it, typically a script missing its executable
+//! bit with `+x`. Paths aren't followed, so a symlink describes itself.
+
+use super::tree::EntryKind;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, Metadata};
+use std::path::Path;
+use std::time::SystemTime;
+
+/// Longest extended attribute value returned as text
+const MAX_XATTR_VALUE: usize = 256;
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Xattr {
+    name: String,
+    size: usize,
+    /// The value when it is short UTF-8 text
+    value: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Owner {
+    uid: u32,
+    gid: u32,
+    user: Option<String>,
+    group: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtendedStat {
+    kind: EntryKind,
+    size: u64,
+    /// Permission bits such as `0o755`, Unix only
+    mode: Option<u32>,
+    /// `rwxr-xr-x`, Unix only
+    permissions: Option<String>,
+    readonly: bool,
+    /// Unix only
+    owner: Option<Owner>,
+    modified: Option<String>,
+    accessed: Option<String>,
+    /// Birth time, where the filesystem records it
+    created: Option<String>,
+    /// Last metadata change, Unix only
+    changed: Option<String>,
+    symlink_target: Option<String>,
+    /// Unix only
+    xattrs: Vec<Xattr>,
+}
+
+/// New permission bits: a number such as `493` (`0o755`), an octal string
+/// such as `"755"`, or `"+x"` / `"-x"` to add or drop the executable bits
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub enum ModeChange {
+    Bits(u32),
+    Text(String),
+}
+
+fn timestamp(time: std::io::Result<SystemTime>) -> Option<String> {
+    let time: chrono::DateTime<chrono::Utc> = time.ok()?.into();
+    Some(time.to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+}
+
+fn kind(meta: &Metadata) -> EntryKind {
+    let kind = meta.file_type();
+    if kind.is_symlink() {
+        EntryKind::Symlink
+    } else if kind.is_dir() {
+        EntryKind::Dir
+    } else if kind.is_file() {
+        EntryKind::File
+    } else {
+        EntryKind::Other
+    }
+}
+
+/// `rwxr-xr-x` for the lower nine bits of `mode`
+#[cfg_attr(not(unix), allow(dead_code))]
+fn permission_string(mode: u32) -> String {
+    (0..9)
+        .map(|bit| {
+            let set = mode & (0o400 >> bit) != 0;
+            match (set, bit % 3) {
+                (false, _) => '-',
+                (true, 0) => 'r',
+                (true, 1) => 'w',
+                (true, _) => 'x',
+            }
+        })
+        .collect()
+}
+
+#[cfg(unix)]
+fn unix_details(path: &Path, meta: &Metadata, stat: &mut ExtendedStat) {
+    use std::os::unix::fs::MetadataExt;
+    let mode = meta.mode() & 0o7777;
+    stat.mode = Some(mode);
+    stat.permissions = Some(permission_string(mode));
+    stat.owner = Some(Owner {
+        uid: meta.uid(),
+        gid: meta.gid(),
+        user: uzers::get_user_by_uid(meta.uid())
+            .map(|user| user.name().to_string_lossy().into_owned()),
+        group: uzers::get_group_by_gid(meta.gid())
+            .map(|group| group.name().to_string_lossy().into_owned()),
+    });
+    stat.changed = chrono::DateTime::from_timestamp(meta.ctime(), meta.ctime_nsec() as u32)
+        .map(|time| time.to_rfc3339_opts(chrono::SecondsFormat::Secs, true));
+
+    // Not every filesystem supports extended attributes
+    if let Ok(names) = xattr::list(path) {
+        for name in names {
+            let value = xattr::get(path, &name).ok().flatten().unwrap_or_default();
+            stat.xattrs.push(Xattr {
+                name: name.to_string_lossy().into_owned(),
+                size: value.len(),
+                value: (value.len() <= MAX_XATTR_VALUE)
+                    .then(|| String::from_utf8(value).ok())
+                    .flatten(),
+            });
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn unix_details(_path: &Path, _meta: &Metadata, _stat: &mut ExtendedStat) {}
+
+fn stat(path: &Path) -> Result<ExtendedStat, String> {
+    let meta = fs::symlink_metadata(path)
+        .map_err(|e| format!("Failed to stat {}: {}", path.display(), e))?;
+    let mut stat = ExtendedStat {
+        kind: kind(&meta),
+        size: meta.len(),
+        mode: None,
+        permissions: None,
+        readonly: meta.permissions().readonly(),
+        owner: None,
+        modified: timestamp(meta.modified()),
+        accessed: timestamp(meta.accessed()),
+        created: timestamp(meta.created()),
+        changed: None,
+        symlink_target: meta
+            .is_symlink()
+            .then(|| fs::read_link(path).ok())
+            .flatten()
+            .map(|target| target.to_string_lossy().into_owned()),
+        xattrs: Vec::new(),
+    };
+    unix_details(path, &meta, &mut stat);
+    Ok(stat)
+}
+
+/// The permission bits `change` produces from `current`
+fn apply(current: u32, change: &ModeChange) -> Result<u32, String> {
+    match change {
+        ModeChange::Bits(mode) => Ok(*mode),
+        // Executable wherever readable, as `chmod +x` does with the default umask
+        ModeChange::Text(text) if text == "+x" => Ok(current | ((current & 0o444) >> 2)),
+        ModeChange::Text(text) if text == "-x" => Ok(current & !0o111),
+        ModeChange::Text(text) => u32::from_str_radix(text.trim_start_matches("0o"), 8)
+            .map_err(|_| format!("Invalid mode '{}'", text)),
+    }
+}
+
+#[cfg(unix)]
+fn chmod(path: &Path, change: &ModeChange) -> Result<u32, String> {
+    use std::os::unix::fs::PermissionsExt;
+    let meta =
+        fs::metadata(path).map_err(|e| format!("Failed to stat {}: {}", path.display(), e))?;
+    let mode = apply(meta.permissions().mode() & 0o7777, change)?;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))
+        .map_err(|e| format!("Failed to change permissions of {}: {}", path.display(), e))?;
+    Ok(mode)
+}
+
+/// Only the write bit means anything here: without it the file is read-only
+#[cfg(not(unix))]
+fn chmod(path: &Path, change: &ModeChange) -> Result<u32, String> {
+    let meta =
+        fs::metadata(path).map_err(|e| format!("Failed to stat {}: {}", path.display(), e))?;
+    let current = if meta.permissions().readonly() {
+        0o444
+    } else {
+        0o644
+    };
+    let mode = apply(current, change)?;
+    let mut permissions = meta.permissions();
+    permissions.set_readonly(mode & 0o200 == 0);
+    fs::set_permissions(path, permissions)
+        .map_err(|e| format!("Failed to change permissions of {}: {}", path.display(), e))?;
+    Ok(mode)
+}
+
+/// Everything the filesystem records about `path`
+#[tauri::command]
+pub async fn stat_extended(path: String) -> Result<ExtendedStat, String> {
+    stat(Path::new(&path))
+}
+
+/// Change the permission bits of `path`, returning the new mode
+#[tauri::command]
+pub async fn set_permissions(path: String, mode: ModeChange) -> Result<u32, String> {
+    chmod(Path::new(&path), &mode)
+}
@@ -0,0 +1,274 @@
+//! Chunked reads of large files
+//!
+//! `read_file_stream` sends a file as a sequence of `file-stream-chunk`
+//! events instead of one string, so a multi-hundred-megabyte log never has
+//! to exist in one piece on either side of the bridge. The reader stays at
+//! most a few chunks ahead of the frontend, which acknowledges each chunk it
+//! has handled with `ack_file_stream`; a stream that is never acknowledged
+//! gives up. Chunks are UTF-8 text and never split a character.
+
+use parking_lot::{Condvar, Mutex};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// Chunk size used when the caller sets none
+const DEFAULT_CHUNK_SIZE: usize = 1024 * 1024;
+
+const MIN_CHUNK_SIZE: usize = 4 * 1024;
+
+const MAX_CHUNK_SIZE: usize = 16 * 1024 * 1024;
+
+/// Chunks sent ahead of the last acknowledged one
+const WINDOW: u64 = 4;
+
+/// How long to wait for an acknowledgement before giving up
+const ACK_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Running streams
+pub struct FileStreamState {
+    streams: Mutex<HashMap<u32, Arc<Flow>>>,
+    next_id: AtomicU32,
+}
+
+impl Default for FileStreamState {
+    fn default() -> Self {
+        Self {
+            streams: Mutex::new(HashMap::new()),
+            next_id: AtomicU32::new(1),
+        }
+    }
+}
+
+/// Flow control between the reader and the frontend
+#[derive(Default)]
+struct Flow {
+    progress: Mutex<Progress>,
+    changed: Condvar,
+}
+
+#[derive(Default)]
+struct Progress {
+    acked: u64,
+    cancelled: bool,
+}
+
+/// Why a stream stopped before the end of the file
+enum Stop {
+    Cancelled,
+    TimedOut,
+}
+
+impl Flow {
+    /// Wait until chunk `seq` may be sent
+    fn wait_turn(&self, seq: u64) -> Result<(), Stop> {
+        let mut progress = self.progress.lock();
+        while !progress.cancelled && seq > progress.acked + WINDOW {
+            if self
+                .changed
+                .wait_for(&mut progress, ACK_TIMEOUT)
+                .timed_out()
+            {
+                return Err(Stop::TimedOut);
+            }
+        }
+        if progress.cancelled {
+            return Err(Stop::Cancelled);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct FileStreamStart {
+    id: u32,
+    /// Size of the file in bytes when the stream started
+    size: u64,
+}
+
+/// Event payload for one chunk
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ChunkEvent {
+    stream_id: u32,
+    /// 1-based, to pass to `ack_file_stream`
+    seq: u64,
+    /// Byte offset of the chunk in the file
+    offset: u64,
+    data: String,
+}
+
+/// Event payload sent after the last chunk or when the stream stops early
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StreamEndEvent {
+    stream_id: u32,
+    bytes: u64,
+    chunks: u64,
+    cancelled: bool,
+    error: Option<String>,
+}
+
+/// Length of `bytes` without an incomplete character at the end
+fn complete_len(bytes: &[u8]) -> usize {
+    // Find the last lead byte within the longest encoding of a character
+    for back in 1..=bytes.len().min(4) {
+        let byte = bytes[bytes.len() - back];
+        if byte & 0b1100_0000 == 0b1000_0000 {
+            continue;
+        }
+        let width = match byte {
+            0x00..=0x7f => 1,
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            _ => 4,
+        };
+        return if back < width {
+            bytes.len() - back
+        } else {
+            bytes.len()
+        };
+    }
+    bytes.len()
+}
+
+fn stream(
+    app: &AppHandle,
+    id: u32,
+    mut file: File,
+    chunk_size: usize,
+    flow: &Flow,
+) -> StreamEndEvent {
+    let mut end = StreamEndEvent {
+        stream_id: id,
+        bytes: 0,
+        chunks: 0,
+        cancelled: false,
+        error: None,
+    };
+    let mut buf = Vec::with_capacity(chunk_size + 4);
+    let mut offset = 0;
+
+    loop {
+        // Top up after the bytes carried over from the last chunk
+        let wanted = chunk_size.saturating_sub(buf.len()).max(1) as u64;
+        let read = match (&mut file).take(wanted).read_to_end(&mut buf) {
+            Ok(read) => read,
+            Err(e) => {
+                end.error = Some(format!("Read failed: {}", e));
+                return end;
+            }
+        };
+        if buf.is_empty() {
+            return end;
+        }
+        let at_end = read == 0;
+        let cut = if at_end {
+            buf.len()
+        } else {
+            complete_len(&buf)
+        };
+        if cut == 0 {
+            continue;
+        }
+
+        let seq = end.chunks + 1;
+        match flow.wait_turn(seq) {
+            Ok(()) => {}
+            Err(Stop::Cancelled) => {
+                end.cancelled = true;
+                return end;
+            }
+            Err(Stop::TimedOut) => {
+                end.error = Some("Chunks were not acknowledged".to_string());
+                return end;
+            }
+        }
+
+        let rest = buf.split_off(cut);
+        let data = String::from_utf8_lossy(&buf).into_owned();
+        let _ = app.emit(
+            "file-stream-chunk",
+            ChunkEvent {
+                stream_id: id,
+                seq,
+                offset,
+                data,
+            },
+        );
+        offset += cut as u64;
+        end.bytes = offset;
+        end.chunks = seq;
+        buf = rest;
+    }
+}
+
+/// Start sending `path` in chunks of `chunk_size` bytes
+#[tauri::command]
+pub async fn read_file_stream(
+    app: AppHandle,
+    state: State<'_, FileStreamState>,
+    path: String,
+    chunk_size: Option<usize>,
+) -> Result<FileStreamStart, String> {
+    let path = PathBuf::from(&path);
+    let file =
+        File::open(&path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let size = file
+        .metadata()
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+        .len();
+    let chunk_size = chunk_size
+        .unwrap_or(DEFAULT_CHUNK_SIZE)
+        .clamp(MIN_CHUNK_SIZE, MAX_CHUNK_SIZE);
+
+    let id = state.next_id.fetch_add(1, Ordering::SeqCst);
+    let flow = Arc::new(Flow::default());
+    state.streams.lock().insert(id, flow.clone());
+
+    thread::spawn(move || {
+        let end = stream(&app, id, file, chunk_size, &flow);
+        app.state::<FileStreamState>().streams.lock().remove(&id);
+        let _ = app.emit("file-stream-end", end);
+    });
+
+    Ok(FileStreamStart { id, size })
+}
+
+/// Report that chunk `seq` of a stream has been handled
+#[tauri::command]
+pub async fn ack_file_stream(
+    state: State<'_, FileStreamState>,
+    id: u32,
+    seq: u64,
+) -> Result<(), String> {
+    // The last chunks are acknowledged after the stream has already ended
+    let Some(flow) = state.streams.lock().get(&id).cloned() else {
+        return Ok(());
+    };
+    let mut progress = flow.progress.lock();
+    progress.acked = progress.acked.max(seq);
+    flow.changed.notify_all();
+    Ok(())
+}
+
+/// Stop a stream; `file-stream-end` still follows
+#[tauri::command]
+pub async fn cancel_file_stream(state: State<'_, FileStreamState>, id: u32) -> Result<(), String> {
+    let flow = state
+        .streams
+        .lock()
+        .get(&id)
+        .cloned()
+        .ok_or_else(|| format!("File stream {} not found", id))?;
+    flow.progress.lock().cancelled = true;
+    flow.changed.notify_all();
+    Ok(())
+}
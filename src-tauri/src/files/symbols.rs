@@ -0,0 +1,251 @@
+//! Symbol outlines
+//!
+//! `extract_symbols` parses a source file with its tree-sitter grammar and
+//! runs the grammar's own tags query, the one GitHub's code navigation uses,
+//! to find functions, classes and the like. The file preview shows the
+//! outline and the agent-context builder quotes a symbol by its range, with
+//! no language server involved. Rust, JavaScript, TypeScript, Python and Go
+//! are supported.
+
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use tree_sitter::{Language, Node, Parser, Query, QueryCursor, StreamingIterator};
+
+/// Larger files are generated more often than not, and slow to parse
+const MAX_FILE_SIZE: u64 = 2 * 1024 * 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SymbolKind {
+    Function,
+    Method,
+    Class,
+    Interface,
+    Module,
+    Type,
+    Constant,
+    Macro,
+}
+
+impl SymbolKind {
+    /// The kind a `@definition.<kind>` capture names
+    fn from_capture(name: &str) -> Option<Self> {
+        match name.strip_prefix("definition.")? {
+            "function" => Some(Self::Function),
+            "method" => Some(Self::Method),
+            "class" => Some(Self::Class),
+            "interface" => Some(Self::Interface),
+            "module" => Some(Self::Module),
+            "type" => Some(Self::Type),
+            "constant" => Some(Self::Constant),
+            "macro" => Some(Self::Macro),
+            _ => None,
+        }
+    }
+}
+
+/// A span of the file, with 1-based lines and character columns
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceRange {
+    start_line: usize,
+    start_column: usize,
+    end_line: usize,
+    end_column: usize,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Symbol {
+    name: String,
+    kind: SymbolKind,
+    /// The whole definition, body included
+    range: SourceRange,
+    /// Just the name, for highlighting or placing the cursor
+    name_range: SourceRange,
+    /// Definitions nested inside this one, such as a class's methods
+    children: Vec<Symbol>,
+    #[serde(skip)]
+    bytes: (usize, usize),
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SymbolOutline {
+    language: &'static str,
+    symbols: Vec<Symbol>,
+}
+
+/// The grammar and tags query for `path`, by extension
+fn language(path: &Path) -> Option<(&'static str, Language, String)> {
+    let extension = path.extension()?.to_str()?.to_lowercase();
+    let (name, language, query) = match extension.as_str() {
+        "rs" => (
+            "rust",
+            tree_sitter_rust::LANGUAGE.into(),
+            tree_sitter_rust::TAGS_QUERY.to_string(),
+        ),
+        "js" | "mjs" | "cjs" | "jsx" => (
+            "javascript",
+            tree_sitter_javascript::LANGUAGE.into(),
+            tree_sitter_javascript::TAGS_QUERY.to_string(),
+        ),
+        // TypeScript's tags only cover what it adds to JavaScript
+        "ts" | "mts" | "cts" => (
+            "typescript",
+            tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            format!(
+                "{}\n{}",
+                tree_sitter_javascript::TAGS_QUERY,
+                tree_sitter_typescript::TAGS_QUERY
+            ),
+        ),
+        "tsx" => (
+            "tsx",
+            tree_sitter_typescript::LANGUAGE_TSX.into(),
+            format!(
+                "{}\n{}",
+                tree_sitter_javascript::TAGS_QUERY,
+                tree_sitter_typescript::TAGS_QUERY
+            ),
+        ),
+        "py" | "pyi" => (
+            "python",
+            tree_sitter_python::LANGUAGE.into(),
+            tree_sitter_python::TAGS_QUERY.to_string(),
+        ),
+        "go" => (
+            "go",
+            tree_sitter_go::LANGUAGE.into(),
+            tree_sitter_go::TAGS_QUERY.to_string(),
+        ),
+        _ => return None,
+    };
+    Some((name, language, query))
+}
+
+/// 1-based line and character column of a byte offset
+fn position(source: &str, byte: usize, row: usize, byte_column: usize) -> (usize, usize) {
+    let line_start = byte - byte_column;
+    let column = source
+        .get(line_start..byte)
+        .map_or(byte_column, |text| text.chars().count());
+    (row + 1, column + 1)
+}
+
+fn range(source: &str, node: Node) -> SourceRange {
+    let (start, end) = (node.start_position(), node.end_position());
+    let (start_line, start_column) = position(source, node.start_byte(), start.row, start.column);
+    let (end_line, end_column) = position(source, node.end_byte(), end.row, end.column);
+    SourceRange {
+        start_line,
+        start_column,
+        end_line,
+        end_column,
+    }
+}
+
+/// Nest each symbol under the closest one that encloses it
+fn nest(mut flat: Vec<Symbol>) -> Vec<Symbol> {
+    // Outer definitions first where two start together
+    flat.sort_by_key(|symbol| (symbol.bytes.0, std::cmp::Reverse(symbol.bytes.1)));
+
+    fn close(stack: &mut Vec<Symbol>, roots: &mut Vec<Symbol>) {
+        let symbol = stack.pop().expect("stack is not empty");
+        match stack.last_mut() {
+            Some(parent) => parent.children.push(symbol),
+            None => roots.push(symbol),
+        }
+    }
+
+    let mut roots = Vec::new();
+    let mut stack: Vec<Symbol> = Vec::new();
+    for symbol in flat {
+        while stack
+            .last()
+            .is_some_and(|open| open.bytes.1 <= symbol.bytes.0 || open.bytes.1 < symbol.bytes.1)
+        {
+            close(&mut stack, &mut roots);
+        }
+        stack.push(symbol);
+    }
+    while !stack.is_empty() {
+        close(&mut stack, &mut roots);
+    }
+    roots
+}
+
+fn extract(path: &Path) -> Result<SymbolOutline, String> {
+    let (name, language, query) =
+        language(path).ok_or_else(|| format!("No symbol support for {}", path.display()))?;
+    let meta =
+        fs::metadata(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    if meta.len() > MAX_FILE_SIZE {
+        return Err(format!("{} is too large to outline", path.display()));
+    }
+    let data = fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let source = String::from_utf8_lossy(&data);
+
+    let mut parser = Parser::new();
+    parser
+        .set_language(&language)
+        .map_err(|e| format!("Failed to load the {} grammar: {}", name, e))?;
+    let tree = parser
+        .parse(source.as_bytes(), None)
+        .ok_or_else(|| format!("Failed to parse {}", path.display()))?;
+    let query = Query::new(&language, &query)
+        .map_err(|e| format!("Failed to load the {} tags query: {}", name, e))?;
+    let captures = query.capture_names();
+
+    let mut flat: Vec<Symbol> = Vec::new();
+    let mut seen = HashSet::new();
+    let mut cursor = QueryCursor::new();
+    let mut matches = cursor.matches(&query, tree.root_node(), source.as_bytes());
+    while let Some(found) = matches.next() {
+        let mut definition = None;
+        let mut name_node = None;
+        for capture in found.captures() {
+            let capture_name = captures[capture.index as usize];
+            if capture_name == "name" {
+                name_node = Some(capture.node);
+            } else if let Some(kind) = SymbolKind::from_capture(capture_name) {
+                definition = Some((kind, capture.node));
+            }
+        }
+        let (Some((kind, node)), Some(name_node)) = (definition, name_node) else {
+            continue;
+        };
+        let bytes = (node.start_byte(), node.end_byte());
+        // Patterns with and without a leading doc comment can both match
+        if !seen.insert(bytes) {
+            continue;
+        }
+        let Ok(symbol_name) = name_node.utf8_text(source.as_bytes()) else {
+            continue;
+        };
+        flat.push(Symbol {
+            name: symbol_name.to_string(),
+            kind,
+            range: range(&source, node),
+            name_range: range(&source, name_node),
+            children: Vec::new(),
+            bytes,
+        });
+    }
+
+    Ok(SymbolOutline {
+        language: name,
+        symbols: nest(flat),
+    })
+}
+
+/// Functions, classes and other definitions in the source file at `path`,
+/// nested as they are in the file
+#[tauri::command]
+pub async fn extract_symbols(path: String) -> Result<SymbolOutline, String> {
+    tauri::async_runtime::spawn_blocking(move || extract(Path::new(&path)))
+        .await
+        .map_err(|e| format!("Outlining failed: {}", e))?
+}
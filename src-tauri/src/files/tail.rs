@@ -0,0 +1,286 @@
+//! `tail -F` for log files
+//!
+//! `tail_file` returns the last lines of a file and, when following, polls
+//! it for appended lines and emits them as `file-tail` events. A file that
+//! shrinks was truncated and is read again from the start; a new file at the
+//! same path (log rotation) is picked up once the old one is drained. Both
+//! are announced with `file-tail-reset` so the viewer can mark the break.
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{File, Metadata};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// Lines returned when the caller sets no count, as `tail` does
+const DEFAULT_LINES: usize = 10;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Block size for reading backwards from the end
+const CHUNK: u64 = 64 * 1024;
+
+/// A line longer than this is emitted in pieces
+const MAX_LINE: usize = 1024 * 1024;
+
+/// Followed files
+pub struct TailState {
+    tails: Mutex<HashMap<u32, Arc<AtomicBool>>>,
+    next_id: AtomicU32,
+}
+
+impl Default for TailState {
+    fn default() -> Self {
+        Self {
+            tails: Mutex::new(HashMap::new()),
+            next_id: AtomicU32::new(1),
+        }
+    }
+}
+
+/// Options for `tail_file`
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TailOptions {
+    lines: Option<usize>,
+    /// Keep emitting lines as they are appended
+    #[serde(default)]
+    follow: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TailStart {
+    /// Tags the `file-tail` events when following
+    id: Option<u32>,
+    lines: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResetReason {
+    Truncated,
+    Rotated,
+}
+
+/// Event payload for appended lines
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TailLinesEvent {
+    tail_id: u32,
+    lines: Vec<String>,
+}
+
+/// Event payload sent when following stops
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TailEndedEvent {
+    tail_id: u32,
+    /// Why the file could no longer be read, unless `stop_tail` ended it
+    error: Option<String>,
+}
+
+/// Event payload sent before reading a truncated or replaced file again
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TailResetEvent {
+    tail_id: u32,
+    reason: ResetReason,
+}
+
+/// Identifies the file behind a path, to notice it being replaced
+#[cfg(unix)]
+fn identity(meta: &Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((meta.dev(), meta.ino()))
+}
+
+/// Identifies the file behind a path; without inodes only truncation is seen
+#[cfg(not(unix))]
+fn identity(_meta: &Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+/// The last `count` lines of `file` and the offset where they end
+fn last_lines(file: &mut File, count: usize) -> std::io::Result<(Vec<String>, u64)> {
+    let end = file.seek(SeekFrom::End(0))?;
+    let mut start = end;
+    let mut data = Vec::new();
+    // One newline more than lines wanted, unless the file ends in one
+    while start > 0 && data.iter().filter(|&&b| b == b'\n').count() <= count {
+        let size = CHUNK.min(start);
+        start -= size;
+        let mut chunk = vec![0; size as usize];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(&mut chunk)?;
+        chunk.extend_from_slice(&data);
+        data = chunk;
+    }
+
+    let text = String::from_utf8_lossy(&data);
+    let text = text.strip_suffix('\n').unwrap_or(&text);
+    let mut lines: Vec<String> = text
+        .split('\n')
+        .map(|line| line.trim_end_matches('\r').to_string())
+        .collect();
+    if start > 0 || lines.len() > count {
+        // The first piece may be the tail of a longer line
+        lines.drain(..lines.len().saturating_sub(count));
+    }
+    if data.is_empty() {
+        lines.clear();
+    }
+    Ok((lines, end))
+}
+
+/// Splits appended bytes into complete lines
+#[derive(Default)]
+struct LineBuffer {
+    pending: Vec<u8>,
+}
+
+impl LineBuffer {
+    fn push(&mut self, data: &[u8]) -> Vec<String> {
+        self.pending.extend_from_slice(data);
+        let mut lines = Vec::new();
+        while let Some(end) = self.pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line[..end]);
+            lines.push(line.trim_end_matches('\r').to_string());
+        }
+        if self.pending.len() > MAX_LINE {
+            lines.push(String::from_utf8_lossy(&self.pending).into_owned());
+            self.pending.clear();
+        }
+        lines
+    }
+}
+
+struct Follower {
+    app: AppHandle,
+    id: u32,
+    path: PathBuf,
+    file: File,
+    position: u64,
+    buffer: LineBuffer,
+}
+
+impl Follower {
+    /// Emit whatever was appended since the last read
+    fn drain(&mut self) -> std::io::Result<()> {
+        let mut data = Vec::new();
+        self.file.seek(SeekFrom::Start(self.position))?;
+        self.position += self.file.read_to_end(&mut data)? as u64;
+        let lines = self.buffer.push(&data);
+        if !lines.is_empty() {
+            let _ = self.app.emit(
+                "file-tail",
+                TailLinesEvent {
+                    tail_id: self.id,
+                    lines,
+                },
+            );
+        }
+        Ok(())
+    }
+
+    fn reset(&mut self, reason: ResetReason) {
+        self.position = 0;
+        self.buffer = LineBuffer::default();
+        let _ = self.app.emit(
+            "file-tail-reset",
+            TailResetEvent {
+                tail_id: self.id,
+                reason,
+            },
+        );
+    }
+
+    fn poll(&mut self) -> std::io::Result<()> {
+        let current = self.file.metadata()?;
+        // A missing path means rotation is in progress; keep the old file
+        if let Ok(latest) = std::fs::metadata(&self.path) {
+            if identity(&latest) != identity(&current) {
+                self.drain()?;
+                // Replaced again before we got to it; try on the next poll
+                let Ok(file) = File::open(&self.path) else {
+                    return Ok(());
+                };
+                self.file = file;
+                self.reset(ResetReason::Rotated);
+                return self.drain();
+            }
+        }
+        if current.len() < self.position {
+            self.reset(ResetReason::Truncated);
+        }
+        self.drain()
+    }
+}
+
+/// The last lines of `path`, then with `follow` every line appended to it
+#[tauri::command]
+pub async fn tail_file(
+    app: AppHandle,
+    state: State<'_, TailState>,
+    path: String,
+    options: Option<TailOptions>,
+) -> Result<TailStart, String> {
+    let options = options.unwrap_or_default();
+    let path = PathBuf::from(&path);
+    let mut file =
+        File::open(&path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let (lines, position) = last_lines(&mut file, options.lines.unwrap_or(DEFAULT_LINES))
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    if !options.follow {
+        return Ok(TailStart { id: None, lines });
+    }
+
+    let id = state.next_id.fetch_add(1, Ordering::SeqCst);
+    let stopped = Arc::new(AtomicBool::new(false));
+    state.tails.lock().insert(id, stopped.clone());
+
+    let mut follower = Follower {
+        app,
+        id,
+        path,
+        file,
+        position,
+        buffer: LineBuffer::default(),
+    };
+    thread::spawn(move || {
+        let mut error = None;
+        while !stopped.load(Ordering::SeqCst) {
+            thread::sleep(POLL_INTERVAL);
+            if let Err(e) = follower.poll() {
+                error = Some(format!("Failed to read {}: {}", follower.path.display(), e));
+                break;
+            }
+        }
+        let app = follower.app;
+        app.state::<TailState>().tails.lock().remove(&id);
+        let _ = app.emit("file-tail-ended", TailEndedEvent { tail_id: id, error });
+    });
+
+    Ok(TailStart {
+        id: Some(id),
+        lines,
+    })
+}
+
+/// Stop following a file
+#[tauri::command]
+pub async fn stop_tail(state: State<'_, TailState>, id: u32) -> Result<(), String> {
+    let stopped = state
+        .tails
+        .lock()
+        .remove(&id)
+        .ok_or_else(|| format!("Tail {} not found", id))?;
+    stopped.store(true, Ordering::SeqCst);
+    Ok(())
+}
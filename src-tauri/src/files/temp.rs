@@ -0,0 +1,174 @@
+//! Temporary workspaces
+//!
+//! Scratch directories for throwaway clones, export staging and agent
+//! sandboxes live under one root in the app's cache directory. Each is
+//! tracked while the app runs and removed when it exits; the root is emptied
+//! at startup too, which catches whatever a crash left behind.
+
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager, State};
+
+/// Directory under the app cache dir holding every workspace
+const TEMP_ROOT: &str = "workspaces";
+
+/// Workspaces created by this run of the app
+pub struct TempWorkspaceState {
+    workspaces: Mutex<HashMap<u32, TempWorkspace>>,
+    next_id: AtomicU32,
+}
+
+impl Default for TempWorkspaceState {
+    fn default() -> Self {
+        Self {
+            workspaces: Mutex::new(HashMap::new()),
+            next_id: AtomicU32::new(1),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TempWorkspace {
+    id: u32,
+    label: String,
+    path: String,
+    created_at: String,
+}
+
+fn temp_root(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(app
+        .path()
+        .app_cache_dir()
+        .map_err(|e| e.to_string())?
+        .join(TEMP_ROOT))
+}
+
+/// `label` reduced to characters that are safe in a directory name
+fn dir_name(label: &str) -> String {
+    let name: String = label
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .take(40)
+        .collect();
+    let name = name.trim_matches('-');
+    if name.is_empty() {
+        "workspace".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+/// Empty the temp root, left over from a previous run. The root is moved
+/// aside first so new workspaces aren't caught by the slow delete.
+pub(crate) fn clear_stale(app: &AppHandle) {
+    let Ok(root) = temp_root(app) else {
+        return;
+    };
+    let Some(cache) = root.parent().map(PathBuf::from) else {
+        return;
+    };
+    let stale = format!("{}-stale", TEMP_ROOT);
+    if root.exists() {
+        let nonce = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_millis())
+            .unwrap_or_default();
+        let _ = fs::rename(&root, cache.join(format!("{}-{}", stale, nonce)));
+    }
+
+    thread::spawn(move || {
+        let Ok(entries) = fs::read_dir(&cache) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            if entry.file_name().to_string_lossy().starts_with(&stale) {
+                if let Err(e) = fs::remove_dir_all(entry.path()) {
+                    eprintln!("Failed to clear {}: {}", entry.path().display(), e);
+                }
+            }
+        }
+    });
+}
+
+/// Remove every workspace this run created
+pub(crate) fn remove_all(app: &AppHandle) {
+    let workspaces = std::mem::take(&mut *app.state::<TempWorkspaceState>().workspaces.lock());
+    for workspace in workspaces.into_values() {
+        let _ = fs::remove_dir_all(&workspace.path);
+    }
+}
+
+/// Create an empty scratch directory that is removed when the app exits
+#[tauri::command]
+pub async fn create_temp_workspace(
+    app: AppHandle,
+    state: State<'_, TempWorkspaceState>,
+    label: Option<String>,
+) -> Result<TempWorkspace, String> {
+    let label = label.unwrap_or_default();
+    let id = state.next_id.fetch_add(1, Ordering::SeqCst);
+    let nonce = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis())
+        .unwrap_or_default();
+    let path = temp_root(&app)?.join(format!("{}-{}-{}", dir_name(&label), nonce, id));
+    fs::create_dir_all(&path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+
+    let workspace = TempWorkspace {
+        id,
+        label,
+        path: path.to_string_lossy().into_owned(),
+        created_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+    };
+    state.workspaces.lock().insert(id, workspace.clone());
+    Ok(workspace)
+}
+
+/// Temporary workspaces that still exist, oldest first
+#[tauri::command]
+pub async fn list_temp_workspaces(
+    state: State<'_, TempWorkspaceState>,
+) -> Result<Vec<TempWorkspace>, String> {
+    let mut workspaces: Vec<_> = state.workspaces.lock().values().cloned().collect();
+    workspaces.sort_by_key(|workspace| workspace.id);
+    Ok(workspaces)
+}
+
+/// Delete a temporary workspace before the app exits
+#[tauri::command]
+pub async fn remove_temp_workspace(
+    state: State<'_, TempWorkspaceState>,
+    id: u32,
+) -> Result<(), String> {
+    let workspace = state
+        .workspaces
+        .lock()
+        .remove(&id)
+        .ok_or_else(|| format!("Temporary workspace {} not found", id))?;
+    tauri::async_runtime::spawn_blocking(move || {
+        fs::remove_dir_all(&workspace.path)
+            .or_else(|e| {
+                if e.kind() == std::io::ErrorKind::NotFound {
+                    Ok(())
+                } else {
+                    Err(e)
+                }
+            })
+            .map_err(|e| format!("Failed to remove {}: {}", workspace.path, e))
+    })
+    .await
+    .map_err(|e| format!("Removal failed: {}", e))?
+}
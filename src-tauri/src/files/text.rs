@@ -0,0 +1,295 @@
+//! Text encodings and line endings
+//!
+//! `detect_text_format` reports a text file's encoding, byte order mark,
+//! line endings and whether it ends with a newline, so the app can warn when
+//! an agent on Windows has left a file with mixed endings.
+//! `convert_text_format` rewrites it - atomically, through `write_atomic` -
+//! with whichever of those the caller wants changed. UTF-8 and UTF-16 are
+//! supported; files in other encodings are reported but not converted.
+
+use super::atomic::write_atomic;
+use super::inspect::{guess_encoding, looks_binary};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// Larger files are left alone; they are rarely edited by hand
+const MAX_FILE_SIZE: u64 = 64 * 1024 * 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum TextEncoding {
+    #[serde(rename = "utf-8")]
+    Utf8,
+    #[serde(rename = "utf-16le")]
+    Utf16Le,
+    #[serde(rename = "utf-16be")]
+    Utf16Be,
+}
+
+impl TextEncoding {
+    fn bom(self) -> &'static [u8] {
+        match self {
+            Self::Utf8 => &[0xef, 0xbb, 0xbf],
+            Self::Utf16Le => &[0xff, 0xfe],
+            Self::Utf16Be => &[0xfe, 0xff],
+        }
+    }
+}
+
+/// The line endings a file uses
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LineEndings {
+    Lf,
+    Crlf,
+    /// Classic Mac OS
+    Cr,
+    Mixed,
+    /// A single line
+    None,
+}
+
+/// The line ending to convert to
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+}
+
+impl LineEnding {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Lf => "\n",
+            Self::Crlf => "\r\n",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextFormat {
+    /// `ascii`, `utf-8`, `utf-16le` or `utf-16be`; `None` for text in an
+    /// encoding that couldn't be told
+    encoding: Option<String>,
+    bom: bool,
+    line_endings: LineEndings,
+    /// Lines ending in each style
+    lf: usize,
+    crlf: usize,
+    cr: usize,
+    final_newline: bool,
+}
+
+/// What `convert_text_format` changes; anything unset is kept as it is
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConvertTextOptions {
+    line_ending: Option<LineEnding>,
+    encoding: Option<TextEncoding>,
+    bom: Option<bool>,
+    /// `true` ends the file with a line ending, `false` strips trailing ones
+    final_newline: Option<bool>,
+}
+
+/// A decoded text file
+struct Text {
+    text: String,
+    encoding: Option<TextEncoding>,
+    bom: bool,
+    /// Set when the encoding is unknown and `text` is lossy
+    lossy: bool,
+}
+
+fn read(path: &Path) -> Result<(Text, Option<&'static str>), String> {
+    let meta =
+        fs::metadata(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    if meta.is_dir() {
+        return Err(format!("{} is a directory", path.display()));
+    }
+    if meta.len() > MAX_FILE_SIZE {
+        return Err(format!("{} is too large", path.display()));
+    }
+    let data = fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    let (name, bom) = guess_encoding(&data);
+    let encoding = match name {
+        Some("ascii" | "utf-8") => Some(TextEncoding::Utf8),
+        Some("utf-16le") => Some(TextEncoding::Utf16Le),
+        Some("utf-16be") => Some(TextEncoding::Utf16Be),
+        _ => None,
+    };
+    if encoding.is_none_or(|encoding| encoding == TextEncoding::Utf8) && looks_binary(&data) {
+        return Err(format!("{} is not a text file", path.display()));
+    }
+
+    let body = if bom {
+        &data[encoding.map_or(0, |encoding| encoding.bom().len())..]
+    } else {
+        &data[..]
+    };
+    let text = match encoding {
+        Some(TextEncoding::Utf8) => std::str::from_utf8(body).map(str::to_string).ok(),
+        Some(TextEncoding::Utf16Le) => decode_utf16(body, u16::from_le_bytes),
+        Some(TextEncoding::Utf16Be) => decode_utf16(body, u16::from_be_bytes),
+        None => None,
+    };
+    let text = match text {
+        Some(text) => Text {
+            text,
+            encoding,
+            bom,
+            lossy: false,
+        },
+        // Line endings are still worth reporting in an unknown encoding
+        None => Text {
+            text: String::from_utf8_lossy(body).into_owned(),
+            encoding: None,
+            bom,
+            lossy: true,
+        },
+    };
+    let name = name.filter(|_| !text.lossy);
+    Ok((text, name))
+}
+
+fn decode_utf16(data: &[u8], unit: fn([u8; 2]) -> u16) -> Option<String> {
+    if !data.len().is_multiple_of(2) {
+        return None;
+    }
+    let units = data.chunks_exact(2).map(|pair| unit([pair[0], pair[1]]));
+    char::decode_utf16(units)
+        .collect::<Result<String, _>>()
+        .ok()
+}
+
+fn encode(text: &str, encoding: TextEncoding, bom: bool) -> Vec<u8> {
+    let mut data = Vec::with_capacity(text.len() + 3);
+    if bom {
+        data.extend_from_slice(encoding.bom());
+    }
+    match encoding {
+        TextEncoding::Utf8 => data.extend_from_slice(text.as_bytes()),
+        TextEncoding::Utf16Le => data.extend(text.encode_utf16().flat_map(u16::to_le_bytes)),
+        TextEncoding::Utf16Be => data.extend(text.encode_utf16().flat_map(u16::to_be_bytes)),
+    }
+    data
+}
+
+/// Lines ending in LF, CRLF and CR
+fn count_line_endings(text: &str) -> (usize, usize, usize) {
+    let bytes = text.as_bytes();
+    let (mut lf, mut crlf, mut cr) = (0, 0, 0);
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\r' if bytes.get(i + 1) == Some(&b'\n') => {
+                crlf += 1;
+                i += 1;
+            }
+            b'\r' => cr += 1,
+            b'\n' => lf += 1,
+            _ => {}
+        }
+        i += 1;
+    }
+    (lf, crlf, cr)
+}
+
+fn format(text: &Text, encoding: Option<&str>) -> TextFormat {
+    let (lf, crlf, cr) = count_line_endings(&text.text);
+    let line_endings = match (lf > 0, crlf > 0, cr > 0) {
+        (false, false, false) => LineEndings::None,
+        (true, false, false) => LineEndings::Lf,
+        (false, true, false) => LineEndings::Crlf,
+        (false, false, true) => LineEndings::Cr,
+        _ => LineEndings::Mixed,
+    };
+    TextFormat {
+        encoding: encoding.map(str::to_string),
+        bom: text.bom,
+        line_endings,
+        lf,
+        crlf,
+        cr,
+        final_newline: text.text.ends_with(['\n', '\r']),
+    }
+}
+
+/// `text` with every line ending replaced by `ending`
+fn normalize(text: &str, ending: LineEnding) -> String {
+    text.replace("\r\n", "\n")
+        .replace('\r', "\n")
+        .replace('\n', ending.as_str())
+}
+
+fn convert(path: &Path, options: ConvertTextOptions) -> Result<TextFormat, String> {
+    let (current, _) = read(path)?;
+    if current.lossy {
+        return Err(format!(
+            "{} isn't UTF-8 or UTF-16, so it can't be converted safely",
+            path.display()
+        ));
+    }
+    let encoding = options
+        .encoding
+        .or(current.encoding)
+        .unwrap_or(TextEncoding::Utf8);
+    // A re-encoded file gets the usual mark for its encoding: UTF-16 is
+    // unreadable to most tools without one, UTF-8 rarely needs it
+    let bom = options
+        .bom
+        .unwrap_or(if Some(encoding) == current.encoding {
+            current.bom
+        } else {
+            encoding != TextEncoding::Utf8
+        });
+
+    let mut text = match options.line_ending {
+        Some(ending) => normalize(&current.text, ending),
+        None => current.text,
+    };
+    match options.final_newline {
+        Some(true) if !text.is_empty() && !text.ends_with(['\n', '\r']) => {
+            let ending = options.line_ending.unwrap_or_else(|| {
+                let (lf, crlf, _) = count_line_endings(&text);
+                if crlf > lf {
+                    LineEnding::Crlf
+                } else {
+                    LineEnding::Lf
+                }
+            });
+            text.push_str(ending.as_str());
+        }
+        Some(false) => text.truncate(text.trim_end_matches(['\n', '\r']).len()),
+        _ => {}
+    }
+
+    write_atomic(path, &encode(&text, encoding, bom), false)?;
+    let (written, name) = read(path)?;
+    Ok(format(&written, name))
+}
+
+/// The encoding, byte order mark and line endings of the text file at `path`
+#[tauri::command]
+pub async fn detect_text_format(path: String) -> Result<TextFormat, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let (text, encoding) = read(Path::new(&path))?;
+        Ok(format(&text, encoding))
+    })
+    .await
+    .map_err(|e| format!("Detection failed: {}", e))?
+}
+
+/// Rewrite the text file at `path` in the format `opts` asks for, returning
+/// the format it ended up in
+#[tauri::command]
+pub async fn convert_text_format(
+    path: String,
+    opts: Option<ConvertTextOptions>,
+) -> Result<TextFormat, String> {
+    let options = opts.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || convert(Path::new(&path), options))
+        .await
+        .map_err(|e| format!("Conversion failed: {}", e))?
+}
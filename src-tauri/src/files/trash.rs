@@ -0,0 +1,25 @@
+//! Move to Trash
+//!
+//! Deletions the user starts from the app go to the platform's Trash or
+//! Recycle Bin instead of being permanent, so a removed worktree or file can
+//! be restored from the file manager.
+
+use std::path::Path;
+
+/// Move `path` to the Trash
+pub(crate) fn trash(path: &Path) -> Result<(), String> {
+    // symlink_metadata so a dangling link can be trashed too
+    if path.symlink_metadata().is_err() {
+        return Err(format!("{} does not exist", path.display()));
+    }
+    trash::delete(path)
+        .map_err(|e| format!("Failed to move {} to the Trash: {}", path.display(), e))
+}
+
+/// Move a file or directory to the Trash
+#[tauri::command]
+pub async fn trash_path(path: String) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || trash(Path::new(&path)))
+        .await
+        .map_err(|e| format!("Trash failed: {}", e))?
+}
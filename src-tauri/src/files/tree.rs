@@ -0,0 +1,174 @@
+//! Directory tree listing
+//!
+//! `list_tree` returns a directory as one nested structure for the file
+//! explorer, honouring `.gitignore` the way `grep_project` does. Directories
+//! below the requested depth come back without children so the panel can
+//! load them when they are expanded.
+
+use ignore::WalkBuilder;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Entries listed at most, so a huge tree can't stall the UI
+const MAX_ENTRIES: usize = 20_000;
+
+/// Options for `list_tree`
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListTreeOptions {
+    /// Levels below the root to list, all by default
+    depth: Option<usize>,
+    /// Skip ignored files, the default
+    respect_gitignore: Option<bool>,
+    /// List dotfiles; `.git` is always skipped
+    #[serde(default)]
+    include_hidden: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryKind {
+    File,
+    Dir,
+    Symlink,
+    Other,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TreeNode {
+    name: String,
+    /// Relative to the listed root, `/`-separated; empty for the root
+    path: String,
+    kind: EntryKind,
+    /// Size in bytes, for files
+    size: Option<u64>,
+    /// `None` for files and for directories below the requested depth
+    children: Option<Vec<TreeNode>>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TreeListing {
+    root: TreeNode,
+    /// Stopped after the maximum number of entries
+    truncated: bool,
+}
+
+/// Everything the walk found, keyed by parent directory
+struct Walked {
+    children: HashMap<PathBuf, Vec<TreeNode>>,
+    depth: Option<usize>,
+}
+
+impl Walked {
+    /// Attach the walked children to `node` and its subdirectories
+    fn attach(&mut self, node: &mut TreeNode, dir: &Path, level: usize) {
+        if node.kind != EntryKind::Dir || self.depth.is_some_and(|depth| level >= depth) {
+            return;
+        }
+        let mut children = self.children.remove(dir).unwrap_or_default();
+        for child in &mut children {
+            let path = dir.join(&child.name);
+            self.attach(child, &path, level + 1);
+        }
+        children.sort_by(|a, b| {
+            (a.kind != EntryKind::Dir)
+                .cmp(&(b.kind != EntryKind::Dir))
+                .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+        });
+        node.children = Some(children);
+    }
+}
+
+fn list(root: &Path, options: ListTreeOptions) -> Result<TreeListing, String> {
+    if !root.is_dir() {
+        return Err(format!("{} is not a directory", root.display()));
+    }
+    let respect = options.respect_gitignore.unwrap_or(true);
+    let walker = WalkBuilder::new(root)
+        .max_depth(options.depth)
+        .hidden(!options.include_hidden)
+        .git_ignore(respect)
+        .git_exclude(respect)
+        .git_global(respect)
+        .ignore(respect)
+        .parents(respect)
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .build();
+
+    let mut walked = Walked {
+        children: HashMap::new(),
+        depth: options.depth,
+    };
+    let mut count = 0;
+    let mut truncated = false;
+    for entry in walker.flatten() {
+        if entry.depth() == 0 {
+            continue;
+        }
+        if count == MAX_ENTRIES {
+            truncated = true;
+            break;
+        }
+        count += 1;
+
+        let kind = match entry.file_type() {
+            Some(kind) if kind.is_symlink() => EntryKind::Symlink,
+            Some(kind) if kind.is_dir() => EntryKind::Dir,
+            Some(kind) if kind.is_file() => EntryKind::File,
+            _ => EntryKind::Other,
+        };
+        let size = (kind == EntryKind::File)
+            .then(|| entry.metadata().ok().map(|meta| meta.len()))
+            .flatten();
+        let path = entry.path();
+        let relative: Vec<_> = path
+            .strip_prefix(root)
+            .unwrap_or(path)
+            .components()
+            .map(|part| part.as_os_str().to_string_lossy())
+            .collect();
+        let Some(parent) = path.parent() else {
+            continue;
+        };
+        walked
+            .children
+            .entry(parent.to_path_buf())
+            .or_default()
+            .push(TreeNode {
+                name: entry.file_name().to_string_lossy().into_owned(),
+                path: relative.join("/"),
+                kind,
+                size,
+                children: None,
+            });
+    }
+
+    let mut node = TreeNode {
+        name: root
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        path: String::new(),
+        kind: EntryKind::Dir,
+        size: None,
+        children: None,
+    };
+    walked.attach(&mut node, root, 0);
+    Ok(TreeListing {
+        root: node,
+        truncated,
+    })
+}
+
+/// The directory tree under `root`
+#[tauri::command]
+pub async fn list_tree(
+    root: String,
+    options: Option<ListTreeOptions>,
+) -> Result<TreeListing, String> {
+    let options = options.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || list(Path::new(&root), options))
+        .await
+        .map_err(|e| format!("Listing failed: {}", e))?
+}
@@ -0,0 +1,223 @@
+//! Path watcher
+//!
+//! `watch_path` watches a file or directory and emits `file-changes` with
+//! everything that happened during a quiet period: a burst of writes to one
+//! file is reported once, a file created and removed again not at all.
+//! Paths can be narrowed with the same globs as `grep_project`.
+
+use ignore::overrides::{Override, OverrideBuilder};
+use notify::event::{EventKind, ModifyKind};
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, State};
+
+/// Quiet period used when the caller sets none
+const DEFAULT_DEBOUNCE_MS: u64 = 200;
+
+/// Active path watchers; dropping one ends its event thread
+pub struct FileWatchState {
+    watchers: Mutex<HashMap<u32, RecommendedWatcher>>,
+    next_id: AtomicU32,
+}
+
+impl Default for FileWatchState {
+    fn default() -> Self {
+        Self {
+            watchers: Mutex::new(HashMap::new()),
+            next_id: AtomicU32::new(1),
+        }
+    }
+}
+
+/// Options for `watch_path`
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchOptions {
+    /// Paths to report, or to skip with a leading `!`, relative to the
+    /// watched directory
+    #[serde(default)]
+    globs: Vec<String>,
+    /// Include subdirectories, the default
+    recursive: Option<bool>,
+    debounce_ms: Option<u64>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Created,
+    Modified,
+    Removed,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct FileChange {
+    path: String,
+    kind: ChangeKind,
+}
+
+/// Event payload for a batch of changes
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FileChangesEvent {
+    watch_id: u32,
+    changes: Vec<FileChange>,
+}
+
+/// What a batch amounts to for one path, given what it already held
+fn coalesce(before: Option<ChangeKind>, now: ChangeKind) -> Option<ChangeKind> {
+    use ChangeKind::*;
+    match (before, now) {
+        (None, now) => Some(now),
+        (Some(Created), Removed) => None,
+        (Some(Created), _) => Some(Created),
+        (Some(Removed), Created | Modified) => Some(Modified),
+        (Some(_), now) => Some(now),
+    }
+}
+
+fn kind_of(event: &EventKind, path: &Path) -> Option<ChangeKind> {
+    match event {
+        EventKind::Access(_) => None,
+        EventKind::Create(_) => Some(ChangeKind::Created),
+        EventKind::Remove(_) => Some(ChangeKind::Removed),
+        // Either side of a rename; the path tells which
+        EventKind::Modify(ModifyKind::Name(_)) => Some(if path.exists() {
+            ChangeKind::Created
+        } else {
+            ChangeKind::Removed
+        }),
+        EventKind::Modify(_) => Some(ChangeKind::Modified),
+        _ => Some(if path.exists() {
+            ChangeKind::Modified
+        } else {
+            ChangeKind::Removed
+        }),
+    }
+}
+
+#[derive(Default)]
+struct Batch {
+    changes: BTreeMap<PathBuf, ChangeKind>,
+}
+
+impl Batch {
+    fn add(&mut self, event: Event, filter: &Override) {
+        for path in event.paths {
+            let Some(kind) = kind_of(&event.kind, &path) else {
+                continue;
+            };
+            if filter.matched(&path, path.is_dir()).is_ignore() {
+                continue;
+            }
+            match coalesce(self.changes.get(&path).copied(), kind) {
+                Some(kind) => self.changes.insert(path, kind),
+                None => self.changes.remove(&path),
+            };
+        }
+    }
+}
+
+/// Start watching a file or directory, returning the watch ID that tags its
+/// `file-changes` events
+#[tauri::command]
+pub async fn watch_path(
+    app: AppHandle,
+    state: State<'_, FileWatchState>,
+    path: String,
+    options: Option<WatchOptions>,
+) -> Result<u32, String> {
+    let options = options.unwrap_or_default();
+    let path = PathBuf::from(&path);
+    if !path.exists() {
+        return Err(format!("{} does not exist", path.display()));
+    }
+
+    // Globs are relative to the watched directory, or a watched file's parent
+    let base = if path.is_dir() {
+        path.clone()
+    } else {
+        path.parent().map(Path::to_path_buf).unwrap_or_default()
+    };
+    let mut overrides = OverrideBuilder::new(&base);
+    for glob in &options.globs {
+        overrides
+            .add(glob)
+            .map_err(|e| format!("Invalid glob '{}': {}", glob, e))?;
+    }
+    let filter = overrides
+        .build()
+        .map_err(|e| format!("Invalid globs: {}", e))?;
+
+    let (tx, rx) = mpsc::channel::<notify::Result<Event>>();
+    let mut watcher = notify::recommended_watcher(move |event| {
+        let _ = tx.send(event);
+    })
+    .map_err(|e| format!("Failed to create watcher: {}", e))?;
+    let mode = if options.recursive.unwrap_or(true) {
+        RecursiveMode::Recursive
+    } else {
+        RecursiveMode::NonRecursive
+    };
+    watcher
+        .watch(&path, mode)
+        .map_err(|e| format!("Failed to watch {}: {}", path.display(), e))?;
+
+    let id = state.next_id.fetch_add(1, Ordering::SeqCst);
+    state.watchers.lock().insert(id, watcher);
+
+    let debounce = Duration::from_millis(options.debounce_ms.unwrap_or(DEFAULT_DEBOUNCE_MS));
+    thread::spawn(move || {
+        // recv fails once the watcher (and with it the sender) is dropped
+        while let Ok(first) = rx.recv() {
+            let mut batch = Batch::default();
+            if let Ok(event) = first {
+                batch.add(event, &filter);
+            }
+            while let Ok(next) = rx.recv_timeout(debounce) {
+                if let Ok(event) = next {
+                    batch.add(event, &filter);
+                }
+            }
+            if batch.changes.is_empty() {
+                continue;
+            }
+
+            let changes = batch
+                .changes
+                .into_iter()
+                .map(|(path, kind)| FileChange {
+                    path: path.to_string_lossy().into_owned(),
+                    kind,
+                })
+                .collect();
+            let _ = app.emit(
+                "file-changes",
+                FileChangesEvent {
+                    watch_id: id,
+                    changes,
+                },
+            );
+        }
+    });
+
+    Ok(id)
+}
+
+/// Stop a watch started by `watch_path`
+#[tauri::command]
+pub async fn unwatch(state: State<'_, FileWatchState>, id: u32) -> Result<(), String> {
+    state
+        .watchers
+        .lock()
+        .remove(&id)
+        .map(|_| ())
+        .ok_or_else(|| format!("Watch {} not found", id))
+}
@@ -0,0 +1,108 @@
+//! Backend selection for git operations
+//!
+//! Read-heavy commands (status, diff, log) are implemented on libgit2 and
+//! fall back to the git CLI when libgit2 can't handle a repository or an
+//! option. The backend that served each command is recorded for debugging.
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tauri::State;
+
+/// Implementation that handled a git call
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GitBackend {
+    Libgit2,
+    Cli,
+}
+
+/// Which backend to try first
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackendPreference {
+    /// libgit2 with CLI fallback
+    #[default]
+    Auto,
+    /// Always shell out to git
+    Cli,
+}
+
+/// State for backend preference and per-command bookkeeping
+#[derive(Default)]
+pub struct GitBackendState {
+    preference: Mutex<BackendPreference>,
+    last_used: Mutex<HashMap<&'static str, GitBackend>>,
+}
+
+impl GitBackendState {
+    /// Run `op` on libgit2 if preferred, falling back to the CLI implementation
+    pub(crate) fn run<T>(
+        &self,
+        op: &'static str,
+        native: impl FnOnce() -> Result<T, git2::Error>,
+        cli: impl FnOnce() -> Result<T, String>,
+    ) -> Result<T, String> {
+        if *self.preference.lock() == BackendPreference::Auto {
+            match native() {
+                Ok(value) => {
+                    self.last_used.lock().insert(op, GitBackend::Libgit2);
+                    return Ok(value);
+                }
+                Err(e) => eprintln!("libgit2 {} failed, falling back to git CLI: {}", op, e),
+            }
+        }
+
+        self.run_cli(op, cli)
+    }
+
+    /// Run `op` on the CLI, for options only it supports
+    pub(crate) fn run_cli<T>(
+        &self,
+        op: &'static str,
+        cli: impl FnOnce() -> Result<T, String>,
+    ) -> Result<T, String> {
+        let value = cli()?;
+        self.last_used.lock().insert(op, GitBackend::Cli);
+        Ok(value)
+    }
+}
+
+/// Debug information about git backends
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitBackendInfo {
+    preference: BackendPreference,
+    libgit2_version: String,
+    last_used: HashMap<String, GitBackend>,
+}
+
+/// Report the backend preference and which backend served each command last
+#[tauri::command]
+pub async fn get_git_backend_info(
+    state: State<'_, GitBackendState>,
+) -> Result<GitBackendInfo, String> {
+    let version = git2::Version::get();
+    let (major, minor, patch) = version.libgit2_version();
+
+    Ok(GitBackendInfo {
+        preference: *state.preference.lock(),
+        libgit2_version: format!("{}.{}.{}", major, minor, patch),
+        last_used: state
+            .last_used
+            .lock()
+            .iter()
+            .map(|(op, backend)| (op.to_string(), *backend))
+            .collect(),
+    })
+}
+
+/// Choose whether libgit2 is tried before the git CLI
+#[tauri::command]
+pub async fn set_git_backend(
+    state: State<'_, GitBackendState>,
+    preference: BackendPreference,
+) -> Result<(), String> {
+    *state.preference.lock() = preference;
+    Ok(())
+}
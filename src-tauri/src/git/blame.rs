@@ -0,0 +1,216 @@
+//! Blame command
+//!
+//! Parses `git blame --porcelain`. This stays on the CLI: libgit2's blame is
+//! considerably slower than git's on long histories.
+
+use super::log::format_time;
+use super::run_git;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Line range for `git_blame`, 1-based and inclusive
+#[derive(Debug, Deserialize)]
+pub struct BlameRange {
+    start: u32,
+    end: u32,
+}
+
+/// Options for `git_blame`
+#[derive(Debug, Default, Deserialize)]
+pub struct BlameOptions {
+    range: Option<BlameRange>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlameLine {
+    line: u32,
+    hash: String,
+    author: String,
+    author_email: String,
+    date: String,
+    summary: String,
+    content: String,
+}
+
+/// Commit details that porcelain output only prints the first time a commit appears
+#[derive(Clone, Default)]
+struct BlameCommit {
+    author: String,
+    author_email: String,
+    time: i64,
+    tz_minutes: i32,
+    summary: String,
+}
+
+/// Parse a "+0130" style timezone into minutes
+fn parse_tz(tz: &str) -> i32 {
+    let sign = if tz.starts_with('-') { -1 } else { 1 };
+    let digits = tz.trim_start_matches(['+', '-']);
+    let hours: i32 = digits.get(..2).and_then(|h| h.parse().ok()).unwrap_or(0);
+    let minutes: i32 = digits.get(2..4).and_then(|m| m.parse().ok()).unwrap_or(0);
+    sign * (hours * 60 + minutes)
+}
+
+fn parse_blame(output: &str) -> Vec<BlameLine> {
+    let mut commits: HashMap<String, BlameCommit> = HashMap::new();
+    let mut lines = Vec::new();
+    let mut current: Option<(String, u32)> = None;
+
+    for line in output.lines() {
+        if let Some(content) = line.strip_prefix('\t') {
+            let Some((hash, number)) = current.take() else {
+                continue;
+            };
+            let commit = commits.get(&hash).cloned().unwrap_or_default();
+            lines.push(BlameLine {
+                line: number,
+                date: format_time(git2::Time::new(commit.time, commit.tz_minutes)),
+                hash,
+                author: commit.author,
+                author_email: commit.author_email,
+                summary: commit.summary,
+                content: content.to_string(),
+            });
+            continue;
+        }
+
+        match &current {
+            None => {
+                // "<hash> <original line> <final line> [<lines in group>]"
+                let mut parts = line.split(' ');
+                if let (Some(hash), Some(_), Some(number)) =
+                    (parts.next(), parts.next(), parts.next())
+                {
+                    if let Ok(number) = number.parse() {
+                        commits.entry(hash.to_string()).or_default();
+                        current = Some((hash.to_string(), number));
+                    }
+                }
+            }
+            Some((hash, _)) => {
+                let Some(commit) = commits.get_mut(hash) else {
+                    continue;
+                };
+                let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+                match key {
+                    "author" => commit.author = value.to_string(),
+                    "author-mail" => {
+                        commit.author_email = value.trim_matches(['<', '>']).to_string()
+                    }
+                    "author-time" => commit.time = value.parse().unwrap_or(0),
+                    "author-tz" => commit.tz_minutes = parse_tz(value),
+                    "summary" => commit.summary = value.to_string(),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    lines
+}
+
+/// Annotate each line of a file with the commit that last changed it
+#[tauri::command]
+pub async fn git_blame(
+    repo: String,
+    path: String,
+    options: Option<BlameOptions>,
+) -> Result<Vec<BlameLine>, String> {
+    let options = options.unwrap_or_default();
+
+    super::blocking("git_blame", move || {
+        let range = options.range.map(|r| format!("-L{},{}", r.start, r.end));
+
+        let mut args = vec!["blame", "--porcelain"];
+        if let Some(range) = range.as_deref() {
+            args.push(range);
+        }
+        args.push("--");
+        args.push(&path);
+
+        let output = run_git(&repo, &args)?;
+        Ok(parse_blame(&output))
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reuses_commit_details_across_groups() {
+        let output = "\
+aaaa 1 1 2
+author Ada
+author-mail <ada@example.com>
+author-time 1700000000
+author-tz -0500
+summary First commit
+filename f.rs
+\tfn main() {
+aaaa 2 2
+\t}
+bbbb 5 3 1
+author Bo
+author-mail <bo@example.com>
+author-time 0
+author-tz +0130
+summary Second
+previous cccc f.rs
+boundary
+filename f.rs
+\t    tabs\tand spaces
+";
+        let lines = parse_blame(output);
+        let summary: Vec<_> = lines
+            .iter()
+            .map(|l| (l.line, l.hash.as_str(), l.content.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (1, "aaaa", "fn main() {"),
+                (2, "aaaa", "}"),
+                (3, "bbbb", "    tabs\tand spaces"),
+            ]
+        );
+        for line in &lines[..2] {
+            assert_eq!(line.author, "Ada");
+            assert_eq!(line.author_email, "ada@example.com");
+            assert_eq!(line.date, "2023-11-14T17:13:20-05:00");
+            assert_eq!(line.summary, "First commit");
+        }
+        assert_eq!(lines[2].author, "Bo");
+        assert_eq!(lines[2].date, "1970-01-01T01:30:00+01:30");
+        assert_eq!(lines[2].summary, "Second");
+    }
+
+    #[test]
+    fn skips_content_without_a_header() {
+        let output = "\
+\torphan
+not-a-header
+dddd 1 x
+\u{e9}
+eeee 1 7
+\tseven
+";
+        let lines = parse_blame(output);
+        assert_eq!(lines.len(), 1);
+        assert_eq!((lines[0].line, lines[0].hash.as_str()), (7, "eeee"));
+        assert_eq!(lines[0].author, "");
+        assert_eq!(lines[0].date, "1970-01-01T00:00:00+00:00");
+    }
+
+    #[test]
+    fn parses_timezones() {
+        assert_eq!(parse_tz("+0130"), 90);
+        assert_eq!(parse_tz("-0800"), -480);
+        assert_eq!(parse_tz("+05"), 300);
+        assert_eq!(parse_tz(""), 0);
+        assert_eq!(parse_tz("-"), 0);
+        assert_eq!(parse_tz("junk"), 0);
+    }
+}
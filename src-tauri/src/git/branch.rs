@@ -0,0 +1,146 @@
+//! Branch commands
+//!
+//! Issue branches are named `issue-{number}-{slug}` (or `{number}-{slug}` as
+//! generated by older versions of the frontend). Stale detection relies on
+//! that naming to tie a branch back to its issue.
+
+use super::run_git;
+use serde::Serialize;
+use std::collections::HashSet;
+
+/// A local branch as reported by `git for-each-ref`
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BranchInfo {
+    pub(crate) name: String,
+    pub(crate) head: String,
+    pub(crate) upstream: Option<String>,
+    pub(crate) current: bool,
+    pub(crate) worktree_path: Option<String>,
+    pub(crate) issue_number: Option<u64>,
+}
+
+/// Why a branch is considered stale
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StaleReason {
+    IssueClosed,
+    WorktreeMissing,
+}
+
+/// An issue branch that can be cleaned up
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StaleBranch {
+    name: String,
+    issue_number: u64,
+    reasons: Vec<StaleReason>,
+}
+
+/// Extract the issue number from an issue branch name
+pub(crate) fn issue_number_from_branch(name: &str) -> Option<u64> {
+    let rest = name.strip_prefix("issue-").unwrap_or(name);
+    let (digits, _) = rest.split_once('-')?;
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+pub(crate) fn branches(repo: &str) -> Result<Vec<BranchInfo>, String> {
+    let output = run_git(
+        repo,
+        &[
+            "for-each-ref",
+            "--format=%(refname:short)%00%(objectname)%00%(upstream:short)%00%(HEAD)%00%(worktreepath)",
+            "refs/heads",
+        ],
+    )?;
+
+    let branches = output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\0');
+            let name = fields.next()?.to_string();
+            let head = fields.next()?.to_string();
+            let upstream = non_empty(fields.next().unwrap_or_default());
+            let current = fields.next() == Some("*");
+            let worktree_path = non_empty(fields.next().unwrap_or_default());
+            let issue_number = issue_number_from_branch(&name);
+
+            Some(BranchInfo {
+                name,
+                head,
+                upstream,
+                current,
+                worktree_path,
+                issue_number,
+            })
+        })
+        .collect();
+
+    Ok(branches)
+}
+
+/// Create a branch from `base` (defaults to HEAD)
+#[tauri::command]
+pub async fn create_branch(repo: String, name: String, base: Option<String>) -> Result<(), String> {
+    let mut args = vec!["branch", name.as_str()];
+    if let Some(base) = base.as_deref() {
+        args.push(base);
+    }
+    run_git(&repo, &args)?;
+    Ok(())
+}
+
+/// Delete a local branch, forcing deletion of unmerged work only when asked
+#[tauri::command]
+pub async fn delete_branch(repo: String, name: String, force: Option<bool>) -> Result<(), String> {
+    let flag = if force.unwrap_or(false) { "-D" } else { "-d" };
+    run_git(&repo, &["branch", flag, &name])?;
+    Ok(())
+}
+
+/// List local branches
+#[tauri::command]
+pub async fn list_branches(repo: String) -> Result<Vec<BranchInfo>, String> {
+    branches(&repo)
+}
+
+/// Find issue branches whose issue is closed or that have no worktree anymore
+///
+/// Issue state lives on GitHub, so the caller passes the closed issue numbers.
+/// The currently checked-out branch is never reported.
+#[tauri::command]
+pub async fn find_stale_branches(
+    repo: String,
+    closed_issues: Vec<u64>,
+) -> Result<Vec<StaleBranch>, String> {
+    let closed: HashSet<u64> = closed_issues.into_iter().collect();
+
+    let stale = branches(&repo)?
+        .into_iter()
+        .filter(|branch| !branch.current)
+        .filter_map(|branch| {
+            let issue_number = branch.issue_number?;
+            let mut reasons = Vec::new();
+            if closed.contains(&issue_number) {
+                reasons.push(StaleReason::IssueClosed);
+            }
+            if branch.worktree_path.is_none() {
+                reasons.push(StaleReason::WorktreeMissing);
+            }
+            (!reasons.is_empty()).then_some(StaleBranch {
+                name: branch.name,
+                issue_number,
+                reasons,
+            })
+        })
+        .collect();
+
+    Ok(stale)
+}
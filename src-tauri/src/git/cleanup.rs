@@ -0,0 +1,187 @@
+//! Worktree garbage collection
+//!
+//! Removes abandoned worktrees under the worktree base directory. A worktree
+//! is abandoned when neither its HEAD commit nor its index changed within the
+//! age limit. Dirty and locked worktrees are never removed.
+
+use super::status::git_dir;
+use super::worktree::{same_path, worktrees, WorktreeInfo};
+use super::{ref_exists, run_git};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Worktree base used by the frontend, relative to the repository root
+pub(crate) const DEFAULT_WORKTREE_DIR: &str = ".worktrees";
+
+/// Options for `cleanup_worktrees`
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CleanupOptions {
+    /// Only remove worktrees idle for at least this many days
+    older_than_days: Option<u64>,
+    /// Only remove worktrees whose branch is merged into `merged_into`
+    #[serde(default)]
+    only_merged: bool,
+    /// Branch used for the merged check (defaults to the repository's HEAD)
+    merged_into: Option<String>,
+    /// Directory containing managed worktrees (defaults to `<repo>/.worktrees`)
+    base: Option<String>,
+    /// Report what would be removed without removing anything
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CleanupCandidate {
+    path: String,
+    branch: Option<String>,
+    idle_days: u64,
+    merged: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedWorktree {
+    path: String,
+    reason: String,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CleanupReport {
+    dry_run: bool,
+    removed: Vec<CleanupCandidate>,
+    skipped: Vec<SkippedWorktree>,
+}
+
+/// Most recent activity in a worktree: its HEAD commit or its index, whichever is newer
+fn last_activity(worktree: &WorktreeInfo) -> Option<SystemTime> {
+    let commit_time = run_git(&worktree.path, &["log", "-1", "--format=%ct"])
+        .ok()
+        .and_then(|out| out.trim().parse::<u64>().ok())
+        .map(|secs| UNIX_EPOCH + Duration::from_secs(secs));
+
+    let index_time = git_dir(&worktree.path)
+        .ok()
+        .and_then(|dir| dir.join("index").metadata().ok())
+        .and_then(|meta| meta.modified().ok());
+
+    commit_time.max(index_time)
+}
+
+fn is_dirty(path: &str) -> bool {
+    run_git(path, &["status", "--porcelain"])
+        .map(|out| !out.trim().is_empty())
+        .unwrap_or(true)
+}
+
+fn is_merged(repo: &str, branch: &str, target: &str) -> bool {
+    run_git(repo, &["merge-base", "--is-ancestor", branch, target]).is_ok()
+}
+
+pub(crate) fn worktree_base(repo: &str, base: Option<&str>) -> String {
+    match base {
+        Some(base) => base.to_string(),
+        None => Path::new(repo)
+            .join(DEFAULT_WORKTREE_DIR)
+            .to_string_lossy()
+            .into_owned(),
+    }
+}
+
+fn is_under(path: &str, base: &str) -> bool {
+    match (
+        Path::new(path).canonicalize(),
+        Path::new(base).canonicalize(),
+    ) {
+        (Ok(path), Ok(base)) => path.starts_with(base),
+        _ => Path::new(path).starts_with(base),
+    }
+}
+
+/// Remove abandoned worktrees under the worktree base
+#[tauri::command]
+pub async fn cleanup_worktrees(
+    repo: String,
+    options: Option<CleanupOptions>,
+) -> Result<CleanupReport, String> {
+    let options = options.unwrap_or_default();
+    let base = worktree_base(&repo, options.base.as_deref());
+    let target = options.merged_into.as_deref().unwrap_or("HEAD");
+    let min_idle = Duration::from_secs(options.older_than_days.unwrap_or(0) * 24 * 60 * 60);
+    let now = SystemTime::now();
+
+    if options.only_merged && !ref_exists(&repo, target) {
+        return Err(format!("Branch {} not found", target));
+    }
+
+    let mut report = CleanupReport {
+        dry_run: options.dry_run,
+        ..Default::default()
+    };
+
+    for worktree in worktrees(&repo)? {
+        if worktree.bare || same_path(&worktree.path, &repo) || !is_under(&worktree.path, &base) {
+            continue;
+        }
+
+        let skip = |reason: &str| SkippedWorktree {
+            path: worktree.path.clone(),
+            reason: reason.to_string(),
+        };
+
+        if worktree.locked {
+            report.skipped.push(skip("locked"));
+            continue;
+        }
+
+        // Directory already gone - pruning below takes care of it
+        if worktree.prunable {
+            continue;
+        }
+
+        let idle = last_activity(&worktree)
+            .and_then(|t| now.duration_since(t).ok())
+            .unwrap_or_default();
+        if idle < min_idle {
+            report.skipped.push(skip("recently active"));
+            continue;
+        }
+
+        let merged = worktree
+            .branch
+            .as_deref()
+            .is_some_and(|branch| is_merged(&repo, branch, target));
+        if options.only_merged && !merged {
+            report.skipped.push(skip("not merged"));
+            continue;
+        }
+
+        if is_dirty(&worktree.path) {
+            report.skipped.push(skip("uncommitted changes"));
+            continue;
+        }
+
+        if !options.dry_run {
+            if let Err(e) = run_git(&repo, &["worktree", "remove", &worktree.path]) {
+                report.skipped.push(skip(&e));
+                continue;
+            }
+        }
+
+        report.removed.push(CleanupCandidate {
+            path: worktree.path,
+            branch: worktree.branch,
+            idle_days: idle.as_secs() / (24 * 60 * 60),
+            merged,
+        });
+    }
+
+    if !options.dry_run {
+        run_git(&repo, &["worktree", "prune"])?;
+    }
+
+    Ok(report)
+}
@@ -0,0 +1,151 @@
+//! Clone command
+//!
+//! Runs `git clone --progress` on a background thread, turning git's
+//! progress output into `git-clone-progress` events and taskbar progress.
+//! Clones are identified by an id so they can be cancelled.
+
+use super::{non_interactive, parse_progress, read_progress_lines, GitProgress};
+use crate::taskbar;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+use std::thread;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// State for clones that are still running
+pub struct CloneState {
+    clones: Mutex<HashMap<u32, Arc<CloneJob>>>,
+    next_id: AtomicU32,
+}
+
+struct CloneJob {
+    child: Mutex<Child>,
+    cancelled: AtomicBool,
+}
+
+impl Default for CloneState {
+    fn default() -> Self {
+        Self {
+            clones: Mutex::new(HashMap::new()),
+            next_id: AtomicU32::new(1),
+        }
+    }
+}
+
+/// Event payload for clone progress
+#[derive(Clone, Serialize)]
+struct CloneProgressEvent {
+    id: u32,
+    #[serde(flatten)]
+    progress: GitProgress,
+}
+
+/// Event payload for a finished, failed or cancelled clone
+#[derive(Clone, Serialize)]
+struct CloneDoneEvent {
+    id: u32,
+    success: bool,
+    cancelled: bool,
+    error: Option<String>,
+}
+
+/// Share of the clone done: receiving objects takes most of it, resolving
+/// deltas the rest. Other phases count their own work, so they are skipped.
+fn share_done(progress: &GitProgress) -> Option<f64> {
+    let percent = f64::from(progress.percent?) / 100.0;
+    match progress.phase.as_str() {
+        "Receiving objects" => Some(percent * 0.9),
+        "Resolving deltas" => Some(0.9 + percent * 0.1),
+        _ => None,
+    }
+}
+
+/// Clone a repository in the background; progress is reported via events
+#[tauri::command]
+pub async fn git_clone(
+    app: AppHandle,
+    state: State<'_, CloneState>,
+    url: String,
+    dest: String,
+) -> Result<u32, String> {
+    let dest_existed = Path::new(&dest).exists();
+
+    let mut child = non_interactive(Command::new("git").args(["clone", "--progress", &url, &dest]))
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+
+    let mut stderr = child
+        .stderr
+        .take()
+        .ok_or_else(|| "Failed to capture git output".to_string())?;
+
+    let id = state.next_id.fetch_add(1, Ordering::SeqCst);
+    let job = Arc::new(CloneJob {
+        child: Mutex::new(child),
+        cancelled: AtomicBool::new(false),
+    });
+    state.clones.lock().insert(id, job.clone());
+    let taskbar_key = format!("clone:{}", id);
+    taskbar::report(&app, &taskbar_key, None);
+
+    thread::spawn(move || {
+        let mut last_line = String::new();
+        read_progress_lines(&mut stderr, |line| {
+            if let Some(progress) = parse_progress(line) {
+                if let Some(share) = share_done(&progress) {
+                    taskbar::report(&app, &taskbar_key, Some(share));
+                }
+                let _ = app.emit("git-clone-progress", CloneProgressEvent { id, progress });
+            }
+            last_line = line.to_string();
+        });
+
+        let status = job.child.lock().wait();
+        let cancelled = job.cancelled.load(Ordering::SeqCst);
+        let success = !cancelled && status.as_ref().map(|s| s.success()).unwrap_or(false);
+
+        // Don't leave a half-cloned directory behind
+        if !success && !dest_existed {
+            let _ = std::fs::remove_dir_all(&dest);
+        }
+
+        let error = match status {
+            _ if success || cancelled => None,
+            Err(e) => Some(format!("Failed to wait for git: {}", e)),
+            Ok(_) => Some(last_line),
+        };
+
+        app.state::<CloneState>().clones.lock().remove(&id);
+        taskbar::finish(&app, &taskbar_key);
+        let _ = app.emit(
+            "git-clone-done",
+            CloneDoneEvent {
+                id,
+                success,
+                cancelled,
+                error,
+            },
+        );
+    });
+
+    Ok(id)
+}
+
+/// Cancel a running clone
+#[tauri::command]
+pub async fn cancel_clone(state: State<'_, CloneState>, id: u32) -> Result<(), String> {
+    let job = state.clones.lock().get(&id).cloned();
+
+    if let Some(job) = job {
+        job.cancelled.store(true, Ordering::SeqCst);
+        let _ = job.child.lock().kill();
+    }
+
+    Ok(())
+}
@@ -0,0 +1,82 @@
+//! Commit command
+//!
+//! Lets the orchestrator make checkpoint commits of agent work under a
+//! distinct identity, so agent commits are easy to tell apart from the
+//! user's own in history.
+
+use super::run_git_env;
+use serde::{Deserialize, Serialize};
+
+/// Name and email used for author and committer
+#[derive(Debug, Deserialize)]
+pub struct CommitIdentity {
+    name: String,
+    email: String,
+}
+
+/// Options for `git_commit`
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitOptions {
+    /// Identity for both author and committer (defaults to the user's git config)
+    author: Option<CommitIdentity>,
+    /// Add a Signed-off-by trailer
+    #[serde(default)]
+    signoff: bool,
+    /// Stage all modified and deleted tracked files first (`git commit --all`)
+    #[serde(default)]
+    all: bool,
+    /// Stage untracked files as well before committing
+    #[serde(default)]
+    include_untracked: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CommitResult {
+    hash: String,
+    summary: String,
+}
+
+/// Create a commit, optionally as a dedicated agent identity
+#[tauri::command]
+pub async fn git_commit(
+    repo: String,
+    message: String,
+    options: Option<CommitOptions>,
+) -> Result<CommitResult, String> {
+    let options = options.unwrap_or_default();
+
+    let mut envs = Vec::new();
+    if let Some(identity) = options.author.as_ref() {
+        envs.extend([
+            ("GIT_AUTHOR_NAME", identity.name.as_str()),
+            ("GIT_AUTHOR_EMAIL", identity.email.as_str()),
+            ("GIT_COMMITTER_NAME", identity.name.as_str()),
+            ("GIT_COMMITTER_EMAIL", identity.email.as_str()),
+        ]);
+    }
+
+    if options.include_untracked {
+        run_git_env(&repo, &["add", "--all"], &envs)?;
+    }
+
+    let mut args = vec!["commit", "--message", message.as_str()];
+    if options.all {
+        args.push("--all");
+    }
+    if options.signoff {
+        args.push("--signoff");
+    }
+    run_git_env(&repo, &args, &envs)?;
+
+    let output = run_git_env(&repo, &["log", "-1", "--format=%H%x1f%s"], &[])?;
+    let (hash, summary) = output
+        .trim_end()
+        .split_once('\x1f')
+        .unwrap_or((output.trim_end(), ""));
+
+    Ok(CommitResult {
+        hash: hash.to_string(),
+        summary: summary.to_string(),
+    })
+}
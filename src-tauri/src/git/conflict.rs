@@ -0,0 +1,84 @@
+//! Conflict detection
+//!
+//! Inspects a worktree for an interrupted merge, rebase or cherry-pick and
+//! for conflicted index entries, emitting `git-conflict` when any are found
+//! so the card can show that the agent is stuck on a conflict.
+
+use super::backend::GitBackendState;
+use super::status::{cli_status, native_operation, RepoOperation};
+use git2::Repository;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, State};
+
+/// Conflict state of a repository or worktree
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConflictState {
+    repo: String,
+    operation: Option<RepoOperation>,
+    files: Vec<String>,
+}
+
+impl ConflictState {
+    pub(crate) fn has_conflicts(&self) -> bool {
+        !self.files.is_empty()
+    }
+}
+
+fn native_conflicts(repo: &str) -> Result<ConflictState, git2::Error> {
+    let repository = Repository::open(repo)?;
+    let index = repository.index()?;
+
+    let mut files = Vec::new();
+    if index.has_conflicts() {
+        for conflict in index.conflicts()? {
+            let conflict = conflict?;
+            // Any side carries the path; a file deleted on one side has no entry there
+            if let Some(entry) = conflict.our.or(conflict.their).or(conflict.ancestor) {
+                files.push(String::from_utf8_lossy(&entry.path).into_owned());
+            }
+        }
+    }
+
+    Ok(ConflictState {
+        repo: repo.to_string(),
+        operation: native_operation(repository.state()),
+        files,
+    })
+}
+
+fn cli_conflicts(repo: &str) -> Result<ConflictState, String> {
+    let status = cli_status(repo)?;
+    Ok(ConflictState {
+        repo: repo.to_string(),
+        operation: status.operation,
+        files: status.conflicted,
+    })
+}
+
+pub(crate) fn conflict_state(
+    backend: &GitBackendState,
+    repo: &str,
+) -> Result<ConflictState, String> {
+    backend.run(
+        "check_conflicts",
+        || native_conflicts(repo),
+        || cli_conflicts(repo),
+    )
+}
+
+/// Check a worktree for conflicts, emitting `git-conflict` if there are any
+#[tauri::command]
+pub async fn check_conflicts(
+    app: AppHandle,
+    state: State<'_, GitBackendState>,
+    repo: String,
+) -> Result<ConflictState, String> {
+    let conflicts = conflict_state(&state, &repo)?;
+
+    if conflicts.has_conflicts() {
+        let _ = app.emit("git-conflict", conflicts.clone());
+    }
+
+    Ok(conflicts)
+}
@@ -0,0 +1,485 @@
+//! Diff command
+//!
+//! Parses unified diff output into files, hunks and numbered lines so the
+//! frontend can render a diff without shipping its own parser.
+
+use super::backend::GitBackendState;
+use super::run_git;
+use git2::{Delta, DiffFormat, Repository};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+/// Options for `git_diff`
+#[derive(Debug, Default, Deserialize)]
+pub struct DiffOptions {
+    /// Diff the index against HEAD instead of the worktree against the index
+    #[serde(default)]
+    staged: bool,
+    /// Limit the diff to a single path
+    path: Option<String>,
+    /// Compare against this commit or branch instead of the index
+    base: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffFileStatus {
+    Added,
+    Deleted,
+    Modified,
+    Renamed,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffLineKind {
+    Context,
+    Added,
+    Removed,
+}
+
+/// A single line of a hunk with its line numbers on each side
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffLine {
+    pub(crate) kind: DiffLineKind,
+    pub(crate) content: String,
+    pub(crate) old_line: Option<u32>,
+    pub(crate) new_line: Option<u32>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffHunk {
+    pub(crate) old_start: u32,
+    pub(crate) old_lines: u32,
+    pub(crate) new_start: u32,
+    pub(crate) new_lines: u32,
+    pub(crate) header: String,
+    pub(crate) lines: Vec<DiffLine>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffFile {
+    old_path: Option<String>,
+    new_path: Option<String>,
+    status: DiffFileStatus,
+    binary: bool,
+    hunks: Vec<DiffHunk>,
+}
+
+impl DiffFile {
+    fn from_header(line: &str) -> Self {
+        // "diff --git a/<old> b/<new>" - only a fallback, the ---/+++ lines win
+        let paths = line.strip_prefix("diff --git a/").unwrap_or_default();
+        let (old, new) = paths.split_once(" b/").unwrap_or((paths, paths));
+        Self {
+            old_path: Some(old.to_string()),
+            new_path: Some(new.to_string()),
+            status: DiffFileStatus::Modified,
+            binary: false,
+            hunks: Vec::new(),
+        }
+    }
+}
+
+/// Parse "-a,b" / "+c,d" ranges where the count defaults to 1
+fn parse_range(range: &str) -> (u32, u32) {
+    let range = range.get(1..).unwrap_or_default();
+    match range.split_once(',') {
+        Some((start, count)) => (start.parse().unwrap_or(0), count.parse().unwrap_or(0)),
+        None => (range.parse().unwrap_or(0), 1),
+    }
+}
+
+fn parse_hunk_header(line: &str) -> Option<DiffHunk> {
+    // "@@ -a,b +c,d @@ optional section header"
+    let rest = line.strip_prefix("@@ ")?;
+    let (ranges, header) = rest.split_once(" @@")?;
+    let (old, new) = ranges.split_once(' ')?;
+    let (old_start, old_lines) = parse_range(old);
+    let (new_start, new_lines) = parse_range(new);
+
+    Some(DiffHunk {
+        old_start,
+        old_lines,
+        new_start,
+        new_lines,
+        header: header.trim().to_string(),
+        lines: Vec::new(),
+    })
+}
+
+/// Strip the "a/" or "b/" prefix from ---/+++ paths, mapping /dev/null to None
+fn diff_path(path: &str) -> Option<String> {
+    if path == "/dev/null" {
+        return None;
+    }
+    let path = path
+        .strip_prefix("a/")
+        .or_else(|| path.strip_prefix("b/"))
+        .unwrap_or(path);
+    Some(path.to_string())
+}
+
+pub(crate) fn parse_diff(output: &str) -> Vec<DiffFile> {
+    let mut files: Vec<DiffFile> = Vec::new();
+    // Line numbers of the next line on each side and how many hunk lines are left,
+    // so removed lines starting with "--" are never mistaken for file headers
+    let (mut old_line, mut new_line) = (0, 0);
+    let (mut old_left, mut new_left) = (0u32, 0u32);
+
+    for line in output.lines() {
+        if old_left > 0 || new_left > 0 {
+            let Some(hunk) = files.last_mut().and_then(|f| f.hunks.last_mut()) else {
+                continue;
+            };
+            let (kind, content) = match line.chars().next() {
+                Some('+') => (DiffLineKind::Added, &line[1..]),
+                Some('-') => (DiffLineKind::Removed, &line[1..]),
+                Some('\\') => continue, // "\ No newline at end of file"
+                Some(' ') => (DiffLineKind::Context, &line[1..]),
+                _ => (DiffLineKind::Context, line),
+            };
+            let (old, new) = match kind {
+                DiffLineKind::Context => (Some(old_line), Some(new_line)),
+                DiffLineKind::Added => (None, Some(new_line)),
+                DiffLineKind::Removed => (Some(old_line), None),
+            };
+            if old.is_some() {
+                old_line += 1;
+                old_left = old_left.saturating_sub(1);
+            }
+            if new.is_some() {
+                new_line += 1;
+                new_left = new_left.saturating_sub(1);
+            }
+            hunk.lines.push(DiffLine {
+                kind,
+                content: content.to_string(),
+                old_line: old,
+                new_line: new,
+            });
+            continue;
+        }
+
+        if line.starts_with("diff --git ") {
+            files.push(DiffFile::from_header(line));
+            continue;
+        }
+
+        let Some(file) = files.last_mut() else {
+            continue;
+        };
+
+        if line.starts_with("@@") {
+            if let Some(hunk) = parse_hunk_header(line) {
+                (old_line, new_line) = (hunk.old_start, hunk.new_start);
+                (old_left, new_left) = (hunk.old_lines, hunk.new_lines);
+                file.hunks.push(hunk);
+            }
+        } else if line.starts_with("new file mode") {
+            file.status = DiffFileStatus::Added;
+            file.old_path = None;
+        } else if line.starts_with("deleted file mode") {
+            file.status = DiffFileStatus::Deleted;
+            file.new_path = None;
+        } else if let Some(path) = line.strip_prefix("rename from ") {
+            file.status = DiffFileStatus::Renamed;
+            file.old_path = Some(path.to_string());
+        } else if let Some(path) = line.strip_prefix("rename to ") {
+            file.new_path = Some(path.to_string());
+        } else if let Some(path) = line.strip_prefix("--- ") {
+            file.old_path = diff_path(path);
+        } else if let Some(path) = line.strip_prefix("+++ ") {
+            file.new_path = diff_path(path);
+        } else if line.starts_with("Binary files ") {
+            file.binary = true;
+        }
+    }
+
+    files
+}
+
+fn cli_diff(repo: &str, options: &DiffOptions) -> Result<Vec<DiffFile>, String> {
+    let mut args = vec![
+        "-c",
+        "core.quotePath=false",
+        "diff",
+        "--no-color",
+        "--no-ext-diff",
+        "--find-renames",
+    ];
+    if options.staged {
+        args.push("--cached");
+    }
+    if let Some(base) = options.base.as_deref() {
+        if base.starts_with('-') {
+            return Err(format!("'{}' is not a revision", base));
+        }
+        args.push(base);
+    }
+    // Whatever follows is a path, never a revision
+    args.push("--");
+    if let Some(path) = options.path.as_deref() {
+        args.push(path);
+    }
+
+    let output = run_git(repo, &args)?;
+    Ok(parse_diff(&output))
+}
+
+fn delta_path(file: git2::DiffFile<'_>) -> Option<String> {
+    file.path().map(|p| p.to_string_lossy().into_owned())
+}
+
+fn native_diff(repo: &str, options: &DiffOptions) -> Result<Vec<DiffFile>, git2::Error> {
+    let repository = Repository::open(repo)?;
+
+    let mut diff_options = git2::DiffOptions::new();
+    if let Some(path) = options.path.as_deref() {
+        diff_options.pathspec(path);
+    }
+
+    let base_tree = match options.base.as_deref() {
+        Some(base) => Some(repository.revparse_single(base)?.peel_to_tree()?),
+        // Unborn HEAD: everything staged is an addition
+        None if options.staged => repository.head().ok().and_then(|h| h.peel_to_tree().ok()),
+        None => None,
+    };
+
+    let mut diff = match (options.staged, base_tree.as_ref()) {
+        (true, tree) => repository.diff_tree_to_index(tree, None, Some(&mut diff_options))?,
+        (false, Some(tree)) => {
+            repository.diff_tree_to_workdir_with_index(Some(tree), Some(&mut diff_options))?
+        }
+        (false, None) => repository.diff_index_to_workdir(None, Some(&mut diff_options))?,
+    };
+    diff.find_similar(None)?;
+
+    let mut files: Vec<DiffFile> = Vec::new();
+    diff.print(DiffFormat::Patch, |delta, hunk, line| {
+        match line.origin() {
+            'F' => {
+                let status = match delta.status() {
+                    Delta::Added | Delta::Untracked => DiffFileStatus::Added,
+                    Delta::Deleted => DiffFileStatus::Deleted,
+                    Delta::Renamed => DiffFileStatus::Renamed,
+                    _ => DiffFileStatus::Modified,
+                };
+                files.push(DiffFile {
+                    old_path: (status != DiffFileStatus::Added)
+                        .then(|| delta_path(delta.old_file()))
+                        .flatten(),
+                    new_path: (status != DiffFileStatus::Deleted)
+                        .then(|| delta_path(delta.new_file()))
+                        .flatten(),
+                    status,
+                    binary: delta.flags().is_binary(),
+                    hunks: Vec::new(),
+                });
+            }
+            'H' => {
+                if let (Some(file), Some(hunk)) = (files.last_mut(), hunk) {
+                    let header = String::from_utf8_lossy(hunk.header());
+                    let header = header.split_once(" @@").map(|(_, h)| h).unwrap_or_default();
+                    file.hunks.push(DiffHunk {
+                        old_start: hunk.old_start(),
+                        old_lines: hunk.old_lines(),
+                        new_start: hunk.new_start(),
+                        new_lines: hunk.new_lines(),
+                        header: header.trim().to_string(),
+                        lines: Vec::new(),
+                    });
+                }
+            }
+            'B' => {
+                if let Some(file) = files.last_mut() {
+                    file.binary = true;
+                }
+            }
+            origin @ (' ' | '+' | '-') => {
+                if let Some(hunk) = files.last_mut().and_then(|f| f.hunks.last_mut()) {
+                    let kind = match origin {
+                        '+' => DiffLineKind::Added,
+                        '-' => DiffLineKind::Removed,
+                        _ => DiffLineKind::Context,
+                    };
+                    let content = String::from_utf8_lossy(line.content());
+                    hunk.lines.push(DiffLine {
+                        kind,
+                        content: content.trim_end_matches(['\n', '\r']).to_string(),
+                        old_line: line.old_lineno(),
+                        new_line: line.new_lineno(),
+                    });
+                }
+            }
+            _ => {}
+        }
+        true
+    })?;
+
+    Ok(files)
+}
+
+/// Get the parsed diff of a repository or worktree
+#[tauri::command]
+pub async fn git_diff(
+    app: AppHandle,
+    repo: String,
+    options: Option<DiffOptions>,
+) -> Result<Vec<DiffFile>, String> {
+    let options = options.unwrap_or_default();
+    super::blocking("git_diff", move || {
+        app.state::<GitBackendState>().run(
+            "git_diff",
+            || native_diff(&repo, &options),
+            || cli_diff(&repo, &options),
+        )
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(hunk: &DiffHunk) -> Vec<(DiffLineKind, &str, Option<u32>, Option<u32>)> {
+        hunk.lines
+            .iter()
+            .map(|l| (l.kind, l.content.as_str(), l.old_line, l.new_line))
+            .collect()
+    }
+
+    #[test]
+    fn parses_hunks_and_line_numbers() {
+        let output = "\
+diff --git a/src/a.rs b/src/a.rs
+index 1111111..2222222 100644
+--- a/src/a.rs
++++ b/src/a.rs
+@@ -1,3 +1,3 @@ fn main() {
+ one
+--- not a header
++++ not a header either
+ three
+@@ -10 +10,2 @@
+-last
+\\ No newline at end of file
++last
++more
+";
+        let files = parse_diff(output);
+        assert_eq!(files.len(), 1);
+        let file = &files[0];
+        assert_eq!(file.old_path.as_deref(), Some("src/a.rs"));
+        assert_eq!(file.new_path.as_deref(), Some("src/a.rs"));
+        assert_eq!(file.status, DiffFileStatus::Modified);
+        assert_eq!(file.hunks.len(), 2);
+        assert_eq!(file.hunks[0].header, "fn main() {");
+        assert_eq!(
+            lines(&file.hunks[0]),
+            [
+                (DiffLineKind::Context, "one", Some(1), Some(1)),
+                (DiffLineKind::Removed, "-- not a header", Some(2), None),
+                (DiffLineKind::Added, "++ not a header either", None, Some(2)),
+                (DiffLineKind::Context, "three", Some(3), Some(3)),
+            ]
+        );
+        let second = &file.hunks[1];
+        assert_eq!((second.old_start, second.old_lines), (10, 1));
+        assert_eq!((second.new_start, second.new_lines), (10, 2));
+        assert_eq!(
+            lines(second),
+            [
+                (DiffLineKind::Removed, "last", Some(10), None),
+                (DiffLineKind::Added, "last", None, Some(10)),
+                (DiffLineKind::Added, "more", None, Some(11)),
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_file_headers() {
+        let output = "\
+diff --git a/new.rs b/new.rs
+new file mode 100644
+--- /dev/null
++++ b/new.rs
+@@ -0,0 +1 @@
++created
+diff --git a/gone.rs b/gone.rs
+deleted file mode 100644
+--- a/gone.rs
++++ /dev/null
+diff --git a/old name.rs b/new name.rs
+similarity index 100%
+rename from old name.rs
+rename to new name.rs
+diff --git a/image.png b/image.png
+Binary files a/image.png and b/image.png differ
+";
+        let files = parse_diff(output);
+        let summary: Vec<_> = files
+            .iter()
+            .map(|f| {
+                (
+                    f.old_path.as_deref(),
+                    f.new_path.as_deref(),
+                    f.status,
+                    f.binary,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (None, Some("new.rs"), DiffFileStatus::Added, false),
+                (Some("gone.rs"), None, DiffFileStatus::Deleted, false),
+                (
+                    Some("old name.rs"),
+                    Some("new name.rs"),
+                    DiffFileStatus::Renamed,
+                    false
+                ),
+                (
+                    Some("image.png"),
+                    Some("image.png"),
+                    DiffFileStatus::Modified,
+                    true
+                ),
+            ]
+        );
+        assert_eq!(
+            lines(&files[0].hunks[0]),
+            [(DiffLineKind::Added, "created", None, Some(1))]
+        );
+    }
+
+    #[test]
+    fn survives_malformed_input() {
+        let output = "\
++orphan line
+@@ -1 +1 @@
+diff --git a/a b/a
+@@ garbage
+@@  +1 @@
+ x
+@@ \u{e9} +1 @@
+ y
+@@ -1,0 +1,1 @@
+-removed although the hunk has no old lines
+ context
+diff --git a/b b/b
+";
+        let files = parse_diff(output);
+        assert_eq!(files.len(), 2);
+        let hunks = &files[0].hunks;
+        assert_eq!(hunks.len(), 3);
+        assert_eq!((hunks[0].old_start, hunks[0].old_lines), (0, 1));
+        assert_eq!((hunks[1].old_start, hunks[1].old_lines), (0, 1));
+        assert_eq!(hunks[2].lines.len(), 2);
+        assert!(files[1].hunks.is_empty());
+    }
+}
@@ -0,0 +1,130 @@
+//! Repository discovery
+//!
+//! Walks directories looking for git repositories to offer in the project
+//! picker. Found repositories are not descended into, and well-known build
+//! and dependency directories are skipped.
+
+use git2::Repository;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::Path;
+use walkdir::WalkDir;
+
+/// How deep below each root to look when no depth is given
+const DEFAULT_MAX_DEPTH: usize = 4;
+
+/// Directory names never worth descending into
+const DEFAULT_IGNORES: [&str; 8] = [
+    "node_modules",
+    "target",
+    "dist",
+    "build",
+    "vendor",
+    ".worktrees",
+    "Library",
+    "AppData",
+];
+
+/// Options for `discover_repos`
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscoverOptions {
+    max_depth: Option<usize>,
+    /// Extra directory names to skip
+    #[serde(default)]
+    ignore: Vec<String>,
+    /// Descend into dot-directories
+    #[serde(default)]
+    include_hidden: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscoveredRepo {
+    path: String,
+    name: String,
+    branch: Option<String>,
+    remote_url: Option<String>,
+    last_commit_time: Option<i64>,
+}
+
+fn describe(path: &Path) -> Option<DiscoveredRepo> {
+    let repository = Repository::open(path).ok()?;
+    let head = repository.head().ok();
+
+    Some(DiscoveredRepo {
+        path: path.to_string_lossy().into_owned(),
+        name: path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        branch: head
+            .as_ref()
+            .filter(|h| h.is_branch())
+            .and_then(|h| h.shorthand())
+            .map(str::to_string),
+        remote_url: repository
+            .find_remote("origin")
+            .ok()
+            .and_then(|r| r.url().map(str::to_string)),
+        last_commit_time: head
+            .and_then(|h| h.peel_to_commit().ok())
+            .map(|c| c.time().seconds()),
+    })
+}
+
+fn discover(roots: Vec<String>, options: DiscoverOptions) -> Vec<DiscoveredRepo> {
+    let ignores: HashSet<String> = DEFAULT_IGNORES
+        .iter()
+        .map(|s| s.to_string())
+        .chain(options.ignore)
+        .collect();
+    let max_depth = options.max_depth.unwrap_or(DEFAULT_MAX_DEPTH);
+
+    let mut repos = Vec::new();
+    for root in roots {
+        let mut walker = WalkDir::new(&root).max_depth(max_depth).into_iter();
+
+        while let Some(entry) = walker.next() {
+            let Ok(entry) = entry else {
+                continue;
+            };
+            if !entry.file_type().is_dir() {
+                continue;
+            }
+
+            // Root itself is never filtered by name
+            if entry.depth() > 0 {
+                let name = entry.file_name().to_string_lossy();
+                if ignores.contains(name.as_ref())
+                    || (!options.include_hidden && name.starts_with('.'))
+                {
+                    walker.skip_current_dir();
+                    continue;
+                }
+            }
+
+            // `.git` is a directory for clones and a file for linked worktrees
+            if entry.path().join(".git").exists() {
+                if let Some(repo) = describe(entry.path()) {
+                    repos.push(repo);
+                }
+                walker.skip_current_dir();
+            }
+        }
+    }
+
+    repos
+}
+
+/// Find git repositories under the given root directories
+#[tauri::command]
+pub async fn discover_repos(
+    roots: Vec<String>,
+    options: Option<DiscoverOptions>,
+) -> Result<Vec<DiscoveredRepo>, String> {
+    let options = options.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || discover(roots, options))
+        .await
+        .map_err(|e| format!("Repository discovery failed: {}", e))
+}
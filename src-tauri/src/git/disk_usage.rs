@@ -0,0 +1,111 @@
+//! Worktree disk usage
+//!
+//! Sizes every worktree directory under a worktree base on a blocking thread,
+//! emitting `worktree-disk-usage-progress` after each one. Sizes are allocated
+//! disk space on Unix and apparent file size elsewhere; symlinks aren't followed.
+
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use tauri::{AppHandle, Emitter};
+use walkdir::WalkDir;
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorktreeSize {
+    path: String,
+    name: String,
+    bytes: u64,
+    files: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiskUsageReport {
+    base: String,
+    total_bytes: u64,
+    worktrees: Vec<WorktreeSize>,
+}
+
+/// Event payload emitted after each worktree has been measured
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DiskUsageProgressEvent {
+    base: String,
+    done: usize,
+    total: usize,
+    worktree: WorktreeSize,
+}
+
+#[cfg(unix)]
+pub(crate) fn allocated_size(meta: &fs::Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    meta.blocks() * 512
+}
+
+#[cfg(not(unix))]
+pub(crate) fn allocated_size(meta: &fs::Metadata) -> u64 {
+    meta.len()
+}
+
+fn measure(path: &Path) -> (u64, u64) {
+    WalkDir::new(path)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| entry.metadata().ok())
+        .fold((0, 0), |(bytes, files), meta| {
+            (bytes + allocated_size(&meta), files + 1)
+        })
+}
+
+fn disk_usage(app: &AppHandle, base: &str) -> Result<DiskUsageReport, String> {
+    let mut dirs: Vec<_> = fs::read_dir(base)
+        .map_err(|e| format!("Failed to read {}: {}", base, e))?
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().map(|t| t.is_dir()).unwrap_or(false))
+        .map(|entry| entry.path())
+        .collect();
+    dirs.sort();
+
+    let total = dirs.len();
+    let mut worktrees = Vec::with_capacity(total);
+
+    for (idx, dir) in dirs.into_iter().enumerate() {
+        let (bytes, files) = measure(&dir);
+        let worktree = WorktreeSize {
+            path: dir.to_string_lossy().into_owned(),
+            name: dir
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            bytes,
+            files,
+        };
+
+        let _ = app.emit(
+            "worktree-disk-usage-progress",
+            DiskUsageProgressEvent {
+                base: base.to_string(),
+                done: idx + 1,
+                total,
+                worktree: worktree.clone(),
+            },
+        );
+        worktrees.push(worktree);
+    }
+
+    Ok(DiskUsageReport {
+        base: base.to_string(),
+        total_bytes: worktrees.iter().map(|w| w.bytes).sum(),
+        worktrees,
+    })
+}
+
+/// Compute the size of each worktree under `base`
+#[tauri::command]
+pub async fn worktree_disk_usage(app: AppHandle, base: String) -> Result<DiskUsageReport, String> {
+    tauri::async_runtime::spawn_blocking(move || disk_usage(&app, &base))
+        .await
+        .map_err(|e| format!("Disk usage computation failed: {}", e))?
+}
@@ -0,0 +1,215 @@
+//! Branch drift tracking
+//!
+//! Computes how far each issue branch is ahead of and behind its base branch,
+//! either on demand or periodically via `branch-drift-updated` events.
+
+use super::backend::GitBackendState;
+use super::branch::branches;
+use super::{ref_exists, run_git};
+use git2::Repository;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// Polling interval when tracking is started without one
+const DEFAULT_INTERVAL_SECS: u64 = 300;
+
+/// State for periodic drift trackers
+pub struct DriftState {
+    trackers: Mutex<HashMap<u32, Arc<AtomicBool>>>,
+    next_id: AtomicU32,
+}
+
+impl Default for DriftState {
+    fn default() -> Self {
+        Self {
+            trackers: Mutex::new(HashMap::new()),
+            next_id: AtomicU32::new(1),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BranchDrift {
+    branch: String,
+    issue_number: u64,
+    base: String,
+    ahead: usize,
+    behind: usize,
+}
+
+/// Options for `start_drift_tracking`
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DriftTrackingOptions {
+    base: Option<String>,
+    interval_secs: Option<u64>,
+}
+
+/// Event payload for periodic drift updates
+#[derive(Clone, Serialize)]
+struct DriftUpdatedEvent {
+    id: u32,
+    repo: String,
+    branches: Vec<BranchDrift>,
+}
+
+/// Resolve the base branch: the remote's default branch, else main or master
+pub(crate) fn default_base(repo: &str) -> Result<String, String> {
+    if let Ok(output) = run_git(
+        repo,
+        &["symbolic-ref", "--short", "-q", "refs/remotes/origin/HEAD"],
+    ) {
+        let base = output.trim();
+        if !base.is_empty() {
+            return Ok(base.to_string());
+        }
+    }
+
+    ["main", "master"]
+        .into_iter()
+        .find(|name| ref_exists(repo, &format!("refs/heads/{}", name)))
+        .map(str::to_string)
+        .ok_or_else(|| "Could not determine the base branch".to_string())
+}
+
+fn native_counts(
+    repo: &str,
+    base: &str,
+    heads: &[(String, String)],
+) -> Result<Vec<(usize, usize)>, git2::Error> {
+    let repository = Repository::open(repo)?;
+    let base_oid = repository.revparse_single(base)?.peel_to_commit()?.id();
+
+    heads
+        .iter()
+        .map(|(_, head)| repository.graph_ahead_behind(git2::Oid::from_str(head)?, base_oid))
+        .collect()
+}
+
+fn cli_counts(
+    repo: &str,
+    base: &str,
+    heads: &[(String, String)],
+) -> Result<Vec<(usize, usize)>, String> {
+    heads
+        .iter()
+        .map(|(name, _)| {
+            let range = format!("{}...{}", base, name);
+            let output = run_git(repo, &["rev-list", "--left-right", "--count", &range])?;
+            let mut counts = output.split_whitespace().map(|n| n.parse().unwrap_or(0));
+            let behind = counts.next().unwrap_or(0);
+            let ahead = counts.next().unwrap_or(0);
+            Ok((ahead, behind))
+        })
+        .collect()
+}
+
+pub(crate) fn drift(
+    backend: &GitBackendState,
+    repo: &str,
+    base: Option<&str>,
+) -> Result<Vec<BranchDrift>, String> {
+    let base = match base {
+        Some(base) => base.to_string(),
+        None => default_base(repo)?,
+    };
+
+    let issue_branches: Vec<(String, String, u64)> = branches(repo)?
+        .into_iter()
+        .filter_map(|b| Some((b.name, b.head, b.issue_number?)))
+        .collect();
+    let heads: Vec<(String, String)> = issue_branches
+        .iter()
+        .map(|(name, head, _)| (name.clone(), head.clone()))
+        .collect();
+
+    let counts = backend.run(
+        "branch_drift",
+        || native_counts(repo, &base, &heads),
+        || cli_counts(repo, &base, &heads),
+    )?;
+
+    Ok(issue_branches
+        .into_iter()
+        .zip(counts)
+        .map(|((branch, _, issue_number), (ahead, behind))| BranchDrift {
+            branch,
+            issue_number,
+            base: base.clone(),
+            ahead,
+            behind,
+        })
+        .collect())
+}
+
+/// Ahead/behind counts of every issue branch versus `base`
+#[tauri::command]
+pub async fn branch_drift(
+    state: State<'_, GitBackendState>,
+    repo: String,
+    base: Option<String>,
+) -> Result<Vec<BranchDrift>, String> {
+    drift(&state, &repo, base.as_deref())
+}
+
+/// Periodically emit `branch-drift-updated` events for a repository
+#[tauri::command]
+pub async fn start_drift_tracking(
+    app: AppHandle,
+    state: State<'_, DriftState>,
+    repo: String,
+    options: Option<DriftTrackingOptions>,
+) -> Result<u32, String> {
+    let options = options.unwrap_or_default();
+    let interval = Duration::from_secs(
+        options
+            .interval_secs
+            .unwrap_or(DEFAULT_INTERVAL_SECS)
+            .max(1),
+    );
+
+    let id = state.next_id.fetch_add(1, Ordering::SeqCst);
+    let stopped = Arc::new(AtomicBool::new(false));
+    state.trackers.lock().insert(id, stopped.clone());
+
+    thread::spawn(move || {
+        while !stopped.load(Ordering::SeqCst) {
+            match drift(
+                &app.state::<GitBackendState>(),
+                &repo,
+                options.base.as_deref(),
+            ) {
+                Ok(branches) => {
+                    let _ = app.emit(
+                        "branch-drift-updated",
+                        DriftUpdatedEvent {
+                            id,
+                            repo: repo.clone(),
+                            branches,
+                        },
+                    );
+                }
+                Err(e) => eprintln!("Branch drift check failed for {}: {}", repo, e),
+            }
+            thread::sleep(crate::power::stretch(&app, interval));
+        }
+    });
+
+    Ok(id)
+}
+
+/// Stop periodic drift tracking
+#[tauri::command]
+pub async fn stop_drift_tracking(state: State<'_, DriftState>, id: u32) -> Result<(), String> {
+    if let Some(stopped) = state.trackers.lock().remove(&id) {
+        stopped.store(true, Ordering::SeqCst);
+    }
+    Ok(())
+}
@@ -0,0 +1,172 @@
+//! Antler-managed git hooks
+//!
+//! Hooks are installed into the directory git actually uses (respecting
+//! `core.hooksPath`), which is shared by all worktrees of a repository.
+//! A user hook that is already in place is moved aside to
+//! `<hook>.antler-chained` and run first, so installing never clobbers it
+//! and uninstalling restores it.
+//!
+//! - `pre-commit` snapshots the worktree into `refs/worktree/antler-checkpoint`
+//!   (a per-worktree ref) before each commit.
+//! - `post-commit` appends the new commit to `antler/hook-events` in the
+//!   common git directory for Antler to pick up.
+
+use super::run_git;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Marker line identifying hooks written by Antler
+const HOOK_MARKER: &str = "# antler-managed-hook";
+
+/// Suffix for user hooks that Antler chains to
+const CHAINED_SUFFIX: &str = ".antler-chained";
+
+const MANAGED_HOOKS: [&str; 2] = ["pre-commit", "post-commit"];
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HookStatus {
+    name: String,
+    installed: bool,
+    /// A pre-existing user hook is chained before Antler's
+    chained: bool,
+    /// A user hook exists that is not managed by Antler
+    user_hook: bool,
+}
+
+fn hook_body(name: &str) -> &'static str {
+    match name {
+        "pre-commit" => {
+            r#"sha=$(git stash create "antler checkpoint") && [ -n "$sha" ] && git update-ref refs/worktree/antler-checkpoint "$sha"
+exit 0"#
+        }
+        "post-commit" => {
+            r#"dir="$(git rev-parse --git-common-dir)/antler"
+mkdir -p "$dir" && printf 'commit %s %s\n' "$(git rev-parse HEAD)" "$(git rev-parse --show-toplevel)" >> "$dir/hook-events"
+exit 0"#
+        }
+        _ => "exit 0",
+    }
+}
+
+fn hook_script(name: &str) -> String {
+    format!(
+        "#!/bin/sh\n{marker}\n\n# Run the hook that was here before Antler\nif [ -x \"$0{suffix}\" ]; then\n  \"$0{suffix}\" \"$@\" || exit $?\nfi\n\n{body}\n",
+        marker = HOOK_MARKER,
+        suffix = CHAINED_SUFFIX,
+        body = hook_body(name),
+    )
+}
+
+fn hooks_dir(repo: &str) -> Result<PathBuf, String> {
+    let output = run_git(
+        repo,
+        &["rev-parse", "--path-format=absolute", "--git-path", "hooks"],
+    )?;
+    Ok(PathBuf::from(output.trim()))
+}
+
+fn is_managed(path: &Path) -> bool {
+    fs::read_to_string(path)
+        .map(|content| content.contains(HOOK_MARKER))
+        .unwrap_or(false)
+}
+
+fn chained_path(path: &Path) -> PathBuf {
+    let mut chained = path.as_os_str().to_owned();
+    chained.push(CHAINED_SUFFIX);
+    PathBuf::from(chained)
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> Result<(), String> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755))
+        .map_err(|e| format!("Failed to make hook executable: {}", e))
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> Result<(), String> {
+    Ok(())
+}
+
+fn status_of(dir: &Path, name: &str) -> HookStatus {
+    let path = dir.join(name);
+    let installed = is_managed(&path);
+    HookStatus {
+        name: name.to_string(),
+        installed,
+        chained: installed && chained_path(&path).exists(),
+        user_hook: path.exists() && !installed,
+    }
+}
+
+/// Install Antler's hooks, chaining any existing user hooks
+#[tauri::command]
+pub async fn install_hooks(repo: String) -> Result<Vec<HookStatus>, String> {
+    let dir = hooks_dir(&repo)?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create hooks directory: {}", e))?;
+
+    for name in MANAGED_HOOKS {
+        let path = dir.join(name);
+
+        if path.exists() && !is_managed(&path) {
+            let chained = chained_path(&path);
+            if chained.exists() {
+                return Err(format!(
+                    "Cannot install {} hook: {} already exists",
+                    name,
+                    chained.display()
+                ));
+            }
+            fs::rename(&path, &chained)
+                .map_err(|e| format!("Failed to move existing {} hook aside: {}", name, e))?;
+        }
+
+        fs::write(&path, hook_script(name))
+            .map_err(|e| format!("Failed to write {} hook: {}", name, e))?;
+        make_executable(&path)?;
+    }
+
+    Ok(MANAGED_HOOKS
+        .iter()
+        .map(|name| status_of(&dir, name))
+        .collect())
+}
+
+/// Remove Antler's hooks and restore chained user hooks
+#[tauri::command]
+pub async fn uninstall_hooks(repo: String) -> Result<Vec<HookStatus>, String> {
+    let dir = hooks_dir(&repo)?;
+
+    for name in MANAGED_HOOKS {
+        let path = dir.join(name);
+        if !is_managed(&path) {
+            continue;
+        }
+
+        fs::remove_file(&path).map_err(|e| format!("Failed to remove {} hook: {}", name, e))?;
+
+        let chained = chained_path(&path);
+        if chained.exists() {
+            fs::rename(&chained, &path)
+                .map_err(|e| format!("Failed to restore {} hook: {}", name, e))?;
+        }
+    }
+
+    Ok(MANAGED_HOOKS
+        .iter()
+        .map(|name| status_of(&dir, name))
+        .collect())
+}
+
+/// Report whether Antler's hooks are installed and whether user hooks exist
+#[tauri::command]
+pub async fn hook_status(repo: String) -> Result<Vec<HookStatus>, String> {
+    let dir = hooks_dir(&repo)?;
+    Ok(MANAGED_HOOKS
+        .iter()
+        .map(|name| status_of(&dir, name))
+        .collect())
+}
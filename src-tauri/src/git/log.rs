@@ -0,0 +1,271 @@
+//! Log command
+//!
+//! Commit metadata plus per-file line counts from `git log --numstat`.
+
+use super::backend::GitBackendState;
+use super::run_git;
+use chrono::{DateTime, FixedOffset};
+use git2::{Patch, Repository, Sort};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+/// Commits returned when no limit is given
+const DEFAULT_LOG_LIMIT: u32 = 100;
+
+/// Options for `git_log`
+#[derive(Debug, Default, Deserialize)]
+pub struct LogOptions {
+    /// Branch or revision range to walk (defaults to HEAD)
+    branch: Option<String>,
+    /// Maximum number of commits
+    limit: Option<u32>,
+    /// Only commits newer than this date (anything `git log --since` accepts)
+    since: Option<String>,
+}
+
+/// A file touched by a commit; counts are None for binary files
+#[derive(Clone, Debug, Serialize)]
+pub struct CommitFile {
+    path: String,
+    additions: Option<u32>,
+    deletions: Option<u32>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitInfo {
+    hash: String,
+    author_name: String,
+    author_email: String,
+    date: String,
+    subject: String,
+    files: Vec<CommitFile>,
+}
+
+fn parse_log(output: &str) -> Vec<CommitInfo> {
+    // Each record starts with \x1e and has \x1f-separated header fields on its first line
+    output
+        .split('\x1e')
+        .filter(|record| !record.trim().is_empty())
+        .filter_map(|record| {
+            let mut lines = record.lines();
+            let mut fields = lines.next()?.split('\x1f');
+            let hash = fields.next()?.to_string();
+            let author_name = fields.next()?.to_string();
+            let author_email = fields.next()?.to_string();
+            let date = fields.next()?.to_string();
+            let subject = fields.next().unwrap_or_default().to_string();
+
+            let files = lines
+                .filter_map(|line| {
+                    let mut parts = line.splitn(3, '\t');
+                    let additions = parts.next()?;
+                    let deletions = parts.next()?;
+                    let path = parts.next()?;
+                    Some(CommitFile {
+                        path: path.to_string(),
+                        additions: additions.parse().ok(),
+                        deletions: deletions.parse().ok(),
+                    })
+                })
+                .collect();
+
+            Some(CommitInfo {
+                hash,
+                author_name,
+                author_email,
+                date,
+                subject,
+                files,
+            })
+        })
+        .collect()
+}
+
+fn cli_log(repo: &str, options: &LogOptions) -> Result<Vec<CommitInfo>, String> {
+    let limit = format!("--max-count={}", options.limit.unwrap_or(DEFAULT_LOG_LIMIT));
+    let since = options.since.as_ref().map(|s| format!("--since={}", s));
+
+    let mut args = vec![
+        "-c",
+        "core.quotePath=false",
+        "log",
+        "--no-color",
+        "--numstat",
+        "--format=%x1e%H%x1f%an%x1f%ae%x1f%aI%x1f%s",
+        limit.as_str(),
+    ];
+    if let Some(since) = since.as_deref() {
+        args.push(since);
+    }
+    args.push(options.branch.as_deref().unwrap_or("HEAD"));
+    args.push("--");
+
+    let output = run_git(repo, &args)?;
+    Ok(parse_log(&output))
+}
+
+/// Format a git timestamp like `%aI` (strict ISO 8601 in the author's timezone)
+pub(crate) fn format_time(time: git2::Time) -> String {
+    FixedOffset::east_opt(time.offset_minutes() * 60)
+        .zip(DateTime::from_timestamp(time.seconds(), 0))
+        .map(|(offset, utc)| utc.with_timezone(&offset).to_rfc3339())
+        .unwrap_or_default()
+}
+
+fn commit_files(
+    repository: &Repository,
+    commit: &git2::Commit<'_>,
+) -> Result<Vec<CommitFile>, git2::Error> {
+    // Like `git log --numstat`, merges don't list files
+    if commit.parent_count() > 1 {
+        return Ok(Vec::new());
+    }
+
+    let parent_tree = match commit.parent(0) {
+        Ok(parent) => Some(parent.tree()?),
+        Err(_) => None,
+    };
+    let mut diff =
+        repository.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+    diff.find_similar(None)?;
+
+    let mut files = Vec::new();
+    for (idx, delta) in diff.deltas().enumerate() {
+        let path = delta
+            .new_file()
+            .path()
+            .or_else(|| delta.old_file().path())
+            .map(|p| p.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        let counts = match Patch::from_diff(&diff, idx)? {
+            Some(patch) if !delta.flags().is_binary() => {
+                let (_, additions, deletions) = patch.line_stats()?;
+                Some((additions as u32, deletions as u32))
+            }
+            _ => None,
+        };
+
+        files.push(CommitFile {
+            path,
+            additions: counts.map(|c| c.0),
+            deletions: counts.map(|c| c.1),
+        });
+    }
+
+    Ok(files)
+}
+
+fn native_log(repo: &str, options: &LogOptions) -> Result<Vec<CommitInfo>, git2::Error> {
+    let repository = Repository::open(repo)?;
+    let mut walk = repository.revwalk()?;
+    walk.set_sorting(Sort::TIME)?;
+
+    let spec = options.branch.as_deref().unwrap_or("HEAD");
+    if spec.contains("..") {
+        walk.push_range(spec)?;
+    } else {
+        walk.push(repository.revparse_single(spec)?.peel_to_commit()?.id())?;
+    }
+
+    let limit = options.limit.unwrap_or(DEFAULT_LOG_LIMIT) as usize;
+    let mut commits = Vec::new();
+    for oid in walk.take(limit) {
+        let commit = repository.find_commit(oid?)?;
+        let author = commit.author();
+        commits.push(CommitInfo {
+            hash: commit.id().to_string(),
+            author_name: author.name().unwrap_or_default().to_string(),
+            author_email: author.email().unwrap_or_default().to_string(),
+            date: format_time(author.when()),
+            subject: commit.summary().unwrap_or_default().to_string(),
+            files: commit_files(&repository, &commit)?,
+        });
+    }
+
+    Ok(commits)
+}
+
+/// Get commit history with changed files
+#[tauri::command]
+pub async fn git_log(
+    app: AppHandle,
+    repo: String,
+    options: Option<LogOptions>,
+) -> Result<Vec<CommitInfo>, String> {
+    let options = options.unwrap_or_default();
+    super::blocking("git_log", move || {
+        let state = app.state::<GitBackendState>();
+        if options.since.is_some() {
+            // `--since` takes git's approxidate syntax ("2 weeks ago"), which
+            // libgit2 can't parse
+            return state.run_cli("git_log", || cli_log(&repo, &options));
+        }
+        state.run(
+            "git_log",
+            || native_log(&repo, &options),
+            || cli_log(&repo, &options),
+        )
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_commits_with_numstat() {
+        let output =
+            "\x1eaaaa\x1fAda\x1fada@example.com\x1f2024-05-01T10:00:00+02:00\x1fFix\tthings\n\
+            \n\
+            3\t1\tsrc/lib.rs\n\
+            -\t-\tlogo.png\n\
+            0\t2\tdir/with\ttab.txt\n\
+            \x1ebbbb\x1fBo\x1fbo@example.com\x1f2024-04-30T09:00:00Z\n";
+        let commits = parse_log(output);
+        assert_eq!(commits.len(), 2);
+
+        let first = &commits[0];
+        assert_eq!(first.hash, "aaaa");
+        assert_eq!(first.author_name, "Ada");
+        assert_eq!(first.author_email, "ada@example.com");
+        assert_eq!(first.date, "2024-05-01T10:00:00+02:00");
+        assert_eq!(first.subject, "Fix\tthings");
+        let files: Vec<_> = first
+            .files
+            .iter()
+            .map(|f| (f.path.as_str(), f.additions, f.deletions))
+            .collect();
+        assert_eq!(
+            files,
+            [
+                ("src/lib.rs", Some(3), Some(1)),
+                ("logo.png", None, None),
+                ("dir/with\ttab.txt", Some(0), Some(2)),
+            ]
+        );
+
+        assert_eq!(commits[1].subject, "");
+        assert!(commits[1].files.is_empty());
+    }
+
+    #[test]
+    fn skips_records_missing_header_fields() {
+        let commits = parse_log("garbage before\n\x1ecccc\x1fname only\n\x1e\n\x1edddd\x1fn\x1fe\x1fd\x1fs\nnot numstat\n");
+        let hashes: Vec<_> = commits.iter().map(|c| c.hash.as_str()).collect();
+        assert_eq!(hashes, ["dddd"]);
+        assert!(commits[0].files.is_empty());
+    }
+
+    #[test]
+    fn formats_times_in_their_own_offset() {
+        let time = git2::Time::new(1_700_000_000, -300);
+        assert_eq!(format_time(time), "2023-11-14T17:13:20-05:00");
+        assert_eq!(
+            format_time(git2::Time::new(0, 0)),
+            "1970-01-01T00:00:00+00:00"
+        );
+    }
+}
@@ -0,0 +1,186 @@
+//! Git module - native bridge for git operations
+//!
+//! Wraps git so the frontend receives structured results instead of
+//! assembling shell strings and parsing output in TypeScript. Read-heavy
+//! commands use libgit2 with a CLI fallback (see `backend`), everything else
+//! shells out to the git CLI.
+
+pub mod backend;
+pub mod blame;
+pub mod branch;
+pub mod cleanup;
+pub mod clone;
+pub mod commit;
+pub mod conflict;
+pub mod diff;
+pub mod discover;
+pub mod disk_usage;
+pub mod drift;
+pub mod hooks;
+pub mod log;
+pub mod pr;
+pub mod remote;
+pub mod stash;
+pub mod status;
+pub mod watch;
+pub mod worktree;
+
+use serde::Serialize;
+use std::io::Read;
+use std::process::{Command, Stdio};
+
+/// Name of the git subcommand in `args`, skipping `-c key=value` options
+fn subcommand<'a>(args: &[&'a str]) -> &'a str {
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if *arg == "-c" {
+            iter.next();
+        } else if !arg.starts_with('-') {
+            return arg;
+        }
+    }
+    ""
+}
+
+/// Run `f` off the async runtime, so a large repository doesn't hold up
+/// other commands
+pub(crate) async fn blocking<T: Send + 'static>(
+    op: &str,
+    f: impl FnOnce() -> Result<T, String> + Send + 'static,
+) -> Result<T, String> {
+    tauri::async_runtime::spawn_blocking(f)
+        .await
+        .map_err(|e| format!("{} failed: {}", op, e))?
+}
+
+/// Run a git command inside `repo` and return its stdout
+pub(crate) fn run_git(repo: &str, args: &[&str]) -> Result<String, String> {
+    run_git_env(repo, args, &[])
+}
+
+/// Run a git command inside `repo` with extra environment variables
+pub(crate) fn run_git_env(
+    repo: &str,
+    args: &[&str],
+    envs: &[(&str, &str)],
+) -> Result<String, String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(args)
+        .envs(envs.iter().copied())
+        .output()
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        // Some failures (e.g. "nothing to commit") are only reported on stdout
+        let message = if stderr.trim().is_empty() {
+            stdout
+        } else {
+            stderr
+        };
+        return Err(format!(
+            "git {} failed: {}",
+            subcommand(args),
+            message.trim()
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Serde default for options that are on unless turned off
+pub(crate) fn default_true() -> bool {
+    true
+}
+
+/// Make git fail fast instead of waiting on a password prompt nobody can see
+///
+/// Credentials still come from the configured credential helper (osxkeychain,
+/// Git Credential Manager, libsecret) and SSH keys from the running ssh-agent.
+pub(crate) fn non_interactive(cmd: &mut Command) -> &mut Command {
+    cmd.env("GIT_TERMINAL_PROMPT", "0")
+        .env("GCM_INTERACTIVE", "never")
+        .env("GIT_SSH_COMMAND", "ssh -o BatchMode=yes")
+        .stdin(Stdio::null())
+}
+
+/// Check whether a ref resolves in `repo` without treating failure as an error
+pub(crate) fn ref_exists(repo: &str, reference: &str) -> bool {
+    run_git(repo, &["rev-parse", "--verify", "--quiet", reference]).is_ok()
+}
+
+/// One progress update parsed from git's `--progress` output
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct GitProgress {
+    phase: String,
+    percent: Option<u32>,
+    current: Option<u64>,
+    total: Option<u64>,
+}
+
+/// Parse a progress line such as "Receiving objects:  45% (450/1000), 1.2 MiB | 3 MiB/s"
+pub(crate) fn parse_progress(line: &str) -> Option<GitProgress> {
+    let line = line.strip_prefix("remote: ").unwrap_or(line);
+    let (phase, rest) = line.split_once(':')?;
+    let phase = phase.trim().to_string();
+    let rest = rest.trim_start();
+
+    let percent = rest
+        .split_once('%')
+        .and_then(|(p, _)| p.trim().parse().ok());
+
+    let (current, total) = rest
+        .split_once('(')
+        .and_then(|(_, r)| r.split_once(')'))
+        .and_then(|(counts, _)| counts.split_once('/'))
+        .map(|(c, t)| (c.parse().ok(), t.parse().ok()))
+        .unwrap_or((None, None));
+
+    if percent.is_none() && current.is_none() {
+        // "Counting objects: 12, done." style lines without a ratio
+        let count = rest.split(',').next()?.trim().parse().ok()?;
+        return Some(GitProgress {
+            phase,
+            percent: None,
+            current: Some(count),
+            total: None,
+        });
+    }
+
+    Some(GitProgress {
+        phase,
+        percent,
+        current,
+        total,
+    })
+}
+
+/// Read git's stderr until EOF, calling `on_line` for every non-empty line
+///
+/// git rewrites progress lines in place with `\r`, so both terminators split lines.
+pub(crate) fn read_progress_lines(reader: &mut impl Read, mut on_line: impl FnMut(&str)) {
+    let mut buf = [0u8; 4096];
+    let mut pending = String::new();
+
+    while let Ok(n) = reader.read(&mut buf) {
+        if n == 0 {
+            break;
+        }
+        pending.push_str(&String::from_utf8_lossy(&buf[..n]));
+        while let Some(pos) = pending.find(['\r', '\n']) {
+            let line: String = pending.drain(..=pos).collect();
+            let line = line.trim_end();
+            if !line.is_empty() {
+                on_line(line);
+            }
+        }
+    }
+
+    let rest = pending.trim_end();
+    if !rest.is_empty() {
+        on_line(rest);
+    }
+}
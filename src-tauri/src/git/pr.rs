@@ -0,0 +1,92 @@
+//! Pull request checkout
+//!
+//! Fetches `refs/pull/<n>/head` into `refs/remotes/<remote>/pr/<n>` and points
+//! a local `pr-<n>` branch at it, optionally in its own worktree under the
+//! worktree base so reviewing doesn't disturb the main checkout.
+
+use super::cleanup::worktree_base;
+use super::remote::{run_remote_git, RemoteError};
+use super::worktree::{add_worktree, worktrees, WorktreeInfo};
+use super::{ref_exists, run_git};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Options for `checkout_pr`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckoutPrOptions {
+    /// Check the branch out in its own worktree
+    #[serde(default = "super::default_true")]
+    worktree: bool,
+    /// Remote hosting the pull request (defaults to origin)
+    remote: Option<String>,
+    /// Worktree location (defaults to `<repo>/.worktrees/pr-<n>`)
+    path: Option<String>,
+}
+
+impl Default for CheckoutPrOptions {
+    fn default() -> Self {
+        Self {
+            worktree: true,
+            remote: None,
+            path: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CheckoutPrResult {
+    branch: String,
+    worktree: Option<WorktreeInfo>,
+}
+
+/// Fetch a pull request and create or update a local branch (and worktree) for it
+#[tauri::command]
+pub async fn checkout_pr(
+    repo: String,
+    pr_number: u64,
+    options: Option<CheckoutPrOptions>,
+) -> Result<CheckoutPrResult, RemoteError> {
+    let options = options.unwrap_or_default();
+    let remote = options.remote.as_deref().unwrap_or("origin");
+    let branch = format!("pr-{}", pr_number);
+    let remote_ref = format!("refs/remotes/{}/pr/{}", remote, pr_number);
+
+    let refspec = format!("+refs/pull/{}/head:{}", pr_number, remote_ref);
+    run_remote_git(&repo, &["fetch", remote, &refspec])?;
+
+    let existing = worktrees(&repo)
+        .map_err(RemoteError::Git)?
+        .into_iter()
+        .find(|wt| wt.branch.as_deref() == Some(branch.as_str()));
+
+    // A branch checked out in a worktree can't be moved with `branch -f`, update it in place
+    if let Some(worktree) = existing {
+        run_git(&worktree.path, &["merge", "--ff-only", &remote_ref])
+            .map_err(RemoteError::Conflict)?;
+        return Ok(CheckoutPrResult {
+            branch,
+            worktree: Some(worktree),
+        });
+    }
+
+    if ref_exists(&repo, &format!("refs/heads/{}", branch)) {
+        run_git(&repo, &["branch", "--force", &branch, &remote_ref]).map_err(RemoteError::Git)?;
+    } else {
+        run_git(&repo, &["branch", &branch, &remote_ref]).map_err(RemoteError::Git)?;
+    }
+
+    let worktree = if options.worktree {
+        let path = options.path.unwrap_or_else(|| {
+            Path::new(&worktree_base(&repo, None))
+                .join(&branch)
+                .to_string_lossy()
+                .into_owned()
+        });
+        Some(add_worktree(&repo, &branch, &path).map_err(RemoteError::Git)?)
+    } else {
+        None
+    };
+
+    Ok(CheckoutPrResult { branch, worktree })
+}
@@ -0,0 +1,226 @@
+//! Fetch, pull and push commands
+//!
+//! These never prompt: credentials come from the OS keychain through git's
+//! credential helper or from ssh-agent, and anything that would have needed a
+//! prompt fails with an `auth` error the frontend can act on.
+
+use super::non_interactive;
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+
+/// Error returned by commands that talk to a remote
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", content = "message", rename_all = "snake_case")]
+pub enum RemoteError {
+    /// Missing or rejected credentials
+    Auth(String),
+    /// DNS, connection or TLS failure
+    Network(String),
+    /// Remote repository or ref does not exist
+    NotFound(String),
+    /// Pull could not be completed without manual conflict resolution, or
+    /// push was rejected because the remote has commits we don't
+    Conflict(String),
+    /// Any other git failure
+    Git(String),
+}
+
+impl RemoteError {
+    fn classify(stderr: &str) -> Self {
+        let message = stderr.trim().to_string();
+        let lower = message.to_lowercase();
+
+        let has = |needles: &[&str]| needles.iter().any(|n| lower.contains(n));
+
+        if has(&[
+            "authentication failed",
+            "could not read username",
+            "could not read password",
+            "permission denied (publickey",
+            "terminal prompts disabled",
+            "invalid username or password",
+            "host key verification failed",
+            "403",
+        ]) {
+            Self::Auth(message)
+        } else if has(&[
+            "could not resolve host",
+            "connection timed out",
+            "connection refused",
+            "network is unreachable",
+            "unable to access",
+            "ssl",
+            "operation timed out",
+        ]) {
+            Self::Network(message)
+        } else if has(&[
+            "repository not found",
+            "couldn't find remote ref",
+            "does not appear to be a git repository",
+        ]) {
+            Self::NotFound(message)
+        } else if has(&[
+            "conflict",
+            "not possible to fast-forward",
+            "would be overwritten",
+            "divergent branches",
+            "non-fast-forward",
+            "fetch first",
+        ]) {
+            Self::Conflict(message)
+        } else {
+            Self::Git(message)
+        }
+    }
+}
+
+/// Options for `git_fetch`
+#[derive(Debug, Default, Deserialize)]
+pub struct FetchOptions {
+    /// Remote to fetch (defaults to all remotes)
+    remote: Option<String>,
+    #[serde(default)]
+    prune: bool,
+}
+
+/// Options for `git_pull`
+#[derive(Debug, Default, Deserialize)]
+pub struct PullOptions {
+    remote: Option<String>,
+    branch: Option<String>,
+    /// Rebase local commits instead of only fast-forwarding
+    #[serde(default)]
+    rebase: bool,
+}
+
+/// Options for `git_push`
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PushOptions {
+    /// Remote to push to (defaults to origin)
+    remote: Option<String>,
+    /// Branch to push (defaults to the current branch)
+    branch: Option<String>,
+    /// Overwrite the remote branch if it still points where we last saw it
+    #[serde(default)]
+    force_with_lease: bool,
+}
+
+pub(crate) fn run_remote_git(repo: &str, args: &[&str]) -> Result<String, RemoteError> {
+    let output = non_interactive(Command::new("git").arg("-C").arg(repo).args(args))
+        .output()
+        .map_err(|e| RemoteError::Git(format!("Failed to run git: {}", e)))?;
+
+    if !output.status.success() {
+        return Err(RemoteError::classify(&String::from_utf8_lossy(
+            &output.stderr,
+        )));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Run `f` off the async runtime, as network git calls can take a while
+pub(crate) async fn off_runtime<T: Send + 'static>(
+    f: impl FnOnce() -> Result<T, RemoteError> + Send + 'static,
+) -> Result<T, RemoteError> {
+    tauri::async_runtime::spawn_blocking(f)
+        .await
+        .map_err(|e| RemoteError::Git(format!("git failed: {}", e)))?
+}
+
+/// Fetch from a remote (or all remotes)
+#[tauri::command]
+pub async fn git_fetch(repo: String, options: Option<FetchOptions>) -> Result<(), RemoteError> {
+    let options = options.unwrap_or_default();
+
+    off_runtime(move || {
+        let mut args = vec!["fetch"];
+        if options.prune {
+            args.push("--prune");
+        }
+        match options.remote.as_deref() {
+            Some(remote) => args.push(remote),
+            None => args.push("--all"),
+        }
+
+        run_remote_git(&repo, &args)?;
+        Ok(())
+    })
+    .await
+}
+
+/// Pull the current branch, fast-forward only unless rebase is requested
+#[tauri::command]
+pub async fn git_pull(repo: String, options: Option<PullOptions>) -> Result<(), RemoteError> {
+    let options = options.unwrap_or_default();
+
+    off_runtime(move || {
+        let mut args = vec![
+            "pull",
+            if options.rebase {
+                "--rebase"
+            } else {
+                "--ff-only"
+            },
+        ];
+        if let Some(remote) = options.remote.as_deref() {
+            args.push(remote);
+            if let Some(branch) = options.branch.as_deref() {
+                args.push(branch);
+            }
+        }
+
+        run_remote_git(&repo, &args)?;
+        Ok(())
+    })
+    .await
+}
+
+/// Push `branch` and set it as the upstream of the local branch
+pub(crate) fn push_branch(
+    repo: &str,
+    remote: &str,
+    branch: &str,
+    force_with_lease: bool,
+) -> Result<(), RemoteError> {
+    let refspec = format!("refs/heads/{0}:refs/heads/{0}", branch);
+    let mut args = vec!["push", "--set-upstream"];
+    if force_with_lease {
+        args.push("--force-with-lease");
+    }
+    args.push(remote);
+    args.push(&refspec);
+
+    run_remote_git(repo, &args)?;
+    Ok(())
+}
+
+/// Name of the checked-out branch, `None` when HEAD is detached
+pub(crate) fn current_branch(repo: &str) -> Result<Option<String>, RemoteError> {
+    let output = run_remote_git(repo, &["rev-parse", "--abbrev-ref", "HEAD"])?;
+    let branch = output.trim();
+    Ok((branch != "HEAD").then(|| branch.to_string()))
+}
+
+/// Push a branch (default: current) to a remote (default: origin)
+#[tauri::command]
+pub async fn git_push(repo: String, options: Option<PushOptions>) -> Result<(), RemoteError> {
+    let options = options.unwrap_or_default();
+
+    off_runtime(move || {
+        let branch = match options.branch {
+            Some(branch) => branch,
+            None => current_branch(&repo)?
+                .ok_or_else(|| RemoteError::Git("HEAD is detached; nothing to push".to_string()))?,
+        };
+
+        push_branch(
+            &repo,
+            options.remote.as_deref().unwrap_or("origin"),
+            &branch,
+            options.force_with_lease,
+        )
+    })
+    .await
+}
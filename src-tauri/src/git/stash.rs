@@ -0,0 +1,103 @@
+//! Stash commands
+//!
+//! Used to park uncommitted work in a worktree before it is switched or
+//! cleaned up. Stashes are addressed by index, matching `stash@{n}`.
+
+use super::run_git;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StashEntry {
+    index: u32,
+    name: String,
+    hash: String,
+    message: String,
+    date: String,
+}
+
+/// Options for `stash_push`
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StashPushOptions {
+    message: Option<String>,
+    #[serde(default)]
+    include_untracked: bool,
+}
+
+fn stashes(repo: &str) -> Result<Vec<StashEntry>, String> {
+    let output = run_git(repo, &["stash", "list", "--format=%gd%x1f%H%x1f%gs%x1f%aI"])?;
+
+    let entries = output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\x1f');
+            let name = fields.next()?.to_string();
+            let hash = fields.next()?.to_string();
+            let message = fields.next()?.to_string();
+            let date = fields.next()?.to_string();
+            let index = name
+                .strip_prefix("stash@{")?
+                .strip_suffix('}')?
+                .parse()
+                .ok()?;
+            Some(StashEntry {
+                index,
+                name,
+                hash,
+                message,
+                date,
+            })
+        })
+        .collect();
+
+    Ok(entries)
+}
+
+fn stash_ref(index: Option<u32>) -> String {
+    format!("stash@{{{}}}", index.unwrap_or(0))
+}
+
+/// List stashes, newest first
+#[tauri::command]
+pub async fn list_stashes(repo: String) -> Result<Vec<StashEntry>, String> {
+    stashes(&repo)
+}
+
+/// Stash uncommitted changes; returns None when there was nothing to stash
+#[tauri::command]
+pub async fn stash_push(
+    repo: String,
+    options: Option<StashPushOptions>,
+) -> Result<Option<StashEntry>, String> {
+    let options = options.unwrap_or_default();
+    let before = stashes(&repo)?.first().map(|s| s.hash.clone());
+
+    let mut args = vec!["stash", "push"];
+    if options.include_untracked {
+        args.push("--include-untracked");
+    }
+    if let Some(message) = options.message.as_deref() {
+        args.push("--message");
+        args.push(message);
+    }
+    run_git(&repo, &args)?;
+
+    // git exits 0 with "No local changes to save", so compare the top of the stack
+    let top = stashes(&repo)?.into_iter().next();
+    Ok(top.filter(|s| Some(&s.hash) != before.as_ref()))
+}
+
+/// Apply a stash and drop it from the stack
+#[tauri::command]
+pub async fn stash_pop(repo: String, index: Option<u32>) -> Result<(), String> {
+    run_git(&repo, &["stash", "pop", &stash_ref(index)])?;
+    Ok(())
+}
+
+/// Apply a stash but keep it on the stack
+#[tauri::command]
+pub async fn stash_apply(repo: String, index: Option<u32>) -> Result<(), String> {
+    run_git(&repo, &["stash", "apply", &stash_ref(index)])?;
+    Ok(())
+}
@@ -0,0 +1,423 @@
+//! Status command
+//!
+//! Parses `git status --porcelain=v2 -z`, which keeps renames and paths with
+//! spaces or newlines unambiguous and reports submodule state per entry.
+
+use super::backend::GitBackendState;
+use super::run_git;
+use git2::{FileMode, Repository, RepositoryState, StatusOptions};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+/// Kind of change recorded for one side (index or worktree) of an entry
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Added,
+    Modified,
+    Deleted,
+    Renamed,
+    Copied,
+    TypeChanged,
+}
+
+impl ChangeKind {
+    fn from_code(code: char) -> Option<Self> {
+        match code {
+            'A' => Some(Self::Added),
+            'M' => Some(Self::Modified),
+            'D' => Some(Self::Deleted),
+            'R' => Some(Self::Renamed),
+            'C' => Some(Self::Copied),
+            'T' => Some(Self::TypeChanged),
+            _ => None,
+        }
+    }
+}
+
+/// A staged or unstaged change to a tracked file
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileChange {
+    path: String,
+    original_path: Option<String>,
+    kind: ChangeKind,
+    submodule: bool,
+}
+
+/// In-progress operation that leaves the repository in a special state
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RepoOperation {
+    Merge,
+    Rebase,
+    CherryPick,
+    Revert,
+    Bisect,
+}
+
+/// Parsed result of `git status`
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitStatus {
+    branch: Option<String>,
+    oid: Option<String>,
+    upstream: Option<String>,
+    ahead: u32,
+    behind: u32,
+    staged: Vec<FileChange>,
+    unstaged: Vec<FileChange>,
+    untracked: Vec<String>,
+    pub(crate) conflicted: Vec<String>,
+    pub(crate) operation: Option<RepoOperation>,
+}
+
+/// Absolute path of the git directory for `repo` (per-worktree for linked worktrees)
+pub(crate) fn git_dir(repo: &str) -> Result<PathBuf, String> {
+    let output = run_git(repo, &["rev-parse", "--absolute-git-dir"])?;
+    Ok(PathBuf::from(output.trim()))
+}
+
+/// Detect a merge, rebase, cherry-pick, revert or bisect in progress
+pub(crate) fn detect_operation(git_dir: &Path) -> Option<RepoOperation> {
+    if git_dir.join("rebase-merge").exists() || git_dir.join("rebase-apply").exists() {
+        Some(RepoOperation::Rebase)
+    } else if git_dir.join("MERGE_HEAD").exists() {
+        Some(RepoOperation::Merge)
+    } else if git_dir.join("CHERRY_PICK_HEAD").exists() {
+        Some(RepoOperation::CherryPick)
+    } else if git_dir.join("REVERT_HEAD").exists() {
+        Some(RepoOperation::Revert)
+    } else if git_dir.join("BISECT_LOG").exists() {
+        Some(RepoOperation::Bisect)
+    } else {
+        None
+    }
+}
+
+/// Push index and worktree sides of an `XY` code as separate changes
+fn push_changes(
+    status: &mut GitStatus,
+    xy: &str,
+    submodule: &str,
+    path: &str,
+    original_path: Option<&str>,
+) {
+    let mut codes = xy.chars();
+    let index = codes.next().and_then(ChangeKind::from_code);
+    let worktree = codes.next().and_then(ChangeKind::from_code);
+    let submodule = submodule.starts_with('S');
+
+    if let Some(kind) = index {
+        status.staged.push(FileChange {
+            path: path.to_string(),
+            original_path: original_path.map(str::to_string),
+            kind,
+            submodule,
+        });
+    }
+    if let Some(kind) = worktree {
+        status.unstaged.push(FileChange {
+            path: path.to_string(),
+            original_path: None,
+            kind,
+            submodule,
+        });
+    }
+}
+
+fn parse_status(output: &str) -> GitStatus {
+    let mut status = GitStatus::default();
+    let mut records = output.split('\0').filter(|r| !r.is_empty());
+
+    while let Some(record) = records.next() {
+        if let Some(header) = record.strip_prefix("# ") {
+            let (key, value) = header.split_once(' ').unwrap_or((header, ""));
+            match key {
+                "branch.oid" if value != "(initial)" => status.oid = Some(value.to_string()),
+                "branch.head" if value != "(detached)" => status.branch = Some(value.to_string()),
+                "branch.upstream" => status.upstream = Some(value.to_string()),
+                "branch.ab" => {
+                    for part in value.split(' ') {
+                        if let Some(n) = part.strip_prefix('+') {
+                            status.ahead = n.parse().unwrap_or(0);
+                        } else if let Some(n) = part.strip_prefix('-') {
+                            status.behind = n.parse().unwrap_or(0);
+                        }
+                    }
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        let Some((kind, rest)) = record.split_at_checked(1) else {
+            continue;
+        };
+        let rest = rest.trim_start();
+        match kind {
+            // 1 <XY> <sub> <mH> <mI> <mW> <hH> <hI> <path>
+            "1" => {
+                let fields: Vec<&str> = rest.splitn(8, ' ').collect();
+                if let [xy, sub, _, _, _, _, _, path] = fields.as_slice() {
+                    push_changes(&mut status, xy, sub, path, None);
+                }
+            }
+            // 2 <XY> <sub> <mH> <mI> <mW> <hH> <hI> <X><score> <path>, then <origPath>
+            "2" => {
+                let fields: Vec<&str> = rest.splitn(9, ' ').collect();
+                let original = records.next();
+                if let [xy, sub, _, _, _, _, _, _, path] = fields.as_slice() {
+                    push_changes(&mut status, xy, sub, path, original);
+                }
+            }
+            // u <XY> <sub> <m1> <m2> <m3> <mW> <h1> <h2> <h3> <path>
+            "u" => {
+                if let Some(path) = rest.splitn(10, ' ').nth(9) {
+                    status.conflicted.push(path.to_string());
+                }
+            }
+            "?" => status.untracked.push(rest.to_string()),
+            _ => {}
+        }
+    }
+
+    status
+}
+
+pub(crate) fn cli_status(repo: &str) -> Result<GitStatus, String> {
+    let output = run_git(
+        repo,
+        &[
+            "status",
+            "--porcelain=v2",
+            "--branch",
+            "-z",
+            "--untracked-files=all",
+        ],
+    )?;
+
+    let mut status = parse_status(&output);
+    status.operation = detect_operation(&git_dir(repo)?);
+
+    Ok(status)
+}
+
+pub(crate) fn native_operation(state: RepositoryState) -> Option<RepoOperation> {
+    match state {
+        RepositoryState::Clean | RepositoryState::ApplyMailbox => None,
+        RepositoryState::Merge => Some(RepoOperation::Merge),
+        RepositoryState::Revert | RepositoryState::RevertSequence => Some(RepoOperation::Revert),
+        RepositoryState::CherryPick | RepositoryState::CherryPickSequence => {
+            Some(RepoOperation::CherryPick)
+        }
+        RepositoryState::Bisect => Some(RepoOperation::Bisect),
+        RepositoryState::Rebase
+        | RepositoryState::RebaseInteractive
+        | RepositoryState::RebaseMerge
+        | RepositoryState::ApplyMailboxOrRebase => Some(RepoOperation::Rebase),
+    }
+}
+
+fn native_status(repo: &str) -> Result<GitStatus, git2::Error> {
+    let repository = Repository::open(repo)?;
+    let mut status = GitStatus {
+        operation: native_operation(repository.state()),
+        ..Default::default()
+    };
+
+    // Branch, upstream and ahead/behind; an unborn HEAD simply has none of these
+    if let Ok(head) = repository.head() {
+        status.oid = head.target().map(|oid| oid.to_string());
+        if head.is_branch() {
+            status.branch = head.shorthand().map(str::to_string);
+            let branch = git2::Branch::wrap(head);
+            if let Ok(upstream) = branch.upstream() {
+                status.upstream = upstream.name()?.map(str::to_string);
+                if let (Some(local), Some(remote)) =
+                    (branch.get().target(), upstream.get().target())
+                {
+                    let (ahead, behind) = repository.graph_ahead_behind(local, remote)?;
+                    status.ahead = ahead as u32;
+                    status.behind = behind as u32;
+                }
+            }
+        }
+    }
+
+    let mut options = StatusOptions::new();
+    options
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .renames_head_to_index(true);
+
+    for entry in repository.statuses(Some(&mut options))?.iter() {
+        let flags = entry.status();
+        let Some(path) = entry.path().map(str::to_string) else {
+            continue;
+        };
+
+        if flags.is_conflicted() {
+            status.conflicted.push(path);
+            continue;
+        }
+        if flags.is_wt_new() {
+            status.untracked.push(path);
+            continue;
+        }
+
+        let staged = if flags.is_index_renamed() {
+            Some(ChangeKind::Renamed)
+        } else if flags.is_index_new() {
+            Some(ChangeKind::Added)
+        } else if flags.is_index_deleted() {
+            Some(ChangeKind::Deleted)
+        } else if flags.is_index_typechange() {
+            Some(ChangeKind::TypeChanged)
+        } else if flags.is_index_modified() {
+            Some(ChangeKind::Modified)
+        } else {
+            None
+        };
+        let unstaged = if flags.is_wt_deleted() {
+            Some(ChangeKind::Deleted)
+        } else if flags.is_wt_typechange() {
+            Some(ChangeKind::TypeChanged)
+        } else if flags.is_wt_modified() {
+            Some(ChangeKind::Modified)
+        } else {
+            None
+        };
+
+        let submodule = entry
+            .index_to_workdir()
+            .or_else(|| entry.head_to_index())
+            .is_some_and(|delta| delta.new_file().mode() == FileMode::Commit);
+
+        // entry.path() is the pre-rename path; report renames under their new path like the CLI
+        let (path, original_path) = match entry.head_to_index().filter(|_| flags.is_index_renamed())
+        {
+            Some(delta) => (
+                delta
+                    .new_file()
+                    .path()
+                    .map(|p| p.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| path.clone()),
+                Some(path),
+            ),
+            None => (path, None),
+        };
+
+        if let Some(kind) = staged {
+            status.staged.push(FileChange {
+                path: path.clone(),
+                original_path,
+                kind,
+                submodule,
+            });
+        }
+        if let Some(kind) = unstaged {
+            status.unstaged.push(FileChange {
+                path,
+                original_path: None,
+                kind,
+                submodule,
+            });
+        }
+    }
+
+    Ok(status)
+}
+
+/// Get the structured working tree status of a repository or worktree
+#[tauri::command]
+pub async fn git_status(app: AppHandle, repo: String) -> Result<GitStatus, String> {
+    super::blocking("git_status", move || {
+        app.state::<GitBackendState>().run(
+            "git_status",
+            || native_status(&repo),
+            || cli_status(&repo),
+        )
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(changes: &[FileChange]) -> Vec<(&str, Option<&str>, ChangeKind)> {
+        changes
+            .iter()
+            .map(|c| (c.path.as_str(), c.original_path.as_deref(), c.kind))
+            .collect()
+    }
+
+    #[test]
+    fn parses_headers_and_entries() {
+        let output = [
+            "# branch.oid 1234abcd",
+            "# branch.head main",
+            "# branch.upstream origin/main",
+            "# branch.ab +2 -1",
+            "1 MM N... 100644 100644 100644 aaaa bbbb src/a file.rs",
+            "1 A. N... 000000 100644 100644 0000 cccc new.rs",
+            "2 R. N... 100644 100644 100644 dddd dddd R100 renamed.rs",
+            "old name.rs",
+            "1 .M SC.. 160000 160000 160000 eeee eeee vendor/lib",
+            "u UU N... 100644 100644 100644 100644 ffff 1111 2222 conflict.rs",
+            "? untracked dir/x.txt",
+            "",
+        ]
+        .join("\0");
+        let status = parse_status(&output);
+
+        assert_eq!(status.oid.as_deref(), Some("1234abcd"));
+        assert_eq!(status.branch.as_deref(), Some("main"));
+        assert_eq!(status.upstream.as_deref(), Some("origin/main"));
+        assert_eq!((status.ahead, status.behind), (2, 1));
+        assert_eq!(
+            paths(&status.staged),
+            [
+                ("src/a file.rs", None, ChangeKind::Modified),
+                ("new.rs", None, ChangeKind::Added),
+                ("renamed.rs", Some("old name.rs"), ChangeKind::Renamed),
+            ]
+        );
+        assert_eq!(
+            paths(&status.unstaged),
+            [
+                ("src/a file.rs", None, ChangeKind::Modified),
+                ("vendor/lib", None, ChangeKind::Modified),
+            ]
+        );
+        assert!(status.unstaged[1].submodule && !status.unstaged[0].submodule);
+        assert_eq!(status.conflicted, ["conflict.rs"]);
+        assert_eq!(status.untracked, ["untracked dir/x.txt"]);
+    }
+
+    #[test]
+    fn leaves_initial_and_detached_heads_unset() {
+        let status = parse_status("# branch.oid (initial)\0# branch.head (detached)\0");
+        assert_eq!(status.oid, None);
+        assert_eq!(status.branch, None);
+    }
+
+    #[test]
+    fn skips_malformed_records() {
+        let output = [
+            "# branch.ab +x -",
+            "1 M",
+            "u UU N...",
+            "\u{e9}t\u{e9}",
+            "! ignored",
+            "2 R. N... 100644",
+        ]
+        .join("\0");
+        let status = parse_status(&output);
+        assert_eq!((status.ahead, status.behind), (0, 0));
+        assert!(status.staged.is_empty() && status.unstaged.is_empty());
+        assert!(status.conflicted.is_empty() && status.untracked.is_empty());
+    }
+}
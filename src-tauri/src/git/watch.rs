@@ -0,0 +1,226 @@
+//! Repository watcher
+//!
+//! Watches a repository's git directory and emits `git-head-changed` when
+//! HEAD moves (checkout, commit, reset) and `git-index-changed` when the
+//! index is rewritten, so the UI doesn't have to poll `git status`. Events
+//! are coalesced because a single git command touches several files.
+
+use super::backend::GitBackendState;
+use super::conflict::conflict_state;
+use super::run_git;
+use super::status::git_dir;
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// How long to wait for more filesystem events before reporting a change
+const DEBOUNCE: Duration = Duration::from_millis(150);
+
+/// State for active repository watchers
+pub struct GitWatchState {
+    watchers: Mutex<HashMap<u32, RepoWatcher>>,
+    next_id: AtomicU32,
+}
+
+struct RepoWatcher {
+    repo: String,
+    // Dropping the watcher ends its event thread
+    _watcher: RecommendedWatcher,
+}
+
+impl Default for GitWatchState {
+    fn default() -> Self {
+        Self {
+            watchers: Mutex::new(HashMap::new()),
+            next_id: AtomicU32::new(1),
+        }
+    }
+}
+
+/// Event payload for HEAD changes
+#[derive(Clone, Serialize, PartialEq)]
+struct HeadChangedEvent {
+    id: u32,
+    repo: String,
+    branch: Option<String>,
+    head: Option<String>,
+}
+
+/// Event payload for index changes
+#[derive(Clone, Serialize)]
+struct IndexChangedEvent {
+    id: u32,
+    repo: String,
+}
+
+/// A watched repository
+#[derive(Clone, Serialize)]
+pub struct WatchedRepo {
+    id: u32,
+    repo: String,
+}
+
+fn read_head(id: u32, repo: &str) -> HeadChangedEvent {
+    let branch = run_git(repo, &["symbolic-ref", "--short", "-q", "HEAD"])
+        .ok()
+        .map(|out| out.trim().to_string())
+        .filter(|b| !b.is_empty());
+    let head = run_git(repo, &["rev-parse", "--verify", "-q", "HEAD"])
+        .ok()
+        .map(|out| out.trim().to_string());
+
+    HeadChangedEvent {
+        id,
+        repo: repo.to_string(),
+        branch,
+        head,
+    }
+}
+
+#[derive(Default)]
+struct Changes {
+    head: bool,
+    index: bool,
+}
+
+impl Changes {
+    fn add(&mut self, event: &Event, refs_dir: &Path) {
+        for path in &event.paths {
+            let name = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or_default();
+            if name.ends_with(".lock") {
+                continue;
+            }
+            match name {
+                "HEAD" | "ORIG_HEAD" | "packed-refs" => self.head = true,
+                "index" | "MERGE_HEAD" | "CHERRY_PICK_HEAD" | "REVERT_HEAD" => self.index = true,
+                _ if path.starts_with(refs_dir) => self.head = true,
+                _ => {}
+            }
+        }
+    }
+}
+
+fn common_dir(repo: &str) -> Result<PathBuf, String> {
+    let output = run_git(
+        repo,
+        &["rev-parse", "--path-format=absolute", "--git-common-dir"],
+    )?;
+    Ok(PathBuf::from(output.trim()))
+}
+
+/// Start watching a repository or worktree for HEAD and index changes
+#[tauri::command]
+pub async fn watch_repo(
+    app: AppHandle,
+    state: State<'_, GitWatchState>,
+    repo: String,
+) -> Result<u32, String> {
+    let git_dir = git_dir(&repo)?;
+    let common_dir = common_dir(&repo)?;
+    let refs_dir = common_dir.join("refs").join("heads");
+
+    let (tx, rx) = mpsc::channel::<notify::Result<Event>>();
+    let mut watcher = notify::recommended_watcher(move |event| {
+        let _ = tx.send(event);
+    })
+    .map_err(|e| format!("Failed to create watcher: {}", e))?;
+
+    watcher
+        .watch(&git_dir, RecursiveMode::NonRecursive)
+        .map_err(|e| format!("Failed to watch {}: {}", git_dir.display(), e))?;
+    if common_dir != git_dir {
+        watcher
+            .watch(&common_dir, RecursiveMode::NonRecursive)
+            .map_err(|e| format!("Failed to watch {}: {}", common_dir.display(), e))?;
+    }
+    if refs_dir.exists() {
+        watcher
+            .watch(&refs_dir, RecursiveMode::Recursive)
+            .map_err(|e| format!("Failed to watch {}: {}", refs_dir.display(), e))?;
+    }
+
+    let id = state.next_id.fetch_add(1, Ordering::SeqCst);
+    state.watchers.lock().insert(
+        id,
+        RepoWatcher {
+            repo: repo.clone(),
+            _watcher: watcher,
+        },
+    );
+
+    thread::spawn(move || {
+        let mut last_head = read_head(id, &repo);
+
+        // recv fails once the watcher (and with it the sender) is dropped
+        while let Ok(first) = rx.recv() {
+            let mut changes = Changes::default();
+            if let Ok(event) = first {
+                changes.add(&event, &refs_dir);
+            }
+            while let Ok(next) = rx.recv_timeout(DEBOUNCE) {
+                if let Ok(event) = next {
+                    changes.add(&event, &refs_dir);
+                }
+            }
+
+            if changes.head {
+                let head = read_head(id, &repo);
+                if head != last_head {
+                    let _ = app.emit("git-head-changed", head.clone());
+                    last_head = head;
+                }
+            }
+
+            if changes.index {
+                let _ = app.emit(
+                    "git-index-changed",
+                    IndexChangedEvent {
+                        id,
+                        repo: repo.clone(),
+                    },
+                );
+
+                // Surface conflicts as soon as a merge or rebase stops on them
+                if let Ok(conflicts) = conflict_state(&app.state::<GitBackendState>(), &repo) {
+                    if conflicts.has_conflicts() {
+                        let _ = app.emit("git-conflict", conflicts);
+                    }
+                }
+            }
+        }
+    });
+
+    Ok(id)
+}
+
+/// Stop watching a repository
+#[tauri::command]
+pub async fn unwatch_repo(state: State<'_, GitWatchState>, id: u32) -> Result<(), String> {
+    state.watchers.lock().remove(&id);
+    Ok(())
+}
+
+/// List active repository watchers
+#[tauri::command]
+pub async fn list_watched_repos(
+    state: State<'_, GitWatchState>,
+) -> Result<Vec<WatchedRepo>, String> {
+    let watchers = state.watchers.lock();
+    Ok(watchers
+        .iter()
+        .map(|(id, w)| WatchedRepo {
+            id: *id,
+            repo: w.repo.clone(),
+        })
+        .collect())
+}
@@ -0,0 +1,269 @@
+//! Worktree commands
+//!
+//! Each work session gets its own worktree, so these commands have to cope
+//! with leftovers from earlier sessions: a branch that still exists after its
+//! worktree directory was deleted is reused rather than recreated with `-b`.
+//! Fresh worktrees don't have submodules checked out, so creation also
+//! initializes them unless told not to.
+
+use super::{
+    non_interactive, parse_progress, read_progress_lines, ref_exists, run_git, GitProgress,
+};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+use tauri::{AppHandle, Emitter};
+
+/// A worktree as reported by `git worktree list --porcelain`
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorktreeInfo {
+    pub(crate) path: String,
+    pub(crate) head: Option<String>,
+    pub(crate) branch: Option<String>,
+    pub(crate) bare: bool,
+    pub(crate) detached: bool,
+    pub(crate) locked: bool,
+    pub(crate) prunable: bool,
+}
+
+/// Parse the porcelain output of `git worktree list`
+fn parse_worktree_list(output: &str) -> Vec<WorktreeInfo> {
+    let mut worktrees = Vec::new();
+    let mut current: Option<WorktreeInfo> = None;
+
+    for line in output.lines() {
+        if let Some(path) = line.strip_prefix("worktree ") {
+            if let Some(done) = current.take() {
+                worktrees.push(done);
+            }
+            current = Some(WorktreeInfo {
+                path: path.to_string(),
+                ..Default::default()
+            });
+            continue;
+        }
+
+        let Some(info) = current.as_mut() else {
+            continue;
+        };
+
+        if let Some(head) = line.strip_prefix("HEAD ") {
+            info.head = Some(head.to_string());
+        } else if let Some(branch) = line.strip_prefix("branch ") {
+            let name = branch.strip_prefix("refs/heads/").unwrap_or(branch);
+            info.branch = Some(name.to_string());
+        } else if line == "bare" {
+            info.bare = true;
+        } else if line == "detached" {
+            info.detached = true;
+        } else if line == "locked" || line.starts_with("locked ") {
+            info.locked = true;
+        } else if line == "prunable" || line.starts_with("prunable ") {
+            info.prunable = true;
+        }
+    }
+
+    if let Some(done) = current {
+        worktrees.push(done);
+    }
+
+    worktrees
+}
+
+pub(crate) fn worktrees(repo: &str) -> Result<Vec<WorktreeInfo>, String> {
+    let output = run_git(repo, &["worktree", "list", "--porcelain"])?;
+    Ok(parse_worktree_list(&output))
+}
+
+/// Compare paths leniently so `./foo` and `/abs/foo` refer to the same worktree
+pub(crate) fn same_path(a: &str, b: &str) -> bool {
+    match (Path::new(a).canonicalize(), Path::new(b).canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => Path::new(a) == Path::new(b),
+    }
+}
+
+pub(crate) fn add_worktree(repo: &str, branch: &str, path: &str) -> Result<WorktreeInfo, String> {
+    // Drop registrations for worktrees whose directories were deleted by hand,
+    // otherwise git refuses to check their branch out again
+    run_git(repo, &["worktree", "prune"])?;
+
+    // Reuse a worktree that is already registered for this branch at this path
+    if let Some(existing) = worktrees(repo)?
+        .into_iter()
+        .find(|wt| wt.branch.as_deref() == Some(branch) && same_path(&wt.path, path))
+    {
+        return Ok(existing);
+    }
+
+    let local_ref = format!("refs/heads/{}", branch);
+    let remote_ref = format!("refs/remotes/origin/{}", branch);
+
+    if ref_exists(repo, &local_ref) {
+        // Branch survived its previous worktree - check it out again
+        run_git(repo, &["worktree", "add", path, branch])?;
+    } else if ref_exists(repo, &remote_ref) {
+        let upstream = format!("origin/{}", branch);
+        run_git(
+            repo,
+            &["worktree", "add", "--track", "-b", branch, path, &upstream],
+        )?;
+    } else {
+        run_git(repo, &["worktree", "add", "-b", branch, path])?;
+    }
+
+    worktrees(repo)?
+        .into_iter()
+        .find(|wt| same_path(&wt.path, path))
+        .ok_or_else(|| format!("Worktree at {} was not registered by git", path))
+}
+
+/// Options for `create_worktree`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateWorktreeOptions {
+    /// Initialize and update submodules in the new worktree
+    #[serde(default = "super::default_true")]
+    submodules: bool,
+}
+
+impl Default for CreateWorktreeOptions {
+    fn default() -> Self {
+        Self { submodules: true }
+    }
+}
+
+/// Event payload for submodule checkout progress in a new worktree
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SubmoduleProgressEvent {
+    worktree_path: String,
+    submodule: Option<String>,
+    #[serde(flatten)]
+    progress: Option<GitProgress>,
+    message: String,
+}
+
+/// Run `git submodule update --init --recursive`, streaming progress as events
+fn update_submodules(app: &AppHandle, path: &str) -> Result<(), String> {
+    if !Path::new(path).join(".gitmodules").exists() {
+        return Ok(());
+    }
+
+    let mut child = non_interactive(Command::new("git").arg("-C").arg(path).args([
+        "submodule",
+        "update",
+        "--init",
+        "--recursive",
+        "--progress",
+    ]))
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .spawn()
+    .map_err(|e| format!("Failed to run git: {}", e))?;
+
+    let mut stderr = child
+        .stderr
+        .take()
+        .ok_or_else(|| "Failed to capture git output".to_string())?;
+    let mut stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| "Failed to capture git output".to_string())?;
+
+    // "Submodule path 'x': checked out ..." goes to stdout, clone progress to stderr
+    let stdout_reader = thread::spawn(move || {
+        let mut lines = Vec::new();
+        read_progress_lines(&mut stdout, |line| lines.push(line.to_string()));
+        lines
+    });
+
+    let mut submodule: Option<String> = None;
+    let mut last_line = String::new();
+    read_progress_lines(&mut stderr, |line| {
+        // "Cloning into '/repo/.worktrees/x/vendor/lib'..." marks the start of a submodule
+        if let Some(rest) = line.strip_prefix("Cloning into '") {
+            submodule = rest.split('\'').next().map(str::to_string);
+        }
+        let _ = app.emit(
+            "git-submodule-progress",
+            SubmoduleProgressEvent {
+                worktree_path: path.to_string(),
+                submodule: submodule.clone(),
+                progress: parse_progress(line),
+                message: line.to_string(),
+            },
+        );
+        last_line = line.to_string();
+    });
+
+    let _ = stdout_reader.join();
+    let status = child
+        .wait()
+        .map_err(|e| format!("Failed to wait for git: {}", e))?;
+
+    if !status.success() {
+        return Err(format!("git submodule update failed: {}", last_line));
+    }
+
+    Ok(())
+}
+
+/// Create a worktree for `branch` at `path`, reusing the branch if it exists
+///
+/// Submodules are checked out too unless disabled in `options`, emitting
+/// `git-submodule-progress` events while they clone.
+#[tauri::command]
+pub async fn create_worktree(
+    app: AppHandle,
+    repo: String,
+    branch: String,
+    path: String,
+    options: Option<CreateWorktreeOptions>,
+) -> Result<WorktreeInfo, String> {
+    let options = options.unwrap_or_default();
+    let worktree = add_worktree(&repo, &branch, &path)?;
+
+    if options.submodules {
+        update_submodules(&app, &worktree.path)?;
+    }
+
+    Ok(worktree)
+}
+
+/// List all worktrees of a repository
+#[tauri::command]
+pub async fn list_worktrees(repo: String) -> Result<Vec<WorktreeInfo>, String> {
+    worktrees(&repo)
+}
+
+/// Remove a worktree and prune stale worktree metadata
+///
+/// With `trash` the directory is moved to the Trash instead of deleted, which
+/// also works for a worktree with uncommitted changes.
+#[tauri::command]
+pub async fn remove_worktree(
+    repo: String,
+    path: String,
+    force: Option<bool>,
+    trash: Option<bool>,
+) -> Result<(), String> {
+    if trash.unwrap_or(false) {
+        crate::files::trash::trash(Path::new(&path))?;
+        run_git(&repo, &["worktree", "prune"])?;
+        return Ok(());
+    }
+
+    let mut args = vec!["worktree", "remove"];
+    if force.unwrap_or(false) {
+        args.push("--force");
+    }
+    args.push(&path);
+
+    run_git(&repo, &args)?;
+    run_git(&repo, &["worktree", "prune"])?;
+
+    Ok(())
+}
@@ -0,0 +1,375 @@
+//! Issue cache - SQLite store for offline board loading
+//!
+//! Issues and labels are cached per repository together with the ETags of the
+//! pages that produced them. `load_board` answers from the cache straight
+//! away and refreshes in the background; the refresh uses `If-None-Match`, so
+//! an unchanged board costs a 304 per page.
+
+use super::issues::{issues_path, Issue, ListIssuesOptions, RawIssue};
+use super::labels::Label;
+use super::{Conditional, GitHubError, GitHubState};
+use crate::store::Store;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+const CACHE_FILE: &str = "github-cache.sqlite";
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS issues (
+    repo TEXT NOT NULL,
+    number INTEGER NOT NULL,
+    updated_at TEXT NOT NULL,
+    data TEXT NOT NULL,
+    PRIMARY KEY (repo, number)
+);
+CREATE TABLE IF NOT EXISTS labels (
+    repo TEXT NOT NULL,
+    name TEXT NOT NULL,
+    data TEXT NOT NULL,
+    PRIMARY KEY (repo, name)
+);
+CREATE TABLE IF NOT EXISTS comments (
+    repo TEXT NOT NULL,
+    issue INTEGER NOT NULL,
+    id INTEGER NOT NULL,
+    data TEXT NOT NULL,
+    PRIMARY KEY (repo, id)
+);
+CREATE TABLE IF NOT EXISTS mutations (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    repo TEXT NOT NULL,
+    issue INTEGER NOT NULL,
+    data TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending',
+    error TEXT,
+    created_at TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS etags (
+    key TEXT PRIMARY KEY,
+    etag TEXT,
+    fetched_at TEXT NOT NULL
+);
+";
+
+impl From<rusqlite::Error> for GitHubError {
+    fn from(e: rusqlite::Error) -> Self {
+        GitHubError::Cache {
+            message: e.to_string(),
+        }
+    }
+}
+
+impl From<serde_json::Error> for GitHubError {
+    fn from(e: serde_json::Error) -> Self {
+        GitHubError::Cache {
+            message: e.to_string(),
+        }
+    }
+}
+
+/// Board contents for one repository
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Board {
+    repo: String,
+    pub(crate) issues: Vec<Issue>,
+    labels: Vec<Label>,
+    /// When the issues were last fetched (RFC 3339), `None` if never
+    fetched_at: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+struct BoardRefreshFailed {
+    repo: String,
+    error: GitHubError,
+}
+
+/// Lazily opened SQLite connection in the app data directory
+pub struct IssueCache {
+    store: Store,
+}
+
+impl Default for IssueCache {
+    fn default() -> Self {
+        Self {
+            store: Store::new(CACHE_FILE, SCHEMA, "issue cache"),
+        }
+    }
+}
+
+impl IssueCache {
+    pub(crate) fn with<T>(
+        &self,
+        app: &AppHandle,
+        f: impl FnOnce(&mut Connection) -> Result<T, GitHubError>,
+    ) -> Result<T, GitHubError> {
+        let mut conn = self
+            .store
+            .connection(app)
+            .map_err(|message| GitHubError::Cache { message })?;
+        f(&mut conn)
+    }
+}
+
+fn issues_key(repo: &str) -> String {
+    format!("issues:{}", repo)
+}
+
+fn labels_key(repo: &str) -> String {
+    format!("labels:{}", repo)
+}
+
+pub(crate) fn etag(conn: &Connection, key: &str) -> Result<Option<String>, GitHubError> {
+    Ok(conn
+        .query_row("SELECT etag FROM etags WHERE key = ?1", [key], |row| {
+            row.get::<_, Option<String>>(0)
+        })
+        .optional()?
+        .flatten())
+}
+
+fn fetched_at(conn: &Connection, key: &str) -> Result<Option<String>, GitHubError> {
+    Ok(conn
+        .query_row(
+            "SELECT fetched_at FROM etags WHERE key = ?1",
+            [key],
+            |row| row.get(0),
+        )
+        .optional()?)
+}
+
+/// Mark a cached list as checked just now, keeping its ETag
+pub(crate) fn touch_etag(conn: &Connection, key: &str) -> Result<(), GitHubError> {
+    conn.execute(
+        "INSERT INTO etags (key, etag, fetched_at) VALUES (?1, NULL, ?2)
+         ON CONFLICT(key) DO UPDATE SET fetched_at = ?2",
+        params![key, chrono::Utc::now().to_rfc3339()],
+    )?;
+    Ok(())
+}
+
+/// Record a fresh fetch of a cached list and the ETag to revalidate it with
+pub(crate) fn set_etag(
+    conn: &Connection,
+    key: &str,
+    etag: Option<&str>,
+) -> Result<(), GitHubError> {
+    conn.execute(
+        "INSERT INTO etags (key, etag, fetched_at) VALUES (?1, ?2, ?3)
+         ON CONFLICT(key) DO UPDATE SET etag = ?2, fetched_at = ?3",
+        params![key, etag, chrono::Utc::now().to_rfc3339()],
+    )?;
+    Ok(())
+}
+
+pub(crate) fn cached_issues(conn: &Connection, repo: &str) -> Result<Vec<Issue>, GitHubError> {
+    let mut stmt = conn.prepare("SELECT data FROM issues WHERE repo = ?1 ORDER BY number DESC")?;
+    let rows = stmt.query_map([repo], |row| row.get::<_, String>(0))?;
+    rows.map(|data| Ok(serde_json::from_str(&data?)?)).collect()
+}
+
+/// Replace the cached issues of a repository.
+///
+/// A `complete` list replaces them all. One cut short only replaces the
+/// newest issues, down to the oldest it reached, so those past the cut stay.
+pub(crate) fn store_issues(
+    conn: &mut Connection,
+    repo: &str,
+    issues: &[Issue],
+    etag: Option<&str>,
+    complete: bool,
+) -> Result<(), GitHubError> {
+    let tx = conn.transaction()?;
+    let oldest = match issues.iter().map(|issue| issue.number).min() {
+        Some(oldest) if !complete => oldest,
+        _ => 0,
+    };
+    tx.execute(
+        "DELETE FROM issues WHERE repo = ?1 AND number >= ?2",
+        params![repo, oldest as i64],
+    )?;
+    for issue in issues {
+        tx.execute(
+            "INSERT OR REPLACE INTO issues (repo, number, updated_at, data) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                repo,
+                issue.number as i64,
+                issue.updated_at,
+                serde_json::to_string(issue)?
+            ],
+        )?;
+    }
+    set_etag(&tx, &issues_key(repo), etag)?;
+    tx.commit()?;
+    Ok(())
+}
+
+fn cached_labels(conn: &Connection, repo: &str) -> Result<Vec<Label>, GitHubError> {
+    let mut stmt = conn.prepare("SELECT data FROM labels WHERE repo = ?1 ORDER BY name")?;
+    let rows = stmt.query_map([repo], |row| row.get::<_, String>(0))?;
+    rows.map(|data| Ok(serde_json::from_str(&data?)?)).collect()
+}
+
+/// Replace the cached labels of a repository, or add to them when the list
+/// was cut short
+fn store_labels(
+    conn: &mut Connection,
+    repo: &str,
+    labels: &[Label],
+    etag: Option<&str>,
+    complete: bool,
+) -> Result<(), GitHubError> {
+    let tx = conn.transaction()?;
+    if complete {
+        tx.execute("DELETE FROM labels WHERE repo = ?1", [repo])?;
+    }
+    for label in labels {
+        tx.execute(
+            "INSERT OR REPLACE INTO labels (repo, name, data) VALUES (?1, ?2, ?3)",
+            params![repo, label.name, serde_json::to_string(label)?],
+        )?;
+    }
+    set_etag(&tx, &labels_key(repo), etag)?;
+    tx.commit()?;
+    Ok(())
+}
+
+fn cached_board(conn: &Connection, repo: &str) -> Result<Board, GitHubError> {
+    Ok(Board {
+        repo: repo.to_string(),
+        issues: cached_issues(conn, repo)?,
+        labels: cached_labels(conn, repo)?,
+        fetched_at: fetched_at(conn, &issues_key(repo))?,
+    })
+}
+
+/// Fetch open issues and labels, skipping whatever the cache already has.
+///
+/// Returns the refreshed board, and whether anything changed.
+pub(crate) async fn refresh_board_cache(
+    app: &AppHandle,
+    repo: &str,
+) -> Result<(Board, bool), GitHubError> {
+    let github = app.state::<GitHubState>();
+    let cache = app.state::<IssueCache>();
+
+    let (issues_etag, labels_etag) = cache.with(app, |conn| {
+        Ok((
+            etag(conn, &issues_key(repo))?,
+            etag(conn, &labels_key(repo))?,
+        ))
+    })?;
+
+    let path = issues_path(repo, &ListIssuesOptions::default());
+    let issues = github
+        .get_all_conditional::<RawIssue>(&path, issues_etag.as_deref())
+        .await?;
+    let labels = github
+        .get_all_conditional::<Label>(
+            &format!("/repos/{}/labels?per_page=100", repo),
+            labels_etag.as_deref(),
+        )
+        .await?;
+
+    let mut changed = false;
+    cache.with(app, |conn| {
+        match &issues {
+            Conditional::Modified {
+                value,
+                etag,
+                complete,
+            } => {
+                if !complete {
+                    eprintln!("Issues of {} stopped at the page limit", repo);
+                }
+                let issues: Vec<Issue> = value
+                    .iter()
+                    .filter(|issue| !issue.is_pull_request())
+                    .cloned()
+                    .map(Issue::from)
+                    .collect();
+                store_issues(conn, repo, &issues, etag.as_deref(), *complete)?;
+                changed = true;
+            }
+            Conditional::NotModified => touch_etag(conn, &issues_key(repo))?,
+        }
+        match &labels {
+            Conditional::Modified {
+                value,
+                etag,
+                complete,
+            } => {
+                store_labels(conn, repo, value, etag.as_deref(), *complete)?;
+                changed = true;
+            }
+            Conditional::NotModified => touch_etag(conn, &labels_key(repo))?,
+        }
+        Ok(())
+    })?;
+
+    let board = cache.with(app, |conn| cached_board(conn, repo))?;
+    Ok((board, changed))
+}
+
+/// Return the cached board immediately and refresh it in the background.
+///
+/// Emits `board-refreshed` with the new board when the refresh changed
+/// anything, or `board-refresh-failed` if it could not reach GitHub.
+#[tauri::command]
+pub async fn load_board(
+    app: AppHandle,
+    cache: State<'_, IssueCache>,
+    repo: String,
+) -> Result<Board, GitHubError> {
+    let board = cache.with(&app, |conn| cached_board(conn, &repo))?;
+
+    let handle = app.clone();
+    tauri::async_runtime::spawn(async move {
+        match refresh_board_cache(&handle, &repo).await {
+            Ok((board, true)) => {
+                let _ = handle.emit("board-refreshed", board);
+            }
+            Ok((_, false)) => {}
+            Err(error) => {
+                let _ = handle.emit("board-refresh-failed", BoardRefreshFailed { repo, error });
+            }
+        }
+    });
+
+    Ok(board)
+}
+
+/// Refresh the cached board and wait for the result
+#[tauri::command]
+pub async fn refresh_board(app: AppHandle, repo: String) -> Result<Board, GitHubError> {
+    refresh_board_cache(&app, &repo)
+        .await
+        .map(|(board, _)| board)
+}
+
+/// Drop everything cached for a repository
+#[tauri::command]
+pub async fn clear_issue_cache(
+    app: AppHandle,
+    cache: State<'_, IssueCache>,
+    repo: String,
+) -> Result<(), GitHubError> {
+    cache.with(&app, |conn| {
+        conn.execute("DELETE FROM issues WHERE repo = ?1", [&repo])?;
+        conn.execute("DELETE FROM labels WHERE repo = ?1", [&repo])?;
+        conn.execute("DELETE FROM comments WHERE repo = ?1", [&repo])?;
+        conn.execute(
+            "DELETE FROM etags WHERE key IN (?1, ?2)",
+            params![issues_key(&repo), labels_key(&repo)],
+        )?;
+        // Comment ETags go too, or the next fetch gets a 304 for a thread
+        // no longer cached. Matched by prefix since `_` in a name is a LIKE
+        // wildcard.
+        conn.execute(
+            "DELETE FROM etags WHERE substr(key, 1, length(?1)) = ?1",
+            [format!("comments:{}#", repo)],
+        )?;
+        Ok(())
+    })
+}
@@ -0,0 +1,281 @@
+//! CI check status
+//!
+//! Combines check runs (GitHub Actions and apps) with legacy commit statuses
+//! into one summary per PR or branch. Polling emits `checks-updated` only
+//! when the summary changes.
+
+use super::{GitHubError, GitHubState};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// Poll interval when polling is started without one
+const DEFAULT_INTERVAL_SECS: u64 = 30;
+
+/// State for check pollers
+pub struct ChecksState {
+    pollers: Mutex<HashMap<u32, Arc<AtomicBool>>>,
+    next_id: AtomicU32,
+}
+
+impl Default for ChecksState {
+    fn default() -> Self {
+        Self {
+            pollers: Mutex::new(HashMap::new()),
+            next_id: AtomicU32::new(1),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckState {
+    Pending,
+    Success,
+    Failure,
+    /// Skipped, cancelled as neutral, or no checks at all
+    Neutral,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Check {
+    name: String,
+    state: CheckState,
+    url: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckSummary {
+    sha: String,
+    /// Failure if any check failed, else pending if any is running
+    state: CheckState,
+    checks: Vec<Check>,
+}
+
+/// What to read checks for: a pull request or a branch/sha
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckTarget {
+    pr_number: Option<u64>,
+    git_ref: Option<String>,
+}
+
+/// Options for `start_checks_polling`
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChecksPollingOptions {
+    interval_secs: Option<u64>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ChecksUpdatedEvent {
+    id: u32,
+    repo: String,
+    pr_number: Option<u64>,
+    git_ref: Option<String>,
+    summary: CheckSummary,
+}
+
+#[derive(Deserialize)]
+struct RawCheckRuns {
+    check_runs: Vec<RawCheckRun>,
+}
+
+#[derive(Deserialize)]
+struct RawCheckRun {
+    name: String,
+    status: String,
+    conclusion: Option<String>,
+    html_url: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RawCombinedStatus {
+    statuses: Vec<RawStatus>,
+}
+
+#[derive(Deserialize)]
+struct RawStatus {
+    context: String,
+    state: String,
+    target_url: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RawPullHead {
+    head: RawSha,
+}
+
+#[derive(Deserialize)]
+struct RawSha {
+    sha: String,
+}
+
+fn run_state(status: &str, conclusion: Option<&str>) -> CheckState {
+    if status != "completed" {
+        return CheckState::Pending;
+    }
+    match conclusion {
+        Some("success") => CheckState::Success,
+        Some("failure" | "timed_out" | "cancelled" | "action_required" | "startup_failure") => {
+            CheckState::Failure
+        }
+        _ => CheckState::Neutral,
+    }
+}
+
+fn status_state(state: &str) -> CheckState {
+    match state {
+        "success" => CheckState::Success,
+        "failure" | "error" => CheckState::Failure,
+        _ => CheckState::Pending,
+    }
+}
+
+fn overall(checks: &[Check]) -> CheckState {
+    if checks.iter().any(|c| c.state == CheckState::Failure) {
+        CheckState::Failure
+    } else if checks.iter().any(|c| c.state == CheckState::Pending) {
+        CheckState::Pending
+    } else if checks.iter().any(|c| c.state == CheckState::Success) {
+        CheckState::Success
+    } else {
+        CheckState::Neutral
+    }
+}
+
+pub(crate) async fn check_summary(
+    github: &GitHubState,
+    repo: &str,
+    target: &CheckTarget,
+) -> Result<CheckSummary, GitHubError> {
+    let sha = match (target.pr_number, target.git_ref.as_deref()) {
+        (Some(number), _) => {
+            let pull: RawPullHead = github
+                .get(&format!("/repos/{}/pulls/{}", repo, number))
+                .await?;
+            pull.head.sha
+        }
+        (None, Some(git_ref)) => {
+            let commit: RawSha = github
+                .get(&format!("/repos/{}/commits/{}", repo, git_ref))
+                .await?;
+            commit.sha
+        }
+        (None, None) => {
+            return Err(GitHubError::Validation {
+                message: "Either prNumber or gitRef is required".to_string(),
+            })
+        }
+    };
+
+    let runs: RawCheckRuns = github
+        .get(&format!(
+            "/repos/{}/commits/{}/check-runs?per_page=100",
+            repo, sha
+        ))
+        .await?;
+    let statuses: RawCombinedStatus = github
+        .get(&format!("/repos/{}/commits/{}/status", repo, sha))
+        .await?;
+
+    let mut checks: Vec<Check> = runs
+        .check_runs
+        .into_iter()
+        .map(|run| Check {
+            state: run_state(&run.status, run.conclusion.as_deref()),
+            name: run.name,
+            url: run.html_url,
+        })
+        .chain(statuses.statuses.into_iter().map(|status| Check {
+            state: status_state(&status.state),
+            name: status.context,
+            url: status.target_url,
+        }))
+        .collect();
+    checks.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(CheckSummary {
+        sha,
+        state: overall(&checks),
+        checks,
+    })
+}
+
+/// Current check status of a pull request or ref
+#[tauri::command]
+pub async fn get_checks(
+    github: State<'_, GitHubState>,
+    repo: String,
+    target: CheckTarget,
+) -> Result<CheckSummary, GitHubError> {
+    check_summary(&github, &repo, &target).await
+}
+
+/// Poll checks for a pull request or ref, emitting `checks-updated` on change
+#[tauri::command]
+pub async fn start_checks_polling(
+    app: AppHandle,
+    state: State<'_, ChecksState>,
+    repo: String,
+    target: CheckTarget,
+    options: Option<ChecksPollingOptions>,
+) -> Result<u32, String> {
+    if target.pr_number.is_none() && target.git_ref.is_none() {
+        return Err("Either prNumber or gitRef is required".to_string());
+    }
+
+    let options = options.unwrap_or_default();
+    let interval = Duration::from_secs(
+        options
+            .interval_secs
+            .unwrap_or(DEFAULT_INTERVAL_SECS)
+            .max(5),
+    );
+
+    let id = state.next_id.fetch_add(1, Ordering::SeqCst);
+    let stopped = Arc::new(AtomicBool::new(false));
+    state.pollers.lock().insert(id, stopped.clone());
+
+    tauri::async_runtime::spawn(async move {
+        let mut last: Option<CheckSummary> = None;
+        while !stopped.load(Ordering::SeqCst) {
+            match check_summary(&app.state::<GitHubState>(), &repo, &target).await {
+                Ok(summary) if last.as_ref() != Some(&summary) => {
+                    let _ = app.emit(
+                        "checks-updated",
+                        ChecksUpdatedEvent {
+                            id,
+                            repo: repo.clone(),
+                            pr_number: target.pr_number,
+                            git_ref: target.git_ref.clone(),
+                            summary: summary.clone(),
+                        },
+                    );
+                    last = Some(summary);
+                }
+                Ok(_) => {}
+                Err(e) => eprintln!("Check polling failed for {}: {}", repo, e),
+            }
+            tokio::time::sleep(interval).await;
+        }
+    });
+
+    Ok(id)
+}
+
+/// Stop a check poller
+#[tauri::command]
+pub async fn stop_checks_polling(state: State<'_, ChecksState>, id: u32) -> Result<(), String> {
+    if let Some(stopped) = state.pollers.lock().remove(&id) {
+        stopped.store(true, Ordering::SeqCst);
+    }
+    Ok(())
+}
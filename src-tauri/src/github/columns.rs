@@ -0,0 +1,144 @@
+//! Board columns backed by labels
+//!
+//! Each board column is a label on the issue. Moving a card replaces the
+//! issue's whole label set in one `PUT`, so an issue is never left with two
+//! column labels (or none) when a request fails halfway.
+
+use super::issues::{Issue, RawIssue};
+use super::labels::Label;
+use super::queue::{enqueue, Mutation};
+use super::{GitHubError, GitHubState};
+use crate::network;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::{AppHandle, State};
+
+/// Column labels used when the caller does not pass its own
+pub(crate) const DEFAULT_COLUMN_LABELS: &[&str] =
+    &["backlog", "feature", "development", "review", "done"];
+
+/// Attempts before giving up on a column move
+const MAX_ATTEMPTS: u32 = 3;
+
+/// Options for `set_issue_column`
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetColumnOptions {
+    /// Every label that represents a column; defaults to `DEFAULT_COLUMN_LABELS`
+    columns: Option<Vec<String>>,
+}
+
+#[derive(Serialize)]
+struct SetLabelsBody<'a> {
+    labels: &'a [String],
+}
+
+/// Label set after moving to `column`: other column labels dropped, the rest kept
+pub(crate) fn labels_for_column(
+    current: &[Label],
+    columns: &[String],
+    column: &str,
+) -> Vec<String> {
+    let mut labels: Vec<String> = current
+        .iter()
+        .map(|l| l.name.clone())
+        .filter(|name| !columns.iter().any(|c| c.eq_ignore_ascii_case(name)))
+        .collect();
+    labels.push(column.to_string());
+    labels
+}
+
+/// Errors worth retrying: the network, GitHub's own failures, and the
+/// 409 returned when the issue was edited concurrently
+fn is_retryable(error: &GitHubError) -> bool {
+    match error {
+        GitHubError::Network { .. } => true,
+        GitHubError::Api { status, .. } => *status == 409 || *status >= 500,
+        _ => false,
+    }
+}
+
+pub(crate) async fn move_to_column(
+    github: &GitHubState,
+    repo: &str,
+    number: u64,
+    column: &str,
+    columns: &[String],
+) -> Result<Issue, GitHubError> {
+    let issue_path = format!("/repos/{}/issues/{}", repo, number);
+    let mut attempt = 0;
+
+    loop {
+        attempt += 1;
+        let result = async {
+            // Re-read every attempt so labels added meanwhile are kept
+            let issue: RawIssue = github.get(&issue_path).await?;
+            let current = Issue::from(issue);
+            let labels = labels_for_column(&current.labels, columns, column);
+
+            let _: Vec<Label> = github
+                .send(
+                    Method::PUT,
+                    &format!("{}/labels", issue_path),
+                    &SetLabelsBody { labels: &labels },
+                )
+                .await?;
+            let issue: RawIssue = github.get(&issue_path).await?;
+            Ok(Issue::from(issue))
+        }
+        .await;
+
+        match result {
+            Err(e) if attempt < MAX_ATTEMPTS && is_retryable(&e) => {
+                tokio::time::sleep(Duration::from_millis(500 * 2u64.pow(attempt - 1))).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Move an issue to a board column by swapping its column label
+///
+/// Moves that fail because GitHub is unreachable are queued for replay and
+/// reported as a `queued` error.
+#[tauri::command]
+pub async fn set_issue_column(
+    app: AppHandle,
+    github: State<'_, GitHubState>,
+    repo: String,
+    number: u64,
+    column: String,
+    options: Option<SetColumnOptions>,
+) -> Result<Issue, GitHubError> {
+    let columns = options.unwrap_or_default().columns.unwrap_or_else(|| {
+        DEFAULT_COLUMN_LABELS
+            .iter()
+            .map(|c| c.to_string())
+            .collect()
+    });
+
+    if !columns.iter().any(|c| c.eq_ignore_ascii_case(&column)) {
+        return Err(GitHubError::Validation {
+            message: format!("'{}' is not a board column", column),
+        });
+    }
+
+    if network::is_offline(&app) {
+        return Err(enqueue(
+            &app,
+            &repo,
+            number,
+            Mutation::Column { column, columns },
+        ));
+    }
+    match move_to_column(&github, &repo, number, &column, &columns).await {
+        Err(GitHubError::Network { .. }) => Err(enqueue(
+            &app,
+            &repo,
+            number,
+            Mutation::Column { column, columns },
+        )),
+        result => result,
+    }
+}
@@ -0,0 +1,286 @@
+//! Issue comment commands
+//!
+//! Comment threads are cached next to issues, so the detail modal opens with
+//! the last known thread when offline and refreshes with `If-None-Match`.
+
+use super::cache::{etag, set_etag, IssueCache};
+use super::issues::RawUser;
+use super::queue::{enqueue, Mutation};
+use super::{Conditional, GitHubError, GitHubState};
+use crate::network;
+use reqwest::Method;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+
+/// GitHub rejects comment bodies longer than this
+const MAX_COMMENT_CHARS: usize = 65_536;
+
+/// Transcript length kept when the caller does not choose one
+const DEFAULT_TRANSCRIPT_CHARS: usize = 20_000;
+
+#[derive(Deserialize)]
+struct RawComment {
+    id: u64,
+    body: Option<String>,
+    user: Option<RawUser>,
+    html_url: String,
+    created_at: String,
+    updated_at: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Comment {
+    id: u64,
+    author: String,
+    body: String,
+    url: String,
+    created_at: String,
+    updated_at: String,
+}
+
+impl From<RawComment> for Comment {
+    fn from(raw: RawComment) -> Self {
+        Self {
+            id: raw.id,
+            author: raw.user.map(|u| u.login).unwrap_or_default(),
+            body: raw.body.unwrap_or_default(),
+            url: raw.html_url,
+            created_at: raw.created_at,
+            updated_at: raw.updated_at,
+        }
+    }
+}
+
+/// Options for `list_issue_comments`
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListCommentsOptions {
+    /// Return the cached thread without contacting GitHub
+    #[serde(default)]
+    cached_only: bool,
+}
+
+/// Options for `post_agent_transcript`
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptOptions {
+    /// Heading shown above the transcript
+    title: Option<String>,
+    /// Characters kept from the end of the transcript
+    max_chars: Option<usize>,
+}
+
+fn comments_key(repo: &str, number: u64) -> String {
+    format!("comments:{}#{}", repo, number)
+}
+
+fn cached_comments(
+    conn: &Connection,
+    repo: &str,
+    number: u64,
+) -> Result<Vec<Comment>, GitHubError> {
+    let mut stmt =
+        conn.prepare("SELECT data FROM comments WHERE repo = ?1 AND issue = ?2 ORDER BY id")?;
+    let rows = stmt.query_map(params![repo, number as i64], |row| row.get::<_, String>(0))?;
+    rows.map(|data| Ok(serde_json::from_str(&data?)?)).collect()
+}
+
+fn insert_comment(
+    conn: &Connection,
+    repo: &str,
+    number: u64,
+    comment: &Comment,
+) -> Result<(), GitHubError> {
+    conn.execute(
+        "INSERT OR REPLACE INTO comments (repo, issue, id, data) VALUES (?1, ?2, ?3, ?4)",
+        params![
+            repo,
+            number as i64,
+            comment.id as i64,
+            serde_json::to_string(comment)?
+        ],
+    )?;
+    Ok(())
+}
+
+/// Replace the cached comments of an issue; a list cut short only replaces
+/// the oldest, up to the newest it reached
+fn store_comments(
+    conn: &mut Connection,
+    repo: &str,
+    number: u64,
+    comments: &[Comment],
+    etag: Option<&str>,
+    complete: bool,
+) -> Result<(), GitHubError> {
+    let tx = conn.transaction()?;
+    let newest = match comments.iter().map(|comment| comment.id).max() {
+        Some(newest) if !complete => newest as i64,
+        _ => i64::MAX,
+    };
+    tx.execute(
+        "DELETE FROM comments WHERE repo = ?1 AND issue = ?2 AND id <= ?3",
+        params![repo, number as i64, newest],
+    )?;
+    for comment in comments {
+        insert_comment(&tx, repo, number, comment)?;
+    }
+    set_etag(&tx, &comments_key(repo, number), etag)?;
+    tx.commit()?;
+    Ok(())
+}
+
+/// Keep the tail of a transcript so it fits in a comment
+pub(crate) fn trim_transcript(transcript: &str, max_chars: usize) -> (String, bool) {
+    let transcript = transcript.trim();
+    let total = transcript.chars().count();
+    if total <= max_chars {
+        return (transcript.to_string(), false);
+    }
+
+    let tail: String = transcript.chars().skip(total - max_chars).collect();
+    // Start on a line boundary so the first line isn't cut mid-way
+    let tail = match tail.split_once('\n') {
+        Some((_, rest)) if !rest.is_empty() => rest.to_string(),
+        _ => tail,
+    };
+    (tail, true)
+}
+
+pub(crate) async fn post_comment(
+    github: &GitHubState,
+    app: &AppHandle,
+    cache: &IssueCache,
+    repo: &str,
+    number: u64,
+    body: &str,
+) -> Result<Comment, GitHubError> {
+    let raw: RawComment = github
+        .send(
+            Method::POST,
+            &format!("/repos/{}/issues/{}/comments", repo, number),
+            &serde_json::json!({ "body": body }),
+        )
+        .await?;
+    let comment = Comment::from(raw);
+
+    cache.with(app, |conn| insert_comment(conn, repo, number, &comment))?;
+    Ok(comment)
+}
+
+/// Fetch an issue's comment thread, answering from the cache when unchanged
+/// or when GitHub can't be reached
+#[tauri::command]
+pub async fn list_issue_comments(
+    app: AppHandle,
+    github: State<'_, GitHubState>,
+    cache: State<'_, IssueCache>,
+    repo: String,
+    number: u64,
+    options: Option<ListCommentsOptions>,
+) -> Result<Vec<Comment>, GitHubError> {
+    let options = options.unwrap_or_default();
+    let key = comments_key(&repo, number);
+    let cached_etag = cache.with(&app, |conn| etag(conn, &key))?;
+
+    if options.cached_only {
+        return cache.with(&app, |conn| cached_comments(conn, &repo, number));
+    }
+
+    let path = format!("/repos/{}/issues/{}/comments?per_page=100", repo, number);
+    match github
+        .get_all_conditional::<RawComment>(&path, cached_etag.as_deref())
+        .await
+    {
+        Ok(Conditional::Modified {
+            value,
+            etag,
+            complete,
+        }) => {
+            let comments: Vec<Comment> = value.into_iter().map(Comment::from).collect();
+            cache.with(&app, |conn| {
+                store_comments(conn, &repo, number, &comments, etag.as_deref(), complete)
+            })?;
+            Ok(comments)
+        }
+        Ok(Conditional::NotModified) | Err(GitHubError::Network { .. }) => {
+            cache.with(&app, |conn| cached_comments(conn, &repo, number))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Post a comment on an issue.
+///
+/// Comments that fail because GitHub is unreachable are queued for replay
+/// and reported as a `queued` error.
+#[tauri::command]
+pub async fn post_issue_comment(
+    app: AppHandle,
+    github: State<'_, GitHubState>,
+    cache: State<'_, IssueCache>,
+    repo: String,
+    number: u64,
+    body: String,
+) -> Result<Comment, GitHubError> {
+    if body.trim().is_empty() {
+        return Err(GitHubError::Validation {
+            message: "Comment body is empty".to_string(),
+        });
+    }
+    if body.chars().count() > MAX_COMMENT_CHARS {
+        return Err(GitHubError::Validation {
+            message: format!("Comment is longer than {} characters", MAX_COMMENT_CHARS),
+        });
+    }
+
+    if network::is_offline(&app) {
+        return Err(enqueue(&app, &repo, number, Mutation::Comment { body }));
+    }
+    match post_comment(&github, &app, &cache, &repo, number, &body).await {
+        Err(GitHubError::Network { .. }) => {
+            Err(enqueue(&app, &repo, number, Mutation::Comment { body }))
+        }
+        result => result,
+    }
+}
+
+/// Post the end of an agent session's transcript as a collapsed comment
+#[tauri::command]
+pub async fn post_agent_transcript(
+    app: AppHandle,
+    github: State<'_, GitHubState>,
+    cache: State<'_, IssueCache>,
+    repo: String,
+    number: u64,
+    transcript: String,
+    options: Option<TranscriptOptions>,
+) -> Result<Comment, GitHubError> {
+    let options = options.unwrap_or_default();
+    let title = options
+        .title
+        .unwrap_or_else(|| "Agent transcript".to_string());
+    // Leave room for the wrapper markup
+    let max_chars = options
+        .max_chars
+        .unwrap_or(DEFAULT_TRANSCRIPT_CHARS)
+        .min(MAX_COMMENT_CHARS.saturating_sub(title.len() + 200));
+
+    let (tail, trimmed) = trim_transcript(&transcript, max_chars);
+    if tail.is_empty() {
+        return Err(GitHubError::Validation {
+            message: "Transcript is empty".to_string(),
+        });
+    }
+
+    let fence = if tail.contains("```") { "````" } else { "```" };
+    let note = if trimmed { " (last part)" } else { "" };
+    let body = format!(
+        "<details>\n<summary>{}{}</summary>\n\n{}text\n{}\n{}\n\n</details>",
+        title, note, fence, tail, fence
+    );
+
+    post_comment(&github, &app, &cache, &repo, number, &body).await
+}
@@ -0,0 +1,64 @@
+//! GitHub instance selection
+//!
+//! Points the module at GitHub Enterprise Server instead of github.com. The
+//! frontend calls `set_github_host` at startup from the project config.
+
+use super::{GitHubError, GitHubHost, GitHubState};
+use serde::Deserialize;
+use tauri::State;
+
+/// Options for `set_github_host`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitHubHostOptions {
+    /// Web UI base, e.g. `https://ghe.example.com`
+    web_base: String,
+    /// REST API base, defaults to `<webBase>/api/v3`
+    api_base: Option<String>,
+}
+
+fn normalize(url: &str) -> Result<String, GitHubError> {
+    let url = url.trim().trim_end_matches('/');
+    if !url.starts_with("https://") && !url.starts_with("http://") {
+        return Err(GitHubError::Validation {
+            message: format!("'{}' is not an http(s) URL", url),
+        });
+    }
+    Ok(url.to_string())
+}
+
+/// Use a custom GitHub instance; its token is stored separately
+#[tauri::command]
+pub async fn set_github_host(
+    state: State<'_, GitHubState>,
+    options: GitHubHostOptions,
+) -> Result<GitHubHost, GitHubError> {
+    let web_base = normalize(&options.web_base)?;
+    let mut host = GitHubHost {
+        api_base: String::new(),
+        web_base,
+    };
+    host.api_base = match options.api_base {
+        Some(api_base) => normalize(&api_base)?,
+        None if host.is_github_com() => GitHubHost::default().api_base,
+        None => format!("{}/api/v3", host.web_base),
+    };
+
+    if state.host() != host {
+        state.set_host(host.clone());
+    }
+    Ok(host)
+}
+
+/// Go back to github.com
+#[tauri::command]
+pub async fn reset_github_host(state: State<'_, GitHubState>) -> Result<GitHubHost, GitHubError> {
+    state.set_host(GitHubHost::default());
+    Ok(GitHubHost::default())
+}
+
+/// The GitHub instance in use
+#[tauri::command]
+pub async fn get_github_host(state: State<'_, GitHubState>) -> Result<GitHubHost, GitHubError> {
+    Ok(state.host())
+}
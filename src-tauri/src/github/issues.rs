@@ -0,0 +1,329 @@
+//! Issue commands
+//!
+//! The issues endpoint also returns pull requests; those are filtered out.
+
+use super::labels::{encode_label, Label};
+use super::milestones::Milestone;
+use super::queue::{enqueue, Mutation};
+use super::{double_option, GitHubError, GitHubState};
+use crate::network;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+
+#[derive(Clone, Debug, Deserialize)]
+pub(crate) struct RawUser {
+    pub(crate) login: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub(crate) struct RawIssue {
+    number: u64,
+    title: String,
+    body: Option<String>,
+    state: String,
+    html_url: String,
+    user: Option<RawUser>,
+    #[serde(default)]
+    labels: Vec<Label>,
+    #[serde(default)]
+    assignees: Vec<RawUser>,
+    milestone: Option<Milestone>,
+    comments: u64,
+    created_at: String,
+    pub(crate) updated_at: String,
+    pull_request: Option<serde_json::Value>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Issue {
+    pub(crate) number: u64,
+    pub(crate) title: String,
+    pub(crate) body: String,
+    state: String,
+    pub(crate) url: String,
+    author: String,
+    pub(crate) labels: Vec<Label>,
+    assignees: Vec<String>,
+    milestone: Option<Milestone>,
+    comment_count: u64,
+    created_at: String,
+    pub(crate) updated_at: String,
+}
+
+impl From<RawIssue> for Issue {
+    fn from(raw: RawIssue) -> Self {
+        Self {
+            number: raw.number,
+            title: raw.title,
+            body: raw.body.unwrap_or_default(),
+            state: raw.state,
+            url: raw.html_url,
+            author: raw.user.map(|u| u.login).unwrap_or_default(),
+            labels: raw.labels,
+            assignees: raw.assignees.into_iter().map(|u| u.login).collect(),
+            milestone: raw.milestone,
+            comment_count: raw.comments,
+            created_at: raw.created_at,
+            updated_at: raw.updated_at,
+        }
+    }
+}
+
+impl RawIssue {
+    pub(crate) fn is_pull_request(&self) -> bool {
+        self.pull_request.is_some()
+    }
+}
+
+/// Options for `list_issues`
+#[derive(Debug, Default, Deserialize)]
+pub struct ListIssuesOptions {
+    /// "open" (default), "closed" or "all"
+    state: Option<String>,
+    /// Only issues carrying all of these labels
+    #[serde(default)]
+    labels: Vec<String>,
+}
+
+/// Parameters for `create_issue`
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CreateIssueParams {
+    title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    body: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    labels: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    assignees: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    milestone: Option<u64>,
+}
+
+/// Parameters for `update_issue`, mirroring the frontend's add/remove style
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateIssueParams {
+    title: Option<String>,
+    body: Option<String>,
+    /// "open" or "closed"
+    state: Option<String>,
+    #[serde(default)]
+    add_labels: Vec<String>,
+    #[serde(default)]
+    remove_labels: Vec<String>,
+    #[serde(default)]
+    add_assignees: Vec<String>,
+    #[serde(default)]
+    remove_assignees: Vec<String>,
+    /// Milestone number; `null` clears it
+    #[serde(default, deserialize_with = "double_option")]
+    milestone: Option<Option<u64>>,
+}
+
+#[derive(Serialize)]
+struct EditIssueBody<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    body: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    state: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    milestone: Option<Option<u64>>,
+}
+
+#[derive(Serialize)]
+struct AssigneesBody<'a> {
+    assignees: &'a [String],
+}
+
+pub(crate) fn issues_path(repo: &str, options: &ListIssuesOptions) -> String {
+    let mut path = format!(
+        "/repos/{}/issues?state={}&per_page=100",
+        repo,
+        options.state.as_deref().unwrap_or("open")
+    );
+    if !options.labels.is_empty() {
+        let labels: Vec<String> = options.labels.iter().map(|l| encode_label(l)).collect();
+        path.push_str("&labels=");
+        path.push_str(&labels.join(","));
+    }
+    path
+}
+
+/// List issues of a repository (pull requests excluded)
+#[tauri::command]
+pub async fn list_issues(
+    state: State<'_, GitHubState>,
+    repo: String,
+    options: Option<ListIssuesOptions>,
+) -> Result<Vec<Issue>, GitHubError> {
+    let options = options.unwrap_or_default();
+    let raw: Vec<RawIssue> = state.get_all(&issues_path(&repo, &options)).await?;
+
+    Ok(raw
+        .into_iter()
+        .filter(|issue| !issue.is_pull_request())
+        .map(Issue::from)
+        .collect())
+}
+
+/// Get a single issue
+#[tauri::command]
+pub async fn get_issue(
+    state: State<'_, GitHubState>,
+    repo: String,
+    number: u64,
+) -> Result<Issue, GitHubError> {
+    let raw: RawIssue = state
+        .get(&format!("/repos/{}/issues/{}", repo, number))
+        .await?;
+    Ok(raw.into())
+}
+
+/// Create an issue
+#[tauri::command]
+pub async fn create_issue(
+    state: State<'_, GitHubState>,
+    repo: String,
+    params: CreateIssueParams,
+) -> Result<Issue, GitHubError> {
+    let raw: RawIssue = state
+        .send(Method::POST, &format!("/repos/{}/issues", repo), &params)
+        .await?;
+    Ok(raw.into())
+}
+
+/// Add and remove labels on an issue
+pub(crate) async fn edit_labels(
+    state: &GitHubState,
+    repo: &str,
+    number: u64,
+    add: &[String],
+    remove: &[String],
+) -> Result<(), GitHubError> {
+    let issue_path = format!("/repos/{}/issues/{}", repo, number);
+
+    if !add.is_empty() {
+        let _: Vec<Label> = state
+            .send(
+                Method::POST,
+                &format!("{}/labels", issue_path),
+                &serde_json::json!({ "labels": add }),
+            )
+            .await?;
+    }
+    for label in remove {
+        match state
+            .delete(&format!("{}/labels/{}", issue_path, encode_label(label)))
+            .await
+        {
+            // Already gone is fine
+            Ok(()) | Err(GitHubError::NotFound { .. }) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+impl UpdateIssueParams {
+    /// Whether the update only touches labels, and so can be queued offline
+    fn is_label_only(&self) -> bool {
+        self.title.is_none()
+            && self.body.is_none()
+            && self.state.is_none()
+            && self.add_assignees.is_empty()
+            && self.remove_assignees.is_empty()
+            && self.milestone.is_none()
+    }
+}
+
+async fn apply_update(
+    state: &GitHubState,
+    repo: &str,
+    number: u64,
+    params: &UpdateIssueParams,
+) -> Result<Issue, GitHubError> {
+    let issue_path = format!("/repos/{}/issues/{}", repo, number);
+
+    edit_labels(
+        state,
+        repo,
+        number,
+        &params.add_labels,
+        &params.remove_labels,
+    )
+    .await?;
+    if !params.add_assignees.is_empty() {
+        let _: RawIssue = state
+            .send(
+                Method::POST,
+                &format!("{}/assignees", issue_path),
+                &AssigneesBody {
+                    assignees: &params.add_assignees,
+                },
+            )
+            .await?;
+    }
+    if !params.remove_assignees.is_empty() {
+        let _: RawIssue = state
+            .send(
+                Method::DELETE,
+                &format!("{}/assignees", issue_path),
+                &AssigneesBody {
+                    assignees: &params.remove_assignees,
+                },
+            )
+            .await?;
+    }
+
+    // PATCH last so the returned issue reflects every change
+    let raw: RawIssue = state
+        .send(
+            Method::PATCH,
+            &issue_path,
+            &EditIssueBody {
+                title: params.title.as_deref(),
+                body: params.body.as_deref(),
+                state: params.state.as_deref(),
+                milestone: params.milestone,
+            },
+        )
+        .await?;
+    Ok(raw.into())
+}
+
+/// Update an issue's fields, labels and assignees.
+///
+/// Label-only edits that fail because GitHub is unreachable are queued for
+/// replay and reported as a `queued` error.
+#[tauri::command]
+pub async fn update_issue(
+    app: AppHandle,
+    state: State<'_, GitHubState>,
+    repo: String,
+    number: u64,
+    params: UpdateIssueParams,
+) -> Result<Issue, GitHubError> {
+    let result = if network::is_offline(&app) {
+        Err(GitHubError::Network {
+            message: "Offline".to_string(),
+        })
+    } else {
+        apply_update(&state, &repo, number, &params).await
+    };
+    match result {
+        Err(GitHubError::Network { .. }) if params.is_label_only() => Err(enqueue(
+            &app,
+            &repo,
+            number,
+            Mutation::Labels {
+                add: params.add_labels,
+                remove: params.remove_labels,
+            },
+        )),
+        result => result,
+    }
+}
@@ -0,0 +1,100 @@
+//! Label commands
+
+use super::{GitHubError, GitHubState};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Label {
+    pub(crate) name: String,
+    color: String,
+    description: Option<String>,
+}
+
+/// Parameters for `create_label`
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CreateLabelParams {
+    name: String,
+    color: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+}
+
+/// Percent-encode a label name for use in a URL path
+pub(crate) fn encode_label(name: &str) -> String {
+    name.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// List all labels of a repository
+#[tauri::command]
+pub async fn list_labels(
+    state: State<'_, GitHubState>,
+    repo: String,
+) -> Result<Vec<Label>, GitHubError> {
+    state
+        .get_all(&format!("/repos/{}/labels?per_page=100", repo))
+        .await
+}
+
+/// Create a label in a repository
+#[tauri::command]
+pub async fn create_label(
+    state: State<'_, GitHubState>,
+    repo: String,
+    params: CreateLabelParams,
+) -> Result<Label, GitHubError> {
+    state
+        .send(Method::POST, &format!("/repos/{}/labels", repo), &params)
+        .await
+}
+
+/// Add labels to an issue, returning the issue's labels
+#[tauri::command]
+pub async fn add_issue_labels(
+    state: State<'_, GitHubState>,
+    repo: String,
+    number: u64,
+    labels: Vec<String>,
+) -> Result<Vec<Label>, GitHubError> {
+    #[derive(Serialize)]
+    struct Body {
+        labels: Vec<String>,
+    }
+
+    state
+        .send(
+            Method::POST,
+            &format!("/repos/{}/issues/{}/labels", repo, number),
+            &Body { labels },
+        )
+        .await
+}
+
+/// Remove a label from an issue, returning the issue's remaining labels
+#[tauri::command]
+pub async fn remove_issue_label(
+    state: State<'_, GitHubState>,
+    repo: String,
+    number: u64,
+    label: String,
+) -> Result<Vec<Label>, GitHubError> {
+    state
+        .send_empty(
+            Method::DELETE,
+            &format!(
+                "/repos/{}/issues/{}/labels/{}",
+                repo,
+                number,
+                encode_label(&label)
+            ),
+        )
+        .await
+}
@@ -0,0 +1,62 @@
+//! Milestone commands
+
+use super::{GitHubError, GitHubState};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all(serialize = "camelCase"))]
+pub struct Milestone {
+    number: u64,
+    title: String,
+    description: Option<String>,
+    state: String,
+    #[serde(alias = "dueOn")]
+    due_on: Option<String>,
+    #[serde(alias = "openIssues")]
+    open_issues: u64,
+    #[serde(alias = "closedIssues")]
+    closed_issues: u64,
+}
+
+/// Parameters for `create_milestone`
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all(deserialize = "camelCase"))]
+pub struct CreateMilestoneParams {
+    title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    due_on: Option<String>,
+}
+
+/// List open milestones of a repository
+#[tauri::command]
+pub async fn list_milestones(
+    state: State<'_, GitHubState>,
+    repo: String,
+) -> Result<Vec<Milestone>, GitHubError> {
+    state
+        .get_all(&format!(
+            "/repos/{}/milestones?state=open&per_page=100",
+            repo
+        ))
+        .await
+}
+
+/// Create a milestone in a repository
+#[tauri::command]
+pub async fn create_milestone(
+    state: State<'_, GitHubState>,
+    repo: String,
+    params: CreateMilestoneParams,
+) -> Result<Milestone, GitHubError> {
+    state
+        .send(
+            Method::POST,
+            &format!("/repos/{}/milestones", repo),
+            &params,
+        )
+        .await
+}
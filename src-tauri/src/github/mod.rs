@@ -0,0 +1,430 @@
+//! GitHub module - native REST client
+//!
+//! Talks to the GitHub REST API directly instead of going through the `gh`
+//! CLI. The token is kept in the OS keychain (see `token`) and cached in
+//! memory once read. Errors are typed so the frontend can tell a missing
+//! token from a rate limit or a network failure.
+
+pub mod cache;
+pub mod checks;
+pub mod columns;
+pub mod comments;
+pub mod host;
+pub mod issues;
+pub mod labels;
+pub mod milestones;
+pub mod oauth;
+pub mod pulls;
+pub mod queue;
+pub mod sync;
+pub mod token;
+pub mod webhook;
+
+use parking_lot::Mutex;
+use reqwest::header::{HeaderMap, ACCEPT, AUTHORIZATION, ETAG, IF_NONE_MATCH, LINK, USER_AGENT};
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// Public GitHub REST API
+const DEFAULT_API_BASE: &str = "https://api.github.com";
+
+/// Public GitHub web UI, also serving OAuth endpoints
+const DEFAULT_WEB_BASE: &str = "https://github.com";
+
+/// Upper bound on pages fetched by `get_all`
+const MAX_PAGES: usize = 10;
+
+/// Result of a conditional request
+pub(crate) enum Conditional<T> {
+    NotModified,
+    /// `complete` is false when a list ran past `MAX_PAGES` and was cut short
+    Modified {
+        value: T,
+        etag: Option<String>,
+        complete: bool,
+    },
+}
+
+/// Core rate limit reported by the last response
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct RateLimit {
+    pub(crate) remaining: u32,
+    /// Unix timestamp when the window resets
+    pub(crate) reset_at: i64,
+}
+
+/// Error returned by GitHub commands
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum GitHubError {
+    /// No token stored yet
+    NoToken,
+    /// Token missing permissions or revoked
+    Unauthorized {
+        message: String,
+    },
+    NotFound {
+        message: String,
+    },
+    /// Rate limit exhausted; `reset_at` is a Unix timestamp
+    RateLimited {
+        reset_at: Option<i64>,
+    },
+    /// GitHub rejected the request payload
+    Validation {
+        message: String,
+    },
+    Network {
+        message: String,
+    },
+    Api {
+        status: u16,
+        message: String,
+    },
+    Keychain {
+        message: String,
+    },
+    /// GitHub was unreachable; the mutation was queued for replay
+    Queued {
+        mutation_id: i64,
+    },
+    /// Local issue cache could not be read or written
+    Cache {
+        message: String,
+    },
+    /// The user stopped a device login before it finished
+    Cancelled,
+}
+
+impl std::fmt::Display for GitHubError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GitHubError::NoToken => write!(f, "No GitHub token stored"),
+            GitHubError::Cancelled => write!(f, "Login cancelled"),
+            GitHubError::RateLimited { reset_at } => {
+                let reset = reset_at.and_then(|at| chrono::DateTime::from_timestamp(at, 0));
+                match reset {
+                    Some(reset) => write!(f, "GitHub rate limit exhausted until {}", reset),
+                    None => write!(f, "GitHub rate limit exhausted"),
+                }
+            }
+            GitHubError::Api { status, message } => {
+                write!(f, "GitHub answered {}: {}", status, message)
+            }
+            GitHubError::Queued { mutation_id } => write!(
+                f,
+                "GitHub is unreachable; the change was queued as mutation {}",
+                mutation_id
+            ),
+            GitHubError::Unauthorized { message }
+            | GitHubError::NotFound { message }
+            | GitHubError::Validation { message }
+            | GitHubError::Network { message }
+            | GitHubError::Keychain { message }
+            | GitHubError::Cache { message } => f.write_str(message),
+        }
+    }
+}
+
+impl From<reqwest::Error> for GitHubError {
+    fn from(e: reqwest::Error) -> Self {
+        GitHubError::Network {
+            message: e.to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ApiErrorBody {
+    message: Option<String>,
+}
+
+/// API and web endpoints of the GitHub instance in use
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitHubHost {
+    pub(crate) api_base: String,
+    pub(crate) web_base: String,
+}
+
+impl Default for GitHubHost {
+    fn default() -> Self {
+        Self {
+            api_base: DEFAULT_API_BASE.to_string(),
+            web_base: DEFAULT_WEB_BASE.to_string(),
+        }
+    }
+}
+
+impl GitHubHost {
+    /// Host name of the web UI, e.g. `github.com` or `ghe.example.com`
+    pub(crate) fn hostname(&self) -> &str {
+        let rest = self
+            .web_base
+            .split_once("://")
+            .map_or(self.web_base.as_str(), |(_, rest)| rest);
+        rest.split(['/', ':']).next().unwrap_or(rest)
+    }
+
+    pub(crate) fn is_github_com(&self) -> bool {
+        self.hostname().eq_ignore_ascii_case("github.com")
+    }
+}
+
+/// State shared by GitHub commands
+pub struct GitHubState {
+    http: Mutex<reqwest::Client>,
+    host: Mutex<GitHubHost>,
+    token: Mutex<Option<String>>,
+    rate_limit: Mutex<Option<RateLimit>>,
+}
+
+impl Default for GitHubState {
+    fn default() -> Self {
+        Self {
+            http: Mutex::new(reqwest::Client::new()),
+            host: Mutex::new(GitHubHost::default()),
+            token: Mutex::new(None),
+            rate_limit: Mutex::new(None),
+        }
+    }
+}
+
+impl GitHubState {
+    /// Token from the in-memory cache, falling back to the keychain
+    pub(crate) fn token(&self) -> Result<String, GitHubError> {
+        if let Some(token) = self.token.lock().clone() {
+            return Ok(token);
+        }
+        let token = token::load_token(&self.host())?.ok_or(GitHubError::NoToken)?;
+        *self.token.lock() = Some(token.clone());
+        Ok(token)
+    }
+
+    pub(crate) fn host(&self) -> GitHubHost {
+        self.host.lock().clone()
+    }
+
+    /// Switch instances; the token and rate limit belong to the old one
+    pub(crate) fn set_host(&self, host: GitHubHost) {
+        *self.host.lock() = host;
+        *self.token.lock() = None;
+        *self.rate_limit.lock() = None;
+    }
+
+    pub(crate) fn http(&self) -> reqwest::Client {
+        self.http.lock().clone()
+    }
+
+    /// Send later requests through a client set up for a new proxy
+    pub(crate) fn set_http(&self, http: reqwest::Client) {
+        *self.http.lock() = http;
+    }
+
+    pub(crate) fn set_cached_token(&self, token: Option<String>) {
+        *self.token.lock() = token;
+    }
+
+    fn request(&self, method: Method, path: &str) -> Result<RequestBuilder, GitHubError> {
+        let url = if path.starts_with("https://") || path.starts_with("http://") {
+            path.to_string()
+        } else {
+            format!("{}{}", self.host.lock().api_base, path)
+        };
+
+        Ok(self
+            .http()
+            .request(method, url)
+            .header(AUTHORIZATION, format!("Bearer {}", self.token()?))
+            .header(ACCEPT, "application/vnd.github+json")
+            .header(USER_AGENT, "Antler")
+            .header("X-GitHub-Api-Version", "2022-11-28"))
+    }
+
+    pub(crate) async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, GitHubError> {
+        let response = self
+            .check(self.request(Method::GET, path)?.send().await?)
+            .await?;
+        Ok(response.json().await?)
+    }
+
+    /// GET every page of a list endpoint by following `Link: rel="next"`
+    pub(crate) async fn get_all<T: DeserializeOwned>(
+        &self,
+        path: &str,
+    ) -> Result<Vec<T>, GitHubError> {
+        match self.get_all_conditional(path, None).await? {
+            Conditional::Modified { value, .. } => Ok(value),
+            Conditional::NotModified => Ok(Vec::new()),
+        }
+    }
+
+    /// Like `get_all`, but revalidates the pages of an earlier fetch first.
+    ///
+    /// `etag` is the one that fetch returned: the ETag and URL of each page,
+    /// a line apiece, since a change on a later page leaves the first page's
+    /// ETag alone. The list is unchanged when every page answers 304, which
+    /// does not count against the rate limit. Lists cut short at `MAX_PAGES`
+    /// get no `etag`, as the pages past it can't be revalidated.
+    pub(crate) async fn get_all_conditional<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        etag: Option<&str>,
+    ) -> Result<Conditional<Vec<T>>, GitHubError> {
+        if let Some(etag) = etag {
+            if self.pages_unchanged(etag).await? {
+                return Ok(Conditional::NotModified);
+            }
+        }
+
+        let mut items = Vec::new();
+        let mut pages = Vec::new();
+        let mut next = Some(path.to_string());
+        for _ in 0..MAX_PAGES {
+            let Some(url) = next.take() else {
+                break;
+            };
+            let response = self
+                .check(self.request(Method::GET, &url)?.send().await?)
+                .await?;
+            let etag = response.headers().get(ETAG).and_then(|v| v.to_str().ok());
+            pages.push(etag.map(|etag| format!("{} {}", etag, url)));
+            next = next_page(response.headers());
+            items.extend(response.json::<Vec<T>>().await?);
+        }
+
+        let complete = next.is_none();
+        let etag = pages
+            .into_iter()
+            .collect::<Option<Vec<_>>>()
+            .filter(|_| complete)
+            .map(|pages| pages.join("\n"));
+        Ok(Conditional::Modified {
+            value: items,
+            etag,
+            complete,
+        })
+    }
+
+    /// Whether every page of an earlier fetch answers 304
+    async fn pages_unchanged(&self, etag: &str) -> Result<bool, GitHubError> {
+        for page in etag.lines() {
+            let Some((etag, url)) = page.split_once(' ') else {
+                return Ok(false);
+            };
+            let response = self
+                .request(Method::GET, url)?
+                .header(IF_NONE_MATCH, etag)
+                .send()
+                .await?;
+            if response.status() != StatusCode::NOT_MODIFIED {
+                self.check(response).await?;
+                return Ok(false);
+            }
+            self.record_rate_limit(response.headers());
+        }
+        Ok(true)
+    }
+
+    pub(crate) async fn send<T: DeserializeOwned, B: Serialize + ?Sized>(
+        &self,
+        method: Method,
+        path: &str,
+        body: &B,
+    ) -> Result<T, GitHubError> {
+        let response = self
+            .check(self.request(method, path)?.json(body).send().await?)
+            .await?;
+        Ok(response.json().await?)
+    }
+
+    /// Bodyless request whose response is decoded
+    pub(crate) async fn send_empty<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+    ) -> Result<T, GitHubError> {
+        let response = self
+            .check(self.request(method, path)?.send().await?)
+            .await?;
+        Ok(response.json().await?)
+    }
+
+    pub(crate) async fn delete(&self, path: &str) -> Result<(), GitHubError> {
+        self.check(self.request(Method::DELETE, path)?.send().await?)
+            .await?;
+        Ok(())
+    }
+
+    fn record_rate_limit(&self, headers: &HeaderMap) -> Option<RateLimit> {
+        let header = |name: &str| headers.get(name)?.to_str().ok();
+        let limit = RateLimit {
+            remaining: header("x-ratelimit-remaining")?.parse().ok()?,
+            reset_at: header("x-ratelimit-reset")?.parse().ok()?,
+        };
+        *self.rate_limit.lock() = Some(limit);
+        Some(limit)
+    }
+
+    /// Rate limit as of the last response, if any
+    pub(crate) fn rate_limit(&self) -> Option<RateLimit> {
+        *self.rate_limit.lock()
+    }
+
+    /// Record rate-limit headers and map non-success responses to a typed error
+    async fn check(&self, response: Response) -> Result<Response, GitHubError> {
+        let rate_limit = self.record_rate_limit(response.headers());
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+
+        let rate_limited = rate_limit.is_some_and(|limit| limit.remaining == 0);
+        let reset_at = rate_limit.map(|limit| limit.reset_at);
+
+        let message = response
+            .json::<ApiErrorBody>()
+            .await
+            .ok()
+            .and_then(|body| body.message)
+            .unwrap_or_else(|| status.to_string());
+
+        Err(match status {
+            StatusCode::UNAUTHORIZED => GitHubError::Unauthorized { message },
+            StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS if rate_limited => {
+                GitHubError::RateLimited { reset_at }
+            }
+            StatusCode::FORBIDDEN => GitHubError::Unauthorized { message },
+            StatusCode::NOT_FOUND => GitHubError::NotFound { message },
+            StatusCode::UNPROCESSABLE_ENTITY => GitHubError::Validation { message },
+            _ => GitHubError::Api {
+                status: status.as_u16(),
+                message,
+            },
+        })
+    }
+}
+
+/// Extract the `rel="next"` URL from a Link header
+fn next_page(headers: &HeaderMap) -> Option<String> {
+    let link = headers.get(LINK)?.to_str().ok()?;
+    link.split(',').find_map(|part| {
+        let (url, rel) = part.split_once(';')?;
+        rel.contains("rel=\"next\"").then(|| {
+            url.trim()
+                .trim_start_matches('<')
+                .trim_end_matches('>')
+                .to_string()
+        })
+    })
+}
+
+/// Deserialize a field that distinguishes "absent" (None) from "null" (Some(None))
+pub(crate) fn double_option<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
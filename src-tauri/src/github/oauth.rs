@@ -0,0 +1,177 @@
+//! Device-flow OAuth login
+//!
+//! Runs GitHub's device authorization flow: the user code is emitted as a
+//! `github-device-code` event for the frontend to display, the token endpoint
+//! is polled until the user approves, and the token goes into the keychain.
+//! GitHub Enterprise Server instances use their own web base for the flow.
+
+use super::token::store_token;
+use super::{GitHubError, GitHubState};
+use reqwest::header::ACCEPT;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, State};
+
+/// Scopes requested when none are given
+const DEFAULT_SCOPES: &str = "repo read:org";
+
+/// OAuth app client ID baked in at build time, if any
+const BUILT_IN_CLIENT_ID: Option<&str> = option_env!("ANTLER_GITHUB_CLIENT_ID");
+
+/// Cancellation flag of the login in progress
+#[derive(Default)]
+pub struct LoginState {
+    cancelled: parking_lot::Mutex<Option<Arc<AtomicBool>>>,
+}
+
+/// Options for `github_login`
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoginOptions {
+    /// OAuth app client ID, defaults to the one built into the app
+    client_id: Option<String>,
+    /// Space-separated scopes
+    scopes: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct DeviceCodeResponse {
+    device_code: String,
+    user_code: String,
+    verification_uri: String,
+    expires_in: u64,
+    interval: u64,
+}
+
+/// Payload of `github-device-code`
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DeviceCodeEvent {
+    user_code: String,
+    verification_uri: String,
+    expires_in: u64,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: Option<String>,
+    error: Option<String>,
+    error_description: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct AuthenticatedUser {
+    login: String,
+}
+
+/// Run the device flow and store the resulting token.
+///
+/// Returns the login of the authenticated user.
+#[tauri::command]
+pub async fn github_login(
+    app: AppHandle,
+    github: State<'_, GitHubState>,
+    state: State<'_, LoginState>,
+    options: Option<LoginOptions>,
+) -> Result<String, GitHubError> {
+    let options = options.unwrap_or_default();
+    let client_id = options
+        .client_id
+        .or_else(|| BUILT_IN_CLIENT_ID.map(str::to_string))
+        .ok_or_else(|| GitHubError::Validation {
+            message: "No OAuth client ID configured".to_string(),
+        })?;
+
+    let cancelled = Arc::new(AtomicBool::new(false));
+    if let Some(previous) = state.cancelled.lock().replace(cancelled.clone()) {
+        previous.store(true, Ordering::SeqCst);
+    }
+
+    let host = github.host();
+    let device: DeviceCodeResponse = github
+        .http()
+        .post(format!("{}/login/device/code", host.web_base))
+        .header(ACCEPT, "application/json")
+        .json(&serde_json::json!({
+            "client_id": client_id,
+            "scope": options.scopes.as_deref().unwrap_or(DEFAULT_SCOPES),
+        }))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    let _ = app.emit(
+        "github-device-code",
+        DeviceCodeEvent {
+            user_code: device.user_code,
+            verification_uri: device.verification_uri,
+            expires_in: device.expires_in,
+        },
+    );
+
+    let mut interval = Duration::from_secs(device.interval.max(1));
+    let deadline = std::time::Instant::now() + Duration::from_secs(device.expires_in);
+
+    let token = loop {
+        tokio::time::sleep(interval).await;
+        if cancelled.load(Ordering::SeqCst) {
+            return Err(GitHubError::Cancelled);
+        }
+        if std::time::Instant::now() > deadline {
+            return Err(GitHubError::Unauthorized {
+                message: "Device code expired".to_string(),
+            });
+        }
+
+        let response: TokenResponse = github
+            .http()
+            .post(format!("{}/login/oauth/access_token", host.web_base))
+            .header(ACCEPT, "application/json")
+            .json(&serde_json::json!({
+                "client_id": client_id,
+                "device_code": device.device_code,
+                "grant_type": "urn:ietf:params:oauth:grant-type:device_code",
+            }))
+            .send()
+            .await?
+            .json()
+            .await?;
+
+        if let Some(token) = response.access_token {
+            break token;
+        }
+        match response.error.as_deref() {
+            Some("authorization_pending") => {}
+            // GitHub asks for an extra 5 seconds between polls
+            Some("slow_down") => interval += Duration::from_secs(5),
+            _ => {
+                return Err(GitHubError::Unauthorized {
+                    message: response
+                        .error_description
+                        .or(response.error)
+                        .unwrap_or_else(|| "Login failed".to_string()),
+                })
+            }
+        }
+    };
+
+    store_token(&host, &token)?;
+    github.set_cached_token(Some(token));
+    state.cancelled.lock().take();
+
+    let user: AuthenticatedUser = github.get("/user").await?;
+    Ok(user.login)
+}
+
+/// Abort a `github_login` that is waiting for approval
+#[tauri::command]
+pub async fn cancel_github_login(state: State<'_, LoginState>) -> Result<(), GitHubError> {
+    if let Some(cancelled) = state.cancelled.lock().take() {
+        cancelled.store(true, Ordering::SeqCst);
+    }
+    Ok(())
+}
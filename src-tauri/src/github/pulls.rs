@@ -0,0 +1,230 @@
+//! Pull request commands
+//!
+//! `create_pr` works from a worktree: it checks there is something to
+//! review, pushes the branch, and opens the pull request against the
+//! repository the remote points at.
+
+use super::{GitHubError, GitHubHost, GitHubState};
+use crate::git::drift::default_base;
+use crate::git::remote::{current_branch, off_runtime, push_branch, run_remote_git, RemoteError};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+/// Error returned by `create_pr`
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CreatePrError {
+    /// HEAD is detached and no head branch was given
+    NoBranch,
+    /// The head branch has no commits that are not on the base branch
+    NoCommits {
+        head: String,
+        base: String,
+    },
+    /// The remote is not a repository on the configured GitHub instance
+    NotGitHub {
+        remote: String,
+    },
+    /// Pushing the branch failed
+    Push {
+        error: RemoteError,
+    },
+    /// A pull request for this branch is already open
+    AlreadyExists {
+        pull_request: Box<PullRequest>,
+    },
+    GitHub {
+        error: GitHubError,
+    },
+}
+
+impl From<GitHubError> for CreatePrError {
+    fn from(error: GitHubError) -> Self {
+        CreatePrError::GitHub { error }
+    }
+}
+
+impl From<RemoteError> for CreatePrError {
+    fn from(error: RemoteError) -> Self {
+        CreatePrError::Push { error }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct RawPullRequest {
+    number: u64,
+    title: String,
+    state: String,
+    draft: Option<bool>,
+    html_url: String,
+    head: RawRef,
+    base: RawRef,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct RawRef {
+    #[serde(rename = "ref")]
+    name: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PullRequest {
+    number: u64,
+    title: String,
+    state: String,
+    draft: bool,
+    url: String,
+    head: String,
+    base: String,
+}
+
+impl From<RawPullRequest> for PullRequest {
+    fn from(raw: RawPullRequest) -> Self {
+        Self {
+            number: raw.number,
+            title: raw.title,
+            state: raw.state,
+            draft: raw.draft.unwrap_or(false),
+            url: raw.html_url,
+            head: raw.head.name,
+            base: raw.base.name,
+        }
+    }
+}
+
+/// Parameters for `create_pr`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatePrParams {
+    title: String,
+    #[serde(default)]
+    body: String,
+    /// Branch to open the PR from (defaults to the worktree's branch)
+    head: Option<String>,
+    /// Branch to merge into (defaults to the remote's default branch)
+    base: Option<String>,
+    #[serde(default)]
+    draft: bool,
+    /// Remote to push to (defaults to origin)
+    remote: Option<String>,
+}
+
+#[derive(Serialize)]
+struct CreatePrBody<'a> {
+    title: &'a str,
+    body: &'a str,
+    head: &'a str,
+    base: &'a str,
+    draft: bool,
+}
+
+/// `owner/name` from a remote URL on `hostname` (https, ssh or scp-style)
+pub(crate) fn github_slug(url: &str, hostname: &str) -> Option<String> {
+    let url = url.trim().trim_end_matches('/');
+    let scp_prefix = format!("git@{}:", hostname);
+    let path = url.strip_prefix(&scp_prefix).or_else(|| {
+        url.split_once(&format!("{}/", hostname))
+            .map(|(_, path)| path)
+    })?;
+    let path = path.strip_suffix(".git").unwrap_or(path);
+
+    let mut parts = path.split('/');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(owner), Some(name), None) if !owner.is_empty() && !name.is_empty() => {
+            Some(format!("{}/{}", owner, name))
+        }
+        _ => None,
+    }
+}
+
+/// `owner/name` of the repository a worktree's remote points at
+pub(crate) fn remote_slug(
+    worktree: &str,
+    remote: &str,
+    host: &GitHubHost,
+) -> Result<String, CreatePrError> {
+    let url = run_remote_git(worktree, &["remote", "get-url", remote])?;
+    github_slug(&url, host.hostname()).ok_or_else(|| CreatePrError::NotGitHub {
+        remote: url.trim().to_string(),
+    })
+}
+
+/// Push the worktree's branch and open a pull request for it
+#[tauri::command]
+pub async fn create_pr(
+    github: State<'_, GitHubState>,
+    worktree: String,
+    params: CreatePrParams,
+) -> Result<PullRequest, CreatePrError> {
+    let remote = params.remote.as_deref().unwrap_or("origin");
+    let head = match params.head {
+        Some(head) => head,
+        None => current_branch(&worktree)?.ok_or(CreatePrError::NoBranch)?,
+    };
+    let base = match params.base {
+        Some(base) => base,
+        None => default_base(&worktree)
+            .map_err(RemoteError::Git)?
+            .trim_start_matches(&format!("{}/", remote))
+            .to_string(),
+    };
+
+    let remote_base = format!("{}/{}", remote, base);
+    let compare_base =
+        if crate::git::ref_exists(&worktree, &format!("refs/remotes/{}", remote_base)) {
+            remote_base
+        } else {
+            base.clone()
+        };
+    let ahead = run_remote_git(
+        &worktree,
+        &[
+            "rev-list",
+            "--count",
+            &format!("{}..{}", compare_base, head),
+        ],
+    )?;
+    if ahead.trim() == "0" {
+        return Err(CreatePrError::NoCommits { head, base });
+    }
+
+    let slug = remote_slug(&worktree, remote, &github.host())?;
+    let (repo, to, branch) = (worktree.clone(), remote.to_string(), head.clone());
+    off_runtime(move || push_branch(&repo, &to, &branch, false)).await?;
+
+    let created: Result<RawPullRequest, GitHubError> = github
+        .send(
+            Method::POST,
+            &format!("/repos/{}/pulls", slug),
+            &CreatePrBody {
+                title: &params.title,
+                body: &params.body,
+                head: &head,
+                base: &base,
+                draft: params.draft,
+            },
+        )
+        .await;
+
+    match created {
+        Ok(raw) => Ok(raw.into()),
+        Err(GitHubError::Validation { message }) => {
+            let owner = slug.split('/').next().unwrap_or_default();
+            let existing: Vec<RawPullRequest> = github
+                .get(&format!(
+                    "/repos/{}/pulls?state=open&head={}:{}",
+                    slug, owner, head
+                ))
+                .await?;
+            match existing.into_iter().next() {
+                Some(raw) => Err(CreatePrError::AlreadyExists {
+                    pull_request: Box::new(raw.into()),
+                }),
+                None => Err(GitHubError::Validation { message }.into()),
+            }
+        }
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,345 @@
+//! Offline mutation queue
+//!
+//! Label edits, comments and column moves that fail because GitHub is
+//! unreachable are stored in the issue cache database and replayed later,
+//! either explicitly or after the next successful sync poll.
+//!
+//! Label edits and column moves are skipped as conflicts when someone else
+//! changed the issue after the mutation was queued; they stay in the queue
+//! until retried with `force` or discarded. Comments are append-only and are
+//! always replayed.
+
+use super::cache::IssueCache;
+use super::columns::move_to_column;
+use super::comments::post_comment;
+use super::issues::{edit_labels, RawIssue};
+use super::{GitHubError, GitHubState};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// A change that can be replayed against GitHub
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Mutation {
+    Labels {
+        add: Vec<String>,
+        remove: Vec<String>,
+    },
+    Comment {
+        body: String,
+    },
+    Column {
+        column: String,
+        columns: Vec<String>,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MutationStatus {
+    Pending,
+    /// The issue changed remotely after the mutation was queued
+    Conflict,
+    /// GitHub rejected the mutation
+    Failed,
+}
+
+impl MutationStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Conflict => "conflict",
+            Self::Failed => "failed",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value {
+            "conflict" => Self::Conflict,
+            "failed" => Self::Failed,
+            _ => Self::Pending,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueuedMutation {
+    id: i64,
+    repo: String,
+    number: u64,
+    mutation: Mutation,
+    status: MutationStatus,
+    error: Option<String>,
+    created_at: String,
+}
+
+/// Payload of `mutations-flushed`
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FlushReport {
+    applied: Vec<i64>,
+    conflicts: Vec<i64>,
+    failed: Vec<i64>,
+    /// Still queued because GitHub remained unreachable
+    pub(crate) remaining: usize,
+}
+
+impl FlushReport {
+    fn is_empty(&self) -> bool {
+        self.applied.is_empty() && self.conflicts.is_empty() && self.failed.is_empty()
+    }
+}
+
+/// Options for `flush_mutations`
+#[derive(Debug, Default, Deserialize)]
+pub struct FlushOptions {
+    /// Also replay mutations marked as conflicts, overwriting remote changes
+    #[serde(default)]
+    force: bool,
+}
+
+fn now() -> String {
+    chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+}
+
+fn queued(
+    conn: &Connection,
+    include: &[MutationStatus],
+) -> Result<Vec<QueuedMutation>, GitHubError> {
+    let mut stmt = conn.prepare(
+        "SELECT id, repo, issue, data, status, error, created_at FROM mutations ORDER BY id",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, i64>(2)?,
+            row.get::<_, String>(3)?,
+            row.get::<_, String>(4)?,
+            row.get::<_, Option<String>>(5)?,
+            row.get::<_, String>(6)?,
+        ))
+    })?;
+
+    let mut mutations = Vec::new();
+    for row in rows {
+        let (id, repo, number, data, status, error, created_at) = row?;
+        let status = MutationStatus::parse(&status);
+        if include.contains(&status) {
+            mutations.push(QueuedMutation {
+                id,
+                repo,
+                number: number as u64,
+                mutation: serde_json::from_str(&data)?,
+                status,
+                error,
+                created_at,
+            });
+        }
+    }
+    Ok(mutations)
+}
+
+fn set_status(
+    conn: &Connection,
+    id: i64,
+    status: MutationStatus,
+    error: Option<&str>,
+) -> Result<(), GitHubError> {
+    conn.execute(
+        "UPDATE mutations SET status = ?2, error = ?3 WHERE id = ?1",
+        params![id, status.as_str(), error],
+    )?;
+    Ok(())
+}
+
+fn insert(
+    app: &AppHandle,
+    repo: &str,
+    number: u64,
+    mutation: &Mutation,
+) -> Result<QueuedMutation, GitHubError> {
+    let created_at = now();
+    let id = app.state::<IssueCache>().with(app, |conn| {
+        conn.execute(
+            "INSERT INTO mutations (repo, issue, data, created_at) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                repo,
+                number as i64,
+                serde_json::to_string(mutation)?,
+                created_at
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    })?;
+
+    Ok(QueuedMutation {
+        id,
+        repo: repo.to_string(),
+        number,
+        mutation: mutation.clone(),
+        status: MutationStatus::Pending,
+        error: None,
+        created_at,
+    })
+}
+
+/// Queue a mutation and return the `queued` error to hand back to the caller
+pub(crate) fn enqueue(app: &AppHandle, repo: &str, number: u64, mutation: Mutation) -> GitHubError {
+    match insert(app, repo, number, &mutation) {
+        Ok(queued) => {
+            let mutation_id = queued.id;
+            let _ = app.emit("mutation-queued", queued);
+            GitHubError::Queued { mutation_id }
+        }
+        // Could not queue either; report the original problem
+        Err(e) => e,
+    }
+}
+
+async fn apply(
+    github: &GitHubState,
+    app: &AppHandle,
+    queued: &QueuedMutation,
+) -> Result<(), GitHubError> {
+    let repo = &queued.repo;
+    match &queued.mutation {
+        Mutation::Labels { add, remove } => {
+            edit_labels(github, repo, queued.number, add, remove).await
+        }
+        Mutation::Comment { body } => {
+            let cache = app.state::<IssueCache>();
+            post_comment(github, app, &cache, repo, queued.number, body)
+                .await
+                .map(|_| ())
+        }
+        Mutation::Column { column, columns } => {
+            move_to_column(github, repo, queued.number, column, columns)
+                .await
+                .map(|_| ())
+        }
+    }
+}
+
+/// Replay queued mutations in order, stopping at the first network failure
+pub(crate) async fn flush(app: &AppHandle, force: bool) -> Result<FlushReport, GitHubError> {
+    let github = app.state::<GitHubState>();
+    let cache = app.state::<IssueCache>();
+
+    let mut include = vec![MutationStatus::Pending];
+    if force {
+        include.push(MutationStatus::Conflict);
+    }
+    let pending = cache.with(app, |conn| queued(conn, &include))?;
+
+    let mut report = FlushReport::default();
+    // Issues changed by this flush; their newer updated_at is our own doing
+    let mut touched: HashSet<(String, u64)> = HashSet::new();
+
+    for (index, queued) in pending.iter().enumerate() {
+        let key = (queued.repo.clone(), queued.number);
+        let check_conflict = !force
+            && !matches!(queued.mutation, Mutation::Comment { .. })
+            && !touched.contains(&key);
+
+        let result = async {
+            if check_conflict {
+                let issue: RawIssue = github
+                    .get(&format!("/repos/{}/issues/{}", queued.repo, queued.number))
+                    .await?;
+                if issue.updated_at.as_str() > queued.created_at.as_str() {
+                    return Ok(false);
+                }
+            }
+            apply(&github, app, queued).await.map(|_| true)
+        }
+        .await;
+
+        match result {
+            Ok(true) => {
+                cache.with(app, |conn| {
+                    conn.execute("DELETE FROM mutations WHERE id = ?1", [queued.id])?;
+                    Ok(())
+                })?;
+                touched.insert(key);
+                report.applied.push(queued.id);
+            }
+            Ok(false) => {
+                cache.with(app, |conn| {
+                    set_status(conn, queued.id, MutationStatus::Conflict, None)
+                })?;
+                report.conflicts.push(queued.id);
+            }
+            Err(GitHubError::Network { .. }) => {
+                report.remaining = pending.len() - index;
+                break;
+            }
+            Err(e) => {
+                let message = e.to_string();
+                cache.with(app, |conn| {
+                    set_status(conn, queued.id, MutationStatus::Failed, Some(&message))
+                })?;
+                report.failed.push(queued.id);
+            }
+        }
+    }
+
+    if !report.is_empty() {
+        let _ = app.emit("mutations-flushed", report.clone());
+    }
+    Ok(report)
+}
+
+/// Whether anything is waiting to be replayed
+pub(crate) fn has_pending(app: &AppHandle) -> Result<bool, GitHubError> {
+    app.state::<IssueCache>().with(app, |conn| {
+        Ok(conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM mutations WHERE status = 'pending')",
+            [],
+            |row| row.get(0),
+        )?)
+    })
+}
+
+/// Every queued mutation, including conflicts and failures
+#[tauri::command]
+pub async fn list_queued_mutations(
+    app: AppHandle,
+    cache: State<'_, IssueCache>,
+) -> Result<Vec<QueuedMutation>, GitHubError> {
+    cache.with(&app, |conn| {
+        queued(
+            conn,
+            &[
+                MutationStatus::Pending,
+                MutationStatus::Conflict,
+                MutationStatus::Failed,
+            ],
+        )
+    })
+}
+
+/// Replay queued mutations now
+#[tauri::command]
+pub async fn flush_mutations(
+    app: AppHandle,
+    options: Option<FlushOptions>,
+) -> Result<FlushReport, GitHubError> {
+    flush(&app, options.unwrap_or_default().force).await
+}
+
+/// Drop a queued mutation without applying it
+#[tauri::command]
+pub async fn discard_mutation(
+    app: AppHandle,
+    cache: State<'_, IssueCache>,
+    id: i64,
+) -> Result<(), GitHubError> {
+    cache.with(&app, |conn| {
+        conn.execute("DELETE FROM mutations WHERE id = ?1", [id])?;
+        Ok(())
+    })
+}
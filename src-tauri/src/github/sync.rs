@@ -0,0 +1,219 @@
+//! Background issue sync
+//!
+//! Polls a repository's issues on an interval and emits `issues-changed`
+//! with only what was added, updated or removed since the last poll. Polls
+//! go through the issue cache, so unchanged boards cost a 304 and the
+//! frontend needs no polling timers of its own. A successful poll also
+//! replays mutations queued while offline. Polls are skipped while the
+//! connectivity watcher finds the network down.
+
+use super::cache::{cached_issues, refresh_board_cache, IssueCache};
+use super::issues::Issue;
+use super::queue::{flush, has_pending};
+use super::{GitHubError, GitHubState};
+use crate::jobs::{self, JobKind};
+use crate::network;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// Poll interval when sync is started without one
+const DEFAULT_INTERVAL_SECS: u64 = 60;
+
+/// Remaining requests below which polling waits for the rate-limit reset
+const RATE_LIMIT_RESERVE: u32 = 50;
+
+/// Running syncs, keyed by repository
+#[derive(Default)]
+pub struct IssueSyncState {
+    syncs: Mutex<HashMap<String, Arc<AtomicBool>>>,
+}
+
+/// Options for `start_issue_sync`
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IssueSyncOptions {
+    interval_secs: Option<u64>,
+}
+
+/// Payload of `issues-changed`
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IssuesChanged {
+    repo: String,
+    added: Vec<Issue>,
+    updated: Vec<Issue>,
+    /// Numbers of issues that were closed or deleted
+    removed: Vec<u64>,
+}
+
+impl IssuesChanged {
+    fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.updated.is_empty() && self.removed.is_empty()
+    }
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct IssueSyncError {
+    repo: String,
+    error: GitHubError,
+}
+
+/// Compare two snapshots of a repository's issues
+pub(crate) fn issue_delta(repo: &str, before: &[Issue], after: Vec<Issue>) -> IssuesChanged {
+    let previous: HashMap<u64, &Issue> = before.iter().map(|i| (i.number, i)).collect();
+    let mut delta = IssuesChanged {
+        repo: repo.to_string(),
+        added: Vec::new(),
+        updated: Vec::new(),
+        removed: Vec::new(),
+    };
+
+    for issue in &after {
+        if !previous.contains_key(&issue.number) {
+            delta.added.push(issue.clone());
+        }
+    }
+    let current: HashMap<u64, Issue> = after.into_iter().map(|i| (i.number, i)).collect();
+    for (number, old) in &previous {
+        match current.get(number) {
+            Some(new) if new.updated_at != old.updated_at => delta.updated.push(new.clone()),
+            Some(_) => {}
+            None => delta.removed.push(*number),
+        }
+    }
+
+    delta.removed.sort_unstable();
+    delta
+}
+
+/// Seconds to wait before the next poll, stretched while rate-limited
+fn next_delay(app: &AppHandle, interval: Duration, error: Option<&GitHubError>) -> Duration {
+    let now = chrono::Utc::now().timestamp();
+    let until_reset = |reset_at: i64| Duration::from_secs((reset_at - now).max(0) as u64 + 1);
+
+    if let Some(GitHubError::RateLimited {
+        reset_at: Some(reset_at),
+    }) = error
+    {
+        return until_reset(*reset_at).max(interval);
+    }
+    match app.state::<GitHubState>().rate_limit() {
+        Some(limit) if limit.remaining < RATE_LIMIT_RESERVE => {
+            until_reset(limit.reset_at).max(interval)
+        }
+        _ => interval,
+    }
+}
+
+async fn poll(app: &AppHandle, repo: &str) -> Result<Option<IssuesChanged>, GitHubError> {
+    let cache = app.state::<IssueCache>();
+    let before = cache.with(app, |conn| cached_issues(conn, repo))?;
+
+    let (board, changed) = refresh_board_cache(app, repo).await?;
+
+    // GitHub is reachable again; replay anything queued while offline
+    if has_pending(app)? {
+        if let Err(e) = flush(app, false).await {
+            eprintln!("Replaying queued mutations failed: {}", e);
+            // Keep retrying in the background rather than waiting for the next poll
+            if let Err(e) = jobs::enqueue(app, JobKind::FlushMutations, None, None) {
+                eprintln!("Failed to queue mutation replay: {}", e);
+            }
+        }
+    }
+    if !changed {
+        return Ok(None);
+    }
+
+    let delta = issue_delta(repo, &before, board.issues);
+    Ok((!delta.is_empty()).then_some(delta))
+}
+
+/// Start polling a repository's issues, emitting `issues-changed` deltas.
+///
+/// Starting a sync for a repository that already has one restarts it with
+/// the new interval.
+#[tauri::command]
+pub async fn start_issue_sync(
+    app: AppHandle,
+    state: State<'_, IssueSyncState>,
+    repo: String,
+    options: Option<IssueSyncOptions>,
+) -> Result<(), String> {
+    let options = options.unwrap_or_default();
+    let interval = Duration::from_secs(
+        options
+            .interval_secs
+            .unwrap_or(DEFAULT_INTERVAL_SECS)
+            .max(10),
+    );
+
+    let stopped = Arc::new(AtomicBool::new(false));
+    if let Some(previous) = state.syncs.lock().insert(repo.clone(), stopped.clone()) {
+        previous.store(true, Ordering::SeqCst);
+    }
+
+    tauri::async_runtime::spawn(async move {
+        while !stopped.load(Ordering::SeqCst) {
+            // A poll would only fail; pick up as soon as the network is back
+            if network::is_offline(&app) {
+                network::wait_for_online(&app, interval).await;
+                continue;
+            }
+
+            let result = poll(&app, &repo).await;
+            if stopped.load(Ordering::SeqCst) {
+                break;
+            }
+
+            let error = match result {
+                Ok(Some(delta)) => {
+                    let _ = app.emit("issues-changed", delta);
+                    None
+                }
+                Ok(None) => None,
+                Err(error) => {
+                    if let GitHubError::Network { .. } = error {
+                        network::recheck(&app);
+                    }
+                    let _ = app.emit(
+                        "issue-sync-error",
+                        IssueSyncError {
+                            repo: repo.clone(),
+                            error: error.clone(),
+                        },
+                    );
+                    Some(error)
+                }
+            };
+
+            let interval = crate::power::stretch(&app, interval);
+            tokio::time::sleep(next_delay(&app, interval, error.as_ref())).await;
+        }
+    });
+
+    Ok(())
+}
+
+/// Stop polling a repository
+#[tauri::command]
+pub async fn stop_issue_sync(state: State<'_, IssueSyncState>, repo: String) -> Result<(), String> {
+    if let Some(stopped) = state.syncs.lock().remove(&repo) {
+        stopped.store(true, Ordering::SeqCst);
+    }
+    Ok(())
+}
+
+/// Repositories with a running sync
+#[tauri::command]
+pub async fn list_issue_syncs(state: State<'_, IssueSyncState>) -> Result<Vec<String>, String> {
+    let mut repos: Vec<String> = state.syncs.lock().keys().cloned().collect();
+    repos.sort();
+    Ok(repos)
+}
@@ -0,0 +1,77 @@
+//! GitHub token storage in the OS keychain
+//!
+//! macOS Keychain, Windows Credential Manager or the Secret Service on Linux.
+//! Each GitHub instance has its own entry, so a GHE token and a github.com
+//! token can coexist.
+
+use super::{GitHubError, GitHubHost, GitHubState};
+use keyring::Entry;
+use tauri::State;
+
+const KEYCHAIN_SERVICE: &str = "com.antler.app";
+const KEYCHAIN_ACCOUNT: &str = "github-token";
+
+fn entry(host: &GitHubHost) -> Result<Entry, GitHubError> {
+    let account = if host.is_github_com() {
+        KEYCHAIN_ACCOUNT.to_string()
+    } else {
+        format!("{}@{}", KEYCHAIN_ACCOUNT, host.hostname())
+    };
+    Entry::new(KEYCHAIN_SERVICE, &account).map_err(|e| GitHubError::Keychain {
+        message: e.to_string(),
+    })
+}
+
+pub(crate) fn load_token(host: &GitHubHost) -> Result<Option<String>, GitHubError> {
+    match entry(host)?.get_password() {
+        Ok(token) => Ok(Some(token)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(GitHubError::Keychain {
+            message: e.to_string(),
+        }),
+    }
+}
+
+pub(crate) fn store_token(host: &GitHubHost, token: &str) -> Result<(), GitHubError> {
+    entry(host)?
+        .set_password(token)
+        .map_err(|e| GitHubError::Keychain {
+            message: e.to_string(),
+        })
+}
+
+/// Store a token for the current GitHub instance in the keychain
+#[tauri::command]
+pub async fn set_github_token(
+    state: State<'_, GitHubState>,
+    token: String,
+) -> Result<(), GitHubError> {
+    store_token(&state.host(), &token)?;
+    state.set_cached_token(Some(token));
+    Ok(())
+}
+
+/// Remove the stored token of the current GitHub instance
+#[tauri::command]
+pub async fn clear_github_token(state: State<'_, GitHubState>) -> Result<(), GitHubError> {
+    match entry(&state.host())?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => {}
+        Err(e) => {
+            return Err(GitHubError::Keychain {
+                message: e.to_string(),
+            })
+        }
+    }
+    state.set_cached_token(None);
+    Ok(())
+}
+
+/// Whether a GitHub token is available
+#[tauri::command]
+pub async fn has_github_token(state: State<'_, GitHubState>) -> Result<bool, GitHubError> {
+    match state.token() {
+        Ok(_) => Ok(true),
+        Err(GitHubError::NoToken) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
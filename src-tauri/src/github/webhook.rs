@@ -0,0 +1,198 @@
+//! Webhook receiver - local HTTP listener for GitHub deliveries
+//!
+//! For users who can point a repository webhook (or a smee.io proxy) at their
+//! machine. Deliveries are verified against the shared secret with
+//! `X-Hub-Signature-256` and re-emitted as `github-webhook` events, so the
+//! board updates without waiting for the next sync poll.
+
+use super::issues::{Issue, RawIssue};
+use axum::body::Bytes;
+use axum::extract::State as AxumState;
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::post;
+use axum::Router;
+use hmac::{Hmac, Mac};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use tauri::{AppHandle, Emitter, State};
+use tokio::sync::oneshot;
+
+/// Port used when the receiver is started without one
+const DEFAULT_PORT: u16 = 8787;
+
+/// Events that are translated; anything else is acknowledged and dropped
+const HANDLED_EVENTS: &[&str] = &["issues", "issue_comment", "pull_request"];
+
+struct Receiver {
+    port: u16,
+    shutdown: oneshot::Sender<()>,
+}
+
+/// The running receiver, if any
+#[derive(Default)]
+pub struct WebhookState {
+    receiver: Mutex<Option<Receiver>>,
+}
+
+/// Options for `start_webhook_receiver`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookOptions {
+    /// Secret configured on the GitHub webhook
+    secret: String,
+    port: Option<u16>,
+    /// Listen on all interfaces instead of loopback only
+    #[serde(default)]
+    public: bool,
+}
+
+/// Payload of `github-webhook`
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookEvent {
+    /// `issues`, `issue_comment` or `pull_request`
+    event: String,
+    action: Option<String>,
+    /// `owner/name` of the repository the delivery is about
+    repo: Option<String>,
+    /// Issue or pull request number
+    number: Option<u64>,
+    /// The issue, for `issues` and `issue_comment` deliveries
+    issue: Option<Issue>,
+    payload: serde_json::Value,
+}
+
+#[derive(Clone)]
+struct ReceiverContext {
+    app: AppHandle,
+    secret: String,
+}
+
+/// Check `X-Hub-Signature-256` against the body
+fn verify_signature(secret: &str, body: &[u8], signature: Option<&str>) -> bool {
+    let Some(hex_digest) = signature.and_then(|s| s.strip_prefix("sha256=")) else {
+        return false;
+    };
+    let Ok(expected) = hex::decode(hex_digest) else {
+        return false;
+    };
+    let Ok(mut mac) = Hmac::<Sha256>::new_from_slice(secret.as_bytes()) else {
+        return false;
+    };
+    mac.update(body);
+    mac.verify_slice(&expected).is_ok()
+}
+
+fn translate(event: &str, payload: serde_json::Value) -> WebhookEvent {
+    let action = payload["action"].as_str().map(str::to_string);
+    let repo = payload["repository"]["full_name"]
+        .as_str()
+        .map(str::to_string);
+    let number = payload["issue"]["number"]
+        .as_u64()
+        .or_else(|| payload["pull_request"]["number"].as_u64());
+    let issue = serde_json::from_value::<RawIssue>(payload["issue"].clone())
+        .ok()
+        .filter(|issue| !issue.is_pull_request())
+        .map(Issue::from);
+
+    WebhookEvent {
+        event: event.to_string(),
+        action,
+        repo,
+        number,
+        issue,
+        payload,
+    }
+}
+
+async fn handle_delivery(
+    AxumState(ctx): AxumState<ReceiverContext>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> StatusCode {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+
+    if !verify_signature(&ctx.secret, &body, header("x-hub-signature-256")) {
+        return StatusCode::UNAUTHORIZED;
+    }
+    let Some(event) = header("x-github-event") else {
+        return StatusCode::BAD_REQUEST;
+    };
+    if !HANDLED_EVENTS.contains(&event) {
+        // Includes `ping`, sent when the webhook is created
+        return StatusCode::NO_CONTENT;
+    }
+    let Ok(payload) = serde_json::from_slice::<serde_json::Value>(&body) else {
+        return StatusCode::BAD_REQUEST;
+    };
+
+    let _ = ctx.app.emit("github-webhook", translate(event, payload));
+    StatusCode::NO_CONTENT
+}
+
+/// Start the webhook receiver, returning the port it listens on.
+///
+/// Deliveries are accepted at `POST /` and `POST /webhook`. Starting while a
+/// receiver is already running replaces it.
+#[tauri::command]
+pub async fn start_webhook_receiver(
+    app: AppHandle,
+    state: State<'_, WebhookState>,
+    options: WebhookOptions,
+) -> Result<u16, String> {
+    if options.secret.is_empty() {
+        return Err("A webhook secret is required".to_string());
+    }
+    if let Some(previous) = state.receiver.lock().take() {
+        let _ = previous.shutdown.send(());
+    }
+
+    let host = if options.public {
+        "0.0.0.0"
+    } else {
+        "127.0.0.1"
+    };
+    let addr = format!("{}:{}", host, options.port.unwrap_or(DEFAULT_PORT));
+    let listener = tokio::net::TcpListener::bind(&addr)
+        .await
+        .map_err(|e| format!("Failed to listen on {}: {}", addr, e))?;
+    let port = listener.local_addr().map_err(|e| e.to_string())?.port();
+
+    let router = Router::new()
+        .route("/", post(handle_delivery))
+        .route("/webhook", post(handle_delivery))
+        .with_state(ReceiverContext {
+            app,
+            secret: options.secret,
+        });
+
+    let (shutdown, shutdown_rx) = oneshot::channel::<()>();
+    tauri::async_runtime::spawn(async move {
+        let server = axum::serve(listener, router).with_graceful_shutdown(async {
+            let _ = shutdown_rx.await;
+        });
+        if let Err(e) = server.await {
+            eprintln!("Webhook receiver stopped: {}", e);
+        }
+    });
+
+    *state.receiver.lock() = Some(Receiver { port, shutdown });
+    Ok(port)
+}
+
+/// Stop the webhook receiver
+#[tauri::command]
+pub async fn stop_webhook_receiver(state: State<'_, WebhookState>) -> Result<(), String> {
+    if let Some(receiver) = state.receiver.lock().take() {
+        let _ = receiver.shutdown.send(());
+    }
+    Ok(())
+}
+
+/// Port of the running receiver, if any
+#[tauri::command]
+pub async fn webhook_receiver_port(state: State<'_, WebhookState>) -> Result<Option<u16>, String> {
+    Ok(state.receiver.lock().as_ref().map(|r| r.port))
+}
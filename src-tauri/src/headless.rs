@@ -0,0 +1,94 @@
+//! Headless mode - `antler --headless` runs without opening a window
+//!
+//! The windows from `tauri.conf.json` are taken out of the context before the
+//! app starts, so the job worker, scheduler, issue sync and PTYs run as usual
+//! with nothing on screen, and closing a window later doesn't quit the app.
+//! The windows are kept so the UI can be opened on demand: from the Dock on
+//! macOS, or by running `antler --show`, which the single-instance plugin
+//! hands to the running instance instead of starting another.
+
+use parking_lot::Mutex;
+use tauri::utils::config::WindowConfig;
+use tauri::{AppHandle, Context, Manager, RunEvent, Runtime, WebviewWindowBuilder, Wry};
+
+/// Command-line flag that starts Antler without a window
+const FLAG: &str = "--headless";
+
+/// Command-line flag that opens the UI of a running headless instance
+const SHOW_FLAG: &str = "--show";
+
+/// Windows held back from a headless start
+#[derive(Default)]
+pub struct HeadlessState {
+    windows: Mutex<Vec<WindowConfig>>,
+}
+
+/// Whether the process was started with `--headless`
+pub(crate) fn requested() -> bool {
+    std::env::args().skip(1).any(|arg| arg == FLAG)
+}
+
+/// Take the configured windows out of `context` so none open at startup
+pub(crate) fn hold_windows<R: Runtime>(context: &mut Context<R>) -> HeadlessState {
+    let windows = std::mem::take(&mut context.config_mut().app.windows);
+    HeadlessState {
+        windows: Mutex::new(windows),
+    }
+}
+
+/// Open the windows a headless start held back, unless they already are
+pub(crate) fn show_ui(app: &AppHandle<Wry>) -> Result<(), String> {
+    let state = app.state::<HeadlessState>();
+    let windows = state.windows.lock().clone();
+    for config in windows {
+        if let Some(window) = app.get_webview_window(&config.label) {
+            let _ = window.show();
+            let _ = window.set_focus();
+            continue;
+        }
+        let window = WebviewWindowBuilder::from_config(app, &config)
+            .and_then(|builder| builder.build())
+            .map_err(|e| format!("Failed to open window '{}': {}", config.label, e))?;
+        crate::window_state::restore(&window.as_ref().window());
+        crate::badge::refresh(app);
+    }
+    Ok(())
+}
+
+/// Handle a second launch's arguments, returning whether they asked for the
+/// UI of a headless instance and got it
+pub(crate) fn on_second_instance(app: &AppHandle<Wry>, args: &[String]) -> bool {
+    let held = !app.state::<HeadlessState>().windows.lock().is_empty();
+    if !held || !args.iter().skip(1).any(|arg| arg == SHOW_FLAG) {
+        return false;
+    }
+    if let Err(e) = show_ui(app) {
+        eprintln!("{}", e);
+    }
+    true
+}
+
+/// Event loop handling for a headless instance
+#[cfg_attr(not(target_os = "macos"), allow(unused_variables))]
+pub(crate) fn on_run_event(app: &AppHandle<Wry>, event: RunEvent) {
+    match event {
+        // Closing the last window only hides the UI again
+        RunEvent::ExitRequested {
+            api, code: None, ..
+        } => api.prevent_exit(),
+        #[cfg(target_os = "macos")]
+        RunEvent::Reopen { .. } => {
+            if let Err(e) = show_ui(app) {
+                eprintln!("{}", e);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Open the windows a headless start held back (a no-op otherwise); from
+/// outside the app, `antler --show` does the same
+#[tauri::command]
+pub async fn show_main_window(app: AppHandle) -> Result<(), String> {
+    show_ui(&app)
+}
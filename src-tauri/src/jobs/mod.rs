@@ -0,0 +1,540 @@
+//! Durable job queue
+//!
+//! Background work that must not be lost to a flaky network - board
+//! refreshes, mutation replays, worktree cleanup, and rollback steps that
+//! failed - is stored in a SQLite file and run by a single worker. Failed
+//! jobs are retried with exponential backoff; jobs that keep failing, or fail
+//! in a way retrying can't fix, are moved to the dead-letter list where they
+//! stay until requeued or cancelled. Every change is emitted as `job-updated`.
+
+pub mod schedule;
+
+use crate::devcontainer::devcontainer_down;
+use crate::git::cleanup::{cleanup_worktrees, CleanupOptions};
+use crate::git::run_git;
+use crate::git::worktree::remove_worktree;
+use crate::github::cache::refresh_board_cache;
+use crate::github::columns::move_to_column;
+use crate::github::issues::edit_labels;
+use crate::github::queue::flush;
+use crate::github::{GitHubError, GitHubState};
+use crate::sessions::{reap_stale, SessionRegistry};
+use crate::store::Store;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::sync::Notify;
+
+const JOBS_FILE: &str = "jobs.sqlite";
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS schedules (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE,
+    cron TEXT NOT NULL,
+    data TEXT NOT NULL,
+    enabled INTEGER NOT NULL DEFAULT 1,
+    last_run_at INTEGER,
+    next_run_at INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS jobs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    data TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending',
+    attempts INTEGER NOT NULL DEFAULT 0,
+    max_attempts INTEGER NOT NULL,
+    next_run_at INTEGER NOT NULL,
+    last_error TEXT,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+";
+
+/// Attempts before a job is dead-lettered, unless the caller chooses
+const DEFAULT_MAX_ATTEMPTS: u32 = 5;
+
+/// Delay before the first retry; doubled for each further attempt
+const RETRY_BASE_SECS: i64 = 15;
+
+/// Longest delay between retries
+const RETRY_MAX_SECS: i64 = 30 * 60;
+
+/// How often the worker looks for due jobs when nothing wakes it
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Finished jobs are kept this long for inspection
+const KEEP_DONE_DAYS: i64 = 7;
+
+/// Work the queue knows how to run
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(
+    tag = "kind",
+    rename_all = "snake_case",
+    rename_all_fields = "camelCase"
+)]
+pub enum JobKind {
+    /// Refresh a repository's cached board
+    RefreshBoard { repo: String },
+    /// Replay mutations queued while offline
+    FlushMutations,
+    CleanupWorktrees {
+        repo: String,
+        #[serde(default)]
+        options: CleanupOptions,
+    },
+    /// Remove a worktree, and its branch when given
+    RemoveWorktree {
+        repo: String,
+        path: String,
+        branch: Option<String>,
+    },
+    /// Stop a workspace's devcontainer, and remove it unless `keep` is set
+    DevcontainerDown {
+        workspace: String,
+        #[serde(default)]
+        keep: bool,
+    },
+    /// Add and remove issue labels
+    EditLabels {
+        repo: String,
+        number: u64,
+        add: Vec<String>,
+        remove: Vec<String>,
+    },
+    /// Move an issue to a board column
+    SetColumn {
+        repo: String,
+        number: u64,
+        column: String,
+        columns: Vec<String>,
+    },
+    /// Forget registry entries whose worktree was deleted
+    ReapStaleSessions,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Pending,
+    Running,
+    Done,
+    /// Out of attempts or failed permanently
+    Dead,
+}
+
+impl JobStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Running => "running",
+            Self::Done => "done",
+            Self::Dead => "dead",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value {
+            "running" => Self::Running,
+            "done" => Self::Done,
+            "dead" => Self::Dead,
+            _ => Self::Pending,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Job {
+    pub(crate) id: i64,
+    job: JobKind,
+    status: JobStatus,
+    attempts: u32,
+    max_attempts: u32,
+    /// Unix time the job is due
+    next_run_at: i64,
+    last_error: Option<String>,
+    created_at: String,
+    updated_at: String,
+}
+
+/// Options for `enqueue_job`
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnqueueOptions {
+    max_attempts: Option<u32>,
+    /// Wait this long before the first attempt
+    delay_secs: Option<u64>,
+}
+
+/// Options for `list_jobs`
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListJobsOptions {
+    status: Option<JobStatus>,
+}
+
+/// Why a job failed and whether trying again could help
+struct JobFailure {
+    message: String,
+    retry: bool,
+}
+
+impl From<String> for JobFailure {
+    fn from(message: String) -> Self {
+        Self {
+            message,
+            retry: true,
+        }
+    }
+}
+
+impl From<GitHubError> for JobFailure {
+    fn from(e: GitHubError) -> Self {
+        let retry = match &e {
+            GitHubError::Network { .. } | GitHubError::RateLimited { .. } => true,
+            GitHubError::Api { status, .. } => *status == 409 || *status >= 500,
+            _ => false,
+        };
+        Self {
+            message: e.to_string(),
+            retry,
+        }
+    }
+}
+
+/// Make jobs interrupted by a quit due again and drop old finished ones
+fn recover(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE jobs SET status = 'pending' WHERE status = 'running'",
+        [],
+    )?;
+    let cutoff = chrono::Utc::now() - chrono::Duration::days(KEEP_DONE_DAYS);
+    conn.execute(
+        "DELETE FROM jobs WHERE status = 'done' AND updated_at < ?1",
+        [cutoff.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)],
+    )?;
+    Ok(())
+}
+
+/// Lazily opened job database and the worker's wake-up signal
+pub struct JobQueue {
+    store: Store,
+    wake: Notify,
+}
+
+impl Default for JobQueue {
+    fn default() -> Self {
+        Self {
+            store: Store::new(JOBS_FILE, SCHEMA, "job queue").with_setup(recover),
+            wake: Default::default(),
+        }
+    }
+}
+
+impl JobQueue {
+    fn with<T>(
+        &self,
+        app: &AppHandle,
+        f: impl FnOnce(&mut Connection) -> rusqlite::Result<T>,
+    ) -> Result<T, String> {
+        self.store.with(app, f)
+    }
+}
+
+fn now() -> String {
+    chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+}
+
+fn from_row(row: &Row) -> rusqlite::Result<Job> {
+    let data: String = row.get(1)?;
+    let job = serde_json::from_str(&data).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(1, rusqlite::types::Type::Text, Box::new(e))
+    })?;
+    Ok(Job {
+        id: row.get(0)?,
+        job,
+        status: JobStatus::parse(&row.get::<_, String>(2)?),
+        attempts: row.get(3)?,
+        max_attempts: row.get(4)?,
+        next_run_at: row.get(5)?,
+        last_error: row.get(6)?,
+        created_at: row.get(7)?,
+        updated_at: row.get(8)?,
+    })
+}
+
+const COLUMNS: &str =
+    "id, data, status, attempts, max_attempts, next_run_at, last_error, created_at, updated_at";
+
+fn load(conn: &Connection, id: i64) -> rusqlite::Result<Option<Job>> {
+    conn.query_row(
+        &format!("SELECT {} FROM jobs WHERE id = ?1", COLUMNS),
+        [id],
+        from_row,
+    )
+    .optional()
+}
+
+fn emit_job(app: &AppHandle, conn: &Connection, id: i64) -> rusqlite::Result<Option<Job>> {
+    let job = load(conn, id)?;
+    if let Some(job) = &job {
+        let _ = app.emit("job-updated", job.clone());
+    }
+    Ok(job)
+}
+
+/// Add a job to the queue and wake the worker
+pub(crate) fn enqueue(
+    app: &AppHandle,
+    job: JobKind,
+    max_attempts: Option<u32>,
+    delay: Option<Duration>,
+) -> Result<Job, String> {
+    let queue = app.state::<JobQueue>();
+    let data = serde_json::to_string(&job).map_err(|e| e.to_string())?;
+    let run_at = chrono::Utc::now().timestamp() + delay.map_or(0, |d| d.as_secs() as i64);
+
+    let job = queue.with(app, |conn| {
+        let now = now();
+        conn.execute(
+            "INSERT INTO jobs (data, max_attempts, next_run_at, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?4)",
+            params![
+                data,
+                max_attempts.unwrap_or(DEFAULT_MAX_ATTEMPTS).max(1),
+                run_at,
+                now
+            ],
+        )?;
+        emit_job(app, conn, conn.last_insert_rowid())
+    })?;
+    queue.wake.notify_one();
+    job.ok_or_else(|| "Job was not stored".to_string())
+}
+
+async fn run_job(app: &AppHandle, job: &JobKind) -> Result<(), JobFailure> {
+    match job {
+        JobKind::RefreshBoard { repo } => {
+            refresh_board_cache(app, repo).await?;
+        }
+        JobKind::FlushMutations => {
+            let report = flush(app, false).await?;
+            if report.remaining > 0 {
+                return Err(JobFailure {
+                    message: format!("{} mutations still waiting for GitHub", report.remaining),
+                    retry: true,
+                });
+            }
+        }
+        JobKind::CleanupWorktrees { repo, options } => {
+            cleanup_worktrees(repo.clone(), Some(options.clone())).await?;
+        }
+        JobKind::RemoveWorktree { repo, path, branch } => {
+            // Already gone counts as done
+            if std::path::Path::new(path).exists() {
+                remove_worktree(repo.clone(), path.clone(), Some(true), None).await?;
+            }
+            if let Some(branch) = branch {
+                if crate::git::ref_exists(repo, &format!("refs/heads/{}", branch)) {
+                    run_git(repo, &["branch", "-D", branch])?;
+                }
+            }
+        }
+        JobKind::DevcontainerDown { workspace, keep } => {
+            devcontainer_down(workspace.clone(), Some(!keep)).await?;
+        }
+        JobKind::EditLabels {
+            repo,
+            number,
+            add,
+            remove,
+        } => {
+            let github = app.state::<GitHubState>();
+            edit_labels(&github, repo, *number, add, remove).await?;
+        }
+        JobKind::ReapStaleSessions => {
+            let registry = app.state::<SessionRegistry>();
+            reap_stale(app, &registry)?;
+        }
+        JobKind::SetColumn {
+            repo,
+            number,
+            column,
+            columns,
+        } => {
+            let github = app.state::<GitHubState>();
+            move_to_column(&github, repo, *number, column, columns).await?;
+        }
+    }
+    Ok(())
+}
+
+fn retry_delay(attempts: u32) -> i64 {
+    let exponent = attempts.saturating_sub(1).min(16);
+    (RETRY_BASE_SECS << exponent).min(RETRY_MAX_SECS)
+}
+
+/// Claim the next due job, marking it running
+fn claim(app: &AppHandle, queue: &JobQueue) -> Result<Option<Job>, String> {
+    queue.with(app, |conn| {
+        let due = conn
+            .query_row(
+                "SELECT id FROM jobs WHERE status = 'pending' AND next_run_at <= ?1
+                 ORDER BY next_run_at, id LIMIT 1",
+                [chrono::Utc::now().timestamp()],
+                |row| row.get::<_, i64>(0),
+            )
+            .optional()?;
+        let Some(id) = due else {
+            return Ok(None);
+        };
+        conn.execute(
+            "UPDATE jobs SET status = 'running', attempts = attempts + 1, updated_at = ?2
+             WHERE id = ?1",
+            params![id, now()],
+        )?;
+        emit_job(app, conn, id)
+    })
+}
+
+fn finish(
+    app: &AppHandle,
+    queue: &JobQueue,
+    job: &Job,
+    result: Result<(), JobFailure>,
+) -> Result<(), String> {
+    queue.with(app, |conn| {
+        match result {
+            Ok(()) => {
+                conn.execute(
+                    "UPDATE jobs SET status = 'done', last_error = NULL, updated_at = ?2
+                     WHERE id = ?1",
+                    params![job.id, now()],
+                )?;
+            }
+            Err(failure) => {
+                let status = if failure.retry && job.attempts < job.max_attempts {
+                    JobStatus::Pending
+                } else {
+                    JobStatus::Dead
+                };
+                let next_run_at = chrono::Utc::now().timestamp() + retry_delay(job.attempts);
+                conn.execute(
+                    "UPDATE jobs SET status = ?2, last_error = ?3, next_run_at = ?4,
+                     updated_at = ?5 WHERE id = ?1",
+                    params![job.id, status.as_str(), failure.message, next_run_at, now()],
+                )?;
+            }
+        }
+        emit_job(app, conn, job.id).map(|_| ())
+    })
+}
+
+/// Run due jobs in the background for the lifetime of the app
+pub(crate) fn start_worker(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let queue = app.state::<JobQueue>();
+            match claim(&app, &queue) {
+                Ok(Some(job)) => {
+                    let result = run_job(&app, &job.job).await;
+                    if let Err(e) = finish(&app, &queue, &job, result) {
+                        eprintln!("Failed to record job {}: {}", job.id, e);
+                    }
+                    continue;
+                }
+                Ok(None) => {}
+                Err(e) => eprintln!("Job queue unavailable: {}", e),
+            }
+            let _ = tokio::time::timeout(POLL_INTERVAL, queue.wake.notified()).await;
+        }
+    });
+}
+
+/// Queue a job
+#[tauri::command]
+pub async fn enqueue_job(
+    app: AppHandle,
+    job: JobKind,
+    options: Option<EnqueueOptions>,
+) -> Result<Job, String> {
+    let options = options.unwrap_or_default();
+    enqueue(
+        &app,
+        job,
+        options.max_attempts,
+        options.delay_secs.map(Duration::from_secs),
+    )
+}
+
+/// Jobs in the queue, newest first, optionally with one status
+#[tauri::command]
+pub async fn list_jobs(
+    app: AppHandle,
+    queue: State<'_, JobQueue>,
+    options: Option<ListJobsOptions>,
+) -> Result<Vec<Job>, String> {
+    let status = options
+        .unwrap_or_default()
+        .status
+        .map(|status| status.as_str());
+    queue.with(&app, |conn| {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM jobs WHERE ?1 IS NULL OR status = ?1 ORDER BY id DESC",
+            COLUMNS
+        ))?;
+        let rows = stmt.query_map([status], from_row)?;
+        rows.collect()
+    })
+}
+
+/// Jobs that ran out of attempts or failed permanently
+#[tauri::command]
+pub async fn list_dead_jobs(
+    app: AppHandle,
+    queue: State<'_, JobQueue>,
+) -> Result<Vec<Job>, String> {
+    list_jobs(
+        app,
+        queue,
+        Some(ListJobsOptions {
+            status: Some(JobStatus::Dead),
+        }),
+    )
+    .await
+}
+
+/// Put a dead or pending job back at the front of the queue with fresh attempts
+#[tauri::command]
+pub async fn requeue_job(
+    app: AppHandle,
+    queue: State<'_, JobQueue>,
+    id: i64,
+) -> Result<Job, String> {
+    let job = queue.with(&app, |conn| {
+        conn.execute(
+            "UPDATE jobs SET status = 'pending', attempts = 0, next_run_at = ?2, updated_at = ?3
+             WHERE id = ?1 AND status != 'running'",
+            params![id, chrono::Utc::now().timestamp(), now()],
+        )?;
+        emit_job(&app, conn, id)
+    })?;
+    queue.wake.notify_one();
+    job.ok_or_else(|| format!("Job {} not found", id))
+}
+
+/// Remove a job that is not running
+#[tauri::command]
+pub async fn cancel_job(app: AppHandle, queue: State<'_, JobQueue>, id: i64) -> Result<(), String> {
+    let removed = queue.with(&app, |conn| {
+        conn.execute(
+            "DELETE FROM jobs WHERE id = ?1 AND status != 'running'",
+            [id],
+        )
+    })?;
+    if removed == 0 {
+        return Err(format!("Job {} not found or running", id));
+    }
+    Ok(())
+}
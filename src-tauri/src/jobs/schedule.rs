@@ -0,0 +1,271 @@
+//! Recurring jobs
+//!
+//! Scheduled tasks pair a job with a cron expression (five fields, local
+//! time) and live in the job database next to the queue. The scheduler checks
+//! them periodically and enqueues each one that is due, so a task missed while
+//! the app was closed runs once on the next start rather than once per missed
+//! slot. The frontend registers per-repository tasks such as board syncs
+//! (`*/2 * * * *`) and nightly worktree cleanup; the stale-session reaper is
+//! built in.
+
+use super::{enqueue, JobKind, JobQueue};
+use croner::Cron;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// How often due tasks are looked for
+const TICK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Tasks every install has; users may disable them but they come back if removed
+const BUILT_IN: &[(&str, &str, JobKind)] = &[(
+    "reap-stale-sessions",
+    "0 * * * *",
+    JobKind::ReapStaleSessions,
+)];
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledTask {
+    id: i64,
+    name: String,
+    cron: String,
+    job: JobKind,
+    enabled: bool,
+    /// Unix time the task was last enqueued
+    last_run_at: Option<i64>,
+    /// Unix time the task is next due
+    next_run_at: i64,
+}
+
+/// Options for `add_scheduled_task`
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleOptions {
+    /// Defaults to true
+    enabled: Option<bool>,
+}
+
+const COLUMNS: &str = "id, name, cron, data, enabled, last_run_at, next_run_at";
+
+fn from_row(row: &Row) -> rusqlite::Result<ScheduledTask> {
+    let data: String = row.get(3)?;
+    let job = serde_json::from_str(&data).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(3, rusqlite::types::Type::Text, Box::new(e))
+    })?;
+    Ok(ScheduledTask {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        cron: row.get(2)?,
+        job,
+        enabled: row.get(4)?,
+        last_run_at: row.get(5)?,
+        next_run_at: row.get(6)?,
+    })
+}
+
+fn load(conn: &Connection, name: &str) -> rusqlite::Result<Option<ScheduledTask>> {
+    conn.query_row(
+        &format!("SELECT {} FROM schedules WHERE name = ?1", COLUMNS),
+        [name],
+        from_row,
+    )
+    .optional()
+}
+
+fn parse_cron(expr: &str) -> Result<Cron, String> {
+    Cron::new(expr)
+        .parse()
+        .map_err(|e| format!("Invalid cron expression '{}': {}", expr, e))
+}
+
+/// Unix time of the first occurrence after now
+fn next_run(expr: &str) -> Result<i64, String> {
+    parse_cron(expr)?
+        .find_next_occurrence(&chrono::Local::now(), false)
+        .map(|next| next.timestamp())
+        .map_err(|e| format!("No upcoming run for '{}': {}", expr, e))
+}
+
+fn emit_task(
+    app: &AppHandle,
+    conn: &Connection,
+    name: &str,
+) -> rusqlite::Result<Option<ScheduledTask>> {
+    let task = load(conn, name)?;
+    if let Some(task) = &task {
+        let _ = app.emit("scheduled-task-updated", task.clone());
+    }
+    Ok(task)
+}
+
+fn seed(app: &AppHandle, queue: &JobQueue) -> Result<(), String> {
+    for (name, cron, job) in BUILT_IN {
+        let data = serde_json::to_string(job).map_err(|e| e.to_string())?;
+        let next_run_at = next_run(cron)?;
+        queue.with(app, |conn| {
+            conn.execute(
+                "INSERT OR IGNORE INTO schedules (name, cron, data, next_run_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![name, cron, data, next_run_at],
+            )
+        })?;
+    }
+    Ok(())
+}
+
+/// Enqueue a task's job and move it to its next slot
+fn run_task(
+    app: &AppHandle,
+    queue: &JobQueue,
+    task: &ScheduledTask,
+) -> Result<ScheduledTask, String> {
+    enqueue(app, task.job.clone(), None, None)?;
+    let next_run_at = next_run(&task.cron)?;
+    queue
+        .with(app, |conn| {
+            conn.execute(
+                "UPDATE schedules SET last_run_at = ?2, next_run_at = ?3 WHERE id = ?1",
+                params![task.id, chrono::Utc::now().timestamp(), next_run_at],
+            )?;
+            emit_task(app, conn, &task.name)
+        })?
+        .ok_or_else(|| format!("Scheduled task '{}' not found", task.name))
+}
+
+fn tick(app: &AppHandle, queue: &JobQueue) -> Result<(), String> {
+    let due: Vec<ScheduledTask> = queue.with(app, |conn| {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM schedules WHERE enabled = 1 AND next_run_at <= ?1",
+            COLUMNS
+        ))?;
+        let rows = stmt.query_map([chrono::Utc::now().timestamp()], from_row)?;
+        rows.collect()
+    })?;
+    for task in due {
+        if let Err(e) = run_task(app, queue, &task) {
+            eprintln!("Failed to run scheduled task '{}': {}", task.name, e);
+        }
+    }
+    Ok(())
+}
+
+/// Enqueue scheduled tasks as they come due for the lifetime of the app
+pub(crate) fn start_scheduler(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let queue = app.state::<JobQueue>();
+        if let Err(e) = seed(&app, &queue) {
+            eprintln!("Failed to add built-in scheduled tasks: {}", e);
+        }
+        loop {
+            if let Err(e) = tick(&app, &queue) {
+                eprintln!("Scheduler unavailable: {}", e);
+            }
+            tokio::time::sleep(crate::power::stretch(&app, TICK_INTERVAL)).await;
+        }
+    });
+}
+
+/// Register a recurring job, replacing any task with the same name
+#[tauri::command]
+pub async fn add_scheduled_task(
+    app: AppHandle,
+    queue: State<'_, JobQueue>,
+    name: String,
+    cron: String,
+    job: JobKind,
+    options: Option<ScheduleOptions>,
+) -> Result<ScheduledTask, String> {
+    let options = options.unwrap_or_default();
+    let next_run_at = next_run(&cron)?;
+    let data = serde_json::to_string(&job).map_err(|e| e.to_string())?;
+    queue
+        .with(&app, |conn| {
+            conn.execute(
+                "INSERT INTO schedules (name, cron, data, enabled, next_run_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)
+                 ON CONFLICT(name) DO UPDATE SET cron = ?2, data = ?3, enabled = ?4,
+                 next_run_at = ?5",
+                params![
+                    name,
+                    cron,
+                    data,
+                    options.enabled.unwrap_or(true),
+                    next_run_at
+                ],
+            )?;
+            emit_task(&app, conn, &name)
+        })?
+        .ok_or_else(|| format!("Scheduled task '{}' was not stored", name))
+}
+
+/// Delete a scheduled task; built-in tasks are added again on the next start
+#[tauri::command]
+pub async fn remove_scheduled_task(
+    app: AppHandle,
+    queue: State<'_, JobQueue>,
+    name: String,
+) -> Result<(), String> {
+    let removed = queue.with(&app, |conn| {
+        conn.execute("DELETE FROM schedules WHERE name = ?1", [&name])
+    })?;
+    if removed == 0 {
+        return Err(format!("Scheduled task '{}' not found", name));
+    }
+    Ok(())
+}
+
+/// Pause or resume a scheduled task
+#[tauri::command]
+pub async fn set_scheduled_task_enabled(
+    app: AppHandle,
+    queue: State<'_, JobQueue>,
+    name: String,
+    enabled: bool,
+) -> Result<ScheduledTask, String> {
+    queue
+        .with(&app, |conn| {
+            // Resuming shouldn't fire every slot missed while paused
+            let task = load(conn, &name)?;
+            let next_run_at = match &task {
+                Some(task) => next_run(&task.cron).unwrap_or(task.next_run_at),
+                None => return Ok(None),
+            };
+            conn.execute(
+                "UPDATE schedules SET enabled = ?2, next_run_at = ?3 WHERE name = ?1",
+                params![name, enabled, next_run_at],
+            )?;
+            emit_task(&app, conn, &name)
+        })?
+        .ok_or_else(|| format!("Scheduled task '{}' not found", name))
+}
+
+/// All scheduled tasks, soonest first
+#[tauri::command]
+pub async fn list_scheduled_tasks(
+    app: AppHandle,
+    queue: State<'_, JobQueue>,
+) -> Result<Vec<ScheduledTask>, String> {
+    queue.with(&app, |conn| {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM schedules ORDER BY next_run_at, name",
+            COLUMNS
+        ))?;
+        let rows = stmt.query_map([], from_row)?;
+        rows.collect()
+    })
+}
+
+/// Enqueue a scheduled task's job now, whether or not it is enabled
+#[tauri::command]
+pub async fn run_scheduled_task_now(
+    app: AppHandle,
+    queue: State<'_, JobQueue>,
+    name: String,
+) -> Result<ScheduledTask, String> {
+    let task = queue
+        .with(&app, |conn| load(conn, &name))?
+        .ok_or_else(|| format!("Scheduled task '{}' not found", name))?;
+    run_task(&app, &queue, &task)
+}
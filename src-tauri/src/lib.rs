@@ -2,27 +2,483 @@
 //!
 //! All business logic lives in TypeScript.
 //! Rust only hosts plugins for shell commands and filesystem access.
-//! PTY commands are the one exception - they provide native terminal capabilities.
+//! PTY commands are the one exception - they provide native terminal capabilities,
+//! including attaching to existing tmux sessions.
+//! Git commands are thin bridges that return structured data instead of raw CLI output.
+//! GitHub commands call the REST API directly with a token kept in the OS keychain.
+//! Agent commands supervise Claude Code sessions running in PTYs.
+//! File commands cover what the fs plugin can't, such as watching paths.
+//! With `--headless` no window opens and the background subsystems run alone.
 
+mod agents;
+mod app_menu;
+mod attention;
+mod badge;
+mod bookmark;
+mod clipboard;
+mod deeplink;
+mod dev_session;
+mod devcontainer;
+mod docker;
+mod editor;
+mod file_drop;
+mod files;
+mod git;
+mod github;
+mod headless;
+mod jobs;
+mod lifecycle;
+mod mcp;
+mod network;
+mod notifications;
+mod popout;
+mod ports;
+mod power;
+mod proxy;
 mod pty;
+mod quake;
+mod scripts;
+mod search;
+mod server;
+mod sessions;
+mod sleep;
+mod ssh;
+mod store;
+mod taskbar;
+mod tmux;
+mod tray;
+mod updater;
+mod usage;
+mod window_state;
+mod workspace;
 
+use agents::AgentState;
+use app_menu::AppMenuState;
+use attention::AttentionState;
+use badge::BadgeState;
+use deeplink::DeepLinkState;
+use docker::DockerState;
+use file_drop::FileDropState;
+use files::archive::ArchiveState;
+use files::copy::CopyState;
+use files::download::DownloadState;
+use files::size::DirSizeState;
+use files::stream::FileStreamState;
+use files::tail::TailState;
+use files::temp::TempWorkspaceState;
+use files::watch::FileWatchState;
+use git::backend::GitBackendState;
+use git::clone::CloneState;
+use git::drift::DriftState;
+use git::watch::GitWatchState;
+use github::cache::IssueCache;
+use github::checks::ChecksState;
+use github::oauth::LoginState;
+use github::sync::IssueSyncState;
+use github::webhook::WebhookState;
+use github::GitHubState;
+use headless::HeadlessState;
+use jobs::JobQueue;
+use lifecycle::LifecycleState;
+use mcp::McpClientState;
+use network::ConnectivityState;
+use notifications::NotificationState;
+use ports::forward::ForwardState;
+use ports::PortState;
+use power::PowerState;
+use proxy::ProxyState;
 use pty::PtyState;
+use quake::QuakeState;
+use search::fuzzy::FuzzyState;
+use search::index::ContentIndexState;
+use search::SearchState;
+use server::mcp::McpState;
+use server::share::ShareState;
+use server::terminal::MirrorState;
+use server::LocalServerState;
+use sessions::SessionRegistry;
+use sleep::SleepState;
+use ssh::sftp::SftpState;
+use ssh::SshState;
+use taskbar::TaskbarState;
+use tmux::control::TmuxControlState;
+use tray::TrayState;
+use updater::UpdaterState;
+use usage::UsageStore;
+use window_state::WindowStateStore;
+use workspace::Workspace;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    let headless = headless::requested();
+    let mut context = tauri::generate_context!();
+    let headless_state = if headless {
+        headless::hold_windows(&mut context)
+    } else {
+        HeadlessState::default()
+    };
+
     tauri::Builder::default()
+        // Must come first so a second launch exits before doing anything else
+        .plugin(tauri_plugin_single_instance::init(|app, args, _| {
+            if !headless::on_second_instance(app, &args) {
+                deeplink::on_second_instance(app, args)
+            }
+        }))
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_os::init())
         .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_global_shortcut::Builder::new().build())
+        .plugin(tauri_plugin_deep_link::init())
+        .plugin(tauri_plugin_updater::Builder::new().build())
+        .manage(headless_state)
         .manage(PtyState::default())
+        .manage(PortState::default())
+        .manage(ForwardState::default())
+        .manage(SearchState::default())
+        .manage(FuzzyState::default())
+        .manage(ContentIndexState::default())
+        .manage(FileWatchState::default())
+        .manage(TailState::default())
+        .manage(FileStreamState::default())
+        .manage(DirSizeState::default())
+        .manage(ArchiveState::default())
+        .manage(CopyState::default())
+        .manage(DownloadState::default())
+        .manage(TempWorkspaceState::default())
+        .manage(TmuxControlState::default())
+        .manage(TrayState::default())
+        .manage(NotificationState::default())
+        .manage(QuakeState::default())
+        .manage(DeepLinkState::default())
+        .manage(WindowStateStore::default())
+        .manage(UpdaterState::default())
+        .manage(BadgeState::default())
+        .manage(SleepState::default())
+        .manage(PowerState::default())
+        .manage(ConnectivityState::default())
+        .manage(ProxyState::default())
+        .manage(FileDropState::default())
+        .manage(AppMenuState::default())
+        .manage(TaskbarState::default())
+        .manage(AttentionState::default())
+        .manage(SshState::default())
+        .manage(SftpState::default())
+        .manage(LocalServerState::default())
+        .manage(MirrorState::default())
+        .manage(ShareState::default())
+        .manage(McpState::default())
+        .manage(McpClientState::default())
+        .manage(DockerState::default())
+        .manage(AgentState::default())
+        .manage(SessionRegistry::default())
+        .manage(Workspace::default())
+        .manage(UsageStore::default())
+        .manage(JobQueue::default())
+        .manage(LifecycleState::default())
+        .manage(CloneState::default())
+        .manage(GitBackendState::default())
+        .manage(GitWatchState::default())
+        .manage(DriftState::default())
+        .manage(GitHubState::default())
+        .manage(IssueCache::default())
+        .manage(IssueSyncState::default())
+        .manage(WebhookState::default())
+        .manage(LoginState::default())
+        .manage(ChecksState::default())
+        .setup(|app| {
+            power::start_watcher(app.handle().clone());
+            network::start_watcher(app.handle().clone());
+            jobs::start_worker(app.handle().clone());
+            jobs::schedule::start_scheduler(app.handle().clone());
+            ports::start_watcher(app.handle().clone());
+            files::temp::clear_stale(app.handle());
+            workspace::restore_access(app.handle());
+            notifications::init(app.handle());
+            deeplink::init(app.handle());
+            window_state::restore_all(app.handle());
+            if let Err(e) = tray::create(app.handle()) {
+                eprintln!("Failed to create the tray icon: {}", e);
+            }
+            if let Err(e) = app_menu::create(app.handle()) {
+                eprintln!("Failed to create the menu: {}", e);
+            }
+            badge::init(app.handle());
+            sleep::init(app.handle());
+            updater::start(app.handle());
+            Ok(())
+        })
+        .on_window_event(|window, event| {
+            window_state::on_window_event(window, event);
+            tray::on_window_event(window, event);
+            file_drop::on_window_event(window, event);
+        })
         .invoke_handler(tauri::generate_handler![
             pty::spawn_pty,
             pty::write_pty,
             pty::resize_pty,
             pty::kill_pty,
             pty::list_pty_sessions,
+            pty::list_pty_session_info,
+            popout::pop_out_session,
+            popout::dock_session,
+            file_drop::set_drop_target,
+            window_state::reset_window_state,
+            updater::set_update_channel,
+            updater::get_update_channel,
+            updater::check_for_update,
+            updater::download_update,
+            updater::install_update_and_restart,
+            badge::set_badge_count,
+            sleep::set_sleep_settings,
+            sleep::get_sleep_status,
+            power::get_power_status,
+            network::check_connectivity,
+            proxy::set_proxy_settings,
+            proxy::get_proxy_settings,
+            proxy::get_system_proxy,
+            clipboard::copy_styled,
+            taskbar::set_taskbar_progress,
+            attention::set_session_attention,
+            ssh::list_ssh_hosts,
+            ssh::save_ssh_host,
+            ssh::remove_ssh_host,
+            ssh::set_ssh_passphrase,
+            ssh::scan_ssh_host_key,
+            ssh::trust_ssh_host,
+            ssh::session::spawn_ssh_pty,
+            ssh::sftp::sftp_list,
+            ssh::sftp::sftp_read_file,
+            ssh::sftp::sftp_write_file,
+            ssh::sftp::sftp_upload,
+            ssh::sftp::sftp_download,
+            ssh::sftp::cancel_sftp_transfer,
+            server::start_local_server,
+            server::stop_local_server,
+            server::local_server_info,
+            server::share::share_session,
+            server::share::stop_share,
+            server::share::list_shares,
+            mcp::list_mcp_servers,
+            mcp::save_mcp_server,
+            mcp::remove_mcp_server,
+            mcp::set_mcp_server_token,
+            mcp::start_mcp_server,
+            mcp::stop_mcp_server,
+            mcp::list_mcp_tools,
+            mcp::call_mcp_tool,
+            mcp::list_mcp_resources,
+            mcp::read_mcp_resource,
+            ports::list_pty_ports,
+            ports::forward::forward_port,
+            ports::forward::stop_port_forward,
+            ports::forward::list_port_forwards,
+            ports::wait::is_port_open,
+            ports::wait::wait_for_port,
+            scripts::list_project_scripts,
+            scripts::run_project_script,
+            search::grep_project,
+            search::cancel_search,
+            search::fuzzy::index_project,
+            search::fuzzy::fuzzy_find,
+            search::index::index_project_content,
+            search::index::query_index,
+            files::watch::watch_path,
+            files::watch::unwatch,
+            files::tail::tail_file,
+            files::tail::stop_tail,
+            files::stream::read_file_stream,
+            files::stream::ack_file_stream,
+            files::stream::cancel_file_stream,
+            files::atomic::write_file_atomic,
+            files::trash::trash_path,
+            files::tree::list_tree,
+            files::size::dir_size,
+            files::size::cancel_dir_size,
+            files::diff::diff_text,
+            files::diff::diff_files,
+            files::inspect::inspect_file,
+            files::archive::create_archive,
+            files::archive::extract_archive,
+            files::archive::cancel_archive,
+            files::hash::hash_file,
+            files::stat::stat_extended,
+            files::stat::set_permissions,
+            files::link::create_symlink,
+            files::link::resolve_link,
+            files::temp::create_temp_workspace,
+            files::temp::list_temp_workspaces,
+            files::temp::remove_temp_workspace,
+            files::copy::copy_tree,
+            files::copy::move_tree,
+            files::copy::cancel_copy,
+            files::download::download_file,
+            files::download::cancel_download,
+            files::symbols::extract_symbols,
+            files::text::detect_text_format,
+            files::text::convert_text_format,
+            files::open::reveal_path,
+            files::open::open_path,
+            editor::open_in_editor,
+            editor::list_editors,
+            tmux::list_tmux_sessions,
+            tmux::attach_tmux_session,
+            tmux::detach_tmux_pty,
+            tmux::control::tmux_control_attach,
+            tmux::control::tmux_control_command,
+            tmux::control::tmux_control_list_panes,
+            tmux::control::tmux_control_capture_pane,
+            tmux::control::tmux_control_send_keys,
+            tmux::control::tmux_control_resize,
+            tmux::control::tmux_control_detach,
+            tmux::control::list_tmux_control_clients,
+            devcontainer::devcontainer_up,
+            devcontainer::devcontainer_exec,
+            devcontainer::devcontainer_down,
+            devcontainer::devcontainer_status,
+            docker::list_containers,
+            docker::list_images,
+            docker::stream_container_logs,
+            docker::stop_log_stream,
+            docker::docker_exec,
+            docker::stop_container,
+            docker::remove_container,
+            agents::spawn_agent,
+            agents::stop_agent,
+            agents::restart_agent,
+            agents::respond_to_agent,
+            agents::remove_agent,
+            agents::list_agents,
+            agents::get_agent_activity,
+            usage::list_usage,
+            usage::get_issue_usage,
+            agents::queue::set_agent_limit,
+            agents::queue::get_agent_limit,
+            sessions::update_issue_session,
+            sessions::get_issue_session,
+            sessions::list_issue_sessions,
+            sessions::find_issue_session,
+            sessions::remove_issue_session,
+            dev_session::start_dev_session,
+            workspace::add_project,
+            workspace::remove_project,
+            workspace::list_projects,
+            workspace::switch_project,
+            workspace::get_active_project,
+            headless::show_main_window,
+            tray::set_minimize_to_tray,
+            tray::get_minimize_to_tray,
+            notifications::set_notification_settings,
+            notifications::get_notification_settings,
+            notifications::add_output_watch,
+            notifications::remove_output_watch,
+            notifications::list_output_watches,
+            quake::set_quake_shortcut,
+            quake::set_quake_config,
+            quake::get_quake_config,
+            quake::attach_quake_session,
+            quake::toggle_quake_window,
+            deeplink::take_deep_links,
+            lifecycle::set_lifecycle_hooks,
+            lifecycle::get_lifecycle_hooks,
+            lifecycle::list_hook_runs,
+            jobs::enqueue_job,
+            jobs::list_jobs,
+            jobs::list_dead_jobs,
+            jobs::requeue_job,
+            jobs::cancel_job,
+            jobs::schedule::add_scheduled_task,
+            jobs::schedule::remove_scheduled_task,
+            jobs::schedule::set_scheduled_task_enabled,
+            jobs::schedule::list_scheduled_tasks,
+            jobs::schedule::run_scheduled_task_now,
+            git::worktree::create_worktree,
+            git::worktree::list_worktrees,
+            git::worktree::remove_worktree,
+            git::branch::create_branch,
+            git::branch::delete_branch,
+            git::branch::list_branches,
+            git::branch::find_stale_branches,
+            git::status::git_status,
+            git::diff::git_diff,
+            git::log::git_log,
+            git::stash::list_stashes,
+            git::stash::stash_push,
+            git::stash::stash_pop,
+            git::stash::stash_apply,
+            git::clone::git_clone,
+            git::clone::cancel_clone,
+            git::remote::git_fetch,
+            git::remote::git_pull,
+            git::remote::git_push,
+            git::backend::get_git_backend_info,
+            git::backend::set_git_backend,
+            git::conflict::check_conflicts,
+            git::cleanup::cleanup_worktrees,
+            git::blame::git_blame,
+            git::commit::git_commit,
+            git::hooks::install_hooks,
+            git::hooks::uninstall_hooks,
+            git::hooks::hook_status,
+            git::watch::watch_repo,
+            git::watch::unwatch_repo,
+            git::watch::list_watched_repos,
+            git::discover::discover_repos,
+            git::drift::branch_drift,
+            git::drift::start_drift_tracking,
+            git::drift::stop_drift_tracking,
+            git::pr::checkout_pr,
+            git::disk_usage::worktree_disk_usage,
+            github::token::set_github_token,
+            github::token::clear_github_token,
+            github::token::has_github_token,
+            github::host::set_github_host,
+            github::host::reset_github_host,
+            github::host::get_github_host,
+            github::oauth::github_login,
+            github::oauth::cancel_github_login,
+            github::issues::list_issues,
+            github::issues::get_issue,
+            github::issues::create_issue,
+            github::issues::update_issue,
+            github::labels::list_labels,
+            github::labels::create_label,
+            github::labels::add_issue_labels,
+            github::labels::remove_issue_label,
+            github::milestones::list_milestones,
+            github::milestones::create_milestone,
+            github::columns::set_issue_column,
+            github::comments::list_issue_comments,
+            github::comments::post_issue_comment,
+            github::comments::post_agent_transcript,
+            github::pulls::create_pr,
+            github::checks::get_checks,
+            github::checks::start_checks_polling,
+            github::checks::stop_checks_polling,
+            github::cache::load_board,
+            github::cache::refresh_board,
+            github::cache::clear_issue_cache,
+            github::queue::list_queued_mutations,
+            github::queue::flush_mutations,
+            github::queue::discard_mutation,
+            github::sync::start_issue_sync,
+            github::sync::stop_issue_sync,
+            github::sync::list_issue_syncs,
+            github::webhook::start_webhook_receiver,
+            github::webhook::stop_webhook_receiver,
+            github::webhook::webhook_receiver_port,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(context)
+        .expect("error while building tauri application")
+        .run(move |app, event| {
+            window_state::on_run_event(app, &event);
+            if let tauri::RunEvent::Exit = event {
+                files::temp::remove_all(app);
+                mcp::stop_all(app);
+            }
+            if headless {
+                headless::on_run_event(app, event);
+            }
+        });
 }
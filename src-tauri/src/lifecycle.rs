@@ -0,0 +1,318 @@
+//! Lifecycle hooks - user shell commands run at points in a session's life
+//!
+//! The frontend passes the `hooks` section of the config to
+//! `set_lifecycle_hooks` at startup, e.g.
+//!
+//! ```yaml
+//! hooks:
+//!   post_worktree_create: pnpm install
+//!   pre_session_kill:
+//!     command: git stash
+//!     timeoutSecs: 30
+//!     onFailure: abort
+//! ```
+//!
+//! Hooks run with `sh -c` in the session's worktree, one after another, with
+//! `ANTLER_*` variables describing the session. Every run is emitted as
+//! `lifecycle-hook` and kept in a short log. A failing hook is logged and
+//! ignored unless its policy is `abort`, which stops the action it belongs to
+//! (and rolls back a dev session being started). Hooks run after the action
+//! is over (`post_session_start`, `post_agent_exit`) have nothing to stop.
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// Time a hook may run when it does not set its own limit
+const DEFAULT_TIMEOUT_SECS: u64 = 5 * 60;
+
+/// Runs kept for `list_hook_runs`
+const LOG_SIZE: usize = 100;
+
+/// Output kept per run
+const OUTPUT_CHARS: usize = 16 * 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookEvent {
+    /// A dev session created its worktree
+    PostWorktreeCreate,
+    /// A dev session's devcontainer is up
+    PostDevcontainerUp,
+    /// A dev session is about to start its agent
+    PreAgentStart,
+    /// A dev session finished starting
+    PostSessionStart,
+    /// An agent's terminal is about to be killed
+    PreSessionKill,
+    /// An agent's process exited
+    PostAgentExit,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailurePolicy {
+    /// Log the failure and carry on
+    #[default]
+    Continue,
+    /// Stop the action the hook belongs to
+    Abort,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Hook {
+    command: String,
+    timeout_secs: Option<u64>,
+    #[serde(default)]
+    on_failure: FailurePolicy,
+}
+
+/// A hook as written in the config: a command, a full entry, or a list
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum HookSpec {
+    Command(String),
+    Hook(Hook),
+    List(Vec<HookSpec>),
+}
+
+impl HookSpec {
+    fn flatten(self, hooks: &mut Vec<Hook>) {
+        match self {
+            HookSpec::Command(command) => hooks.push(Hook {
+                command,
+                timeout_secs: None,
+                on_failure: FailurePolicy::default(),
+            }),
+            HookSpec::Hook(hook) => hooks.push(hook),
+            HookSpec::List(specs) => {
+                for spec in specs {
+                    spec.flatten(hooks);
+                }
+            }
+        }
+    }
+}
+
+/// What a hook is told about the session it runs for
+#[derive(Clone, Debug, Default)]
+pub(crate) struct HookContext {
+    pub(crate) repo: String,
+    pub(crate) issue: u64,
+    /// Worktree the hook runs in
+    pub(crate) cwd: String,
+    pub(crate) branch: Option<String>,
+    pub(crate) container_id: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookStatus {
+    Succeeded,
+    Failed,
+    TimedOut,
+}
+
+/// One hook run, as emitted in `lifecycle-hook`
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HookRun {
+    event: HookEvent,
+    command: String,
+    repo: String,
+    issue: u64,
+    status: HookStatus,
+    code: Option<i32>,
+    /// Combined stdout and stderr, truncated from the front
+    output: String,
+    duration_ms: u64,
+    started_at: String,
+}
+
+/// Configured hooks and recent runs
+#[derive(Default)]
+pub struct LifecycleState {
+    hooks: Mutex<HashMap<HookEvent, Vec<Hook>>>,
+    log: Mutex<VecDeque<HookRun>>,
+}
+
+fn read_all(mut reader: impl Read + Send + 'static) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let mut bytes = Vec::new();
+        let _ = reader.read_to_end(&mut bytes);
+        String::from_utf8_lossy(&bytes).into_owned()
+    })
+}
+
+fn keep_tail(mut text: String) -> String {
+    if text.len() > OUTPUT_CHARS {
+        let mut cut = text.len() - OUTPUT_CHARS;
+        while !text.is_char_boundary(cut) {
+            cut += 1;
+        }
+        text.drain(..cut);
+    }
+    text
+}
+
+/// Run one hook to completion or until its timeout
+fn run_hook(event: HookEvent, hook: &Hook, context: &HookContext) -> HookRun {
+    let started = Instant::now();
+    let started_at = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+    let timeout = Duration::from_secs(hook.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS));
+    let event_name = serde_json::to_value(event)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default();
+
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c")
+        .arg(&hook.command)
+        .current_dir(&context.cwd)
+        .env("ANTLER_EVENT", event_name)
+        .env("ANTLER_REPO", &context.repo)
+        .env("ANTLER_ISSUE", context.issue.to_string())
+        .env("ANTLER_WORKTREE", &context.cwd)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if let Some(branch) = &context.branch {
+        cmd.env("ANTLER_BRANCH", branch);
+    }
+    if let Some(container_id) = &context.container_id {
+        cmd.env("ANTLER_CONTAINER_ID", container_id);
+    }
+
+    let finish = |status, code, output: String| HookRun {
+        event,
+        command: hook.command.clone(),
+        repo: context.repo.clone(),
+        issue: context.issue,
+        status,
+        code,
+        output: keep_tail(output),
+        duration_ms: started.elapsed().as_millis() as u64,
+        started_at: started_at.clone(),
+    };
+
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            return finish(
+                HookStatus::Failed,
+                None,
+                format!("Failed to run hook: {}", e),
+            )
+        }
+    };
+    let stdout = child.stdout.take().map(read_all);
+    let stderr = child.stderr.take().map(read_all);
+
+    let (status, code) = loop {
+        match child.try_wait() {
+            Ok(Some(exit)) if exit.success() => break (HookStatus::Succeeded, exit.code()),
+            Ok(Some(exit)) => break (HookStatus::Failed, exit.code()),
+            Ok(None) if started.elapsed() >= timeout => {
+                let _ = child.kill();
+                let _ = child.wait();
+                break (HookStatus::TimedOut, None);
+            }
+            Ok(None) => thread::sleep(Duration::from_millis(50)),
+            Err(_) => break (HookStatus::Failed, None),
+        }
+    };
+
+    // Processes the hook left behind may hold the pipes open
+    if matches!(status, HookStatus::TimedOut) {
+        return finish(status, code, String::new());
+    }
+    let mut output = String::new();
+    for handle in [stdout, stderr].into_iter().flatten() {
+        output.push_str(&handle.join().unwrap_or_default());
+    }
+    finish(status, code, output)
+}
+
+/// Run the hooks registered for `event` in order.
+///
+/// Fails on the first hook with the `abort` policy that does not succeed;
+/// failures of other hooks are only logged.
+pub(crate) async fn run_hooks(
+    app: &AppHandle,
+    event: HookEvent,
+    context: HookContext,
+) -> Result<(), String> {
+    let state = app.state::<LifecycleState>();
+    let hooks = state.hooks.lock().get(&event).cloned().unwrap_or_default();
+
+    for hook in hooks {
+        let context = context.clone();
+        let command = hook.command.clone();
+        let policy = hook.on_failure;
+        let run = tauri::async_runtime::spawn_blocking(move || run_hook(event, &hook, &context))
+            .await
+            .map_err(|e| format!("Hook '{}' did not finish: {}", command, e))?;
+
+        let _ = app.emit("lifecycle-hook", run.clone());
+        {
+            let mut log = state.log.lock();
+            log.push_back(run.clone());
+            while log.len() > LOG_SIZE {
+                log.pop_front();
+            }
+        }
+
+        if matches!(run.status, HookStatus::Succeeded) {
+            continue;
+        }
+        let reason = match (run.status, run.code) {
+            (HookStatus::TimedOut, _) => "timed out".to_string(),
+            (_, Some(code)) => format!("exited with {}", code),
+            _ => "failed".to_string(),
+        };
+        eprintln!("Hook '{}' {}: {}", command, reason, run.output.trim());
+        if policy == FailurePolicy::Abort {
+            return Err(format!("Hook '{}' {}", command, reason));
+        }
+    }
+    Ok(())
+}
+
+/// Replace the configured hooks
+#[tauri::command]
+pub async fn set_lifecycle_hooks(
+    state: State<'_, LifecycleState>,
+    hooks: HashMap<HookEvent, HookSpec>,
+) -> Result<(), String> {
+    let hooks = hooks
+        .into_iter()
+        .map(|(event, spec)| {
+            let mut list = Vec::new();
+            spec.flatten(&mut list);
+            (event, list)
+        })
+        .filter(|(_, list)| !list.is_empty())
+        .collect();
+    *state.hooks.lock() = hooks;
+    Ok(())
+}
+
+/// Configured hooks by event
+#[tauri::command]
+pub async fn get_lifecycle_hooks(
+    state: State<'_, LifecycleState>,
+) -> Result<HashMap<HookEvent, Vec<Hook>>, String> {
+    Ok(state.hooks.lock().clone())
+}
+
+/// Recent hook runs, oldest first
+#[tauri::command]
+pub async fn list_hook_runs(state: State<'_, LifecycleState>) -> Result<Vec<HookRun>, String> {
+    Ok(state.log.lock().iter().cloned().collect())
+}
@@ -0,0 +1,400 @@
+//! MCP connections - JSON-RPC over a child's stdio or over HTTP
+//!
+//! A stdio server is a child process reading requests from stdin and writing
+//! one message per line to stdout; a thread reads them, hands responses to
+//! their waiting requests, answers the server's pings and passes its
+//! notifications on as `mcp-notification`. Its stderr lines become
+//! `mcp-server-log` events. An HTTP server gets each message POSTed and
+//! answers with JSON or a short event stream, keyed to the session by the
+//! `Mcp-Session-Id` it handed out at initialization.
+
+use parking_lot::Mutex;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, ACCEPT, AUTHORIZATION, CONTENT_TYPE};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::oneshot;
+
+/// Time a server gets to answer a request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+const SESSION_HEADER: &str = "mcp-session-id";
+const VERSION_HEADER: &str = "mcp-protocol-version";
+
+/// Tells connections apart, so an old one's exit isn't taken for its
+/// replacement's
+static NEXT_CONNECTION: AtomicU64 = AtomicU64::new(1);
+
+type Pending = Arc<Mutex<HashMap<u64, oneshot::Sender<Value>>>>;
+
+/// Payload of `mcp-notification`
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct NotificationEvent {
+    server: String,
+    method: String,
+    params: Value,
+}
+
+/// Payload of `mcp-server-log`
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ServerLogEvent {
+    server: String,
+    line: String,
+}
+
+enum Transport {
+    Stdio {
+        child: Arc<Mutex<Child>>,
+        stdin: Arc<Mutex<ChildStdin>>,
+        pending: Pending,
+    },
+    Http {
+        http: reqwest::Client,
+        url: String,
+        headers: HeaderMap,
+        session: Mutex<Option<HeaderValue>>,
+    },
+}
+
+/// A connection to one MCP server
+pub(crate) struct Client {
+    app: AppHandle,
+    name: String,
+    pub(crate) id: u64,
+    transport: Transport,
+    next_request: AtomicU64,
+    /// Revision agreed on at initialization, sent along on HTTP
+    version: Mutex<Option<HeaderValue>>,
+    /// Set once a stdio server's output ends
+    closed: AtomicBool,
+}
+
+fn write_message(stdin: &Mutex<ChildStdin>, message: &Value) -> Result<(), String> {
+    let mut line = message.to_string();
+    line.push('\n');
+    let mut stdin = stdin.lock();
+    stdin
+        .write_all(line.as_bytes())
+        .and_then(|()| stdin.flush())
+        .map_err(|e| format!("Failed to write to the server: {}", e))
+}
+
+fn emit_notification(app: &AppHandle, server: &str, message: &Value) {
+    let _ = app.emit(
+        "mcp-notification",
+        NotificationEvent {
+            server: server.to_string(),
+            method: message["method"].as_str().unwrap_or_default().to_string(),
+            params: message["params"].clone(),
+        },
+    );
+}
+
+/// The result of a response, or its error
+fn into_result(response: Value) -> Result<Value, String> {
+    match response.get("error") {
+        Some(error) => Err(error["message"]
+            .as_str()
+            .map(str::to_string)
+            .unwrap_or_else(|| error.to_string())),
+        None => Ok(response["result"].clone()),
+    }
+}
+
+/// Read a stdio server's messages until it exits
+fn read_stdout(client: Arc<Client>, stdout: impl std::io::Read) {
+    let Transport::Stdio { stdin, pending, .. } = &client.transport else {
+        return;
+    };
+    for line in BufReader::new(stdout).lines() {
+        let Ok(line) = line else { break };
+        let Ok(message) = serde_json::from_str::<Value>(&line) else {
+            continue;
+        };
+        let id = message.get("id").filter(|id| !id.is_null());
+        match (id, message.get("method")) {
+            (Some(id), None) => {
+                if let Some(waiting) = id.as_u64().and_then(|id| pending.lock().remove(&id)) {
+                    let _ = waiting.send(message);
+                }
+            }
+            // Requests from the server: only pings are supported
+            (Some(id), Some(method)) => {
+                let reply = if method == "ping" {
+                    json!({ "jsonrpc": "2.0", "id": id, "result": {} })
+                } else {
+                    json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": { "code": -32601, "message": "Method not supported" },
+                    })
+                };
+                let _ = write_message(stdin, &reply);
+            }
+            (None, Some(_)) => emit_notification(&client.app, &client.name, &message),
+            (None, None) => {}
+        }
+    }
+    // Requests still waiting fail as their senders drop
+    pending.lock().clear();
+    client.closed.store(true, Ordering::SeqCst);
+    super::connection_closed(&client.app, &client.name, client.id);
+}
+
+fn read_stderr(app: AppHandle, server: String, stderr: impl std::io::Read) {
+    for line in BufReader::new(stderr).lines() {
+        let Ok(line) = line else { break };
+        let _ = app.emit(
+            "mcp-server-log",
+            ServerLogEvent {
+                server: server.clone(),
+                line,
+            },
+        );
+    }
+}
+
+/// The JSON-RPC messages in an event-stream body
+fn stream_messages(body: &str) -> Vec<Value> {
+    body.replace("\r\n", "\n")
+        .split("\n\n")
+        .filter_map(|event| {
+            let data: Vec<&str> = event
+                .lines()
+                .filter_map(|line| line.strip_prefix("data:"))
+                .map(|data| data.strip_prefix(' ').unwrap_or(data))
+                .collect();
+            serde_json::from_str(&data.join("\n")).ok()
+        })
+        .collect()
+}
+
+impl Client {
+    /// Start a stdio server
+    pub(crate) fn spawn(
+        app: &AppHandle,
+        name: &str,
+        mut command: Command,
+    ) -> Result<Arc<Self>, String> {
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to start MCP server {}: {}", name, e))?;
+        let (Some(stdin), Some(stdout), Some(stderr)) =
+            (child.stdin.take(), child.stdout.take(), child.stderr.take())
+        else {
+            let _ = child.kill();
+            return Err(format!("MCP server {} has no stdio", name));
+        };
+
+        let client = Arc::new(Self {
+            app: app.clone(),
+            name: name.to_string(),
+            id: NEXT_CONNECTION.fetch_add(1, Ordering::SeqCst),
+            transport: Transport::Stdio {
+                child: Arc::new(Mutex::new(child)),
+                stdin: Arc::new(Mutex::new(stdin)),
+                pending: Arc::default(),
+            },
+            next_request: AtomicU64::new(1),
+            version: Mutex::new(None),
+            closed: AtomicBool::new(false),
+        });
+        let reader = client.clone();
+        thread::spawn(move || read_stdout(reader, stdout));
+        let (app, server) = (app.clone(), name.to_string());
+        thread::spawn(move || read_stderr(app, server, stderr));
+        Ok(client)
+    }
+
+    /// Connect to an HTTP server; `token` is sent as a bearer token
+    pub(crate) fn http(
+        app: &AppHandle,
+        name: &str,
+        url: &str,
+        headers: &HashMap<String, String>,
+        token: Option<String>,
+    ) -> Result<Arc<Self>, String> {
+        let mut header_map = HeaderMap::new();
+        for (key, value) in headers {
+            let key = HeaderName::try_from(key.as_str())
+                .map_err(|e| format!("Bad header name {}: {}", key, e))?;
+            let value = HeaderValue::try_from(value.as_str())
+                .map_err(|e| format!("Bad value for header {}: {}", key, e))?;
+            header_map.insert(key, value);
+        }
+        if let Some(token) = token {
+            let mut value = HeaderValue::try_from(format!("Bearer {}", token))
+                .map_err(|e| format!("Bad token: {}", e))?;
+            value.set_sensitive(true);
+            header_map.insert(AUTHORIZATION, value);
+        }
+        header_map.insert(
+            ACCEPT,
+            HeaderValue::from_static("application/json, text/event-stream"),
+        );
+
+        Ok(Arc::new(Self {
+            app: app.clone(),
+            name: name.to_string(),
+            id: NEXT_CONNECTION.fetch_add(1, Ordering::SeqCst),
+            transport: Transport::Http {
+                http: crate::proxy::client(app),
+                url: url.to_string(),
+                headers: header_map,
+                session: Mutex::new(None),
+            },
+            next_request: AtomicU64::new(1),
+            version: Mutex::new(None),
+            closed: AtomicBool::new(false),
+        }))
+    }
+
+    /// POST a message, returning the response to request `id` if it is one
+    async fn post(&self, message: &Value, id: Option<u64>) -> Result<Option<Value>, String> {
+        let Transport::Http {
+            http,
+            url,
+            headers,
+            session,
+        } = &self.transport
+        else {
+            return Ok(None);
+        };
+        let mut request = http.post(url).headers(headers.clone()).json(message);
+        if let Some(session) = session.lock().clone() {
+            request = request.header(SESSION_HEADER, session);
+        }
+        if let Some(version) = self.version.lock().clone() {
+            request = request.header(VERSION_HEADER, version);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("Failed to reach MCP server {}: {}", self.name, e))?;
+        if let Some(id) = response.headers().get(SESSION_HEADER) {
+            *session.lock() = Some(id.clone());
+        }
+        let status = response.status();
+        if !status.is_success() {
+            return Err(format!("MCP server {} answered {}", self.name, status));
+        }
+        let Some(id) = id else {
+            return Ok(None);
+        };
+
+        let streamed = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("text/event-stream"));
+        let body = response
+            .text()
+            .await
+            .map_err(|e| format!("Failed to read from MCP server {}: {}", self.name, e))?;
+        if !streamed {
+            return serde_json::from_str(&body)
+                .map(Some)
+                .map_err(|e| format!("Bad response from MCP server {}: {}", self.name, e));
+        }
+        let mut answer = None;
+        for message in stream_messages(&body) {
+            if message["id"].as_u64() == Some(id) && message.get("method").is_none() {
+                answer = Some(message);
+            } else if message.get("method").is_some() && message.get("id").is_none() {
+                emit_notification(&self.app, &self.name, &message);
+            }
+        }
+        answer
+            .map(Some)
+            .ok_or_else(|| format!("MCP server {} sent no response", self.name))
+    }
+
+    /// Send a request and wait for its result
+    pub(crate) async fn request(&self, method: &str, params: Value) -> Result<Value, String> {
+        let id = self.next_request.fetch_add(1, Ordering::SeqCst);
+        let message = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        let response = match &self.transport {
+            Transport::Stdio { stdin, pending, .. } => {
+                let (sender, receiver) = oneshot::channel();
+                pending.lock().insert(id, sender);
+                if let Err(e) = write_message(stdin, &message) {
+                    pending.lock().remove(&id);
+                    return Err(e);
+                }
+                match tokio::time::timeout(REQUEST_TIMEOUT, receiver).await {
+                    Ok(Ok(response)) => response,
+                    Ok(Err(_)) => return Err(format!("MCP server {} exited", self.name)),
+                    Err(_) => {
+                        pending.lock().remove(&id);
+                        return Err(format!("MCP server {} did not answer", self.name));
+                    }
+                }
+            }
+            Transport::Http { .. } => {
+                match tokio::time::timeout(REQUEST_TIMEOUT, self.post(&message, Some(id))).await {
+                    Ok(response) => response?.unwrap_or_default(),
+                    Err(_) => return Err(format!("MCP server {} did not answer", self.name)),
+                }
+            }
+        };
+        into_result(response)
+    }
+
+    /// Send a notification
+    pub(crate) async fn notify(&self, method: &str, params: Value) -> Result<(), String> {
+        let message = json!({ "jsonrpc": "2.0", "method": method, "params": params });
+        match &self.transport {
+            Transport::Stdio { stdin, .. } => write_message(stdin, &message),
+            Transport::Http { .. } => self.post(&message, None).await.map(|_| ()),
+        }
+    }
+
+    /// Remember the protocol revision the server agreed to
+    pub(crate) fn set_version(&self, version: &str) {
+        *self.version.lock() = HeaderValue::try_from(version).ok();
+    }
+
+    /// Whether a stdio server has exited
+    pub(crate) fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+    }
+
+    /// End the connection: stdio servers are killed, HTTP sessions ended
+    pub(crate) async fn close(&self) {
+        match &self.transport {
+            Transport::Stdio { child, .. } => {
+                let _ = child.lock().kill();
+                let child = child.clone();
+                let _ = tauri::async_runtime::spawn_blocking(move || child.lock().wait()).await;
+            }
+            Transport::Http {
+                http,
+                url,
+                headers,
+                session,
+            } => {
+                let session = session.lock().take();
+                if let Some(session) = session {
+                    let _ = http
+                        .delete(url)
+                        .headers(headers.clone())
+                        .header(SESSION_HEADER, session)
+                        .send()
+                        .await;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,471 @@
+//! MCP client - the user's MCP servers, run and reached from the backend
+//!
+//! Servers are configured once (a command to run over stdio, or the URL of
+//! an HTTP server) and kept in a SQLite file in the app data directory; a
+//! server's token lives in the OS keychain, set with `set_mcp_server_token`,
+//! and is sent as a bearer token or put in the child's environment. Started
+//! servers are initialized here and their tools and resources offered to
+//! the frontend through `list_mcp_tools`, `call_mcp_tool`,
+//! `list_mcp_resources` and `read_mcp_resource`. `mcp-server-state` reports
+//! servers coming up and going away, their own exits included; all of them
+//! are stopped when the app quits.
+
+mod client;
+
+use crate::store::Store;
+use client::Client;
+use keyring::Entry;
+use parking_lot::Mutex;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::process::Command;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+const MCP_FILE: &str = "mcp.sqlite";
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS servers (
+    name TEXT PRIMARY KEY,
+    config TEXT NOT NULL,
+    added_at TEXT NOT NULL
+);
+";
+
+const KEYCHAIN_SERVICE: &str = "com.antler.app";
+
+/// Protocol revision asked for at initialization
+const PROTOCOL_VERSION: &str = "2025-06-18";
+
+/// Time HTTP servers get to end their session when the app quits
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Saved servers and the running ones
+pub struct McpClientState {
+    store: Store,
+    running: Mutex<HashMap<String, Running>>,
+}
+
+impl Default for McpClientState {
+    fn default() -> Self {
+        Self {
+            store: Store::new(MCP_FILE, SCHEMA, "MCP servers"),
+            running: Default::default(),
+        }
+    }
+}
+
+struct Running {
+    client: Arc<Client>,
+    /// `serverInfo` and `capabilities` from the initialize result
+    initialized: Value,
+}
+
+impl McpClientState {
+    fn with<T>(
+        &self,
+        app: &AppHandle,
+        f: impl FnOnce(&mut Connection) -> rusqlite::Result<T>,
+    ) -> Result<T, String> {
+        self.store.with(app, f)
+    }
+
+    fn client(&self, name: &str) -> Result<Arc<Client>, String> {
+        self.running
+            .lock()
+            .get(name)
+            .map(|running| running.client.clone())
+            .ok_or_else(|| format!("MCP server {} is not running", name))
+    }
+}
+
+/// How a server is reached
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(
+    tag = "transport",
+    rename_all = "snake_case",
+    rename_all_fields = "camelCase"
+)]
+pub enum McpTransport {
+    /// A child process speaking over stdin and stdout
+    Stdio {
+        command: String,
+        #[serde(default)]
+        args: Vec<String>,
+        #[serde(default)]
+        env: HashMap<String, String>,
+        #[serde(default)]
+        cwd: Option<String>,
+    },
+    Http {
+        url: String,
+        #[serde(default)]
+        headers: HashMap<String, String>,
+    },
+}
+
+/// A configured MCP server
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpServerConfig {
+    name: String,
+    #[serde(flatten)]
+    transport: McpTransport,
+    /// Environment variable a stdio server gets its token in
+    #[serde(default)]
+    token_env: Option<String>,
+}
+
+/// A configured server and whether it runs
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpServerInfo {
+    #[serde(flatten)]
+    config: McpServerConfig,
+    running: bool,
+    /// `serverInfo` and `capabilities` the server reported, while running
+    initialized: Option<Value>,
+}
+
+/// Payload of `mcp-server-state`
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ServerStateEvent {
+    name: String,
+    running: bool,
+}
+
+fn token_entry(name: &str) -> Result<Entry, String> {
+    Entry::new(KEYCHAIN_SERVICE, &format!("mcp-token:{}", name)).map_err(|e| e.to_string())
+}
+
+fn load_token(name: &str) -> Result<Option<String>, String> {
+    match token_entry(name)?.get_password() {
+        Ok(token) => Ok(Some(token)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(format!("Failed to read the token for {}: {}", name, e)),
+    }
+}
+
+fn lookup(app: &AppHandle, name: &str) -> Result<McpServerConfig, String> {
+    let config: Option<String> = app.state::<McpClientState>().with(app, |conn| {
+        conn.query_row(
+            "SELECT config FROM servers WHERE name = ?1",
+            [name],
+            |row| row.get(0),
+        )
+        .optional()
+    })?;
+    let config = config.ok_or_else(|| format!("No MCP server named '{}'", name))?;
+    serde_json::from_str(&config).map_err(|e| format!("Bad config for {}: {}", name, e))
+}
+
+fn emit_state(app: &AppHandle, name: &str, running: bool) {
+    let _ = app.emit(
+        "mcp-server-state",
+        ServerStateEvent {
+            name: name.to_string(),
+            running,
+        },
+    );
+}
+
+/// Forget a connection that ended by itself, unless it was replaced already
+pub(crate) fn connection_closed(app: &AppHandle, name: &str, id: u64) {
+    let removed = {
+        let state = app.state::<McpClientState>();
+        let mut running = state.running.lock();
+        match running.get(name) {
+            Some(current) if current.client.id == id => running.remove(name).is_some(),
+            _ => false,
+        }
+    };
+    if removed {
+        emit_state(app, name, false);
+    }
+}
+
+fn connect(app: &AppHandle, config: &McpServerConfig) -> Result<Arc<Client>, String> {
+    let token = load_token(&config.name)?;
+    match &config.transport {
+        McpTransport::Stdio {
+            command,
+            args,
+            env,
+            cwd,
+        } => {
+            let mut cmd = Command::new(command);
+            cmd.args(args).envs(env);
+            if let Some(cwd) = cwd {
+                cmd.current_dir(cwd);
+            }
+            if let (Some(var), Some(token)) = (&config.token_env, token) {
+                cmd.env(var, token);
+            }
+            #[cfg(windows)]
+            {
+                use std::os::windows::process::CommandExt;
+                // No console window flashing up for the server
+                const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+                cmd.creation_flags(CREATE_NO_WINDOW);
+            }
+            Client::spawn(app, &config.name, cmd)
+        }
+        McpTransport::Http { url, headers } => Client::http(app, &config.name, url, headers, token),
+    }
+}
+
+/// Stop every running server, when the app quits
+pub(crate) fn stop_all(app: &AppHandle) {
+    let running: Vec<Running> = app
+        .state::<McpClientState>()
+        .running
+        .lock()
+        .drain()
+        .map(|(_, running)| running)
+        .collect();
+    tauri::async_runtime::block_on(async {
+        for running in running {
+            let _ = tokio::time::timeout(CLOSE_TIMEOUT, running.client.close()).await;
+        }
+    });
+}
+
+/// Every item of a paginated list request
+async fn list_all(client: &Client, method: &str, key: &str) -> Result<Vec<Value>, String> {
+    let mut items = Vec::new();
+    let mut cursor: Option<String> = None;
+    loop {
+        let params = match &cursor {
+            Some(cursor) => json!({ "cursor": cursor }),
+            None => json!({}),
+        };
+        let mut page = client.request(method, params).await?;
+        if let Some(Value::Array(page_items)) = page.get_mut(key).map(Value::take) {
+            items.extend(page_items);
+        }
+        match page["nextCursor"].as_str() {
+            Some(next) if cursor.as_deref() != Some(next) => cursor = Some(next.to_string()),
+            _ => return Ok(items),
+        }
+    }
+}
+
+/// Configured servers, by name
+#[tauri::command]
+pub async fn list_mcp_servers(
+    app: AppHandle,
+    state: State<'_, McpClientState>,
+) -> Result<Vec<McpServerInfo>, String> {
+    let configs: Vec<String> = state.with(&app, |conn| {
+        let mut stmt = conn.prepare("SELECT config FROM servers ORDER BY name")?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        rows.collect()
+    })?;
+    let running = state.running.lock();
+    Ok(configs
+        .iter()
+        .filter_map(|config| serde_json::from_str::<McpServerConfig>(config).ok())
+        .map(|config| {
+            let initialized = running
+                .get(&config.name)
+                .map(|running| running.initialized.clone());
+            McpServerInfo {
+                running: initialized.is_some(),
+                initialized,
+                config,
+            }
+        })
+        .collect())
+}
+
+/// Add a server, or replace the one with the same name; a running one keeps
+/// its old settings until restarted
+#[tauri::command]
+pub async fn save_mcp_server(
+    app: AppHandle,
+    state: State<'_, McpClientState>,
+    config: McpServerConfig,
+) -> Result<(), String> {
+    if config.name.trim().is_empty() {
+        return Err("An MCP server needs a name".to_string());
+    }
+    let json = serde_json::to_string(&config).map_err(|e| e.to_string())?;
+    state.with(&app, |conn| {
+        conn.execute(
+            "INSERT INTO servers (name, config, added_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT(name) DO UPDATE SET config = excluded.config",
+            params![config.name, json, chrono::Utc::now().to_rfc3339()],
+        )
+        .map(|_| ())
+    })
+}
+
+/// Stop a server, forget it and its token
+#[tauri::command]
+pub async fn remove_mcp_server(
+    app: AppHandle,
+    state: State<'_, McpClientState>,
+    name: String,
+) -> Result<(), String> {
+    stop_mcp_server(app.clone(), state.clone(), name.clone()).await?;
+    state.with(&app, |conn| {
+        conn.execute("DELETE FROM servers WHERE name = ?1", [&name])
+            .map(|_| ())
+    })?;
+    match token_entry(&name)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(format!("Failed to remove the token for {}: {}", name, e)),
+    }
+}
+
+/// Keep a server's token in the keychain, or remove it with `None`
+#[tauri::command]
+pub async fn set_mcp_server_token(name: String, token: Option<String>) -> Result<(), String> {
+    let entry = token_entry(&name)?;
+    match token {
+        Some(token) => entry
+            .set_password(&token)
+            .map_err(|e| format!("Failed to store the token for {}: {}", name, e)),
+        None => match entry.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(format!("Failed to remove the token for {}: {}", name, e)),
+        },
+    }
+}
+
+/// Start and initialize a server, returning what it reported about itself.
+///
+/// A server that is running already is restarted.
+#[tauri::command]
+pub async fn start_mcp_server(
+    app: AppHandle,
+    state: State<'_, McpClientState>,
+    name: String,
+) -> Result<Value, String> {
+    stop_mcp_server(app.clone(), state.clone(), name.clone()).await?;
+    let config = lookup(&app, &name)?;
+    let client = connect(&app, &config)?;
+
+    let initialize = client
+        .request(
+            "initialize",
+            json!({
+                "protocolVersion": PROTOCOL_VERSION,
+                "capabilities": {},
+                "clientInfo": { "name": "antler", "version": env!("CARGO_PKG_VERSION") },
+            }),
+        )
+        .await;
+    let initialized = match initialize {
+        Ok(result) => result,
+        Err(e) => {
+            client.close().await;
+            return Err(format!("Failed to initialize MCP server {}: {}", name, e));
+        }
+    };
+    if let Some(version) = initialized["protocolVersion"].as_str() {
+        client.set_version(version);
+    }
+    if let Err(e) = client.notify("notifications/initialized", json!({})).await {
+        client.close().await;
+        return Err(e);
+    }
+
+    let info = json!({
+        "serverInfo": initialized["serverInfo"],
+        "capabilities": initialized["capabilities"],
+        "instructions": initialized["instructions"],
+    });
+    // Another start may have finished while this one was initializing
+    let replaced = {
+        let mut running = state.running.lock();
+        let replaced = running.insert(
+            name.clone(),
+            Running {
+                client: client.clone(),
+                initialized: info.clone(),
+            },
+        );
+        emit_state(&app, &name, true);
+        replaced
+    };
+    if let Some(replaced) = replaced {
+        replaced.client.close().await;
+    }
+    // A server that exited before it was recorded had nothing to remove
+    if client.is_closed() {
+        connection_closed(&app, &name, client.id);
+        return Err(format!("MCP server {} exited while starting", name));
+    }
+    Ok(info)
+}
+
+/// Stop a server; stdio servers are killed
+#[tauri::command]
+pub async fn stop_mcp_server(
+    app: AppHandle,
+    state: State<'_, McpClientState>,
+    name: String,
+) -> Result<(), String> {
+    let running = state.running.lock().remove(&name);
+    if let Some(running) = running {
+        running.client.close().await;
+        emit_state(&app, &name, false);
+    }
+    Ok(())
+}
+
+/// Tools a running server offers, as it describes them
+#[tauri::command]
+pub async fn list_mcp_tools(
+    state: State<'_, McpClientState>,
+    name: String,
+) -> Result<Vec<Value>, String> {
+    let client = state.client(&name)?;
+    list_all(&client, "tools/list", "tools").await
+}
+
+/// Call a tool, returning its result with `content` and `isError`
+#[tauri::command]
+pub async fn call_mcp_tool(
+    state: State<'_, McpClientState>,
+    name: String,
+    tool: String,
+    arguments: Option<Value>,
+) -> Result<Value, String> {
+    let client = state.client(&name)?;
+    client
+        .request(
+            "tools/call",
+            json!({ "name": tool, "arguments": arguments.unwrap_or_else(|| json!({})) }),
+        )
+        .await
+}
+
+/// Resources a running server offers
+#[tauri::command]
+pub async fn list_mcp_resources(
+    state: State<'_, McpClientState>,
+    name: String,
+) -> Result<Vec<Value>, String> {
+    let client = state.client(&name)?;
+    list_all(&client, "resources/list", "resources").await
+}
+
+/// Read a resource, returning its `contents`
+#[tauri::command]
+pub async fn read_mcp_resource(
+    state: State<'_, McpClientState>,
+    name: String,
+    uri: String,
+) -> Result<Value, String> {
+    let client = state.client(&name)?;
+    let mut result = client
+        .request("resources/read", json!({ "uri": uri }))
+        .await?;
+    Ok(result["contents"].take())
+}
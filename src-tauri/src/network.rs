@@ -0,0 +1,187 @@
+//! Network connectivity - one watcher instead of a probe per feature
+//!
+//! The watcher sends a HEAD to the configured GitHub instance's web UI, which
+//! unlike its API costs no rate limit, and when that goes unanswered fetches
+//! a known plain-HTTP page to tell a network that is down from one behind a
+//! captive portal (which answers with its login page instead). Changes are emitted as `connectivity-changed`. Issue sync skips
+//! its polls while offline and resumes the moment the network returns, when
+//! mutations queued in the meantime are replayed too; new mutations are
+//! queued straight away rather than waiting on a request that can't succeed.
+
+use crate::github::queue::has_pending;
+use crate::github::GitHubState;
+use crate::jobs::{self, JobKind};
+use crate::power;
+use crate::proxy;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::Notify;
+
+/// Plain-HTTP page with a known body; a portal serves something else
+const PORTAL_PROBE_URL: &str = "http://detectportal.firefox.com/success.txt";
+const PORTAL_PROBE_BODY: &str = "success";
+
+/// Time a probe may take before the network counts as down
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How often the network is probed while online, and while it isn't
+const ONLINE_INTERVAL: Duration = Duration::from_secs(30);
+const OFFLINE_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectivityStatus {
+    /// Not probed yet
+    #[default]
+    Unknown,
+    Online,
+    Offline,
+    /// Requests are answered by a login page until the user signs in
+    CaptivePortal,
+}
+
+/// Payload of `connectivity-changed`
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Connectivity {
+    status: ConnectivityStatus,
+    /// Whether GitHub answered; the network can be up without it
+    github_reachable: bool,
+}
+
+/// State for the connectivity watcher
+pub struct ConnectivityState {
+    http: Mutex<reqwest::Client>,
+    last: Mutex<Connectivity>,
+    /// Woken when the network comes back
+    online: Notify,
+    /// Woken to probe again before the interval is up
+    recheck: Notify,
+}
+
+impl Default for ConnectivityState {
+    fn default() -> Self {
+        Self {
+            http: Mutex::new(probe_client(reqwest::Client::builder())),
+            last: Mutex::new(Connectivity::default()),
+            online: Notify::new(),
+            recheck: Notify::new(),
+        }
+    }
+}
+
+fn probe_client(builder: reqwest::ClientBuilder) -> reqwest::Client {
+    builder
+        .timeout(PROBE_TIMEOUT)
+        // A portal's redirect to its login page is the answer itself
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap_or_default()
+}
+
+async fn probe(app: &AppHandle) -> Connectivity {
+    let http = app.state::<ConnectivityState>().http.lock().clone();
+    let web_base = app.state::<GitHubState>().host().web_base;
+    let github_reachable = http
+        .head(&web_base)
+        .header(reqwest::header::USER_AGENT, "Antler")
+        .send()
+        .await
+        .is_ok();
+    if github_reachable {
+        return Connectivity {
+            status: ConnectivityStatus::Online,
+            github_reachable,
+        };
+    }
+
+    let status = match http.get(PORTAL_PROBE_URL).send().await {
+        Err(_) => ConnectivityStatus::Offline,
+        Ok(response) if !response.status().is_success() => ConnectivityStatus::CaptivePortal,
+        Ok(response) => match response.text().await {
+            Ok(body) if body.trim() == PORTAL_PROBE_BODY => ConnectivityStatus::Online,
+            Ok(_) => ConnectivityStatus::CaptivePortal,
+            Err(_) => ConnectivityStatus::Offline,
+        },
+    };
+    Connectivity {
+        status,
+        github_reachable,
+    }
+}
+
+/// Probe the network, announcing any change and catching up on what
+/// waited for it
+async fn refresh(app: &AppHandle) -> Connectivity {
+    let current = probe(app).await;
+    let state = app.state::<ConnectivityState>();
+    let previous = std::mem::replace(&mut *state.last.lock(), current.clone());
+    if previous == current {
+        return current;
+    }
+
+    let _ = app.emit("connectivity-changed", current.clone());
+    if current.status == ConnectivityStatus::Online && previous.status != ConnectivityStatus::Online
+    {
+        state.online.notify_waiters();
+        // An inline replay would hold up the watcher behind GitHub
+        match has_pending(app) {
+            Ok(true) => {
+                if let Err(e) = jobs::enqueue(app, JobKind::FlushMutations, None, None) {
+                    eprintln!("Failed to queue mutation replay: {}", e);
+                }
+            }
+            Ok(false) => {}
+            Err(e) => eprintln!("Failed to read queued mutations: {}", e),
+        }
+    }
+    current
+}
+
+/// Whether the last probe found the network down or behind a portal
+pub(crate) fn is_offline(app: &AppHandle) -> bool {
+    matches!(
+        app.state::<ConnectivityState>().last.lock().status,
+        ConnectivityStatus::Offline | ConnectivityStatus::CaptivePortal
+    )
+}
+
+/// Wait until the network comes back, for `at_most`
+pub(crate) async fn wait_for_online(app: &AppHandle, at_most: Duration) {
+    let state = app.state::<ConnectivityState>();
+    let _ = tokio::time::timeout(at_most, state.online.notified()).await;
+}
+
+/// Probe again soon, after a request failed for want of a network
+pub(crate) fn recheck(app: &AppHandle) {
+    app.state::<ConnectivityState>().recheck.notify_one();
+}
+
+/// Probe through the proxy just configured
+pub(crate) fn reconfigure(app: &AppHandle) {
+    let state = app.state::<ConnectivityState>();
+    *state.http.lock() = probe_client(proxy::configure(app, reqwest::Client::builder()));
+    state.recheck.notify_one();
+}
+
+/// Watch connectivity for the lifetime of the app
+pub(crate) fn start_watcher(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let interval = match refresh(&app).await.status {
+                ConnectivityStatus::Online => power::stretch(&app, ONLINE_INTERVAL),
+                _ => OFFLINE_INTERVAL,
+            };
+            let state = app.state::<ConnectivityState>();
+            let _ = tokio::time::timeout(interval, state.recheck.notified()).await;
+        }
+    });
+}
+
+/// Probe the network now
+#[tauri::command]
+pub async fn check_connectivity(app: AppHandle) -> Result<Connectivity, String> {
+    Ok(refresh(&app).await)
+}
@@ -0,0 +1,369 @@
+//! Native notifications - agent lifecycle and output watches
+//!
+//! The webview only sees events while it is loaded, so anything worth a
+//! notification when the window is closed or hidden is posted from here:
+//! an agent's process exiting on its own, an agent stopping at an approval
+//! prompt, and a line of terminal output matching one of the patterns added
+//! with `add_output_watch` ("tests failed", say). Clicking a notification
+//! brings the window back and emits `notification-clicked` with the agent,
+//! issue and PTY it was about, so the UI can focus the card.
+//!
+//! Notifications are skipped while an Antler window has focus, unless
+//! `whenFocused` is set with `set_notification_settings`.
+
+use crate::agents::{self, AgentInfo, AgentState};
+use crate::tray;
+use notify_rust::Notification;
+use parking_lot::Mutex;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// A watch fires at most once per PTY in this long, so a test runner
+/// printing a failure per test posts a single notification
+const WATCH_COOLDOWN: Duration = Duration::from_secs(10);
+
+/// Longest partial line kept between output chunks
+const MAX_PENDING_LINE: usize = 4 * 1024;
+
+/// Longest matched line shown in a notification
+const MAX_BODY_CHARS: usize = 200;
+
+/// State for notifications and output watches
+pub struct NotificationState {
+    settings: Mutex<NotificationSettings>,
+    watches: Mutex<HashMap<u32, OutputWatch>>,
+    next_id: AtomicU32,
+    /// Output after the last line break, per PTY
+    pending: Mutex<HashMap<u32, String>>,
+}
+
+impl Default for NotificationState {
+    fn default() -> Self {
+        Self {
+            settings: Mutex::new(NotificationSettings::default()),
+            watches: Mutex::new(HashMap::new()),
+            next_id: AtomicU32::new(1),
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+/// Which notifications are posted
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NotificationSettings {
+    agent_exit: bool,
+    needs_input: bool,
+    output_watches: bool,
+    /// Also notify while an Antler window has focus
+    when_focused: bool,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            agent_exit: true,
+            needs_input: true,
+            output_watches: true,
+            when_focused: false,
+        }
+    }
+}
+
+struct OutputWatch {
+    pattern: Regex,
+    title: Option<String>,
+    /// Only this PTY's output is matched, or every PTY's when unset
+    pty_id: Option<u32>,
+    /// When each PTY last fired this watch
+    fired: HashMap<u32, Instant>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputWatchInfo {
+    id: u32,
+    pattern: String,
+    title: Option<String>,
+    pty_id: Option<u32>,
+}
+
+/// What a notification is about, sent back with `notification-clicked`
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct NotificationTarget {
+    agent_id: Option<u32>,
+    pty_id: Option<u32>,
+    repo: Option<String>,
+    issue: Option<u64>,
+    watch_id: Option<u32>,
+}
+
+impl NotificationTarget {
+    fn agent(agent: &AgentInfo) -> Self {
+        Self {
+            agent_id: Some(agent.id),
+            pty_id: agent.pty_id,
+            repo: Some(agent.issue.repo.clone()),
+            issue: Some(agent.issue.number),
+            watch_id: None,
+        }
+    }
+}
+
+/// Tell the OS which app is posting, where it needs to be told
+pub(crate) fn init(app: &AppHandle) {
+    #[cfg(target_os = "macos")]
+    let _ = notify_rust::set_application(&app.config().identifier);
+    #[cfg(not(target_os = "macos"))]
+    let _ = app;
+}
+
+pub(crate) fn window_focused(app: &AppHandle) -> bool {
+    app.webview_windows()
+        .values()
+        .any(|window| window.is_visible().unwrap_or(false) && window.is_focused().unwrap_or(false))
+}
+
+fn find_agent(app: &AppHandle, id: u32) -> Option<AgentInfo> {
+    agents::agent_list(&app.state::<AgentState>())
+        .into_iter()
+        .find(|agent| agent.id == id)
+}
+
+fn agent_body(agent: &AgentInfo) -> String {
+    format!(
+        "{} #{}: {}",
+        agent.issue.repo, agent.issue.number, agent.issue.title
+    )
+}
+
+/// Post a notification and wait in the background for it to be clicked
+fn post(app: &AppHandle, title: String, body: String, target: NotificationTarget) {
+    let app = app.clone();
+    thread::spawn(move || {
+        let handle = match Notification::new()
+            .appname("Antler")
+            .summary(&title)
+            .body(&body)
+            .action("default", "Show")
+            .show()
+        {
+            Ok(handle) => handle,
+            Err(e) => {
+                eprintln!("Failed to post notification: {}", e);
+                return;
+            }
+        };
+        handle.wait_for_action(|action| {
+            if action == "__closed" {
+                return;
+            }
+            tray::show_windows(&app);
+            let _ = app.emit("notification-clicked", target);
+        });
+    });
+}
+
+/// Whether notifications of the kind `enabled` picks should go out now
+fn should_notify(app: &AppHandle, enabled: impl FnOnce(&NotificationSettings) -> bool) -> bool {
+    let settings = app.state::<NotificationState>().settings.lock().clone();
+    enabled(&settings) && (settings.when_focused || !window_focused(app))
+}
+
+/// An agent's process exited without being stopped
+pub(crate) fn agent_exited(app: &AppHandle, id: u32) {
+    if !should_notify(app, |settings| settings.agent_exit) {
+        return;
+    }
+    if let Some(agent) = find_agent(app, id) {
+        post(
+            app,
+            "Agent finished".to_string(),
+            agent_body(&agent),
+            NotificationTarget::agent(&agent),
+        );
+    }
+}
+
+/// An agent is waiting at an approval prompt
+pub(crate) fn agent_needs_input(app: &AppHandle, id: u32, question: &str) {
+    if !should_notify(app, |settings| settings.needs_input) {
+        return;
+    }
+    if let Some(agent) = find_agent(app, id) {
+        post(
+            app,
+            format!("Agent needs input: {}", question),
+            agent_body(&agent),
+            NotificationTarget::agent(&agent),
+        );
+    }
+}
+
+/// Match a chunk of a PTY's output against the output watches
+pub(crate) fn scan_output(app: &AppHandle, pty_id: u32, data: &str) {
+    let state = app.state::<NotificationState>();
+    if state.watches.lock().is_empty() {
+        return;
+    }
+
+    let lines: Vec<String> = {
+        let mut pending = state.pending.lock();
+        let buffer = pending.entry(pty_id).or_default();
+        buffer.push_str(&agents::strip_ansi(data));
+        let Some(end) = buffer.rfind('\n') else {
+            if buffer.len() > MAX_PENDING_LINE {
+                buffer.clear();
+            }
+            return;
+        };
+        let complete: String = buffer.drain(..=end).collect();
+        complete
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect()
+    };
+    if lines.is_empty() {
+        return;
+    }
+
+    let mut matched = Vec::new();
+    {
+        let mut watches = state.watches.lock();
+        for (id, watch) in watches.iter_mut() {
+            if watch.pty_id.is_some_and(|only| only != pty_id) {
+                continue;
+            }
+            let Some(line) = lines.iter().find(|line| watch.pattern.is_match(line)) else {
+                continue;
+            };
+            let now = Instant::now();
+            if watch
+                .fired
+                .get(&pty_id)
+                .is_some_and(|last| now.duration_since(*last) < WATCH_COOLDOWN)
+            {
+                continue;
+            }
+            watch.fired.insert(pty_id, now);
+            matched.push((*id, watch.title.clone(), line.clone()));
+        }
+    }
+
+    if matched.is_empty() || !should_notify(app, |settings| settings.output_watches) {
+        return;
+    }
+    let agent = agents::agent_list(&app.state::<AgentState>())
+        .into_iter()
+        .find(|agent| agent.pty_id == Some(pty_id));
+    for (watch_id, title, line) in matched {
+        let mut body: String = line.chars().take(MAX_BODY_CHARS).collect();
+        if let Some(agent) = &agent {
+            body = format!("{}\n{}", body, agent_body(agent));
+        }
+        let target = NotificationTarget {
+            pty_id: Some(pty_id),
+            watch_id: Some(watch_id),
+            ..agent
+                .as_ref()
+                .map(NotificationTarget::agent)
+                .unwrap_or_default()
+        };
+        post(
+            app,
+            title.unwrap_or_else(|| "Output matched".to_string()),
+            body,
+            target,
+        );
+    }
+}
+
+/// Drop what was kept of a PTY's output once it exits
+pub(crate) fn forget_output(app: &AppHandle, pty_id: u32) {
+    let state = app.state::<NotificationState>();
+    state.pending.lock().remove(&pty_id);
+    for watch in state.watches.lock().values_mut() {
+        watch.fired.remove(&pty_id);
+    }
+}
+
+/// Choose which notifications are posted
+#[tauri::command]
+pub async fn set_notification_settings(
+    state: State<'_, NotificationState>,
+    settings: NotificationSettings,
+) -> Result<(), String> {
+    *state.settings.lock() = settings;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_notification_settings(
+    state: State<'_, NotificationState>,
+) -> Result<NotificationSettings, String> {
+    Ok(state.settings.lock().clone())
+}
+
+/// Notify when a line of terminal output matches `pattern`, a regular
+/// expression. `title` heads the notification, and `ptyId` limits the watch
+/// to one terminal.
+#[tauri::command]
+pub async fn add_output_watch(
+    state: State<'_, NotificationState>,
+    pattern: String,
+    title: Option<String>,
+    pty_id: Option<u32>,
+) -> Result<u32, String> {
+    let pattern =
+        Regex::new(&pattern).map_err(|e| format!("Invalid pattern '{}': {}", pattern, e))?;
+    let id = state.next_id.fetch_add(1, Ordering::SeqCst);
+    state.watches.lock().insert(
+        id,
+        OutputWatch {
+            pattern,
+            title,
+            pty_id,
+            fired: HashMap::new(),
+        },
+    );
+    Ok(id)
+}
+
+#[tauri::command]
+pub async fn remove_output_watch(
+    state: State<'_, NotificationState>,
+    id: u32,
+) -> Result<(), String> {
+    state
+        .watches
+        .lock()
+        .remove(&id)
+        .map(|_| ())
+        .ok_or_else(|| format!("Output watch {} not found", id))
+}
+
+#[tauri::command]
+pub async fn list_output_watches(
+    state: State<'_, NotificationState>,
+) -> Result<Vec<OutputWatchInfo>, String> {
+    let watches = state.watches.lock();
+    let mut info: Vec<OutputWatchInfo> = watches
+        .iter()
+        .map(|(id, watch)| OutputWatchInfo {
+            id: *id,
+            pattern: watch.pattern.as_str().to_string(),
+            title: watch.title.clone(),
+            pty_id: watch.pty_id,
+        })
+        .collect();
+    info.sort_by_key(|watch| watch.id);
+    Ok(info)
+}
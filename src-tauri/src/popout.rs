@@ -0,0 +1,123 @@
+//! Pop-out terminals - a PTY session in a window of its own
+//!
+//! `pop_out_session` moves a session into a new native window, opened at
+//! `index.html?window=terminal&pty=<id>`, and `dock_session` brings it back.
+//! Every window shares the one `PtyState`, so input, resizing and killing
+//! work the same from any of them; only the output is routed, going to the
+//! popped-out window alone while it is open. Closing that window docks the
+//! session again. Each move is announced with a `pty-window-changed` event
+//! so the main window can hide or restore its own view.
+
+use crate::pty::{self, PtyState};
+use crate::window_state;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State, WebviewUrl, WebviewWindowBuilder, WindowEvent};
+
+/// Labels of pop-out windows start with this, followed by the PTY ID
+pub(crate) const LABEL_PREFIX: &str = "terminal-";
+
+/// Options for `pop_out_session`
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PopOutOptions {
+    title: Option<String>,
+    width: Option<f64>,
+    height: Option<f64>,
+}
+
+/// Payload of `pty-window-changed`
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PtyWindowEvent {
+    pty_id: u32,
+    /// Label of the window now showing the session, `None` once docked
+    window: Option<String>,
+}
+
+pub(crate) fn label(pty_id: u32) -> String {
+    format!("{}{}", LABEL_PREFIX, pty_id)
+}
+
+/// Route the session's output back to every window and tell them
+fn docked(app: &AppHandle, pty_id: u32) {
+    // The session may have exited and been removed already
+    let _ = pty::set_owner(&app.state::<PtyState>(), pty_id, None);
+    let _ = app.emit(
+        "pty-window-changed",
+        PtyWindowEvent {
+            pty_id,
+            window: None,
+        },
+    );
+}
+
+/// Move PTY session `ptyId` into its own window, returning the window's
+/// label. A session already popped out has its window focused instead.
+#[tauri::command]
+pub async fn pop_out_session(
+    app: AppHandle,
+    state: State<'_, PtyState>,
+    pty_id: u32,
+    options: Option<PopOutOptions>,
+) -> Result<String, String> {
+    let options = options.unwrap_or_default();
+    let label = label(pty_id);
+    if let Some(window) = app.get_webview_window(&label) {
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+        return Ok(label);
+    }
+
+    // Claimed before the window opens so the main view stops getting output
+    pty::set_owner(&state, pty_id, Some(label.clone()))?;
+    let url = WebviewUrl::App(format!("index.html?window=terminal&pty={}", pty_id).into());
+    let built = WebviewWindowBuilder::new(&app, &label, url)
+        .title(options.title.as_deref().unwrap_or("Antler"))
+        .inner_size(
+            options.width.unwrap_or(900.0),
+            options.height.unwrap_or(600.0),
+        )
+        .build();
+    let window = match built {
+        Ok(window) => window,
+        Err(e) => {
+            docked(&app, pty_id);
+            return Err(format!("Failed to open a window for PTY {}: {}", pty_id, e));
+        }
+    };
+
+    window_state::restore(&window.as_ref().window());
+
+    let handle = app.clone();
+    window.on_window_event(move |event| {
+        if let WindowEvent::Destroyed = event {
+            docked(&handle, pty_id);
+        }
+    });
+    let _ = app.emit(
+        "pty-window-changed",
+        PtyWindowEvent {
+            pty_id,
+            window: Some(label.clone()),
+        },
+    );
+    Ok(label)
+}
+
+/// Bring a popped-out session back to the main window and close its window
+#[tauri::command]
+pub async fn dock_session(app: AppHandle, pty_id: u32) -> Result<(), String> {
+    match app.get_webview_window(&label(pty_id)) {
+        // Closing it docks the session
+        Some(window) => {
+            window_state::save(&window.as_ref().window());
+            window
+                .destroy()
+                .map_err(|e| format!("Failed to close the window of PTY {}: {}", pty_id, e))
+        }
+        None => {
+            docked(&app, pty_id);
+            Ok(())
+        }
+    }
+}
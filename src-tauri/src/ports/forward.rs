@@ -0,0 +1,341 @@
+//! Port forwarding into containers
+//!
+//! `forward_port` listens on a host port and relays each connection to a port
+//! inside a container, so a dev server in a devcontainer opens in the host
+//! browser without publishing ports. Connections go straight to the container's
+//! IP when the host can reach it (Linux), and otherwise through `docker exec`
+//! running `socat` or `nc` inside the container (Docker Desktop). A forward
+//! whose container IP can't be reached stops trying it after the first
+//! connection, so later ones don't wait out the timeout.
+
+use crate::docker::DockerState;
+use crate::sessions::{get_issue_session, SessionRegistry};
+use bollard::exec::{CreateExecOptions, StartExecResults};
+use bollard::Docker;
+use futures_util::StreamExt;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// How long a direct connection to the container IP may take before falling
+/// back to `docker exec`
+const DIRECT_CONNECT_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Where forwarded connections go
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(
+    tag = "kind",
+    rename_all = "snake_case",
+    rename_all_fields = "camelCase"
+)]
+pub enum ForwardTarget {
+    Container {
+        container: String,
+    },
+    /// The container recorded for an issue's session
+    Session {
+        repo: String,
+        issue: u64,
+    },
+}
+
+/// Options for `forward_port`
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForwardOptions {
+    /// Host port to listen on; defaults to the remote port when free
+    local_port: Option<u16>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortForward {
+    local_port: u16,
+    remote_port: u16,
+    target: ForwardTarget,
+    container: String,
+}
+
+/// Payload of `port-forward-stopped`
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ForwardStoppedEvent {
+    local_port: u16,
+    error: Option<String>,
+}
+
+struct Forward {
+    info: PortForward,
+    listener: JoinHandle<()>,
+}
+
+/// Active forwards keyed by local port
+#[derive(Default)]
+pub struct ForwardState {
+    forwards: Mutex<HashMap<u16, Forward>>,
+}
+
+async fn container_id(app: &AppHandle, target: &ForwardTarget) -> Result<String, String> {
+    match target {
+        ForwardTarget::Container { container } => Ok(container.clone()),
+        ForwardTarget::Session { repo, issue } => get_issue_session(
+            app.clone(),
+            app.state::<SessionRegistry>(),
+            repo.clone(),
+            *issue,
+        )
+        .await?
+        .and_then(|session| session.container_id)
+        .ok_or_else(|| format!("No container recorded for {}#{}", repo, issue)),
+    }
+}
+
+/// First IP address the container has on any network
+async fn container_ip(docker: &Docker, container: &str) -> Result<Option<IpAddr>, String> {
+    let inspect = docker
+        .inspect_container(container, None)
+        .await
+        .map_err(|e| format!("Failed to inspect container {}: {}", container, e))?;
+    if !inspect
+        .state
+        .and_then(|state| state.running)
+        .unwrap_or(false)
+    {
+        return Err(format!("Container {} is not running", container));
+    }
+    Ok(inspect
+        .network_settings
+        .and_then(|settings| settings.networks)
+        .into_iter()
+        .flat_map(|networks| networks.into_values())
+        .filter_map(|network| network.ip_address)
+        .find_map(|ip| ip.parse().ok()))
+}
+
+/// Relay one connection through a process inside the container
+async fn relay_exec(
+    docker: &Docker,
+    container: &str,
+    remote_port: u16,
+    client: TcpStream,
+) -> Result<(), String> {
+    let script = "if command -v socat >/dev/null 2>&1; then exec socat - TCP:127.0.0.1:$0; \
+                  else exec nc 127.0.0.1 $0; fi";
+    let exec = docker
+        .create_exec(
+            container,
+            CreateExecOptions {
+                cmd: Some(vec![
+                    "sh".to_string(),
+                    "-c".to_string(),
+                    script.to_string(),
+                    remote_port.to_string(),
+                ]),
+                attach_stdin: Some(true),
+                attach_stdout: Some(true),
+                ..Default::default()
+            },
+        )
+        .await
+        .map_err(|e| format!("Failed to create exec: {}", e))?;
+    let StartExecResults::Attached {
+        mut output,
+        mut input,
+    } = docker
+        .start_exec(&exec.id, None)
+        .await
+        .map_err(|e| format!("Failed to start exec: {}", e))?
+    else {
+        return Err("Exec did not attach".to_string());
+    };
+
+    let (mut client_read, mut client_write) = client.into_split();
+    let upstream = async {
+        let mut buf = [0u8; 8192];
+        loop {
+            let n = client_read.read(&mut buf).await?;
+            if n == 0 {
+                break;
+            }
+            input.write_all(&buf[..n]).await?;
+        }
+        input.shutdown().await
+    };
+    let downstream = async {
+        while let Some(chunk) = output.next().await {
+            let Ok(chunk) = chunk else { break };
+            client_write.write_all(&chunk.into_bytes()).await?;
+        }
+        client_write.shutdown().await
+    };
+    let (up, down) = futures_util::future::join(upstream, downstream).await;
+    up.and(down).map_err(|e| format!("Relay failed: {}", e))
+}
+
+async fn relay(
+    docker: Docker,
+    container: String,
+    ip: Option<IpAddr>,
+    direct: Arc<AtomicBool>,
+    remote_port: u16,
+    mut client: TcpStream,
+) {
+    if let Some(ip) = ip.filter(|_| direct.load(Ordering::Relaxed)) {
+        let connect = TcpStream::connect(SocketAddr::new(ip, remote_port));
+        match tokio::time::timeout(DIRECT_CONNECT_TIMEOUT, connect).await {
+            Ok(Ok(mut upstream)) => {
+                let _ = tokio::io::copy_bidirectional(&mut client, &mut upstream).await;
+                return;
+            }
+            // The container answered; nothing is listening on the port yet
+            Ok(Err(e)) if e.kind() == std::io::ErrorKind::ConnectionRefused => {}
+            _ => direct.store(false, Ordering::Relaxed),
+        }
+    }
+    if let Err(e) = relay_exec(&docker, &container, remote_port, client).await {
+        eprintln!("Forward to {}:{} failed: {}", container, remote_port, e);
+    }
+}
+
+async fn bind(local_port: Option<u16>, remote_port: u16) -> Result<TcpListener, String> {
+    let localhost = IpAddr::V4(Ipv4Addr::LOCALHOST);
+    if let Some(port) = local_port {
+        return TcpListener::bind(SocketAddr::new(localhost, port))
+            .await
+            .map_err(|e| format!("Failed to listen on port {}: {}", port, e));
+    }
+    // Same port as the dev server prints, when possible
+    match TcpListener::bind(SocketAddr::new(localhost, remote_port)).await {
+        Ok(listener) => Ok(listener),
+        Err(_) => TcpListener::bind(SocketAddr::new(localhost, 0))
+            .await
+            .map_err(|e| format!("Failed to listen on a local port: {}", e)),
+    }
+}
+
+/// Forward a host port to `remote_port` in a container, returning the host port
+#[tauri::command]
+pub async fn forward_port(
+    app: AppHandle,
+    docker: State<'_, DockerState>,
+    state: State<'_, ForwardState>,
+    target: ForwardTarget,
+    remote_port: u16,
+    options: Option<ForwardOptions>,
+) -> Result<u16, String> {
+    let options = options.unwrap_or_default();
+    let container = container_id(&app, &target).await?;
+
+    // Reuse an existing forward to the same place
+    if let Some(forward) = state.forwards.lock().values().find(|forward| {
+        forward.info.container == container
+            && forward.info.remote_port == remote_port
+            && options
+                .local_port
+                .is_none_or(|port| port == forward.info.local_port)
+    }) {
+        return Ok(forward.info.local_port);
+    }
+
+    let client = docker.client()?;
+    let ip = container_ip(&client, &container).await?;
+    let listener = bind(options.local_port, remote_port).await?;
+    let local_port = listener
+        .local_addr()
+        .map_err(|e| format!("Failed to read local address: {}", e))?
+        .port();
+
+    // Cleared once the container IP turns out to be unreachable
+    let direct = Arc::new(AtomicBool::new(true));
+    let app_clone = app.clone();
+    let container_clone = container.clone();
+    let task = tauri::async_runtime::spawn(async move {
+        let error = loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    tauri::async_runtime::spawn(relay(
+                        client.clone(),
+                        container_clone.clone(),
+                        ip,
+                        direct.clone(),
+                        remote_port,
+                        stream,
+                    ));
+                }
+                Err(e) => break e.to_string(),
+            }
+        };
+        app_clone
+            .state::<ForwardState>()
+            .forwards
+            .lock()
+            .remove(&local_port);
+        let _ = app_clone.emit(
+            "port-forward-stopped",
+            ForwardStoppedEvent {
+                local_port,
+                error: Some(error),
+            },
+        );
+    });
+
+    state.forwards.lock().insert(
+        local_port,
+        Forward {
+            info: PortForward {
+                local_port,
+                remote_port,
+                target,
+                container,
+            },
+            listener: task,
+        },
+    );
+    Ok(local_port)
+}
+
+/// Stop listening on a forwarded port; open connections finish on their own
+#[tauri::command]
+pub async fn stop_port_forward(
+    app: AppHandle,
+    state: State<'_, ForwardState>,
+    local_port: u16,
+) -> Result<(), String> {
+    let forward = state
+        .forwards
+        .lock()
+        .remove(&local_port)
+        .ok_or_else(|| format!("No forward on port {}", local_port))?;
+    forward.listener.abort();
+    let _ = app.emit(
+        "port-forward-stopped",
+        ForwardStoppedEvent {
+            local_port,
+            error: None,
+        },
+    );
+    Ok(())
+}
+
+/// Active port forwards
+#[tauri::command]
+pub async fn list_port_forwards(
+    state: State<'_, ForwardState>,
+) -> Result<Vec<PortForward>, String> {
+    let mut forwards: Vec<PortForward> = state
+        .forwards
+        .lock()
+        .values()
+        .map(|forward| forward.info.clone())
+        .collect();
+    forwards.sort_by_key(|forward| forward.local_port);
+    Ok(forwards)
+}
@@ -0,0 +1,231 @@
+//! Dev-server port detection
+//!
+//! A watcher thread periodically lists listening sockets with `lsof` and the
+//! process tree with `ps`, and attributes each socket to the PTY whose child
+//! it descends from. Ports appearing or disappearing are emitted as
+//! `pty-port-opened` and `pty-port-closed`, so cards can show
+//! `localhost:5173` chips and open previews.
+
+pub mod forward;
+pub mod wait;
+
+use crate::pty::{self, PtyState};
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// How often sockets are listed while PTYs are open
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Protocol {
+    Tcp,
+    Udp,
+}
+
+/// A socket bound by a process in a PTY's tree
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListeningPort {
+    port: u16,
+    protocol: Protocol,
+    /// Bound address, `*` for all interfaces
+    address: String,
+    pid: u32,
+    /// Command name of the owning process
+    process: String,
+}
+
+/// Payload of `pty-port-opened` and `pty-port-closed`
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PortEvent {
+    id: u32,
+    #[serde(flatten)]
+    port: ListeningPort,
+}
+
+/// Ports last seen for each PTY
+#[derive(Default)]
+pub struct PortState {
+    ports: Mutex<HashMap<u32, HashSet<ListeningPort>>>,
+}
+
+/// Parent of every running process
+fn process_parents() -> Result<HashMap<u32, u32>, String> {
+    let output = Command::new("ps")
+        .args(["-A", "-o", "pid=,ppid="])
+        .output()
+        .map_err(|e| format!("Failed to run ps: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "ps failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let pid = fields.next()?.parse().ok()?;
+            let ppid = fields.next()?.parse().ok()?;
+            Some((pid, ppid))
+        })
+        .collect())
+}
+
+/// Split `127.0.0.1:5173`, `[::1]:5173` or `*:5173` into address and port
+fn split_address(name: &str) -> Option<(String, u16)> {
+    let (address, port) = name.rsplit_once(':')?;
+    let address = address.trim_start_matches('[').trim_end_matches(']');
+    Some((address.to_string(), port.parse().ok()?))
+}
+
+/// Listening TCP sockets and bound UDP sockets of all processes
+fn listening_sockets() -> Result<Vec<ListeningPort>, String> {
+    let output = Command::new("lsof")
+        .args(["-nP", "-iTCP", "-sTCP:LISTEN", "-iUDP", "-F", "pcPn"])
+        .output()
+        .map_err(|e| format!("Failed to run lsof: {}", e))?;
+
+    // One `p` line per process, then `f`/`P`/`n` lines per descriptor
+    let mut sockets = Vec::new();
+    let mut pid = 0;
+    let mut process = String::new();
+    let mut protocol = None;
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let (field, value) = line.split_at(line.len().min(1));
+        match field {
+            "p" => pid = value.parse().unwrap_or(0),
+            "c" => process = value.to_string(),
+            "P" => {
+                protocol = match value {
+                    "TCP" => Some(Protocol::Tcp),
+                    "UDP" => Some(Protocol::Udp),
+                    _ => None,
+                }
+            }
+            // Connected UDP sockets are clients, not servers
+            "n" if !value.contains("->") => {
+                if let (Some(protocol), Some((address, port))) = (protocol, split_address(value)) {
+                    sockets.push(ListeningPort {
+                        port,
+                        protocol,
+                        address,
+                        pid,
+                        process: process.clone(),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(sockets)
+}
+
+/// The PTY whose child `pid` is, or descends from
+fn owning_pty(pid: u32, roots: &HashMap<u32, u32>, parents: &HashMap<u32, u32>) -> Option<u32> {
+    let mut current = pid;
+    // Bounded in case of a cycle while processes are being reparented
+    for _ in 0..64 {
+        if let Some(id) = roots.get(&current) {
+            return Some(*id);
+        }
+        match parents.get(&current) {
+            Some(&parent) if parent > 1 && parent != current => current = parent,
+            _ => return None,
+        }
+    }
+    None
+}
+
+/// Current ports of every PTY that has any
+fn scan(app: &AppHandle) -> Result<HashMap<u32, HashSet<ListeningPort>>, String> {
+    let roots: HashMap<u32, u32> = pty::child_pids(&app.state::<PtyState>())
+        .into_iter()
+        .map(|(id, pid)| (pid, id))
+        .collect();
+    if roots.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let sockets = listening_sockets()?;
+    let parents = process_parents()?;
+    let mut ports: HashMap<u32, HashSet<ListeningPort>> = HashMap::new();
+    for socket in sockets {
+        if let Some(id) = owning_pty(socket.pid, &roots, &parents) {
+            ports.entry(id).or_default().insert(socket);
+        }
+    }
+    Ok(ports)
+}
+
+fn emit_changes(
+    app: &AppHandle,
+    previous: &HashMap<u32, HashSet<ListeningPort>>,
+    current: &HashMap<u32, HashSet<ListeningPort>>,
+) {
+    let empty = HashSet::new();
+    for (id, ports) in previous {
+        for port in ports.difference(current.get(id).unwrap_or(&empty)) {
+            let event = PortEvent {
+                id: *id,
+                port: port.clone(),
+            };
+            let _ = app.emit("pty-port-closed", event);
+        }
+    }
+    for (id, ports) in current {
+        for port in ports.difference(previous.get(id).unwrap_or(&empty)) {
+            let event = PortEvent {
+                id: *id,
+                port: port.clone(),
+            };
+            let _ = app.emit("pty-port-opened", event);
+        }
+    }
+}
+
+/// Watch PTY process trees for listening sockets for the lifetime of the app
+pub(crate) fn start_watcher(app: AppHandle) {
+    thread::spawn(move || {
+        // Not every platform ships lsof
+        if let Err(e) = Command::new("lsof").arg("-v").output() {
+            eprintln!("Port detection disabled, lsof unavailable: {}", e);
+            return;
+        }
+        loop {
+            match scan(&app) {
+                Ok(current) => {
+                    let state = app.state::<PortState>();
+                    let mut ports = state.ports.lock();
+                    emit_changes(&app, &ports, &current);
+                    *ports = current;
+                }
+                Err(e) => eprintln!("Port detection failed: {}", e),
+            }
+            thread::sleep(crate::power::stretch(&app, POLL_INTERVAL));
+        }
+    });
+}
+
+/// Ports currently open by a PTY's processes
+#[tauri::command]
+pub async fn list_pty_ports(
+    state: State<'_, PortState>,
+    id: u32,
+) -> Result<Vec<ListeningPort>, String> {
+    let ports = state.ports.lock();
+    let mut list: Vec<ListeningPort> = ports
+        .get(&id)
+        .map(|ports| ports.iter().cloned().collect())
+        .unwrap_or_default();
+    list.sort_by(|a, b| a.port.cmp(&b.port).then(a.pid.cmp(&b.pid)));
+    Ok(list)
+}
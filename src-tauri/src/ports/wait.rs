@@ -0,0 +1,71 @@
+//! Waiting for ports - TCP connect checks, for orchestration steps
+//!
+//! `wait_for_port` holds a step back until something accepts connections,
+//! such as a devcontainer's database before an agent is launched, by
+//! connecting every so often rather than shelling out to `nc`. A port counts
+//! as open once any address the host resolves to accepts a connection.
+
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+
+/// Time one connection attempt may take
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Wait between attempts while the port is closed
+const RETRY_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How long `wait_for_port` waits when not told otherwise
+const DEFAULT_WAIT: Duration = Duration::from_secs(30);
+
+/// Whether something on `host` accepts connections on `port`, each address
+/// getting at most `timeout`
+pub(crate) async fn port_open(host: &str, port: u16, timeout: Duration) -> Result<bool, String> {
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|e| format!("Failed to resolve {}: {}", host, e))?
+        .collect();
+    for addr in addrs {
+        if let Ok(Ok(_)) = tokio::time::timeout(timeout, TcpStream::connect(addr)).await {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Whether a TCP port accepts connections right now
+#[tauri::command]
+pub async fn is_port_open(host: String, port: u16) -> Result<bool, String> {
+    port_open(&host, port, CONNECT_TIMEOUT).await
+}
+
+/// Wait until a TCP port accepts connections, failing after `timeout_secs`
+/// (default 30)
+#[tauri::command]
+pub async fn wait_for_port(
+    host: String,
+    port: u16,
+    timeout_secs: Option<u64>,
+) -> Result<(), String> {
+    let wait = timeout_secs.map_or(DEFAULT_WAIT, Duration::from_secs);
+    let deadline = Instant::now() + wait;
+    loop {
+        let left = deadline.saturating_duration_since(Instant::now());
+        // A name that doesn't resolve yet may be a container still starting
+        if port_open(&host, port, CONNECT_TIMEOUT.min(left))
+            .await
+            .unwrap_or(false)
+        {
+            return Ok(());
+        }
+        if Instant::now() + RETRY_INTERVAL >= deadline {
+            return Err(format!(
+                "Nothing accepted connections on {}:{} within {}s",
+                host,
+                port,
+                wait.as_secs()
+            ));
+        }
+        tokio::time::sleep(RETRY_INTERVAL).await;
+    }
+}
@@ -0,0 +1,208 @@
+//! Power source - easing off background work on battery
+//!
+//! A watcher thread reads the power source every minute, from sysfs on
+//! Linux, `pmset` on macOS and WMI on Windows, and emits `power-changed`
+//! when it switches or the charge moves. While on battery the pollers that
+//! run on their own - issue sync, the job scheduler, port detection and
+//! branch drift - wait `BATTERY_STRETCH` times longer between rounds through
+//! `stretch`. Machines without a battery always count as on AC power.
+
+use parking_lot::Mutex;
+use serde::Serialize;
+#[cfg(any(target_os = "macos", windows))]
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// How often the power source is read
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Factor background polling intervals are multiplied by on battery
+const BATTERY_STRETCH: u32 = 3;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PowerSource {
+    Ac,
+    Battery,
+    /// Not reported by the system
+    #[default]
+    Unknown,
+}
+
+/// Payload of `power-changed`
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PowerStatus {
+    source: PowerSource,
+    /// Charge of the internal battery, if there is one
+    battery_percent: Option<u8>,
+    charging: bool,
+}
+
+impl PowerStatus {
+    fn on_battery(&self) -> bool {
+        self.source == PowerSource::Battery
+    }
+}
+
+/// Last power status read
+#[derive(Default)]
+pub struct PowerState {
+    status: Mutex<PowerStatus>,
+}
+
+#[cfg(target_os = "linux")]
+fn read_status() -> Result<PowerStatus, String> {
+    use std::fs;
+
+    let read = |path: &std::path::Path, name: &str| {
+        fs::read_to_string(path.join(name))
+            .map(|value| value.trim().to_string())
+            .unwrap_or_default()
+    };
+    // Containers and some desktops have no power supply class at all
+    let entries = fs::read_dir("/sys/class/power_supply")
+        .into_iter()
+        .flatten();
+
+    let mut mains_online = false;
+    let mut battery: Option<(Option<u8>, String)> = None;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        match read(&path, "type").as_str() {
+            "Mains" | "USB" => mains_online |= read(&path, "online") == "1",
+            // A scope of `Device` marks the battery of a mouse or headset
+            "Battery" if read(&path, "scope") != "Device" && battery.is_none() => {
+                battery = Some((read(&path, "capacity").parse().ok(), read(&path, "status")));
+            }
+            _ => {}
+        }
+    }
+
+    Ok(match battery {
+        Some((percent, status)) => PowerStatus {
+            source: if !mains_online && status == "Discharging" {
+                PowerSource::Battery
+            } else {
+                PowerSource::Ac
+            },
+            battery_percent: percent,
+            charging: status == "Charging",
+        },
+        None => PowerStatus {
+            source: PowerSource::Ac,
+            ..Default::default()
+        },
+    })
+}
+
+#[cfg(target_os = "macos")]
+fn read_status() -> Result<PowerStatus, String> {
+    // Now drawing from 'Battery Power'
+    //  -InternalBattery-0 (id=1234567)	85%; discharging; 4:37 remaining present: true
+    let output = Command::new("pmset")
+        .args(["-g", "batt"])
+        .output()
+        .map_err(|e| format!("Failed to run pmset: {}", e))?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    let source = if text.contains("'Battery Power'") {
+        PowerSource::Battery
+    } else if text.contains("'AC Power'") {
+        PowerSource::Ac
+    } else {
+        PowerSource::Unknown
+    };
+    let battery = regex::Regex::new(r"(\d+)%;\s*([^;]+);")
+        .expect("valid pattern")
+        .captures(&text)
+        .map(|caps| (caps[1].parse().ok(), caps[2].trim() == "charging"));
+    Ok(PowerStatus {
+        source,
+        battery_percent: battery.and_then(|(percent, _)| percent),
+        charging: battery.is_some_and(|(_, charging)| charging),
+    })
+}
+
+#[cfg(windows)]
+fn read_status() -> Result<PowerStatus, String> {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    // One `<BatteryStatus> <EstimatedChargeRemaining>` line per battery
+    let output = Command::new("powershell")
+        .args([
+            "-NoProfile",
+            "-NonInteractive",
+            "-Command",
+            "Get-CimInstance Win32_Battery | ForEach-Object { \"$($_.BatteryStatus) $($_.EstimatedChargeRemaining)\" }",
+        ])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .map_err(|e| format!("Failed to run powershell: {}", e))?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    let Some(line) = text.lines().find(|line| !line.trim().is_empty()) else {
+        return Ok(PowerStatus {
+            source: PowerSource::Ac,
+            ..Default::default()
+        });
+    };
+    let mut fields = line.split_whitespace();
+    let status: u32 = fields.next().and_then(|s| s.parse().ok()).unwrap_or(0);
+    Ok(PowerStatus {
+        // 1 discharging, 4 low and 5 critical are on battery
+        source: match status {
+            1 | 4 | 5 => PowerSource::Battery,
+            0 => PowerSource::Unknown,
+            _ => PowerSource::Ac,
+        },
+        battery_percent: fields.next().and_then(|s| s.parse().ok()),
+        charging: (6..=9).contains(&status),
+    })
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn read_status() -> Result<PowerStatus, String> {
+    Ok(PowerStatus::default())
+}
+
+/// Read the power source, emitting `power-changed` if it differs from the
+/// last reading
+fn refresh(app: &AppHandle) -> Result<PowerStatus, String> {
+    let status = read_status()?;
+    let state = app.state::<PowerState>();
+    let mut last = state.status.lock();
+    if *last != status {
+        *last = status.clone();
+        let _ = app.emit("power-changed", status.clone());
+    }
+    Ok(status)
+}
+
+/// `interval`, stretched while running on battery
+pub(crate) fn stretch(app: &AppHandle, interval: Duration) -> Duration {
+    if app.state::<PowerState>().status.lock().on_battery() {
+        interval * BATTERY_STRETCH
+    } else {
+        interval
+    }
+}
+
+/// Follow the power source for the lifetime of the app
+pub(crate) fn start_watcher(app: AppHandle) {
+    thread::spawn(move || loop {
+        if let Err(e) = refresh(&app) {
+            eprintln!("Power status unavailable: {}", e);
+        }
+        thread::sleep(POLL_INTERVAL);
+    });
+}
+
+/// Whether the machine runs on AC power or battery, and the battery charge
+#[tauri::command]
+pub async fn get_power_status(app: AppHandle) -> Result<PowerStatus, String> {
+    tauri::async_runtime::spawn_blocking(move || refresh(&app))
+        .await
+        .map_err(|e| e.to_string())?
+}
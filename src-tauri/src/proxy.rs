@@ -0,0 +1,256 @@
+//! Proxies - one HTTP(S) proxy configuration for every outgoing request
+//!
+//! By default requests go wherever the system sends them: through the proxies
+//! in `HTTP_PROXY`, `HTTPS_PROXY` and `ALL_PROXY` (minus the hosts in
+//! `NO_PROXY`), or else those of the macOS or Windows network settings.
+//! `set_proxy_settings` can send them direct or through named proxies
+//! instead. The GitHub client and the connectivity watcher switch over at
+//! once; MCP servers, downloads and update checks on their next connection.
+//! The frontend calls it at startup from the user's settings.
+//!
+//! With `injectEnv` set, new sessions get the proxy variables too, so tools
+//! run in them go the same way. SSH sessions take them from their host
+//! profile's override when it has one, since a remote box often sits behind
+//! a different proxy than the laptop connecting to it.
+
+use crate::github::GitHubState;
+use crate::network;
+use parking_lot::Mutex;
+use reqwest::{ClientBuilder, NoProxy, Proxy};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tauri::{AppHandle, Manager, State, Url};
+
+/// Variables read and set, upper case first as most tools check them
+const HTTP_VARS: &[&str] = &["HTTP_PROXY", "http_proxy"];
+const HTTPS_VARS: &[&str] = &["HTTPS_PROXY", "https_proxy"];
+const ALL_VARS: &[&str] = &["ALL_PROXY", "all_proxy"];
+const NO_PROXY_VARS: &[&str] = &["NO_PROXY", "no_proxy"];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProxyMode {
+    /// The proxy variables, then the OS settings
+    #[default]
+    System,
+    /// No proxy at all
+    Direct,
+    /// The proxies given
+    Manual,
+}
+
+/// Where requests go
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxyConfig {
+    #[serde(default)]
+    mode: ProxyMode,
+    /// Proxy for `http://` URLs, in manual mode
+    #[serde(default)]
+    http: Option<String>,
+    /// Proxy for `https://` URLs, in manual mode; `http` when unset
+    #[serde(default)]
+    https: Option<String>,
+    /// Comma-separated hosts, domains and CIDR ranges reached directly, in
+    /// manual mode
+    #[serde(default)]
+    no_proxy: Option<String>,
+}
+
+/// Options for `set_proxy_settings`
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxySettings {
+    #[serde(flatten)]
+    config: ProxyConfig,
+    /// Set the proxy variables in the environment of new sessions
+    #[serde(default)]
+    inject_env: bool,
+    /// Proxies for sessions on SSH hosts, by profile name
+    #[serde(default)]
+    profiles: HashMap<String, ProxyConfig>,
+}
+
+/// The proxy variables of this process, as found by `get_system_proxy`
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemProxy {
+    http: Option<String>,
+    https: Option<String>,
+    all: Option<String>,
+    no_proxy: Option<String>,
+}
+
+#[derive(Default)]
+pub struct ProxyState {
+    settings: Mutex<ProxySettings>,
+}
+
+fn var(names: &[&str]) -> Option<String> {
+    names
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.trim().is_empty())
+}
+
+impl ProxyConfig {
+    fn https(&self) -> Option<&String> {
+        self.https.as_ref().or(self.http.as_ref())
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.mode != ProxyMode::Manual {
+            return Ok(());
+        }
+        if self.http.is_none() && self.https.is_none() {
+            return Err("Manual proxy mode needs a proxy URL".to_string());
+        }
+        for url in self.http.iter().chain(self.https.iter()) {
+            Proxy::all(url).map_err(|e| format!("Bad proxy '{}': {}", url, e))?;
+        }
+        Ok(())
+    }
+
+    /// Route a client's requests this way
+    fn configure(&self, builder: ClientBuilder) -> ClientBuilder {
+        match self.mode {
+            ProxyMode::System => builder,
+            ProxyMode::Direct => builder.no_proxy(),
+            ProxyMode::Manual => {
+                let no_proxy = self.no_proxy.as_deref().and_then(NoProxy::from_string);
+                let mut builder = builder;
+                if let Some(Ok(proxy)) = self.http.as_deref().map(Proxy::http) {
+                    builder = builder.proxy(proxy.no_proxy(no_proxy.clone()));
+                }
+                if let Some(Ok(proxy)) = self.https().map(Proxy::https) {
+                    builder = builder.proxy(proxy.no_proxy(no_proxy));
+                }
+                builder
+            }
+        }
+    }
+
+    /// The proxy variables for a session's environment
+    fn env(&self) -> Vec<(String, String)> {
+        let (http, https, all, no_proxy) = match self.mode {
+            ProxyMode::System => (
+                var(HTTP_VARS),
+                var(HTTPS_VARS),
+                var(ALL_VARS),
+                var(NO_PROXY_VARS),
+            ),
+            ProxyMode::Direct => return Vec::new(),
+            ProxyMode::Manual => (
+                self.http.clone(),
+                self.https().cloned(),
+                None,
+                self.no_proxy.clone(),
+            ),
+        };
+        [
+            (HTTP_VARS, http),
+            (HTTPS_VARS, https),
+            (ALL_VARS, all),
+            (NO_PROXY_VARS, no_proxy),
+        ]
+        .into_iter()
+        .filter_map(|(names, value)| value.map(|value| (names, value)))
+        .flat_map(|(names, value)| {
+            names
+                .iter()
+                .map(move |name| (name.to_string(), value.clone()))
+        })
+        .collect()
+    }
+}
+
+/// Route a client's requests through the configured proxy
+pub(crate) fn configure(app: &AppHandle, builder: ClientBuilder) -> ClientBuilder {
+    app.state::<ProxyState>()
+        .settings
+        .lock()
+        .config
+        .configure(builder)
+}
+
+/// A client going through the configured proxy
+pub(crate) fn client(app: &AppHandle) -> reqwest::Client {
+    configure(app, reqwest::Client::builder())
+        .build()
+        .unwrap_or_default()
+}
+
+/// Where a client that takes a single proxy URL should send requests
+pub(crate) enum Route {
+    System,
+    Direct,
+    Through(Url),
+}
+
+/// The route for HTTPS requests, for the updater
+pub(crate) fn https_route(app: &AppHandle) -> Route {
+    let state = app.state::<ProxyState>();
+    let settings = state.settings.lock();
+    match settings.config.mode {
+        ProxyMode::System => Route::System,
+        ProxyMode::Direct => Route::Direct,
+        ProxyMode::Manual => settings
+            .config
+            .https()
+            .and_then(|url| Url::parse(url).ok())
+            .map_or(Route::System, Route::Through),
+    }
+}
+
+/// Variables to add to a new session's environment, for the SSH host
+/// profile it runs on if any
+pub(crate) fn session_env(app: &AppHandle, profile: Option<&str>) -> Vec<(String, String)> {
+    let state = app.state::<ProxyState>();
+    let settings = state.settings.lock();
+    if !settings.inject_env {
+        return Vec::new();
+    }
+    profile
+        .and_then(|profile| settings.profiles.get(profile))
+        .unwrap_or(&settings.config)
+        .env()
+}
+
+/// Choose where requests go and what sessions are told
+#[tauri::command]
+pub async fn set_proxy_settings(
+    app: AppHandle,
+    state: State<'_, ProxyState>,
+    settings: ProxySettings,
+) -> Result<(), String> {
+    settings.config.validate()?;
+    for (profile, config) in &settings.profiles {
+        config
+            .validate()
+            .map_err(|e| format!("{} (SSH host '{}')", e, profile))?;
+    }
+    let changed = state.settings.lock().config != settings.config;
+    *state.settings.lock() = settings;
+    if changed {
+        app.state::<GitHubState>().set_http(client(&app));
+        network::reconfigure(&app);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_proxy_settings(state: State<'_, ProxyState>) -> Result<ProxySettings, String> {
+    Ok(state.settings.lock().clone())
+}
+
+/// The proxy variables Antler was started with; the OS settings apply when
+/// there are none
+#[tauri::command]
+pub async fn get_system_proxy() -> Result<SystemProxy, String> {
+    Ok(SystemProxy {
+        http: var(HTTP_VARS),
+        https: var(HTTPS_VARS),
+        all: var(ALL_VARS),
+        no_proxy: var(NO_PROXY_VARS),
+    })
+}
@@ -4,12 +4,14 @@
 //! All business logic remains in TypeScript - this only exposes native PTY capabilities.
 
 use parking_lot::Mutex;
-use portable_pty::{native_pty_system, CommandBuilder, PtyPair, PtySize};
+use portable_pty::{native_pty_system, Child, CommandBuilder, PtyPair, PtySize};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::io::{Read, Write};
 use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
 use std::thread;
+use std::time::{Duration, Instant};
 use tauri::{AppHandle, Emitter, State};
 
 /// State for managing active PTY sessions
@@ -19,11 +21,24 @@ pub struct PtyState {
 }
 
 struct PtySession {
-    #[allow(dead_code)]
-    pair: PtyPair,
-    #[allow(dead_code)]
-    child: Box<dyn portable_pty::Child + Send + Sync>,
+    process: Box<dyn PtyProcess>,
     writer: Mutex<Box<dyn Write + Send>>,
+    /// tmux session this PTY is attached to, if it runs `tmux attach`
+    tmux_session: Option<String>,
+    /// Window the session is popped out into, which alone gets its output
+    owner: Arc<Mutex<Option<String>>>,
+    /// Program the session was started with, empty for the default shell
+    program: String,
+}
+
+/// Summary of a PTY session for the frontend
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PtySessionInfo {
+    id: u32,
+    tmux_session: Option<String>,
+    /// Label of the window the session is popped out into
+    window: Option<String>,
 }
 
 impl Default for PtyState {
@@ -49,6 +64,48 @@ struct PtyExitEvent {
     code: Option<u32>,
 }
 
+/// What runs at the other end of a session's terminal: a child on a native
+/// PTY, or a shell on a remote host
+pub(crate) trait PtyProcess: Send {
+    fn resize(&self, size: PtySize) -> Result<(), String>;
+    fn kill(&mut self);
+    fn has_exited(&mut self) -> bool;
+    /// Local process ID, `None` for remote processes
+    fn process_id(&self) -> Option<u32>;
+}
+
+struct LocalProcess {
+    pair: PtyPair,
+    child: Box<dyn Child + Send + Sync>,
+}
+
+impl PtyProcess for LocalProcess {
+    fn resize(&self, size: PtySize) -> Result<(), String> {
+        self.pair
+            .master
+            .resize(size)
+            .map_err(|e| format!("Failed to resize PTY: {}", e))
+    }
+
+    fn kill(&mut self) {
+        let _ = self.child.kill();
+    }
+
+    fn has_exited(&mut self) -> bool {
+        matches!(self.child.try_wait(), Ok(Some(_)))
+    }
+
+    fn process_id(&self) -> Option<u32> {
+        self.child.process_id()
+    }
+}
+
+/// Receives a PTY's output alongside the `pty-data` events
+pub(crate) trait PtyObserver: Send {
+    fn on_data(&mut self, data: &str);
+    fn on_exit(&mut self);
+}
+
 /// Options for spawning a PTY
 #[derive(Deserialize)]
 pub struct SpawnOptions {
@@ -67,12 +124,34 @@ pub async fn spawn_pty(
     app: AppHandle,
     state: State<'_, PtyState>,
     options: SpawnOptions,
+) -> Result<u32, String> {
+    let mut cmd = CommandBuilder::new(&options.cmd);
+    cmd.args(&options.args);
+    cmd.cwd(&options.cwd);
+
+    // Add environment variables
+    for (key, value) in &options.env {
+        cmd.env(key, value);
+    }
+
+    spawn_session(&app, &state, cmd, options.cols, options.rows, None, None)
+}
+
+/// Open a PTY of the given size, run `cmd` in it and stream its output
+pub(crate) fn spawn_session(
+    app: &AppHandle,
+    state: &PtyState,
+    mut cmd: CommandBuilder,
+    cols: u16,
+    rows: u16,
+    tmux_session: Option<String>,
+    observer: Option<Box<dyn PtyObserver>>,
 ) -> Result<u32, String> {
     let pty_system = native_pty_system();
 
     let size = PtySize {
-        rows: options.rows,
-        cols: options.cols,
+        rows,
+        cols,
         pixel_width: 0,
         pixel_height: 0,
     };
@@ -81,27 +160,26 @@ pub async fn spawn_pty(
         .openpty(size)
         .map_err(|e| format!("Failed to open PTY: {}", e))?;
 
-    let mut cmd = CommandBuilder::new(&options.cmd);
-    cmd.args(&options.args);
-    cmd.cwd(&options.cwd);
-
-    // Add environment variables
-    for (key, value) in &options.env {
-        cmd.env(key, value);
+    for (key, value) in crate::proxy::session_env(app, None) {
+        if cmd.get_env(&key).is_none() {
+            cmd.env(key, value);
+        }
     }
-
     // Set TERM environment variable for proper terminal emulation
     cmd.env("TERM", "xterm-256color");
+    let program = cmd
+        .get_argv()
+        .first()
+        .map(|program| program.to_string_lossy().into_owned())
+        .unwrap_or_default();
 
     let child = pair
         .slave
         .spawn_command(cmd)
         .map_err(|e| format!("Failed to spawn command: {}", e))?;
 
-    let id = state.next_id.fetch_add(1, Ordering::SeqCst);
-
     // Clone reader for the output thread
-    let mut reader = pair
+    let reader = pair
         .master
         .try_clone_reader()
         .map_err(|e| format!("Failed to clone reader: {}", e))?;
@@ -112,55 +190,189 @@ pub async fn spawn_pty(
         .take_writer()
         .map_err(|e| format!("Failed to get writer: {}", e))?;
 
+    let process = Box::new(LocalProcess { pair, child });
+    Ok(start_session(
+        app,
+        state,
+        process,
+        reader,
+        writer,
+        program,
+        tmux_session,
+        observer,
+    ))
+}
+
+/// Register a session whose process is already running and stream its
+/// output, returning the session's ID
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn start_session(
+    app: &AppHandle,
+    state: &PtyState,
+    process: Box<dyn PtyProcess>,
+    mut reader: Box<dyn Read + Send>,
+    writer: Box<dyn Write + Send>,
+    program: String,
+    tmux_session: Option<String>,
+    mut observer: Option<Box<dyn PtyObserver>>,
+) -> u32 {
+    let id = state.next_id.fetch_add(1, Ordering::SeqCst);
+
     // Store the session with the writer
+    let owner = Arc::new(Mutex::new(None));
     {
         let mut sessions = state.sessions.lock();
         sessions.insert(
             id,
             PtySession {
-                pair,
-                child,
+                process,
                 writer: Mutex::new(writer),
+                tmux_session,
+                owner: owner.clone(),
+                program,
             },
         );
     }
 
+    crate::app_menu::refresh_sessions(app);
+
     // Spawn a thread to read PTY output and emit events
     let app_clone = app.clone();
     let id_clone = id;
     thread::spawn(move || {
         let mut buf = [0u8; 4096];
         loop {
-            match reader.read(&mut buf) {
-                Ok(0) => {
-                    // EOF - process exited
-                    let _ = app_clone.emit("pty-exit", PtyExitEvent { id: id_clone, code: None });
-                    break;
-                }
-                Ok(n) => {
-                    // Convert to string (lossy for invalid UTF-8)
-                    let data = String::from_utf8_lossy(&buf[..n]).to_string();
-                    let _ = app_clone.emit("pty-data", PtyDataEvent { id: id_clone, data });
-                }
+            let n = match reader.read(&mut buf) {
+                // EOF - process exited
+                Ok(0) => break,
+                Ok(n) => n,
                 Err(e) => {
                     eprintln!("PTY read error: {}", e);
-                    let _ = app_clone.emit("pty-exit", PtyExitEvent { id: id_clone, code: None });
                     break;
                 }
+            };
+            // Convert to string (lossy for invalid UTF-8)
+            let data = String::from_utf8_lossy(&buf[..n]).to_string();
+            if let Some(observer) = observer.as_mut() {
+                observer.on_data(&data);
             }
+            session_output(&app_clone, id_clone, &data);
+            let event = PtyDataEvent { id: id_clone, data };
+            // A popped-out session's output only goes to its window
+            let _ = match owner.lock().as_deref() {
+                Some(window) => app_clone.emit_to(window, "pty-data", event),
+                None => app_clone.emit("pty-data", event),
+            };
+        }
+        if let Some(observer) = observer.as_mut() {
+            observer.on_exit();
         }
+        session_exited(&app_clone, id_clone);
     });
 
-    Ok(id)
+    id
+}
+
+/// Pass a chunk of a session's output to the features watching sessions
+fn session_output(app: &AppHandle, id: u32, data: &str) {
+    crate::notifications::scan_output(app, id, data);
+    crate::attention::scan_output(app, id, data);
+    crate::server::terminal::mirror_output(app, id, data);
+    crate::server::mcp::record_output(app, id, data);
+}
+
+/// Tell the features watching sessions, the menu and the UI that one exited
+fn session_exited(app: &AppHandle, id: u32) {
+    crate::notifications::forget_output(app, id);
+    crate::attention::forget_session(app, id);
+    crate::server::terminal::mirror_exit(app, id);
+    crate::server::mcp::session_exited(app, id);
+    crate::server::share::session_exited(app, id);
+    crate::app_menu::refresh_sessions(app);
+    let _ = app.emit("pty-exit", PtyExitEvent { id, code: None });
+}
+
+/// Process ID of the PTY's child, used to find its tmux client
+pub(crate) fn child_pid(state: &PtyState, id: u32) -> Result<Option<u32>, String> {
+    let sessions = state.sessions.lock();
+    let session = sessions
+        .get(&id)
+        .ok_or_else(|| format!("PTY session {} not found", id))?;
+
+    Ok(session.process.process_id())
+}
+
+/// Process IDs of every running PTY's child, keyed by PTY ID
+pub(crate) fn child_pids(state: &PtyState) -> HashMap<u32, u32> {
+    let sessions = state.sessions.lock();
+    sessions
+        .iter()
+        .filter_map(|(id, session)| session.process.process_id().map(|pid| (*id, pid)))
+        .collect()
+}
+
+/// tmux session a PTY is attached to, if any
+pub(crate) fn tmux_session(state: &PtyState, id: u32) -> Result<Option<String>, String> {
+    let sessions = state.sessions.lock();
+    let session = sessions
+        .get(&id)
+        .ok_or_else(|| format!("PTY session {} not found", id))?;
+
+    Ok(session.tmux_session.clone())
+}
+
+/// ID and program of every PTY session still running, in the order they
+/// were opened
+pub(crate) fn session_programs(state: &PtyState) -> Vec<(u32, String)> {
+    let mut sessions = state.sessions.lock();
+    let mut list: Vec<(u32, String)> = sessions
+        .iter_mut()
+        .filter_map(|(id, session)| {
+            (!session.process.has_exited()).then(|| (*id, session.program.clone()))
+        })
+        .collect();
+    list.sort_by_key(|(id, _)| *id);
+    list
+}
+
+/// Whether a PTY session exists and its process hasn't exited
+pub(crate) fn is_running(state: &PtyState, id: u32) -> bool {
+    state
+        .sessions
+        .lock()
+        .get_mut(&id)
+        .is_some_and(|session| !session.process.has_exited())
+}
+
+/// Program a PTY was started with, empty for the default shell
+pub(crate) fn program(state: &PtyState, id: u32) -> Result<String, String> {
+    let sessions = state.sessions.lock();
+    let session = sessions
+        .get(&id)
+        .ok_or_else(|| format!("PTY session {} not found", id))?;
+
+    Ok(session.program.clone())
+}
+
+/// Route a PTY's output to one window only, or to every window with `None`
+pub(crate) fn set_owner(state: &PtyState, id: u32, window: Option<String>) -> Result<(), String> {
+    let sessions = state.sessions.lock();
+    let session = sessions
+        .get(&id)
+        .ok_or_else(|| format!("PTY session {} not found", id))?;
+
+    *session.owner.lock() = window;
+    Ok(())
 }
 
 /// Write data to a PTY
 #[tauri::command]
-pub async fn write_pty(
-    state: State<'_, PtyState>,
-    id: u32,
-    data: String,
-) -> Result<(), String> {
+pub async fn write_pty(state: State<'_, PtyState>, id: u32, data: String) -> Result<(), String> {
+    write_session(&state, id, &data)
+}
+
+/// Write to a PTY from Rust, as `write_pty` does for the frontend
+pub(crate) fn write_session(state: &PtyState, id: u32, data: &str) -> Result<(), String> {
     let sessions = state.sessions.lock();
     let session = sessions
         .get(&id)
@@ -193,31 +405,52 @@ pub async fn resize_pty(
         .get(&id)
         .ok_or_else(|| format!("PTY session {} not found", id))?;
 
-    session
-        .pair
-        .master
-        .resize(PtySize {
-            rows,
-            cols,
-            pixel_width: 0,
-            pixel_height: 0,
-        })
-        .map_err(|e| format!("Failed to resize PTY: {}", e))?;
-
-    Ok(())
+    session.process.resize(PtySize {
+        rows,
+        cols,
+        pixel_width: 0,
+        pixel_height: 0,
+    })
 }
 
 /// Kill a PTY process
 #[tauri::command]
 pub async fn kill_pty(state: State<'_, PtyState>, id: u32) -> Result<(), String> {
+    kill_session(&state, id);
+    Ok(())
+}
+
+/// Kill a PTY process from Rust
+pub(crate) fn kill_session(state: &PtyState, id: u32) {
     let mut sessions = state.sessions.lock();
 
     if let Some(mut session) = sessions.remove(&id) {
         // Try to kill the child process
-        let _ = session.child.kill();
+        session.process.kill();
+    }
+}
+
+/// Hang up every PTY and give the processes up to `grace` to exit, as
+/// closing their terminals would
+pub(crate) fn shutdown_all(state: &PtyState, grace: Duration) {
+    let mut sessions: Vec<PtySession> = state
+        .sessions
+        .lock()
+        .drain()
+        .map(|(_, session)| session)
+        .collect();
+    for session in sessions.iter_mut() {
+        session.process.kill();
     }
 
-    Ok(())
+    let deadline = Instant::now() + grace;
+    while Instant::now() < deadline {
+        sessions.retain_mut(|session| !session.process.has_exited());
+        if sessions.is_empty() {
+            break;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
 }
 
 /// Get list of active PTY session IDs
@@ -226,3 +459,21 @@ pub async fn list_pty_sessions(state: State<'_, PtyState>) -> Result<Vec<u32>, S
     let sessions = state.sessions.lock();
     Ok(sessions.keys().copied().collect())
 }
+
+/// Get active PTY sessions with their tmux attachment
+#[tauri::command]
+pub async fn list_pty_session_info(
+    state: State<'_, PtyState>,
+) -> Result<Vec<PtySessionInfo>, String> {
+    let sessions = state.sessions.lock();
+    let mut info: Vec<PtySessionInfo> = sessions
+        .iter()
+        .map(|(id, session)| PtySessionInfo {
+            id: *id,
+            tmux_session: session.tmux_session.clone(),
+            window: session.owner.lock().clone(),
+        })
+        .collect();
+    info.sort_by_key(|session| session.id);
+    Ok(info)
+}
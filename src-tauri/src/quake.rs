@@ -0,0 +1,233 @@
+//! Drop-down terminal - a quake-style window on a global shortcut
+//!
+//! `set_quake_shortcut` registers a system-wide shortcut that toggles a
+//! borderless, always-on-top window sliding in from the top of the screen
+//! the cursor is on. The window shows one PTY session, chosen with
+//! `attach_quake_session`: it is opened at `index.html?window=quake&pty=<id>`
+//! and told about later changes through `quake-session` events. Showing,
+//! hiding, sizing and placement all happen here; the page only renders the
+//! terminal.
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tauri::{
+    AppHandle, Emitter, Manager, PhysicalPosition, PhysicalSize, State, WebviewUrl, WebviewWindow,
+    WebviewWindowBuilder, WindowEvent,
+};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+/// Label of the drop-down window
+pub(crate) const WINDOW_LABEL: &str = "quake";
+
+/// Share of the screen's height the window takes when not configured
+const DEFAULT_HEIGHT: f64 = 0.4;
+
+/// State for the drop-down terminal
+#[derive(Default)]
+pub struct QuakeState {
+    shortcut: Mutex<Option<Shortcut>>,
+    config: Mutex<QuakeConfig>,
+}
+
+/// How the drop-down window looks and behaves
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct QuakeConfig {
+    /// PTY session the window shows
+    pty_id: Option<u32>,
+    /// Share of the screen's height, between 0.1 and 1
+    height: f64,
+    /// Hide the window when it loses focus
+    hide_on_blur: bool,
+}
+
+impl Default for QuakeConfig {
+    fn default() -> Self {
+        Self {
+            pty_id: None,
+            height: DEFAULT_HEIGHT,
+            hide_on_blur: true,
+        }
+    }
+}
+
+/// Payload of `quake-session`
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct QuakeSessionEvent {
+    pty_id: Option<u32>,
+}
+
+fn page_url(pty_id: Option<u32>) -> WebviewUrl {
+    let path = match pty_id {
+        Some(id) => format!("index.html?window=quake&pty={}", id),
+        None => "index.html?window=quake".to_string(),
+    };
+    WebviewUrl::App(path.into())
+}
+
+/// The drop-down window, created hidden the first time it is needed
+fn window(app: &AppHandle) -> Result<WebviewWindow, String> {
+    if let Some(window) = app.get_webview_window(WINDOW_LABEL) {
+        return Ok(window);
+    }
+    let pty_id = app.state::<QuakeState>().config.lock().pty_id;
+    let window = WebviewWindowBuilder::new(app, WINDOW_LABEL, page_url(pty_id))
+        .title("Antler")
+        .decorations(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .resizable(false)
+        .visible(false)
+        .build()
+        .map_err(|e| format!("Failed to open the drop-down terminal: {}", e))?;
+    // Windows and Linux give every window the app menu
+    #[cfg(not(target_os = "macos"))]
+    let _ = window.remove_menu();
+
+    let handle = app.clone();
+    window.on_window_event(move |event| {
+        if let WindowEvent::Focused(false) = event {
+            let hide = handle.state::<QuakeState>().config.lock().hide_on_blur;
+            if hide {
+                if let Some(window) = handle.get_webview_window(WINDOW_LABEL) {
+                    let _ = window.hide();
+                }
+            }
+        }
+    });
+    Ok(window)
+}
+
+/// Stretch the window across the top of the screen under the cursor
+fn place(app: &AppHandle, window: &WebviewWindow) -> Result<(), String> {
+    let monitor = app
+        .cursor_position()
+        .ok()
+        .and_then(|cursor| app.monitor_from_point(cursor.x, cursor.y).ok().flatten())
+        .or_else(|| app.primary_monitor().ok().flatten())
+        .ok_or_else(|| "No screen to show the drop-down terminal on".to_string())?;
+    let area = monitor.work_area();
+    let share = app
+        .state::<QuakeState>()
+        .config
+        .lock()
+        .height
+        .clamp(0.1, 1.0);
+    let height = (f64::from(area.size.height) * share).round() as u32;
+
+    window
+        .set_size(PhysicalSize::new(area.size.width, height))
+        .and_then(|_| window.set_position(PhysicalPosition::new(area.position.x, area.position.y)))
+        .map_err(|e| format!("Failed to place the drop-down terminal: {}", e))
+}
+
+/// Show the drop-down window if it is hidden or in the background, and
+/// hide it otherwise
+pub(crate) fn toggle(app: &AppHandle) -> Result<(), String> {
+    let window = window(app)?;
+    let visible = window.is_visible().unwrap_or(false);
+    let focused = window.is_focused().unwrap_or(false);
+    if visible && focused {
+        return window
+            .hide()
+            .map_err(|e| format!("Failed to hide the drop-down terminal: {}", e));
+    }
+    place(app, &window)?;
+    window
+        .show()
+        .and_then(|_| window.set_focus())
+        .map_err(|e| format!("Failed to show the drop-down terminal: {}", e))
+}
+
+/// Register `shortcut` (such as `"Alt+Space"`) to toggle the drop-down
+/// terminal, replacing the previous one; `None` removes it
+#[tauri::command]
+pub async fn set_quake_shortcut(
+    app: AppHandle,
+    state: State<'_, QuakeState>,
+    shortcut: Option<String>,
+) -> Result<(), String> {
+    let shortcut = shortcut
+        .map(|text| {
+            text.parse::<Shortcut>()
+                .map_err(|e| format!("Invalid shortcut '{}': {}", text, e))
+        })
+        .transpose()?;
+
+    let mut current = state.shortcut.lock();
+    if let Some(previous) = current.take() {
+        let _ = app.global_shortcut().unregister(previous);
+    }
+    if let Some(shortcut) = shortcut {
+        app.global_shortcut()
+            .on_shortcut(shortcut, |app, _, event| {
+                if event.state == ShortcutState::Pressed {
+                    if let Err(e) = toggle(app) {
+                        eprintln!("{}", e);
+                    }
+                }
+            })
+            .map_err(|e| format!("Failed to register the shortcut: {}", e))?;
+        *current = Some(shortcut);
+    }
+    Ok(())
+}
+
+/// Change the drop-down window's session, height and whether it hides on
+/// blur
+#[tauri::command]
+pub async fn set_quake_config(
+    app: AppHandle,
+    state: State<'_, QuakeState>,
+    config: QuakeConfig,
+) -> Result<(), String> {
+    let pty_changed = {
+        let mut current = state.config.lock();
+        let pty_changed = current.pty_id != config.pty_id;
+        *current = config;
+        pty_changed
+    };
+    if pty_changed {
+        notify_session(&app, &state);
+    }
+    if let Some(window) = app.get_webview_window(WINDOW_LABEL) {
+        if window.is_visible().unwrap_or(false) {
+            place(&app, &window)?;
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_quake_config(state: State<'_, QuakeState>) -> Result<QuakeConfig, String> {
+    Ok(state.config.lock().clone())
+}
+
+/// PTY session the drop-down window shows
+pub(crate) fn attached_session(app: &AppHandle) -> Option<u32> {
+    app.state::<QuakeState>().config.lock().pty_id
+}
+
+fn notify_session(app: &AppHandle, state: &QuakeState) {
+    let pty_id = state.config.lock().pty_id;
+    let _ = app.emit_to(WINDOW_LABEL, "quake-session", QuakeSessionEvent { pty_id });
+}
+
+/// Show PTY session `ptyId` in the drop-down terminal
+#[tauri::command]
+pub async fn attach_quake_session(
+    app: AppHandle,
+    state: State<'_, QuakeState>,
+    pty_id: u32,
+) -> Result<(), String> {
+    state.config.lock().pty_id = Some(pty_id);
+    notify_session(&app, &state);
+    Ok(())
+}
+
+/// Show or hide the drop-down terminal, as the shortcut does
+#[tauri::command]
+pub async fn toggle_quake_window(app: AppHandle) -> Result<(), String> {
+    toggle(&app)
+}
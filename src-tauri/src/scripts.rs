@@ -0,0 +1,317 @@
+//! Project scripts - package.json scripts, justfile recipes and Makefile targets
+//!
+//! `list_project_scripts` reads the files directly rather than asking each
+//! tool, so listing works before dependencies are installed. Short scripts run
+//! to completion with their output captured; long-running ones (dev servers,
+//! watchers) get a PTY so they can be watched and stopped like any terminal.
+
+use crate::pty::{spawn_session, PtyState};
+use portable_pty::CommandBuilder;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::Instant;
+use tauri::{AppHandle, State};
+
+/// Script names that usually keep running until stopped
+const LONG_RUNNING: [&str; 5] = ["dev", "start", "serve", "watch", "preview"];
+
+const JUSTFILE_NAMES: [&str; 3] = ["justfile", "Justfile", ".justfile"];
+
+const MAKEFILE_NAMES: [&str; 3] = ["GNUmakefile", "makefile", "Makefile"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScriptSource {
+    Package,
+    Just,
+    Make,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectScript {
+    name: String,
+    source: ScriptSource,
+    /// What the script runs, when the file says (package.json bodies)
+    command: Option<String>,
+    /// Whether `run_project_script` gives it a PTY by default
+    long_running: bool,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunMode {
+    /// PTY for long-running scripts, captured otherwise
+    #[default]
+    Auto,
+    Capture,
+    Pty,
+}
+
+/// Options for `run_project_script`
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunScriptOptions {
+    /// Needed when several sources define the same name
+    source: Option<ScriptSource>,
+    #[serde(default)]
+    mode: RunMode,
+    /// Extra arguments passed to the script
+    #[serde(default)]
+    args: Vec<String>,
+    cols: Option<u16>,
+    rows: Option<u16>,
+}
+
+/// Result of `run_project_script`
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptRun {
+    name: String,
+    source: ScriptSource,
+    /// Program and arguments that were run
+    command: Vec<String>,
+    /// PTY running the script, when it was not captured
+    pty_id: Option<u32>,
+    /// Exit code of a captured run
+    code: Option<i32>,
+    success: Option<bool>,
+    stdout: String,
+    stderr: String,
+    duration_ms: u64,
+}
+
+#[derive(Deserialize)]
+struct PackageJson {
+    #[serde(default)]
+    scripts: serde_json::Map<String, serde_json::Value>,
+}
+
+fn is_long_running(name: &str) -> bool {
+    LONG_RUNNING
+        .iter()
+        .any(|long| name == *long || name.starts_with(&format!("{}:", long)))
+}
+
+fn package_scripts(root: &Path) -> Vec<ProjectScript> {
+    let Ok(text) = std::fs::read_to_string(root.join("package.json")) else {
+        return Vec::new();
+    };
+    let Ok(package) = serde_json::from_str::<PackageJson>(&text) else {
+        return Vec::new();
+    };
+    package
+        .scripts
+        .into_iter()
+        .map(|(name, body)| ProjectScript {
+            long_running: is_long_running(&name),
+            command: body.as_str().map(str::to_string),
+            name,
+            source: ScriptSource::Package,
+        })
+        .collect()
+}
+
+fn read_first(root: &Path, names: &[&str]) -> Option<String> {
+    names
+        .iter()
+        .find_map(|name| std::fs::read_to_string(root.join(name)).ok())
+}
+
+/// Recipe names from a justfile, skipping private `_recipes` and settings
+fn just_recipes(root: &Path) -> Vec<ProjectScript> {
+    let Some(text) = read_first(root, &JUSTFILE_NAMES) else {
+        return Vec::new();
+    };
+    text.lines()
+        .filter(|line| !line.starts_with([' ', '\t', '#']))
+        .filter_map(|line| {
+            let (head, _) = line.split_once(':')?;
+            // `name := value` is an assignment
+            if line[head.len()..].starts_with(":=") {
+                return None;
+            }
+            let name = head.trim_start_matches('@').split_whitespace().next()?;
+            let valid = name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+            (valid
+                && !name.starts_with('_')
+                && !["set", "alias", "export", "import", "mod"].contains(&name))
+            .then(|| ProjectScript {
+                name: name.to_string(),
+                source: ScriptSource::Just,
+                command: None,
+                long_running: is_long_running(name),
+            })
+        })
+        .collect()
+}
+
+/// Explicit Makefile targets, skipping special, pattern and variable ones
+fn make_targets(root: &Path) -> Vec<ProjectScript> {
+    let Some(text) = read_first(root, &MAKEFILE_NAMES) else {
+        return Vec::new();
+    };
+    let mut targets: Vec<ProjectScript> = Vec::new();
+    for line in text.lines() {
+        if line.starts_with(['\t', ' ', '#', '.']) {
+            continue;
+        }
+        let Some((head, rest)) = line.split_once(':') else {
+            continue;
+        };
+        if rest.starts_with('=') || rest.starts_with(":=") || head.contains(['=', '%', '$']) {
+            continue;
+        }
+        for name in head.split_whitespace() {
+            if !targets.iter().any(|target| target.name == name) {
+                targets.push(ProjectScript {
+                    name: name.to_string(),
+                    source: ScriptSource::Make,
+                    command: None,
+                    long_running: is_long_running(name),
+                });
+            }
+        }
+    }
+    targets
+}
+
+/// Package manager the project's lockfile belongs to
+fn package_manager(root: &Path) -> &'static str {
+    if root.join("pnpm-lock.yaml").exists() {
+        "pnpm"
+    } else if root.join("yarn.lock").exists() {
+        "yarn"
+    } else if root.join("bun.lockb").exists() || root.join("bun.lock").exists() {
+        "bun"
+    } else {
+        "npm"
+    }
+}
+
+fn script_command(root: &Path, source: ScriptSource, name: &str, args: &[String]) -> Vec<String> {
+    let mut command: Vec<String> = match source {
+        ScriptSource::Package => {
+            let manager = package_manager(root);
+            let mut command = vec![manager.to_string(), "run".to_string(), name.to_string()];
+            // npm only forwards arguments after `--`
+            if manager == "npm" && !args.is_empty() {
+                command.push("--".to_string());
+            }
+            command
+        }
+        ScriptSource::Just => vec!["just".to_string(), name.to_string()],
+        ScriptSource::Make => vec!["make".to_string(), name.to_string()],
+    };
+    command.extend(args.iter().cloned());
+    // npm, pnpm and yarn are `.cmd` shims on Windows, which only cmd can run
+    if cfg!(windows) && source == ScriptSource::Package {
+        command.splice(0..0, ["cmd".to_string(), "/C".to_string()]);
+    }
+    command
+}
+
+/// Scripts defined by package.json, a justfile and a Makefile in `path`
+#[tauri::command]
+pub async fn list_project_scripts(path: String) -> Result<Vec<ProjectScript>, String> {
+    let root = Path::new(&path);
+    if !root.is_dir() {
+        return Err(format!("{} is not a directory", path));
+    }
+    let mut scripts = package_scripts(root);
+    scripts.extend(just_recipes(root));
+    scripts.extend(make_targets(root));
+    Ok(scripts)
+}
+
+/// Run a project script, in a PTY or to completion depending on `mode`
+#[tauri::command]
+pub async fn run_project_script(
+    app: AppHandle,
+    state: State<'_, PtyState>,
+    path: String,
+    name: String,
+    options: Option<RunScriptOptions>,
+) -> Result<ScriptRun, String> {
+    let options = options.unwrap_or_default();
+    let scripts = list_project_scripts(path.clone()).await?;
+    let matching: Vec<&ProjectScript> = scripts
+        .iter()
+        .filter(|script| {
+            script.name == name && options.source.is_none_or(|source| source == script.source)
+        })
+        .collect();
+    let script = match matching.as_slice() {
+        [] => return Err(format!("No script named '{}' in {}", name, path)),
+        [script] => *script,
+        _ => {
+            return Err(format!(
+                "'{}' is defined by several files; pass a source",
+                name
+            ))
+        }
+    };
+
+    let root = Path::new(&path);
+    let command = script_command(root, script.source, &name, &options.args);
+    let pty = match options.mode {
+        RunMode::Auto => script.long_running,
+        RunMode::Capture => false,
+        RunMode::Pty => true,
+    };
+    let started = Instant::now();
+
+    if pty {
+        let mut cmd = CommandBuilder::new(&command[0]);
+        cmd.args(&command[1..]);
+        cmd.cwd(&path);
+        let id = spawn_session(
+            &app,
+            &state,
+            cmd,
+            options.cols.unwrap_or(80),
+            options.rows.unwrap_or(24),
+            None,
+            None,
+        )?;
+        return Ok(ScriptRun {
+            name,
+            source: script.source,
+            command,
+            pty_id: Some(id),
+            code: None,
+            success: None,
+            stdout: String::new(),
+            stderr: String::new(),
+            duration_ms: 0,
+        });
+    }
+
+    // A build can take minutes; it shouldn't hold up other commands
+    let (program, args) = (command[0].clone(), command[1..].to_vec());
+    let output = tauri::async_runtime::spawn_blocking(move || {
+        Command::new(&program)
+            .args(&args)
+            .current_dir(&path)
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|e| format!("Failed to run {}: {}", program, e))
+    })
+    .await
+    .map_err(|e| format!("Script failed: {}", e))??;
+
+    Ok(ScriptRun {
+        name,
+        source: script.source,
+        command,
+        pty_id: None,
+        code: output.status.code(),
+        success: Some(output.status.success()),
+        stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        duration_ms: started.elapsed().as_millis() as u64,
+    })
+}
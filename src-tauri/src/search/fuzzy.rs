@@ -0,0 +1,239 @@
+//! Fuzzy file finder
+//!
+//! `index_project` walks the project once, with the same `.gitignore` rules
+//! as `grep_project`, and keeps the file list in memory; a watcher on the
+//! root adds and removes entries as files come and go, so the index stays
+//! current without rescanning. `fuzzy_find` scores the cached list with the
+//! nucleo matcher, fast enough to run on every keystroke of a file picker.
+
+use ignore::WalkBuilder;
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use nucleo_matcher::pattern::{CaseMatching, Normalization, Pattern};
+use nucleo_matcher::{Config, Matcher, Utf32Str};
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, State};
+
+/// How long to wait for more filesystem events before updating the index
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// Results returned when the caller sets no limit
+const DEFAULT_LIMIT: usize = 50;
+
+/// The indexed project and a matcher reused across queries
+pub struct FuzzyState {
+    index: Mutex<Option<FileIndex>>,
+    matcher: Mutex<Matcher>,
+}
+
+impl Default for FuzzyState {
+    fn default() -> Self {
+        Self {
+            index: Mutex::new(None),
+            matcher: Mutex::new(Matcher::new(Config::DEFAULT.match_paths())),
+        }
+    }
+}
+
+struct FileIndex {
+    /// Paths relative to the indexed root, `/`-separated
+    files: Arc<Mutex<BTreeSet<String>>>,
+    // Dropping the watcher ends its update thread
+    _watcher: RecommendedWatcher,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FuzzyMatch {
+    /// Relative to the indexed root
+    path: String,
+    score: u32,
+    /// Character positions in `path` that matched, for highlighting
+    indices: Vec<u32>,
+}
+
+/// Event payload sent after the watcher changed the index
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct IndexUpdatedEvent {
+    root: String,
+    files: usize,
+}
+
+fn relative(root: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(root).ok()?;
+    let parts: Vec<_> = relative
+        .components()
+        .map(|part| part.as_os_str().to_string_lossy())
+        .collect();
+    (!parts.is_empty()).then(|| parts.join("/"))
+}
+
+/// Add the files under `dir`, `depth` levels deep at most
+fn walk(root: &Path, dir: &Path, depth: Option<usize>, files: &mut BTreeSet<String>) {
+    for entry in WalkBuilder::new(dir).max_depth(depth).build().flatten() {
+        if entry.file_type().is_some_and(|kind| kind.is_file()) {
+            if let Some(path) = relative(root, entry.path()) {
+                files.insert(path);
+            }
+        }
+    }
+}
+
+/// Bring the index in line with the current state of `path`
+fn refresh(root: &Path, path: &Path, files: &mut BTreeSet<String>) {
+    let Some(key) = relative(root, path) else {
+        return;
+    };
+    let prefix = format!("{}/", key);
+    files.remove(&key);
+    files.retain(|file| !file.starts_with(&prefix));
+
+    if path.is_dir() {
+        walk(root, path, None, files);
+    } else if path.is_file() {
+        // Walking the parent applies its ignore rules to the file
+        if let Some(parent) = path.parent() {
+            let mut siblings = BTreeSet::new();
+            walk(root, parent, Some(1), &mut siblings);
+            if siblings.contains(&key) {
+                files.insert(key);
+            }
+        }
+    }
+}
+
+fn watch(
+    app: AppHandle,
+    root: PathBuf,
+    files: Arc<Mutex<BTreeSet<String>>>,
+) -> Result<RecommendedWatcher, String> {
+    let (tx, rx) = mpsc::channel::<notify::Result<Event>>();
+    let mut watcher = notify::recommended_watcher(move |event| {
+        let _ = tx.send(event);
+    })
+    .map_err(|e| format!("Failed to create watcher: {}", e))?;
+    watcher
+        .watch(&root, RecursiveMode::Recursive)
+        .map_err(|e| format!("Failed to watch {}: {}", root.display(), e))?;
+
+    thread::spawn(move || {
+        // recv fails once the watcher (and with it the sender) is dropped
+        while let Ok(first) = rx.recv() {
+            let mut changed = HashSet::new();
+            let mut add = |event: notify::Result<Event>| {
+                if let Ok(event) = event {
+                    changed.extend(event.paths);
+                }
+            };
+            add(first);
+            while let Ok(next) = rx.recv_timeout(DEBOUNCE) {
+                add(next);
+            }
+
+            let mut files = files.lock();
+            let before = files.len();
+            for path in changed {
+                if path.components().any(|part| part.as_os_str() == ".git") {
+                    continue;
+                }
+                refresh(&root, &path, &mut files);
+            }
+            if files.len() != before {
+                let _ = app.emit(
+                    "project-index-updated",
+                    IndexUpdatedEvent {
+                        root: root.to_string_lossy().into_owned(),
+                        files: files.len(),
+                    },
+                );
+            }
+        }
+    });
+
+    Ok(watcher)
+}
+
+/// Index the files under `root` for `fuzzy_find`, replacing any previous
+/// index, and return how many were found
+#[tauri::command]
+pub async fn index_project(
+    app: AppHandle,
+    state: State<'_, FuzzyState>,
+    root: String,
+) -> Result<usize, String> {
+    let root = PathBuf::from(&root);
+    if !root.is_dir() {
+        return Err(format!("{} is not a directory", root.display()));
+    }
+
+    let walk_root = root.clone();
+    let files = tauri::async_runtime::spawn_blocking(move || {
+        let mut files = BTreeSet::new();
+        walk(&walk_root, &walk_root, None, &mut files);
+        files
+    })
+    .await
+    .map_err(|e| format!("Indexing failed: {}", e))?;
+
+    let count = files.len();
+    let files = Arc::new(Mutex::new(files));
+    let watcher = watch(app, root, files.clone())?;
+    *state.index.lock() = Some(FileIndex {
+        files,
+        _watcher: watcher,
+    });
+    Ok(count)
+}
+
+/// Best matches for `query` among the indexed files, best first
+#[tauri::command]
+pub async fn fuzzy_find(
+    state: State<'_, FuzzyState>,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<FuzzyMatch>, String> {
+    let index = state.index.lock();
+    let index = index
+        .as_ref()
+        .ok_or_else(|| "No project indexed. Call index_project first".to_string())?;
+    let files = index.files.lock();
+    let mut matcher = state.matcher.lock();
+    let pattern = Pattern::parse(&query, CaseMatching::Smart, Normalization::Smart);
+    let limit = limit.unwrap_or(DEFAULT_LIMIT);
+
+    let mut buf = Vec::new();
+    let mut scored: Vec<(&String, u32)> = files
+        .iter()
+        .filter_map(|file| {
+            pattern
+                .score(Utf32Str::new(file, &mut buf), &mut matcher)
+                .map(|score| (file, score))
+        })
+        .collect();
+    // Prefer shorter paths among equal scores
+    scored.sort_by_key(|(file, score)| (Reverse(*score), file.len()));
+    scored.truncate(limit);
+
+    Ok(scored
+        .into_iter()
+        .map(|(file, score)| {
+            let mut indices = Vec::new();
+            pattern.indices(Utf32Str::new(file, &mut buf), &mut matcher, &mut indices);
+            indices.sort_unstable();
+            indices.dedup();
+            FuzzyMatch {
+                path: file.clone(),
+                score,
+                indices,
+            }
+        })
+        .collect())
+}
@@ -0,0 +1,532 @@
+//! Full-text content index
+//!
+//! `index_project_content` builds a tantivy index over the project's text
+//! files, with the same `.gitignore` rules as `grep_project`, and
+//! `query_index` ranks files against a free-form query - "where we discussed
+//! rate limiting" - faster than a regex search over a big monorepo. The index
+//! lives in the app cache directory, so a later run only re-reads files whose
+//! modification time changed, and a watcher on the root keeps it current
+//! while the app runs. Builds without the `content-index` feature leave it
+//! out.
+
+use crate::files::inspect::looks_binary;
+use ignore::WalkBuilder;
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant, UNIX_EPOCH};
+use tantivy::collector::TopDocs;
+use tantivy::directory::MmapDirectory;
+use tantivy::query::QueryParser;
+use tantivy::schema::{Field, Schema, Value, STORED, STRING, TEXT};
+use tantivy::{
+    doc, Index, IndexReader, IndexWriter, ReloadPolicy, SnippetGenerator, TantivyDocument, Term,
+};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// Directory under the app cache dir holding one index per project
+const INDEX_DIR: &str = "content-index";
+
+/// Memory the writer may use before flushing a segment
+const WRITER_MEMORY: usize = 50_000_000;
+
+/// Larger files are left out; they are rarely prose or hand-written code
+const MAX_FILE_SIZE: u64 = 1024 * 1024;
+
+/// How long to wait for more filesystem events before updating the index
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Results returned when the caller sets no limit
+const DEFAULT_LIMIT: usize = 20;
+
+/// Characters of context returned around the best match in each file
+const SNIPPET_CHARS: usize = 200;
+
+/// The indexed project, if any
+#[derive(Default)]
+pub struct ContentIndexState {
+    project: Mutex<Option<IndexedProject>>,
+}
+
+struct IndexedProject {
+    index: Arc<ContentIndex>,
+    // Dropping the watcher ends its update thread
+    _watcher: RecommendedWatcher,
+}
+
+#[derive(Clone, Copy)]
+struct Fields {
+    path: Field,
+    modified: Field,
+    body: Field,
+}
+
+struct ContentIndex {
+    root: PathBuf,
+    index: Index,
+    reader: IndexReader,
+    fields: Fields,
+    writer: Mutex<Writer>,
+}
+
+/// The writer and what it has indexed, changed together
+struct Writer {
+    writer: IndexWriter,
+    /// Modification time of each indexed file, keyed by relative path
+    docs: BTreeMap<String, u64>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexHit {
+    /// Relative to the indexed root
+    path: String,
+    score: f32,
+    /// The best matching passage
+    snippet: String,
+    /// Character ranges in `snippet` that matched, for highlighting
+    highlights: Vec<[usize; 2]>,
+}
+
+/// Event payload for progress through the initial scan
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct IndexProgressEvent {
+    root: String,
+    scanned: u64,
+    indexed: u64,
+}
+
+/// Event payload for a finished scan or watcher update
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct IndexReadyEvent {
+    root: String,
+    files: usize,
+    error: Option<String>,
+}
+
+fn schema() -> (Schema, Fields) {
+    let mut builder = Schema::builder();
+    let fields = Fields {
+        path: builder.add_text_field("path", STRING | STORED),
+        modified: builder.add_u64_field("modified", STORED),
+        body: builder.add_text_field("body", TEXT),
+    };
+    (builder.build(), fields)
+}
+
+fn relative(root: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(root).ok()?;
+    let parts: Vec<_> = relative
+        .components()
+        .map(|part| part.as_os_str().to_string_lossy())
+        .collect();
+    (!parts.is_empty()).then(|| parts.join("/"))
+}
+
+fn modified(meta: &fs::Metadata) -> u64 {
+    meta.modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+/// The text of `path`, unless it is too large or binary
+fn read_text(path: &Path, meta: &fs::Metadata) -> Option<String> {
+    if meta.len() > MAX_FILE_SIZE {
+        return None;
+    }
+    let data = fs::read(path).ok()?;
+    if looks_binary(&data) {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&data).into_owned())
+}
+
+impl ContentIndex {
+    /// Open the index for `root` under `dir`, starting over if it is unreadable
+    fn open(root: PathBuf, dir: &Path) -> Result<Self, String> {
+        let (schema, fields) = schema();
+        let open = || {
+            fs::create_dir_all(dir)?;
+            let directory = MmapDirectory::open(dir)?;
+            Index::open_or_create(directory, schema.clone())
+        };
+        let index = match open() {
+            Ok(index) => index,
+            Err(_) => {
+                // Most likely written with an older schema
+                let _ = fs::remove_dir_all(dir);
+                open().map_err(|e| format!("Failed to open content index: {}", e))?
+            }
+        };
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::OnCommitWithDelay)
+            .try_into()
+            .map_err(|e| format!("Failed to open content index: {}", e))?;
+        let writer = index
+            .writer(WRITER_MEMORY)
+            .map_err(|e| format!("Failed to open content index: {}", e))?;
+
+        // What a previous run indexed, to skip files that haven't changed
+        let mut docs = BTreeMap::new();
+        let searcher = reader.searcher();
+        for segment in searcher.segment_readers() {
+            let store = segment
+                .get_store_reader(0)
+                .map_err(|e| format!("Failed to read content index: {}", e))?;
+            for doc_id in segment.doc_ids_alive() {
+                let Ok(doc) = store.get::<TantivyDocument>(doc_id) else {
+                    continue;
+                };
+                let path = doc.get_first(fields.path).and_then(|value| value.as_str());
+                let modified = doc
+                    .get_first(fields.modified)
+                    .and_then(|value| value.as_u64());
+                if let (Some(path), Some(modified)) = (path, modified) {
+                    docs.insert(path.to_string(), modified);
+                }
+            }
+        }
+
+        Ok(Self {
+            root,
+            index,
+            reader,
+            fields,
+            writer: Mutex::new(Writer { writer, docs }),
+        })
+    }
+
+    /// Index the file at `path` if it changed, returning whether it did
+    fn update(&self, writer: &mut Writer, key: &str, path: &Path) -> bool {
+        let Ok(meta) = fs::metadata(path) else {
+            return self.remove(writer, key);
+        };
+        let modified = modified(&meta);
+        if writer.docs.get(key) == Some(&modified) {
+            return false;
+        }
+        let Some(text) = read_text(path, &meta) else {
+            return self.remove(writer, key);
+        };
+        let fields = self.fields;
+        writer
+            .writer
+            .delete_term(Term::from_field_text(fields.path, key));
+        let added = writer.writer.add_document(doc!(
+            fields.path => key,
+            fields.modified => modified,
+            fields.body => text,
+        ));
+        if added.is_ok() {
+            writer.docs.insert(key.to_string(), modified);
+        }
+        true
+    }
+
+    fn remove(&self, writer: &mut Writer, key: &str) -> bool {
+        if writer.docs.remove(key).is_none() {
+            return false;
+        }
+        writer
+            .writer
+            .delete_term(Term::from_field_text(self.fields.path, key));
+        true
+    }
+
+    /// Drop `key` and anything indexed under it as a directory
+    fn remove_tree(&self, writer: &mut Writer, key: &str) -> bool {
+        let prefix = format!("{}/", key);
+        let nested: Vec<String> = writer
+            .docs
+            .range(prefix.clone()..)
+            .map(|(path, _)| path)
+            .take_while(|path| path.starts_with(&prefix))
+            .cloned()
+            .collect();
+        let mut changed = self.remove(writer, key);
+        for path in nested {
+            changed |= self.remove(writer, &path);
+        }
+        changed
+    }
+
+    fn commit(&self, writer: &mut Writer) -> Result<(), String> {
+        writer
+            .writer
+            .commit()
+            .map_err(|e| format!("Failed to update content index: {}", e))?;
+        self.reader
+            .reload()
+            .map_err(|e| format!("Failed to update content index: {}", e))
+    }
+
+    /// Bring the whole index in line with the files under the root
+    fn sync(&self, app: &AppHandle) -> Result<usize, String> {
+        let root = self.root.to_string_lossy().into_owned();
+        let mut writer = self.writer.lock();
+        let mut seen = HashSet::new();
+        let (mut scanned, mut indexed) = (0, 0);
+        let mut reported = Instant::now();
+
+        for entry in WalkBuilder::new(&self.root).build().flatten() {
+            if !entry.file_type().is_some_and(|kind| kind.is_file()) {
+                continue;
+            }
+            let Some(key) = relative(&self.root, entry.path()) else {
+                continue;
+            };
+            scanned += 1;
+            if self.update(&mut writer, &key, entry.path()) {
+                indexed += 1;
+            }
+            seen.insert(key);
+
+            if reported.elapsed() >= PROGRESS_INTERVAL {
+                reported = Instant::now();
+                let _ = app.emit(
+                    "content-index-progress",
+                    IndexProgressEvent {
+                        root: root.clone(),
+                        scanned,
+                        indexed,
+                    },
+                );
+            }
+        }
+
+        let gone: Vec<String> = writer
+            .docs
+            .keys()
+            .filter(|key| !seen.contains(*key))
+            .cloned()
+            .collect();
+        for key in gone {
+            self.remove(&mut writer, &key);
+        }
+        self.commit(&mut writer)?;
+        Ok(writer.docs.len())
+    }
+
+    /// Bring the index in line with the current state of `path`
+    fn refresh(&self, writer: &mut Writer, path: &Path) -> bool {
+        let Some(key) = relative(&self.root, path) else {
+            return false;
+        };
+        if path.is_dir() {
+            let mut changed = false;
+            for entry in WalkBuilder::new(path).build().flatten() {
+                if entry.file_type().is_some_and(|kind| kind.is_file()) {
+                    if let Some(key) = relative(&self.root, entry.path()) {
+                        changed |= self.update(writer, &key, entry.path());
+                    }
+                }
+            }
+            return changed;
+        }
+        if !path.is_file() {
+            return self.remove_tree(writer, &key);
+        }
+
+        // Walking the parent applies its ignore rules to the file
+        let included = path.parent().is_some_and(|parent| {
+            WalkBuilder::new(parent)
+                .max_depth(Some(1))
+                .build()
+                .flatten()
+                .any(|entry| entry.path() == path)
+        });
+        if included {
+            self.update(writer, &key, path)
+        } else {
+            self.remove(writer, &key)
+        }
+    }
+
+    fn search(&self, query: &str, limit: usize) -> Result<Vec<IndexHit>, String> {
+        let searcher = self.reader.searcher();
+        let parser = QueryParser::for_index(&self.index, vec![self.fields.body]);
+        // Free-form text shouldn't fail on a stray quote or colon
+        let (query, _) = parser.parse_query_lenient(query);
+        let top = searcher
+            .search(&query, &TopDocs::with_limit(limit))
+            .map_err(|e| format!("Search failed: {}", e))?;
+        let mut snippets = SnippetGenerator::create(&searcher, &*query, self.fields.body)
+            .map_err(|e| format!("Search failed: {}", e))?;
+        snippets.set_max_num_chars(SNIPPET_CHARS);
+
+        let mut hits = Vec::new();
+        for (score, address) in top {
+            let doc: TantivyDocument = searcher
+                .doc(address)
+                .map_err(|e| format!("Search failed: {}", e))?;
+            let Some(path) = doc
+                .get_first(self.fields.path)
+                .and_then(|value| value.as_str())
+            else {
+                continue;
+            };
+            // Bodies aren't stored, so the passage comes from the file itself
+            let text = fs::read(self.root.join(path))
+                .map(|data| String::from_utf8_lossy(&data).into_owned())
+                .unwrap_or_default();
+            let snippet = snippets.snippet(&text);
+            let fragment = snippet.fragment();
+            let chars = |offset: usize| fragment[..offset].chars().count();
+            hits.push(IndexHit {
+                path: path.to_string(),
+                score,
+                snippet: fragment.to_string(),
+                highlights: snippet
+                    .highlighted()
+                    .iter()
+                    .map(|range| [chars(range.start), chars(range.end)])
+                    .collect(),
+            });
+        }
+        Ok(hits)
+    }
+}
+
+fn watch(app: AppHandle, index: Arc<ContentIndex>) -> Result<RecommendedWatcher, String> {
+    let (tx, rx) = mpsc::channel::<notify::Result<Event>>();
+    let mut watcher = notify::recommended_watcher(move |event| {
+        let _ = tx.send(event);
+    })
+    .map_err(|e| format!("Failed to create watcher: {}", e))?;
+    watcher
+        .watch(&index.root, RecursiveMode::Recursive)
+        .map_err(|e| format!("Failed to watch {}: {}", index.root.display(), e))?;
+
+    thread::spawn(move || {
+        // recv fails once the watcher (and with it the sender) is dropped
+        while let Ok(first) = rx.recv() {
+            let mut changed = HashSet::new();
+            let mut add = |event: notify::Result<Event>| {
+                if let Ok(event) = event {
+                    changed.extend(event.paths);
+                }
+            };
+            add(first);
+            while let Ok(next) = rx.recv_timeout(DEBOUNCE) {
+                add(next);
+            }
+
+            let mut writer = index.writer.lock();
+            let mut updated = false;
+            for path in changed {
+                if path.components().any(|part| part.as_os_str() == ".git") {
+                    continue;
+                }
+                updated |= index.refresh(&mut writer, &path);
+            }
+            if !updated {
+                continue;
+            }
+            let error = index.commit(&mut writer).err();
+            let _ = app.emit(
+                "content-index-updated",
+                IndexReadyEvent {
+                    root: index.root.to_string_lossy().into_owned(),
+                    files: writer.docs.len(),
+                    error,
+                },
+            );
+        }
+    });
+
+    Ok(watcher)
+}
+
+/// Build or catch up the content index for `root` on a background thread,
+/// replacing the index of any other project. `content-index-ready` follows.
+#[tauri::command]
+pub async fn index_project_content(
+    app: AppHandle,
+    state: State<'_, ContentIndexState>,
+    root: String,
+) -> Result<(), String> {
+    let root = fs::canonicalize(&root).map_err(|e| format!("Failed to open {}: {}", root, e))?;
+    if !root.is_dir() {
+        return Err(format!("{} is not a directory", root.display()));
+    }
+
+    let current = state
+        .project
+        .lock()
+        .as_ref()
+        .filter(|project| project.index.root == root)
+        .map(|project| project.index.clone());
+    let index = match current {
+        Some(index) => index,
+        None => {
+            let hash = blake3::hash(root.to_string_lossy().as_bytes()).to_hex();
+            let dir = app
+                .path()
+                .app_cache_dir()
+                .map_err(|e| e.to_string())?
+                .join(INDEX_DIR)
+                .join(&hash[..16]);
+            // Stop watching the previous project
+            state.project.lock().take();
+            let index =
+                tauri::async_runtime::spawn_blocking(move || ContentIndex::open(root, &dir))
+                    .await
+                    .map_err(|e| format!("Indexing failed: {}", e))??;
+            let index = Arc::new(index);
+            let watcher = watch(app.clone(), index.clone())?;
+            *state.project.lock() = Some(IndexedProject {
+                index: index.clone(),
+                _watcher: watcher,
+            });
+            index
+        }
+    };
+
+    thread::spawn(move || {
+        let result = index.sync(&app);
+        let _ = app.emit(
+            "content-index-ready",
+            IndexReadyEvent {
+                root: index.root.to_string_lossy().into_owned(),
+                files: result.as_ref().map_or(0, |files| *files),
+                error: result.err(),
+            },
+        );
+    });
+    Ok(())
+}
+
+/// Indexed files ranked against `q`, best first
+#[tauri::command]
+pub async fn query_index(
+    state: State<'_, ContentIndexState>,
+    q: String,
+    limit: Option<usize>,
+) -> Result<Vec<IndexHit>, String> {
+    let index = state
+        .project
+        .lock()
+        .as_ref()
+        .map(|project| project.index.clone())
+        .ok_or_else(|| "No project indexed. Call index_project_content first".to_string())?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT);
+    // tantivy panics on a limit of zero
+    if limit == 0 {
+        return Ok(Vec::new());
+    }
+    tauri::async_runtime::spawn_blocking(move || index.search(&q, limit))
+        .await
+        .map_err(|e| format!("Search failed: {}", e))?
+}
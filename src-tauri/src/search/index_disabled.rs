@@ -0,0 +1,32 @@
+//! Full-text content index, left out of this build
+//!
+//! Built without the `content-index` feature; the commands keep their
+//! signatures so the frontend can tell the index is unavailable.
+
+use serde::Serialize;
+use tauri::State;
+
+const DISABLED: &str = "This build of Antler has no content index";
+
+#[derive(Default)]
+pub struct ContentIndexState {}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct IndexHit;
+
+#[tauri::command]
+pub async fn index_project_content(
+    _state: State<'_, ContentIndexState>,
+    _root: String,
+) -> Result<(), String> {
+    Err(DISABLED.to_string())
+}
+
+#[tauri::command]
+pub async fn query_index(
+    _state: State<'_, ContentIndexState>,
+    _q: String,
+    _limit: Option<usize>,
+) -> Result<Vec<IndexHit>, String> {
+    Err(DISABLED.to_string())
+}
@@ -0,0 +1,239 @@
+//! Project search built on the ripgrep crates
+//!
+//! `grep_project` walks the project the way `rg` does (respecting
+//! `.gitignore`, skipping hidden and binary files) on a background thread and
+//! emits each file's matches as a `search-matches` event as soon as it is
+//! searched, then `search-done` with the totals. Searches can be cancelled.
+
+pub mod fuzzy;
+#[cfg(feature = "content-index")]
+pub mod index;
+#[cfg(not(feature = "content-index"))]
+#[path = "index_disabled.rs"]
+pub mod index;
+
+use grep_matcher::Matcher;
+use grep_regex::RegexMatcherBuilder;
+use grep_searcher::sinks::UTF8;
+use grep_searcher::{BinaryDetection, SearcherBuilder};
+use ignore::overrides::OverrideBuilder;
+use ignore::WalkBuilder;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+use std::thread;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// Matches reported when the caller sets no limit
+const DEFAULT_MAX_RESULTS: usize = 1000;
+
+/// Characters of the matching line sent for display
+const PREVIEW_CHARS: usize = 300;
+
+/// Running searches
+pub struct SearchState {
+    searches: Mutex<HashMap<u32, Arc<AtomicBool>>>,
+    next_id: AtomicU32,
+}
+
+impl Default for SearchState {
+    fn default() -> Self {
+        Self {
+            searches: Mutex::new(HashMap::new()),
+            next_id: AtomicU32::new(1),
+        }
+    }
+}
+
+/// Options for `grep_project`
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GrepOptions {
+    /// Treat the query as a regular expression instead of literal text
+    #[serde(default)]
+    regex: bool,
+    /// Case sensitivity; by default only queries with uppercase letters are
+    case_sensitive: Option<bool>,
+    /// Paths to include, or exclude with a leading `!`, e.g. `src/**/*.ts`
+    #[serde(default)]
+    globs: Vec<String>,
+    max_results: Option<usize>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchMatch {
+    line: u64,
+    /// 1-based character column of the match
+    column: usize,
+    /// Length of the match in characters
+    length: usize,
+    /// The matching line, cut to a few hundred characters
+    preview: String,
+}
+
+/// Payload of `search-matches`
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SearchMatchesEvent {
+    search_id: u32,
+    /// Relative to the searched root
+    path: String,
+    matches: Vec<SearchMatch>,
+}
+
+/// Payload of `search-done`
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SearchDoneEvent {
+    search_id: u32,
+    matches: usize,
+    files: usize,
+    /// Stopped at `maxResults`
+    truncated: bool,
+    cancelled: bool,
+}
+
+fn search_match(line: u64, text: &str, start: usize, end: usize) -> SearchMatch {
+    let text = text.trim_end_matches(['\r', '\n']);
+    let column = text[..start.min(text.len())].chars().count();
+    let length = text
+        .get(start..end.min(text.len()))
+        .map_or(0, |found| found.chars().count());
+    // Keep the match visible on long lines
+    let skip = column.saturating_sub(PREVIEW_CHARS / 3);
+    SearchMatch {
+        line,
+        column: column + 1,
+        length,
+        preview: text.chars().skip(skip).take(PREVIEW_CHARS).collect(),
+    }
+}
+
+/// Search every file under `root` for `query`, returning the search ID that
+/// tags its events
+#[tauri::command]
+pub async fn grep_project(
+    app: AppHandle,
+    state: State<'_, SearchState>,
+    root: String,
+    query: String,
+    options: Option<GrepOptions>,
+) -> Result<u32, String> {
+    let options = options.unwrap_or_default();
+    if query.is_empty() {
+        return Err("Search query is empty".to_string());
+    }
+    if !Path::new(&root).is_dir() {
+        return Err(format!("{} is not a directory", root));
+    }
+
+    let mut builder = RegexMatcherBuilder::new();
+    builder.fixed_strings(!options.regex);
+    match options.case_sensitive {
+        Some(sensitive) => builder.case_insensitive(!sensitive),
+        None => builder.case_smart(true),
+    };
+    let matcher = builder
+        .build(&query)
+        .map_err(|e| format!("Invalid search pattern: {}", e))?;
+
+    let mut overrides = OverrideBuilder::new(&root);
+    for glob in &options.globs {
+        overrides
+            .add(glob)
+            .map_err(|e| format!("Invalid glob '{}': {}", glob, e))?;
+    }
+    let overrides = overrides
+        .build()
+        .map_err(|e| format!("Invalid globs: {}", e))?;
+
+    let id = state.next_id.fetch_add(1, Ordering::SeqCst);
+    let cancelled = Arc::new(AtomicBool::new(false));
+    state.searches.lock().insert(id, cancelled.clone());
+    let max_results = options.max_results.unwrap_or(DEFAULT_MAX_RESULTS);
+
+    thread::spawn(move || {
+        let mut searcher = SearcherBuilder::new()
+            .binary_detection(BinaryDetection::quit(b'\x00'))
+            .line_number(true)
+            .build();
+        let mut total = 0;
+        let mut files = 0;
+        let mut truncated = false;
+
+        for entry in WalkBuilder::new(&root).overrides(overrides).build() {
+            if cancelled.load(Ordering::SeqCst) {
+                break;
+            }
+            let Ok(entry) = entry else { continue };
+            if !entry.file_type().is_some_and(|kind| kind.is_file()) {
+                continue;
+            }
+
+            let mut matches = Vec::new();
+            let _ = searcher.search_path(
+                &matcher,
+                entry.path(),
+                UTF8(|line, text| {
+                    if let Ok(Some(found)) = matcher.find(text.as_bytes()) {
+                        matches.push(search_match(line, text, found.start(), found.end()));
+                    }
+                    Ok(total + matches.len() < max_results)
+                }),
+            );
+            if matches.is_empty() {
+                continue;
+            }
+
+            total += matches.len();
+            files += 1;
+            let path = entry
+                .path()
+                .strip_prefix(&root)
+                .unwrap_or(entry.path())
+                .to_string_lossy()
+                .into_owned();
+            let _ = app.emit(
+                "search-matches",
+                SearchMatchesEvent {
+                    search_id: id,
+                    path,
+                    matches,
+                },
+            );
+            if total >= max_results {
+                truncated = true;
+                break;
+            }
+        }
+
+        app.state::<SearchState>().searches.lock().remove(&id);
+        let _ = app.emit(
+            "search-done",
+            SearchDoneEvent {
+                search_id: id,
+                matches: total,
+                files,
+                truncated,
+                cancelled: cancelled.load(Ordering::SeqCst),
+            },
+        );
+    });
+
+    Ok(id)
+}
+
+/// Stop a running search; `search-done` still follows
+#[tauri::command]
+pub async fn cancel_search(state: State<'_, SearchState>, id: u32) -> Result<(), String> {
+    let searches = state.searches.lock();
+    let cancelled = searches
+        .get(&id)
+        .ok_or_else(|| format!("Search {} not found", id))?;
+    cancelled.store(true, Ordering::SeqCst);
+    Ok(())
+}
@@ -0,0 +1,71 @@
+//! Control endpoints - what scripts need besides watching
+//!
+//! - `POST /sessions/{id}/input` types the request body into a session
+//! - `POST /sessions/{id}/kill` hangs up a session
+//! - `GET /agents` lists agents as `list_agents` does
+//! - `POST /agents/{id}/respond` answers an agent with the request body, as
+//!   `respond_to_agent` does
+//! - `POST /agents/{id}/stop` stops an agent, running its pre-kill hooks
+//!
+//! They answer 204 when done and 400 with the error as text otherwise.
+
+use super::ServerContext;
+use crate::agents::{self, AgentInfo, AgentState};
+use crate::pty::{self, PtyState};
+use axum::extract::{Path, State as AxumState};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use tauri::Manager;
+
+fn outcome(result: Result<(), String>) -> Response {
+    match result {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, e).into_response(),
+    }
+}
+
+pub(super) async fn input(
+    AxumState(ctx): AxumState<ServerContext>,
+    Path(id): Path<u32>,
+    body: String,
+) -> Response {
+    // Writes to a remote session can wait on the network
+    let written = tauri::async_runtime::spawn_blocking(move || {
+        pty::write_session(&ctx.app.state::<PtyState>(), id, &body)
+    })
+    .await;
+    outcome(written.map_err(|e| e.to_string()).and_then(|result| result))
+}
+
+pub(super) async fn kill(
+    AxumState(ctx): AxumState<ServerContext>,
+    Path(id): Path<u32>,
+) -> Response {
+    let state = ctx.app.state::<PtyState>();
+    if pty::program(&state, id).is_err() {
+        return outcome(Err(format!("PTY session {} not found", id)));
+    }
+    pty::kill_session(&state, id);
+    outcome(Ok(()))
+}
+
+pub(super) async fn list_agents(AxumState(ctx): AxumState<ServerContext>) -> Json<Vec<AgentInfo>> {
+    Json(agents::agent_list(&ctx.app.state::<AgentState>()))
+}
+
+pub(super) async fn respond(
+    AxumState(ctx): AxumState<ServerContext>,
+    Path(id): Path<u32>,
+    answer: String,
+) -> Response {
+    let state = ctx.app.state::<AgentState>();
+    outcome(agents::respond_to_agent(ctx.app.clone(), state, id, answer).await)
+}
+
+pub(super) async fn stop(
+    AxumState(ctx): AxumState<ServerContext>,
+    Path(id): Path<u32>,
+) -> Response {
+    outcome(agents::stop_agent(ctx.app, id).await)
+}
@@ -0,0 +1,101 @@
+//! Session logs - a session's events as a server-sent event stream
+//!
+//! `GET /sessions/{id}/logs` streams the app events about PTY `id` from the
+//! moment it connects, under their app event names and with the same JSON
+//! data: its output, bells and connection state, and the state, prompts and
+//! activity of an agent running in it. The stream ends after `pty-exit`, or
+//! when the client falls so far behind that events would be lost.
+
+use super::ServerContext;
+use crate::pty::{self, PtyState};
+use axum::extract::{Path, State as AxumState};
+use axum::http::StatusCode;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use futures_util::StreamExt;
+use parking_lot::Mutex;
+use std::convert::Infallible;
+use std::sync::Arc;
+use tauri::{AppHandle, EventId, Listener, Manager};
+use tokio::sync::mpsc;
+
+/// Events a client may be behind before the stream ends
+const BACKLOG: usize = 1024;
+
+/// Events passed on, with the payload field holding the PTY ID
+const LOG_EVENTS: &[(&str, &str)] = &[
+    ("pty-data", "id"),
+    ("pty-exit", "id"),
+    ("pty-bell", "ptyId"),
+    ("session-degraded", "ptyId"),
+    ("session-recovered", "ptyId"),
+    ("agent-state", "ptyId"),
+    ("agent-needs-input", "ptyId"),
+    ("agent-activity", "ptyId"),
+];
+
+/// Removes the stream's event listeners once the client is gone
+struct Listeners {
+    app: AppHandle,
+    ids: Vec<EventId>,
+}
+
+impl Drop for Listeners {
+    fn drop(&mut self) {
+        for id in self.ids.drain(..) {
+            self.app.unlisten(id);
+        }
+    }
+}
+
+pub(super) async fn stream(
+    AxumState(ctx): AxumState<ServerContext>,
+    Path(pty_id): Path<u32>,
+) -> Response {
+    if pty::program(&ctx.app.state::<PtyState>(), pty_id).is_err() {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+
+    let (sender, receiver) = mpsc::channel::<(&'static str, String)>(BACKLOG);
+    // Dropped when the client falls behind, which ends the stream
+    let sender = Arc::new(Mutex::new(Some(sender)));
+    let ids = LOG_EVENTS
+        .iter()
+        .map(|&(name, field)| {
+            let sender = sender.clone();
+            ctx.app.listen_any(name, move |event| {
+                let Ok(payload) = serde_json::from_str::<serde_json::Value>(event.payload()) else {
+                    return;
+                };
+                if payload[field].as_u64() != Some(pty_id.into()) {
+                    return;
+                }
+                let mut sender = sender.lock();
+                if let Some(tx) = sender.as_ref() {
+                    if tx.try_send((name, event.payload().to_string())).is_err() {
+                        *sender = None;
+                    }
+                }
+            })
+        })
+        .collect();
+    let listeners = Listeners {
+        app: ctx.app.clone(),
+        ids,
+    };
+
+    let events = futures_util::stream::unfold(
+        (receiver, Some(listeners)),
+        |(mut receiver, listeners)| async move {
+            // Taken after `pty-exit`, so the next call ends the stream
+            listeners.as_ref()?;
+            let (name, data) = receiver.recv().await?;
+            let listeners = listeners.filter(|_| name != "pty-exit");
+            let event = Event::default().event(name).data(data);
+            Some((Ok::<_, Infallible>(event), (receiver, listeners)))
+        },
+    );
+    Sse::new(events.take_until(ctx.stopped()))
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
@@ -0,0 +1,359 @@
+//! MCP server - Antler's own tools for Claude Code sessions
+//!
+//! `POST /mcp` speaks the Model Context Protocol's HTTP transport, answering
+//! each JSON-RPC message (or batch) with JSON, so an agent can drive the app
+//! it runs in. Register it with
+//! `claude mcp add --transport http antler http://127.0.0.1:<port>/mcp
+//! --header "Authorization: Bearer <token>"`. The tools:
+//!
+//! - `spawn_terminal` opens a terminal, announced as `mcp-terminal-spawned`
+//!   so the UI can show it
+//! - `read_session_output` returns the last lines a session printed
+//! - `list_worktrees` lists a repository's worktrees
+//! - `move_issue_column` moves an issue to a board column
+//!
+//! The text of each session's recent output is kept for
+//! `read_session_output`, also for a while after it exits.
+
+use super::ServerContext;
+use crate::agents;
+use crate::git::worktree;
+use crate::github::columns;
+use crate::pty::{self, PtyState};
+use axum::body::Bytes;
+use axum::extract::State as AxumState;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use parking_lot::Mutex;
+use portable_pty::CommandBuilder;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Newest protocol revision spoken, answered to clients asking for another
+const PROTOCOL_VERSION: &str = "2025-06-18";
+
+/// Older revisions accepted as asked for
+const PROTOCOL_VERSIONS: &[&str] = &[PROTOCOL_VERSION, "2025-03-26", "2024-11-05"];
+
+/// Bytes of output text kept per session
+const MAX_OUTPUT: usize = 64 * 1024;
+
+/// Exited sessions whose output is kept
+const MAX_EXITED: usize = 32;
+
+/// Lines `read_session_output` returns when not told otherwise
+const DEFAULT_LINES: usize = 100;
+
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const PARSE_ERROR: i64 = -32700;
+
+/// Recent output of each session
+#[derive(Default)]
+pub struct McpState {
+    output: Mutex<HashMap<u32, String>>,
+    /// Exited sessions, oldest first
+    exited: Mutex<VecDeque<u32>>,
+}
+
+/// Payload of `mcp-terminal-spawned`
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TerminalSpawnedEvent {
+    pty_id: u32,
+    cwd: String,
+    /// `None` for the default shell
+    command: Option<String>,
+}
+
+/// Keep the text of a chunk of a PTY's output
+pub(crate) fn record_output(app: &AppHandle, pty_id: u32, data: &str) {
+    let state = app.state::<McpState>();
+    let mut output = state.output.lock();
+    let text = output.entry(pty_id).or_default();
+    text.push_str(&agents::strip_ansi(data));
+    if text.len() > MAX_OUTPUT {
+        let mut start = text.len() - MAX_OUTPUT;
+        while !text.is_char_boundary(start) {
+            start += 1;
+        }
+        text.drain(..start);
+    }
+}
+
+/// Keep a PTY's output for a while after it exited
+pub(crate) fn session_exited(app: &AppHandle, pty_id: u32) {
+    let state = app.state::<McpState>();
+    let mut exited = state.exited.lock();
+    exited.push_back(pty_id);
+    while exited.len() > MAX_EXITED {
+        if let Some(id) = exited.pop_front() {
+            state.output.lock().remove(&id);
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RpcRequest {
+    /// Absent for notifications, `notifications/initialized` among them
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+type RpcError = (i64, String);
+
+#[derive(Deserialize)]
+struct ToolCall {
+    name: String,
+    #[serde(default)]
+    arguments: Value,
+}
+
+#[derive(Deserialize)]
+struct SpawnTerminalArgs {
+    cwd: String,
+    command: Option<String>,
+    #[serde(default)]
+    args: Vec<String>,
+    cols: Option<u16>,
+    rows: Option<u16>,
+}
+
+#[derive(Deserialize)]
+struct ReadOutputArgs {
+    id: u32,
+    lines: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct ListWorktreesArgs {
+    repo: String,
+}
+
+#[derive(Deserialize)]
+struct MoveIssueColumnArgs {
+    repo: String,
+    number: u64,
+    column: String,
+}
+
+fn tools() -> Value {
+    json!([
+        {
+            "name": "spawn_terminal",
+            "description": "Open a terminal in Antler, running a command or the user's shell. \
+                Returns its session ID.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "cwd": { "type": "string", "description": "Directory to start in" },
+                    "command": { "type": "string", "description": "Program to run instead of the shell" },
+                    "args": { "type": "array", "items": { "type": "string" } },
+                    "cols": { "type": "integer" },
+                    "rows": { "type": "integer" }
+                },
+                "required": ["cwd"]
+            }
+        },
+        {
+            "name": "read_session_output",
+            "description": "The last lines a terminal session printed, without colors.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "id": { "type": "integer", "description": "Session ID" },
+                    "lines": { "type": "integer", "description": "How many lines, 100 by default" }
+                },
+                "required": ["id"]
+            }
+        },
+        {
+            "name": "list_worktrees",
+            "description": "Git worktrees of a repository, with their branches.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "repo": { "type": "string", "description": "Path of the repository" }
+                },
+                "required": ["repo"]
+            }
+        },
+        {
+            "name": "move_issue_column",
+            "description": "Move a GitHub issue to a column of the Antler board: backlog, \
+                feature, development, review or done.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "repo": { "type": "string", "description": "owner/name" },
+                    "number": { "type": "integer" },
+                    "column": { "type": "string" }
+                },
+                "required": ["repo", "number", "column"]
+            }
+        }
+    ])
+}
+
+fn arguments<T: DeserializeOwned>(arguments: Value) -> Result<T, RpcError> {
+    let arguments = match arguments {
+        Value::Null => json!({}),
+        arguments => arguments,
+    };
+    serde_json::from_value(arguments).map_err(|e| (INVALID_PARAMS, e.to_string()))
+}
+
+fn spawn_terminal(app: &AppHandle, args: SpawnTerminalArgs) -> Result<String, String> {
+    let mut cmd = match &args.command {
+        Some(command) => {
+            let mut cmd = CommandBuilder::new(command);
+            cmd.args(&args.args);
+            cmd
+        }
+        None => CommandBuilder::new_default_prog(),
+    };
+    cmd.cwd(&args.cwd);
+    let id = pty::spawn_session(
+        app,
+        &app.state::<PtyState>(),
+        cmd,
+        args.cols.unwrap_or(80),
+        args.rows.unwrap_or(24),
+        None,
+        None,
+    )?;
+    let _ = app.emit(
+        "mcp-terminal-spawned",
+        TerminalSpawnedEvent {
+            pty_id: id,
+            cwd: args.cwd,
+            command: args.command,
+        },
+    );
+    Ok(json!({ "id": id }).to_string())
+}
+
+fn read_session_output(app: &AppHandle, args: ReadOutputArgs) -> Result<String, String> {
+    let state = app.state::<McpState>();
+    let output = state.output.lock();
+    let text = match output.get(&args.id) {
+        Some(text) => text,
+        None if pty::program(&app.state::<PtyState>(), args.id).is_ok() => "",
+        None => return Err(format!("No session {}", args.id)),
+    };
+    let lines: Vec<&str> = text.lines().collect();
+    let count = args.lines.unwrap_or(DEFAULT_LINES).min(lines.len());
+    Ok(lines[lines.len() - count..].join("\n"))
+}
+
+async fn list_worktrees(args: ListWorktreesArgs) -> Result<String, String> {
+    let worktrees = tauri::async_runtime::spawn_blocking(move || worktree::worktrees(&args.repo))
+        .await
+        .map_err(|e| e.to_string())??;
+    serde_json::to_string_pretty(&worktrees).map_err(|e| e.to_string())
+}
+
+async fn move_issue_column(app: &AppHandle, args: MoveIssueColumnArgs) -> Result<String, String> {
+    let issue = columns::set_issue_column(
+        app.clone(),
+        app.state(),
+        args.repo,
+        args.number,
+        args.column,
+        None,
+    )
+    .await
+    .map_err(|e| serde_json::to_string(&e).unwrap_or_else(|_| "GitHub request failed".into()))?;
+    serde_json::to_string_pretty(&issue).map_err(|e| e.to_string())
+}
+
+async fn call_tool(app: &AppHandle, params: Value) -> Result<Value, RpcError> {
+    let call: ToolCall = arguments(params)?;
+    let outcome = match call.name.as_str() {
+        "spawn_terminal" => spawn_terminal(app, arguments(call.arguments)?),
+        "read_session_output" => read_session_output(app, arguments(call.arguments)?),
+        "list_worktrees" => list_worktrees(arguments(call.arguments)?).await,
+        "move_issue_column" => move_issue_column(app, arguments(call.arguments)?).await,
+        name => return Err((INVALID_PARAMS, format!("Unknown tool {}", name))),
+    };
+    // Failures of the tool itself are results the model gets to see
+    let (text, is_error) = match outcome {
+        Ok(text) => (text, false),
+        Err(e) => (e, true),
+    };
+    Ok(json!({
+        "content": [{ "type": "text", "text": text }],
+        "isError": is_error,
+    }))
+}
+
+fn initialize(params: &Value) -> Value {
+    let asked = params["protocolVersion"].as_str();
+    let version = asked
+        .filter(|version| PROTOCOL_VERSIONS.contains(version))
+        .unwrap_or(PROTOCOL_VERSION);
+    json!({
+        "protocolVersion": version,
+        "capabilities": { "tools": {} },
+        "serverInfo": { "name": "antler", "version": env!("CARGO_PKG_VERSION") },
+    })
+}
+
+fn reply(id: Value, result: Result<Value, RpcError>) -> Value {
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err((code, message)) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": code, "message": message },
+        }),
+    }
+}
+
+/// Answer one message, `None` for notifications
+async fn dispatch(app: &AppHandle, message: Value) -> Option<Value> {
+    let Ok(request) = serde_json::from_value::<RpcRequest>(message) else {
+        return Some(reply(
+            Value::Null,
+            Err((INVALID_REQUEST, "Invalid request".to_string())),
+        ));
+    };
+    let id = request.id?;
+    let result = match request.method.as_str() {
+        "initialize" => Ok(initialize(&request.params)),
+        "ping" => Ok(json!({})),
+        "tools/list" => Ok(json!({ "tools": tools() })),
+        "tools/call" => call_tool(app, request.params).await,
+        method => Err((METHOD_NOT_FOUND, format!("Unknown method {}", method))),
+    };
+    Some(reply(id, result))
+}
+
+pub(super) async fn handle(AxumState(ctx): AxumState<ServerContext>, body: Bytes) -> Response {
+    let Ok(message) = serde_json::from_slice::<Value>(&body) else {
+        let error = reply(Value::Null, Err((PARSE_ERROR, "Parse error".to_string())));
+        return Json(error).into_response();
+    };
+    let replies = match message {
+        Value::Array(batch) => {
+            let mut replies = Vec::new();
+            for message in batch {
+                replies.extend(dispatch(&ctx.app, message).await);
+            }
+            (!replies.is_empty()).then_some(Value::Array(replies))
+        }
+        message => dispatch(&ctx.app, message).await,
+    };
+    match replies {
+        Some(replies) => Json(replies).into_response(),
+        // Notifications and responses get no answer
+        None => StatusCode::ACCEPTED.into_response(),
+    }
+}
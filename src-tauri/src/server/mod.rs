@@ -0,0 +1,262 @@
+//! Local server - opt-in HTTP and WebSocket access for outside tools
+//!
+//! Scripts, external tools and browser tabs on another monitor reach
+//! Antler-managed terminals through it. It listens on loopback only, and
+//! every request needs the token `start_local_server` returns, as
+//! `Authorization: Bearer <token>` or, for browser WebSocket clients, which
+//! can't set headers, a `token` query parameter.
+//!
+//! - `GET /sessions` lists the PTY sessions
+//! - `GET /sessions/{id}/pty` opens a WebSocket mirroring one (see
+//!   `terminal`)
+//! - `GET /sessions/{id}/logs` streams a session's events (see `logs`)
+//! - a few endpoints control sessions and agents (see `control`)
+//! - `POST /mcp` serves Antler's tools to MCP clients (see `mcp`)
+//!
+//! Sharing a session with a teammate uses a listener of its own, reachable
+//! from the local network (see `share`).
+
+mod control;
+mod logs;
+pub mod mcp;
+pub mod share;
+pub mod terminal;
+
+use crate::pty::{self, PtyState};
+use axum::extract::{Query, Request, State as AxumState};
+use axum::http::header::AUTHORIZATION;
+use axum::http::StatusCode;
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+use tokio::sync::watch;
+
+/// Port used when the server is started without one
+const DEFAULT_PORT: u16 = 8790;
+
+/// Tries at binding a port the previous server is still letting go of
+const BIND_ATTEMPTS: u32 = 20;
+const BIND_RETRY: Duration = Duration::from_millis(50);
+
+struct Server {
+    info: LocalServerInfo,
+    /// Set, or dropped, to stop the server and close its connections
+    shutdown: watch::Sender<bool>,
+}
+
+/// The running server, if any
+#[derive(Default)]
+pub struct LocalServerState {
+    server: Mutex<Option<Server>>,
+}
+
+/// Options for `start_local_server`
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalServerOptions {
+    port: Option<u16>,
+    /// Token to require, so clients keep working across restarts; a random
+    /// one is made without it
+    token: Option<String>,
+}
+
+/// Where the server listens and the token it requires
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalServerInfo {
+    port: u16,
+    token: String,
+}
+
+#[derive(Clone)]
+struct ServerContext {
+    app: AppHandle,
+    token: String,
+    shutdown: watch::Receiver<bool>,
+}
+
+impl ServerContext {
+    /// Resolves once the server stops, for connections that outlive requests:
+    /// a stopped server shouldn't leave clients attached with its token
+    fn stopped(&self) -> impl Future<Output = ()> + Send + 'static {
+        stopped(&self.shutdown)
+    }
+}
+
+/// Resolves once `shutdown` is set or its sender dropped
+fn stopped(shutdown: &watch::Receiver<bool>) -> impl Future<Output = ()> + Send + 'static {
+    let mut shutdown = shutdown.clone();
+    async move {
+        let _ = shutdown.wait_for(|stopped| *stopped).await;
+    }
+}
+
+#[derive(Deserialize)]
+struct TokenQuery {
+    token: Option<String>,
+}
+
+/// Entry of `GET /sessions`
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SessionSummary {
+    id: u32,
+    program: String,
+}
+
+fn new_token() -> Result<String, String> {
+    let mut bytes = [0u8; 32];
+    getrandom::fill(&mut bytes).map_err(|e| format!("Failed to generate a token: {}", e))?;
+    Ok(hex::encode(bytes))
+}
+
+/// Whether a client gave the expected token
+fn token_matches(given: &str, expected: &str) -> bool {
+    // blake3 hashes compare in constant time, so timing gives nothing away
+    blake3::hash(given.as_bytes()) == blake3::hash(expected.as_bytes())
+}
+
+async fn require_token(
+    AxumState(ctx): AxumState<ServerContext>,
+    Query(query): Query<TokenQuery>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let bearer = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    let given = bearer.or(query.token.as_deref()).unwrap_or_default();
+    if !token_matches(given, &ctx.token) {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    next.run(request).await
+}
+
+async fn list_sessions(AxumState(ctx): AxumState<ServerContext>) -> Json<Vec<SessionSummary>> {
+    let mut sessions: Vec<SessionSummary> = pty::session_programs(&ctx.app.state::<PtyState>())
+        .into_iter()
+        .map(|(id, program)| SessionSummary { id, program })
+        .collect();
+    sessions.sort_by_key(|session| session.id);
+    Json(sessions)
+}
+
+/// Bind `addr`, waiting a moment for a server shutting down to let go of it
+async fn bind_released(addr: &str) -> std::io::Result<tokio::net::TcpListener> {
+    for _ in 1..BIND_ATTEMPTS {
+        match tokio::net::TcpListener::bind(addr).await {
+            Err(e) if e.kind() == std::io::ErrorKind::AddrInUse => {
+                tokio::time::sleep(BIND_RETRY).await;
+            }
+            result => return result,
+        }
+    }
+    tokio::net::TcpListener::bind(addr).await
+}
+
+/// Start the local server, returning its port and token.
+///
+/// Starting while it is already running on the same port with the same
+/// token keeps it as it is. Otherwise the new one is bound before the old
+/// one stops, so a failed start leaves the old one running, and clients of
+/// the old one are disconnected, as they are when it stops.
+#[tauri::command]
+pub async fn start_local_server(
+    app: AppHandle,
+    state: State<'_, LocalServerState>,
+    options: LocalServerOptions,
+) -> Result<LocalServerInfo, String> {
+    if options.token.as_ref().is_some_and(|token| token.len() < 16) {
+        return Err("The token must be at least 16 characters".to_string());
+    }
+    let port = options.port.unwrap_or(DEFAULT_PORT);
+    let same_port = match state.server.lock().as_ref() {
+        Some(running) if running.info.port == port => {
+            let given = options.token.as_ref();
+            if given.is_none_or(|token| *token == running.info.token) {
+                return Ok(running.info.clone());
+            }
+            true
+        }
+        _ => false,
+    };
+    let token = match options.token {
+        Some(token) => token,
+        None => new_token()?,
+    };
+
+    let addr = format!("127.0.0.1:{}", port);
+    let listener = if same_port {
+        // Only a new token on the same port: the old server has to let go
+        // of the port first
+        if let Some(previous) = state.server.lock().take() {
+            let _ = previous.shutdown.send(true);
+        }
+        bind_released(&addr).await
+    } else {
+        tokio::net::TcpListener::bind(&addr).await
+    }
+    .map_err(|e| format!("Failed to listen on {}: {}", addr, e))?;
+    let port = listener.local_addr().map_err(|e| e.to_string())?.port();
+
+    let (shutdown, shutdown_rx) = watch::channel(false);
+    let ctx = ServerContext {
+        app,
+        token: token.clone(),
+        shutdown: shutdown_rx,
+    };
+    let router = Router::new()
+        .route("/sessions", get(list_sessions))
+        .route("/sessions/{id}/pty", get(terminal::attach))
+        .route("/sessions/{id}/logs", get(logs::stream))
+        .route("/sessions/{id}/input", post(control::input))
+        .route("/sessions/{id}/kill", post(control::kill))
+        .route("/agents", get(control::list_agents))
+        .route("/agents/{id}/respond", post(control::respond))
+        .route("/agents/{id}/stop", post(control::stop))
+        .route("/mcp", post(mcp::handle))
+        .route_layer(middleware::from_fn_with_state(ctx.clone(), require_token))
+        .with_state(ctx.clone());
+
+    tauri::async_runtime::spawn(async move {
+        let server = axum::serve(listener, router).with_graceful_shutdown(ctx.stopped());
+        if let Err(e) = server.await {
+            eprintln!("Local server stopped: {}", e);
+        }
+    });
+
+    let info = LocalServerInfo { port, token };
+    let previous = state.server.lock().replace(Server {
+        info: info.clone(),
+        shutdown,
+    });
+    if let Some(previous) = previous {
+        let _ = previous.shutdown.send(true);
+    }
+    Ok(info)
+}
+
+/// Stop the local server
+#[tauri::command]
+pub async fn stop_local_server(state: State<'_, LocalServerState>) -> Result<(), String> {
+    if let Some(server) = state.server.lock().take() {
+        let _ = server.shutdown.send(true);
+    }
+    Ok(())
+}
+
+/// Port and token of the running server, if any
+#[tauri::command]
+pub async fn local_server_info(
+    state: State<'_, LocalServerState>,
+) -> Result<Option<LocalServerInfo>, String> {
+    Ok(state.server.lock().as_ref().map(|s| s.info.clone()))
+}
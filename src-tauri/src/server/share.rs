@@ -0,0 +1,276 @@
+//! Session sharing - a live view of one session for a teammate
+//!
+//! `share_session` opens a listener on all interfaces and returns a
+//! `ws://` link, on the machine's LAN address, for a single viewer. The
+//! link's token works once: the first client to attach with it gets the
+//! session's output (see `terminal`), read-only unless input was granted,
+//! and anyone after gets 401. The share ends when that viewer leaves, when
+//! the session exits, when it expires or with `stop_share`, closing the
+//! viewer's connection and announcing `share-ended`; `share-joined` reports
+//! who attached. Traffic isn't encrypted, so links are for trusted networks.
+
+use super::{new_token, stopped, terminal, token_matches, TokenQuery};
+use crate::pty::{self, PtyState};
+use axum::extract::ws::WebSocketUpgrade;
+use axum::extract::{ConnectInfo, Query, State as AxumState};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::sync::watch;
+
+/// How long a share lasts when not told otherwise
+const DEFAULT_EXPIRY: Duration = Duration::from_secs(30 * 60);
+
+struct Share {
+    info: ShareInfo,
+    /// Set, or dropped, to end the share and close its viewer
+    shutdown: watch::Sender<bool>,
+}
+
+/// Open shares
+pub struct ShareState {
+    shares: Mutex<HashMap<u32, Share>>,
+    next_id: AtomicU32,
+}
+
+impl Default for ShareState {
+    fn default() -> Self {
+        Self {
+            shares: Mutex::new(HashMap::new()),
+            next_id: AtomicU32::new(1),
+        }
+    }
+}
+
+/// Options for `share_session`
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShareOptions {
+    /// Let the viewer type into the session
+    #[serde(default)]
+    allow_input: bool,
+    /// Seconds until the share ends, 30 minutes by default
+    expiry_secs: Option<u64>,
+    /// Port to listen on, any free one without it
+    port: Option<u16>,
+}
+
+/// An open share
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShareInfo {
+    id: u32,
+    pty_id: u32,
+    /// Link for the viewer, token included
+    url: String,
+    allow_input: bool,
+    /// Unix time the share ends at
+    expires_at: i64,
+}
+
+/// Payload of `share-joined`
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ShareJoinedEvent {
+    id: u32,
+    pty_id: u32,
+    /// Address of the viewer
+    peer: String,
+}
+
+/// Payload of `share-ended`
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ShareEndedEvent {
+    id: u32,
+    pty_id: u32,
+}
+
+#[derive(Clone)]
+struct ShareContext {
+    app: AppHandle,
+    id: u32,
+    pty_id: u32,
+    allow_input: bool,
+    /// Taken by the viewer that attaches with it
+    token: Arc<Mutex<Option<String>>>,
+    shutdown: watch::Receiver<bool>,
+}
+
+/// Address other machines on the network reach this one at
+fn lan_address() -> IpAddr {
+    // Connecting a UDP socket only picks the interface a packet would leave
+    // by; nothing is sent
+    UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+        .and_then(|socket| {
+            socket
+                .connect((Ipv4Addr::new(192, 0, 2, 1), 80))
+                .map(|_| socket)
+        })
+        .and_then(|socket| socket.local_addr())
+        .map_or(IpAddr::V4(Ipv4Addr::LOCALHOST), |addr| addr.ip())
+}
+
+/// End a share, closing its viewer's connection
+fn end(app: &AppHandle, id: u32) {
+    if let Some(share) = app.state::<ShareState>().shares.lock().remove(&id) {
+        let _ = share.shutdown.send(true);
+    }
+}
+
+/// End the shares of a session that exited
+pub(crate) fn session_exited(app: &AppHandle, pty_id: u32) {
+    let ended: Vec<u32> = app
+        .state::<ShareState>()
+        .shares
+        .lock()
+        .values()
+        .filter(|share| share.info.pty_id == pty_id)
+        .map(|share| share.info.id)
+        .collect();
+    for id in ended {
+        end(app, id);
+    }
+}
+
+async fn join(
+    AxumState(ctx): AxumState<ShareContext>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    Query(query): Query<TokenQuery>,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    if !pty::is_running(&ctx.app.state::<PtyState>(), ctx.pty_id) {
+        return StatusCode::GONE.into_response();
+    }
+    {
+        let given = query.token.unwrap_or_default();
+        let mut token = ctx.token.lock();
+        let valid = token
+            .as_ref()
+            .is_some_and(|token| token_matches(&given, token));
+        if !valid {
+            return StatusCode::UNAUTHORIZED.into_response();
+        }
+        *token = None;
+    }
+
+    let frames = terminal::subscribe(&ctx.app, ctx.pty_id);
+    let _ = ctx.app.emit(
+        "share-joined",
+        ShareJoinedEvent {
+            id: ctx.id,
+            pty_id: ctx.pty_id,
+            peer: peer.to_string(),
+        },
+    );
+    let stopped = stopped(&ctx.shutdown);
+    upgrade.on_upgrade(move |socket| async move {
+        let mirror = terminal::mirror(ctx.app.clone(), ctx.pty_id, socket, frames, ctx.allow_input);
+        futures_util::pin_mut!(mirror, stopped);
+        futures_util::future::select(mirror, stopped).await;
+        end(&ctx.app, ctx.id);
+    })
+}
+
+/// Share a session's output with one viewer on the local network, returning
+/// the link to give them
+#[tauri::command]
+pub async fn share_session(
+    app: AppHandle,
+    state: State<'_, ShareState>,
+    pty_id: u32,
+    options: Option<ShareOptions>,
+) -> Result<ShareInfo, String> {
+    let options = options.unwrap_or_default();
+    if !pty::is_running(&app.state::<PtyState>(), pty_id) {
+        return Err(format!("PTY session {} is not running", pty_id));
+    }
+    let token = new_token()?;
+
+    let addr = SocketAddr::from((Ipv4Addr::UNSPECIFIED, options.port.unwrap_or(0)));
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .map_err(|e| format!("Failed to listen on {}: {}", addr, e))?;
+    let port = listener.local_addr().map_err(|e| e.to_string())?.port();
+
+    let id = state.next_id.fetch_add(1, Ordering::SeqCst);
+    let expiry = options
+        .expiry_secs
+        .map_or(DEFAULT_EXPIRY, Duration::from_secs);
+    let info = ShareInfo {
+        id,
+        pty_id,
+        url: format!("ws://{}:{}/?token={}", lan_address(), port, token),
+        allow_input: options.allow_input,
+        expires_at: chrono::Utc::now().timestamp() + expiry.as_secs() as i64,
+    };
+
+    let (shutdown, shutdown_rx) = watch::channel(false);
+    let ctx = ShareContext {
+        app: app.clone(),
+        id,
+        pty_id,
+        allow_input: options.allow_input,
+        token: Arc::new(Mutex::new(Some(token))),
+        shutdown: shutdown_rx,
+    };
+    let ended = stopped(&ctx.shutdown);
+    let cancelled = stopped(&ctx.shutdown);
+    let router = Router::new().route("/", get(join)).with_state(ctx);
+    state.shares.lock().insert(
+        id,
+        Share {
+            info: info.clone(),
+            shutdown,
+        },
+    );
+
+    let expiring = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if tokio::time::timeout(expiry, cancelled).await.is_err() {
+            end(&expiring, id);
+        }
+    });
+    tauri::async_runtime::spawn(async move {
+        let server = axum::serve(
+            listener,
+            router.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .with_graceful_shutdown(ended);
+        if let Err(e) = server.await {
+            eprintln!("Share {} stopped: {}", id, e);
+        }
+        end(&app, id);
+        let _ = app.emit("share-ended", ShareEndedEvent { id, pty_id });
+    });
+    Ok(info)
+}
+
+/// End a share, disconnecting its viewer
+#[tauri::command]
+pub async fn stop_share(app: AppHandle, id: u32) -> Result<(), String> {
+    end(&app, id);
+    Ok(())
+}
+
+/// Open shares
+#[tauri::command]
+pub async fn list_shares(state: State<'_, ShareState>) -> Result<Vec<ShareInfo>, String> {
+    let mut shares: Vec<ShareInfo> = state
+        .shares
+        .lock()
+        .values()
+        .map(|share| share.info.clone())
+        .collect();
+    shares.sort_by_key(|share| share.id);
+    Ok(shares)
+}
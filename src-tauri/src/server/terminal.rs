@@ -0,0 +1,143 @@
+//! Terminal mirrors - PTY sessions over the local server's WebSocket
+//!
+//! A client attached at `/sessions/{id}/pty` receives the session's output
+//! from then on as text messages, and its text or binary messages are typed
+//! into the session, alongside the app's own terminal. It is closed when the
+//! session exits, or when it falls so far behind that output would be lost.
+//! Resizing stays with the app. Shared sessions (see `share`) use the same
+//! mirror, read-only unless input was granted.
+
+use super::ServerContext;
+use crate::pty::{self, PtyState};
+use axum::extract::ws::{close_code, CloseFrame, Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path, State as AxumState};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use futures_util::{SinkExt, StreamExt};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use tauri::{AppHandle, Manager};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+/// Output chunks a client may be behind before it is dropped
+const BACKLOG: usize = 1024;
+
+#[derive(Clone)]
+pub(super) enum Frame {
+    Output(String),
+    Exit,
+}
+
+/// Output streams of the sessions that have clients attached
+#[derive(Default)]
+pub struct MirrorState {
+    streams: Mutex<HashMap<u32, broadcast::Sender<Frame>>>,
+}
+
+/// Pass a chunk of a PTY's output to its clients
+pub(crate) fn mirror_output(app: &AppHandle, pty_id: u32, data: &str) {
+    let state = app.state::<MirrorState>();
+    let mut streams = state.streams.lock();
+    if let Some(sender) = streams.get(&pty_id) {
+        // Fails once the last client left
+        if sender.send(Frame::Output(data.to_string())).is_err() {
+            streams.remove(&pty_id);
+        }
+    }
+}
+
+/// Tell a PTY's clients it exited
+pub(crate) fn mirror_exit(app: &AppHandle, pty_id: u32) {
+    if let Some(sender) = app.state::<MirrorState>().streams.lock().remove(&pty_id) {
+        let _ = sender.send(Frame::Exit);
+    }
+}
+
+pub(super) fn subscribe(app: &AppHandle, pty_id: u32) -> broadcast::Receiver<Frame> {
+    app.state::<MirrorState>()
+        .streams
+        .lock()
+        .entry(pty_id)
+        .or_insert_with(|| broadcast::channel(BACKLOG).0)
+        .subscribe()
+}
+
+fn close(code: u16, reason: &str) -> Message {
+    Message::Close(Some(CloseFrame {
+        code,
+        reason: reason.into(),
+    }))
+}
+
+pub(super) async fn attach(
+    AxumState(ctx): AxumState<ServerContext>,
+    Path(id): Path<u32>,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    if pty::program(&ctx.app.state::<PtyState>(), id).is_err() {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    let frames = subscribe(&ctx.app, id);
+    let stopped = ctx.stopped();
+    upgrade.on_upgrade(move |socket| async move {
+        let mirror = mirror(ctx.app, id, socket, frames, true);
+        futures_util::pin_mut!(mirror, stopped);
+        futures_util::future::select(mirror, stopped).await;
+    })
+}
+
+/// Stream a session's output to a client until either side is done, typing
+/// what the client sends into the session if `input` is set
+pub(super) async fn mirror(
+    app: AppHandle,
+    id: u32,
+    socket: WebSocket,
+    mut frames: broadcast::Receiver<Frame>,
+    input: bool,
+) {
+    let (mut sink, mut stream) = socket.split();
+
+    let outgoing = async move {
+        loop {
+            let message = match frames.recv().await {
+                Ok(Frame::Output(data)) => Message::Text(data.into()),
+                Ok(Frame::Exit) | Err(RecvError::Closed) => {
+                    close(close_code::NORMAL, "Session exited")
+                }
+                Err(RecvError::Lagged(_)) => {
+                    close(close_code::POLICY, "Fell behind the session's output")
+                }
+            };
+            let last = matches!(message, Message::Close(_));
+            if sink.send(message).await.is_err() || last {
+                break;
+            }
+        }
+    };
+
+    let incoming = async move {
+        while let Some(Ok(message)) = stream.next().await {
+            let data = match message {
+                Message::Text(text) => text.to_string(),
+                Message::Binary(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+                Message::Close(_) => break,
+                _ => continue,
+            };
+            if !input {
+                continue;
+            }
+            let app = app.clone();
+            // Writes to a remote session can wait on the network
+            let written = tauri::async_runtime::spawn_blocking(move || {
+                pty::write_session(&app.state::<PtyState>(), id, &data)
+            })
+            .await;
+            if !matches!(written, Ok(Ok(()))) {
+                break;
+            }
+        }
+    };
+
+    futures_util::pin_mut!(outgoing, incoming);
+    futures_util::future::select(outgoing, incoming).await;
+}
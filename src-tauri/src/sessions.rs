@@ -0,0 +1,344 @@
+//! Issue session registry - which resources belong to which issue
+//!
+//! Records the worktree, branch, container, PTYs and agent created for an
+//! issue in a SQLite file in the app data directory, so the board can rebuild
+//! its cards after a reload or restart. PTY and agent IDs only mean something
+//! to the running process and are cleared when the registry is first opened.
+
+use crate::store::Store;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, State};
+
+const REGISTRY_FILE: &str = "sessions.sqlite";
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS sessions (
+    repo TEXT NOT NULL,
+    issue INTEGER NOT NULL,
+    worktree TEXT,
+    branch TEXT,
+    container_id TEXT,
+    pty_ids TEXT NOT NULL DEFAULT '[]',
+    agent_id INTEGER,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    PRIMARY KEY (repo, issue)
+);
+";
+
+/// IDs from a previous run would point at whatever reuses them now
+fn reset_ids(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute("UPDATE sessions SET pty_ids = '[]', agent_id = NULL", [])?;
+    Ok(())
+}
+
+/// Lazily opened registry database
+pub struct SessionRegistry {
+    store: Store,
+}
+
+impl Default for SessionRegistry {
+    fn default() -> Self {
+        Self {
+            store: Store::new(REGISTRY_FILE, SCHEMA, "session registry").with_setup(reset_ids),
+        }
+    }
+}
+
+impl SessionRegistry {
+    fn with<T>(
+        &self,
+        app: &AppHandle,
+        f: impl FnOnce(&mut Connection) -> rusqlite::Result<T>,
+    ) -> Result<T, String> {
+        self.store.with(app, f)
+    }
+}
+
+/// Resources recorded for one issue
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IssueSession {
+    repo: String,
+    issue: u64,
+    worktree: Option<String>,
+    branch: Option<String>,
+    pub(crate) container_id: Option<String>,
+    pty_ids: Vec<u32>,
+    agent_id: Option<u32>,
+    created_at: String,
+    updated_at: String,
+}
+
+/// Changes for `update_issue_session`; omitted fields are left alone and
+/// `null` clears a field
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionUpdate {
+    #[serde(default, deserialize_with = "crate::github::double_option")]
+    pub(crate) worktree: Option<Option<String>>,
+    #[serde(default, deserialize_with = "crate::github::double_option")]
+    pub(crate) branch: Option<Option<String>>,
+    #[serde(default, deserialize_with = "crate::github::double_option")]
+    pub(crate) container_id: Option<Option<String>>,
+    #[serde(default, deserialize_with = "crate::github::double_option")]
+    pub(crate) agent_id: Option<Option<u32>>,
+    #[serde(default)]
+    pub(crate) add_pty_ids: Vec<u32>,
+    #[serde(default)]
+    pub(crate) remove_pty_ids: Vec<u32>,
+}
+
+/// Filters for `find_issue_session`
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionQuery {
+    pty_id: Option<u32>,
+    agent_id: Option<u32>,
+    container_id: Option<String>,
+    worktree: Option<String>,
+}
+
+/// Payload of `issue-session-removed`
+#[derive(Clone, Serialize)]
+struct SessionRemovedEvent {
+    repo: String,
+    issue: u64,
+}
+
+const COLUMNS: &str =
+    "repo, issue, worktree, branch, container_id, pty_ids, agent_id, created_at, updated_at";
+
+fn from_row(row: &Row) -> rusqlite::Result<IssueSession> {
+    let pty_ids: String = row.get(5)?;
+    Ok(IssueSession {
+        repo: row.get(0)?,
+        issue: row.get::<_, i64>(1)? as u64,
+        worktree: row.get(2)?,
+        branch: row.get(3)?,
+        container_id: row.get(4)?,
+        pty_ids: serde_json::from_str(&pty_ids).unwrap_or_default(),
+        agent_id: row.get(6)?,
+        created_at: row.get(7)?,
+        updated_at: row.get(8)?,
+    })
+}
+
+fn load(conn: &Connection, repo: &str, issue: u64) -> rusqlite::Result<Option<IssueSession>> {
+    conn.query_row(
+        &format!(
+            "SELECT {} FROM sessions WHERE repo = ?1 AND issue = ?2",
+            COLUMNS
+        ),
+        params![repo, issue as i64],
+        from_row,
+    )
+    .optional()
+}
+
+fn now() -> String {
+    chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+}
+
+/// Apply `update` to the issue's record, creating it if needed
+pub(crate) fn update_session(
+    app: &AppHandle,
+    registry: &SessionRegistry,
+    repo: &str,
+    issue: u64,
+    update: SessionUpdate,
+) -> Result<IssueSession, String> {
+    let session = registry.with(app, |conn| {
+        let tx = conn.transaction()?;
+        let now = now();
+        let mut session = load(&tx, repo, issue)?.unwrap_or_else(|| IssueSession {
+            repo: repo.to_string(),
+            issue,
+            worktree: None,
+            branch: None,
+            container_id: None,
+            pty_ids: Vec::new(),
+            agent_id: None,
+            created_at: now.clone(),
+            updated_at: now.clone(),
+        });
+
+        if let Some(worktree) = update.worktree {
+            session.worktree = worktree;
+        }
+        if let Some(branch) = update.branch {
+            session.branch = branch;
+        }
+        if let Some(container_id) = update.container_id {
+            session.container_id = container_id;
+        }
+        if let Some(agent_id) = update.agent_id {
+            session.agent_id = agent_id;
+        }
+        session
+            .pty_ids
+            .retain(|id| !update.remove_pty_ids.contains(id));
+        for id in update.add_pty_ids {
+            if !session.pty_ids.contains(&id) {
+                session.pty_ids.push(id);
+            }
+        }
+        session.updated_at = now;
+
+        tx.execute(
+            &format!(
+                "INSERT OR REPLACE INTO sessions ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                COLUMNS
+            ),
+            params![
+                session.repo,
+                session.issue as i64,
+                session.worktree,
+                session.branch,
+                session.container_id,
+                serde_json::to_string(&session.pty_ids).unwrap_or_else(|_| "[]".to_string()),
+                session.agent_id,
+                session.created_at,
+                session.updated_at,
+            ],
+        )?;
+        tx.commit()?;
+        Ok(session)
+    })?;
+
+    let _ = app.emit("issue-session-updated", session.clone());
+    Ok(session)
+}
+
+/// Delete the issue's record
+pub(crate) fn remove_session(
+    app: &AppHandle,
+    registry: &SessionRegistry,
+    repo: &str,
+    issue: u64,
+) -> Result<(), String> {
+    registry.with(app, |conn| {
+        conn.execute(
+            "DELETE FROM sessions WHERE repo = ?1 AND issue = ?2",
+            params![repo, issue as i64],
+        )
+    })?;
+    let _ = app.emit(
+        "issue-session-removed",
+        SessionRemovedEvent {
+            repo: repo.to_string(),
+            issue,
+        },
+    );
+    Ok(())
+}
+
+/// Forget sessions whose worktree has been deleted, returning how many
+pub(crate) fn reap_stale(app: &AppHandle, registry: &SessionRegistry) -> Result<usize, String> {
+    let stale: Vec<(String, u64)> = registry.with(app, |conn| {
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM sessions", COLUMNS))?;
+        let rows = stmt.query_map([], from_row)?;
+        rows.filter_map(|session| match session {
+            Ok(session)
+                if session
+                    .worktree
+                    .as_deref()
+                    .is_some_and(|path| !std::path::Path::new(path).exists()) =>
+            {
+                Some(Ok((session.repo, session.issue)))
+            }
+            Ok(_) => None,
+            Err(e) => Some(Err(e)),
+        })
+        .collect()
+    })?;
+
+    for (repo, issue) in &stale {
+        remove_session(app, registry, repo, *issue)?;
+    }
+    Ok(stale.len())
+}
+
+/// Record resources for an issue, merging with what is already known
+#[tauri::command]
+pub async fn update_issue_session(
+    app: AppHandle,
+    registry: State<'_, SessionRegistry>,
+    repo: String,
+    issue: u64,
+    update: SessionUpdate,
+) -> Result<IssueSession, String> {
+    update_session(&app, &registry, &repo, issue, update)
+}
+
+/// Resources recorded for an issue, if any
+#[tauri::command]
+pub async fn get_issue_session(
+    app: AppHandle,
+    registry: State<'_, SessionRegistry>,
+    repo: String,
+    issue: u64,
+) -> Result<Option<IssueSession>, String> {
+    registry.with(&app, |conn| load(conn, &repo, issue))
+}
+
+/// All recorded sessions, optionally for one repository
+#[tauri::command]
+pub async fn list_issue_sessions(
+    app: AppHandle,
+    registry: State<'_, SessionRegistry>,
+    repo: Option<String>,
+) -> Result<Vec<IssueSession>, String> {
+    registry.with(&app, |conn| {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM sessions WHERE ?1 IS NULL OR repo = ?1 ORDER BY repo, issue",
+            COLUMNS
+        ))?;
+        let rows = stmt.query_map([repo], from_row)?;
+        rows.collect()
+    })
+}
+
+/// Session owning a PTY, agent, container or worktree
+#[tauri::command]
+pub async fn find_issue_session(
+    app: AppHandle,
+    registry: State<'_, SessionRegistry>,
+    query: SessionQuery,
+) -> Result<Option<IssueSession>, String> {
+    if query.pty_id.is_none()
+        && query.agent_id.is_none()
+        && query.container_id.is_none()
+        && query.worktree.is_none()
+    {
+        return Err("No filter given".to_string());
+    }
+
+    let sessions = list_issue_sessions(app, registry, None).await?;
+    Ok(sessions.into_iter().find(|session| {
+        query.pty_id.is_none_or(|id| session.pty_ids.contains(&id))
+            && query.agent_id.is_none_or(|id| session.agent_id == Some(id))
+            && query
+                .container_id
+                .as_ref()
+                .is_none_or(|id| session.container_id.as_ref() == Some(id))
+            && query.worktree.as_ref().is_none_or(|path| {
+                session
+                    .worktree
+                    .as_deref()
+                    .is_some_and(|worktree| crate::git::worktree::same_path(worktree, path))
+            })
+    }))
+}
+
+/// Forget an issue's resources (the resources themselves are left alone)
+#[tauri::command]
+pub async fn remove_issue_session(
+    app: AppHandle,
+    registry: State<'_, SessionRegistry>,
+    repo: String,
+    issue: u64,
+) -> Result<(), String> {
+    remove_session(&app, &registry, &repo, issue)
+}
@@ -0,0 +1,151 @@
+//! Sleep prevention - keeping the machine awake while agents work
+//!
+//! While any agent is running or waiting, Antler holds a power assertion on
+//! macOS, a systemd-logind inhibitor on Linux and an execution state on
+//! Windows, so an overnight run isn't suspended halfway through; it is let go
+//! as soon as the last one finishes. The display may still sleep unless
+//! `keepDisplayOn` is set. Turn it off altogether with `set_sleep_settings`.
+
+use crate::agents::{self, AgentState};
+use crate::tray;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::sync::mpsc;
+use std::thread;
+use tauri::{AppHandle, Listener, Manager, State};
+
+/// When sleep is prevented
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SleepSettings {
+    /// Keep the system awake while agents are active
+    enabled: bool,
+    /// Keep the display on too
+    keep_display_on: bool,
+}
+
+impl Default for SleepSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            keep_display_on: false,
+        }
+    }
+}
+
+/// State for sleep prevention
+#[derive(Default)]
+pub struct SleepState {
+    settings: Mutex<SleepSettings>,
+    hold: Mutex<Option<Hold>>,
+}
+
+/// A held wake lock, released when dropped
+struct Hold {
+    keep_display_on: bool,
+    /// Closing the channel ends the thread holding the lock
+    _release: mpsc::Sender<()>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SleepStatus {
+    #[serde(flatten)]
+    settings: SleepSettings,
+    /// Whether sleep is being prevented right now
+    preventing: bool,
+}
+
+/// Take a wake lock. Windows ties it to the thread that took it, so it is
+/// held by a thread of its own.
+fn acquire(keep_display_on: bool) -> Result<Hold, String> {
+    let (release, released) = mpsc::channel::<()>();
+    let (ready_tx, ready) = mpsc::channel();
+    thread::spawn(move || {
+        let awake = keepawake::Builder::default()
+            .idle(true)
+            .display(keep_display_on)
+            .reason("Agent sessions are running")
+            .app_name("Antler")
+            .app_reverse_domain("com.antler.app")
+            .create();
+        match awake {
+            Ok(awake) => {
+                let _ = ready_tx.send(Ok(()));
+                // Returns once the sender is dropped
+                let _ = released.recv();
+                drop(awake);
+            }
+            Err(e) => {
+                let _ = ready_tx.send(Err(e.to_string()));
+            }
+        }
+    });
+    ready
+        .recv()
+        .map_err(|_| "The wake lock thread exited".to_string())?
+        .map_err(|e| format!("Failed to prevent sleep: {}", e))?;
+    Ok(Hold {
+        keep_display_on,
+        _release: release,
+    })
+}
+
+/// Take or release the wake lock to match the agents and settings
+fn update(app: &AppHandle) {
+    let state = app.state::<SleepState>();
+    // Taken first so overlapping updates each see the latest agents
+    let mut hold = state.hold.lock();
+    let settings = state.settings.lock().clone();
+    let wanted = settings.enabled
+        && agents::agent_list(&app.state::<AgentState>())
+            .iter()
+            .any(tray::is_active);
+    match hold.as_ref() {
+        Some(current) if wanted && current.keep_display_on == settings.keep_display_on => {}
+        None if !wanted => {}
+        _ => {
+            // Let go of a lock with the wrong display setting before the new one
+            *hold = None;
+            if wanted {
+                match acquire(settings.keep_display_on) {
+                    Ok(acquired) => *hold = Some(acquired),
+                    Err(e) => eprintln!("{}", e),
+                }
+            }
+        }
+    }
+}
+
+/// Update the wake lock off the calling thread; taking one can mean a round
+/// trip to the system bus
+fn schedule_update(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || update(&app));
+}
+
+/// Follow the agents as they start and finish
+pub(crate) fn init(app: &AppHandle) {
+    let handle = app.clone();
+    app.listen("agent-state", move |_| schedule_update(&handle));
+}
+
+/// Choose whether and how sleep is prevented while agents are active
+#[tauri::command]
+pub async fn set_sleep_settings(
+    app: AppHandle,
+    state: State<'_, SleepState>,
+    settings: SleepSettings,
+) -> Result<(), String> {
+    *state.settings.lock() = settings;
+    schedule_update(&app);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_sleep_status(state: State<'_, SleepState>) -> Result<SleepStatus, String> {
+    Ok(SleepStatus {
+        settings: state.settings.lock().clone(),
+        preventing: state.hold.lock().is_some(),
+    })
+}
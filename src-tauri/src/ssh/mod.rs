@@ -0,0 +1,609 @@
+//! SSH - host profiles and connections to remote dev boxes
+//!
+//! Profiles (host, user, port, identity file and an optional jump host,
+//! itself a profile) live in a SQLite file in the app data directory.
+//! Connections check the server's key against `~/.ssh/known_hosts` and
+//! refuse hosts that aren't in it or whose key changed; `scan_ssh_host_key`
+//! shows an unknown host's fingerprint and `trust_ssh_host` adds it once the
+//! user compared it. Authentication tries the keys of the running ssh-agent,
+//! then the profile's identity file or the default ones in `~/.ssh`, with a
+//! passphrase kept in the OS keychain by `set_ssh_passphrase`. Jump hosts are
+//! reached first and the target through a direct-tcpip channel on them.
+//!
+//! libssh2 sessions are used non-blocking once connected, so a terminal's
+//! output thread and its input never wait on each other; SFTP connections
+//! (see `sftp`) stay blocking, with a timeout.
+
+pub mod session;
+pub mod sftp;
+
+use crate::store::Store;
+use base64::engine::general_purpose::{STANDARD, STANDARD_NO_PAD};
+use base64::Engine;
+use keyring::Entry;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use ssh2::{Channel, CheckResult, HashType, HostKeyType, KnownHostFileKind, KnownHosts, Session};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State};
+
+const SSH_FILE: &str = "ssh.sqlite";
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS hosts (
+    name TEXT PRIMARY KEY,
+    host TEXT NOT NULL,
+    user TEXT NOT NULL,
+    port INTEGER NOT NULL,
+    identity TEXT,
+    jump_host TEXT,
+    added_at TEXT NOT NULL
+);
+";
+
+const KEYCHAIN_SERVICE: &str = "com.antler.app";
+
+/// Keys tried in `~/.ssh` when a profile names no identity file
+const DEFAULT_IDENTITIES: &[&str] = &["id_ed25519", "id_ecdsa", "id_rsa"];
+
+/// Time to reach a host and finish the handshake and authentication
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Jump hosts a connection may pass through, which also stops loops
+const MAX_JUMPS: usize = 4;
+
+/// How often an idle connection tells the server it is still there
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Wait between polls of a non-blocking session with nothing to do
+pub(crate) const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Longest a single read, write or request may keep coming back
+/// "would block" before the connection counts as stuck
+const IO_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Lazily opened profile database
+pub struct SshState {
+    store: Store,
+}
+
+impl Default for SshState {
+    fn default() -> Self {
+        Self {
+            store: Store::new(SSH_FILE, SCHEMA, "SSH hosts"),
+        }
+    }
+}
+
+impl SshState {
+    fn with<T>(
+        &self,
+        app: &AppHandle,
+        f: impl FnOnce(&mut Connection) -> rusqlite::Result<T>,
+    ) -> Result<T, String> {
+        self.store.with(app, f)
+    }
+}
+
+fn default_port() -> u16 {
+    22
+}
+
+/// A remote machine and how to log in to it
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SshHost {
+    /// Name the profile is saved and picked under
+    pub(crate) name: String,
+    pub(crate) host: String,
+    pub(crate) user: String,
+    #[serde(default = "default_port")]
+    pub(crate) port: u16,
+    /// Private key file, tried after the agent's keys
+    #[serde(default)]
+    identity: Option<String>,
+    /// Profile of the host to connect through
+    #[serde(default)]
+    jump_host: Option<String>,
+}
+
+impl SshHost {
+    fn address(&self) -> String {
+        format!("{}@{}:{}", self.user, self.host, self.port)
+    }
+}
+
+/// Where a host's key stands against `known_hosts`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HostKeyStatus {
+    Known,
+    /// The host is listed with a different key
+    Changed,
+    Unknown,
+}
+
+/// The key a host presented
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostKeyInfo {
+    /// `SHA256:...`, as `ssh` prints it
+    fingerprint: String,
+    key_type: String,
+    status: HostKeyStatus,
+}
+
+const COLUMNS: &str = "name, host, user, port, identity, jump_host";
+
+fn from_row(row: &Row) -> rusqlite::Result<SshHost> {
+    Ok(SshHost {
+        name: row.get(0)?,
+        host: row.get(1)?,
+        user: row.get(2)?,
+        port: row.get(3)?,
+        identity: row.get(4)?,
+        jump_host: row.get(5)?,
+    })
+}
+
+/// The profile saved as `name`
+pub(crate) fn lookup(app: &AppHandle, name: &str) -> Result<SshHost, String> {
+    app.state::<SshState>()
+        .with(app, |conn| {
+            conn.query_row(
+                &format!("SELECT {} FROM hosts WHERE name = ?1", COLUMNS),
+                [name],
+                from_row,
+            )
+            .optional()
+        })?
+        .ok_or_else(|| format!("No SSH host named '{}'", name))
+}
+
+fn would_block(e: &io::Error) -> bool {
+    e.kind() == io::ErrorKind::WouldBlock
+}
+
+/// Run `op` on a non-blocking session until it stops asking to be retried
+pub(crate) fn retry<T>(mut op: impl FnMut() -> io::Result<T>) -> io::Result<T> {
+    let deadline = Instant::now() + IO_TIMEOUT;
+    loop {
+        match op() {
+            Err(e) if would_block(&e) && Instant::now() < deadline => thread::sleep(POLL_INTERVAL),
+            result => return result,
+        }
+    }
+}
+
+/// `write_all` for non-blocking writers
+pub(crate) fn write_all(writer: &mut impl Write, mut data: &[u8]) -> io::Result<()> {
+    while !data.is_empty() {
+        match retry(|| writer.write(data))? {
+            0 => return Err(io::Error::from(io::ErrorKind::WriteZero)),
+            n => data = &data[n..],
+        }
+    }
+    Ok(())
+}
+
+/// Send a keepalive if one is due; an error means the connection is gone
+pub(crate) fn keepalive(session: &Session) -> io::Result<()> {
+    match session.keepalive_send().map_err(io::Error::from) {
+        Err(e) if !would_block(&e) => Err(e),
+        _ => Ok(()),
+    }
+}
+
+fn home_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .home_dir()
+        .map_err(|e| format!("No home directory: {}", e))
+}
+
+fn known_hosts_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(home_dir(app)?.join(".ssh").join("known_hosts"))
+}
+
+/// `known_hosts` as libssh2 reads it, line by line so that entries it
+/// doesn't understand (certificate authorities, security keys) are skipped
+/// rather than failing the whole file
+fn known_hosts(app: &AppHandle, session: &Session) -> Result<KnownHosts, String> {
+    let mut known = session
+        .known_hosts()
+        .map_err(|e| format!("Failed to read known hosts: {}", e))?;
+    let path = known_hosts_path(app)?;
+    if let Ok(text) = fs::read_to_string(&path) {
+        for line in text.lines() {
+            let line = line.trim();
+            if !line.is_empty() && !line.starts_with('#') {
+                let _ = known.read_str(line, KnownHostFileKind::OpenSSH);
+            }
+        }
+    }
+    Ok(known)
+}
+
+fn key_type_name(kind: HostKeyType) -> &'static str {
+    match kind {
+        HostKeyType::Rsa => "ssh-rsa",
+        HostKeyType::Dss => "ssh-dss",
+        HostKeyType::Ecdsa256 => "ecdsa-sha2-nistp256",
+        HostKeyType::Ecdsa384 => "ecdsa-sha2-nistp384",
+        HostKeyType::Ecdsa521 => "ecdsa-sha2-nistp521",
+        HostKeyType::Ed25519 => "ssh-ed25519",
+        HostKeyType::Unknown => "unknown",
+    }
+}
+
+fn host_key_info(
+    app: &AppHandle,
+    session: &Session,
+    host: &SshHost,
+) -> Result<HostKeyInfo, String> {
+    let (key, kind) = session
+        .host_key()
+        .ok_or_else(|| format!("{} sent no host key", host.host))?;
+    let hash = session
+        .host_key_hash(HashType::Sha256)
+        .ok_or_else(|| format!("Failed to hash the host key of {}", host.host))?;
+    let status = match known_hosts(app, session)?.check_port(&host.host, host.port, key) {
+        CheckResult::Match => HostKeyStatus::Known,
+        CheckResult::Mismatch => HostKeyStatus::Changed,
+        CheckResult::NotFound => HostKeyStatus::Unknown,
+        CheckResult::Failure => {
+            return Err(format!("Failed to check the host key of {}", host.host));
+        }
+    };
+    Ok(HostKeyInfo {
+        fingerprint: format!("SHA256:{}", STANDARD_NO_PAD.encode(hash)),
+        key_type: key_type_name(kind).to_string(),
+        status,
+    })
+}
+
+fn open_tcp(host: &str, port: u16) -> Result<TcpStream, String> {
+    let addrs = (host, port)
+        .to_socket_addrs()
+        .map_err(|e| format!("Failed to resolve {}: {}", host, e))?;
+    let mut last_error = None;
+    for addr in addrs {
+        match TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(match last_error {
+        Some(e) => format!("Failed to connect to {}:{}: {}", host, port, e),
+        None => format!("{} has no address", host),
+    })
+}
+
+/// Carry bytes between a tunnel's local socket and its channel until
+/// either side closes; the jump host's session ends with it
+fn pump(jump: Session, mut channel: Channel, mut local: TcpStream) {
+    let mut buf = [0u8; 16 * 1024];
+    loop {
+        let mut idle = true;
+        match local.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => {
+                if write_all(&mut channel, &buf[..n]).is_err() {
+                    break;
+                }
+                idle = false;
+            }
+            Err(e) if would_block(&e) => {}
+            Err(_) => break,
+        }
+        match channel.read(&mut buf) {
+            Ok(0) if channel.eof() => break,
+            Ok(0) => {}
+            Ok(n) => {
+                if write_all(&mut local, &buf[..n]).is_err() {
+                    break;
+                }
+                idle = false;
+            }
+            Err(e) if would_block(&e) => {}
+            Err(_) => break,
+        }
+        if idle {
+            if keepalive(&jump).is_err() {
+                break;
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+    let _ = channel.close();
+}
+
+/// A socket reaching `host:port` through `jump`. libssh2 wants a real
+/// socket to run a session over, so the channel is bridged to a loopback
+/// connection.
+fn tunnel(jump: Session, host: &str, port: u16) -> Result<TcpStream, String> {
+    let tunnel_error = |e: io::Error| format!("Failed to open a tunnel to {}: {}", host, e);
+    let channel = jump
+        .channel_direct_tcpip(host, port, None)
+        .map_err(|e| format!("The jump host can't reach {}:{}: {}", host, port, e))?;
+    let listener = TcpListener::bind(("127.0.0.1", 0)).map_err(tunnel_error)?;
+    let client =
+        TcpStream::connect(listener.local_addr().map_err(tunnel_error)?).map_err(tunnel_error)?;
+    let (local, peer) = listener.accept().map_err(tunnel_error)?;
+    // Another local process could have raced us to the port
+    if Some(peer) != client.local_addr().ok() {
+        return Err(format!("Failed to open a tunnel to {}: port taken", host));
+    }
+    local.set_nonblocking(true).map_err(tunnel_error)?;
+    jump.set_blocking(false);
+    thread::spawn(move || pump(jump, channel, local));
+    Ok(client)
+}
+
+/// Reach `host`, through its jump hosts, and complete the handshake
+fn handshake(app: &AppHandle, host: &SshHost, hops: usize) -> Result<Session, String> {
+    let tcp = match &host.jump_host {
+        Some(name) if hops >= MAX_JUMPS => {
+            return Err(format!("Too many jump hosts on the way to {}", name));
+        }
+        Some(name) => {
+            let jump = connect_through(app, &lookup(app, name)?, hops + 1)?;
+            tunnel(jump, &host.host, host.port)?
+        }
+        None => open_tcp(&host.host, host.port)?,
+    };
+    let mut session = Session::new().map_err(|e| format!("Failed to start SSH: {}", e))?;
+    session.set_tcp_stream(tcp);
+    session.set_timeout(CONNECT_TIMEOUT.as_millis() as u32);
+    session
+        .handshake()
+        .map_err(|e| format!("SSH handshake with {} failed: {}", host.address(), e))?;
+    Ok(session)
+}
+
+fn passphrase_entry(name: &str) -> Result<Entry, String> {
+    Entry::new(KEYCHAIN_SERVICE, &format!("ssh-passphrase:{}", name)).map_err(|e| e.to_string())
+}
+
+fn load_passphrase(name: &str) -> Result<Option<String>, String> {
+    match passphrase_entry(name)?.get_password() {
+        Ok(passphrase) => Ok(Some(passphrase)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(format!("Failed to read the key passphrase: {}", e)),
+    }
+}
+
+fn authenticate(app: &AppHandle, session: &Session, host: &SshHost) -> Result<(), String> {
+    if let Ok(mut agent) = session.agent() {
+        if agent.connect().is_ok() && agent.list_identities().is_ok() {
+            for identity in agent.identities().unwrap_or_default() {
+                if agent.userauth(&host.user, &identity).is_ok() {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    let keys: Vec<PathBuf> = match &host.identity {
+        Some(identity) => vec![PathBuf::from(identity)],
+        None => {
+            let dir = home_dir(app)?.join(".ssh");
+            DEFAULT_IDENTITIES
+                .iter()
+                .map(|name| dir.join(name))
+                .collect()
+        }
+    };
+    let passphrase = load_passphrase(&host.name)?;
+    for key in keys.iter().filter(|key| key.is_file()) {
+        let result = session.userauth_pubkey_file(&host.user, None, key, passphrase.as_deref());
+        if result.is_ok() && session.authenticated() {
+            return Ok(());
+        }
+    }
+    Err(format!(
+        "Authentication as {} failed: no key from the agent or {} was accepted",
+        host.address(),
+        match &host.identity {
+            Some(identity) => identity.clone(),
+            None => "~/.ssh".to_string(),
+        }
+    ))
+}
+
+fn connect_through(app: &AppHandle, host: &SshHost, hops: usize) -> Result<Session, String> {
+    let session = handshake(app, host, hops)?;
+    let info = host_key_info(app, &session, host)?;
+    match info.status {
+        HostKeyStatus::Known => {}
+        HostKeyStatus::Changed => {
+            return Err(format!(
+                "The host key of {} changed to {}. Someone may be intercepting the \
+                 connection; if the change is expected, remove the old key from \
+                 ~/.ssh/known_hosts",
+                host.host, info.fingerprint
+            ));
+        }
+        HostKeyStatus::Unknown => {
+            return Err(format!(
+                "{} is not a known host. Its key fingerprint is {}",
+                host.host, info.fingerprint
+            ));
+        }
+    }
+    authenticate(app, &session, host)?;
+    session.set_timeout(0);
+    session.set_keepalive(false, KEEPALIVE_INTERVAL.as_secs() as u32);
+    Ok(session)
+}
+
+/// A verified, authenticated session with `host`
+pub(crate) fn connect(app: &AppHandle, host: &SshHost) -> Result<Session, String> {
+    connect_through(app, host, 0)
+}
+
+/// Saved SSH host profiles, by name
+#[tauri::command]
+pub async fn list_ssh_hosts(
+    app: AppHandle,
+    state: State<'_, SshState>,
+) -> Result<Vec<SshHost>, String> {
+    state.with(&app, |conn| {
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM hosts ORDER BY name", COLUMNS))?;
+        let rows = stmt.query_map([], from_row)?;
+        rows.collect()
+    })
+}
+
+/// Save a host profile, replacing the one with the same name
+#[tauri::command]
+pub async fn save_ssh_host(
+    app: AppHandle,
+    state: State<'_, SshState>,
+    host: SshHost,
+) -> Result<SshHost, String> {
+    if host.name.trim().is_empty() || host.host.trim().is_empty() || host.user.trim().is_empty() {
+        return Err("An SSH host needs a name, a host and a user".to_string());
+    }
+    if host.port == 0 {
+        return Err("Invalid SSH port 0".to_string());
+    }
+    if host.jump_host.as_deref() == Some(host.name.as_str()) {
+        return Err(format!("{} can't be its own jump host", host.name));
+    }
+    let now = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+    state.with(&app, |conn| {
+        conn.execute(
+            "INSERT INTO hosts (name, host, user, port, identity, jump_host, added_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT(name) DO UPDATE SET
+                 host = ?2, user = ?3, port = ?4, identity = ?5, jump_host = ?6",
+            params![
+                host.name,
+                host.host,
+                host.user,
+                host.port,
+                host.identity,
+                host.jump_host,
+                now
+            ],
+        )
+    })?;
+    Ok(host)
+}
+
+/// Delete a host profile and its stored passphrase
+#[tauri::command]
+pub async fn remove_ssh_host(
+    app: AppHandle,
+    state: State<'_, SshState>,
+    name: String,
+) -> Result<(), String> {
+    state.with(&app, |conn| {
+        conn.execute("DELETE FROM hosts WHERE name = ?1", [&name])
+    })?;
+    match passphrase_entry(&name)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(format!("Failed to remove the key passphrase: {}", e)),
+    }
+}
+
+/// Store the passphrase of a profile's identity file in the keychain, or
+/// remove it with `None`
+#[tauri::command]
+pub async fn set_ssh_passphrase(name: String, passphrase: Option<String>) -> Result<(), String> {
+    let entry = passphrase_entry(&name)?;
+    match passphrase {
+        Some(passphrase) => entry
+            .set_password(&passphrase)
+            .map_err(|e| format!("Failed to store the key passphrase: {}", e)),
+        None => match entry.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(format!("Failed to remove the key passphrase: {}", e)),
+        },
+    }
+}
+
+/// The key a host presents and whether `known_hosts` lists it
+#[tauri::command]
+pub async fn scan_ssh_host_key(app: AppHandle, name: String) -> Result<HostKeyInfo, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let host = lookup(&app, &name)?;
+        let session = handshake(&app, &host, 0)?;
+        host_key_info(&app, &session, &host)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Add a host's key to `known_hosts`, if it still has the `fingerprint`
+/// the user was shown. A changed key is never replaced here.
+#[tauri::command]
+pub async fn trust_ssh_host(
+    app: AppHandle,
+    name: String,
+    fingerprint: String,
+) -> Result<HostKeyInfo, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let host = lookup(&app, &name)?;
+        let session = handshake(&app, &host, 0)?;
+        let info = host_key_info(&app, &session, &host)?;
+        if info.fingerprint != fingerprint {
+            return Err(format!(
+                "The host key of {} is now {}, not the one shown",
+                host.host, info.fingerprint
+            ));
+        }
+        match info.status {
+            HostKeyStatus::Known => return Ok(info),
+            HostKeyStatus::Changed => {
+                return Err(format!(
+                    "{} is listed with another key; remove it from ~/.ssh/known_hosts first",
+                    host.host
+                ));
+            }
+            HostKeyStatus::Unknown => {}
+        }
+
+        let (key, _) = session
+            .host_key()
+            .ok_or_else(|| format!("{} sent no host key", host.host))?;
+        let pattern = if host.port == 22 {
+            host.host.clone()
+        } else {
+            format!("[{}]:{}", host.host, host.port)
+        };
+        let line = format!("{} {} {}\n", pattern, info.key_type, STANDARD.encode(key));
+        let path = known_hosts_path(&app)?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+        // Keep the file's last entry intact if it lacks a final newline
+        let ends_with_newline = fs::read(&path)
+            .map(|data| data.is_empty() || data.ends_with(b"\n"))
+            .unwrap_or(true);
+        let line = if ends_with_newline {
+            line
+        } else {
+            format!("\n{}", line)
+        };
+        file.write_all(line.as_bytes())
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        Ok(HostKeyInfo {
+            status: HostKeyStatus::Known,
+            ..info
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
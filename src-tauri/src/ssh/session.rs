@@ -0,0 +1,402 @@
+//! Remote PTY sessions over SSH
+//!
+//! `spawn_ssh_pty` opens a shell (or runs a command) on a saved host in a
+//! remote PTY and registers it with the local ones, so `pty-data`,
+//! `pty-exit`, `write_pty`, `resize_pty`, `kill_pty` and pop-outs work the
+//! same for it.
+//!
+//! When the connection drops, the session emits `session-degraded` and
+//! reconnects with backoff for a few minutes, emitting `session-recovered`
+//! once it is back; input sent meanwhile fails rather than queueing up. A
+//! session with a `backend` runs inside a remote tmux or screen session of
+//! that name and reattaches to it, picking up where it was. Without one a
+//! login shell comes back as a new shell, and a command isn't run again:
+//! the session ends, with `pty-exit` following `session-degraded`.
+
+use super::{connect, keepalive, lookup, retry, write_all, POLL_INTERVAL};
+use crate::proxy;
+use crate::pty::{self, PtyProcess, PtyState};
+use parking_lot::Mutex;
+use portable_pty::PtySize;
+use serde::{Deserialize, Serialize};
+use ssh2::{Channel, Session};
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// How long a dropped session keeps trying to reconnect
+const RECONNECT_WINDOW: Duration = Duration::from_secs(5 * 60);
+
+/// Longest wait between reconnection attempts
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Options for `spawn_ssh_pty`
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SshSpawnOptions {
+    /// Name of the saved host profile
+    host: String,
+    cols: u16,
+    rows: u16,
+    /// Run this instead of the login shell
+    #[serde(default)]
+    command: Option<String>,
+    /// Remote directory to start in
+    #[serde(default)]
+    cwd: Option<String>,
+    /// Sent to the server, which keeps only what its `AcceptEnv` allows
+    #[serde(default)]
+    env: HashMap<String, String>,
+    /// Remote multiplexer session to run in and reattach to
+    #[serde(default)]
+    backend: Option<RemoteBackend>,
+}
+
+/// A tmux or screen session on the host, created on first attach
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteBackend {
+    kind: Multiplexer,
+    name: String,
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Multiplexer {
+    Tmux,
+    Screen,
+}
+
+/// Payload of `session-degraded`
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SessionDegradedEvent {
+    pty_id: u32,
+    reason: String,
+    /// False when the session ends instead
+    reconnecting: bool,
+}
+
+/// Payload of `session-recovered`
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SessionRecoveredEvent {
+    pty_id: u32,
+    /// Whether it is back in its tmux or screen session rather than in a new
+    /// shell
+    reattached: bool,
+}
+
+/// An open remote PTY
+#[derive(Clone)]
+struct Connection {
+    session: Session,
+    channel: Channel,
+}
+
+impl Connection {
+    fn open(app: &AppHandle, options: &SshSpawnOptions, size: PtySize) -> Result<Self, String> {
+        let host = lookup(app, &options.host)?;
+        let session = connect(app, &host)?;
+        let channel_error =
+            |e: ssh2::Error| format!("Failed to open a shell on {}: {}", host.host, e);
+
+        let mut channel = session.channel_session().map_err(channel_error)?;
+        for (key, value) in &options.env {
+            let _ = channel.setenv(key, value);
+        }
+        for (key, value) in proxy::session_env(app, Some(&options.host)) {
+            if !options.env.contains_key(&key) {
+                let _ = channel.setenv(&key, &value);
+            }
+        }
+        channel
+            .request_pty(
+                "xterm-256color",
+                None,
+                Some((size.cols.into(), size.rows.into(), 0, 0)),
+            )
+            .map_err(channel_error)?;
+        match remote_command(options) {
+            Some(command) => channel.exec(&command),
+            None => channel.shell(),
+        }
+        .map_err(channel_error)?;
+        session.set_blocking(false);
+        Ok(Self { session, channel })
+    }
+}
+
+/// A remote session and its connection, which is replaced on reconnecting
+struct Remote {
+    app: AppHandle,
+    options: SshSpawnOptions,
+    /// `None` while reconnecting
+    connection: Mutex<Option<Connection>>,
+    size: Mutex<PtySize>,
+    /// Set by `kill`, so a hang-up isn't taken for a dropped connection
+    closed: AtomicBool,
+    pty_id: OnceLock<u32>,
+}
+
+impl Remote {
+    fn current(&self) -> Option<Connection> {
+        self.connection.lock().clone()
+    }
+
+    fn emit<S: Serialize + Clone>(&self, event: &str, payload: impl FnOnce(u32) -> S) {
+        if let Some(&id) = self.pty_id.get() {
+            let _ = self.app.emit(event, payload(id));
+        }
+    }
+
+    /// Try to get the connection back after it failed with `error`,
+    /// returning whether it did
+    fn reconnect(&self, error: io::Error) -> bool {
+        *self.connection.lock() = None;
+        if self.closed.load(Ordering::SeqCst) {
+            return false;
+        }
+        let reconnecting = self.options.backend.is_some() || self.options.command.is_none();
+        self.emit("session-degraded", |pty_id| SessionDegradedEvent {
+            pty_id,
+            reason: format!("Connection to {} lost: {}", self.options.host, error),
+            reconnecting,
+        });
+        if !reconnecting {
+            return false;
+        }
+
+        let deadline = Instant::now() + RECONNECT_WINDOW;
+        let mut backoff = Duration::from_secs(1);
+        while Instant::now() < deadline {
+            thread::sleep(backoff);
+            if self.closed.load(Ordering::SeqCst) {
+                return false;
+            }
+            let size = *self.size.lock();
+            match Connection::open(&self.app, &self.options, size) {
+                Ok(connection) => {
+                    // Sizes set while it was down would otherwise be lost
+                    let size = *self.size.lock();
+                    let _ = resize_channel(&connection.channel, size);
+                    *self.connection.lock() = Some(connection);
+                    self.emit("session-recovered", |pty_id| SessionRecoveredEvent {
+                        pty_id,
+                        reattached: self.options.backend.is_some(),
+                    });
+                    return true;
+                }
+                Err(e) => eprintln!("Reconnecting to {} failed: {}", self.options.host, e),
+            }
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+        false
+    }
+}
+
+fn resize_channel(channel: &Channel, size: PtySize) -> io::Result<()> {
+    // Handles to a channel share it, and this one needs to be mutable
+    let mut channel = channel.clone();
+    retry(|| {
+        channel
+            .request_pty_size(size.cols.into(), size.rows.into(), None, None)
+            .map_err(io::Error::from)
+    })
+}
+
+/// The shell at the other end of a remote session
+struct RemoteProcess {
+    remote: Arc<Remote>,
+}
+
+impl PtyProcess for RemoteProcess {
+    fn resize(&self, size: PtySize) -> Result<(), String> {
+        *self.remote.size.lock() = size;
+        match self.remote.current() {
+            Some(connection) => resize_channel(&connection.channel, size)
+                .map_err(|e| format!("Failed to resize remote PTY: {}", e)),
+            // Applied once reconnected
+            None => Ok(()),
+        }
+    }
+
+    fn kill(&mut self) {
+        self.remote.closed.store(true, Ordering::SeqCst);
+        // Hanging up ends the remote shell, as closing a terminal would
+        if let Some(connection) = self.remote.current() {
+            let _ = retry(|| {
+                connection
+                    .session
+                    .disconnect(None, "Session closed", None)
+                    .map_err(io::Error::from)
+            });
+        }
+    }
+
+    fn has_exited(&mut self) -> bool {
+        // Once hung up there is nothing left to wait for
+        self.remote.closed.load(Ordering::SeqCst)
+            || self
+                .remote
+                .current()
+                .is_some_and(|connection| connection.channel.eof())
+    }
+
+    fn process_id(&self) -> Option<u32> {
+        None
+    }
+}
+
+/// Blocking reads from a non-blocking channel, for the PTY output thread,
+/// which also reconnects
+struct ChannelReader {
+    remote: Arc<Remote>,
+}
+
+impl ChannelReader {
+    /// Read from the connection, `Ok(None)` while it has nothing yet
+    fn poll(connection: &Connection, buf: &mut [u8]) -> io::Result<Option<usize>> {
+        match connection.channel.stream(0).read(buf) {
+            Ok(0) if !connection.channel.eof() => {}
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+            result => return result.map(Some),
+        }
+        keepalive(&connection.session)?;
+        Ok(None)
+    }
+}
+
+impl Read for ChannelReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let Some(connection) = self.remote.current() else {
+                return Ok(0);
+            };
+            match Self::poll(&connection, buf) {
+                Ok(Some(n)) => return Ok(n),
+                Ok(None) => thread::sleep(POLL_INTERVAL),
+                Err(e) if self.remote.closed.load(Ordering::SeqCst) => return Err(e),
+                Err(e) => {
+                    let message = e.to_string();
+                    if !self.remote.reconnect(e) {
+                        return Err(io::Error::new(io::ErrorKind::ConnectionAborted, message));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Blocking writes to a non-blocking channel
+struct ChannelWriter {
+    remote: Arc<Remote>,
+}
+
+impl ChannelWriter {
+    fn stream(&self) -> io::Result<ssh2::Stream> {
+        self.remote
+            .current()
+            .map(|connection| connection.channel.stream(0))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotConnected, "Reconnecting"))
+    }
+}
+
+impl Write for ChannelWriter {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        write_all(&mut self.stream()?, data).map(|_| data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let mut stream = self.stream()?;
+        retry(|| stream.flush())
+    }
+}
+
+fn quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+/// What the remote end runs, `None` for the login shell
+fn remote_command(options: &SshSpawnOptions) -> Option<String> {
+    if let Some(backend) = &options.backend {
+        // Both attach to the named session if it exists, detaching clients
+        // left behind by a dropped connection, and create it otherwise
+        return Some(match backend.kind {
+            Multiplexer::Tmux => {
+                let mut command = format!("tmux new-session -A -D -s {}", quote(&backend.name));
+                if let Some(cwd) = &options.cwd {
+                    command.push_str(&format!(" -c {}", quote(cwd)));
+                }
+                if let Some(program) = &options.command {
+                    command.push_str(&format!(" {}", quote(program)));
+                }
+                command
+            }
+            Multiplexer::Screen => {
+                let mut command = format!("screen -D -R -S {}", quote(&backend.name));
+                if let Some(program) = &options.command {
+                    command.push_str(&format!(" sh -c {}", quote(program)));
+                }
+                match &options.cwd {
+                    Some(cwd) => format!("cd {} && {}", quote(cwd), command),
+                    None => command,
+                }
+            }
+        });
+    }
+    match (&options.cwd, &options.command) {
+        (None, command) => command.clone(),
+        (Some(cwd), Some(command)) => Some(format!("cd {} && {}", quote(cwd), command)),
+        (Some(cwd), None) => Some(format!("cd {} && exec \"$SHELL\" -l", quote(cwd))),
+    }
+}
+
+/// Open a remote PTY on a saved host, returning its PTY session ID
+#[tauri::command]
+pub async fn spawn_ssh_pty(app: AppHandle, options: SshSpawnOptions) -> Result<u32, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let size = PtySize {
+            rows: options.rows,
+            cols: options.cols,
+            pixel_width: 0,
+            pixel_height: 0,
+        };
+        let connection = Connection::open(&app, &options, size)?;
+        let remote = Arc::new(Remote {
+            app: app.clone(),
+            options,
+            connection: Mutex::new(Some(connection)),
+            size: Mutex::new(size),
+            closed: AtomicBool::new(false),
+            pty_id: OnceLock::new(),
+        });
+
+        let state = app.state::<PtyState>();
+        let id = pty::start_session(
+            &app,
+            &state,
+            Box::new(RemoteProcess {
+                remote: remote.clone(),
+            }),
+            Box::new(ChannelReader {
+                remote: remote.clone(),
+            }),
+            Box::new(ChannelWriter {
+                remote: remote.clone(),
+            }),
+            // Remote shells quote dropped paths the POSIX way
+            "ssh".to_string(),
+            None,
+            None,
+        );
+        let _ = remote.pty_id.set(id);
+        Ok(id)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
@@ -0,0 +1,491 @@
+//! SFTP - files on saved SSH hosts
+//!
+//! Listing, reading and writing go through one SFTP connection per host,
+//! opened on first use and replaced when it drops; a call that finds it
+//! dropped reconnects once before failing. `sftp_upload` and
+//! `sftp_download` run on a background thread, emitting `sftp-progress` a
+//! few times a second and `sftp-done` with the outcome, and they write to a
+//! temporary name that is renamed into place at the end, so a failed or
+//! cancelled transfer never leaves half a file under the real name.
+
+use super::{connect, lookup};
+use crate::files::tree::EntryKind;
+use crate::taskbar;
+use parking_lot::Mutex;
+use serde::Serialize;
+use ssh2::{ErrorCode, FileStat, FileType, OpenFlags, OpenType, Session, Sftp};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+const BUFFER_SIZE: usize = 256 * 1024;
+
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Largest file `sftp_read_file` returns; bigger ones are downloaded
+const MAX_READ_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Time an SFTP request may take before the connection counts as dropped
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// An open SFTP connection
+struct Remote {
+    // Kept so the connection outlives requests in flight
+    _session: Session,
+    sftp: Sftp,
+}
+
+/// SFTP connections and running transfers
+pub struct SftpState {
+    connections: Mutex<HashMap<String, Arc<Remote>>>,
+    transfers: Mutex<HashMap<u32, Arc<AtomicBool>>>,
+    next_id: AtomicU32,
+}
+
+impl Default for SftpState {
+    fn default() -> Self {
+        Self {
+            connections: Mutex::new(HashMap::new()),
+            transfers: Mutex::new(HashMap::new()),
+            next_id: AtomicU32::new(1),
+        }
+    }
+}
+
+/// An entry of a remote directory
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteEntry {
+    name: String,
+    path: String,
+    kind: EntryKind,
+    /// Size in bytes, for files
+    size: Option<u64>,
+    /// Seconds since the Unix epoch
+    modified: Option<u64>,
+    /// Permission bits, such as `0o644`
+    mode: Option<u32>,
+}
+
+/// Event payload for transfer progress
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SftpProgressEvent {
+    id: u32,
+    bytes: u64,
+    total: u64,
+}
+
+/// Event payload for a finished, failed or cancelled transfer
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SftpDoneEvent {
+    id: u32,
+    dest: String,
+    bytes: u64,
+    cancelled: bool,
+    error: Option<String>,
+}
+
+/// The host's SFTP connection and whether it was just opened
+fn remote(app: &AppHandle, name: &str) -> Result<(Arc<Remote>, bool), String> {
+    let state = app.state::<SftpState>();
+    if let Some(remote) = state.connections.lock().get(name) {
+        return Ok((remote.clone(), false));
+    }
+    let session = connect(app, &lookup(app, name)?)?;
+    session.set_timeout(REQUEST_TIMEOUT.as_millis() as u32);
+    let sftp = session
+        .sftp()
+        .map_err(|e| format!("Failed to start SFTP on {}: {}", name, e))?;
+    let remote = Arc::new(Remote {
+        _session: session,
+        sftp,
+    });
+    state
+        .connections
+        .lock()
+        .insert(name.to_string(), remote.clone());
+    Ok((remote, true))
+}
+
+/// Whether the connection still answers, after a request on it failed
+fn alive(sftp: &Sftp) -> bool {
+    match sftp.realpath(Path::new(".")) {
+        Ok(_) => true,
+        Err(e) => !matches!(e.code(), ErrorCode::Session(_)),
+    }
+}
+
+/// Run `op` on the host's SFTP connection, reconnecting once if the one
+/// kept from before dropped
+fn with_sftp<T>(
+    app: &AppHandle,
+    name: &str,
+    mut op: impl FnMut(&Sftp) -> io::Result<T>,
+) -> Result<T, String> {
+    let (current, fresh) = remote(app, name)?;
+    match op(&current.sftp) {
+        Err(e) if !alive(&current.sftp) => {
+            app.state::<SftpState>().connections.lock().remove(name);
+            if fresh {
+                return Err(format!("SFTP connection to {} dropped: {}", name, e));
+            }
+            let (current, _) = remote(app, name)?;
+            op(&current.sftp).map_err(|e| e.to_string())
+        }
+        result => result.map_err(|e| e.to_string()),
+    }
+}
+
+fn entry_kind(stat: &FileStat) -> EntryKind {
+    match stat.file_type() {
+        FileType::Directory => EntryKind::Dir,
+        FileType::RegularFile => EntryKind::File,
+        FileType::Symlink => EntryKind::Symlink,
+        _ => EntryKind::Other,
+    }
+}
+
+/// Where a file is written before it is renamed over `path`
+fn temp_path(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.with_file_name(format!(".{}.antler-part", name))
+}
+
+fn create_remote(sftp: &Sftp, path: &Path) -> Result<ssh2::File, ssh2::Error> {
+    sftp.open_mode(
+        path,
+        OpenFlags::WRITE | OpenFlags::CREATE | OpenFlags::TRUNCATE,
+        0o644,
+        OpenType::File,
+    )
+}
+
+/// Rename `temp` over `path`, keeping the permissions of the file it replaces
+fn replace_remote(sftp: &Sftp, temp: &Path, path: &Path) -> Result<(), ssh2::Error> {
+    if let Ok(existing) = sftp.stat(path) {
+        let perm = FileStat {
+            size: None,
+            uid: None,
+            gid: None,
+            perm: existing.perm,
+            atime: None,
+            mtime: None,
+        };
+        let _ = sftp.setstat(temp, perm);
+    }
+    // SFTP version 3 servers, OpenSSH among them, won't rename over a file
+    if sftp.rename(temp, path, None).is_err() {
+        let _ = sftp.unlink(path);
+        sftp.rename(temp, path, None)?;
+    }
+    Ok(())
+}
+
+/// Progress of a transfer, reported as it goes
+struct Tracker {
+    app: AppHandle,
+    id: u32,
+    total: u64,
+    bytes: u64,
+    cancelled: Arc<AtomicBool>,
+    reported: Instant,
+}
+
+impl Tracker {
+    fn add(&mut self, bytes: u64) {
+        self.bytes += bytes;
+        if self.reported.elapsed() >= PROGRESS_INTERVAL {
+            self.reported = Instant::now();
+            let _ = self.app.emit(
+                "sftp-progress",
+                SftpProgressEvent {
+                    id: self.id,
+                    bytes: self.bytes,
+                    total: self.total,
+                },
+            );
+            let done = (self.total > 0).then(|| self.bytes as f64 / self.total as f64);
+            taskbar::report(&self.app, &format!("sftp:{}", self.id), done);
+        }
+    }
+
+    fn copy(&mut self, reader: &mut impl Read, writer: &mut impl Write) -> Result<(), String> {
+        let mut buf = vec![0u8; BUFFER_SIZE];
+        loop {
+            if self.cancelled.load(Ordering::SeqCst) {
+                return Err("cancelled".to_string());
+            }
+            let n = reader
+                .read(&mut buf)
+                .map_err(|e| format!("Read failed: {}", e))?;
+            if n == 0 {
+                break;
+            }
+            writer
+                .write_all(&buf[..n])
+                .map_err(|e| format!("Write failed: {}", e))?;
+            self.add(n as u64);
+        }
+        Ok(())
+    }
+}
+
+fn upload(
+    app: &AppHandle,
+    name: &str,
+    local: &Path,
+    dest: &Path,
+    tracker: &mut Tracker,
+) -> Result<(), String> {
+    let mut file =
+        File::open(local).map_err(|e| format!("Failed to open {}: {}", local.display(), e))?;
+    tracker.total = file.metadata().map(|meta| meta.len()).unwrap_or(0);
+    let (remote, _) = remote(app, name)?;
+    let temp = temp_path(dest);
+    let result = create_remote(&remote.sftp, &temp)
+        .map_err(|e| format!("Failed to create {}: {}", temp.display(), e))
+        .and_then(|mut out| tracker.copy(&mut file, &mut out))
+        .and_then(|()| {
+            replace_remote(&remote.sftp, &temp, dest)
+                .map_err(|e| format!("Failed to replace {}: {}", dest.display(), e))
+        });
+    if result.is_err() {
+        let _ = remote.sftp.unlink(&temp);
+        app.state::<SftpState>().connections.lock().remove(name);
+    }
+    result
+}
+
+fn download(
+    app: &AppHandle,
+    name: &str,
+    src: &Path,
+    local: &Path,
+    tracker: &mut Tracker,
+) -> Result<(), String> {
+    let (remote, _) = remote(app, name)?;
+    let mut file = remote
+        .sftp
+        .open(src)
+        .map_err(|e| format!("Failed to open {}: {}", src.display(), e))?;
+    tracker.total = file.stat().ok().and_then(|stat| stat.size).unwrap_or(0);
+    if let Some(parent) = local
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let temp = temp_path(local);
+    let result = File::create(&temp)
+        .map_err(|e| format!("Failed to create {}: {}", temp.display(), e))
+        .and_then(|mut out| tracker.copy(&mut file, &mut out))
+        .and_then(|()| {
+            fs::rename(&temp, local)
+                .map_err(|e| format!("Failed to replace {}: {}", local.display(), e))
+        });
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+#[derive(Clone, Copy)]
+enum Direction {
+    Upload,
+    Download,
+}
+
+/// Run a transfer on a background thread, returning the ID that tags its
+/// events
+fn start_transfer(
+    app: AppHandle,
+    state: &SftpState,
+    name: String,
+    direction: Direction,
+    src: String,
+    dest: String,
+) -> u32 {
+    let id = state.next_id.fetch_add(1, Ordering::SeqCst);
+    let cancelled = Arc::new(AtomicBool::new(false));
+    state.transfers.lock().insert(id, cancelled.clone());
+
+    thread::spawn(move || {
+        let taskbar_key = format!("sftp:{}", id);
+        taskbar::report(&app, &taskbar_key, None);
+        let mut tracker = Tracker {
+            app: app.clone(),
+            id,
+            total: 0,
+            bytes: 0,
+            cancelled: cancelled.clone(),
+            reported: Instant::now(),
+        };
+        let (src, dest) = (Path::new(&src), Path::new(&dest));
+        let result = match direction {
+            Direction::Upload => upload(&app, &name, src, dest, &mut tracker),
+            Direction::Download => download(&app, &name, src, dest, &mut tracker),
+        };
+        let cancelled = cancelled.load(Ordering::SeqCst);
+
+        app.state::<SftpState>().transfers.lock().remove(&id);
+        taskbar::finish(&app, &taskbar_key);
+        let _ = app.emit(
+            "sftp-done",
+            SftpDoneEvent {
+                id,
+                dest: dest.to_string_lossy().into_owned(),
+                bytes: tracker.bytes,
+                cancelled,
+                error: result.err().filter(|_| !cancelled),
+            },
+        );
+    });
+    id
+}
+
+/// Entries of a remote directory, directories first; relative paths start
+/// at the remote home directory
+#[tauri::command]
+pub async fn sftp_list(
+    app: AppHandle,
+    host: String,
+    path: String,
+) -> Result<Vec<RemoteEntry>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let listed = with_sftp(&app, &host, |sftp| Ok(sftp.readdir(Path::new(&path))?))
+            .map_err(|e| format!("Failed to list {}: {}", path, e))?;
+        let mut entries: Vec<RemoteEntry> = listed
+            .into_iter()
+            .map(|(entry_path, stat)| {
+                let kind = entry_kind(&stat);
+                RemoteEntry {
+                    name: entry_path
+                        .file_name()
+                        .map(|name| name.to_string_lossy().into_owned())
+                        .unwrap_or_default(),
+                    path: entry_path.to_string_lossy().into_owned(),
+                    size: stat.size.filter(|_| kind == EntryKind::File),
+                    modified: stat.mtime,
+                    mode: stat.perm.map(|perm| perm & 0o7777),
+                    kind,
+                }
+            })
+            .collect();
+        entries.sort_by(|a, b| {
+            (b.kind == EntryKind::Dir)
+                .cmp(&(a.kind == EntryKind::Dir))
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        Ok(entries)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Contents of a remote text file
+#[tauri::command]
+pub async fn sftp_read_file(app: AppHandle, host: String, path: String) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let data = with_sftp(&app, &host, |sftp| {
+            let mut file = sftp.open(Path::new(&path))?;
+            let size = file.stat()?.size.unwrap_or(0);
+            if size > MAX_READ_BYTES {
+                return Ok(None);
+            }
+            let mut data = Vec::with_capacity(size as usize);
+            file.read_to_end(&mut data)?;
+            Ok(Some(data))
+        })
+        .map_err(|e| format!("Failed to read {}: {}", path, e))?
+        .ok_or_else(|| format!("{} is too large to open; download it instead", path))?;
+        String::from_utf8(data).map_err(|_| format!("{} is not UTF-8 text", path))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Replace a remote file's contents, or create it
+#[tauri::command]
+pub async fn sftp_write_file(
+    app: AppHandle,
+    host: String,
+    path: String,
+    contents: String,
+) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let path = PathBuf::from(path);
+        let temp = temp_path(&path);
+        with_sftp(&app, &host, |sftp| {
+            let written = create_remote(sftp, &temp)
+                .map_err(io::Error::from)
+                .and_then(|mut file| file.write_all(contents.as_bytes()))
+                .and_then(|()| Ok(replace_remote(sftp, &temp, &path)?));
+            if written.is_err() {
+                let _ = sftp.unlink(&temp);
+            }
+            written
+        })
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Copy local file `src` to `dest` on the host
+#[tauri::command]
+pub async fn sftp_upload(
+    app: AppHandle,
+    state: State<'_, SftpState>,
+    host: String,
+    src: String,
+    dest: String,
+) -> Result<u32, String> {
+    Ok(start_transfer(
+        app,
+        &state,
+        host,
+        Direction::Upload,
+        src,
+        dest,
+    ))
+}
+
+/// Copy `src` on the host to local file `dest`
+#[tauri::command]
+pub async fn sftp_download(
+    app: AppHandle,
+    state: State<'_, SftpState>,
+    host: String,
+    src: String,
+    dest: String,
+) -> Result<u32, String> {
+    Ok(start_transfer(
+        app,
+        &state,
+        host,
+        Direction::Download,
+        src,
+        dest,
+    ))
+}
+
+/// Stop an upload or download; `sftp-done` still follows
+#[tauri::command]
+pub async fn cancel_sftp_transfer(state: State<'_, SftpState>, id: u32) -> Result<(), String> {
+    if let Some(cancelled) = state.transfers.lock().get(&id) {
+        cancelled.store(true, Ordering::SeqCst);
+    }
+    Ok(())
+}
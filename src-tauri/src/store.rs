@@ -0,0 +1,82 @@
+//! SQLite stores - the databases kept in the app data directory
+//!
+//! Each is opened on first use, creating the directory and running its
+//! schema, so a feature that is never used never creates its file.
+
+use parking_lot::{MappedMutexGuard, Mutex, MutexGuard};
+use rusqlite::Connection;
+use std::fs;
+use tauri::{AppHandle, Manager};
+
+/// A database file, opened when first needed
+pub(crate) struct Store {
+    file: &'static str,
+    schema: &'static str,
+    /// Names the store in errors, lower case: `job queue`
+    label: &'static str,
+    /// Run after the schema on opening, to tidy up after the last run
+    setup: Option<fn(&Connection) -> rusqlite::Result<()>>,
+    conn: Mutex<Option<Connection>>,
+}
+
+impl Store {
+    pub(crate) fn new(file: &'static str, schema: &'static str, label: &'static str) -> Self {
+        Self {
+            file,
+            schema,
+            label,
+            setup: None,
+            conn: Mutex::new(None),
+        }
+    }
+
+    pub(crate) fn with_setup(mut self, setup: fn(&Connection) -> rusqlite::Result<()>) -> Self {
+        self.setup = Some(setup);
+        self
+    }
+
+    /// The connection, opened first if it isn't yet
+    pub(crate) fn connection(
+        &self,
+        app: &AppHandle,
+    ) -> Result<MappedMutexGuard<'_, Connection>, String> {
+        let mut conn = self.conn.lock();
+        if conn.is_none() {
+            let dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+            fs::create_dir_all(&dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+            let opened = Connection::open(dir.join(self.file))
+                .and_then(|conn| {
+                    conn.execute_batch(self.schema)?;
+                    if let Some(setup) = self.setup {
+                        setup(&conn)?;
+                    }
+                    Ok(conn)
+                })
+                .map_err(|e| format!("Failed to open {}: {}", self.label, e))?;
+            *conn = Some(opened);
+        }
+        Ok(MutexGuard::map(conn, |conn| {
+            conn.as_mut().expect("connection opened above")
+        }))
+    }
+
+    /// Run `f` on the connection
+    pub(crate) fn with<T>(
+        &self,
+        app: &AppHandle,
+        f: impl FnOnce(&mut Connection) -> rusqlite::Result<T>,
+    ) -> Result<T, String> {
+        let mut conn = self.connection(app)?;
+        f(&mut conn).map_err(|e| {
+            let mut label = self.label.chars();
+            let first = label.next().map(|c| c.to_uppercase().to_string());
+            format!(
+                "{}{} error: {}",
+                first.unwrap_or_default(),
+                label.as_str(),
+                e
+            )
+        })
+    }
+}
@@ -0,0 +1,134 @@
+//! Taskbar progress - long-running operations on the app's taskbar button
+//!
+//! Clones, archive operations and devcontainer builds report how far they
+//! got, and the taskbar button shows them combined: the average of those
+//! that know their share done, or an indeterminate bar while none does.
+//! `set_taskbar_progress` shows the UI's own progress instead until it is
+//! set back to `none`. Windows draws the bar on each window's button; macOS
+//! and Linux desktops with the Unity launcher API have one bar for the whole
+//! app, shown on the dock icon or launcher entry.
+
+use crate::quake;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use tauri::window::{ProgressBarState, ProgressBarStatus};
+use tauri::{AppHandle, Manager, State};
+
+/// What the taskbar button shows, with the percentage done
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Progress {
+    Hidden,
+    Indeterminate,
+    Normal(u64),
+    Paused(u64),
+    Error(u64),
+}
+
+impl Progress {
+    fn bar(self) -> ProgressBarState {
+        let (status, progress) = match self {
+            Progress::Hidden => (ProgressBarStatus::None, None),
+            Progress::Indeterminate => (ProgressBarStatus::Indeterminate, None),
+            Progress::Normal(percent) => (ProgressBarStatus::Normal, Some(percent)),
+            Progress::Paused(percent) => (ProgressBarStatus::Paused, Some(percent)),
+            Progress::Error(percent) => (ProgressBarStatus::Error, Some(percent)),
+        };
+        ProgressBarState {
+            status: Some(status),
+            progress,
+        }
+    }
+}
+
+/// State for the taskbar progress
+#[derive(Default)]
+pub struct TaskbarState {
+    /// Share done of each running operation, `None` while unknown
+    operations: Mutex<HashMap<String, Option<f64>>>,
+    /// Progress set by the UI, shown instead of the operations'
+    manual: Mutex<Option<Progress>>,
+    /// Progress last shown, so unchanged reports don't reach the windows
+    shown: Mutex<Option<Progress>>,
+}
+
+impl TaskbarState {
+    fn current(&self) -> Progress {
+        if let Some(progress) = *self.manual.lock() {
+            return progress;
+        }
+        let operations = self.operations.lock();
+        if operations.is_empty() {
+            return Progress::Hidden;
+        }
+        let known: Vec<f64> = operations.values().flatten().copied().collect();
+        if known.is_empty() {
+            return Progress::Indeterminate;
+        }
+        let share = known.iter().sum::<f64>() / known.len() as f64;
+        Progress::Normal((share.clamp(0.0, 1.0) * 100.0).round() as u64)
+    }
+}
+
+fn show(app: &AppHandle) {
+    let state = app.state::<TaskbarState>();
+    // Held while reading, so a stale report can't overwrite a newer one
+    let mut shown = state.shown.lock();
+    let progress = state.current();
+    if *shown == Some(progress) {
+        return;
+    }
+    *shown = Some(progress);
+    // The drop-down window has no taskbar button
+    for (label, window) in app.webview_windows() {
+        if label == quake::WINDOW_LABEL {
+            continue;
+        }
+        if let Err(e) = window.set_progress_bar(progress.bar()) {
+            eprintln!("Failed to set taskbar progress: {}", e);
+        }
+    }
+}
+
+/// Record that operation `key` is `done` of the way through (between 0 and
+/// 1), or running for an unknown time with `None`
+pub(crate) fn report(app: &AppHandle, key: &str, done: Option<f64>) {
+    app.state::<TaskbarState>()
+        .operations
+        .lock()
+        .insert(key.to_string(), done);
+    show(app);
+}
+
+/// Take operation `key` off the taskbar once it ended, however it did
+pub(crate) fn finish(app: &AppHandle, key: &str) {
+    app.state::<TaskbarState>().operations.lock().remove(key);
+    show(app);
+}
+
+/// Show `state` on the taskbar button, `value` percent done for the states
+/// with a bar, instead of the running operations' progress; `none` goes
+/// back to them
+#[tauri::command]
+pub async fn set_taskbar_progress(
+    app: AppHandle,
+    taskbar: State<'_, TaskbarState>,
+    state: ProgressBarStatus,
+    value: Option<u64>,
+) -> Result<(), String> {
+    let percent = value.unwrap_or(0);
+    if percent > 100 {
+        return Err(format!(
+            "Progress must be between 0 and 100, not {}",
+            percent
+        ));
+    }
+    *taskbar.manual.lock() = match state {
+        ProgressBarStatus::None => None,
+        ProgressBarStatus::Indeterminate => Some(Progress::Indeterminate),
+        ProgressBarStatus::Normal => Some(Progress::Normal(percent)),
+        ProgressBarStatus::Paused => Some(Progress::Paused(percent)),
+        ProgressBarStatus::Error => Some(Progress::Error(percent)),
+    };
+    show(&app);
+    Ok(())
+}
@@ -0,0 +1,473 @@
+//! tmux control mode client
+//!
+//! Runs `tmux -C attach` and speaks the control protocol instead of
+//! rendering a whole tmux client in one terminal. Pane output arrives as
+//! `tmux-pane-output` events, one stream per pane; window and layout
+//! notifications arrive as `tmux-control` events; commands sent with
+//! `tmux_control_command` get their `%begin`/`%end` block back as lines.
+
+use super::{exact_target, session_exists};
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::mpsc::{channel, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// How long a command may take before `tmux_control_command` gives up
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(10);
+
+type Reply = Result<Vec<String>, String>;
+
+struct ControlClient {
+    session: String,
+    stdin: Mutex<ChildStdin>,
+    child: Mutex<Child>,
+    /// Waiting callers, in the order their commands were written
+    pending: Arc<Mutex<VecDeque<Sender<Reply>>>>,
+}
+
+/// State for control-mode clients
+pub struct TmuxControlState {
+    clients: Mutex<HashMap<u32, Arc<ControlClient>>>,
+    next_id: AtomicU32,
+}
+
+impl Default for TmuxControlState {
+    fn default() -> Self {
+        Self {
+            clients: Mutex::new(HashMap::new()),
+            next_id: AtomicU32::new(1),
+        }
+    }
+}
+
+/// Event payload for pane output
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PaneOutputEvent {
+    id: u32,
+    pane_id: String,
+    data: String,
+}
+
+/// Notification from the tmux server
+#[derive(Clone, Debug, Serialize)]
+#[serde(
+    tag = "kind",
+    rename_all = "kebab-case",
+    rename_all_fields = "camelCase"
+)]
+pub enum ControlNotification {
+    WindowAdd { window_id: String },
+    WindowClose { window_id: String },
+    WindowRenamed { window_id: String, name: String },
+    LayoutChange { window_id: String, layout: String },
+    SessionChanged { session_id: String, name: String },
+    SessionRenamed { name: String },
+    Exit { reason: Option<String> },
+}
+
+/// Event payload for notifications
+#[derive(Clone, Serialize)]
+struct ControlEvent {
+    id: u32,
+    #[serde(flatten)]
+    notification: ControlNotification,
+}
+
+/// A pane as listed by `tmux_control_list_panes`
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TmuxPane {
+    pane_id: String,
+    window_id: String,
+    window_name: String,
+    active: bool,
+    cols: u16,
+    rows: u16,
+    command: String,
+}
+
+/// Undo the octal escaping of `%output` data (`\ooo` for control bytes and `\`)
+pub(crate) fn unescape_output(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len());
+    let mut i = 0;
+    while i < data.len() {
+        if data[i] == b'\\' && i + 3 < data.len() {
+            let digits = &data[i + 1..i + 4];
+            if digits.iter().all(|d| (b'0'..=b'7').contains(d)) {
+                let value = digits
+                    .iter()
+                    .fold(0u32, |acc, d| acc * 8 + u32::from(d - b'0'));
+                out.push(value as u8);
+                i += 4;
+                continue;
+            }
+        }
+        out.push(data[i]);
+        i += 1;
+    }
+    out
+}
+
+fn parse_notification(line: &str) -> Option<ControlNotification> {
+    let (kind, rest) = line.split_once(' ').unwrap_or((line, ""));
+    let mut words = rest.splitn(2, ' ');
+    let mut next = || words.next().unwrap_or_default().to_string();
+
+    Some(match kind {
+        "%window-add" => ControlNotification::WindowAdd { window_id: next() },
+        "%window-close" | "%unlinked-window-close" => {
+            ControlNotification::WindowClose { window_id: next() }
+        }
+        "%window-renamed" => ControlNotification::WindowRenamed {
+            window_id: next(),
+            name: next(),
+        },
+        "%layout-change" => {
+            let window_id = next();
+            let layout = next().split(' ').next().unwrap_or_default().to_string();
+            ControlNotification::LayoutChange { window_id, layout }
+        }
+        "%session-changed" => ControlNotification::SessionChanged {
+            session_id: next(),
+            name: next(),
+        },
+        "%session-renamed" => ControlNotification::SessionRenamed {
+            name: rest.to_string(),
+        },
+        "%exit" => ControlNotification::Exit {
+            reason: (!rest.is_empty()).then(|| rest.to_string()),
+        },
+        _ => return None,
+    })
+}
+
+/// Read the control stream until tmux exits
+fn read_stream(
+    app: AppHandle,
+    id: u32,
+    stdout: impl std::io::Read,
+    pending: Arc<Mutex<VecDeque<Sender<Reply>>>>,
+) {
+    let mut reader = BufReader::new(stdout);
+    let mut line = Vec::new();
+    // Lines of the `%begin` block being read, and whether we sent the command
+    let mut block: Option<(bool, Vec<String>)> = None;
+    let mut exited = false;
+
+    loop {
+        line.clear();
+        match reader.read_until(b'\n', &mut line) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {}
+        }
+        if line.ends_with(b"\n") {
+            line.pop();
+        }
+
+        if let Some((ours, lines)) = block.as_mut() {
+            let text = String::from_utf8_lossy(&line);
+            let end = text.starts_with("%end ");
+            if end || text.starts_with("%error ") {
+                let ours = *ours;
+                let lines = std::mem::take(lines);
+                block = None;
+                if ours {
+                    if let Some(reply) = pending.lock().pop_front() {
+                        let _ = reply.send(if end {
+                            Ok(lines)
+                        } else {
+                            Err(lines.join("\n"))
+                        });
+                    }
+                }
+            } else {
+                lines.push(text.into_owned());
+            }
+            continue;
+        }
+
+        if let Some(rest) = line.strip_prefix(b"%output ") {
+            let Some(split) = rest.iter().position(|b| *b == b' ') else {
+                continue;
+            };
+            let pane_id = String::from_utf8_lossy(&rest[..split]).into_owned();
+            let data = unescape_output(&rest[split + 1..]);
+            let _ = app.emit(
+                "tmux-pane-output",
+                PaneOutputEvent {
+                    id,
+                    pane_id,
+                    data: String::from_utf8_lossy(&data).into_owned(),
+                },
+            );
+            continue;
+        }
+
+        let text = String::from_utf8_lossy(&line);
+        if let Some(rest) = text.strip_prefix("%begin ") {
+            // `%begin <time> <number> <flags>`; flag 1 marks our own commands
+            let ours = rest.split(' ').nth(2) == Some("1");
+            block = Some((ours, Vec::new()));
+        } else if let Some(notification) = parse_notification(&text) {
+            exited |= matches!(notification, ControlNotification::Exit { .. });
+            let _ = app.emit("tmux-control", ControlEvent { id, notification });
+        }
+    }
+
+    for reply in pending.lock().drain(..) {
+        let _ = reply.send(Err("tmux control client exited".to_string()));
+    }
+    if !exited {
+        let _ = app.emit(
+            "tmux-control",
+            ControlEvent {
+                id,
+                notification: ControlNotification::Exit { reason: None },
+            },
+        );
+    }
+    app.state::<TmuxControlState>().clients.lock().remove(&id);
+}
+
+fn client(state: &TmuxControlState, id: u32) -> Result<Arc<ControlClient>, String> {
+    state
+        .clients
+        .lock()
+        .get(&id)
+        .cloned()
+        .ok_or_else(|| format!("tmux control client {} not found", id))
+}
+
+/// Send one command and wait for its output lines
+fn send_command(client: &ControlClient, command: &str) -> Reply {
+    if command.contains('\n') {
+        return Err("tmux commands must be a single line".to_string());
+    }
+
+    let (reply, response) = channel();
+    {
+        // Hold the queue lock while writing so replies stay in order
+        let mut pending = client.pending.lock();
+        let mut stdin = client.stdin.lock();
+        writeln!(stdin, "{}", command)
+            .and_then(|_| stdin.flush())
+            .map_err(|e| format!("Failed to write to tmux: {}", e))?;
+        pending.push_back(reply);
+    }
+
+    response
+        .recv_timeout(COMMAND_TIMEOUT)
+        .map_err(|_| format!("tmux did not answer '{}'", command))?
+}
+
+/// Quote an argument for the tmux command parser
+fn quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
+/// Attach a control-mode client to a session, returning its id
+#[tauri::command]
+pub async fn tmux_control_attach(
+    app: AppHandle,
+    state: State<'_, TmuxControlState>,
+    session: String,
+) -> Result<u32, String> {
+    if !session_exists(&session) {
+        return Err(format!("tmux session '{}' not found", session));
+    }
+
+    let mut child = Command::new("tmux")
+        .args(["-C", "attach-session", "-t", &exact_target(&session)])
+        .env_remove("TMUX")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to run tmux: {}", e))?;
+
+    let stdin = child.stdin.take().ok_or("Failed to open tmux stdin")?;
+    let stdout = child.stdout.take().ok_or("Failed to open tmux stdout")?;
+
+    let id = state.next_id.fetch_add(1, Ordering::SeqCst);
+    let pending = Arc::new(Mutex::new(VecDeque::new()));
+    state.clients.lock().insert(
+        id,
+        Arc::new(ControlClient {
+            session,
+            stdin: Mutex::new(stdin),
+            child: Mutex::new(child),
+            pending: pending.clone(),
+        }),
+    );
+
+    thread::spawn(move || read_stream(app, id, stdout, pending));
+    Ok(id)
+}
+
+/// Run a tmux command through a control client
+#[tauri::command]
+pub async fn tmux_control_command(
+    state: State<'_, TmuxControlState>,
+    id: u32,
+    command: String,
+) -> Result<Vec<String>, String> {
+    let client = client(&state, id)?;
+    tauri::async_runtime::spawn_blocking(move || send_command(&client, &command))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Panes of every window in the client's session
+#[tauri::command]
+pub async fn tmux_control_list_panes(
+    state: State<'_, TmuxControlState>,
+    id: u32,
+) -> Result<Vec<TmuxPane>, String> {
+    let client = client(&state, id)?;
+    let lines = tauri::async_runtime::spawn_blocking(move || {
+        send_command(
+            &client,
+            "list-panes -s -F '#{pane_id}\t#{window_id}\t#{window_name}\t#{pane_active}\t#{pane_width}\t#{pane_height}\t#{pane_current_command}'",
+        )
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    Ok(lines
+        .iter()
+        .filter_map(|line| {
+            let mut fields = line.split('\t');
+            Some(TmuxPane {
+                pane_id: fields.next()?.to_string(),
+                window_id: fields.next()?.to_string(),
+                window_name: fields.next()?.to_string(),
+                active: fields.next()? == "1",
+                cols: fields.next()?.parse().ok()?,
+                rows: fields.next()?.parse().ok()?,
+                command: fields.next().unwrap_or_default().to_string(),
+            })
+        })
+        .collect())
+}
+
+/// Current contents of a pane, with escape sequences, to seed a terminal view
+#[tauri::command]
+pub async fn tmux_control_capture_pane(
+    state: State<'_, TmuxControlState>,
+    id: u32,
+    pane_id: String,
+) -> Result<String, String> {
+    let client = client(&state, id)?;
+    let lines = tauri::async_runtime::spawn_blocking(move || {
+        send_command(
+            &client,
+            &format!("capture-pane -p -e -J -t {}", quote(&pane_id)),
+        )
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    Ok(lines.join("\r\n"))
+}
+
+/// Send input to a pane, byte for byte
+#[tauri::command]
+pub async fn tmux_control_send_keys(
+    state: State<'_, TmuxControlState>,
+    id: u32,
+    pane_id: String,
+    data: String,
+) -> Result<(), String> {
+    if data.is_empty() {
+        return Ok(());
+    }
+    let client = client(&state, id)?;
+    let hex: Vec<String> = data.bytes().map(|b| format!("{:02x}", b)).collect();
+    let command = format!("send-keys -t {} -H {}", quote(&pane_id), hex.join(" "));
+
+    tauri::async_runtime::spawn_blocking(move || send_command(&client, &command))
+        .await
+        .map_err(|e| e.to_string())??;
+    Ok(())
+}
+
+/// Tell tmux the size of the area the client renders into
+#[tauri::command]
+pub async fn tmux_control_resize(
+    state: State<'_, TmuxControlState>,
+    id: u32,
+    cols: u16,
+    rows: u16,
+) -> Result<(), String> {
+    let client = client(&state, id)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        send_command(&client, &format!("refresh-client -C {}x{}", cols, rows))
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    Ok(())
+}
+
+/// Detach a control client, leaving the session running
+#[tauri::command]
+pub async fn tmux_control_detach(
+    state: State<'_, TmuxControlState>,
+    id: u32,
+) -> Result<(), String> {
+    let Some(client) = state.clients.lock().remove(&id) else {
+        return Ok(());
+    };
+
+    // An empty line asks tmux to detach the control client
+    let _ = writeln!(client.stdin.lock());
+    let mut child = client.child.lock();
+    thread::sleep(Duration::from_millis(100));
+    if let Ok(None) = child.try_wait() {
+        let _ = child.kill();
+    }
+    let _ = child.wait();
+    Ok(())
+}
+
+/// Attached control clients and their sessions
+#[tauri::command]
+pub async fn list_tmux_control_clients(
+    state: State<'_, TmuxControlState>,
+) -> Result<Vec<(u32, String)>, String> {
+    let mut clients: Vec<(u32, String)> = state
+        .clients
+        .lock()
+        .iter()
+        .map(|(id, client)| (*id, client.session.clone()))
+        .collect();
+    clients.sort();
+    Ok(clients)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_octal_escapes() {
+        assert_eq!(unescape_output(br"\033[1mhi\015\012"), b"\x1b[1mhi\r\n");
+        assert_eq!(unescape_output(br"a\134b"), br"a\b");
+        assert_eq!(unescape_output(b"plain text"), b"plain text");
+    }
+
+    #[test]
+    fn keeps_incomplete_or_invalid_escapes() {
+        assert_eq!(unescape_output(br"end\01"), br"end\01");
+        assert_eq!(unescape_output(br"\"), br"\");
+        assert_eq!(unescape_output(br"\0a9x"), br"\0a9x");
+        assert_eq!(unescape_output(br"\\012"), b"\\\n");
+        assert_eq!(unescape_output(b""), b"");
+    }
+}
@@ -0,0 +1,160 @@
+//! tmux module - attach PTYs to existing tmux sessions
+//!
+//! Agent sessions often run inside tmux so they survive the app closing.
+//! These commands list sessions and attach a PTY to one; the PTY is tagged
+//! with the session name so the UI can offer detach and reattach. The
+//! `control` submodule talks to tmux in control mode instead.
+
+pub mod control;
+
+use crate::pty::{self, PtyState};
+use portable_pty::CommandBuilder;
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+use tauri::{AppHandle, State};
+
+/// A tmux session as reported by `list-sessions`
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TmuxSession {
+    name: String,
+    windows: u32,
+    attached: u32,
+    created: i64,
+}
+
+/// Options for `attach_tmux_session`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachTmuxOptions {
+    session: String,
+    cols: u16,
+    rows: u16,
+    cwd: Option<String>,
+    /// Detach other clients so the window takes this PTY's size
+    #[serde(default)]
+    detach_others: bool,
+}
+
+pub(crate) fn run_tmux(args: &[&str]) -> Result<String, String> {
+    let output = Command::new("tmux")
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run tmux: {}", e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("tmux {} failed: {}", args[0], stderr.trim()));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Exact-match target, so `foo` does not resolve to `foobar`
+pub(crate) fn exact_target(session: &str) -> String {
+    format!("={}", session)
+}
+
+pub(crate) fn session_exists(session: &str) -> bool {
+    run_tmux(&["has-session", "-t", &exact_target(session)]).is_ok()
+}
+
+fn parse_sessions(output: &str) -> Vec<TmuxSession> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\t');
+            Some(TmuxSession {
+                name: fields.next()?.to_string(),
+                windows: fields.next()?.parse().ok()?,
+                attached: fields.next()?.parse().ok()?,
+                created: fields.next()?.parse().ok()?,
+            })
+        })
+        .collect()
+}
+
+/// List tmux sessions (empty when no server is running)
+#[tauri::command]
+pub async fn list_tmux_sessions() -> Result<Vec<TmuxSession>, String> {
+    match run_tmux(&[
+        "list-sessions",
+        "-F",
+        "#{session_name}\t#{session_windows}\t#{session_attached}\t#{session_created}",
+    ]) {
+        Ok(output) => Ok(parse_sessions(&output)),
+        Err(e) if e.contains("no server running") || e.contains("error connecting") => {
+            Ok(Vec::new())
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Spawn a PTY running `tmux attach` for an existing session
+#[tauri::command]
+pub async fn attach_tmux_session(
+    app: AppHandle,
+    state: State<'_, PtyState>,
+    options: AttachTmuxOptions,
+) -> Result<u32, String> {
+    if !session_exists(&options.session) {
+        return Err(format!("tmux session '{}' not found", options.session));
+    }
+
+    let target = exact_target(&options.session);
+    let mut cmd = CommandBuilder::new("tmux");
+    cmd.arg("attach-session");
+    if options.detach_others {
+        cmd.arg("-d");
+    }
+    cmd.args(["-t", &target]);
+    if let Some(cwd) = &options.cwd {
+        cmd.cwd(cwd);
+    }
+    // Attaching from inside tmux would otherwise refuse to nest
+    cmd.env_remove("TMUX");
+
+    let id = pty::spawn_session(
+        &app,
+        &state,
+        cmd,
+        options.cols,
+        options.rows,
+        Some(options.session.clone()),
+        None,
+    )?;
+
+    // Windows keep the size of the smallest client; make ours count
+    let _ = run_tmux(&[
+        "resize-window",
+        "-t",
+        &target,
+        "-x",
+        &options.cols.to_string(),
+        "-y",
+        &options.rows.to_string(),
+    ]);
+
+    Ok(id)
+}
+
+/// Detach a PTY's tmux client, leaving the session running
+#[tauri::command]
+pub async fn detach_tmux_pty(state: State<'_, PtyState>, id: u32) -> Result<(), String> {
+    if pty::tmux_session(&state, id)?.is_none() {
+        return Err(format!("PTY session {} is not attached to tmux", id));
+    }
+    let pid = pty::child_pid(&state, id)?
+        .ok_or_else(|| format!("PTY session {} has already exited", id))?;
+
+    let clients = run_tmux(&["list-clients", "-F", "#{client_pid}\t#{client_name}"])?;
+    let client = clients
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .find(|(client_pid, _)| client_pid.parse() == Ok(pid))
+        .map(|(_, name)| name.to_string())
+        .ok_or_else(|| format!("No tmux client found for PTY session {}", id))?;
+
+    run_tmux(&["detach-client", "-t", &client])?;
+    Ok(())
+}
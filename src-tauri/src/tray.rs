@@ -0,0 +1,278 @@
+//! Tray icon - agent sessions at a glance
+//!
+//! The tray shows how many agents are running and lists them, each with
+//! "Focus" and "Kill" actions, next to a quick "New terminal". The menu is
+//! rebuilt whenever an `agent-state` event reports a transition. Terminals
+//! live in the frontend, so focusing an agent or opening a terminal shows the
+//! window and asks the UI to do the rest through `tray-focus-agent` and
+//! `tray-new-terminal` events. With `set_minimize_to_tray` on, closing the
+//! window hides it instead and the tray brings it back.
+
+use crate::agents::{self, AgentInfo, AgentState, AgentStatus};
+use crate::headless;
+use crate::quake;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIcon, TrayIconBuilder, TrayIconEvent};
+use tauri::{AppHandle, Emitter, Listener, Manager, State, Window, WindowEvent, Wry};
+
+const TRAY_ID: &str = "main";
+
+/// Longest issue title shown in the menu before it is cut short
+const MAX_TITLE_CHARS: usize = 40;
+
+/// State for the tray icon
+#[derive(Default)]
+pub struct TrayState {
+    minimize_to_tray: AtomicBool,
+}
+
+/// Payload of `tray-focus-agent`
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FocusAgentEvent {
+    id: u32,
+    pty_id: Option<u32>,
+}
+
+/// What a menu item does, parsed from its id
+enum Action {
+    Focus(u32),
+    Kill(u32),
+    NewTerminal,
+    Show,
+    Quit,
+}
+
+impl Action {
+    fn parse(id: &str) -> Option<Self> {
+        if let Some(agent) = id.strip_prefix("focus:") {
+            return agent.parse().ok().map(Self::Focus);
+        }
+        if let Some(agent) = id.strip_prefix("kill:") {
+            return agent.parse().ok().map(Self::Kill);
+        }
+        match id {
+            "new-terminal" => Some(Self::NewTerminal),
+            "show" => Some(Self::Show),
+            "quit" => Some(Self::Quit),
+            _ => None,
+        }
+    }
+}
+
+pub(crate) fn is_active(agent: &AgentInfo) -> bool {
+    matches!(
+        agent.status,
+        AgentStatus::Starting | AgentStatus::Working | AgentStatus::Waiting
+    )
+}
+
+fn summary(active: usize) -> String {
+    match active {
+        0 => "No active sessions".to_string(),
+        1 => "1 active session".to_string(),
+        n => format!("{} active sessions", n),
+    }
+}
+
+pub(crate) fn agent_label(agent: &AgentInfo) -> String {
+    let mut title: String = agent.issue.title.chars().take(MAX_TITLE_CHARS).collect();
+    if agent.issue.title.chars().count() > MAX_TITLE_CHARS {
+        title.push('…');
+    }
+    let status = match agent.status {
+        AgentStatus::Queued => " (queued)",
+        AgentStatus::Waiting => " (waiting)",
+        _ => "",
+    };
+    format!(
+        "{} #{}: {}{}",
+        agent.issue.repo, agent.issue.number, title, status
+    )
+}
+
+/// The tray menu for the agents currently known
+fn build_menu(app: &AppHandle, agents: &[AgentInfo]) -> tauri::Result<Menu<Wry>> {
+    let active = agents.iter().filter(|agent| is_active(agent)).count();
+    let menu = Menu::new(app)?;
+    menu.append(&MenuItem::with_id(
+        app,
+        "summary",
+        summary(active),
+        false,
+        None::<&str>,
+    )?)?;
+
+    let listed: Vec<&AgentInfo> = agents
+        .iter()
+        .filter(|agent| agent.status != AgentStatus::Exited)
+        .collect();
+    if !listed.is_empty() {
+        menu.append(&PredefinedMenuItem::separator(app)?)?;
+    }
+    for agent in listed {
+        let submenu = Submenu::new(app, agent_label(agent), true)?;
+        submenu.append(&MenuItem::with_id(
+            app,
+            format!("focus:{}", agent.id),
+            "Focus",
+            true,
+            None::<&str>,
+        )?)?;
+        submenu.append(&MenuItem::with_id(
+            app,
+            format!("kill:{}", agent.id),
+            "Kill",
+            true,
+            None::<&str>,
+        )?)?;
+        menu.append(&submenu)?;
+    }
+
+    menu.append(&PredefinedMenuItem::separator(app)?)?;
+    menu.append(&MenuItem::with_id(
+        app,
+        "new-terminal",
+        "New terminal",
+        true,
+        None::<&str>,
+    )?)?;
+    menu.append(&MenuItem::with_id(
+        app,
+        "show",
+        "Show Antler",
+        true,
+        None::<&str>,
+    )?)?;
+    menu.append(&MenuItem::with_id(
+        app,
+        "quit",
+        "Quit Antler",
+        true,
+        None::<&str>,
+    )?)?;
+    Ok(menu)
+}
+
+/// Rebuild the menu, tooltip and (on macOS) the count beside the icon
+fn refresh(app: &AppHandle) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    let agents = agents::agent_list(&app.state::<AgentState>());
+    let active = agents.iter().filter(|agent| is_active(agent)).count();
+    match build_menu(app, &agents) {
+        Ok(menu) => {
+            let _ = tray.set_menu(Some(menu));
+        }
+        Err(e) => eprintln!("Failed to build the tray menu: {}", e),
+    }
+    let _ = tray.set_tooltip(Some(format!("Antler - {}", summary(active))));
+    #[cfg(target_os = "macos")]
+    let _ = tray.set_title((active > 0).then(|| active.to_string()));
+}
+
+/// Bring the UI back, reopening the windows of a headless start
+pub(crate) fn show_windows(app: &AppHandle) {
+    if let Err(e) = headless::show_ui(app) {
+        eprintln!("{}", e);
+    }
+    // The drop-down terminal has its own shortcut
+    for (_, window) in app
+        .webview_windows()
+        .into_iter()
+        .filter(|(label, _)| label != quake::WINDOW_LABEL)
+    {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+fn on_menu_event(app: &AppHandle, event: MenuEvent) {
+    match Action::parse(event.id().as_ref()) {
+        Some(Action::Focus(id)) => {
+            show_windows(app);
+            let pty_id = agents::agent_list(&app.state::<AgentState>())
+                .into_iter()
+                .find(|agent| agent.id == id)
+                .and_then(|agent| agent.pty_id);
+            let _ = app.emit("tray-focus-agent", FocusAgentEvent { id, pty_id });
+        }
+        Some(Action::Kill(id)) => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = agents::stop_agent(app, id).await {
+                    eprintln!("Failed to kill agent {}: {}", id, e);
+                }
+            });
+        }
+        Some(Action::NewTerminal) => {
+            show_windows(app);
+            let _ = app.emit("tray-new-terminal", ());
+        }
+        Some(Action::Show) => show_windows(app),
+        Some(Action::Quit) => app.exit(0),
+        None => {}
+    }
+}
+
+fn on_tray_icon_event(tray: &TrayIcon, event: TrayIconEvent) {
+    // Left click opens the window; the menu stays on the right button
+    if let TrayIconEvent::Click {
+        button: MouseButton::Left,
+        button_state: MouseButtonState::Up,
+        ..
+    } = event
+    {
+        show_windows(tray.app_handle());
+    }
+}
+
+/// Add the tray icon and keep its menu in step with the agents
+pub(crate) fn create(app: &AppHandle) -> tauri::Result<()> {
+    let menu = build_menu(app, &[])?;
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .menu(&menu)
+        .tooltip(format!("Antler - {}", summary(0)))
+        .show_menu_on_left_click(false)
+        .on_menu_event(on_menu_event)
+        .on_tray_icon_event(on_tray_icon_event);
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app)?;
+
+    let handle = app.clone();
+    app.listen("agent-state", move |_| refresh(&handle));
+    Ok(())
+}
+
+/// Hide a closing window instead when minimizing to the tray is on
+pub(crate) fn on_window_event(window: &Window, event: &WindowEvent) {
+    if let WindowEvent::CloseRequested { api, .. } = event {
+        let state = window.state::<TrayState>();
+        if state.minimize_to_tray.load(Ordering::SeqCst) {
+            api.prevent_close();
+            let _ = window.hide();
+        }
+    }
+}
+
+/// Turn minimizing to the tray on closing the window on or off
+#[tauri::command]
+pub async fn set_minimize_to_tray(
+    state: State<'_, TrayState>,
+    enabled: bool,
+) -> Result<(), String> {
+    state.minimize_to_tray.store(enabled, Ordering::SeqCst);
+    Ok(())
+}
+
+/// Whether closing the window minimizes to the tray
+#[tauri::command]
+pub async fn get_minimize_to_tray(state: State<'_, TrayState>) -> Result<bool, String> {
+    Ok(state.minimize_to_tray.load(Ordering::SeqCst))
+}
@@ -0,0 +1,367 @@
+//! Updates - checking, downloading and installing new releases
+//!
+//! Releases are published on GitHub with the updater plugin's `latest.json`
+//! manifest: the stable channel reads the one on the latest release, the
+//! beta channel the one on the rolling `beta` release. Packages are verified
+//! against the minisign public key the release build is given in
+//! `ANTLER_UPDATER_PUBKEY`, or else the one in the updater plugin's config;
+//! a build with neither refuses to check rather than fail every install. A
+//! release build
+//! checks once shortly after starting and downloads anything new in the
+//! background, reporting `update-available`, `update-progress` and
+//! `update-download-done`; the UI then offers `install_update_and_restart`,
+//! which stops agents and hangs up PTYs before swapping the app out. The
+//! chosen channel is saved to a SQLite file in the app data directory and
+//! read back before that first check.
+
+use crate::agents::{self, AgentState, AgentStatus};
+use crate::proxy::{self, Route};
+use crate::pty::{self, PtyState};
+use crate::store::Store;
+use parking_lot::Mutex;
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State, Url};
+use tauri_plugin_updater::{Update, UpdaterExt};
+
+const STABLE_ENDPOINT: &str =
+    "https://github.com/christophergyman/Antler/releases/latest/download/latest.json";
+const BETA_ENDPOINT: &str =
+    "https://github.com/christophergyman/Antler/releases/download/beta/latest.json";
+
+const SETTINGS_FILE: &str = "updater.sqlite";
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS settings (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
+";
+
+/// Public key releases are signed with, when the release build sets one
+const PUBKEY: Option<&str> = option_env!("ANTLER_UPDATER_PUBKEY");
+
+/// Wait after startup before the first check, so it doesn't compete with
+/// loading the board
+const FIRST_CHECK_DELAY: Duration = Duration::from_secs(30);
+
+/// Time PTY processes get to exit after being hung up
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UpdateChannel {
+    #[default]
+    Stable,
+    Beta,
+}
+
+impl UpdateChannel {
+    fn as_str(self) -> &'static str {
+        match self {
+            UpdateChannel::Stable => "stable",
+            UpdateChannel::Beta => "beta",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "stable" => Some(UpdateChannel::Stable),
+            "beta" => Some(UpdateChannel::Beta),
+            _ => None,
+        }
+    }
+}
+
+/// State for updates
+pub struct UpdaterState {
+    store: Store,
+    channel: Mutex<UpdateChannel>,
+    /// Latest update found by a check
+    available: Mutex<Option<Update>>,
+    /// Verified package of the available update
+    downloaded: Mutex<Option<(String, Vec<u8>)>>,
+    downloading: AtomicBool,
+}
+
+impl Default for UpdaterState {
+    fn default() -> Self {
+        Self {
+            store: Store::new(SETTINGS_FILE, SCHEMA, "updater settings"),
+            channel: Mutex::default(),
+            available: Mutex::default(),
+            downloaded: Mutex::default(),
+            downloading: AtomicBool::default(),
+        }
+    }
+}
+
+/// Put back the channel chosen in an earlier run
+fn load_channel(app: &AppHandle) -> Result<(), String> {
+    let state = app.state::<UpdaterState>();
+    let saved: Option<String> = state.store.with(app, |conn| {
+        conn.query_row(
+            "SELECT value FROM settings WHERE key = 'channel'",
+            [],
+            |row| row.get(0),
+        )
+        .optional()
+    })?;
+    if let Some(channel) = saved.as_deref().and_then(UpdateChannel::parse) {
+        *state.channel.lock() = channel;
+    }
+    Ok(())
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateInfo {
+    version: String,
+    current_version: String,
+    channel: UpdateChannel,
+    /// Release notes
+    notes: Option<String>,
+    published_at: Option<String>,
+    /// Whether the package is already downloaded
+    downloaded: bool,
+}
+
+/// Payload of `update-progress`
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UpdateProgressEvent {
+    version: String,
+    downloaded: u64,
+    total: Option<u64>,
+}
+
+/// Payload of `update-download-done`
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UpdateDownloadDoneEvent {
+    version: String,
+    error: Option<String>,
+}
+
+fn info(state: &UpdaterState, update: &Update) -> UpdateInfo {
+    let downloaded = state
+        .downloaded
+        .lock()
+        .as_ref()
+        .is_some_and(|(version, _)| *version == update.version);
+    UpdateInfo {
+        version: update.version.clone(),
+        current_version: update.current_version.clone(),
+        channel: *state.channel.lock(),
+        notes: update.body.clone(),
+        published_at: update
+            .date
+            .and_then(|date| chrono::DateTime::from_timestamp(date.unix_timestamp(), 0))
+            .map(|date| date.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)),
+        downloaded,
+    }
+}
+
+/// The key packages are verified against, if this build has one
+fn pubkey(app: &AppHandle) -> Option<String> {
+    let configured = || {
+        let key = app.config().plugins.0.get("updater")?.get("pubkey")?;
+        key.as_str().map(str::to_string)
+    };
+    PUBKEY
+        .map(str::to_string)
+        .or_else(configured)
+        .filter(|key| !key.trim().is_empty())
+}
+
+/// Ask the channel's feed for a newer release, remembering what it offers
+async fn check(app: &AppHandle) -> Result<Option<UpdateInfo>, String> {
+    let pubkey = pubkey(app)
+        .ok_or_else(|| "This build has no updater key to verify updates with".to_string())?;
+    let state = app.state::<UpdaterState>();
+    let endpoint = match *state.channel.lock() {
+        UpdateChannel::Stable => STABLE_ENDPOINT,
+        UpdateChannel::Beta => BETA_ENDPOINT,
+    };
+    let endpoint = Url::parse(endpoint).map_err(|e| e.to_string())?;
+    let updater = app
+        .updater_builder()
+        .pubkey(pubkey)
+        .endpoints(vec![endpoint])
+        .map(|builder| match proxy::https_route(app) {
+            Route::System => builder,
+            Route::Direct => builder.no_proxy(),
+            Route::Through(url) => builder.proxy(url),
+        })
+        .and_then(|builder| builder.build())
+        .map_err(|e| format!("Failed to set up the updater: {}", e))?;
+    let update = updater
+        .check()
+        .await
+        .map_err(|e| format!("Update check failed: {}", e))?;
+
+    let info = update.as_ref().map(|update| info(&state, update));
+    *state.available.lock() = update;
+    Ok(info)
+}
+
+/// Download the available update in the background
+fn start_download(app: &AppHandle) -> Result<(), String> {
+    let state = app.state::<UpdaterState>();
+    let update = state
+        .available
+        .lock()
+        .clone()
+        .ok_or_else(|| "No update available".to_string())?;
+    if let Some((version, _)) = state.downloaded.lock().as_ref() {
+        if *version == update.version {
+            return Ok(());
+        }
+    }
+    if state.downloading.swap(true, Ordering::SeqCst) {
+        return Ok(());
+    }
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let version = update.version.clone();
+        let mut downloaded = 0u64;
+        let result = update
+            .download(
+                |chunk, total| {
+                    downloaded += chunk as u64;
+                    let _ = app.emit(
+                        "update-progress",
+                        UpdateProgressEvent {
+                            version: version.clone(),
+                            downloaded,
+                            total,
+                        },
+                    );
+                },
+                || {},
+            )
+            .await;
+
+        let state = app.state::<UpdaterState>();
+        let error = match result {
+            Ok(bytes) => {
+                *state.downloaded.lock() = Some((version.clone(), bytes));
+                None
+            }
+            Err(e) => Some(format!("Update download failed: {}", e)),
+        };
+        state.downloading.store(false, Ordering::SeqCst);
+        let _ = app.emit(
+            "update-download-done",
+            UpdateDownloadDoneEvent { version, error },
+        );
+    });
+    Ok(())
+}
+
+/// Check once after startup and fetch whatever is new, in release builds
+pub(crate) fn start(app: &AppHandle) {
+    if let Err(e) = load_channel(app) {
+        eprintln!("{}", e);
+    }
+    if cfg!(debug_assertions) {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(FIRST_CHECK_DELAY).await;
+        match check(&app).await {
+            Ok(Some(update)) => {
+                let _ = app.emit("update-available", update);
+                if let Err(e) = start_download(&app) {
+                    eprintln!("{}", e);
+                }
+            }
+            Ok(None) => {}
+            Err(e) => eprintln!("{}", e),
+        }
+    });
+}
+
+/// Choose the release channel later checks use
+#[tauri::command]
+pub async fn set_update_channel(
+    app: AppHandle,
+    state: State<'_, UpdaterState>,
+    channel: UpdateChannel,
+) -> Result<(), String> {
+    state.store.with(&app, |conn| {
+        conn.execute(
+            "INSERT OR REPLACE INTO settings (key, value) VALUES ('channel', ?1)",
+            params![channel.as_str()],
+        )
+    })?;
+    let mut current = state.channel.lock();
+    if *current != channel {
+        *current = channel;
+        // What the other channel offered no longer applies
+        *state.available.lock() = None;
+        *state.downloaded.lock() = None;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_update_channel(state: State<'_, UpdaterState>) -> Result<UpdateChannel, String> {
+    Ok(*state.channel.lock())
+}
+
+/// Look for a newer release on the current channel
+#[tauri::command]
+pub async fn check_for_update(app: AppHandle) -> Result<Option<UpdateInfo>, String> {
+    check(&app).await
+}
+
+/// Download the update the last check found, reporting progress through
+/// events. Does nothing while a download is running or once it's done.
+#[tauri::command]
+pub async fn download_update(app: AppHandle) -> Result<(), String> {
+    start_download(&app)
+}
+
+/// Stop every agent and PTY session, install the downloaded update and
+/// start the new version
+#[tauri::command]
+pub async fn install_update_and_restart(app: AppHandle) -> Result<(), String> {
+    let state = app.state::<UpdaterState>();
+    let update = state
+        .available
+        .lock()
+        .clone()
+        .ok_or_else(|| "No update available".to_string())?;
+    let bytes = match state.downloaded.lock().take() {
+        Some((version, bytes)) if version == update.version => bytes,
+        _ => return Err("The update hasn't been downloaded yet".to_string()),
+    };
+
+    // Agents first, so their pre-kill hooks run
+    let running: Vec<u32> = agents::agent_list(&app.state::<AgentState>())
+        .into_iter()
+        .filter(|agent| agent.status != AgentStatus::Exited)
+        .map(|agent| agent.id)
+        .collect();
+    for id in running {
+        if let Err(e) = agents::stop_agent(app.clone(), id).await {
+            eprintln!("Failed to stop agent {}: {}", id, e);
+        }
+    }
+    let handle = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        pty::shutdown_all(&handle.state::<PtyState>(), SHUTDOWN_GRACE)
+    })
+    .await
+    .map_err(|e| format!("Shutdown failed: {}", e))?;
+
+    update
+        .install(bytes)
+        .map_err(|e| format!("Failed to install the update: {}", e))?;
+    app.restart()
+}
@@ -0,0 +1,206 @@
+//! Token and cost usage per issue
+//!
+//! Agents running with `streamJson` report token counts for every API
+//! response and the run's cost when it finishes; each report is stored in a
+//! SQLite file in the app data directory with the issue and local day it
+//! belongs to. Interactive agents print nothing machine-readable and are not
+//! counted. Every new record is emitted as `usage-recorded`.
+
+use crate::store::Store;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+const USAGE_FILE: &str = "usage.sqlite";
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS usage (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    repo TEXT NOT NULL,
+    issue INTEGER NOT NULL,
+    agent_id INTEGER NOT NULL,
+    day TEXT NOT NULL,
+    model TEXT,
+    input_tokens INTEGER NOT NULL DEFAULT 0,
+    output_tokens INTEGER NOT NULL DEFAULT 0,
+    cache_creation_tokens INTEGER NOT NULL DEFAULT 0,
+    cache_read_tokens INTEGER NOT NULL DEFAULT 0,
+    cost_usd REAL NOT NULL DEFAULT 0,
+    recorded_at TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS usage_issue ON usage (repo, issue);
+CREATE INDEX IF NOT EXISTS usage_day ON usage (day);
+";
+
+/// Lazily opened usage database
+pub struct UsageStore {
+    store: Store,
+}
+
+impl Default for UsageStore {
+    fn default() -> Self {
+        Self {
+            store: Store::new(USAGE_FILE, SCHEMA, "usage store"),
+        }
+    }
+}
+
+impl UsageStore {
+    fn with<T>(
+        &self,
+        app: &AppHandle,
+        f: impl FnOnce(&mut Connection) -> rusqlite::Result<T>,
+    ) -> Result<T, String> {
+        self.store.with(app, f)
+    }
+}
+
+/// One usage report from an agent
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct UsageRecord {
+    pub(crate) repo: String,
+    pub(crate) issue: u64,
+    pub(crate) agent_id: u32,
+    pub(crate) model: Option<String>,
+    pub(crate) input_tokens: u64,
+    pub(crate) output_tokens: u64,
+    pub(crate) cache_creation_tokens: u64,
+    pub(crate) cache_read_tokens: u64,
+    pub(crate) cost_usd: f64,
+}
+
+/// Summed usage for a group of records
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageTotals {
+    /// Set when grouped by issue
+    repo: Option<String>,
+    issue: Option<u64>,
+    /// `YYYY-MM-DD` in local time, set when grouped by day
+    day: Option<String>,
+    input_tokens: u64,
+    output_tokens: u64,
+    cache_creation_tokens: u64,
+    cache_read_tokens: u64,
+    cost_usd: f64,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UsageGrouping {
+    #[default]
+    Issue,
+    Day,
+    IssueDay,
+}
+
+/// Options for `list_usage`
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListUsageOptions {
+    repo: Option<String>,
+    issue: Option<u64>,
+    /// First day included, `YYYY-MM-DD`
+    since: Option<String>,
+    /// Last day included, `YYYY-MM-DD`
+    until: Option<String>,
+    #[serde(default)]
+    group_by: UsageGrouping,
+}
+
+/// Store a usage report
+pub(crate) fn record(app: &AppHandle, record: UsageRecord) -> Result<(), String> {
+    let day = chrono::Local::now().format("%Y-%m-%d").to_string();
+    let now = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+    app.state::<UsageStore>().with(app, |conn| {
+        conn.execute(
+            "INSERT INTO usage (repo, issue, agent_id, day, model, input_tokens, output_tokens,
+             cache_creation_tokens, cache_read_tokens, cost_usd, recorded_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                record.repo,
+                record.issue as i64,
+                record.agent_id,
+                day,
+                record.model,
+                record.input_tokens as i64,
+                record.output_tokens as i64,
+                record.cache_creation_tokens as i64,
+                record.cache_read_tokens as i64,
+                record.cost_usd,
+                now,
+            ],
+        )
+    })?;
+    let _ = app.emit("usage-recorded", record);
+    Ok(())
+}
+
+/// Usage totals, grouped by issue, day or both
+#[tauri::command]
+pub async fn list_usage(
+    app: AppHandle,
+    store: State<'_, UsageStore>,
+    options: Option<ListUsageOptions>,
+) -> Result<Vec<UsageTotals>, String> {
+    let options = options.unwrap_or_default();
+    let (select, group) = match options.group_by {
+        UsageGrouping::Issue => ("repo, issue, NULL", "repo, issue"),
+        UsageGrouping::Day => ("NULL, NULL, day", "day"),
+        UsageGrouping::IssueDay => ("repo, issue, day", "repo, issue, day"),
+    };
+    let sql = format!(
+        "SELECT {}, SUM(input_tokens), SUM(output_tokens), SUM(cache_creation_tokens),
+         SUM(cache_read_tokens), SUM(cost_usd)
+         FROM usage
+         WHERE (?1 IS NULL OR repo = ?1) AND (?2 IS NULL OR issue = ?2)
+           AND (?3 IS NULL OR day >= ?3) AND (?4 IS NULL OR day <= ?4)
+         GROUP BY {} ORDER BY {}",
+        select, group, group
+    );
+
+    store.with(&app, |conn| {
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(
+            params![
+                options.repo,
+                options.issue.map(|issue| issue as i64),
+                options.since,
+                options.until
+            ],
+            |row| {
+                Ok(UsageTotals {
+                    repo: row.get(0)?,
+                    issue: row.get::<_, Option<i64>>(1)?.map(|issue| issue as u64),
+                    day: row.get(2)?,
+                    input_tokens: row.get::<_, i64>(3)? as u64,
+                    output_tokens: row.get::<_, i64>(4)? as u64,
+                    cache_creation_tokens: row.get::<_, i64>(5)? as u64,
+                    cache_read_tokens: row.get::<_, i64>(6)? as u64,
+                    cost_usd: row.get(7)?,
+                })
+            },
+        )?;
+        rows.collect()
+    })
+}
+
+/// Total usage of one issue across all its agent runs
+#[tauri::command]
+pub async fn get_issue_usage(
+    app: AppHandle,
+    store: State<'_, UsageStore>,
+    repo: String,
+    issue: u64,
+) -> Result<Option<UsageTotals>, String> {
+    let options = ListUsageOptions {
+        repo: Some(repo),
+        issue: Some(issue),
+        ..Default::default()
+    };
+    Ok(list_usage(app, store, Some(options))
+        .await?
+        .into_iter()
+        .next())
+}
@@ -0,0 +1,290 @@
+//! Window state - where each window was left
+//!
+//! Position, size, maximized state and monitor are saved to a SQLite file in
+//! the app data directory when a window closes or the app quits, and put
+//! back when the window opens again. Pop-out terminals share one entry since
+//! their PTYs change from run to run; the drop-down terminal places itself
+//! and isn't saved. A window saved on a monitor that is no longer connected
+//! opens centered on the monitor of the same name if it moved, or on the
+//! primary one, shrunk to fit.
+
+use crate::store::Store;
+use crate::{popout, quake};
+use rusqlite::{params, Connection, OptionalExtension};
+use tauri::{
+    AppHandle, Manager, Monitor, PhysicalPosition, PhysicalSize, RunEvent, State, Window,
+    WindowEvent,
+};
+
+const STATE_FILE: &str = "window-state.sqlite";
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS windows (
+    key TEXT PRIMARY KEY,
+    x INTEGER NOT NULL,
+    y INTEGER NOT NULL,
+    width INTEGER NOT NULL,
+    height INTEGER NOT NULL,
+    scale REAL NOT NULL,
+    maximized INTEGER NOT NULL,
+    monitor TEXT,
+    updated_at TEXT NOT NULL
+);
+";
+
+/// Part of a window's title bar that must be on screen for it to be reachable
+const MIN_VISIBLE: i32 = 50;
+
+/// Lazily opened window state database
+pub struct WindowStateStore {
+    store: Store,
+}
+
+impl Default for WindowStateStore {
+    fn default() -> Self {
+        Self {
+            store: Store::new(STATE_FILE, SCHEMA, "window state"),
+        }
+    }
+}
+
+impl WindowStateStore {
+    fn with<T>(
+        &self,
+        app: &AppHandle,
+        f: impl FnOnce(&mut Connection) -> rusqlite::Result<T>,
+    ) -> Result<T, String> {
+        self.store.with(app, f)
+    }
+}
+
+/// Where a window was, in physical pixels
+#[derive(Clone, Debug)]
+struct Geometry {
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    /// Scale factor of the monitor it was on
+    scale: f64,
+    maximized: bool,
+    monitor: Option<String>,
+}
+
+/// The entry a window is saved under, if it is saved at all
+fn key(label: &str) -> Option<&str> {
+    if label == quake::WINDOW_LABEL {
+        None
+    } else if label.starts_with(popout::LABEL_PREFIX) {
+        Some("terminal")
+    } else {
+        Some(label)
+    }
+}
+
+fn load(app: &AppHandle, key: &str) -> Result<Option<Geometry>, String> {
+    app.state::<WindowStateStore>().with(app, |conn| {
+        conn.query_row(
+            "SELECT x, y, width, height, scale, maximized, monitor FROM windows WHERE key = ?1",
+            params![key],
+            |row| {
+                Ok(Geometry {
+                    x: row.get(0)?,
+                    y: row.get(1)?,
+                    width: row.get(2)?,
+                    height: row.get(3)?,
+                    scale: row.get(4)?,
+                    maximized: row.get(5)?,
+                    monitor: row.get(6)?,
+                })
+            },
+        )
+        .optional()
+    })
+}
+
+/// Save where `window` is now
+pub(crate) fn save(window: &Window) {
+    let Some(key) = key(window.label()) else {
+        return;
+    };
+    let app = window.app_handle();
+    if let Err(e) = try_save(app, key, window) {
+        eprintln!(
+            "Failed to save the state of window '{}': {}",
+            window.label(),
+            e
+        );
+    }
+}
+
+fn try_save(app: &AppHandle, key: &str, window: &Window) -> Result<(), String> {
+    // Minimized windows report a position off screen
+    if window.is_minimized().unwrap_or(false) {
+        return Ok(());
+    }
+    let maximized = window.is_maximized().unwrap_or(false);
+    let previous = load(app, key)?;
+    let geometry = match previous {
+        // A maximized window's own size is the screen's; keep the one it
+        // goes back to
+        Some(previous) if maximized => Geometry {
+            maximized,
+            ..previous
+        },
+        _ => {
+            let position = window.outer_position().map_err(|e| e.to_string())?;
+            let size = window.inner_size().map_err(|e| e.to_string())?;
+            Geometry {
+                x: position.x,
+                y: position.y,
+                width: size.width,
+                height: size.height,
+                scale: window.scale_factor().map_err(|e| e.to_string())?,
+                maximized,
+                monitor: window
+                    .current_monitor()
+                    .ok()
+                    .flatten()
+                    .and_then(|monitor| monitor.name().cloned()),
+            }
+        }
+    };
+
+    let now = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+    app.state::<WindowStateStore>().with(app, |conn| {
+        conn.execute(
+            "INSERT INTO windows (key, x, y, width, height, scale, maximized, monitor, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+             ON CONFLICT(key) DO UPDATE SET x = ?2, y = ?3, width = ?4, height = ?5,
+                 scale = ?6, maximized = ?7, monitor = ?8, updated_at = ?9",
+            params![
+                key,
+                geometry.x,
+                geometry.y,
+                geometry.width,
+                geometry.height,
+                geometry.scale,
+                geometry.maximized,
+                geometry.monitor,
+                now
+            ],
+        )?;
+        Ok(())
+    })
+}
+
+/// Whether enough of the title bar at `x`, `y` lies on `monitor`
+fn reachable(monitor: &Monitor, x: i32, y: i32, width: u32) -> bool {
+    let area = monitor.work_area();
+    let (left, top) = (area.position.x, area.position.y);
+    let (right, bottom) = (left + area.size.width as i32, top + area.size.height as i32);
+    let visible = (x + width as i32).min(right) - x.max(left);
+    visible >= MIN_VISIBLE && y >= top && y < bottom - MIN_VISIBLE
+}
+
+/// Put `window` back where it was last saved
+pub(crate) fn restore(window: &Window) {
+    let Some(key) = key(window.label()) else {
+        return;
+    };
+    match load(window.app_handle(), key) {
+        Ok(Some(geometry)) => {
+            if let Err(e) = apply(window, &geometry) {
+                eprintln!(
+                    "Failed to restore the state of window '{}': {}",
+                    window.label(),
+                    e
+                );
+            }
+        }
+        Ok(None) => {}
+        Err(e) => eprintln!("{}", e),
+    }
+}
+
+fn apply(window: &Window, geometry: &Geometry) -> tauri::Result<()> {
+    let monitors = window.available_monitors()?;
+    let on_screen = monitors
+        .iter()
+        .find(|monitor| reachable(monitor, geometry.x, geometry.y, geometry.width));
+    let target = match on_screen {
+        Some(monitor) => Some(monitor.clone()),
+        None => monitors
+            .iter()
+            .find(|monitor| {
+                geometry.monitor.is_some() && monitor.name() == geometry.monitor.as_ref()
+            })
+            .cloned()
+            .or(window.primary_monitor()?),
+    };
+    let Some(target) = target else {
+        return Ok(());
+    };
+
+    // Keep the same size on a monitor with a different scale, within its bounds
+    let area = target.work_area();
+    let ratio = target.scale_factor() / geometry.scale;
+    let width = ((f64::from(geometry.width) * ratio).round() as u32).min(area.size.width);
+    let height = ((f64::from(geometry.height) * ratio).round() as u32).min(area.size.height);
+    window.set_size(PhysicalSize::new(width, height))?;
+
+    let position = if on_screen.is_some() {
+        PhysicalPosition::new(geometry.x, geometry.y)
+    } else {
+        PhysicalPosition::new(
+            area.position.x + (area.size.width - width) as i32 / 2,
+            area.position.y + (area.size.height - height) as i32 / 2,
+        )
+    };
+    window.set_position(position)?;
+    if geometry.maximized {
+        window.maximize()?;
+    }
+    Ok(())
+}
+
+/// Restore the windows open at startup
+pub(crate) fn restore_all(app: &AppHandle) {
+    for window in app.webview_windows().values() {
+        restore(&window.as_ref().window());
+    }
+}
+
+/// Save a window as it closes
+pub(crate) fn on_window_event(window: &Window, event: &WindowEvent) {
+    if let WindowEvent::CloseRequested { .. } = event {
+        save(window);
+    }
+}
+
+/// Save every window as the app quits
+pub(crate) fn on_run_event(app: &AppHandle, event: &RunEvent) {
+    if let RunEvent::ExitRequested { .. } = event {
+        for window in app.webview_windows().values() {
+            save(&window.as_ref().window());
+        }
+    }
+}
+
+/// Forget the saved state of window `label`, or of every window
+#[tauri::command]
+pub async fn reset_window_state(
+    app: AppHandle,
+    state: State<'_, WindowStateStore>,
+    label: Option<String>,
+) -> Result<(), String> {
+    state.with(&app, |conn| {
+        match label {
+            Some(label) => {
+                if let Some(key) = key(&label) {
+                    conn.execute("DELETE FROM windows WHERE key = ?1", params![key])?;
+                }
+            }
+            None => {
+                conn.execute("DELETE FROM windows", [])?;
+            }
+        }
+        Ok(())
+    })
+}
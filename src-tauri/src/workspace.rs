@@ -0,0 +1,283 @@
+//! Workspace - the repositories Antler manages at once
+//!
+//! Each project links a GitHub repository to its local clone, with its own
+//! worktree location and board columns. The list and the active project live
+//! in a SQLite file in the app data directory. Sessions are already keyed by
+//! repository, so several projects can run agents side by side; switching
+//! only changes which board the UI shows and emits `active-project-changed`.
+//! On macOS each project also keeps a security-scoped bookmark, resolved at
+//! launch so a sandboxed build can reach the clone without asking again.
+
+use crate::bookmark;
+use crate::git::run_git;
+use crate::store::Store;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+const WORKSPACE_FILE: &str = "workspace.sqlite";
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS projects (
+    repo TEXT PRIMARY KEY,
+    path TEXT NOT NULL,
+    worktree_base TEXT,
+    columns TEXT,
+    active INTEGER NOT NULL DEFAULT 0,
+    added_at TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS bookmarks (
+    repo TEXT PRIMARY KEY,
+    data BLOB NOT NULL
+);
+";
+
+/// Lazily opened workspace database
+pub struct Workspace {
+    store: Store,
+}
+
+impl Default for Workspace {
+    fn default() -> Self {
+        Self {
+            store: Store::new(WORKSPACE_FILE, SCHEMA, "workspace"),
+        }
+    }
+}
+
+impl Workspace {
+    fn with<T>(
+        &self,
+        app: &AppHandle,
+        f: impl FnOnce(&mut Connection) -> rusqlite::Result<T>,
+    ) -> Result<T, String> {
+        self.store.with(app, f)
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Project {
+    /// `owner/name` on GitHub
+    pub(crate) repo: String,
+    /// Top level of the local clone
+    pub(crate) path: String,
+    /// Where worktrees are created, defaults to `<path>/.worktrees`
+    pub(crate) worktree_base: Option<String>,
+    /// Labels that represent board columns, defaults to the standard set
+    pub(crate) columns: Option<Vec<String>>,
+    active: bool,
+    added_at: String,
+}
+
+/// Options for `add_project`
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddProjectOptions {
+    worktree_base: Option<String>,
+    columns: Option<Vec<String>>,
+    /// Make it the active project; the first project always is
+    #[serde(default)]
+    activate: bool,
+}
+
+const COLUMNS: &str = "repo, path, worktree_base, columns, active, added_at";
+
+fn from_row(row: &Row) -> rusqlite::Result<Project> {
+    let columns: Option<String> = row.get(3)?;
+    Ok(Project {
+        repo: row.get(0)?,
+        path: row.get(1)?,
+        worktree_base: row.get(2)?,
+        columns: columns.and_then(|columns| serde_json::from_str(&columns).ok()),
+        active: row.get(4)?,
+        added_at: row.get(5)?,
+    })
+}
+
+fn load(conn: &Connection, repo: &str) -> rusqlite::Result<Option<Project>> {
+    conn.query_row(
+        &format!("SELECT {} FROM projects WHERE repo = ?1", COLUMNS),
+        [repo],
+        from_row,
+    )
+    .optional()
+}
+
+fn active(conn: &Connection) -> rusqlite::Result<Option<Project>> {
+    conn.query_row(
+        &format!("SELECT {} FROM projects WHERE active = 1", COLUMNS),
+        [],
+        from_row,
+    )
+    .optional()
+}
+
+/// Reopen every project folder from its bookmark, refreshing stale ones
+pub(crate) fn restore_access(app: &AppHandle) {
+    let workspace = app.state::<Workspace>();
+    let bookmarks = workspace.with(app, |conn| {
+        let mut stmt = conn.prepare("SELECT repo, data FROM bookmarks")?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get(1)?)))?;
+        rows.collect::<rusqlite::Result<Vec<(String, Vec<u8>)>>>()
+    });
+    let bookmarks = match bookmarks {
+        Ok(bookmarks) => bookmarks,
+        Err(e) => {
+            eprintln!("Failed to load bookmarks: {}", e);
+            return;
+        }
+    };
+
+    for (repo, data) in bookmarks {
+        let resolved = match bookmark::resolve(&data) {
+            Ok(resolved) => resolved,
+            Err(e) => {
+                eprintln!("Failed to reopen {}: {}", repo, e);
+                continue;
+            }
+        };
+        if !resolved.stale {
+            continue;
+        }
+        if let Some(data) = bookmark::create(&resolved.path) {
+            let _ = workspace.with(app, |conn| {
+                conn.execute(
+                    "UPDATE bookmarks SET data = ?2 WHERE repo = ?1",
+                    params![repo, data],
+                )
+            });
+        }
+    }
+}
+
+/// The project registered for `repo`, if any
+pub(crate) fn project(app: &AppHandle, repo: &str) -> Result<Option<Project>, String> {
+    app.state::<Workspace>().with(app, |conn| load(conn, repo))
+}
+
+fn emit_active(app: &AppHandle, project: &Option<Project>) {
+    let _ = app.emit("active-project-changed", project.clone());
+}
+
+/// Add a repository to the workspace, or update its settings
+#[tauri::command]
+pub async fn add_project(
+    app: AppHandle,
+    workspace: State<'_, Workspace>,
+    repo: String,
+    path: String,
+    options: Option<AddProjectOptions>,
+) -> Result<Project, String> {
+    let options = options.unwrap_or_default();
+    if repo.split('/').filter(|part| !part.is_empty()).count() != 2 {
+        return Err(format!(
+            "Invalid repository '{}'. Expected 'owner/repo'",
+            repo
+        ));
+    }
+    // Taken from the folder as picked, which is what the sandbox granted
+    let bookmark = bookmark::create(&path);
+    let path = run_git(&path, &["rev-parse", "--show-toplevel"])
+        .map(|top| top.trim().to_string())
+        .map_err(|e| format!("{} is not a git repository: {}", path, e))?;
+    let columns = options
+        .columns
+        .map(|columns| serde_json::to_string(&columns).unwrap_or_else(|_| "[]".to_string()));
+    let now = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+
+    let (project, activated) = workspace.with(&app, |conn| {
+        let tx = conn.transaction()?;
+        let activate = options.activate || active(&tx)?.is_none();
+        tx.execute(
+            "INSERT INTO projects (repo, path, worktree_base, columns, added_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT(repo) DO UPDATE SET path = ?2, worktree_base = ?3, columns = ?4",
+            params![repo, path, options.worktree_base, columns, now],
+        )?;
+        if let Some(bookmark) = &bookmark {
+            tx.execute(
+                "INSERT INTO bookmarks (repo, data) VALUES (?1, ?2)
+                 ON CONFLICT(repo) DO UPDATE SET data = ?2",
+                params![repo, bookmark],
+            )?;
+        }
+        if activate {
+            tx.execute("UPDATE projects SET active = (repo = ?1)", [&repo])?;
+        }
+        let project = load(&tx, &repo)?;
+        tx.commit()?;
+        Ok((project, activate))
+    })?;
+
+    if activated {
+        emit_active(&app, &project);
+    }
+    project.ok_or_else(|| format!("Project {} was not stored", repo))
+}
+
+/// Take a repository out of the workspace; its worktrees and sessions stay
+#[tauri::command]
+pub async fn remove_project(
+    app: AppHandle,
+    workspace: State<'_, Workspace>,
+    repo: String,
+) -> Result<(), String> {
+    let (removed, was_active) = workspace.with(&app, |conn| {
+        let was_active = active(conn)?.is_some_and(|project| project.repo == repo);
+        conn.execute("DELETE FROM bookmarks WHERE repo = ?1", [&repo])?;
+        let removed = conn.execute("DELETE FROM projects WHERE repo = ?1", [&repo])?;
+        Ok((removed, was_active))
+    })?;
+    if removed == 0 {
+        return Err(format!("Project {} not found", repo));
+    }
+    if was_active {
+        emit_active(&app, &None);
+    }
+    Ok(())
+}
+
+/// Every project, in the order they were added
+#[tauri::command]
+pub async fn list_projects(
+    app: AppHandle,
+    workspace: State<'_, Workspace>,
+) -> Result<Vec<Project>, String> {
+    workspace.with(&app, |conn| {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM projects ORDER BY added_at, repo",
+            COLUMNS
+        ))?;
+        let rows = stmt.query_map([], from_row)?;
+        rows.collect()
+    })
+}
+
+/// Make `repo` the project the UI shows
+#[tauri::command]
+pub async fn switch_project(
+    app: AppHandle,
+    workspace: State<'_, Workspace>,
+    repo: String,
+) -> Result<Project, String> {
+    let project = workspace.with(&app, |conn| {
+        if load(conn, &repo)?.is_none() {
+            return Ok(None);
+        }
+        conn.execute("UPDATE projects SET active = (repo = ?1)", [&repo])?;
+        load(conn, &repo)
+    })?;
+    let project = project.ok_or_else(|| format!("Project {} not found", repo))?;
+    emit_active(&app, &Some(project.clone()));
+    Ok(project)
+}
+
+/// The project the UI shows, if any has been added
+#[tauri::command]
+pub async fn get_active_project(
+    app: AppHandle,
+    workspace: State<'_, Workspace>,
+) -> Result<Option<Project>, String> {
+    workspace.with(&app, |conn| active(conn))
+}